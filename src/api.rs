@@ -0,0 +1,178 @@
+use crate::calibration::coverage::CoverageGrid;
+use crate::common::config_diff::ConfigDiff;
+use crate::common::session::SessionManifest;
+use crate::config_loader::{self, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::core::supervisor::{Supervisor, TaskStatus};
+use crate::operations::agent_op::{self, AgentParams};
+use crate::operations::aim_op::{self, AimParams, AimSummary};
+use crate::operations::calibrate_op::{self, PrintBoardParams};
+use crate::operations::control_op::{self, ControlParams};
+use crate::operations::convert_op::{self, ConvertParams};
+use crate::operations::coordinator_op::{self, CoordinatorParams};
+use crate::operations::coverage_op::{self, CoverageParams};
+use crate::operations::daemon_op::{self, DaemonParams};
+use crate::operations::diagnostic_op::{self, DiagnosticParams, DiagnosticReport};
+use crate::operations::discover_op::{self, DiscoverParams, DiscoveredDevice};
+use crate::operations::image_capture_op::{self, CaptureImageParams};
+use crate::operations::imaging_op::{self, SetImagingParams};
+use crate::operations::import_op::{self, ImportParams};
+use crate::operations::list_op::{self, DeviceListing, ListParams};
+use crate::operations::op_context::OperationContext;
+use crate::operations::soak_op::{self, SoakParams, SoakReport};
+use crate::operations::op_result::OperationReport;
+use crate::operations::recording_control_op::{self, RecordingControlParams};
+use crate::operations::report_op::{self, ReportParams};
+use crate::operations::time_sync_op::{self, VerifyTimesParams};
+use crate::operations::validate_op::{self, ValidateParams};
+use crate::operations::verify_archive_op::{self, ArchiveVerification, VerifyArchiveParams};
+use crate::operations::video_record_op::{self, RecordVideoParams};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// A loaded configuration and its cameras, with one method per rcam operation.
+/// Each method is the same code path the matching CLI subcommand runs.
+pub struct Rcam {
+    config: MasterConfig,
+    camera_manager: CameraManager,
+}
+
+impl Rcam {
+    /// Builds the camera set from an already loaded (and validated) configuration.
+    pub fn new(config: MasterConfig) -> Result<Self> {
+        let camera_manager = CameraManager::new(&config).context("Failed to initialize CameraManager")?;
+        Ok(Rcam { config, camera_manager })
+    }
+
+    /// Loads and validates a YAML configuration, as `rcam --config` does.
+    pub fn from_config_file(path: &str) -> Result<Self> {
+        Rcam::new(config_loader::load_config(path)?)
+    }
+
+    /// Like `from_config_file`, with a named profile applied, as `rcam --profile` does.
+    pub fn from_config_file_with_profile(path: &str, profile: &str) -> Result<Self> {
+        Rcam::new(config_loader::load_config_with_profile(path, Some(profile))?)
+    }
+
+    pub fn config(&self) -> &MasterConfig {
+        &self.config
+    }
+
+    pub fn camera_manager(&self) -> &CameraManager {
+        &self.camera_manager
+    }
+
+    /// Runs long-lived per-camera tasks under the configured restart policy.
+    pub fn supervisor(&self) -> &Supervisor {
+        self.camera_manager.supervisor()
+    }
+
+    /// State of every supervised task, e.g. for a health endpoint.
+    pub fn task_status(&self) -> Vec<TaskStatus> {
+        self.camera_manager.supervisor().status()
+    }
+
+    /// Context for calling an operation's `execute` directly.
+    pub fn context(&self) -> OperationContext<'_> {
+        OperationContext::new(&self.config, &self.camera_manager)
+    }
+
+    /// Configured devices with their endpoints, credential status and last known health.
+    pub async fn list(&self, params: &ListParams) -> Result<Vec<DeviceListing>> {
+        list_op::execute(params, &self.context()).await
+    }
+
+    /// Cameras announcing themselves over mDNS or SSDP, matched against the configured ones.
+    pub async fn discover(&self, params: &DiscoverParams) -> Result<Vec<DiscoveredDevice>> {
+        discover_op::execute(params, &self.context()).await
+    }
+
+    pub async fn capture_images(&self, params: &CaptureImageParams) -> Result<OperationReport> {
+        image_capture_op::execute(params, &self.context()).await
+    }
+
+    pub async fn record_video(&self, params: &RecordVideoParams) -> Result<OperationReport> {
+        video_record_op::execute(params, &self.context()).await
+    }
+
+    pub async fn set_imaging(&self, params: &SetImagingParams) -> Result<OperationReport> {
+        imaging_op::execute(params, &self.context()).await
+    }
+
+    /// Disruptive actions prompt on the terminal unless `assume_yes` is set; embedders
+    /// without a terminal must set it.
+    pub async fn control(&self, params: &ControlParams) -> Result<OperationReport> {
+        control_op::execute(params, &self.context()).await
+    }
+
+    /// Runs until `params.duration` elapses or the process is interrupted.
+    pub async fn aim(&self, params: &AimParams) -> Result<AimSummary> {
+        aim_op::execute(params, &self.context()).await
+    }
+
+    pub async fn verify_times(&self) -> Result<()> {
+        time_sync_op::execute(&VerifyTimesParams::default(), &self.context()).await
+    }
+
+    /// The report is also written as JSON and HTML into the diagnostics output directory.
+    pub async fn run_diagnostics(&self, params: &DiagnosticParams) -> Result<DiagnosticReport> {
+        diagnostic_op::execute(params, &self.context()).await
+    }
+
+    /// Runs until `params.duration` elapses or the process is interrupted.
+    pub async fn soak_test(&self, params: &SoakParams) -> Result<SoakReport> {
+        soak_op::execute(params, &self.context()).await
+    }
+
+    pub async fn print_board(&self, params: &PrintBoardParams) -> Result<Vec<PathBuf>> {
+        calibrate_op::execute_print_board(params, &self.context()).await
+    }
+
+    pub async fn convert(&self, params: &ConvertParams) -> Result<()> {
+        convert_op::execute(params, &self.context()).await
+    }
+
+    pub async fn import(&self, params: &ImportParams) -> Result<SessionManifest> {
+        import_op::execute(params, &self.context()).await
+    }
+
+    /// Renders a session report and returns the path of the HTML file.
+    pub async fn report(&self, params: &ReportParams) -> Result<PathBuf> {
+        report_op::execute(params, &self.context()).await
+    }
+
+    pub async fn coverage(&self, params: &CoverageParams) -> Result<CoverageGrid> {
+        coverage_op::execute(params, &self.context()).await
+    }
+
+    /// Diff of this configuration against another config file or a session's configuration.
+    pub async fn validate(&self, params: &ValidateParams) -> Result<Option<ConfigDiff>> {
+        validate_op::execute(params, &self.context()).await
+    }
+
+    /// Re-hashes saved media against the checksums in the session manifests.
+    pub async fn verify_archive(&self, params: &VerifyArchiveParams) -> Result<ArchiveVerification> {
+        verify_archive_op::execute(params, &self.context()).await
+    }
+
+    /// Takes scheduled snapshots until the context's cancellation token fires.
+    pub async fn daemon(&self, params: &DaemonParams) -> Result<()> {
+        daemon_op::execute(params, &self.context()).await
+    }
+
+    /// Serves the agent API until the context's cancellation token fires.
+    pub async fn serve_agent(&self, params: &AgentParams) -> Result<()> {
+        agent_op::execute(params, &self.context()).await
+    }
+
+    /// Pauses or resumes the recordings of a running agent. Recordings in this process are
+    /// paused directly with `common::recording_control`.
+    pub async fn recording_control(&self, params: &RecordingControlParams) -> Result<()> {
+        recording_control_op::execute(params, &self.context()).await
+    }
+
+    /// Runs a capture on the configured remote agents and gathers their results.
+    pub async fn coordinate(&self, params: &CoordinatorParams) -> Result<OperationReport> {
+        coordinator_op::execute(params, &self.context()).await
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::{anyhow, bail, Context, Result};
+use opencv::{core as opencv_core, objdetect, prelude::*};
+
+/// Kind of printable calibration target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardType {
+    Charuco,
+    Chessboard,
+}
+
+impl BoardType {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "charuco" => Ok(BoardType::Charuco),
+            "chessboard" | "checkerboard" => Ok(BoardType::Chessboard),
+            other => bail!("Unknown board type '{}' (supported: charuco, chessboard)", other),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoardType::Charuco => "charuco",
+            BoardType::Chessboard => "chessboard",
+        }
+    }
+}
+
+/// Physical layout of a board. Lengths are in millimetres.
+#[derive(Debug, Clone)]
+pub struct BoardSpec {
+    pub board_type: BoardType,
+    pub squares_x: u32,
+    pub squares_y: u32,
+    pub square_mm: f64,
+    pub marker_mm: f64,     // ChArUco only
+    pub dictionary: String, // ChArUco only, e.g. "5x5_100"
+}
+
+impl BoardSpec {
+    pub fn width_mm(&self) -> f64 {
+        self.squares_x as f64 * self.square_mm
+    }
+
+    pub fn height_mm(&self) -> f64 {
+        self.squares_y as f64 * self.square_mm
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.squares_x < 2 || self.squares_y < 2 {
+            bail!("A board needs at least 2x2 squares (got {}x{})", self.squares_x, self.squares_y);
+        }
+        if self.square_mm <= 0.0 {
+            bail!("Square size must be positive (got {} mm)", self.square_mm);
+        }
+        if self.board_type == BoardType::Charuco && (self.marker_mm <= 0.0 || self.marker_mm >= self.square_mm) {
+            bail!("Marker size must be positive and smaller than the square ({} mm, square {} mm)", self.marker_mm, self.square_mm);
+        }
+        Ok(())
+    }
+
+    pub fn description(&self) -> String {
+        match self.board_type {
+            BoardType::Charuco => format!(
+                "ChArUco {}x{}, square {} mm, marker {} mm, dictionary DICT_{}",
+                self.squares_x, self.squares_y, self.square_mm, self.marker_mm, self.dictionary.to_uppercase()
+            ),
+            BoardType::Chessboard => format!(
+                "Chessboard {}x{} squares ({}x{} inner corners), square {} mm",
+                self.squares_x, self.squares_y, self.squares_x - 1, self.squares_y - 1, self.square_mm
+            ),
+        }
+    }
+}
+
+/// Parses "7x5" into (7, 5).
+pub fn parse_squares(value: &str) -> Result<(u32, u32)> {
+    let (x, y) = value.to_lowercase().split_once('x')
+        .map(|(x, y)| (x.trim().to_string(), y.trim().to_string()))
+        .ok_or_else(|| anyhow!("Expected squares as COLSxROWS, e.g. 7x5 (got '{}')", value))?;
+    Ok((
+        x.parse().with_context(|| format!("Invalid column count '{}'", x))?,
+        y.parse().with_context(|| format!("Invalid row count '{}'", y))?,
+    ))
+}
+
+fn predefined_dictionary(name: &str) -> Result<objdetect::PredefinedDictionaryType> {
+    use objdetect::PredefinedDictionaryType::*;
+    Ok(match name.to_lowercase().trim_start_matches("dict_") {
+        "4x4_50" => DICT_4X4_50,
+        "4x4_100" => DICT_4X4_100,
+        "4x4_250" => DICT_4X4_250,
+        "4x4_1000" => DICT_4X4_1000,
+        "5x5_50" => DICT_5X5_50,
+        "5x5_100" => DICT_5X5_100,
+        "5x5_250" => DICT_5X5_250,
+        "5x5_1000" => DICT_5X5_1000,
+        "6x6_50" => DICT_6X6_50,
+        "6x6_100" => DICT_6X6_100,
+        "6x6_250" => DICT_6X6_250,
+        "6x6_1000" => DICT_6X6_1000,
+        "7x7_50" => DICT_7X7_50,
+        "7x7_100" => DICT_7X7_100,
+        "7x7_250" => DICT_7X7_250,
+        "7x7_1000" => DICT_7X7_1000,
+        other => bail!("Unknown ArUco dictionary '{}' (e.g. 4x4_50, 5x5_100, 6x6_250)", other),
+    })
+}
+
+/// Renders the board (without margin) as an 8-bit grayscale image at `dpi`.
+/// Returns (pixels, width, height).
+pub fn render(spec: &BoardSpec, dpi: u32) -> Result<(Vec<u8>, u32, u32)> {
+    spec.validate()?;
+    let px_per_mm = dpi as f64 / 25.4;
+    let square_px = (spec.square_mm * px_per_mm).round() as u32;
+    if square_px < 8 {
+        bail!("Squares would only be {} px at {} DPI; raise --dpi or the square size", square_px, dpi);
+    }
+    let width = square_px * spec.squares_x;
+    let height = square_px * spec.squares_y;
+
+    match spec.board_type {
+        BoardType::Chessboard => {
+            let mut pixels = vec![255u8; (width * height) as usize];
+            for y in 0..height {
+                for x in 0..width {
+                    if ((x / square_px) + (y / square_px)) % 2 == 0 {
+                        pixels[(y * width + x) as usize] = 0;
+                    }
+                }
+            }
+            Ok((pixels, width, height))
+        }
+        BoardType::Charuco => {
+            let dictionary = objdetect::get_predefined_dictionary(predefined_dictionary(&spec.dictionary)?)?;
+            let board = objdetect::CharucoBoard::new_def(
+                opencv_core::Size::new(spec.squares_x as i32, spec.squares_y as i32),
+                spec.square_mm as f32,
+                spec.marker_mm as f32,
+                &dictionary,
+            ).context("OpenCV: Failed to create ChArUco board")?;
+            let mut image = opencv_core::Mat::default();
+            board.generate_image(opencv_core::Size::new(width as i32, height as i32), &mut image, 0, 1)
+                .context("OpenCV: Failed to render ChArUco board")?;
+            let pixels = image.data_bytes()?.to_vec();
+            if pixels.len() != (width * height) as usize {
+                bail!("Unexpected ChArUco image size {} bytes for {}x{}", pixels.len(), width, height);
+            }
+            Ok((pixels, width, height))
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::config_loader::{CameraExtrinsics, CoverageWorkspace, FieldOfView};
+use anyhow::{bail, Result};
+
+/// Grids larger than this are almost certainly a unit mistake (mm instead of m).
+const MAX_VOXELS: usize = 4_000_000;
+
+/// A camera's viewing frustum placed in the rig frame.
+#[derive(Debug, Clone)]
+pub struct CameraFrustum {
+    pub name: String,
+    pub translation: [f64; 3],
+    pub rotation: [f64; 4], // normalized [x, y, z, w]
+    rig_to_camera: [[f64; 3]; 3], // transpose of the camera-to-rig rotation
+    tan_half_h: f64,
+    tan_half_v: f64,
+    pub min_range_m: f64,
+    pub max_range_m: f64,
+    pub horizontal_deg: f64,
+    pub vertical_deg: f64,
+}
+
+impl CameraFrustum {
+    pub fn new(name: &str, extrinsics: &CameraExtrinsics, fov: &FieldOfView) -> Result<Self> {
+        let [x, y, z, w] = extrinsics.rotation;
+        let norm = (x * x + y * y + z * z + w * w).sqrt();
+        if !norm.is_finite() || norm < 1e-9 {
+            bail!("rotation must be a non-zero quaternion [x, y, z, w] (got {:?})", extrinsics.rotation);
+        }
+        if extrinsics.translation.iter().any(|v| !v.is_finite()) {
+            bail!("translation must be finite (got {:?})", extrinsics.translation);
+        }
+        for (key, deg) in [("horizontal_deg", fov.horizontal_deg), ("vertical_deg", fov.vertical_deg)] {
+            if !(deg > 0.0 && deg < 180.0) {
+                bail!("{} must be between 0 and 180 degrees (got {})", key, deg);
+            }
+        }
+        let min_range_m = fov.min_range_m.unwrap_or(0.1);
+        let max_range_m = fov.max_range_m.unwrap_or(10.0);
+        if !(min_range_m >= 0.0 && min_range_m < max_range_m) {
+            bail!("range must satisfy 0 <= min_range_m < max_range_m (got {} .. {})", min_range_m, max_range_m);
+        }
+
+        let (x, y, z, w) = (x / norm, y / norm, z / norm, w / norm);
+        let camera_to_rig = [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+            [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+            [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+        ];
+        let mut rig_to_camera = [[0.0; 3]; 3];
+        for (r, row) in rig_to_camera.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = camera_to_rig[c][r];
+            }
+        }
+
+        Ok(CameraFrustum {
+            name: name.to_string(),
+            translation: extrinsics.translation,
+            rotation: [x, y, z, w],
+            rig_to_camera,
+            tan_half_h: (fov.horizontal_deg.to_radians() / 2.0).tan(),
+            tan_half_v: (fov.vertical_deg.to_radians() / 2.0).tan(),
+            min_range_m,
+            max_range_m,
+            horizontal_deg: fov.horizontal_deg,
+            vertical_deg: fov.vertical_deg,
+        })
+    }
+
+    /// Whether a rig-frame point lies inside the frustum. Occlusion is not modelled.
+    pub fn sees(&self, point: [f64; 3]) -> bool {
+        let d = [point[0] - self.translation[0], point[1] - self.translation[1], point[2] - self.translation[2]];
+        let m = &self.rig_to_camera;
+        let cz = m[2][0] * d[0] + m[2][1] * d[1] + m[2][2] * d[2];
+        if cz < self.min_range_m || cz > self.max_range_m {
+            return false;
+        }
+        let cx = m[0][0] * d[0] + m[0][1] * d[1] + m[0][2] * d[2];
+        let cy = m[1][0] * d[0] + m[1][1] * d[1] + m[1][2] * d[2];
+        cx.abs() <= cz * self.tan_half_h && cy.abs() <= cz * self.tan_half_v
+    }
+}
+
+/// Per-voxel camera visibility over a workspace.
+#[derive(Debug, Clone)]
+pub struct CoverageGrid {
+    pub centers: Vec<[f64; 3]>,
+    pub seen_by: Vec<Vec<usize>>, // indices into the frustum list, per voxel
+    pub min_cameras: usize,
+}
+
+impl CoverageGrid {
+    pub fn count(&self, voxel: usize) -> usize {
+        self.seen_by[voxel].len()
+    }
+
+    pub fn blind(&self) -> usize {
+        self.seen_by.iter().filter(|s| s.is_empty()).count()
+    }
+
+    pub fn under_covered(&self) -> usize {
+        self.seen_by.iter().filter(|s| !s.is_empty() && s.len() < self.min_cameras).count()
+    }
+
+    pub fn covered(&self) -> usize {
+        self.seen_by.iter().filter(|s| s.len() >= self.min_cameras).count()
+    }
+
+    /// Voxels seen by camera `index` and fewer than `min_cameras` others, i.e. where losing
+    /// that camera would open (or widen) a gap.
+    pub fn critical_for(&self, index: usize) -> usize {
+        self.seen_by.iter().filter(|s| s.contains(&index) && s.len() <= self.min_cameras).count()
+    }
+
+    /// Axis-aligned bounds of the blind voxel centres, if any.
+    pub fn blind_bounds(&self) -> Option<([f64; 3], [f64; 3])> {
+        let mut bounds: Option<([f64; 3], [f64; 3])> = None;
+        for (center, seen) in self.centers.iter().zip(&self.seen_by) {
+            if !seen.is_empty() {
+                continue;
+            }
+            let (min, max) = bounds.get_or_insert((*center, *center));
+            for axis in 0..3 {
+                min[axis] = min[axis].min(center[axis]);
+                max[axis] = max[axis].max(center[axis]);
+            }
+        }
+        bounds
+    }
+}
+
+/// Samples the workspace at voxel centres and records which cameras see each one.
+pub fn compute(workspace: &CoverageWorkspace, voxel_size_m: f64, min_cameras: usize, frustums: &[CameraFrustum]) -> Result<CoverageGrid> {
+    let steps: Vec<usize> = (0..3)
+        .map(|axis| ((workspace.max[axis] - workspace.min[axis]) / voxel_size_m).ceil().max(1.0) as usize)
+        .collect();
+    let total = steps[0] * steps[1] * steps[2];
+    if total > MAX_VOXELS {
+        bail!(
+            "Workspace would have {} voxels ({}x{}x{}); use a larger voxel size (limit {})",
+            total, steps[0], steps[1], steps[2], MAX_VOXELS
+        );
+    }
+
+    let mut centers = Vec::with_capacity(total);
+    let mut seen_by = Vec::with_capacity(total);
+    for ix in 0..steps[0] {
+        for iy in 0..steps[1] {
+            for iz in 0..steps[2] {
+                let center = [
+                    (workspace.min[0] + (ix as f64 + 0.5) * voxel_size_m).min(workspace.max[0]),
+                    (workspace.min[1] + (iy as f64 + 0.5) * voxel_size_m).min(workspace.max[1]),
+                    (workspace.min[2] + (iz as f64 + 0.5) * voxel_size_m).min(workspace.max[2]),
+                ];
+                seen_by.push(frustums.iter().enumerate().filter(|(_, f)| f.sees(center)).map(|(i, _)| i).collect());
+                centers.push(center);
+            }
+        }
+    }
+    Ok(CoverageGrid { centers, seen_by, min_cameras })
+}
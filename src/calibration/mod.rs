@@ -0,0 +1,3 @@
+pub mod board;
+pub mod coverage;
+pub mod pdf;
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+/// Writes a single-page PDF that places an 8-bit grayscale image at an exact physical size,
+/// centred on a page with `margin_mm` on every side and a one-line caption in the bottom margin.
+/// Printing at 100% scale ("actual size") reproduces `image_width_mm` x `image_height_mm`.
+pub fn write_grayscale_pdf(
+    path: &Path,
+    pixels: &[u8],
+    width_px: u32,
+    height_px: u32,
+    image_width_mm: f64,
+    image_height_mm: f64,
+    margin_mm: f64,
+    caption: &str,
+) -> Result<()> {
+    let image_w_pt = image_width_mm * POINTS_PER_MM;
+    let image_h_pt = image_height_mm * POINTS_PER_MM;
+    let margin_pt = margin_mm * POINTS_PER_MM;
+    let page_w_pt = image_w_pt + 2.0 * margin_pt;
+    let page_h_pt = image_h_pt + 2.0 * margin_pt;
+    let caption_size = (margin_pt * 0.3).clamp(4.0, 10.0);
+    let caption_escaped = caption.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)");
+
+    let content = format!(
+        "q {:.4} 0 0 {:.4} {:.4} {:.4} cm /Im0 Do Q\nBT /F1 {:.1} Tf {:.4} {:.4} Td ({}) Tj ET\n",
+        image_w_pt, image_h_pt, margin_pt, margin_pt,
+        caption_size, margin_pt, (margin_pt - caption_size) / 2.0, caption_escaped
+    );
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut offsets = Vec::new();
+    out.extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+
+    let mut object = |out: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", offsets.len()).as_bytes());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    };
+
+    object(&mut out, b"<< /Type /Catalog /Pages 2 0 R >>");
+    object(&mut out, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+    object(&mut out, format!(
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.4} {:.4}] /Resources << /XObject << /Im0 5 0 R >> /Font << /F1 6 0 R >> >> /Contents 4 0 R >>",
+        page_w_pt, page_h_pt
+    ).as_bytes());
+    object(&mut out, &[
+        format!("<< /Length {} >>\nstream\n", content.len()).as_bytes(),
+        content.as_bytes(),
+        b"\nendstream",
+    ].concat());
+    object(&mut out, &[
+        format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8 /Interpolate false /Length {} >>\nstream\n",
+            width_px, height_px, pixels.len()
+        ).as_bytes(),
+        pixels,
+        b"\nendstream",
+    ].concat());
+    object(&mut out, b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>");
+
+    let xref_offset = out.len();
+    out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+    for offset in &offsets {
+        out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend_from_slice(format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        offsets.len() + 1, xref_offset
+    ).as_bytes());
+
+    fs::write(path, out).with_context(|| format!("Failed to write PDF {}", path.display()))
+}
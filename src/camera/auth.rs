@@ -0,0 +1,92 @@
+use crate::errors::{ErrorKind, ErrorKindExt, RcamError};
+use anyhow::{bail, Result};
+use diqwest::WithDigestAuth;
+use reqwest::{RequestBuilder, Response};
+use std::env;
+
+/// HTTP authentication scheme of a camera (`auth` config key).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthScheme {
+    Digest, // default; an unauthenticated attempt is made first and retried on a 401 challenge
+    Basic,  // sent preemptively, for devices that reject the unauthenticated first attempt
+    Bearer, // token from the `{NAME}_TOKEN` environment variable
+    None,
+}
+
+impl AuthScheme {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value.map(|v| v.to_lowercase()).as_deref() {
+            None | Some("digest") => Ok(AuthScheme::Digest),
+            Some("basic") => Ok(AuthScheme::Basic),
+            Some("bearer") => Ok(AuthScheme::Bearer),
+            Some("none") => Ok(AuthScheme::None),
+            Some(other) => bail!("Unknown auth scheme '{}' (supported: digest, basic, bearer, none)", other),
+        }
+    }
+}
+
+/// Resolved HTTP credentials for one camera.
+#[derive(Clone)]
+pub struct CameraAuth {
+    pub scheme: AuthScheme,
+    pub username: Option<String>,
+    secret: Option<String>, // password (digest/basic) or token (bearer)
+}
+
+impl std::fmt::Debug for CameraAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CameraAuth")
+            .field("scheme", &self.scheme)
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Environment variable prefix for a camera's secrets, e.g. "front-door" -> "FRONT_DOOR".
+pub fn env_prefix(camera_name: &str) -> String {
+    camera_name.to_uppercase().replace('-', "_")
+}
+
+pub fn password_env_var(camera_name: &str) -> String {
+    format!("{}_PASSWORD", env_prefix(camera_name))
+}
+
+pub fn token_env_var(camera_name: &str) -> String {
+    format!("{}_TOKEN", env_prefix(camera_name))
+}
+
+impl CameraAuth {
+    /// Reads the secret for `scheme` from the environment (`{NAME}_PASSWORD` or `{NAME}_TOKEN`).
+    pub fn from_env(camera_name: &str, scheme: AuthScheme, username: Option<&str>) -> Result<Self> {
+        let secret = match scheme {
+            AuthScheme::Digest | AuthScheme::Basic => {
+                if username.is_none() {
+                    return Err(RcamError::for_camera(ErrorKind::Auth, camera_name, format!("Username not configured for camera '{}' ({:?} auth)", camera_name, scheme)).into());
+                }
+                let var = password_env_var(camera_name);
+                Some(env::var(&var)
+                    .with_kind(ErrorKind::Auth, || format!("Password for camera '{}' not found in environment variable '{}'", camera_name, var))?)
+            }
+            AuthScheme::Bearer => {
+                let var = token_env_var(camera_name);
+                Some(env::var(&var)
+                    .with_kind(ErrorKind::Auth, || format!("Bearer token for camera '{}' not found in environment variable '{}'", camera_name, var))?)
+            }
+            AuthScheme::None => None,
+        };
+        Ok(CameraAuth { scheme, username: username.map(str::to_string), secret })
+    }
+
+    /// Sends the request with this camera's authentication applied.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let username = self.username.as_deref().unwrap_or("");
+        let secret = self.secret.as_deref().unwrap_or("");
+        match self.scheme {
+            AuthScheme::Digest => request.send_with_digest_auth(username, secret).await
+                .kind_context(ErrorKind::Network, "digest-authenticated request failed"),
+            AuthScheme::Basic => Ok(request.basic_auth(username, Some(secret)).send().await?),
+            AuthScheme::Bearer => Ok(request.bearer_auth(secret).send().await?),
+            AuthScheme::None => Ok(request.send().await?),
+        }
+    }
+}
@@ -1,93 +1,130 @@
-use anyhow::{Result, anyhow};
-use log::{debug, warn};
-use chrono::{DateTime, Utc}; // Added DateTime, Utc imports
-// AppSettings is unused in active code, will be caught by compiler if truly unused later
-// use crate::config_loader::AppSettings;
+use crate::camera::imaging::{DayNightMode, ImagingRequest, ImagingSettings};
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::onvif::{self, DeviceInfo};
+use anyhow::{bail, Result, Context};
+use log::{debug, info};
+use chrono::{DateTime, Utc};
+use std::time::Instant;
 
 #[derive(Clone)]
-pub struct CameraController {
-    // http_client: Client, // Commented out
-}
+pub struct CameraController {}
 
 impl CameraController {
     pub fn new() -> Self {
-        debug!("🔧 Initializing CameraController... (currently stubbed)");
+        debug!("🔧 Initializing CameraController...");
         CameraController {}
     }
 
-    pub async fn get_camera_time(&self, _camera_name: &str, _ip: &str, _username: &str, _password_env_var: &str, _app_config: &crate::config_loader::AppSettings) -> Result<DateTime<Utc>> {
-        warn!("get_camera_time is currently stubbed and will return an error.");
-        Err(anyhow!("get_camera_time in CameraController is stubbed"))
-        /* 
-        let cam_name = &camera.config.name;
-        debug!("⏱️ Attempting to get time for camera (HTTP CGI): {}", cam_name);
+    /// Queries the camera clock through its vendor driver (Dahua CGI, Axis VAPIX, ...).
+    pub async fn get_camera_time(&self, device: &IpCameraDevice) -> Result<DateTime<Utc>> {
+        let cam_name = &device.name;
+        debug!("⏱️ Attempting to get time for camera '{}' via '{}' driver", cam_name, device.driver.name());
         let overall_start_time = Instant::now();
 
-        let cgi_path = &app_config.cgi_time_path;
-        let url = format!("http://{}{}", camera.config.ip, cgi_path);
-        let username = &camera.config.username;
-        let password = camera.get_password()
-            .ok_or_else(|| anyhow!("🔑❌ Password not available for HTTP CGI request for camera '{}'", cam_name))?;
+        let auth = device.http_auth()
+            .with_context(|| format!("🔑❌ Credentials not available for time request for camera '{}'", cam_name))?;
+        let client = device.build_http_client()?;
+        let ctx = device.vendor_context(&client, &auth);
 
-        debug!("  Making GET request to {} for camera time ({})", url, cam_name);
-        let req_start_time = Instant::now();
+        let camera_time = device.driver.get_time(&ctx).await
+            .with_context(|| format!("Failed to get time from camera '{}'", cam_name))?;
+        info!("  Camera '{}' reported time {} (fetched in {:?})", cam_name, camera_time.to_rfc3339(), overall_start_time.elapsed());
+        Ok(camera_time)
+    }
 
-        let response_res = self.http_client
-            .get(&url)
-            .basic_auth(username, Some(password))
-            .send()
-            .await
-            .with_context(|| format!("HTTP GET request to {} failed for '{}' 📡💥", url, cam_name));
+    /// Reads exposure, gain and white balance through the camera's ONVIF Imaging service.
+    pub async fn get_imaging_settings(&self, device: &IpCameraDevice) -> Result<ImagingSettings> {
+        if !device.driver.has_http_api() {
+            bail!("Camera '{}' is RTSP-only and has no imaging API", device.name);
+        }
+        let client = device.build_http_client()?;
+        let auth = device.http_auth()?;
+        let base_url = device.onvif_base_url();
+        let service_url = onvif::imaging_service_url(&client, &base_url, &auth).await;
+        let source_token = onvif::get_video_source_token(&client, &base_url, &auth).await?;
+        onvif::get_imaging_settings(&client, &service_url, &auth, &source_token).await
+            .with_context(|| format!("Failed to read imaging settings from camera '{}'", device.name))
+    }
 
-        let mut response = match response_res {
-            Ok(resp) => resp,
-            Err(e) => {
-                error!("  ❌ Initial HTTP request for time failed for '{}' in {:?}: {:#}", cam_name, req_start_time.elapsed(), e);
-                return Err(e);
-            }
+    /// Applies `request` on top of the camera's current imaging settings and returns what
+    /// the camera reports afterwards.
+    pub async fn set_imaging_settings(&self, device: &IpCameraDevice, request: &ImagingRequest) -> Result<ImagingSettings> {
+        if !device.driver.has_http_api() {
+            bail!("Camera '{}' is RTSP-only and has no imaging API", device.name);
+        }
+        let client = device.build_http_client()?;
+        let auth = device.http_auth()?;
+        let base_url = device.onvif_base_url();
+        let service_url = onvif::imaging_service_url(&client, &base_url, &auth).await;
+        let source_token = onvif::get_video_source_token(&client, &base_url, &auth).await?;
+        let current = onvif::get_imaging_settings(&client, &service_url, &auth, &source_token).await
+            .with_context(|| format!("Failed to read imaging settings from camera '{}'", device.name))?;
+        let wb_ranges = if matches!(request.white_balance, Some(crate::camera::imaging::WhiteBalanceRequest::Kelvin(_))) {
+            onvif::get_white_balance_ranges(&client, &service_url, &auth, &source_token).await?
+        } else {
+            None
         };
-        debug!("  Initial HTTP request for '{}' completed in {:?}, status: {}", cam_name, req_start_time.elapsed(), response.status());
+        let target = request.merge_into(&current, wb_ranges)
+            .with_context(|| format!("Invalid imaging request for camera '{}'", device.name))?;
+        debug!("🔧 Camera '{}': {} -> {}", device.name, current, target);
+        onvif::set_imaging_settings(&client, &service_url, &auth, &source_token, &target).await
+            .with_context(|| format!("Camera '{}' rejected the imaging settings", device.name))?;
+        onvif::get_imaging_settings(&client, &service_url, &auth, &source_token).await
+    }
 
-        if response.status() == StatusCode::UNAUTHORIZED {
-            info!("🛡️ Basic auth failed (401) for {}, attempting digest auth for camera: {}", url, cam_name);
-            let digest_req_start_time = Instant::now();
-            response = self.http_client
-                .get(&url)
-                // .digest_auth(username, Some(password), &response) // diqwest would be used here
-                .basic_auth(username, Some(password)) // Placeholder, diqwest needed
-                .send()
-                .await
-                .with_context(|| format!("Digest auth HTTP GET request to {} failed for '{}' 🛡️💥", url, cam_name))?;
-            debug!("  Digest auth HTTP request for '{}' completed in {:?}, status: {}", cam_name, digest_req_start_time.elapsed(), response.status());
+    /// Reads manufacturer, model, firmware and serial number through the ONVIF device service.
+    pub async fn get_device_info(&self, device: &IpCameraDevice) -> Result<DeviceInfo> {
+        if !device.driver.has_http_api() {
+            bail!("Camera '{}' is RTSP-only and has no device API", device.name);
         }
+        let client = device.build_http_client()?;
+        let auth = device.http_auth()?;
+        onvif::get_device_information(&client, &device.onvif_base_url(), &auth).await
+            .with_context(|| format!("Failed to read device information from camera '{}'", device.name))
+    }
 
-        if !response.status().is_success() {
-            error!(
-                "❌ HTTP request for camera time failed for '{}' with status {} after all auth attempts. URL: {}. Body: {:?}",
-                cam_name,
-                response.status(),
-                url,
-                response.text().await.unwrap_or_else(|_| "<failed to read body>".to_string())
-            );
-            bail!(
-                "HTTP request for camera time failed for '{}' with status {} after all auth attempts. URL: {}",
-                cam_name,
-                response.status(),
-                url
-            );
-        }
+    /// Asks the camera to restart through its vendor CGI. The camera drops off the network
+    /// for a minute or so afterwards.
+    pub async fn reboot(&self, device: &IpCameraDevice) -> Result<()> {
+        let auth = device.http_auth()?;
+        let client = device.build_http_client()?;
+        let ctx = device.vendor_context(&client, &auth);
+        device.driver.reboot(&ctx).await
+            .with_context(|| format!("Failed to reboot camera '{}'", device.name))?;
+        info!("🔄 Camera '{}' accepted the reboot request", device.name);
+        Ok(())
+    }
 
-        let body = response.text().await.context("Failed to read response body for camera time")?;
-        debug!("  Successfully fetched time string for '{}': '{}' in {:?}", cam_name, body.trim(), overall_start_time.elapsed());
+    /// Turns the camera's main stream on or off through the vendor driver and returns the
+    /// state read back, so callers report what the camera actually did.
+    pub async fn set_camera_enabled(&self, device: &IpCameraDevice, enabled: bool) -> Result<bool> {
+        let auth = device.http_auth()?;
+        let client = device.build_http_client()?;
+        let ctx = device.vendor_context(&client, &auth);
+        device.driver.set_stream_enabled(&ctx, enabled).await
+            .with_context(|| format!("Failed to {} the stream of camera '{}'", if enabled { "enable" } else { "disable" }, device.name))?;
+        let actual = device.driver.get_stream_enabled(&ctx).await?;
+        if actual != enabled {
+            bail!("Camera '{}' accepted the request but still reports its stream as {}", device.name, if actual { "enabled" } else { "disabled" });
+        }
+        Ok(actual)
+    }
 
-        // Example: var sys_time="2023-10-27 10:30:00";
-        // More robust parsing needed depending on actual camera output format
-        let parsed_time = chrono::NaiveDateTime::parse_from_str(body.trim().split('=').nth(1).unwrap_or_default().trim_matches(|c| c == '\"' || c == ';'), "%Y-%m-%d %H:%M:%S")
-            .with_context(|| format!("Failed to parse time string '{}' for camera '{}'", body.trim(), cam_name))?;
-        
-        Ok(DateTime::from_naive_utc_and_offset(parsed_time, Utc))
-        */
+    /// Reads the day/night (IR-cut) mode through the vendor driver.
+    pub async fn get_day_night(&self, device: &IpCameraDevice) -> Result<DayNightMode> {
+        let auth = device.http_auth()?;
+        let client = device.build_http_client()?;
+        let ctx = device.vendor_context(&client, &auth);
+        device.driver.get_day_night(&ctx).await
     }
 
-    // ... other methods ...
-}
\ No newline at end of file
+    /// Sets the day/night (IR-cut) mode through the vendor driver and returns the mode read back.
+    pub async fn set_day_night(&self, device: &IpCameraDevice, mode: DayNightMode) -> Result<DayNightMode> {
+        let auth = device.http_auth()?;
+        let client = device.build_http_client()?;
+        let ctx = device.vendor_context(&client, &auth);
+        device.driver.set_day_night(&ctx, mode).await
+            .with_context(|| format!("Failed to set day/night mode on camera '{}'", device.name))?;
+        device.driver.get_day_night(&ctx).await
+    }
+}
@@ -1,27 +1,137 @@
 // use crate::app_config::ApplicationConfig; // This import is unused
-use crate::config_loader::AppSettings;
+use crate::config_loader::{AppSettings, CameraTimeouts, PostProcessStep};
+use crate::camera::frame_export;
+use crate::camera::frame_sink::FrameSink;
+use crate::camera::http_fallback::{self, FallbackRecording};
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::post_process::{self, OverlayInfo};
+use crate::camera::rtsp_grab::{self, RtspGrab, RtspTransport};
+use crate::camera::stream_stats::{self, StreamMeter, StreamRate, StreamSummary};
+use crate::common::disk_space;
+use crate::common::recording_control;
+use crate::common::session::RecordingGap;
+use crate::common::sync_start::{self, SyncStart};
+use crate::common::{atomic_file, logging_setup, opencv_pool};
+use crate::common::blocking_watchdog::{self, BlockingPhase, WatchdogSettings};
+use crate::common::rerun_setup::LivePreview;
+use crate::core::capture_source::{CaptureTimings, VideoRecordRequest, VideoRecorder};
+use crate::core::events::{self, Event};
+use crate::core::supervisor::{OnceTask, RestartPolicy, Supervisor};
+use crate::errors::{ErrorKind, RcamError};
 use anyhow::{Context, Result, anyhow};
 use log::{info, warn, error, debug};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use opencv::{
     prelude::*,
     videoio,
-    imgcodecs,
     core as opencv_core
 };
 use std::collections::HashMap;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 use std::sync::Arc;
 use chrono::Utc;
 use futures::future::join_all;
+use tracing::Instrument;
 use chrono::DateTime;
 use std::sync::Barrier;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_util::sync::CancellationToken;
 
+/// Per-camera result of a recording run: the saved files on success (more than one if the
+/// recording rolled over at `max_file_size_mb`, in recording order), the error otherwise.
+pub type CameraRecordingResult = (String, Result<Vec<PathBuf>>);
+
+/// Seconds without a written frame after which a recording reconnects to its stream.
+pub const DEFAULT_STALL_TIMEOUT_SECS: f32 = 10.0;
+/// Reconnects per camera and recording before the camera is given up.
+pub const DEFAULT_MAX_RECONNECTS: u32 = 3;
+/// How often each recording logs its stream's frame rate and bitrate.
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(30);
+/// Supervisor task name of a camera's recording.
+const RECORDING_TASK: &str = "recording";
+
+/// FourCC of the OpenCV VideoWriter for the configured `video_codec` and `video_format`.
+pub(crate) fn writer_fourcc(app_config: &AppSettings, camera_name: &str) -> Result<i32> {
+    let fourcc_str = match app_config.video_codec.to_lowercase().as_str() {
+        "mjpg" | "mjpeg" => "MJPG",
+        "xvid" => "XVID",
+        "mp4v" => "MP4V",
+        "h264" if app_config.video_format.to_lowercase() == "avi" => "H264", // OpenCV's internal H264 for AVI
+        "h264" if app_config.video_format.to_lowercase() == "mp4" => "avc1", // More standard for MP4
+        codec_val => {
+            warn!("⚠️ Unsupported video_codec '{}' for OpenCV VideoWriter with format '{}' for '{}'. Defaulting to MJPG.", codec_val, app_config.video_format, camera_name);
+            "MJPG"
+        }
+    };
+    let mut chars = fourcc_str.chars();
+    let mut next = |default| chars.next().unwrap_or(default);
+    Ok(videoio::VideoWriter::fourcc(next('M'), next('J'), next('P'), next('G'))?)
+}
+
+/// Path of the `part`-th segment of a recording; the first segment keeps the original name
+/// (`cam1_<ts>.avi`, `cam1_<ts>_part2.avi`, ...).
+fn segment_path(first: &Path, part: usize) -> PathBuf {
+    if part <= 1 {
+        return first.to_path_buf();
+    }
+    let stem = first.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let file_name = match first.extension() {
+        Some(ext) => format!("{}_part{}.{}", stem, part, ext.to_string_lossy()),
+        None => format!("{}_part{}", stem, part),
+    };
+    first.with_file_name(file_name)
+}
+
+/// What a camera's recording actually covered: shorter than planned when it was stopped
+/// early, and without the time it spent paused.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedSpan {
+    pub duration: Duration,
+    pub gaps: Vec<RecordingGap>,
+    pub stream: Option<StreamSummary>,
+}
 
 #[derive(Clone)]
 pub struct CameraMediaManager {
-    captures: Arc<Mutex<HashMap<String, Arc<Mutex<videoio::VideoCapture>>>>>,
+    // Open streams kept between captures. A task takes its camera's stream out of the map and owns
+    // it while it runs; the stream is put back only if the task finished cleanly.
+    captures: Arc<Mutex<HashMap<String, videoio::VideoCapture>>>,
+    // Per-camera timeout overrides; cameras without an entry use the AppSettings defaults.
+    camera_timeouts: HashMap<String, CameraTimeouts>,
+    // Per-camera post-processing chains applied to captured still images.
+    post_process: HashMap<String, Vec<PostProcessStep>>,
+    // Per-camera output directories for recordings; cameras without an entry use the shared output_dir.
+    camera_output_dirs: HashMap<String, PathBuf>,
+    // Per-camera CPU sets the camera's blocking OpenCV work is pinned to.
+    cpu_affinity: HashMap<String, Vec<usize>>,
+    // Per-camera RTSP transports; cameras without an entry use FFmpeg's default.
+    rtsp_transport: HashMap<String, RtspTransport>,
+    // Per-camera bitrate floors (min_stream_kbps); cameras without an entry use the AppSettings one.
+    min_stream_kbps: HashMap<String, f64>,
+    // Rerun stream that recordings tee downsampled frames to while they run.
+    live_preview: Option<LivePreview>,
+    // Stops recordings early (e.g. on Ctrl-C); the files written so far are finalized.
+    cancel: Option<CancellationToken>,
+    // Set when a recording stopped because the disk ran low; every camera stops with it.
+    low_disk: Arc<AtomicBool>,
+    // Write variable frame rate files through FFmpeg with each frame's real timestamp.
+    variable_frame_rate: bool,
+    // Caps the streams open at once (max_concurrent_streams); shared by clones of the manager.
+    stream_limit: Option<(usize, Arc<Semaphore>)>,
+    // Connect / first frame / write breakdown of each camera's last recording.
+    timings: Arc<std::sync::Mutex<HashMap<String, CaptureTimings>>>,
+    recorded: Arc<std::sync::Mutex<HashMap<String, RecordedSpan>>>,
+    // Cameras recorded from their HTTP snapshot endpoint when their RTSP stream cannot be opened.
+    http_fallback: HashMap<String, Arc<IpCameraDevice>>,
+    // Recordings that fell back to HTTP snapshots, by camera.
+    fallbacks: Arc<std::sync::Mutex<HashMap<String, FallbackRecording>>>,
+    // Sources that write their own recording instead of a stream (virtual cameras, RealSense .bag).
+    local_sources: HashMap<String, Arc<dyn VideoRecorder>>,
+    // Start recordings at a scheduled UTC instant (start_at) instead of as soon as the streams are open.
+    sync_start: Option<SyncStart>,
+    // Runs the recording tasks, so they show up in its status; a manager of its own unless set.
+    supervisor: Supervisor,
 }
 
 impl CameraMediaManager {
@@ -30,25 +140,211 @@ impl CameraMediaManager {
         let start_time = std::time::Instant::now();
         let manager = CameraMediaManager {
             captures: Arc::new(Mutex::new(HashMap::new())),
+            camera_timeouts: HashMap::new(),
+            post_process: HashMap::new(),
+            camera_output_dirs: HashMap::new(),
+            cpu_affinity: HashMap::new(),
+            rtsp_transport: HashMap::new(),
+            min_stream_kbps: HashMap::new(),
+            live_preview: None,
+            cancel: None,
+            low_disk: Arc::new(AtomicBool::new(false)),
+            variable_frame_rate: false,
+            stream_limit: None,
+            timings: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            recorded: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            http_fallback: HashMap::new(),
+            fallbacks: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            local_sources: HashMap::new(),
+            sync_start: None,
+            supervisor: Supervisor::new(RestartPolicy::Never),
         };
         debug!("✅ CameraMediaManager initialized in {:?}", start_time.elapsed());
         manager
     }
 
-    async fn get_or_init_capture(&self, camera_name: &str, rtsp_url: &str) -> Result<Arc<Mutex<videoio::VideoCapture>>> {
+    pub fn set_camera_timeouts(&mut self, camera_name: &str, timeouts: CameraTimeouts) {
+        debug!("Setting timeouts for '{}': {:?}", camera_name, timeouts);
+        self.camera_timeouts.insert(camera_name.to_string(), timeouts);
+    }
+
+    pub fn set_post_process(&mut self, camera_name: &str, steps: Vec<PostProcessStep>) {
+        debug!("Setting {} post-processing step(s) for '{}'", steps.len(), camera_name);
+        self.post_process.insert(camera_name.to_string(), steps);
+    }
+
+    pub fn set_camera_output_dir(&mut self, camera_name: &str, dir: PathBuf) {
+        debug!("Setting output directory for '{}': {}", camera_name, dir.display());
+        self.camera_output_dirs.insert(camera_name.to_string(), dir);
+    }
+
+    pub fn set_cpu_affinity(&mut self, camera_name: &str, cpus: Vec<usize>) {
+        debug!("Pinning '{}' to CPUs {:?}", camera_name, cpus);
+        self.cpu_affinity.insert(camera_name.to_string(), cpus);
+    }
+
+    pub fn set_rtsp_transport(&mut self, camera_name: &str, transport: RtspTransport) {
+        debug!("Using RTSP transport {:?} for '{}'", transport, camera_name);
+        self.rtsp_transport.insert(camera_name.to_string(), transport);
+    }
+
+    pub fn set_min_stream_kbps(&mut self, camera_name: &str, kbps: f64) {
+        debug!("Warning when '{}' records below {} kbps", camera_name, kbps);
+        self.min_stream_kbps.insert(camera_name.to_string(), kbps);
+    }
+
+    pub fn set_http_fallback(&mut self, camera_name: &str, device: IpCameraDevice) {
+        debug!("Recording '{}' from HTTP snapshots if its RTSP stream cannot be opened", camera_name);
+        self.http_fallback.insert(camera_name.to_string(), Arc::new(device));
+    }
+
+    pub fn set_local_source(&mut self, camera_name: &str, recorder: Arc<dyn VideoRecorder>) {
+        debug!("Recording '{}' locally: {}", camera_name, recorder.describe());
+        self.local_sources.insert(camera_name.to_string(), recorder);
+    }
+
+    pub fn set_live_preview(&mut self, preview: LivePreview) {
+        debug!("Enabling live Rerun preview of recordings at up to {} fps per camera", preview.fps);
+        self.live_preview = Some(preview);
+    }
+
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancel = Some(token);
+    }
+
+    pub fn set_supervisor(&mut self, supervisor: Supervisor) {
+        self.supervisor = supervisor;
+    }
+
+    pub fn set_variable_frame_rate(&mut self, enabled: bool) {
+        self.variable_frame_rate = enabled;
+    }
+
+    /// Starts recordings at the next instant of `sync`, so rcam instances on other hosts start
+    /// in the same frame. Only the first wave is scheduled; later waves follow as streams free up.
+    pub fn set_sync_start(&mut self, sync: SyncStart) {
+        self.sync_start = Some(sync);
+    }
+
+    /// Captures at most `limit` cameras at once. Larger camera lists run in waves of up to
+    /// `limit` cameras; the cameras of one wave start together, as without a limit.
+    pub fn set_max_concurrent_streams(&mut self, limit: usize) {
+        let limit = limit.max(1);
+        debug!("Limiting captures to {} concurrent stream(s)", limit);
+        self.stream_limit = Some((limit, Arc::new(Semaphore::new(limit))));
+    }
+
+    /// Splits the cameras into the waves they are captured in: one wave unless a stream limit is
+    /// set or there are more cameras than OpenCV worker threads, since every camera of a wave
+    /// needs its own thread to reach the start barrier.
+    fn waves<'a>(&self, cameras_info: &'a [(String, String)]) -> Vec<&'a [(String, String)]> {
+        let threads = opencv_pool::threads();
+        let limit = self.stream_limit.as_ref().map_or(threads, |(limit, _)| (*limit).min(threads));
+        if cameras_info.len() > limit {
+            if self.stream_limit.as_ref().is_none_or(|(stream_limit, _)| *stream_limit > threads) {
+                warn!("🧵 {} cameras but only {} OpenCV worker thread(s); capturing in waves. Raise opencv_threads to capture them together.", cameras_info.len(), threads);
+            }
+            cameras_info.chunks(limit).collect()
+        } else {
+            vec![cameras_info]
+        }
+    }
+
+    /// Waits until a wave of `count` streams fits under the stream limit. The permits are held
+    /// until the returned guard is dropped.
+    async fn acquire_streams(&self, count: usize) -> Result<Option<tokio::sync::OwnedSemaphorePermit>> {
+        let Some((limit, semaphore)) = &self.stream_limit else {
+            return Ok(None);
+        };
+        let permits = count.min(*limit) as u32;
+        if semaphore.available_permits() < permits as usize {
+            info!("🚦 Waiting for {} of {} stream slot(s) to free up...", permits, limit);
+        }
+        let permit = semaphore.clone().acquire_many_owned(permits).await
+            .map_err(|e| anyhow!("Stream limit semaphore closed: {}", e))?;
+        Ok(Some(permit))
+    }
+
+    /// Closes the cached streams of a finished wave so the next wave's sessions replace them
+    /// instead of adding to them.
+    async fn release_streams(&self, wave: &[(String, String)]) {
         let mut captures_map = self.captures.lock().await;
-        if let Some(cap_mutex) = captures_map.get(camera_name) {
+        for (name, _) in wave {
+            if captures_map.remove(name).is_some() {
+                debug!("Closed stream for '{}' after its wave", name);
+            }
+        }
+    }
+
+    /// True if the last recording was stopped early because free disk space fell below `min_free_disk_mb`.
+    pub fn stopped_for_low_disk(&self) -> bool {
+        self.low_disk.load(Ordering::SeqCst)
+    }
+
+    /// Timing breakdown of the last recording per camera: connect is opening the stream (near
+    /// zero for a cached one), first frame is from the synchronized start to the first frame
+    /// read, and write is opening and finalizing the output file(s).
+    pub fn timings(&self) -> HashMap<String, CaptureTimings> {
+        self.timings.lock().map(|timings| timings.clone()).unwrap_or_default()
+    }
+
+    /// How long each camera's last recording actually ran, and its pauses.
+    pub fn recorded(&self) -> HashMap<String, RecordedSpan> {
+        self.recorded.lock().map(|recorded| recorded.clone()).unwrap_or_default()
+    }
+
+    /// Cameras whose last recording came from HTTP snapshots instead of RTSP.
+    pub fn http_fallbacks(&self) -> HashMap<String, FallbackRecording> {
+        self.fallbacks.lock().map(|fallbacks| fallbacks.clone()).unwrap_or_default()
+    }
+
+    fn timeouts_for(&self, camera_name: &str, app_config: &AppSettings) -> CameraTimeouts {
+        self.camera_timeouts
+            .get(camera_name)
+            .copied()
+            .unwrap_or_else(|| CameraTimeouts::from_app(app_config))
+    }
+
+    /// Stream timeouts passed to OpenCV when opening an RTSP capture.
+    fn open_params(timeouts: CameraTimeouts) -> opencv_core::Vector<i32> {
+        let mut open_params = opencv_core::Vector::<i32>::new();
+        open_params.push(videoio::CAP_PROP_OPEN_TIMEOUT_MSEC);
+        open_params.push(timeouts.connect.as_millis() as i32);
+        open_params.push(videoio::CAP_PROP_READ_TIMEOUT_MSEC);
+        open_params.push(timeouts.read.as_millis() as i32);
+        open_params
+    }
+
+    /// Takes the camera's open stream out of the cache, or opens a new one. The caller owns the
+    /// stream and hands it back with `return_capture` when done with it.
+    async fn take_or_open_capture(&self, camera_name: &str, rtsp_url: &str, timeouts: CameraTimeouts) -> Result<videoio::VideoCapture> {
+        if let Some(cap) = self.captures.lock().await.remove(camera_name) {
             debug!("Found existing VideoCapture for '{}'", camera_name);
-            return Ok(cap_mutex.clone());
+            return Ok(cap);
         }
 
         debug!("Creating new VideoCapture for '{}' with URL: {}", camera_name, rtsp_url);
         let cap_create_start = std::time::Instant::now();
         
         let rtsp_url_clone = rtsp_url.to_string();
-        let cap = tokio::task::spawn_blocking(move || {
-            videoio::VideoCapture::from_file(&rtsp_url_clone, videoio::CAP_ANY)
-        }).await??;
+        let open_params = Self::open_params(timeouts);
+        let transport = self.rtsp_transport.get(camera_name).copied();
+        let open_task = {
+            let _camera = logging_setup::camera_span(camera_name).entered();
+            opencv_pool::spawn(opencv_pool::pinned(self.cpu_affinity.get(camera_name).cloned(), move || {
+                rtsp_grab::open_stream(&rtsp_url_clone, &open_params, transport)
+            }))
+        };
+        // OpenCV honours CAP_PROP_OPEN_TIMEOUT_MSEC for FFmpeg, but not every backend does,
+        // so the async side enforces the same deadline (plus a small grace period).
+        let open_deadline = timeouts.connect + Duration::from_secs(1);
+        let cap = match tokio::time::timeout(open_deadline, open_task).await {
+            Ok(joined) => joined??,
+            Err(_) => {
+                error!("⏰ Timed out after {:?} opening RTSP stream for '{}'", open_deadline, camera_name);
+                return Err(RcamError::for_camera(ErrorKind::Network, camera_name, format!("Timed out after {:?} opening RTSP stream for '{}'", open_deadline, camera_name)).into());
+            }
+        };
         
         debug!("  VideoCapture created for '{}' in {:?}", camera_name, cap_create_start.elapsed());
 
@@ -62,16 +358,20 @@ impl CameraMediaManager {
             debug!("  VideoCapture::is_opened check for '{}' in {:?} (executed synchronously after cap creation)", camera_name, opened_check_start.elapsed());
             if !opened {
                 error!("❌ Failed to open RTSP stream for '{}': {} - Check camera availability and RTSP path.", camera_name, rtsp_url_for_open_check);
-                return Err(anyhow!("Failed to open RTSP stream for '{}': {} - Check camera availability and RTSP path.", camera_name, rtsp_url_for_open_check));
+                return Err(RcamError::for_camera(ErrorKind::Media, camera_name, format!("Failed to open RTSP stream for '{}': {} - Check camera availability and RTSP path.", camera_name, rtsp_url_for_open_check)).into());
             }
             info!("👍 RTSP stream opened and initialized for '{}'", camera_name);
+            events::publish(Event::CameraConnected { camera: camera_name.to_string(), at: Utc::now() });
             Ok::<_, anyhow::Error>(())
         };
         is_cap_opened?;
 
-        let cap_mutex = Arc::new(Mutex::new(cap));
-        captures_map.insert(camera_name.to_string(), cap_mutex.clone());
-        Ok(cap_mutex)
+        Ok(cap)
+    }
+
+    /// Caches a stream for the next capture from the same camera.
+    async fn return_capture(&self, camera_name: &str, cap: videoio::VideoCapture) {
+        self.captures.lock().await.insert(camera_name.to_string(), cap);
     }
 
     pub async fn capture_image(
@@ -79,6 +379,28 @@ impl CameraMediaManager {
         cameras_info: &[(String, String)], // List of (camera_name, rtsp_url)
         app_config: &AppSettings,
         output_dir: PathBuf,
+    ) -> Result<Vec<PathBuf>> {
+        let waves = self.waves(cameras_info);
+        if waves.len() > 1 {
+            info!("🚦 Capturing {} cameras in {} waves (max_concurrent_streams).", cameras_info.len(), waves.len());
+        }
+        let mut saved_image_paths = Vec::new();
+        for wave in &waves {
+            let _permits = self.acquire_streams(wave.len()).await?;
+            saved_image_paths.extend(self.capture_image_wave(wave, app_config, output_dir.clone()).await?);
+            if waves.len() > 1 {
+                self.release_streams(wave).await;
+            }
+        }
+        Ok(saved_image_paths)
+    }
+
+    /// Captures one frame from each camera at the same time; `capture_image` calls this per wave.
+    async fn capture_image_wave(
+        &self,
+        cameras_info: &[(String, String)],
+        app_config: &AppSettings,
+        output_dir: PathBuf,
     ) -> Result<Vec<PathBuf>> {
         info!("📸 Attempting image capture for {} cameras.", cameras_info.len());
         let overall_start_time = std::time::Instant::now();
@@ -95,22 +417,22 @@ impl CameraMediaManager {
         for (name, url) in cameras_info {
             debug!("  Queueing capture initialization for image capture: {} ({})", name, url);
             temp_camera_names_ordered.push(name.clone());
-            capture_init_futures.push(self.get_or_init_capture(name, url));
+            capture_init_futures.push(self.take_or_open_capture(name, url, self.timeouts_for(name, app_config)).instrument(logging_setup::camera_span(name)));
         }
 
         info!("  Initializing {} camera stream(s) for image capture concurrently...", capture_init_futures.len());
         let init_results = join_all(capture_init_futures).await;
         info!("  All camera stream initialization attempts for image capture completed.");
 
-        let mut capture_arcs = Vec::new();
+        let mut captures = Vec::new();
         let mut camera_names_ordered = Vec::new(); // For successfully initialized cameras
 
         for (i, result) in init_results.into_iter().enumerate() {
             let cam_name = &temp_camera_names_ordered[i];
             match result {
-                Ok(cap_arc) => {
+                Ok(cap) => {
                     debug!("Successfully initialized capture for '{}' for image capture.", cam_name);
-                    capture_arcs.push(cap_arc);
+                    captures.push(cap);
                     camera_names_ordered.push(cam_name.clone());
                 }
                 Err(e) => {
@@ -119,11 +441,12 @@ impl CameraMediaManager {
             }
         }
 
-        if capture_arcs.is_empty() {
+        if captures.is_empty() {
             warn!("🖼️ No camera streams could be initialized for image capture. Aborting.");
             return Ok(Vec::new());
         }
-        info!("Successfully initialized {} out of {} camera streams for image capture.", capture_arcs.len(), cameras_info.len());
+        info!("Successfully initialized {} out of {} camera streams for image capture.", captures.len(), cameras_info.len());
+        let stream_count = captures.len();
 
         // 2. Prepare output directory
         if !output_dir.exists() {
@@ -134,72 +457,73 @@ impl CameraMediaManager {
         
         // 3. Parallel Frame Reading and Saving
         let mut read_tasks = Vec::new();
-        info!("🖼️ Spawning parallel frame read/save tasks for {} cameras.", capture_arcs.len());
+        info!("🖼️ Spawning parallel frame read/save tasks for {} cameras.", stream_count);
 
-        let barrier = Arc::new(Barrier::new(capture_arcs.len()));
+        let barrier = Arc::new(Barrier::new(stream_count));
 
-        for (idx, cap_arc_clone) in capture_arcs.iter().cloned().enumerate() {
+        for (idx, capture) in captures.into_iter().enumerate() {
             let cam_name = camera_names_ordered[idx].clone();
             let app_config_task_clone = app_config.clone();
             let output_dir_task_clone = output_dir.clone();
             let barrier_clone = barrier.clone();
+            let post_process_steps = self.post_process.get(&cam_name).cloned().unwrap_or_default();
 
-            let task = tokio::task::spawn_blocking(move || -> Result<(PathBuf, String, DateTime<Utc>)> {
-                barrier_clone.wait();
+            let cpus = self.cpu_affinity.get(&cam_name).cloned();
+            let _camera = logging_setup::camera_span(&cam_name).entered();
+            // The task owns the stream while it runs and hands it back with the result.
+            let task = opencv_pool::spawn(opencv_pool::pinned(cpus, move || {
+                let mut capture = capture;
+                let result = (|| -> Result<(PathBuf, String, DateTime<Utc>)> {
+                    // Cached streams kept buffering since the last capture; drain them before the shared moment.
+                    // Every task reaches the barrier, even a failed one, or the rest of the wave waits on it forever.
+                    let flushed = RtspGrab::resolve(&app_config_task_clone, None).flush(&mut capture, &cam_name);
+                    barrier_clone.wait();
+                    flushed?;
                 
-                let mut frame = opencv_core::Mat::default();
+                    let mut frame = opencv_core::Mat::default();
                 
-                // Lock inside task
-                // Note: futures::executor::block_on is used here because spawn_blocking runs in a
-                // separate thread pool that doesn't have a Tokio runtime context by default.
-                // Locking an async Mutex from a synchronous context requires a bridge like block_on.
-                let mut cap_guard = match futures::executor::block_on(cap_arc_clone.lock()) {
-                    guard => guard, // This part seems a bit off, direct assignment is fine if lock() returns the guard
-                };
-
-                let read_start_time = std::time::Instant::now();
-                if !cap_guard.read(&mut frame).map_err(|e| anyhow!(e).context(format!("OpenCV: Read failed for {}", cam_name)))? {
-                    return Err(anyhow!("OpenCV: Failed to read frame for '{}'", cam_name));
-                }
-                let capture_utc_ts = Utc::now(); // Timestamp immediately after read
-                debug!("OpenCV (blocking): Frame read for '{}' in {:?}, captured at {}", cam_name, read_start_time.elapsed(), capture_utc_ts);
+                    let read_start_time = std::time::Instant::now();
+                    if !capture.read(&mut frame).map_err(|e| anyhow!(e).context(format!("OpenCV: Read failed for {}", cam_name)))? {
+                        return Err(anyhow!("OpenCV: Failed to read frame for '{}'", cam_name));
+                    }
+                    let capture_utc_ts = Utc::now(); // Timestamp immediately after read
+                    debug!("OpenCV (blocking): Frame read for '{}' in {:?}, captured at {}", cam_name, read_start_time.elapsed(), capture_utc_ts);
 
 
-                if frame.empty() {
-                    return Err(anyhow!("OpenCV: Captured frame is empty for '{}'", cam_name));
-                }
+                    if frame.empty() {
+                        return Err(anyhow!("OpenCV: Captured frame is empty for '{}'", cam_name));
+                    }
 
-                // Generate filename using the precise capture_utc_ts
-                let local_ts_for_filename = DateTime::<chrono::Local>::from(capture_utc_ts);
-                let filename_ts_str = local_ts_for_filename.format(&app_config_task_clone.filename_timestamp_format).to_string();
-                let filename = format!("{}_{}.{}", cam_name, filename_ts_str, app_config_task_clone.image_format);
-                let output_path = output_dir_task_clone.join(&filename);
-
-                // Ensure parent directory exists (it should due to earlier check, but good for safety)
-                if let Some(parent_dir) = output_path.parent() {
-                    if !parent_dir.exists() { // Redundant if output_dir itself was created, but harmless
-                         std::fs::create_dir_all(parent_dir)
-                             .with_context(|| format!("OpenCV: Failed to create parent for image '{}'", output_path.display()))?;
+                    if !post_process_steps.is_empty() {
+                        let overlay = OverlayInfo { camera_name: &cam_name, captured_at: capture_utc_ts };
+                        frame = post_process::apply(&post_process_steps, &frame, Some(&overlay))
+                            .with_context(|| format!("Post-processing failed for '{}'", cam_name))?;
                     }
-                }
 
-                let mut params = opencv_core::Vector::<i32>::new();
-                if app_config_task_clone.image_format.to_lowercase() == "jpg" || app_config_task_clone.image_format.to_lowercase() == "jpeg" {
-                    params.push(imgcodecs::IMWRITE_JPEG_QUALITY);
-                    params.push(app_config_task_clone.jpeg_quality.unwrap_or(95) as i32); // Use configured or default
-                } else if app_config_task_clone.image_format.to_lowercase() == "png" {
-                    params.push(imgcodecs::IMWRITE_PNG_COMPRESSION);
-                    params.push(app_config_task_clone.png_compression.unwrap_or(3) as i32); // Use configured or default
-                }
+                    // Generate filename using the precise capture_utc_ts
+                    let local_ts_for_filename = DateTime::<chrono::Local>::from(capture_utc_ts);
+                    let filename_ts_str = local_ts_for_filename.format(&app_config_task_clone.filename_timestamp_format).to_string();
+                    let filename = format!("{}_{}.{}", cam_name, filename_ts_str, app_config_task_clone.image_format);
+                    let output_path = output_dir_task_clone.join(&filename);
 
+                    // Ensure parent directory exists (it should due to earlier check, but good for safety)
+                    if let Some(parent_dir) = output_path.parent() {
+                        if !parent_dir.exists() { // Redundant if output_dir itself was created, but harmless
+                             std::fs::create_dir_all(parent_dir)
+                                 .with_context(|| format!("OpenCV: Failed to create parent for image '{}'", output_path.display()))?;
+                        }
+                    }
 
-                let imwrite_start = std::time::Instant::now();
-                imgcodecs::imwrite(output_path.to_str().context("Invalid path (not UTF-8) for imwrite")?, &frame, &params)
-                    .map_err(|e| anyhow!(e).context(format!("OpenCV: Imwrite failed for {} to {}", cam_name, output_path.display())))?;
-                debug!("OpenCV (blocking): Image written for '{}' in {:?}", cam_name, imwrite_start.elapsed());
+                    let imwrite_start = std::time::Instant::now();
+                    let output_path = atomic_file::write_with(&output_path, |part| {
+                        frame_export::write_mat(part, &frame, &app_config_task_clone.image_format, app_config_task_clone.jpeg_quality, app_config_task_clone.png_compression)
+                    }).with_context(|| format!("OpenCV: Imwrite failed for {} to {}", cam_name, output_path.display()))?;
+                    debug!("OpenCV (blocking): Image written for '{}' in {:?}", cam_name, imwrite_start.elapsed());
                 
-                Ok((output_path, cam_name, capture_utc_ts))
-            });
+                    Ok((output_path, cam_name, capture_utc_ts))
+                })();
+                (capture, result)
+            }));
             read_tasks.push(task);
         }
 
@@ -209,49 +533,193 @@ impl CameraMediaManager {
         info!("🏁 All parallel image capture/save tasks completed processing.");
         for (idx, result_outer) in frame_save_results.into_iter().enumerate() {
             let cam_name_for_log = &camera_names_ordered.get(idx).map_or_else(|| "unknown_camera".to_string(), |cn| cn.clone());
-            match result_outer { // Handle JoinError from spawn_blocking
-                Ok(Ok((path, name, ts))) => {
+            match result_outer { // Outer error: the task panicked, and its stream is gone with it
+                Ok((capture, Ok((path, name, ts)))) => {
                     // Log success with consistent camera name from original order if available
                     info!("✅ Image saved for '{}' to {} (captured at {} UTC)", name, path.display(), ts.to_rfc3339());
+                    events::publish(Event::FrameCaptured { camera: name.clone(), at: ts, path: Some(path.clone()) });
+                    self.return_capture(&name, capture).await;
                     saved_image_details.push((path, name, ts));
                 }
-                Ok(Err(e)) => { // Error from the task's Result
+                Ok((_, Err(e))) => { // Error from the task's Result; the stream is dropped and reopened next time
                     error!("❌ Error capturing/saving frame for camera '{}': {:#}", cam_name_for_log, e);
                 }
                 Err(e) => { // Task panicked
-                    error!("❌ Image capture task for camera '{}' panicked: {:#}", cam_name_for_log, e);
+                    error!("❌ Image capture for camera '{}' failed: {:#}", cam_name_for_log, e);
                 }
             }
         }
         
         let saved_image_paths: Vec<PathBuf> = saved_image_details.iter().map(|(p, _, _)| p.clone()).collect();
 
-        if saved_image_paths.is_empty() && !cameras_info.is_empty() && !capture_arcs.is_empty() {
+        if saved_image_paths.is_empty() && !cameras_info.is_empty() && stream_count > 0 {
              warn!(
                 "📸 Parallel image capture tasks completed, but no files were produced from {} successfully initialized streams. This might indicate issues during read/save for all processed cameras.",
-                capture_arcs.len()
+                stream_count
             );
-        } else if saved_image_paths.is_empty() && capture_arcs.is_empty() {
-            // This case should be covered by the earlier check on capture_arcs.is_empty(), but for robustness:
+        } else if saved_image_paths.is_empty() && stream_count == 0 {
+            // This case should be covered by the earlier check on captures.is_empty(), but for robustness:
             info!("📸 Image capture: No camera streams were available or initialized successfully.");
         } else {
             info!(
                 "✅ Successfully captured and saved {} image file(s) from {} camera streams in {:?}.",
                 saved_image_paths.len(),
-                capture_arcs.len(), // Log how many streams were attempted in parallel
+                stream_count, // Log how many streams were attempted in parallel
                 overall_start_time.elapsed()
             );
         }
         Ok(saved_image_paths)
     }
 
+    /// Records every camera for `duration`. With a stream limit, cameras queue and record in
+    /// consecutive waves, each for the full duration; queued waves are skipped once the
+    /// recording was cancelled or stopped for low disk space.
     pub async fn record_video(
         &self,
         cameras_info: &[(String, String)],
         app_config: &AppSettings,
         output_dir: PathBuf,
         duration: Duration,
-    ) -> Result<Vec<PathBuf>> {
+    ) -> Result<Vec<CameraRecordingResult>> {
+        self.low_disk.store(false, Ordering::SeqCst);
+        let waves = self.waves(cameras_info);
+        if waves.len() > 1 {
+            info!("🚦 Recording {} cameras in {} waves (max_concurrent_streams).", cameras_info.len(), waves.len());
+        }
+        let mut per_camera_results = Vec::new();
+        for (wave_idx, wave) in waves.iter().enumerate() {
+            let stopped = wave_idx > 0 && (self.stopped_for_low_disk() || self.cancel.as_ref().is_some_and(|c| c.is_cancelled()));
+            if stopped {
+                warn!("🛑 Recording stopped; {} queued camera(s) were not recorded.", waves[wave_idx..].iter().map(|w| w.len()).sum::<usize>());
+                for (name, _) in waves[wave_idx..].iter().copied().flatten() {
+                    per_camera_results.push((name.clone(), Err(anyhow!("Not recorded: the recording stopped before this camera's wave started"))));
+                }
+                break;
+            }
+            if waves.len() > 1 {
+                info!("🚦 Wave {} of {}: {}", wave_idx + 1, waves.len(), wave.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", "));
+            }
+            let _permits = self.acquire_streams(wave.len()).await?;
+            per_camera_results.extend(self.record_video_wave(wave, app_config, output_dir.clone(), duration, self.sync_start.filter(|_| wave_idx == 0)).await?);
+            if waves.len() > 1 {
+                self.release_streams(wave).await;
+            }
+        }
+        Ok(per_camera_results)
+    }
+
+    /// Starts an HTTP snapshot recording for each camera whose RTSP stream could not be opened
+    /// and that has a fallback. It runs alongside the wave's RTSP recordings.
+    fn spawn_http_fallbacks(
+        &self,
+        cameras: Vec<(String, anyhow::Error)>,
+        app_config: &AppSettings,
+        output_dir: &Path,
+        duration: Duration,
+        start_at: Option<DateTime<Utc>>,
+    ) -> Vec<(String, anyhow::Error, OnceTask<FallbackRecording>)> {
+        cameras.into_iter().filter_map(|(name, rtsp_error)| {
+            let device = self.http_fallback.get(&name)?.clone();
+            let dir = self.camera_output_dirs.get(&name).map(PathBuf::as_path).unwrap_or(output_dir).to_path_buf();
+            // "_http" marks the file as a degraded recording.
+            let timestamp = Utc::now().format(&app_config.filename_timestamp_format).to_string();
+            let path = dir.join(format!("{}_{}_http.{}", name, timestamp, app_config.video_format));
+            let fps = app_config.video_fps.unwrap_or(30.0) as f64;
+            let fourcc = writer_fourcc(app_config, &name);
+            let cancel = self.cancel.clone();
+            let fallbacks = self.fallbacks.clone();
+            let camera = name.clone();
+            let task = self.supervisor.spawn_once(&name, RECORDING_TASK, async move {
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create output directory for videos: {}", dir.display()))?;
+                let recording = http_fallback::record(device, &path, fourcc?, fps, duration, start_at, cancel).await?;
+                if let Ok(mut fallbacks) = fallbacks.lock() {
+                    fallbacks.insert(camera, recording.clone());
+                }
+                Ok(recording)
+            }.instrument(logging_setup::camera_span(&name)));
+            Some((name, rtsp_error, task))
+        }).collect()
+    }
+
+    async fn join_http_fallbacks(tasks: Vec<(String, anyhow::Error, OnceTask<FallbackRecording>)>) -> Vec<CameraRecordingResult> {
+        let mut results = Vec::new();
+        for (name, rtsp_error, task) in tasks {
+            match task.await {
+                Ok(recording) => {
+                    events::publish(Event::RecordingStopped { camera: name.clone(), at: Utc::now(), files: vec![recording.path.clone()], error: None });
+                    results.push((name, Ok(vec![recording.path])));
+                }
+                Err(e) => {
+                    let e = e.context(format!("RTSP failed ({:#}) and so did the HTTP snapshot fallback", rtsp_error));
+                    error!("❌ Error recording video for camera '{}': {:#}", name, e);
+                    events::publish(Event::RecordingStopped { camera: name.clone(), at: Utc::now(), files: Vec::new(), error: Some(format!("{:#}", e)) });
+                    results.push((name, Err(e)));
+                }
+            }
+        }
+        results
+    }
+
+    /// Starts a recording for each camera of a wave that records itself. They run alongside
+    /// the wave's stream recordings.
+    fn spawn_local_recordings(
+        &self,
+        cameras: Vec<String>,
+        app_config: &AppSettings,
+        output_dir: &Path,
+        duration: Duration,
+        start_at: Option<DateTime<Utc>>,
+    ) -> Vec<(String, OnceTask<PathBuf>)> {
+        cameras.into_iter().filter_map(|name| {
+            let recorder = self.local_sources.get(&name)?.clone();
+            let dir = self.camera_output_dirs.get(&name).map(PathBuf::as_path).unwrap_or(output_dir).to_path_buf();
+            let timestamp = Utc::now().format(&app_config.filename_timestamp_format).to_string();
+            let path = dir.join(format!("{}_{}.{}", name, timestamp, recorder.extension(app_config)));
+            let request = VideoRecordRequest { app: app_config.clone(), duration, start_at, cancel: self.cancel.clone() };
+            let recorded = self.recorded.clone();
+            let camera = name.clone();
+            let task = self.supervisor.spawn_once(&name, RECORDING_TASK, async move {
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create output directory for videos: {}", dir.display()))?;
+                let (path, recorded_for) = recorder.record(&path, &request).await?;
+                if let Ok(mut recorded) = recorded.lock() {
+                    recorded.insert(camera, RecordedSpan { duration: recorded_for, ..Default::default() });
+                }
+                Ok(path)
+            }.instrument(logging_setup::camera_span(&name)));
+            Some((name, task))
+        }).collect()
+    }
+
+    /// Waits for recordings that do not go through an OpenCV stream (virtual cameras, .bag files).
+    async fn join_local_recordings(tasks: Vec<(String, OnceTask<PathBuf>)>) -> Vec<CameraRecordingResult> {
+        let mut results = Vec::new();
+        for (name, task) in tasks {
+            match task.await {
+                Ok(path) => {
+                    events::publish(Event::RecordingStopped { camera: name.clone(), at: Utc::now(), files: vec![path.clone()], error: None });
+                    results.push((name, Ok(vec![path])));
+                }
+                Err(e) => {
+                    error!("❌ Error recording video for camera '{}': {:#}", name, e);
+                    events::publish(Event::RecordingStopped { camera: name.clone(), at: Utc::now(), files: Vec::new(), error: Some(format!("{:#}", e)) });
+                    results.push((name, Err(e)));
+                }
+            }
+        }
+        results
+    }
+
+    /// Records one wave of cameras, started together behind a barrier.
+    async fn record_video_wave(
+        &self,
+        cameras_info: &[(String, String)],
+        app_config: &AppSettings,
+        output_dir: PathBuf,
+        duration: Duration,
+        sync: Option<SyncStart>,
+    ) -> Result<Vec<CameraRecordingResult>> {
         info!("📹 Attempting video recording for {} cameras for {:?}", cameras_info.len(), duration);
         let overall_start_time = std::time::Instant::now();
 
@@ -263,39 +731,69 @@ impl CameraMediaManager {
         // 1. Get or initialize all captures (Parallelized) - Same as before
         let mut capture_init_futures = Vec::new();
         let mut temp_camera_names_ordered = Vec::new(); 
+        let mut local_cameras = Vec::new();
 
         for (name, url) in cameras_info {
+            if self.local_sources.contains_key(name) {
+                local_cameras.push(name.clone());
+                continue;
+            }
             debug!("  Queueing capture initialization for recording: {} ({})", name, url);
             temp_camera_names_ordered.push(name.clone());
-            capture_init_futures.push(self.get_or_init_capture(name, url));
+            let timeouts = self.timeouts_for(name, app_config);
+            capture_init_futures.push(async move {
+                let connect_start = std::time::Instant::now();
+                let result = self.take_or_open_capture(name, url, timeouts).await;
+                (result, connect_start.elapsed())
+            }.instrument(logging_setup::camera_span(name)));
         }
 
         info!("  Initializing {} camera stream(s) for video recording concurrently...", capture_init_futures.len());
         let init_results = join_all(capture_init_futures).await;
         info!("  All camera stream initialization attempts for video recording completed.");
 
-        let mut capture_arcs = Vec::new();
+        let mut captures = Vec::new();
         let mut camera_names_ordered = Vec::new(); 
+        let mut per_camera_results: Vec<CameraRecordingResult> = Vec::new();
+        let mut fallback_cameras = Vec::new();
 
-        for (i, result) in init_results.into_iter().enumerate() {
+        for (i, (result, connect_time)) in init_results.into_iter().enumerate() {
             let cam_name = &temp_camera_names_ordered[i];
+            if let Ok(mut timings) = self.timings.lock() {
+                timings.insert(cam_name.clone(), CaptureTimings { connect_ms: CaptureTimings::ms(connect_time), ..Default::default() });
+            }
             match result {
-                Ok(cap_arc) => {
+                Ok(cap) => {
                     debug!("Successfully initialized capture for '{}' for video recording.", cam_name);
-                    capture_arcs.push(cap_arc);
+                    captures.push(cap);
                     camera_names_ordered.push(cam_name.clone());
                 }
+                Err(e) if self.http_fallback.contains_key(cam_name) => {
+                    warn!("⚠️ RTSP failed for '{}' ({:#}); recording from HTTP snapshots instead, at a lower frame rate.", cam_name, e);
+                    fallback_cameras.push((cam_name.clone(), e));
+                }
                 Err(e) => {
                     error!("Failed to get/init capture for camera '{}' for video recording: {:#}. Skipping this camera.", cam_name, e);
+                    per_camera_results.push((cam_name.clone(), Err(e)));
                 }
             }
         }
 
-        if capture_arcs.is_empty() {
-            warn!("🎬 No camera streams could be initialized for video recording. Aborting.");
-            return Ok(Vec::new());
+        // Scheduled once the streams are open, so opening them does not eat into the lead time.
+        let start_at = sync.map(|sync| sync.host_time(sync.schedule("Video Recording")));
+        let fallback_tasks = self.spawn_http_fallbacks(fallback_cameras, app_config, &output_dir, duration, start_at);
+        let local_tasks = self.spawn_local_recordings(local_cameras, app_config, &output_dir, duration, start_at);
+
+        if captures.is_empty() {
+            if fallback_tasks.is_empty() && local_tasks.is_empty() {
+                warn!("🎬 No camera streams could be initialized for video recording. Aborting.");
+            }
+            per_camera_results.extend(Self::join_http_fallbacks(fallback_tasks).await);
+            per_camera_results.extend(Self::join_local_recordings(local_tasks).await);
+            return Ok(per_camera_results);
         }
-        info!("Successfully initialized {} out of {} camera streams for video recording.", capture_arcs.len(), cameras_info.len());
+        info!("Successfully initialized {} out of {} camera streams for video recording.", captures.len(), cameras_info.len());
+        let stream_count = captures.len();
 
         // 2. Prepare output directory and output paths per camera
         if !output_dir.exists() {
@@ -307,42 +805,66 @@ impl CameraMediaManager {
         let mut per_camera_output_paths = Vec::new();
         for name in &camera_names_ordered {
             let timestamp = Utc::now().format(&app_config.filename_timestamp_format).to_string(); // Use consistent timestamp format
-            let filename = format!("{}_{}.{}", name, timestamp, app_config.video_format);
-            per_camera_output_paths.push(output_dir.join(filename));
+            // VFR needs a container with per-frame timestamps; Matroska carries any codec.
+            let extension = if self.variable_frame_rate { "mkv" } else { app_config.video_format.as_str() };
+            let filename = format!("{}_{}.{}", name, timestamp, extension);
+            per_camera_output_paths.push(self.camera_output_dirs.get(name).unwrap_or(&output_dir).join(filename));
         }
         
         // 3. Spawn per-camera recording tasks, synchronized by a barrier
         let mut record_tasks = Vec::new();
-        let barrier = Arc::new(Barrier::new(capture_arcs.len()));
-        info!("🎬 Spawning parallel video recording tasks for {} cameras, synchronized by a barrier.", capture_arcs.len());
+        let barrier = Arc::new(Barrier::new(stream_count));
+        let watchdog = WatchdogSettings::from_app(app_config);
+        info!("🎬 Spawning parallel video recording tasks for {} cameras, synchronized by a barrier.", stream_count);
 
-        for i in 0..capture_arcs.len() {
-            let cap_arc_clone = capture_arcs[i].clone();
+        for (i, capture) in captures.into_iter().enumerate() {
             let cam_name_clone = camera_names_ordered[i].clone();
             let output_path_clone = per_camera_output_paths[i].clone();
             let app_config_clone = app_config.clone();
+            let overlay_settings = app_config.overlay.clone().filter(|o| o.enabled);
+            let live_preview = self.live_preview.clone();
+            let cancel = self.cancel.clone();
+            let low_disk = self.low_disk.clone();
+            let rtsp_url = cameras_info.iter().find(|(name, _)| *name == camera_names_ordered[i]).map(|(_, url)| url.clone()).unwrap_or_default();
+            let reopen_params = Self::open_params(self.timeouts_for(&camera_names_ordered[i], app_config));
+            let transport = self.rtsp_transport.get(&camera_names_ordered[i]).copied();
+            let stall_timeout = Duration::from_secs_f32(app_config.stall_timeout_secs.unwrap_or(DEFAULT_STALL_TIMEOUT_SECS));
+            let max_reconnects = app_config.max_reconnects.unwrap_or(DEFAULT_MAX_RECONNECTS);
+            let vfr = self.variable_frame_rate;
+            let log_timestamps = app_config.frame_timestamps.unwrap_or(vfr);
+            let ffmpeg_path = app_config.ffmpeg_path.clone().unwrap_or_else(|| "ffmpeg".to_string());
+            let min_free_bytes = disk_space::min_free_bytes(app_config);
             let duration_clone = duration;
             let barrier_clone = barrier.clone();
+            // The barrier wait is unbounded: a peer stuck opening its writer must not fail the rest.
+            let phase = BlockingPhase::new_unbounded("waiting for other cameras to be ready");
+            let phase_clone = phase.clone();
+            let cpus = self.cpu_affinity.get(&camera_names_ordered[i]).cloned();
+            let timings = self.timings.clone();
+            let recorded = self.recorded.clone();
+            let flush = RtspGrab::resolve(app_config, None);
+            let min_kbps = self.min_stream_kbps.get(&camera_names_ordered[i]).copied().or(app_config.min_stream_kbps);
 
-            let task = tokio::task::spawn_blocking(move || -> Result<PathBuf> {
+            let work = opencv_pool::pinned(cpus, move || -> Result<(Vec<PathBuf>, videoio::VideoCapture)> {
+                let phase = phase_clone;
                 barrier_clone.wait(); // Synchronize start of blocking work
+                phase.enter("reading stream properties");
                 let task_start_time = std::time::Instant::now();
                 info!("🎬 OpenCV (blocking): Starting recording for camera '{}' to {}", cam_name_clone, output_path_clone.display());
 
-                // cap_arc_clone.blocking_lock() will panic if the mutex is poisoned.
-                // This panic will be caught as a JoinError by the task handling logic later.
-                let mut cap_guard = cap_arc_clone.blocking_lock(); // Made cap_guard mutable
+                // The task owns the stream; it goes back to the cache only if the recording succeeds.
+                let mut capture = capture;
 
-                let frame_width_f64 = cap_guard.get(videoio::CAP_PROP_FRAME_WIDTH)
+                let frame_width_f64 = capture.get(videoio::CAP_PROP_FRAME_WIDTH)
                     .map_err(|e| anyhow::Error::from(e).context(format!("OpenCV: Failed to get CAP_PROP_FRAME_WIDTH for '{}'", cam_name_clone)))?;
                 let frame_width = frame_width_f64 as i32;
 
-                let frame_height_f64 = cap_guard.get(videoio::CAP_PROP_FRAME_HEIGHT)
+                let frame_height_f64 = capture.get(videoio::CAP_PROP_FRAME_HEIGHT)
                     .map_err(|e| anyhow::Error::from(e).context(format!("OpenCV: Failed to get CAP_PROP_FRAME_HEIGHT for '{}'", cam_name_clone)))?;
                 let frame_height = frame_height_f64 as i32;
                 
                 // Get camera reported FPS for logging, but use configured FPS for consistency in recording.
-                let camera_reported_fps: f64 = cap_guard.get(videoio::CAP_PROP_FPS)
+                let camera_reported_fps: f64 = capture.get(videoio::CAP_PROP_FPS)
                     .map_err(|e| anyhow::Error::from(e).context(format!("OpenCV: Failed to get CAP_PROP_FPS for '{}'", cam_name_clone)))?;
                 
                 let common_fps = app_config_clone.video_fps.unwrap_or(30.0) as f64; // FPS to be used for recording
@@ -368,90 +890,392 @@ impl CameraMediaManager {
                 }
 
 
-                let fourcc_str = match app_config_clone.video_codec.to_lowercase().as_str() {
-                    "mjpg" | "mjpeg" => "MJPG",
-                    "xvid" => "XVID",
-                    "mp4v" => "MP4V",
-                    "h264" if app_config_clone.video_format.to_lowercase() == "avi" => "H264", // OpenCV's internal H264 for AVI
-                    "h264" if app_config_clone.video_format.to_lowercase() == "mp4" => "avc1", // More standard for MP4
-                    codec_val => {
-                        warn!("⚠️ Unsupported video_codec '{}' for OpenCV VideoWriter with format '{}' for '{}'. Defaulting to MJPG.", codec_val, app_config_clone.video_format, cam_name_clone);
-                        "MJPG"
+                let fourcc = writer_fourcc(&app_config_clone, &cam_name_clone)?;
+
+                let open_writer = |path: &Path| -> Result<FrameSink> {
+                    if vfr {
+                        phase.enter(&format!("starting ffmpeg for {}", path.display()));
+                        let sink = FrameSink::ffmpeg_vfr(&ffmpeg_path, path, opencv_core::Size::new(frame_width, frame_height), &app_config_clone.video_codec, log_timestamps)?;
+                        info!("✍️ OpenCV (blocking): Variable frame rate writer started for '{}' to {}", cam_name_clone, sink.path().display());
+                        return Ok(sink);
                     }
+                    phase.enter(&format!("opening VideoWriter for {}", path.display()));
+                    // The sink writes to a .part file (removed again if opening fails) and renames it on release.
+                    let sink = FrameSink::opencv(path, fourcc, common_fps, opencv_core::Size::new(frame_width, frame_height), log_timestamps)
+                        .map_err(|e| {
+                            error!("❌ OpenCV (blocking): Failed to open VideoWriter for '{}' at path '{}': {:#}", cam_name_clone, path.display(), e);
+                            e.context(format!("Failed to open VideoWriter for '{}' at path '{}'", cam_name_clone, path.display()))
+                        })?;
+                    info!("✍️ OpenCV (blocking): VideoWriter opened for '{}' to {}", cam_name_clone, sink.path().display());
+                    Ok(sink)
                 };
-                let fourcc = videoio::VideoWriter::fourcc(fourcc_str.chars().nth(0).unwrap_or('M'), fourcc_str.chars().nth(1).unwrap_or('J'), fourcc_str.chars().nth(2).unwrap_or('P'), fourcc_str.chars().nth(3).unwrap_or('G'))?;
-
-                let mut writer = videoio::VideoWriter::new(
-                    output_path_clone.to_str().context("Invalid output path for video (not UTF-8)")?,
-                    fourcc,
-                    common_fps, // Use the potentially overridden common_fps
-                    opencv_core::Size::new(frame_width, frame_height),
-                    true,
-                )?;
-
-                if !videoio::VideoWriter::is_opened(&writer)? {
-                    let err_msg = format!("Failed to open VideoWriter for '{}' at path '{}'", cam_name_clone, output_path_clone.display());
-                    error!("❌ OpenCV (blocking): {}", err_msg);
-                    // Attempt to delete the file if writer creation failed but file might have been touched
-                    if output_path_clone.exists() {
-                        if let Err(del_err) = std::fs::remove_file(&output_path_clone) {
-                            warn!("Failed to delete empty/partial file {} after VideoWriter open error: {}", output_path_clone.display(), del_err);
-                        }
+                let writer_open_start = std::time::Instant::now();
+                let mut writer = open_writer(&output_path_clone)?;
+                let mut write_time = writer_open_start.elapsed();
+                let mut first_frame_time: Option<Duration> = None;
+                let mut segments = vec![writer.path().to_path_buf()];
+                let first_segment = segments[0].clone();
+                let segment_limit_bytes = app_config_clone.max_file_size_mb.map(|mb| mb * 1024 * 1024);
+                let mut segment_frames = 0u64;
+
+                let mut scheduled_start = None;
+                if let Some(at) = start_at {
+                    phase.enter_unbounded("waiting for the scheduled start");
+                    let late = sync_start::sleep_until_blocking(at);
+                    if late.is_zero() {
+                        scheduled_start = Some(at);
+                    } else {
+                        warn!("⚠️ OpenCV (blocking) [{}]: Missed the scheduled start by {:?}; recording now.", cam_name_clone, late);
+                    }
+                    // Frames buffered while waiting are older than the start.
+                    if let Err(e) = flush.flush(&mut capture, &cam_name_clone) {
+                        warn!("⚠️ OpenCV (blocking) [{}]: {:#}", cam_name_clone, e);
                     }
-                    return Err(anyhow!(err_msg));
                 }
-                info!("✍️ OpenCV (blocking): VideoWriter opened for '{}' to {}", cam_name_clone, output_path_clone.display());
-                
+
                 let num_frames = (duration_clone.as_secs_f64() * common_fps).round() as u64;
-                info!("  OpenCV (blocking) [{}]: Starting recording loop for {} frames (duration: {:?}, fps: {}).", cam_name_clone, num_frames, duration_clone, common_fps);
+                // Bounded by the duration and the per-frame read timeout rather than the watchdog.
+                phase.enter_unbounded(&format!("recording {:?} of video", duration_clone));
+                info!("  OpenCV (blocking) [{}]: Starting recording loop for {:?} at {} fps ({} frames).", cam_name_clone, duration_clone, common_fps, num_frames);
+                events::publish(Event::RecordingStarted { camera: cam_name_clone.clone(), at: Utc::now(), path: first_segment.clone() });
 
+                let mut last_live_log: Option<std::time::Instant> = None;
+                let mut live_errors = 0u32;
                 let mut last_error_log_time = std::time::Instant::now();
                 let mut frame_read_error_count = 0;
                 const MAX_CONSECUTIVE_READ_ERRORS: u32 = 5; // Allow a few hiccups
+                let mut last_frame_written = std::time::Instant::now();
+                let mut reconnects = 0u32;
 
-                for frame_idx in 0..num_frames {
+                // Frames are paced against the wall clock: output frame k covers [k/fps, (k+1)/fps)
+                // after the start. A slow camera's frames are duplicated to fill their slots, a fast
+                // camera's surplus frames are dropped, and recording stops when the time is up.
+                // In VFR mode every frame is written once and keeps its own timestamp instead.
+                // A scheduled start anchors the timeline at the scheduled instant, so files from
+                // every host line up frame for frame even though the buffer flush took a moment.
+                let recording_start = scheduled_start
+                    .and_then(|at| std::time::Instant::now().checked_sub((Utc::now() - at).to_std().unwrap_or_default()))
+                    .unwrap_or_else(std::time::Instant::now);
+                let mut timeline_frames = 0u64; // output frames the video's timeline covers so far
+                let mut duplicated = 0u64;
+                let mut dropped = 0u64;
+                let mut resync = false; // after a reconnect the gap is not filled; the new segment starts fresh
+                let mut stopped_early = false;
+                let mut last_frame: Option<(opencv_core::Mat, chrono::DateTime<Utc>)> = None;
+                let mut last_disk_check: Option<std::time::Instant> = None;
+                let mut last_progress_log = std::time::Instant::now();
+                let mut last_progress_event = std::time::Instant::now();
+                let mut closed_segment_bytes = 0u64; // size of the segments already finalized
+                let mut meter = StreamMeter::new(min_kbps);
+                let mut last_rate: Option<StreamRate> = None;
+                let mut last_stats_log = std::time::Instant::now();
+                let mut pauses: Vec<RecordingGap> = Vec::new();
+                let mut writer_released = false; // the recording ended while paused
+                let active = recording_control::ActiveRecording::register(&cam_name_clone);
+
+                while recording_start.elapsed() < duration_clone {
+                    if cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                        warn!("🛑 OpenCV (blocking) [{}]: Recording stopped early after {:?} of {:?}.", cam_name_clone, recording_start.elapsed(), duration_clone);
+                        stopped_early = true;
+                        break;
+                    }
+                    if active.stop_requested() {
+                        info!("⏹️ OpenCV (blocking) [{}]: Recording stopped on request after {:?} of {:?}.", cam_name_clone, recording_start.elapsed(), duration_clone);
+                        stopped_early = true;
+                        break;
+                    }
+                    // Check about once a second; the cameras usually share a disk, so one camera running out stops all.
+                    if min_free_bytes > 0 && last_disk_check.is_none_or(|at| at.elapsed() >= Duration::from_secs(1)) {
+                        last_disk_check = Some(std::time::Instant::now());
+                        let segment_dir = output_path_clone.parent().unwrap_or(Path::new("."));
+                        match disk_space::available_bytes(segment_dir) {
+                            Ok(available) if available < min_free_bytes => {
+                                error!("💾 OpenCV (blocking) [{}]: Only {} MB free on the disk holding {}; stopping the recording after {:?} of {:?}.",
+                                    cam_name_clone, available / (1024 * 1024), segment_dir.display(), recording_start.elapsed(), duration_clone);
+                                // The first camera to notice reports it; the others just stop.
+                                if !low_disk.swap(true, Ordering::SeqCst) {
+                                    events::publish(Event::DiskLow {
+                                        at: Utc::now(),
+                                        path: segment_dir.to_path_buf(),
+                                        available_mb: available / (1024 * 1024),
+                                        min_free_mb: min_free_bytes / (1024 * 1024),
+                                    });
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => debug!("OpenCV (blocking) [{}]: {:#}", cam_name_clone, e),
+                        }
+                    }
+                    if low_disk.load(Ordering::SeqCst) {
+                        warn!("🛑 OpenCV (blocking) [{}]: Recording stopped early (low disk space) after {:?} of {:?}.", cam_name_clone, recording_start.elapsed(), duration_clone);
+                        stopped_early = true;
+                        break;
+                    }
+                    // Paused: finalize the segment so it is a complete file, wait, and continue in a new one.
+                    // Paused time still counts toward the duration.
+                    if recording_control::is_paused() {
+                        let segment = segments.last().cloned().unwrap_or_else(|| output_path_clone.clone());
+                        closed_segment_bytes += writer.written_bytes();
+                        phase.enter(&format!("finalizing {}", segment.display()));
+                        writer.release().with_context(|| format!("OpenCV: Failed to finalize '{}'", segment.display()))?;
+                        let paused_at = Utc::now();
+                        info!("⏸️ OpenCV (blocking) [{}]: Paused after {:?}; {} finalized.", cam_name_clone, recording_start.elapsed(), segment.display());
+                        events::publish(Event::RecordingPaused { camera: cam_name_clone.clone(), at: paused_at });
+                        phase.enter_unbounded("paused");
+                        let cancelled = || cancel.as_ref().is_some_and(|c| c.is_cancelled()) || low_disk.load(Ordering::SeqCst) || active.stop_requested();
+                        while recording_control::is_paused() && recording_start.elapsed() < duration_clone && !cancelled() {
+                            std::thread::sleep(Duration::from_millis(50));
+                        }
+                        pauses.push(RecordingGap { from: paused_at, to: Utc::now() });
+                        if recording_control::is_paused() || cancelled() || recording_start.elapsed() >= duration_clone {
+                            info!("⏹️ OpenCV (blocking) [{}]: Recording ended while paused.", cam_name_clone);
+                            writer_released = true;
+                            stopped_early = cancelled();
+                            break;
+                        }
+                        // Frames buffered during the pause are stale.
+                        if let Err(e) = flush.flush(&mut capture, &cam_name_clone) {
+                            warn!("⚠️ OpenCV (blocking) [{}]: {:#}", cam_name_clone, e);
+                        }
+                        let next = segment_path(&first_segment, segments.len() + 1);
+                        writer = open_writer(&next)?;
+                        segments.push(writer.path().to_path_buf());
+                        segment_frames = 0;
+                        resync = true;
+                        frame_read_error_count = 0;
+                        last_frame_written = std::time::Instant::now();
+                        last_frame = None;
+                        meter.restart(closed_segment_bytes);
+                        info!("▶️ OpenCV (blocking) [{}]: Resumed; recording continues in {}.", cam_name_clone, next.display());
+                        events::publish(Event::RecordingResumed { camera: cam_name_clone.clone(), at: Utc::now(), path: writer.path().to_path_buf() });
+                        phase.enter_unbounded(&format!("recording {:?} of video", duration_clone));
+                        continue;
+                    }
+                    // Stalled stream: reopen it and continue in a new segment, so the gap is visible
+                    // in the file names instead of silently shortening the video.
+                    if frame_read_error_count > MAX_CONSECUTIVE_READ_ERRORS || last_frame_written.elapsed() >= stall_timeout {
+                        if reconnects >= max_reconnects {
+                            let err_msg = format!("Aborting recording for '{}': no frame for {:?} and {} reconnect attempt(s) used up.", cam_name_clone, last_frame_written.elapsed(), reconnects);
+                            error!("❌ {}", err_msg);
+                            return Err(anyhow!(err_msg));
+                        }
+                        reconnects += 1;
+                        warn!("🔌 OpenCV (blocking) [{}]: No frame written for {:?}; reconnecting (attempt {} of {}).",
+                            cam_name_clone, last_frame_written.elapsed(), reconnects, max_reconnects);
+                        phase.enter(&format!("reconnecting to the stream of {}", cam_name_clone));
+                        let reopened = rtsp_grab::open_stream(&rtsp_url, &reopen_params, transport)
+                            .map_err(anyhow::Error::from)
+                            .and_then(|cap| if cap.is_opened()? { Ok(cap) } else { Err(anyhow!("stream did not open")) });
+                        events::publish(Event::StreamReconnected {
+                            camera: cam_name_clone.clone(),
+                            at: Utc::now(),
+                            attempt: reconnects,
+                            error: reopened.as_ref().err().map(|e| format!("{:#}", e)),
+                        });
+                        match reopened {
+                            Ok(cap) => {
+                                capture = cap;
+                                let segment = segments.last().cloned().unwrap_or_else(|| output_path_clone.clone());
+                                closed_segment_bytes += writer.written_bytes();
+                                phase.enter(&format!("finalizing {}", segment.display()));
+                                writer.release().with_context(|| format!("OpenCV: Failed to finalize '{}'", segment.display()))?;
+                                let next = segment_path(&first_segment, segments.len() + 1);
+                                writer = open_writer(&next)?;
+                                segments.push(writer.path().to_path_buf());
+                                segment_frames = 0;
+                                resync = true;
+                                info!("🔌 OpenCV (blocking) [{}]: Stream reopened; recording continues in {}.", cam_name_clone, segments.last().map(|p| p.display().to_string()).unwrap_or_default());
+                            }
+                            Err(e) => warn!("⚠️ OpenCV (blocking) [{}]: Reconnect failed: {:#}", cam_name_clone, e),
+                        }
+                        // Either way, give the stream a full stall period (or error budget) before the next attempt.
+                        frame_read_error_count = 0;
+                        last_frame_written = std::time::Instant::now();
+                        phase.enter_unbounded(&format!("recording {:?} of video", duration_clone));
+                    }
                     let mut temp_frame = opencv_core::Mat::default();
                     // Grab and Retrieve in one go for simplicity per frame, per camera
-                    if !cap_guard.read(&mut temp_frame).with_context(|| format!("OpenCV: Read failed for camera '{}'", cam_name_clone))? {
+                    if !capture.read(&mut temp_frame).with_context(|| format!("OpenCV: Read failed for camera '{}'", cam_name_clone))? {
                          if last_error_log_time.elapsed().as_secs() > 2 || frame_read_error_count == 0 {
-                           error!("🚫 OpenCV (blocking) [{}]: Failed to read frame (stream might have ended or temporarily unavailable). Frame index: {}", cam_name_clone, frame_idx);
+                           error!("🚫 OpenCV (blocking) [{}]: Failed to read frame (stream might have ended or temporarily unavailable) at {:?}.", cam_name_clone, recording_start.elapsed());
                            last_error_log_time = std::time::Instant::now();
                         }
-                        frame_read_error_count += 1;
-                        if frame_read_error_count > MAX_CONSECUTIVE_READ_ERRORS {
-                             let err_msg = format!("Aborting recording for '{}' due to {} consecutive frame read errors.", cam_name_clone, MAX_CONSECUTIVE_READ_ERRORS);
-                             error!("❌ {}", err_msg);
-                             return Err(anyhow!(err_msg));
-                        }
+                        frame_read_error_count += 1; // too many in a row triggers a reconnect above
                         // Optional: could sleep briefly before retrying grab on next iteration
                         std::thread::sleep(Duration::from_millis(100)); // Small delay before next attempt
                         continue; // Try next frame
                     }
                     frame_read_error_count = 0; // Reset error count on successful read
+                    let frame_read_at = Utc::now();
+                    let read_elapsed = recording_start.elapsed();
+                    first_frame_time.get_or_insert(read_elapsed);
 
                     if temp_frame.empty() {
                         if last_error_log_time.elapsed().as_secs() > 2 {
-                            warn!("👻 OpenCV (blocking) [{}]: Retrieved empty frame at frame index {}. Skipping write.", cam_name_clone, frame_idx);
+                            warn!("👻 OpenCV (blocking) [{}]: Retrieved empty frame at {:?}. Skipping write.", cam_name_clone, read_elapsed);
                             last_error_log_time = std::time::Instant::now();
                         }
                         continue; 
                     }
-                    writer.write(&temp_frame).with_context(|| format!("OpenCV: Write failed for '{}' to '{}'", cam_name_clone, output_path_clone.display()))?;
+                    if read_elapsed >= duration_clone {
+                        break; // arrived after the end of the recording
+                    }
+                    meter.frame();
+                    // A snapshot of this camera while it records takes the frame from here (without the overlay).
+                    if active.frame_wanted() {
+                        if let Err(e) = active.share_frame(&temp_frame, frame_read_at) {
+                            warn!("⚠️ OpenCV (blocking) [{}]: Could not share a frame with a snapshot: {:#}", cam_name_clone, e);
+                        }
+                    }
+                    if let Some(overlay) = &overlay_settings {
+                        post_process::burn_in(&mut temp_frame, overlay, &cam_name_clone, frame_read_at)
+                            .with_context(|| format!("OpenCV: Overlay failed for '{}'", cam_name_clone))?;
+                    }
+
+                    // Number of output frames the timeline should hold once this frame is written.
+                    let due = ((read_elapsed.as_secs_f64() * common_fps).floor() as u64 + 1).min(num_frames.max(1));
+                    if resync {
+                        timeline_frames = timeline_frames.max(due - 1);
+                        resync = false;
+                    }
+                    let copies = if vfr { 1 } else { due.saturating_sub(timeline_frames) };
+                    if copies == 0 {
+                        dropped += 1; // a newer frame already filled this slot
+                    } else {
+                        let segment = segments.last().cloned().unwrap_or_else(|| output_path_clone.clone());
+                        for _ in 0..copies {
+                            writer.write(&temp_frame, frame_read_at).with_context(|| format!("OpenCV: Write failed for '{}' to '{}'", cam_name_clone, segment.display()))?;
+                        }
+                        duplicated += copies - 1;
+                        timeline_frames = if vfr { timeline_frames + 1 } else { due };
+                        segment_frames += copies;
+                        last_frame_written = std::time::Instant::now();
+
+                        // Roll over before the limit, leaving room for two more frames and the index written on release.
+                        if let Some(limit) = segment_limit_bytes {
+                            let written = writer.written_bytes();
+                            if written + 2 * (written / segment_frames) >= limit - limit / 50 {
+                                closed_segment_bytes += written;
+                                phase.enter(&format!("finalizing {}", segment.display()));
+                                writer.release().with_context(|| format!("OpenCV: Failed to finalize '{}'", segment.display()))?;
+                                let next = segment_path(&first_segment, segments.len() + 1);
+                                info!("📼 OpenCV (blocking) [{}]: {} reached {} MB after {} frames; continuing in {}",
+                                    cam_name_clone, segment.display(), written / (1024 * 1024), segment_frames, next.display());
+                                writer = open_writer(&next)?;
+                                segments.push(writer.path().to_path_buf());
+                                segment_frames = 0;
+                                phase.enter_unbounded(&format!("recording {:?} of video", duration_clone));
+                            }
+                        }
+                    }
+
+                    // The preview is best effort: a Rerun failure never affects the recording.
+                    if let Some(live) = &live_preview {
+                        if last_live_log.is_none_or(|at| at.elapsed() >= live.interval()) {
+                            last_live_log = Some(std::time::Instant::now());
+                            if let Err(e) = live.log_bgr_frame(&cam_name_clone, &temp_frame, frame_read_at) {
+                                if live_errors == 0 {
+                                    warn!("⚠️ Rerun: Live preview of '{}' failed: {:#}. Recording continues.", cam_name_clone, e);
+                                }
+                                live_errors += 1;
+                            }
+                        }
+                    }
                     
-                    if frame_idx > 0 && frame_idx % (common_fps.round() as u64 * 5) == 0 { // Log every 5 seconds approx
-                        debug!("  OpenCV (blocking) [{}]: Recorded frame {} / {} ({:.1}%)", cam_name_clone, frame_idx + 1, num_frames, (frame_idx + 1) as f64 / num_frames as f64 * 100.0);
+                    if last_progress_event.elapsed() >= Duration::from_secs(1) {
+                        last_progress_event = std::time::Instant::now();
+                        let bytes = closed_segment_bytes + writer.written_bytes();
+                        if let Some(rate) = meter.sample(bytes) {
+                            stream_stats::update(&cam_name_clone, rate);
+                            match meter.check(&rate) {
+                                Some(true) => {
+                                    let min_kbps = meter.min_kbps().unwrap_or_default();
+                                    warn!("📉 OpenCV (blocking) [{}]: Stream dropped to {:.0} kbps at {:.1} fps, below min_stream_kbps {:.0}; check the camera's link.",
+                                        cam_name_clone, rate.kbps, rate.fps, min_kbps);
+                                    events::publish(Event::StreamDegraded { camera: cam_name_clone.clone(), at: rate.at, kbps: rate.kbps, fps: rate.fps, min_kbps });
+                                }
+                                Some(false) => info!("📶 OpenCV (blocking) [{}]: Stream recovered to {:.0} kbps at {:.1} fps.", cam_name_clone, rate.kbps, rate.fps),
+                                None => {}
+                            }
+                            last_rate = Some(rate);
+                        }
+                        events::publish(Event::RecordingProgress {
+                            camera: cam_name_clone.clone(),
+                            at: frame_read_at,
+                            frames: timeline_frames,
+                            total_frames: num_frames,
+                            bytes,
+                            fps: last_rate.map(|rate| rate.fps),
+                            kbps: last_rate.map(|rate| rate.kbps),
+                        });
+                    }
+                    if last_stats_log.elapsed() >= STATS_LOG_INTERVAL {
+                        last_stats_log = std::time::Instant::now();
+                        if let Some(rate) = last_rate {
+                            info!("📶 OpenCV (blocking) [{}]: {:.1} fps, {:.0} kbps.", cam_name_clone, rate.fps, rate.kbps);
+                        }
                     }
+                    if last_progress_log.elapsed() >= Duration::from_secs(5) {
+                        last_progress_log = std::time::Instant::now();
+                        debug!("  OpenCV (blocking) [{}]: Recorded frame {} / {} ({:.1}%)", cam_name_clone, timeline_frames, num_frames, timeline_frames as f64 / num_frames.max(1) as f64 * 100.0);
+                    }
+                    last_frame = Some((temp_frame, frame_read_at));
                 }
-                
-                // VideoWriter is dropped here, releasing the file.
-                info!("🏁 OpenCV (blocking) [{}]: Finished recording task in {:?}. Output file: {}", 
-                    cam_name_clone, task_start_time.elapsed(), output_path_clone.display());
-                Ok(output_path_clone)
+
+                // A camera that fell behind at the very end leaves the last slots empty; fill them
+                // so the video is as long as the recording. Early stops keep their true length.
+                if !stopped_early && !vfr && !writer_released {
+                    if let Some((frame, captured_at)) = &last_frame {
+                        let segment = segments.last().cloned().unwrap_or_else(|| output_path_clone.clone());
+                        while timeline_frames < num_frames && !resync {
+                            writer.write(frame, *captured_at).with_context(|| format!("OpenCV: Write failed for '{}' to '{}'", cam_name_clone, segment.display()))?;
+                            timeline_frames += 1;
+                            duplicated += 1;
+                        }
+                    }
+                }
+                if duplicated > 0 || dropped > 0 {
+                    info!("⏱️ OpenCV (blocking) [{}]: Held {} fps against the wall clock: {} frame(s) duplicated, {} dropped.", cam_name_clone, common_fps, duplicated, dropped);
+                }
+
+                // Releasing finalizes the container (index, duration) even when stopped early.
+                let last_segment = segments.last().cloned().unwrap_or_else(|| output_path_clone.clone());
+                phase.enter(&format!("finalizing {}", last_segment.display()));
+                let release_start = std::time::Instant::now();
+                if !writer_released {
+                    writer.release().with_context(|| format!("OpenCV: Failed to finalize '{}'", last_segment.display()))?;
+                }
+                write_time += release_start.elapsed();
+                drop(active);
+                let paused: Duration = pauses.iter().map(|gap| (gap.to - gap.from).to_std().unwrap_or_default()).sum();
+                let recorded_for = recording_start.elapsed().min(duration_clone).saturating_sub(paused);
+                if stopped_early {
+                    info!("⏱️ OpenCV (blocking) [{}]: Recorded {:.1}s of the planned {:?}.", cam_name_clone, recorded_for.as_secs_f64(), duration_clone);
+                }
+                if let Ok(mut recorded) = recorded.lock() {
+                    recorded.insert(cam_name_clone.clone(), RecordedSpan { duration: recorded_for, gaps: pauses, stream: meter.summary() });
+                }
+                if let Ok(mut timings) = timings.lock() {
+                    let entry = timings.entry(cam_name_clone.clone()).or_default();
+                    entry.first_frame_ms = first_frame_time.and_then(CaptureTimings::ms);
+                    entry.write_ms = CaptureTimings::ms(write_time);
+                }
+                info!("🏁 OpenCV (blocking) [{}]: Finished recording task in {:?}. Output file(s): {}", 
+                    cam_name_clone, task_start_time.elapsed(),
+                    segments.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "));
+                Ok((segments, capture))
+            });
+            // Under the supervisor, so a recording shows up in its status while it runs.
+            let camera = camera_names_ordered[i].clone();
+            let task = self.supervisor.spawn_once(&camera_names_ordered[i], RECORDING_TASK, async move {
+                blocking_watchdog::run_blocking(&camera, watchdog, phase, work).await
             });
             record_tasks.push(task);
         }
 
         let task_results = join_all(record_tasks).await;
+        let fallback_results = Self::join_http_fallbacks(fallback_tasks).await;
+        let local_results = Self::join_local_recordings(local_tasks).await;
         let mut successful_paths = Vec::new();
         let mut  had_errors = false;
 
@@ -460,15 +1284,25 @@ impl CameraMediaManager {
             let cam_name_for_log = &camera_names_ordered.get(idx).map_or_else(|| "unknown_camera".to_string(), |cn| cn.clone());
             let output_path_for_log = &per_camera_output_paths.get(idx).map_or_else(|| PathBuf::from("unknown_path"), |p| p.clone());
 
-            match result_outer { // Handle JoinError from spawn_blocking
-                Ok(Ok(path)) => {
-                    info!("✅ Successfully recorded video for '{}' to {}", cam_name_for_log, path.display());
-                    successful_paths.push(path);
+            stream_stats::clear(cam_name_for_log);
+            match result_outer { // Panics and watchdog timeouts arrive as errors too
+                Ok((paths, capture)) => {
+                    self.return_capture(cam_name_for_log, capture).await;
+                    events::publish(Event::RecordingStopped { camera: cam_name_for_log.clone(), at: Utc::now(), files: paths.clone(), error: None });
+                    if paths.len() > 1 {
+                        info!("✅ Successfully recorded video for '{}' to {} segments starting at {}", cam_name_for_log, paths.len(), output_path_for_log.display());
+                    } else {
+                        info!("✅ Successfully recorded video for '{}' to {}", cam_name_for_log, output_path_for_log.display());
+                    }
+                    successful_paths.extend(paths.iter().cloned());
+                    per_camera_results.push((cam_name_for_log.clone(), Ok(paths)));
                 }
-                Ok(Err(e)) => { // Error from the task's Result
+                Err(e) => {
                     error!("❌ Error recording video for camera '{}' to '{}': {:#}", cam_name_for_log, output_path_for_log.display(), e);
+                    events::publish(Event::RecordingStopped { camera: cam_name_for_log.clone(), at: Utc::now(), files: Vec::new(), error: Some(format!("{:#}", e)) });
                     had_errors = true;
-                    // Attempt to delete partially created file on specific task error
+                    per_camera_results.push((cam_name_for_log.clone(), Err(e)));
+                    // Attempt to delete partially created file on task error
                     if output_path_for_log.exists() {
                         debug!("Attempting to delete partially created file on error: {}", output_path_for_log.display());
                         if let Err(del_err) = std::fs::remove_file(output_path_for_log) {
@@ -476,31 +1310,26 @@ impl CameraMediaManager {
                         }
                     }
                 }
-                Err(e) => { // Task panicked
-                    error!("❌ Video recording task for camera '{}' (targeting '{}') panicked: {:#}", cam_name_for_log, output_path_for_log.display(), e);
-                    had_errors = true;
-                     if output_path_for_log.exists() {
-                        debug!("Attempting to delete partially created file on panic: {}", output_path_for_log.display());
-                        if let Err(del_err) = std::fs::remove_file(output_path_for_log) {
-                            warn!("Failed to delete partial file {} for camera '{}' after panic: {}", output_path_for_log.display(), cam_name_for_log, del_err);
-                        }
-                    }
-                }
             }
         }
 
-        if successful_paths.is_empty() && !cameras_info.is_empty() && !capture_arcs.is_empty() {
+        successful_paths.extend(fallback_results.iter().filter_map(|(_, result)| result.as_ref().ok()).flatten().cloned());
+        per_camera_results.extend(fallback_results);
+        successful_paths.extend(local_results.iter().filter_map(|(_, result)| result.as_ref().ok()).flatten().cloned());
+        per_camera_results.extend(local_results);
+        let successful_cameras = per_camera_results.iter().filter(|(_, result)| result.is_ok()).count();
+        if successful_paths.is_empty() && !cameras_info.is_empty() && stream_count > 0 {
              warn!(
                 "🎬 Parallel video recording tasks completed, but no files were successfully produced from {} initialized streams. This might indicate issues during recording for all processed cameras.",
-                capture_arcs.len()
+                stream_count
             );
-        } else if successful_paths.is_empty() && capture_arcs.is_empty() {
+        } else if successful_paths.is_empty() && stream_count == 0 {
             info!("🎬 Video recording: No camera streams were available or initialized successfully.");
         } else if had_errors {
              info!(
                 "⚠️ Partially completed video recording for {} out of {} camera streams in {:?}. {} file(s) successfully saved.",
-                successful_paths.len(),
-                capture_arcs.len(),
+                successful_cameras,
+                stream_count,
                 overall_start_time.elapsed(),
                 successful_paths.len()
             );
@@ -508,11 +1337,11 @@ impl CameraMediaManager {
         else {
             info!(
                 "🎉 Successfully completed video recording for {} camera stream(s) in {:?}. {} file(s) saved.",
-                successful_paths.len(),
+                successful_cameras,
                 overall_start_time.elapsed(),
                 successful_paths.len()
             );
         }
-        Ok(successful_paths)
+        Ok(per_camera_results)
     }
 } 
\ No newline at end of file
@@ -0,0 +1,53 @@
+use crate::core::capture_source::{RsColorFrameData, RsDepthFrameData};
+
+/// Points without a color sample (outside the color camera's view, or no color frame).
+const UNCOLORED: [u8; 3] = [160, 160, 160];
+
+/// Back-projects every `stride`-th depth pixel into a point cloud in the depth camera's frame
+/// (metres, x right, y down, z forward). Points are colored by projecting them into the color
+/// frame when both cameras' models are known; lens distortion is ignored.
+/// Returns None if the depth frame has no intrinsics.
+pub fn depth_to_points(
+    depth: &RsDepthFrameData,
+    color: Option<&RsColorFrameData>,
+    stride: u32,
+) -> Option<(Vec<[f32; 3]>, Vec<[u8; 3]>)> {
+    let intr = depth.intrinsics?;
+    let color_model = color.and_then(|c| Some((c, c.intrinsics?, depth.depth_to_color?)));
+    let stride = stride.max(1) as usize;
+    let mut points = Vec::new();
+    let mut colors = Vec::new();
+
+    for v in (0..depth.height as usize).step_by(stride) {
+        for u in (0..depth.width as usize).step_by(stride) {
+            let raw = depth.depth_data[v * depth.width as usize + u];
+            if raw == 0 {
+                continue; // no depth measured
+            }
+            let z = raw as f32 * depth.depth_units;
+            let point = [(u as f32 - intr.ppx) / intr.fx * z, (v as f32 - intr.ppy) / intr.fy * z, z];
+            let rgb = color_model
+                .and_then(|(frame, color_intr, ext)| {
+                    let r = ext.rotation;
+                    let t = ext.translation;
+                    let x = r[0] * point[0] + r[3] * point[1] + r[6] * point[2] + t[0];
+                    let y = r[1] * point[0] + r[4] * point[1] + r[7] * point[2] + t[1];
+                    let z = r[2] * point[0] + r[5] * point[1] + r[8] * point[2] + t[2];
+                    if z <= 0.0 {
+                        return None;
+                    }
+                    let cu = (x / z * color_intr.fx + color_intr.ppx).round();
+                    let cv = (y / z * color_intr.fy + color_intr.ppy).round();
+                    if cu < 0.0 || cv < 0.0 || cu >= frame.width as f32 || cv >= frame.height as f32 {
+                        return None;
+                    }
+                    let idx = (cv as usize * frame.width as usize + cu as usize) * 3;
+                    Some([frame.rgb_data[idx], frame.rgb_data[idx + 1], frame.rgb_data[idx + 2]])
+                })
+                .unwrap_or(UNCOLORED);
+            points.push(point);
+            colors.push(rgb);
+        }
+    }
+    Some((points, colors))
+}
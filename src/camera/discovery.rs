@@ -0,0 +1,140 @@
+use crate::config_loader::IpCameraSpecificConfig;
+use anyhow::{bail, Context, Result};
+use futures::future::join_all;
+use log::{debug, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// The kernel's neighbour table: IPv4 address per MAC, for hosts this machine talked to recently.
+const ARP_TABLE: &str = "/proc/net/arp";
+/// How long each probe waits. Any packet sent makes the kernel resolve the address over ARP;
+/// whether the connection is accepted does not matter.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+const PROBE_PORT: u16 = 80;
+
+/// A camera located by its `mac_address`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacResolution {
+    pub camera: String,
+    pub ip: String,
+    pub configured_ip: String,
+}
+
+impl MacResolution {
+    /// The camera answers at another address than its configured `ip`, e.g. after a new DHCP lease.
+    pub fn moved(&self) -> bool {
+        self.ip != self.configured_ip
+    }
+}
+
+/// Normalizes a MAC address to lower-case, colon-separated form; '-' separators are accepted.
+pub fn normalize_mac(value: &str) -> Result<String> {
+    let octets: Vec<&str> = value.trim().split(|c| c == ':' || c == '-').collect();
+    if octets.len() != 6 || octets.iter().any(|o| o.len() != 2 || !o.chars().all(|c| c.is_ascii_hexdigit())) {
+        bail!("'{}' is not a MAC address (expected six hex octets, e.g. A0:60:32:05:E1:7C)", value);
+    }
+    Ok(octets.join(":").to_lowercase())
+}
+
+// Process-wide like `recording_control`: every IpCameraDevice built for a camera, including
+// the short-lived ones operations create to resolve RTSP URLs, uses the address found last.
+static RESOLVED: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn resolved() -> MutexGuard<'static, BTreeMap<String, String>> {
+    RESOLVED.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The address to contact `camera` at: the one its MAC was last found at, else the configured one.
+pub fn current_ip(camera: &str, configured: &str) -> String {
+    resolved().get(camera).cloned().unwrap_or_else(|| configured.to_string())
+}
+
+/// Finds each camera's current address by its MAC. The ARP table is read first; cameras
+/// missing from it are contacted at their configured address, and if still missing, every
+/// address of the configured /24 is probed so the kernel resolves them. Cameras that moved are
+/// contacted at the new address from then on. Cameras that cannot be found keep their
+/// configured address (with a warning) and are left out of the result.
+pub async fn resolve_cameras(cameras: &[(String, IpCameraSpecificConfig)]) -> Vec<MacResolution> {
+    let wanted: Vec<(&String, String, &String)> = cameras.iter()
+        .filter_map(|(name, specifics)| {
+            let mac = specifics.mac_address.as_deref()?;
+            normalize_mac(mac).ok().map(|mac| (name, mac, &specifics.ip))
+        })
+        .collect();
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+
+    let mut table = match arp_table() {
+        Ok(table) => table,
+        Err(e) => {
+            debug!("MAC discovery unavailable: {:#}", e);
+            return Vec::new();
+        }
+    };
+    let missing = |table: &HashMap<String, Ipv4Addr>| -> Vec<&String> {
+        wanted.iter().filter(|(_, mac, _)| !table.contains_key(mac)).map(|(_, _, ip)| *ip).collect()
+    };
+    let unknown = missing(&table);
+    if !unknown.is_empty() {
+        probe(unknown.iter().filter_map(|ip| ip.parse().ok()).collect()).await;
+        table = arp_table().unwrap_or(table);
+    }
+    let unknown = missing(&table);
+    if !unknown.is_empty() {
+        let mut subnets: Vec<[u8; 3]> = unknown.iter()
+            .filter_map(|ip| ip.parse::<Ipv4Addr>().ok())
+            .map(|ip| { let o = ip.octets(); [o[0], o[1], o[2]] })
+            .collect();
+        subnets.sort();
+        subnets.dedup();
+        info!("🔎 Probing {} subnet(s) for {} camera(s) missing from the ARP table.", subnets.len(), unknown.len());
+        probe(subnets.iter().flat_map(|s| (1..=254).map(move |host| Ipv4Addr::new(s[0], s[1], s[2], host))).collect()).await;
+        table = arp_table().unwrap_or(table);
+    }
+
+    let mut resolutions = Vec::new();
+    for (name, mac, configured_ip) in wanted {
+        match table.get(&mac) {
+            Some(ip) => {
+                let resolution = MacResolution { camera: name.clone(), ip: ip.to_string(), configured_ip: configured_ip.clone() };
+                if resolution.moved() {
+                    warn!("🔀 Camera '{}' ({}) moved from {} to {}; using the new address.", name, mac, configured_ip, ip);
+                } else {
+                    debug!("Camera '{}' ({}) is at its configured address {}.", name, mac, ip);
+                }
+                resolved().insert(name.clone(), resolution.ip.clone());
+                resolutions.push(resolution);
+            }
+            None => warn!("⚠️ Camera '{}' ({}) was not found on the network; trying its configured address {}.", name, mac, configured_ip),
+        }
+    }
+    resolutions
+}
+
+/// Reads the kernel's ARP table (Linux) into MAC -> IPv4 address, skipping incomplete entries.
+pub(crate) fn arp_table() -> Result<HashMap<String, Ipv4Addr>> {
+    let text = std::fs::read_to_string(ARP_TABLE).with_context(|| format!("Failed to read {}", ARP_TABLE))?;
+    let mut table = HashMap::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 || fields[2] == "0x0" || fields[3] == "00:00:00:00:00:00" {
+            continue;
+        }
+        if let (Ok(ip), Ok(mac)) = (fields[0].parse::<Ipv4Addr>(), normalize_mac(fields[3])) {
+            table.insert(mac, ip);
+        }
+    }
+    Ok(table)
+}
+
+/// Sends a TCP connection attempt to every address at once, so the kernel ARP-resolves them
+/// without the raw socket privileges a real ARP scan needs.
+async fn probe(ips: Vec<Ipv4Addr>) {
+    debug!("Probing {} address(es) to fill the ARP table.", ips.len());
+    join_all(ips.into_iter().map(|ip| async move {
+        let _ = tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(SocketAddr::from((ip, PROBE_PORT)))).await;
+    })).await;
+}
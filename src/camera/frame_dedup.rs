@@ -0,0 +1,104 @@
+use crate::common::session::{SessionManifest, MANIFEST_FILE};
+use crate::core::capture_source::FrameDataBundle;
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use log::{debug, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default Hamming distance (of 64 hash bits) at or below which two frames count as unchanged.
+pub const DEFAULT_MAX_DISTANCE: u32 = 4;
+
+const HASHABLE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "tif", "tiff"];
+
+/// 64-bit difference hash: the frame shrunk to 9x8 grey pixels, one bit per horizontally
+/// adjacent pair. Robust to sensor noise and JPEG artefacts, sensitive to anything moving.
+pub fn dhash(path: &Path) -> Result<u64> {
+    let image = image::open(path).with_context(|| format!("Failed to decode {} for duplicate detection", path.display()))?;
+    let small = image.grayscale().resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Ok(hash)
+}
+
+/// First file of a capture that can be hashed (the image rather than a depth .npy or sidecar).
+fn hashable_path(paths: &[PathBuf]) -> Option<&PathBuf> {
+    paths.iter().find(|path| {
+        path.extension()
+            .map(|ext| HASHABLE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+            .unwrap_or(false)
+    })
+}
+
+/// Compares each new capture of one camera with the last one it kept.
+pub struct DedupFilter {
+    max_distance: u32,
+    previous: Option<u64>,
+}
+
+impl DedupFilter {
+    /// `seed` is the camera's most recent earlier capture, so the first frame of a run is compared too.
+    pub fn new(max_distance: u32, seed: Option<&Path>) -> Self {
+        let previous = seed.and_then(|path| match dhash(path) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                debug!("Duplicate detection: ignoring previous capture: {:#}", e);
+                None
+            }
+        });
+        DedupFilter { max_distance, previous }
+    }
+
+    /// Returns the distance to the previous kept frame if `bundle` is a duplicate of it.
+    /// Frames that can't be hashed are always kept.
+    pub fn check(&mut self, bundle: &FrameDataBundle) -> Option<u32> {
+        let paths = bundle.paths();
+        let path = hashable_path(&paths)?;
+        let hash = match dhash(path) {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("⚠️ {:#}; keeping the frame.", e);
+                return None;
+            }
+        };
+        if let Some(previous) = self.previous {
+            let distance = (hash ^ previous).count_ones();
+            if distance <= self.max_distance {
+                return Some(distance);
+            }
+        }
+        self.previous = Some(hash);
+        None
+    }
+}
+
+/// Deletes the files of a capture that was found to be a duplicate.
+pub fn discard(bundle: &FrameDataBundle) {
+    for path in bundle.paths() {
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("⚠️ Could not delete duplicate frame {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The last image `camera` kept in the most recent earlier session under `base_dir`.
+pub fn previous_capture(base_dir: &Path, current_session_id: &str, camera: &str) -> Option<PathBuf> {
+    let mut sessions: Vec<SessionManifest> = fs::read_dir(base_dir).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join(MANIFEST_FILE))
+        .filter_map(|path| serde_json::from_str::<SessionManifest>(&fs::read_to_string(path).ok()?).ok())
+        .filter(|session| session.session_id != current_session_id)
+        .collect();
+    sessions.sort_by_key(|session| session.started_at);
+    sessions.iter().rev().find_map(|session| {
+        let files = &session.cameras.iter().find(|c| c.name == camera)?.files;
+        files.iter().rev().find(|path| path.exists() && hashable_path(std::slice::from_ref(path)).is_some()).cloned()
+    })
+}
@@ -0,0 +1,199 @@
+use crate::camera::post_process;
+use crate::config_loader::DepthPreviewConfig;
+use anyhow::{bail, Context, Result};
+use opencv::{core as opencv_core, imgcodecs, imgproc, prelude::*};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Once;
+
+/// Formats accepted for `depth_format` (RealSense Z16 frames).
+pub const DEPTH_FORMATS: &[&str] = &["png", "tiff", "tif", "exr", "npy", "raw"];
+
+/// Magic bytes opening a `.raw` frame. The 28-byte header is: magic (8), version u16,
+/// dtype u8 (1 = u8, 2 = u16, 3 = f32), channels u8, width u32, height u32,
+/// depth_units f32 (metres per step, 0 for color); all little-endian, then row-major pixels.
+pub const RAW_MAGIC: &[u8; 8] = b"RCAMRAW\0";
+const RAW_VERSION: u16 = 1;
+
+/// Formats that cameras never deliver directly, so a frame must be decoded and re-written.
+pub fn needs_local_encode(format: &str) -> bool {
+    matches!(format.to_lowercase().as_str(), "tiff" | "tif" | "exr" | "npy" | "raw")
+}
+
+/// Decodes JPEG/PNG bytes (e.g. an HTTP snapshot) into a Mat, keeping bit depth.
+pub fn decode(bytes: &[u8]) -> Result<opencv_core::Mat> {
+    let buffer = opencv_core::Vector::<u8>::from_slice(bytes);
+    let decoded = imgcodecs::imdecode(&buffer, imgcodecs::IMREAD_UNCHANGED).context("OpenCV: Failed to decode image")?;
+    if decoded.empty() {
+        bail!("could not decode image bytes");
+    }
+    Ok(decoded)
+}
+
+/// OpenCV only writes EXR when this is set before the first EXR call.
+fn ensure_exr_enabled() {
+    static ENABLE: Once = Once::new();
+    ENABLE.call_once(|| {
+        if std::env::var_os("OPENCV_IO_ENABLE_OPENEXR").is_none() {
+            std::env::set_var("OPENCV_IO_ENABLE_OPENEXR", "1");
+        }
+    });
+}
+
+fn imwrite(path: &Path, mat: &opencv_core::Mat, params: &opencv_core::Vector<i32>) -> Result<()> {
+    let path_str = path.to_str().context("Invalid output path for image (not UTF-8)")?;
+    if !imgcodecs::imwrite(path_str, mat, params)? {
+        bail!("OpenCV: Failed to write {}", path.display());
+    }
+    Ok(())
+}
+
+/// Writes an OpenCV (BGR) frame in `format`. EXR stores 8/16-bit values normalized to 0..1 as
+/// float32; NPY stores the pixel values unchanged as (rows, cols[, 3]) with color in RGB order.
+pub fn write_mat(path: &Path, mat: &opencv_core::Mat, format: &str, jpeg_quality: Option<u8>, png_compression: Option<u32>) -> Result<()> {
+    match format.to_lowercase().as_str() {
+        "npy" => write_npy_mat(path, mat),
+        "exr" => {
+            ensure_exr_enabled();
+            let scale = match mat.depth() {
+                opencv_core::CV_8U => 1.0 / 255.0,
+                opencv_core::CV_16U => 1.0 / 65535.0,
+                _ => 1.0,
+            };
+            let mut float = opencv_core::Mat::default();
+            mat.convert_to(&mut float, opencv_core::CV_32F, scale, 0.0)?;
+            imwrite(path, &float, &opencv_core::Vector::new())
+        }
+        other => imwrite(path, mat, &post_process::encode_params(other, jpeg_quality, png_compression)),
+    }
+}
+
+/// Writes a Z16 depth frame in `format`. PNG/TIFF/NPY/RAW keep the raw sensor units
+/// (multiply by `depth_units` for metres); EXR stores float32 metres.
+pub fn write_depth(path: &Path, data: &[u16], width: u32, height: u32, depth_units: f32, format: &str) -> Result<()> {
+    match format.to_lowercase().as_str() {
+        "png" => {
+            let buffer: image::ImageBuffer<image::Luma<u16>, Vec<u16>> = image::ImageBuffer::from_raw(width, height, data.to_vec())
+                .context("Depth data does not match the frame size")?;
+            buffer.save_with_format(path, image::ImageFormat::Png)
+                .with_context(|| format!("Failed to write {}", path.display()))
+        }
+        "npy" => write_npy(path, "<u2", &[height as usize, width as usize], &u16_le_bytes(data)),
+        "raw" => write_raw(path, 2, 1, width, height, depth_units, &u16_le_bytes(data)),
+        "exr" => {
+            ensure_exr_enabled();
+            let mat = post_process::mat_from_slice(data, height as i32, 1)?;
+            let mut metres = opencv_core::Mat::default();
+            mat.convert_to(&mut metres, opencv_core::CV_32F, depth_units as f64, 0.0)?;
+            imwrite(path, &metres, &opencv_core::Vector::new())
+        }
+        "tiff" | "tif" => imwrite(path, &post_process::mat_from_slice(data, height as i32, 1)?, &opencv_core::Vector::new()),
+        other => bail!("Unsupported depth format '{}' (supported: {})", other, DEPTH_FORMATS.join(", ")),
+    }
+}
+
+/// Colormaps for depth previews, by config name.
+pub const COLORMAPS: &[(&str, i32)] = &[
+    ("turbo", imgproc::COLORMAP_TURBO),
+    ("jet", imgproc::COLORMAP_JET),
+    ("inferno", imgproc::COLORMAP_INFERNO),
+    ("magma", imgproc::COLORMAP_MAGMA),
+    ("plasma", imgproc::COLORMAP_PLASMA),
+    ("viridis", imgproc::COLORMAP_VIRIDIS),
+    ("bone", imgproc::COLORMAP_BONE),
+];
+
+pub fn colormap(name: &str) -> Option<i32> {
+    COLORMAPS.iter().find(|(known, _)| known.eq_ignore_ascii_case(name.trim())).map(|(_, colormap)| *colormap)
+}
+
+/// Writes a colorized 8-bit PNG of a depth frame. `min_m`..`max_m` spans the colormap; an
+/// unset end follows the frame's own nearest/farthest pixel. Pixels without depth are black.
+pub fn write_depth_preview(path: &Path, data: &[u16], height: u32, depth_units: f32, preview: &DepthPreviewConfig) -> Result<()> {
+    let raw = post_process::mat_from_slice(data, height as i32, 1)?;
+    let mut metres = opencv_core::Mat::default();
+    raw.convert_to(&mut metres, opencv_core::CV_32F, depth_units as f64, 0.0)?;
+    let mut valid = opencv_core::Mat::default();
+    opencv_core::compare(&raw, &opencv_core::Scalar::all(0.0), &mut valid, opencv_core::CMP_GT)?;
+
+    let (mut nearest, mut farthest) = (0.0, 0.0);
+    opencv_core::min_max_loc(&metres, Some(&mut nearest), Some(&mut farthest), None, None, &valid)?;
+    let min = preview.min_m.map_or(nearest, f64::from);
+    let max = preview.max_m.map_or(farthest, f64::from);
+    // Values outside the range saturate at the colormap's ends.
+    let scale = if max > min { 255.0 / (max - min) } else { 0.0 };
+    let mut scaled = opencv_core::Mat::default();
+    metres.convert_to(&mut scaled, opencv_core::CV_8U, scale, -min * scale)?;
+
+    let colormap = preview.colormap.as_deref().map_or(Some(imgproc::COLORMAP_TURBO), colormap)
+        .with_context(|| format!("Unknown colormap '{}'", preview.colormap.as_deref().unwrap_or_default()))?;
+    let mut colored = opencv_core::Mat::default();
+    imgproc::apply_color_map(&scaled, &mut colored, colormap)?;
+    let mut invalid = opencv_core::Mat::default();
+    opencv_core::bitwise_not(&valid, &mut invalid, &opencv_core::no_array())?;
+    colored.set_to(&opencv_core::Scalar::all(0.0), &invalid)?;
+    imwrite(path, &colored, &opencv_core::Vector::new())
+}
+
+/// Writes thermal camera temperatures as a 16-bit TIFF in centikelvin.
+pub fn write_temperatures(path: &Path, temperatures: &crate::camera::radiometric::Temperatures) -> Result<()> {
+    let centikelvin = temperatures.centikelvin();
+    imwrite(path, &post_process::mat_from_slice(&centikelvin, temperatures.height as i32, 1)?, &opencv_core::Vector::new())
+}
+
+fn u16_le_bytes(data: &[u16]) -> Vec<u8> {
+    data.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn write_npy_mat(path: &Path, mat: &opencv_core::Mat) -> Result<()> {
+    let mat = if mat.channels() == 3 {
+        let mut rgb = opencv_core::Mat::default();
+        imgproc::cvt_color_def(mat, &mut rgb, imgproc::COLOR_BGR2RGB)?;
+        rgb
+    } else {
+        mat.try_clone()? // a deep copy is always continuous
+    };
+    let descr = match mat.depth() {
+        opencv_core::CV_8U => "|u1",
+        opencv_core::CV_16U => "<u2",
+        opencv_core::CV_32F => "<f4",
+        other => bail!("NPY export does not support OpenCV depth {}", other),
+    };
+    let mut shape = vec![mat.rows() as usize, mat.cols() as usize];
+    if mat.channels() > 1 {
+        shape.push(mat.channels() as usize);
+    }
+    // OpenCV data is native-endian; every supported target is little-endian.
+    write_npy(path, descr, &shape, mat.data_bytes()?)
+}
+
+/// Writes a NumPy v1.0 `.npy` file (C order).
+fn write_npy(path: &Path, descr: &str, shape: &[usize], data: &[u8]) -> Result<()> {
+    let shape_str = match shape {
+        [single] => format!("({},)", single),
+        _ => format!("({})", shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape_str);
+    // Magic (6) + version (2) + header length (2) + header must be a multiple of 64, ending in '\n'.
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut file = fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(b"\x93NUMPY\x01\x00")?;
+    file.write_all(&(header.len() as u16).to_le_bytes())?;
+    file.write_all(header.as_bytes())?;
+    file.write_all(data).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn write_raw(path: &Path, dtype: u8, channels: u8, width: u32, height: u32, depth_units: f32, data: &[u8]) -> Result<()> {
+    let mut file = fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(RAW_MAGIC)?;
+    file.write_all(&RAW_VERSION.to_le_bytes())?;
+    file.write_all(&[dtype, channels])?;
+    file.write_all(&width.to_le_bytes())?;
+    file.write_all(&height.to_le_bytes())?;
+    file.write_all(&depth_units.to_le_bytes())?;
+    file.write_all(data).with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,184 @@
+use crate::common::atomic_file::PartFile;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use log::debug;
+use opencv::{core as opencv_core, prelude::*, videoio};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/// Where a recording's frames go: OpenCV's VideoWriter for constant frame rate files, or an
+/// FFmpeg process that stamps each frame with the wall-clock time it was handed over, for
+/// variable frame rate files. Either can log every frame's capture time to a CSV next to the video.
+/// The video is written to a `.part` file that gets its final name on `release`.
+pub struct FrameSink {
+    backend: Backend,
+    timestamps: Option<TimestampLog>,
+    file: Option<PartFile>, // None once committed
+    path: PathBuf,
+}
+
+enum Backend {
+    OpenCv(videoio::VideoWriter),
+    Ffmpeg { child: Option<Child>, stdin: Option<ChildStdin>, path: PathBuf, frame_bytes: usize },
+}
+
+impl FrameSink {
+    /// Opens an OpenCV VideoWriter for `path` (or a free `-2`, `-3`, ... variant of it); the
+    /// video's timeline is `frame index / fps`.
+    pub fn opencv(path: &Path, fourcc: i32, fps: f64, size: opencv_core::Size, log_timestamps: bool) -> Result<Self> {
+        let file = PartFile::reserve(path)?;
+        let writer = videoio::VideoWriter::new(
+            file.part_path().to_str().context("Invalid output path for video (not UTF-8)")?,
+            fourcc,
+            fps,
+            size,
+            true,
+        )?;
+        if !writer.is_opened()? {
+            bail!("OpenCV could not open a VideoWriter for {}", file.path().display());
+        }
+        let timestamps = if log_timestamps { Some(TimestampLog::create(file.path())?) } else { None };
+        Ok(FrameSink { backend: Backend::OpenCv(writer), timestamps, path: file.path().to_path_buf(), file: Some(file) })
+    }
+
+    /// Starts `ffmpeg` reading raw BGR frames of `size` from a pipe. Each frame keeps the time
+    /// it arrived (`-use_wallclock_as_timestamps`) and is passed through without resampling.
+    pub fn ffmpeg_vfr(ffmpeg_path: &str, path: &Path, size: opencv_core::Size, codec: &str, log_timestamps: bool) -> Result<Self> {
+        let file = PartFile::reserve(path)?;
+        let mut cmd = Command::new(ffmpeg_path);
+        cmd.arg("-hide_banner").arg("-loglevel").arg("error").arg("-y")
+            .arg("-f").arg("rawvideo").arg("-pix_fmt").arg("bgr24")
+            .arg("-s").arg(format!("{}x{}", size.width, size.height))
+            .arg("-use_wallclock_as_timestamps").arg("1")
+            .arg("-i").arg("-")
+            .args(ffmpeg_codec_args(codec))
+            .arg("-fps_mode").arg("passthrough")
+            .arg(file.part_path())
+            .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped());
+        debug!("VFR writer: running {:?}", cmd);
+        let mut child = cmd.spawn()
+            .with_context(|| format!("Failed to run ffmpeg ('{}') for variable frame rate recording; is it installed and on PATH?", ffmpeg_path))?;
+        let stdin = child.stdin.take().context("ffmpeg stdin was not captured")?;
+        let timestamps = if log_timestamps { Some(TimestampLog::create(file.path())?) } else { None };
+        let frame_bytes = (size.width * size.height * 3) as usize;
+        Ok(FrameSink {
+            backend: Backend::Ffmpeg { child: Some(child), stdin: Some(stdin), path: file.path().to_path_buf(), frame_bytes },
+            timestamps,
+            path: file.path().to_path_buf(),
+            file: Some(file),
+        })
+    }
+
+    /// Final name of the video.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Bytes written to disk so far.
+    pub fn written_bytes(&self) -> u64 {
+        let on_disk = self.file.as_ref().map_or(self.path.as_path(), |file| file.part_path());
+        std::fs::metadata(on_disk).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// Writes one frame that was read from the camera at `captured_at`.
+    pub fn write(&mut self, frame: &opencv_core::Mat, captured_at: DateTime<Utc>) -> Result<()> {
+        match &mut self.backend {
+            Backend::OpenCv(writer) => writer.write(frame)?,
+            Backend::Ffmpeg { stdin, path, frame_bytes, .. } => {
+                let data = frame.data_bytes().context("Frame is not a continuous BGR image")?;
+                if data.len() != *frame_bytes {
+                    bail!("Frame has {} bytes but {} expects {} (did the stream resolution change?)", data.len(), path.display(), frame_bytes);
+                }
+                stdin.as_mut().context("ffmpeg input already closed")?
+                    .write_all(data)
+                    .with_context(|| format!("ffmpeg stopped accepting frames for {}", path.display()))?;
+            }
+        }
+        if let Some(log) = &mut self.timestamps {
+            log.record(captured_at)?;
+        }
+        Ok(())
+    }
+
+    /// Finalizes the file (and the timestamp CSV) and moves it to its final name. Safe to call
+    /// more than once.
+    pub fn release(&mut self) -> Result<()> {
+        match &mut self.backend {
+            Backend::OpenCv(writer) => writer.release()?,
+            Backend::Ffmpeg { child, stdin, path, .. } => {
+                drop(stdin.take()); // end of input lets ffmpeg write the index and exit
+                if let Some(child) = child.take() {
+                    let output = child.wait_with_output().context("Failed to wait for ffmpeg")?;
+                    if !output.status.success() {
+                        return Err(anyhow!(
+                            "ffmpeg exited with {} while writing {}: {}",
+                            output.status, path.display(), String::from_utf8_lossy(&output.stderr).trim()
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(log) = &mut self.timestamps {
+            log.flush()?;
+        }
+        if let Some(file) = self.file.take() {
+            file.commit()?;
+        }
+        Ok(())
+    }
+}
+
+/// FFmpeg encoder arguments for the `video_codec` config value.
+fn ffmpeg_codec_args(codec: &str) -> Vec<&'static str> {
+    match codec.to_lowercase().as_str() {
+        "h264" => vec!["-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p"],
+        "mp4v" | "xvid" => vec!["-c:v", "mpeg4", "-q:v", "3"],
+        _ => vec!["-c:v", "mjpeg", "-q:v", "3"],
+    }
+}
+
+/// `<video>.timestamps.csv`: one row per written frame with the time it was read from the camera.
+pub fn timestamps_path(video_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.timestamps.csv", video_path.display()))
+}
+
+/// Capture times from a video's timestamp CSV, in frame order; None if there is no usable log.
+pub fn read_timestamps(video_path: &Path) -> Option<Vec<DateTime<Utc>>> {
+    let csv = std::fs::read_to_string(timestamps_path(video_path)).ok()?;
+    csv.lines()
+        .skip(1)
+        .map(|line| line.split(',').nth(1).and_then(|at| DateTime::parse_from_rfc3339(at).ok()).map(|at| at.with_timezone(&Utc)))
+        .collect()
+}
+
+struct TimestampLog {
+    file: BufWriter<File>,
+    path: PathBuf,
+    first: Option<DateTime<Utc>>,
+    frames: u64,
+}
+
+impl TimestampLog {
+    fn create(video_path: &Path) -> Result<Self> {
+        let path = timestamps_path(video_path);
+        let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut log = TimestampLog { file: BufWriter::new(file), path, first: None, frames: 0 };
+        writeln!(log.file, "frame,capture_time_utc,seconds_from_first_frame")?;
+        Ok(log)
+    }
+
+    fn record(&mut self, captured_at: DateTime<Utc>) -> Result<()> {
+        let first = *self.first.get_or_insert(captured_at);
+        let offset = (captured_at - first).num_microseconds().unwrap_or(0) as f64 / 1e6;
+        writeln!(self.file, "{},{},{:.6}", self.frames, captured_at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true), offset)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush().with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
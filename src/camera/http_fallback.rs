@@ -0,0 +1,189 @@
+use crate::camera::frame_export;
+use crate::camera::frame_sink::FrameSink;
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::common::{opencv_pool, sync_start};
+use crate::errors::{ErrorKind, RcamError};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use opencv::{core as opencv_core, prelude::*};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// How a recording assembled from HTTP snapshots went. Frames are repeated to keep the video
+/// at the configured frame rate, so `snapshots` is what the camera really delivered.
+#[derive(Debug, Clone)]
+pub struct FallbackRecording {
+    pub path: PathBuf,
+    pub duration: Duration,
+    pub snapshots: u64,      // distinct images fetched
+    pub failed_polls: u64,   // requests that failed or returned an unusable image
+    pub frames_written: u64, // including repeats
+}
+
+impl FallbackRecording {
+    /// Distinct images per second, the recording's real frame rate.
+    pub fn effective_fps(&self) -> f64 {
+        let secs = self.duration.as_secs_f64();
+        if secs > 0.0 { self.snapshots as f64 / secs } else { 0.0 }
+    }
+}
+
+/// Writes fetched snapshots to a constant frame rate video, repeating the newest image until
+/// the next one arrives. Runs on the OpenCV pool, one call per poll.
+struct Assembler {
+    path: PathBuf,
+    fourcc: i32,
+    fps: f64,
+    sink: Option<FrameSink>,
+    size: Option<opencv_core::Size>,
+    last: Option<opencv_core::Mat>,
+    written: u64,
+}
+
+impl Assembler {
+    /// Decodes `jpeg` (if any) as the newest image and writes frames up to index `due`. Returns
+    /// false if the image could not be used.
+    fn advance(&mut self, jpeg: Option<Vec<u8>>, due: u64, name: &str) -> Result<bool> {
+        let mut usable = true;
+        if let Some(jpeg) = jpeg {
+            match frame_export::decode(&jpeg) {
+                Ok(frame) => {
+                    let size = frame.size()?;
+                    match self.size {
+                        Some(expected) if expected != size => {
+                            warn!("⚠️ '{}': snapshot is {}x{} instead of {}x{}; skipped.", name, size.width, size.height, expected.width, expected.height);
+                            usable = false;
+                        }
+                        _ => {
+                            self.size = Some(size);
+                            self.last = Some(frame);
+                        }
+                    }
+                }
+                Err(e) => {
+                    debug!("'{}': could not decode snapshot: {:#}", name, e);
+                    usable = false;
+                }
+            }
+        }
+        let (Some(frame), Some(size)) = (&self.last, self.size) else { return Ok(usable) };
+        if self.sink.is_none() {
+            self.sink = Some(FrameSink::opencv(&self.path, self.fourcc, self.fps, size, false)
+                .with_context(|| format!("Failed to open VideoWriter for '{}' at {}", name, self.path.display()))?);
+        }
+        let sink = self.sink.as_mut().expect("opened above");
+        while self.written < due {
+            sink.write(frame, Utc::now())?;
+            self.written += 1;
+        }
+        Ok(usable)
+    }
+}
+
+/// Records `device` for `duration` by polling its HTTP snapshot endpoint as fast as `fps`
+/// allows (one request at a time) and assembling the images into a video at `fps`. Meant for
+/// when the camera's RTSP stream cannot be opened: the result is a low frame rate recording
+/// instead of none.
+pub async fn record(
+    device: Arc<IpCameraDevice>,
+    output_path: &Path,
+    fourcc: i32,
+    fps: f64,
+    duration: Duration,
+    start_at: Option<DateTime<Utc>>,
+    cancel: Option<CancellationToken>,
+) -> Result<FallbackRecording> {
+    let name = device.name.clone();
+    let client = device.build_http_client()?;
+    let auth = device.http_auth()?;
+    let url = device.snapshot_url();
+    let total_frames = (duration.as_secs_f64() * fps).round().max(1.0) as u64;
+    let poll_interval = Duration::from_secs_f64(1.0 / fps);
+    let mut assembler = Some(Assembler { path: output_path.to_path_buf(), fourcc, fps, sink: None, size: None, last: None, written: 0 });
+    let mut snapshots = 0u64;
+    let mut failed_polls = 0u64;
+
+    if let Some(at) = start_at {
+        let late = sync_start::sleep_until(at).await;
+        if !late.is_zero() {
+            warn!("⚠️ '{}': missed the scheduled start by {:?}; recording from snapshots now.", name, late);
+        }
+    }
+    info!("🐢 '{}': recording from HTTP snapshots at {} for {:?} (target {} fps).", name, url, duration, fps);
+    let started = Instant::now();
+    let cancelled = || cancel.as_ref().is_some_and(|c| c.is_cancelled());
+
+    while started.elapsed() < duration && !cancelled() {
+        let poll_start = Instant::now();
+        let jpeg = match fetch(&client, &auth, &url).await {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                failed_polls += 1;
+                if failed_polls == 1 || failed_polls % 10 == 0 {
+                    warn!("⚠️ '{}': snapshot request failed ({} so far): {:#}", name, failed_polls, e);
+                }
+                None
+            }
+        };
+        let fetched = jpeg.is_some();
+        // The frame due now, so the video keeps real time however long the request took.
+        let due = ((started.elapsed().as_secs_f64() * fps).floor() as u64 + 1).min(total_frames);
+        let mut job = assembler.take().expect("returned by the previous poll");
+        let camera = name.clone();
+        let (job, usable) = opencv_pool::spawn(move || {
+            let usable = job.advance(jpeg, due, &camera);
+            (job, usable)
+        }).await?;
+        assembler = Some(job);
+        match usable? {
+            true if fetched => snapshots += 1,
+            true => {}
+            false => failed_polls += 1,
+        }
+
+        let wait = poll_interval.saturating_sub(poll_start.elapsed());
+        match &cancel {
+            Some(cancel) => tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = tokio::time::sleep(wait) => {}
+            },
+            None => tokio::time::sleep(wait).await,
+        }
+    }
+
+    let recorded_for = started.elapsed().min(duration);
+    let mut job = assembler.take().expect("returned by the last poll");
+    let camera = name.clone();
+    // A full-length recording is padded to its planned length; a cancelled one ends here.
+    let due = if cancelled() { job.written } else { total_frames };
+    let (path, frames_written) = opencv_pool::spawn(move || -> Result<Option<(PathBuf, u64)>> {
+        job.advance(None, due, &camera)?;
+        match job.sink.as_mut() {
+            Some(sink) => {
+                sink.release()?;
+                Ok(Some((sink.path().to_path_buf(), job.written)))
+            }
+            None => Ok(None),
+        }
+    }).await??
+        .ok_or_else(|| RcamError::for_camera(ErrorKind::Network, name.as_str(),
+            format!("No usable snapshot from '{}' in {:?} ({} failed request(s))", name, recorded_for, failed_polls)))?;
+
+    let recording = FallbackRecording { path, duration: recorded_for, snapshots, failed_polls, frames_written };
+    info!("🐢 '{}': snapshot recording saved to {} ({} image(s), {:.2} fps effective, {} failed poll(s)).",
+        name, recording.path.display(), recording.snapshots, recording.effective_fps(), recording.failed_polls);
+    Ok(recording)
+}
+
+async fn fetch(client: &reqwest::Client, auth: &crate::camera::auth::CameraAuth, url: &str) -> Result<Vec<u8>> {
+    let response = auth.send(client.get(url)).await?;
+    let status = response.status();
+    if !status.is_success() {
+        let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+        return Err(RcamError::new(kind, format!("snapshot request returned {}", status)).into());
+    }
+    Ok(response.bytes().await?.to_vec())
+}
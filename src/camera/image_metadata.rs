@@ -0,0 +1,342 @@
+use crate::common::atomic_file;
+use crate::config_loader::{GeoLocation, MasterConfig};
+use crate::core::capture_source::FrameDataBundle;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Timelike, Utc};
+use log::{debug, warn};
+use std::fs;
+use std::path::Path;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// What a snapshot records about itself, so photo tools see it without our sidecars.
+#[derive(Debug, Clone)]
+pub struct CaptureMetadata<'a> {
+    pub camera: &'a str,
+    pub captured_at: DateTime<Utc>,
+    pub location: Option<GeoLocation>,
+}
+
+/// The camera's own `location`, else the site's application latitude/longitude.
+pub fn location_for(config: &MasterConfig, camera: &str) -> Option<GeoLocation> {
+    config.cameras.iter()
+        .find(|c| c.get_name() == camera)
+        .and_then(|c| c.location())
+        .or_else(|| config.application.latitude.zip(config.application.longitude)
+            .map(|(latitude, longitude)| GeoLocation { latitude, longitude, altitude_m: None }))
+}
+
+/// Embeds metadata in every JPEG/PNG of a capture; other formats (depth .npy, .raw, TIFF, ...)
+/// are left alone. A file that can't be rewritten keeps its pixels and only loses the metadata.
+pub async fn embed_in_bundle(bundle: &FrameDataBundle, camera: &str, location: Option<GeoLocation>) {
+    let paths = bundle.paths();
+    let camera = camera.to_string();
+    let captured_at = bundle.captured_at;
+    let result = tokio::task::spawn_blocking(move || {
+        let metadata = CaptureMetadata { camera: &camera, captured_at, location };
+        for path in paths {
+            match embed_file(&path, &metadata) {
+                Ok(true) => debug!("🏷️ Embedded capture metadata in {}", path.display()),
+                Ok(false) => {}
+                Err(e) => warn!("⚠️ Could not embed metadata in {}: {:#}", path.display(), e),
+            }
+        }
+    }).await;
+    if let Err(e) = result {
+        warn!("⚠️ Metadata task failed: {}", e);
+    }
+}
+
+/// Rewrites a JPEG or PNG with `metadata` embedded, replacing any EXIF/XMP it already carried.
+/// Returns false for formats that aren't supported.
+pub fn embed_file(path: &Path, metadata: &CaptureMetadata<'_>) -> Result<bool> {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !matches!(extension.as_str(), "jpg" | "jpeg" | "png") {
+        return Ok(false);
+    }
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let tagged = embed(&bytes, metadata)?;
+    atomic_file::replace_bytes(path, &tagged)?;
+    Ok(true)
+}
+
+/// `bytes` (a JPEG or PNG, detected from its signature) with `metadata` embedded.
+pub fn embed(bytes: &[u8], metadata: &CaptureMetadata<'_>) -> Result<Vec<u8>> {
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        embed_jpeg(bytes, metadata)
+    } else if bytes.starts_with(PNG_SIGNATURE) {
+        embed_png(bytes, metadata)
+    } else {
+        bail!("not a JPEG or PNG file")
+    }
+}
+
+/// Inserts EXIF and XMP APP1 segments after the JFIF header, dropping existing ones.
+fn embed_jpeg(bytes: &[u8], metadata: &CaptureMetadata<'_>) -> Result<Vec<u8>> {
+    let mut jfif = Vec::new();
+    let mut others = Vec::new();
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() && bytes[pos] == 0xFF && (0xE0..=0xEF).contains(&bytes[pos + 1]) {
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > bytes.len() {
+            bail!("truncated JPEG segment at byte {}", pos);
+        }
+        let segment = &bytes[pos..end];
+        let payload = &segment[4..];
+        match bytes[pos + 1] {
+            0xE0 => jfif.push(segment),
+            0xE1 if payload.starts_with(EXIF_HEADER) || payload.starts_with(XMP_HEADER) => {}
+            _ => others.push(segment),
+        }
+        pos = end;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() + 2048);
+    out.extend_from_slice(&bytes[..2]);
+    jfif.iter().for_each(|segment| out.extend_from_slice(segment));
+    for payload in [[EXIF_HEADER, exif_tiff(metadata).as_slice()].concat(), [XMP_HEADER, xmp_packet(metadata).as_bytes()].concat()] {
+        let length = u16::try_from(payload.len() + 2).context("metadata does not fit in a JPEG segment")?;
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&length.to_be_bytes());
+        out.extend_from_slice(&payload);
+    }
+    others.iter().for_each(|segment| out.extend_from_slice(segment));
+    out.extend_from_slice(&bytes[pos..]);
+    Ok(out)
+}
+
+/// Inserts eXIf, tEXt and XMP iTXt chunks after IHDR, dropping existing eXIf/XMP chunks.
+fn embed_png(bytes: &[u8], metadata: &CaptureMetadata<'_>) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len() + 2048);
+    out.extend_from_slice(PNG_SIGNATURE);
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 12 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[pos..pos + 4].try_into()?) as usize;
+        let end = pos + 12 + length;
+        if end > bytes.len() {
+            bail!("truncated PNG chunk at byte {}", pos);
+        }
+        let kind = &bytes[pos + 4..pos + 8];
+        let data = &bytes[pos + 8..pos + 8 + length];
+        let is_xmp = kind == b"iTXt" && data.starts_with(PNG_XMP_KEYWORD) && data.get(PNG_XMP_KEYWORD.len()) == Some(&0);
+        if kind != b"eXIf" && !is_xmp {
+            out.extend_from_slice(&bytes[pos..end]);
+        }
+        if kind == b"IHDR" {
+            write_png_chunk(&mut out, b"eXIf", &exif_tiff(metadata));
+            for (keyword, text) in [
+                ("Creation Time", metadata.captured_at.to_rfc3339()),
+                ("Source", metadata.camera.to_string()),
+                ("Software", software()),
+            ] {
+                let latin1: Vec<u8> = text.chars().map(|c| if (c as u32) < 256 { c as u8 } else { b'?' }).collect();
+                write_png_chunk(&mut out, b"tEXt", &[keyword.as_bytes(), &[0u8][..], latin1.as_slice()].concat());
+            }
+            // Keyword, null, uncompressed, compression method, empty language and translated keyword.
+            write_png_chunk(&mut out, b"iTXt", &[PNG_XMP_KEYWORD, &[0u8; 5][..], xmp_packet(metadata).as_bytes()].concat());
+        }
+        pos = end;
+    }
+    Ok(out)
+}
+
+fn write_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(&[kind.as_slice(), data].concat()).to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn software() -> String {
+    format!("rcam {}", env!("CARGO_PKG_VERSION"))
+}
+
+// --- EXIF (a little-endian TIFF structure) ---
+
+enum TagValue {
+    Byte(Vec<u8>),
+    Ascii(String),
+    Long(u32),
+    Rational(Vec<(u32, u32)>),
+    Undefined(Vec<u8>),
+}
+
+impl TagValue {
+    fn type_and_count(&self) -> (u16, u32) {
+        match self {
+            TagValue::Byte(values) => (1, values.len() as u32),
+            TagValue::Ascii(text) => (2, text.len() as u32 + 1),
+            TagValue::Long(_) => (4, 1),
+            TagValue::Rational(values) => (5, values.len() as u32),
+            TagValue::Undefined(values) => (7, values.len() as u32),
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            TagValue::Byte(values) | TagValue::Undefined(values) => values.clone(),
+            TagValue::Ascii(text) => [text.as_bytes(), &[0]].concat(),
+            TagValue::Long(value) => value.to_le_bytes().to_vec(),
+            TagValue::Rational(values) => values.iter()
+                .flat_map(|(num, den)| num.to_le_bytes().into_iter().chain(den.to_le_bytes()))
+                .collect(),
+        }
+    }
+}
+
+/// Entries sorted by tag, as TIFF requires.
+type Ifd = Vec<(u16, TagValue)>;
+
+fn ifd_size(ifd: &Ifd) -> usize {
+    let overflow: usize = ifd.iter().map(|(_, value)| value.bytes().len()).filter(|len| *len > 4).map(|len| len + len % 2).sum();
+    2 + 12 * ifd.len() + 4 + overflow
+}
+
+/// Appends an IFD at `out.len()` (an offset from the TIFF header), values over four bytes after it.
+fn write_ifd(out: &mut Vec<u8>, ifd: &Ifd) {
+    let mut overflow_offset = out.len() + 2 + 12 * ifd.len() + 4;
+    let mut overflow = Vec::new();
+    out.extend_from_slice(&(ifd.len() as u16).to_le_bytes());
+    for (tag, value) in ifd {
+        let (kind, count) = value.type_and_count();
+        let mut bytes = value.bytes();
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            out.extend_from_slice(&bytes);
+        } else {
+            out.extend_from_slice(&(overflow_offset as u32).to_le_bytes());
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            overflow_offset += bytes.len();
+            overflow.extend_from_slice(&bytes);
+        }
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&overflow);
+}
+
+/// Degrees as degrees, minutes and seconds (to 1/10000 s).
+fn dms(degrees: f64) -> Vec<(u32, u32)> {
+    let degrees = degrees.abs();
+    let minutes = degrees.fract() * 60.0;
+    let seconds = minutes.fract() * 60.0;
+    vec![(degrees.trunc() as u32, 1), (minutes.trunc() as u32, 1), ((seconds * 10_000.0).round() as u32, 10_000)]
+}
+
+/// TIFF body of the EXIF block: IFD0 (description, software, time), the Exif IFD (original
+/// time with UTC offset and milliseconds) and, with a location, the GPS IFD. Times are UTC.
+fn exif_tiff(metadata: &CaptureMetadata<'_>) -> Vec<u8> {
+    let at = metadata.captured_at;
+    let timestamp = at.format("%Y:%m:%d %H:%M:%S").to_string();
+    let exif: Ifd = vec![
+        (0x9000, TagValue::Undefined(b"0232".to_vec())),
+        (0x9003, TagValue::Ascii(timestamp.clone())),
+        (0x9010, TagValue::Ascii("+00:00".to_string())),
+        (0x9011, TagValue::Ascii("+00:00".to_string())),
+        (0x9291, TagValue::Ascii(at.format("%3f").to_string())),
+    ];
+    let gps: Option<Ifd> = metadata.location.map(|location| {
+        let mut gps = vec![
+            (0x0000, TagValue::Byte(vec![2, 3, 0, 0])),
+            (0x0001, TagValue::Ascii(if location.latitude >= 0.0 { "N" } else { "S" }.to_string())),
+            (0x0002, TagValue::Rational(dms(location.latitude))),
+            (0x0003, TagValue::Ascii(if location.longitude >= 0.0 { "E" } else { "W" }.to_string())),
+            (0x0004, TagValue::Rational(dms(location.longitude))),
+        ];
+        if let Some(altitude) = location.altitude_m {
+            gps.push((0x0005, TagValue::Byte(vec![(altitude < 0.0) as u8])));
+            gps.push((0x0006, TagValue::Rational(vec![((altitude.abs() * 100.0).round() as u32, 100)])));
+        }
+        gps.push((0x0007, TagValue::Rational(vec![
+            (at.hour(), 1),
+            (at.minute(), 1),
+            (at.second(), 1),
+        ])));
+        gps.push((0x001D, TagValue::Ascii(at.format("%Y:%m:%d").to_string())));
+        gps
+    });
+
+    let mut ifd0: Ifd = vec![
+        (0x010E, TagValue::Ascii(metadata.camera.to_string())),
+        (0x0131, TagValue::Ascii(software())),
+        (0x0132, TagValue::Ascii(timestamp)),
+        (0x8769, TagValue::Long(0)),
+    ];
+    if gps.is_some() {
+        ifd0.push((0x8825, TagValue::Long(0)));
+    }
+    // Pointer values don't change the IFD's size, so the sub-IFD offsets can be computed first.
+    let exif_offset = 8 + ifd_size(&ifd0);
+    let gps_offset = exif_offset + ifd_size(&exif);
+    for (tag, value) in ifd0.iter_mut() {
+        match tag {
+            0x8769 => *value = TagValue::Long(exif_offset as u32),
+            0x8825 => *value = TagValue::Long(gps_offset as u32),
+            _ => {}
+        }
+    }
+
+    let mut out = b"II*\0".to_vec();
+    out.extend_from_slice(&8u32.to_le_bytes());
+    write_ifd(&mut out, &ifd0);
+    write_ifd(&mut out, &exif);
+    if let Some(gps) = &gps {
+        write_ifd(&mut out, gps);
+    }
+    out
+}
+
+// --- XMP ---
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// XMP coordinate: "DDD,MM.mmmmmmK".
+fn xmp_coordinate(degrees: f64, positive: char, negative: char) -> String {
+    let abs = degrees.abs();
+    format!("{},{:.6}{}", abs.trunc(), abs.fract() * 60.0, if degrees >= 0.0 { positive } else { negative })
+}
+
+fn xmp_packet(metadata: &CaptureMetadata<'_>) -> String {
+    let mut attributes = vec![
+        format!("xmp:CreateDate=\"{}\"", metadata.captured_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        format!("xmp:CreatorTool=\"{}\"", xml_escape(&software())),
+        format!("dc:source=\"{}\"", xml_escape(metadata.camera)),
+    ];
+    if let Some(location) = metadata.location {
+        attributes.push(format!("exif:GPSLatitude=\"{}\"", xmp_coordinate(location.latitude, 'N', 'S')));
+        attributes.push(format!("exif:GPSLongitude=\"{}\"", xmp_coordinate(location.longitude, 'E', 'W')));
+        if let Some(altitude) = location.altitude_m {
+            attributes.push(format!("exif:GPSAltitudeRef=\"{}\"", (altitude < 0.0) as u8));
+            attributes.push(format!("exif:GPSAltitude=\"{}/100\"", (altitude.abs() * 100.0).round() as u32));
+        }
+    }
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+         <rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:exif=\"http://ns.adobe.com/exif/1.0/\" {}/>\
+         </rdf:RDF></x:xmpmeta><?xpacket end=\"w\"?>",
+        attributes.join(" ")
+    )
+}
@@ -0,0 +1,119 @@
+use crate::config_loader::QualityChecks;
+use crate::core::capture_source::FrameDataBundle;
+use anyhow::{bail, Context, Result};
+use log::warn;
+use opencv::{core as opencv_core, imgcodecs, imgproc, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const MEASURABLE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "tif", "tiff"];
+
+/// Per-image quality metrics, computed on the grey image.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ImageQuality {
+    pub sharpness: f64,              // variance of the Laplacian; low means out of focus or a blank scene
+    pub brightness: f64,             // mean grey level, 0-255
+    pub clipped_dark_percent: f64,   // pixels at 0
+    pub clipped_bright_percent: f64, // pixels at 255
+}
+
+impl ImageQuality {
+    /// The thresholds in `checks` this image misses, as readable messages.
+    pub fn violations(&self, checks: &QualityChecks) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(min) = checks.min_sharpness {
+            if self.sharpness < min {
+                violations.push(format!("sharpness {:.1} below {:.1} (out of focus?)", self.sharpness, min));
+            }
+        }
+        if let Some(min) = checks.min_brightness {
+            if self.brightness < min {
+                violations.push(format!("brightness {:.1} below {:.1} (underexposed)", self.brightness, min));
+            }
+        }
+        if let Some(max) = checks.max_brightness {
+            if self.brightness > max {
+                violations.push(format!("brightness {:.1} above {:.1} (overexposed)", self.brightness, max));
+            }
+        }
+        if let Some(max) = checks.max_clipped_percent {
+            let clipped = self.clipped_dark_percent + self.clipped_bright_percent;
+            if clipped > max {
+                violations.push(format!("{:.1}% of pixels clipped, more than {:.1}%", clipped, max));
+            }
+        }
+        violations
+    }
+}
+
+/// Measures the image at `path`. 16-bit and color images are converted to 8-bit grey first.
+pub fn measure(path: &Path) -> Result<ImageQuality> {
+    let path_str = path.to_str().with_context(|| format!("Non UTF-8 path {}", path.display()))?;
+    let grey = imgcodecs::imread(path_str, imgcodecs::IMREAD_GRAYSCALE)
+        .with_context(|| format!("OpenCV: Failed to read {}", path.display()))?;
+    if grey.empty() {
+        bail!("could not decode {} for quality metrics", path.display());
+    }
+    measure_grey(&grey)
+}
+
+/// Measures a decoded frame, e.g. one read live from a stream. BGR frames are converted to grey.
+pub fn measure_frame(frame: &opencv_core::Mat) -> Result<ImageQuality> {
+    if frame.channels() == 1 {
+        return measure_grey(frame);
+    }
+    let mut grey = opencv_core::Mat::default();
+    imgproc::cvt_color_def(frame, &mut grey, imgproc::COLOR_BGR2GRAY).context("OpenCV: Failed to convert frame to grey")?;
+    measure_grey(&grey)
+}
+
+fn measure_grey(grey: &opencv_core::Mat) -> Result<ImageQuality> {
+    let mut laplacian = opencv_core::Mat::default();
+    imgproc::laplacian(grey, &mut laplacian, opencv_core::CV_64F, 1, 1.0, 0.0, opencv_core::BORDER_DEFAULT)?;
+    let mut mean = opencv_core::Vector::<f64>::new();
+    let mut stddev = opencv_core::Vector::<f64>::new();
+    opencv_core::mean_std_dev(&laplacian, &mut mean, &mut stddev, &opencv_core::no_array())?;
+    let sharpness = stddev.get(0)?.powi(2);
+
+    let brightness = opencv_core::mean(grey, &opencv_core::no_array())?[0];
+    let total = grey.total() as f64;
+    let dark = total - opencv_core::count_non_zero(grey)? as f64;
+    let mut saturated = opencv_core::Mat::default();
+    opencv_core::compare(grey, &opencv_core::Scalar::all(255.0), &mut saturated, opencv_core::CMP_EQ)?;
+    let bright = opencv_core::count_non_zero(&saturated)? as f64;
+
+    Ok(ImageQuality {
+        sharpness,
+        brightness,
+        clipped_dark_percent: 100.0 * dark / total,
+        clipped_bright_percent: 100.0 * bright / total,
+    })
+}
+
+/// The visible image of a capture (for RealSense the color frame, which is written before
+/// depth), or None if the capture only produced raw or depth data.
+pub fn visible_image(bundle: &FrameDataBundle) -> Option<PathBuf> {
+    bundle.paths().into_iter().find(|path| {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        MEASURABLE_EXTENSIONS.contains(&extension.as_str()) && !stem.ends_with("_depth") && !stem.ends_with("_depth_preview")
+    })
+}
+
+/// Measures the visible image of a capture off the async runtime; failures are logged and
+/// give None, since quality metrics never cost a capture.
+pub async fn measure_bundle(bundle: &FrameDataBundle) -> Option<(PathBuf, ImageQuality)> {
+    let path = visible_image(bundle)?;
+    let measured = tokio::task::spawn_blocking(move || measure(&path).map(|quality| (path, quality))).await;
+    match measured {
+        Ok(Ok(result)) => Some(result),
+        Ok(Err(e)) => {
+            warn!("⚠️ Could not measure image quality: {:#}", e);
+            None
+        }
+        Err(e) => {
+            warn!("⚠️ Image quality task failed: {}", e);
+            None
+        }
+    }
+}
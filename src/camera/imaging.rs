@@ -0,0 +1,236 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Colour temperature at which a camera's white balance gains are taken to be neutral.
+const REFERENCE_KELVIN: f64 = 6500.0;
+
+/// Exposure, gain and white balance as reported by (or sent to) a camera's ONVIF Imaging service.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImagingSettings {
+    pub exposure_mode: Option<String>, // "AUTO" or "MANUAL"
+    pub exposure_time_us: Option<f64>,
+    pub gain_db: Option<f64>,
+    pub white_balance_mode: Option<String>, // "AUTO" or "MANUAL"
+    pub cr_gain: Option<f64>,
+    pub cb_gain: Option<f64>,
+}
+
+/// Valid range of one white balance gain, from the camera's imaging options.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GainRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExposureRequest {
+    Auto,
+    Manual { time_us: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WhiteBalanceRequest {
+    Auto,
+    Kelvin(u32),
+    Gains { cr: f64, cb: f64 },
+}
+
+/// Day/night (IR-cut filter) mode. Day keeps the filter in and images in colour; night
+/// removes it and images turn monochrome; auto lets the camera switch by light level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayNightMode {
+    Day,
+    Night,
+    Auto,
+}
+
+impl DayNightMode {
+    /// `on`/`off` refer to the IR-cut filter, so `on` is day (colour) mode.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "day" | "on" | "color" | "colour" => Ok(DayNightMode::Day),
+            "night" | "off" | "bw" | "mono" => Ok(DayNightMode::Night),
+            "auto" => Ok(DayNightMode::Auto),
+            other => bail!("Invalid day/night mode '{}' (use on/day, off/night or auto)", other),
+        }
+    }
+}
+
+impl fmt::Display for DayNightMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DayNightMode::Day => "day (IR-cut on, colour)",
+            DayNightMode::Night => "night (IR-cut off, monochrome)",
+            DayNightMode::Auto => "auto",
+        })
+    }
+}
+
+/// Requested changes; fields left as None keep the camera's current value.
+/// Strings use the CLI syntax, so the same values work in the per-camera `imaging` config.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ImagingRequestConfig {
+    pub exposure: Option<String>,      // "auto", "1/250", "4ms", "4000us" or seconds ("0.004")
+    pub gain_db: Option<f64>,
+    pub white_balance: Option<String>, // "auto", "5600K" or "<cr>,<cb>" gains
+    pub day_night: Option<String>,     // "on"/"day", "off"/"night" or "auto"
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImagingRequest {
+    pub exposure: Option<ExposureRequest>,
+    pub gain_db: Option<f64>,
+    pub white_balance: Option<WhiteBalanceRequest>,
+    pub day_night: Option<DayNightMode>, // set through the vendor driver, not ONVIF
+}
+
+impl ImagingRequest {
+    pub fn parse(config: &ImagingRequestConfig) -> Result<Self> {
+        Ok(ImagingRequest {
+            exposure: config.exposure.as_deref().map(parse_exposure).transpose()?,
+            gain_db: config.gain_db,
+            white_balance: config.white_balance.as_deref().map(parse_white_balance).transpose()?,
+            day_night: config.day_night.as_deref().map(DayNightMode::parse).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.has_onvif_changes() && self.day_night.is_none()
+    }
+
+    /// True if exposure, gain or white balance (the ONVIF Imaging part) is requested.
+    pub fn has_onvif_changes(&self) -> bool {
+        self.exposure.is_some() || self.gain_db.is_some() || self.white_balance.is_some()
+    }
+
+    /// Applies the request on top of the camera's current settings. Gain implies manual
+    /// exposure, since ONVIF cameras ignore a fixed gain in auto mode.
+    pub fn merge_into(&self, current: &ImagingSettings, wb_ranges: Option<(GainRange, GainRange)>) -> Result<ImagingSettings> {
+        let mut settings = current.clone();
+        match &self.exposure {
+            Some(ExposureRequest::Auto) => settings.exposure_mode = Some("AUTO".to_string()),
+            Some(ExposureRequest::Manual { time_us }) => {
+                settings.exposure_mode = Some("MANUAL".to_string());
+                settings.exposure_time_us = Some(*time_us);
+            }
+            None => {}
+        }
+        if let Some(gain) = self.gain_db {
+            if self.exposure == Some(ExposureRequest::Auto) {
+                bail!("A fixed gain needs manual exposure; drop --exposure auto or --gain");
+            }
+            settings.exposure_mode = Some("MANUAL".to_string());
+            settings.gain_db = Some(gain);
+        }
+        match &self.white_balance {
+            Some(WhiteBalanceRequest::Auto) => settings.white_balance_mode = Some("AUTO".to_string()),
+            Some(WhiteBalanceRequest::Gains { cr, cb }) => {
+                settings.white_balance_mode = Some("MANUAL".to_string());
+                settings.cr_gain = Some(*cr);
+                settings.cb_gain = Some(*cb);
+            }
+            Some(WhiteBalanceRequest::Kelvin(kelvin)) => {
+                let Some((cr_range, cb_range)) = wb_ranges else {
+                    bail!("Camera does not report white balance gain ranges; set gains directly with --wb <cr>,<cb>");
+                };
+                let (cr, cb) = kelvin_to_gains(*kelvin, cr_range, cb_range);
+                settings.white_balance_mode = Some("MANUAL".to_string());
+                settings.cr_gain = Some(cr);
+                settings.cb_gain = Some(cb);
+            }
+            None => {}
+        }
+        if settings.exposure_mode.as_deref() == Some("MANUAL") && settings.exposure_time_us.is_none() {
+            bail!("Manual exposure needs an exposure time; pass --exposure as well");
+        }
+        Ok(settings)
+    }
+}
+
+/// Accepts "auto", a fraction of a second ("1/250"), "4ms", "4000us" or plain seconds ("0.004").
+pub fn parse_exposure(value: &str) -> Result<ExposureRequest> {
+    let value = value.trim().to_lowercase();
+    if value == "auto" {
+        return Ok(ExposureRequest::Auto);
+    }
+    let number = |s: &str| s.trim().parse::<f64>().with_context(|| format!("Invalid exposure '{}'", value));
+    let seconds = if let Some((num, den)) = value.split_once('/') {
+        number(num)? / number(den)?
+    } else if let Some(us) = value.strip_suffix("us") {
+        number(us)? / 1_000_000.0
+    } else if let Some(ms) = value.strip_suffix("ms") {
+        number(ms)? / 1000.0
+    } else {
+        number(value.strip_suffix('s').unwrap_or(&value))?
+    };
+    if !(seconds.is_finite() && seconds > 0.0 && seconds <= 10.0) {
+        bail!("Exposure '{}' is out of range (0 .. 10 s)", value);
+    }
+    Ok(ExposureRequest::Manual { time_us: seconds * 1_000_000.0 })
+}
+
+/// Accepts "auto", a colour temperature ("5600K") or explicit "<cr>,<cb>" gains.
+pub fn parse_white_balance(value: &str) -> Result<WhiteBalanceRequest> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(WhiteBalanceRequest::Auto);
+    }
+    if let Some(kelvin) = value.strip_suffix(['K', 'k']) {
+        let kelvin: u32 = kelvin.trim().parse().with_context(|| format!("Invalid colour temperature '{}'", value))?;
+        if !(1500..=15000).contains(&kelvin) {
+            bail!("Colour temperature '{}' is out of range (1500K .. 15000K)", value);
+        }
+        return Ok(WhiteBalanceRequest::Kelvin(kelvin));
+    }
+    if let Some((cr, cb)) = value.split_once(',') {
+        let parse = |s: &str| s.trim().parse::<f64>().with_context(|| format!("Invalid white balance gains '{}'", value));
+        return Ok(WhiteBalanceRequest::Gains { cr: parse(cr)?, cb: parse(cb)? });
+    }
+    bail!("Invalid white balance '{}' (use auto, e.g. 5600K, or <cr>,<cb> gains)", value)
+}
+
+/// Approximate blackbody colour (Tanner Helland's fit), as linear 0..255 RGB.
+fn kelvin_to_rgb(kelvin: u32) -> (f64, f64, f64) {
+    let t = kelvin as f64 / 100.0;
+    let red = if t <= 66.0 { 255.0 } else { 329.698_727_446 * (t - 60.0).powf(-0.133_204_759_2) };
+    let green = if t <= 66.0 { 99.470_802_586_1 * t.ln() - 161.119_568_166_1 } else { 288.122_169_528_3 * (t - 60.0).powf(-0.075_514_849_2) };
+    let blue = if t >= 66.0 { 255.0 } else if t <= 19.0 { 0.0 } else { 138.517_731_223_1 * (t - 10.0).ln() - 305.044_792_730_7 };
+    let clamp = |v: f64| v.clamp(1.0, 255.0);
+    (clamp(red), clamp(green), clamp(blue))
+}
+
+/// Maps a colour temperature to Cr/Cb gains: the gains that neutralize a light of that
+/// temperature, relative to `REFERENCE_KELVIN`, placed around the middle of each range.
+/// This is an approximation; check the result against a grey card.
+pub fn kelvin_to_gains(kelvin: u32, cr_range: GainRange, cb_range: GainRange) -> (f64, f64) {
+    let (r, g, b) = kelvin_to_rgb(kelvin);
+    let (r_ref, g_ref, b_ref) = kelvin_to_rgb(REFERENCE_KELVIN as u32);
+    let red_ratio = (g / r) / (g_ref / r_ref);
+    let blue_ratio = (g / b) / (g_ref / b_ref);
+    let place = |range: GainRange, ratio: f64| {
+        let mid = (range.min + range.max) / 2.0;
+        (mid * ratio).clamp(range.min, range.max)
+    };
+    (place(cr_range, red_ratio), place(cb_range, blue_ratio))
+}
+
+impl fmt::Display for ImagingSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opt = |v: Option<f64>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "?".to_string());
+        let exposure = match self.exposure_time_us {
+            Some(us) if us > 0.0 => format!("{} us (1/{:.0} s)", opt(Some(us)), 1_000_000.0 / us),
+            _ => "?".to_string(),
+        };
+        write!(
+            f,
+            "exposure {} {}, gain {} dB, white balance {} (Cr {}, Cb {})",
+            self.exposure_mode.as_deref().unwrap_or("?"),
+            exposure,
+            opt(self.gain_db),
+            self.white_balance_mode.as_deref().unwrap_or("?"),
+            opt(self.cr_gain),
+            opt(self.cb_gain),
+        )
+    }
+}
@@ -1,46 +1,119 @@
-use crate::config_loader::IpCameraSpecificConfig;
-use crate::core::capture_source::{CaptureSource, FrameData, FrameDataBundle};
+use crate::config_loader::{IpCameraSpecificConfig, CameraTimeouts, TlsConfig};
+use crate::camera::auth::{self, AuthScheme, CameraAuth};
+use crate::camera::frame_export;
+use crate::camera::onvif::{self, StreamSelector};
+use crate::camera::post_process::{self, OverlayInfo};
+use crate::camera::discovery;
+use crate::camera::rtsp_grab::{self, RtspGrab, RtspTransport};
+use crate::camera::vendors::{self, VendorContext, VendorDriver};
+use crate::common::{atomic_file, opencv_pool, recording_control};
+use crate::core::capture_source::{CaptureSource, CaptureTimings, FrameData, FrameDataBundle};
+use crate::errors::{ErrorKind, RcamError};
 use anyhow::{Result, anyhow, Context};
 use async_trait::async_trait;
-use log::{debug, error, info};
-use reqwest::Client;
+use log::{debug, error, info, warn};
+use reqwest::{Certificate, Client, ClientBuilder, Identity};
 use std::env;
-use std::path::Path;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-use diqwest::WithDigestAuth;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use opencv::{prelude::*, videoio, core as opencv_core};
 
 pub struct IpCameraDevice {
     pub name: String,
     pub config: IpCameraSpecificConfig,
+    pub timeouts: CameraTimeouts,
+    pub rtsp_grab: RtspGrab,
+    pub rtsp_transport: Option<RtspTransport>,
+    pub driver: Box<dyn VendorDriver>,
+    pub selected_stream: Option<String>, // stream recorded, and stills grabbed from on RTSP-only cameras; None = `stream`
     // Maybe an Arc<Client> if we want to share it across multiple captures for the same device.
     // For now, each capture_image call will create a new client or use a shared one passed in.
     // Let's assume client is created per operation for simplicity now.
 }
 
 impl IpCameraDevice {
-    pub fn new(name: String, config: IpCameraSpecificConfig) -> Self {
-        Self {
+    pub fn new(name: String, config: IpCameraSpecificConfig, timeouts: CameraTimeouts) -> Result<Self> {
+        let driver = vendors::driver_for(config.vendor.as_deref())
+            .with_context(|| format!("Failed to select vendor driver for camera '{}'", name))?;
+        debug!("IP Cam [{}]: Using '{}' vendor driver.", name, driver.name());
+        Ok(Self {
             name,
+            timeouts,
+            rtsp_grab: RtspGrab { flush_frames: config.rtsp_flush_frames.unwrap_or(RtspGrab::DEFAULT_FLUSH_FRAMES) },
+            rtsp_transport: RtspTransport::parse(config.rtsp_transport.as_deref()).ok().flatten(),
             config,
+            driver,
+            selected_stream: None,
+        })
+    }
+
+    /// Address to contact the camera at: where its `mac_address` was last found, else `ip`.
+    pub fn ip(&self) -> String {
+        discovery::current_ip(&self.name, &self.config.ip)
+    }
+
+    pub fn http_scheme(&self) -> String {
+        self.config.http_scheme.as_deref().unwrap_or("http").to_lowercase()
+    }
+
+    pub fn base_url(&self) -> String {
+        let scheme = self.http_scheme();
+        let default_port = if scheme == "https" { 443 } else { 80 };
+        format!("{}://{}:{}", scheme, self.ip(), self.config.http_port.unwrap_or(default_port))
+    }
+
+    /// Snapshot URL: the configured `snapshot_path` (with `{channel}` substituted) or the
+    /// vendor driver's default endpoint, for `snapshot_channel` (default 1).
+    pub fn snapshot_url(&self) -> String {
+        let channel = self.config.snapshot_channel.unwrap_or(1);
+        let path = match &self.config.snapshot_path {
+            Some(custom) => custom.replace("{channel}", &channel.to_string()),
+            None => self.driver.snapshot_path(channel),
+        };
+        format!("{}{}", self.base_url(), path)
+    }
+
+    pub fn vendor_context<'a>(&'a self, client: &'a Client, auth: &'a CameraAuth) -> VendorContext<'a> {
+        VendorContext {
+            camera_name: &self.name,
+            client,
+            base_url: self.base_url(),
+            auth,
+        }
+    }
+
+    /// HTTP credentials for the configured `auth` scheme (default digest).
+    pub fn http_auth(&self) -> Result<CameraAuth> {
+        let scheme = AuthScheme::parse(self.config.auth.as_deref())
+            .with_context(|| format!("Invalid auth for camera '{}'", self.name))?;
+        CameraAuth::from_env(&self.name, scheme, self.config.username.as_deref())
+    }
+
+    pub fn build_http_client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.timeouts.connect)
+            .timeout(self.timeouts.http);
+        if let Some(tls) = &self.config.tls {
+            builder = apply_tls(builder, tls, &self.name)?;
         }
+        builder.build()
+            .with_context(|| format!("Failed to build HTTP client for camera '{}'", self.name))
     }
 
     pub fn get_password(&self) -> Result<String> {
-        let env_var_name = format!("{}_PASSWORD", self.name.to_uppercase().replace("-", "_"));
+        let env_var_name = auth::password_env_var(&self.name);
         env::var(&env_var_name)
             .with_context(|| format!("Password for camera '{}' not found in environment variable '{}'", self.name, env_var_name))
     }
 
-    pub fn get_rtsp_url(&self) -> Result<String> {
+    /// RTSP URL for `path` on the camera (e.g. the configured `rtsp_path`).
+    fn rtsp_url_with_path(&self, path: &str) -> Result<String> {
         let username = self.config.username.as_ref()
             .ok_or_else(|| anyhow!("Username not configured for RTSP for camera '{}'", self.name))?;
         let password = self.get_password()
             .with_context(|| format!("Failed to get password for RTSP URL construction for camera '{}'", self.name))?;
-        let ip = &self.config.ip;
+        let ip = self.ip();
         let port = self.config.rtsp_port.unwrap_or(554); // Default RTSP port
-        let path = self.config.rtsp_path.as_deref()
-            .ok_or_else(|| anyhow!("RTSP path not configured for camera '{}'", self.name))?;
         
         // Ensure path starts with a slash if not empty
         let formatted_path = if !path.is_empty() && !path.starts_with('/') {
@@ -51,6 +124,200 @@ impl IpCameraDevice {
 
         Ok(format!("rtsp://{}:{}@{}:{}{}", username, password, ip, port, formatted_path))
     }
+
+    fn configured_rtsp_path(&self) -> Result<&str> {
+        self.config.rtsp_path.as_deref()
+            .ok_or_else(|| anyhow!("RTSP path not configured for camera '{}'", self.name))
+    }
+
+    /// What the stream called `name` is: its `streams` entry, else the built-in "main" or "sub".
+    pub fn stream_selector(&self, name: &str) -> Result<StreamSelector> {
+        if let Some(source) = self.config.streams.as_ref().and_then(|streams| streams.get(name)) {
+            return Ok(StreamSelector::parse(Some(source)));
+        }
+        match name {
+            "main" | "sub" => Ok(StreamSelector::parse(Some(name))),
+            _ => {
+                let defined: Vec<&str> = self.config.streams.iter().flatten().map(|(name, _)| name.as_str()).collect();
+                Err(RcamError::for_camera(ErrorKind::Config, self.name.as_str(), format!("Camera '{}' has no stream named '{}' (streams: main, sub{}{})",
+                    self.name, name, if defined.is_empty() { "" } else { ", " }, defined.join(", "))).into())
+            }
+        }
+    }
+
+    /// Resolves the RTSP URL of the named stream (see `stream_selector`); None is the camera's
+    /// configured `stream`. A configured `rtsp_path` wins for the main stream; otherwise ONVIF
+    /// GetStreamUri is tried, falling back to the Dahua/Amcrest `realmonitor` CGI path.
+    pub async fn get_rtsp_url(&self, stream_name: Option<&str>) -> Result<String> {
+        let selector = match stream_name {
+            Some(name) => self.stream_selector(name)?,
+            None => StreamSelector::parse(self.config.stream.as_deref()),
+        };
+        if let StreamSelector::Path(path) = &selector {
+            return self.rtsp_url_with_path(path);
+        }
+        if !self.driver.has_http_api() {
+            // No ONVIF/CGI to ask, so the configured rtsp_path is the only source.
+            if selector != StreamSelector::Main {
+                warn!("IP Cam [{}]: RTSP-only camera ignores stream selection {:?}; using rtsp_path.", self.name, selector);
+            }
+            return self.rtsp_url_with_path(self.configured_rtsp_path()?);
+        }
+        if selector == StreamSelector::Main && self.config.rtsp_path.is_some() {
+            return self.rtsp_url_with_path(self.configured_rtsp_path()?);
+        }
+
+        let username = self.config.username.as_ref()
+            .ok_or_else(|| anyhow!("Username not configured for RTSP for camera '{}'", self.name))?;
+        let password = self.get_password()
+            .with_context(|| format!("Failed to get password for RTSP URL resolution for camera '{}'", self.name))?;
+
+        match self.resolve_onvif_stream_uri(&selector).await {
+            Ok(uri) => return Ok(onvif::with_credentials(&uri, username, &password)),
+            Err(e) => warn!("IP Cam [{}]: ONVIF stream resolution for {:?} failed: {:#}. Falling back to vendor CGI path.", self.name, selector, e),
+        }
+
+        let subtype = match &selector {
+            StreamSelector::Main => 0,
+            StreamSelector::Sub => 1,
+            StreamSelector::ProfileToken(token) => {
+                return Err(anyhow!("ONVIF profile token '{}' could not be resolved for camera '{}'", token, self.name));
+            }
+            StreamSelector::Path(path) => return self.rtsp_url_with_path(path),
+        };
+        let port = self.config.rtsp_port.unwrap_or(554);
+        Ok(format!(
+            "rtsp://{}:{}@{}:{}/cam/realmonitor?channel=1&subtype={}",
+            username, password, self.ip(), port, subtype
+        ))
+    }
+
+    /// Base URL of the ONVIF services (`onvif_port` if set, else the HTTP port).
+    pub fn onvif_base_url(&self) -> String {
+        match self.config.onvif_port {
+            Some(port) => format!("{}://{}:{}", self.http_scheme(), self.ip(), port),
+            None => self.base_url(),
+        }
+    }
+
+    async fn resolve_onvif_stream_uri(&self, selector: &StreamSelector) -> Result<String> {
+        let client = self.build_http_client()?;
+        let auth = self.http_auth()?;
+        let base_url = self.onvif_base_url();
+        let profiles = onvif::get_profiles(&client, &base_url, &auth).await?;
+        let profile = selector.select(&profiles)
+            .ok_or_else(|| anyhow!("No ONVIF profile matches {:?} (available: {:?})", selector, profiles.iter().map(|p| &p.token).collect::<Vec<_>>()))?;
+        debug!("IP Cam [{}]: Using ONVIF profile '{}' ({}) for {:?}", self.name, profile.token, profile.name, selector);
+        onvif::get_stream_uri(&client, &base_url, &auth, &profile.token).await
+    }
+
+    /// Grabs a single frame from the RTSP stream and writes it as an image file. Used for
+    /// cameras without an HTTP snapshot endpoint.
+    async fn capture_image_via_rtsp(
+        &self,
+        output_dir: &Path,
+        timestamp_str: &str,
+        image_format_config: &str,
+        jpeg_quality: Option<u8>,
+        png_compression: Option<u32>,
+    ) -> Result<FrameDataBundle> {
+        debug!("IP Cam [{}]: Capturing image by grabbing a frame from the RTSP stream.", self.name);
+        let rtsp_url = self.get_rtsp_url(self.selected_stream.as_deref()).await?;
+        let filename = format!("{}_{}.{}", self.name, timestamp_str, image_format_config);
+        let file_path = output_dir.join(&filename);
+
+        let name = self.name.clone();
+        let timeouts = self.timeouts;
+        let grab = self.rtsp_grab;
+        let transport = self.rtsp_transport;
+        let steps = self.config.post_process.clone().unwrap_or_default();
+        let file_path_clone = file_path.clone();
+        let image_format = image_format_config.to_string();
+        let grab_task = opencv_pool::spawn(opencv_pool::pinned(self.config.cpu_affinity.clone(), move || -> Result<(chrono::DateTime<chrono::Utc>, PathBuf, CaptureTimings)> {
+            // A recording of this camera already holds its stream; take its next frame instead
+            // of opening a second stream the camera may refuse.
+            let wait_start = Instant::now();
+            let (mut frame, captured_at, mut timings) = match recording_control::shared_frame(&name, timeouts.read)? {
+                Some((frame, read_at)) => {
+                    info!("🎞️ IP Cam [{}]: Recording in progress; using its current frame.", name);
+                    (frame, read_at, CaptureTimings { first_frame_ms: CaptureTimings::ms(wait_start.elapsed()), ..Default::default() })
+                }
+                None => {
+                    let connect_start = Instant::now();
+                    let mut open_params = opencv_core::Vector::<i32>::new();
+                    open_params.push(videoio::CAP_PROP_OPEN_TIMEOUT_MSEC);
+                    open_params.push(timeouts.connect.as_millis() as i32);
+                    open_params.push(videoio::CAP_PROP_READ_TIMEOUT_MSEC);
+                    open_params.push(timeouts.read.as_millis() as i32);
+                    let mut cap = rtsp_grab::open_stream(&rtsp_url, &open_params, transport)
+                        .with_context(|| format!("OpenCV: Failed to create VideoCapture for '{}'", name))?;
+                    if !cap.is_opened()? {
+                        return Err(anyhow!("Failed to open RTSP stream for '{}'", name));
+                    }
+                    let mut timings = CaptureTimings { connect_ms: CaptureTimings::ms(connect_start.elapsed()), ..Default::default() };
+                    let grab_start = Instant::now();
+                    RtspGrab::configure(&mut cap);
+                    let frame = grab.grab_latest(&mut cap, &name)?;
+                    let captured_at = chrono::Utc::now();
+                    timings.first_frame_ms = CaptureTimings::ms(grab_start.elapsed());
+                    (frame, captured_at, timings)
+                }
+            };
+            let write_start = Instant::now();
+            if !steps.is_empty() {
+                let overlay = OverlayInfo { camera_name: &name, captured_at };
+                frame = post_process::apply(&steps, &frame, Some(&overlay))
+                    .with_context(|| format!("Post-processing failed for camera '{}'", name))?;
+            }
+            let saved_path = atomic_file::write_with(&file_path_clone, |part| {
+                frame_export::write_mat(part, &frame, &image_format, jpeg_quality, png_compression)
+            }).with_context(|| format!("Failed to write frame for '{}'", name))?;
+            timings.write_ms = CaptureTimings::ms(write_start.elapsed());
+            Ok((captured_at, saved_path, timings))
+        }));
+        // Same deadline as opening a stream for recording, plus the flush and one frame read.
+        let flush_allowance = Duration::from_millis(100) * self.rtsp_grab.flush_frames;
+        let deadline = self.timeouts.connect + self.timeouts.read + flush_allowance + Duration::from_secs(1);
+        let (captured_at, file_path, timings) = match tokio::time::timeout(deadline, grab_task).await {
+            Ok(joined) => joined.map_err(|e| anyhow!("RTSP frame grab for '{}': {}", self.name, e))??,
+            Err(_) => return Err(anyhow!("Timed out after {:?} grabbing a frame from RTSP for '{}'", deadline, self.name)),
+        };
+        info!("✅ IP Cam [{}]: Saved RTSP frame to {}", self.name, file_path.display());
+
+        Ok(FrameDataBundle {
+            frames: vec![FrameData::IpCameraImage {
+                name: self.name.clone(),
+                path: file_path,
+                format: image_format_config.to_string(),
+            }],
+            captured_at,
+            timings,
+        })
+    }
+}
+
+/// Applies a camera's `tls` options to an HTTP client for its `https` endpoints.
+pub(crate) fn apply_tls(mut builder: ClientBuilder, tls: &TlsConfig, camera_name: &str) -> Result<ClientBuilder> {
+    if !tls.verify.unwrap_or(true) {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    if let Some(ca_path) = &tls.ca_cert {
+        let pem = std::fs::read(ca_path)
+            .with_context(|| format!("Failed to read tls.ca_cert '{}' for camera '{}'", ca_path, camera_name))?;
+        let cert = Certificate::from_pem(&pem)
+            .with_context(|| format!("Invalid PEM in tls.ca_cert '{}' for camera '{}'", ca_path, camera_name))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    if let (Some(cert_path), Some(key_path)) = (&tls.client_cert, &tls.client_key) {
+        let cert_pem = std::fs::read(cert_path)
+            .with_context(|| format!("Failed to read tls.client_cert '{}' for camera '{}'", cert_path, camera_name))?;
+        let key_pem = std::fs::read(key_path)
+            .with_context(|| format!("Failed to read tls.client_key '{}' for camera '{}'", key_path, camera_name))?;
+        let identity = Identity::from_pkcs8_pem(&cert_pem, &key_pem)
+            .with_context(|| format!("Invalid client certificate/key for camera '{}'", camera_name))?;
+        builder = builder.identity(identity);
+    }
+    Ok(builder)
 }
 
 #[async_trait]
@@ -63,34 +330,45 @@ impl CaptureSource for IpCameraDevice {
         "ip-camera".to_string()
     }
 
+    fn select_stream(&mut self, stream: Option<&str>) {
+        self.selected_stream = stream.map(str::to_string);
+    }
+
+    async fn stream_url(&mut self) -> Result<Option<String>> {
+        self.get_rtsp_url(self.selected_stream.as_deref()).await.map(Some)
+    }
+
     async fn capture_image(
         &mut self, 
         output_dir: &Path, 
         timestamp_str: &str,
         image_format_config: &str, // e.g. "png" or "jpg"
-        _jpeg_quality: Option<u8>, // TODO: Use these if image crate is used for re-saving/conversion
-        _png_compression: Option<u32>,
+        jpeg_quality: Option<u8>, // Only applied when the frame is encoded locally (RTSP-only or post-processed)
+        png_compression: Option<u32>,
     ) -> Result<FrameDataBundle> {
+        if !self.driver.has_http_api() {
+            return self.capture_image_via_rtsp(output_dir, timestamp_str, image_format_config, jpeg_quality, png_compression).await;
+        }
         debug!("IP Cam [{}]: Capturing image via HTTP CGI.", self.name);
-        let client = Client::new(); // Consider sharing client if making many requests
+        let client = self.build_http_client()?; // Consider sharing client if making many requests
         
-        let username = self.config.username.as_ref()
-            .ok_or_else(|| anyhow!("Username not configured for camera '{}'", self.name))?;
-        let password = self.get_password()
-            .with_context(|| format!("Failed to get password for camera '{}'", self.name))?;
+        let auth = self.http_auth()?;
         
-        let url = format!("http://{}/cgi-bin/snapshot.cgi?channel=1", self.config.ip);
-        info!("IP Cam [{}]: Requesting snapshot from {}", self.name, url);
+        let url = self.snapshot_url();
+        info!("IP Cam [{}]: Requesting snapshot from {} ({:?} auth)", self.name, url, auth.scheme);
 
-        let resp_result = client.get(&url)
-            .send_with_digest_auth(username, &password)
-            .await;
+        let request_start = Instant::now();
+        let resp_result = auth.send(client.get(&url)).await;
+        let mut timings = CaptureTimings { connect_ms: CaptureTimings::ms(request_start.elapsed()), ..Default::default() };
+        let body_start = Instant::now();
 
         let image_content_bytes = match resp_result {
             Ok(response) => {
-                if !response.status().is_success() {
-                    error!("IP Cam [{}]: HTTP request failed with status: {}", self.name, response.status());
-                    return Err(anyhow!("HTTP request failed for {} with status: {}", self.name, response.status()));
+                let status = response.status();
+                if !status.is_success() {
+                    error!("IP Cam [{}]: HTTP request failed with status: {}", self.name, status);
+                    let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+                    return Err(RcamError::for_camera(kind, &self.name, format!("HTTP request failed for {} with status: {}", self.name, status)).into());
                 }
                 debug!("IP Cam [{}]: HTTP request successful (Status: {}). Reading bytes...", self.name, response.status());
                 match response.bytes().await {
@@ -102,28 +380,56 @@ impl CaptureSource for IpCameraDevice {
                 }
             },
             Err(e) => {
-                error!("IP Cam [{}]: HTTP request send failed: {}", self.name, e);
+                error!("IP Cam [{}]: HTTP request send failed (timeout: {:?}): {}", self.name, self.timeouts.http, e);
                 return Err(anyhow!("HTTP send failed for {}: {}", self.name, e));
             }
         };
 
         debug!("IP Cam [{}]: Received {} bytes from HTTP.", self.name, image_content_bytes.len());
+        let captured_at = chrono::Utc::now();
+        timings.first_frame_ms = CaptureTimings::ms(body_start.elapsed());
+        let write_start = Instant::now();
 
         let filename = format!("{}_{}.{}", self.name, timestamp_str, image_format_config);
         let file_path = output_dir.join(&filename);
 
-        match File::create(&file_path).await {
-            Ok(mut f) => {
-                if let Err(e) = f.write_all(&image_content_bytes).await {
-                    error!("IP Cam [{}]: Failed to write image to {}: {}", self.name, file_path.display(), e);
-                    return Err(anyhow!("Failed to write image for {}: {}", self.name, e));
+        let steps = self.config.post_process.clone().unwrap_or_default();
+        if !steps.is_empty() || frame_export::needs_local_encode(image_format_config) {
+            // Decode the camera's JPEG so it can be transformed and/or written in a raw format.
+            let name = self.name.clone();
+            let format = image_format_config.to_string();
+            let file_path_clone = file_path.clone();
+            let file_path = opencv_pool::spawn(opencv_pool::pinned(self.config.cpu_affinity.clone(), move || -> Result<PathBuf> {
+                let mut frame = frame_export::decode(&image_content_bytes)?;
+                if !steps.is_empty() {
+                    let overlay = OverlayInfo { camera_name: &name, captured_at };
+                    frame = post_process::apply(&steps, &frame, Some(&overlay))
+                        .with_context(|| format!("Post-processing failed for camera '{}'", name))?;
                 }
-            }
+                atomic_file::write_with(&file_path_clone, |part| frame_export::write_mat(part, &frame, &format, jpeg_quality, png_compression))
+                    .with_context(|| format!("Failed to write image for camera '{}'", name))
+            })).await.map_err(|e| anyhow!("Image encoding for '{}': {}", self.name, e))??;
+            timings.write_ms = CaptureTimings::ms(write_start.elapsed());
+            info!("✅ IP Cam [{}]: Saved {} snapshot to {}", self.name, image_format_config, file_path.display());
+            return Ok(FrameDataBundle {
+                frames: vec![FrameData::IpCameraImage {
+                    name: self.name.clone(),
+                    path: file_path,
+                    format: image_format_config.to_string(),
+                }],
+                captured_at,
+                timings,
+            });
+        }
+
+        let file_path = match atomic_file::write_bytes(&file_path, &image_content_bytes) {
+            Ok(saved_path) => saved_path,
             Err(e) => {
-                error!("IP Cam [{}]: Failed to create file {}: {}", self.name, file_path.display(), e);
-                return Err(anyhow!("Failed to create file for {}: {}", self.name, e));
+                error!("IP Cam [{}]: Failed to write image to {}: {:#}", self.name, file_path.display(), e);
+                return Err(anyhow!("Failed to write image for {}: {:#}", self.name, e));
             }
-        }
+        };
+        timings.write_ms = CaptureTimings::ms(write_start.elapsed());
         info!("✅ IP Cam [{}]: Saved snapshot ({} bytes) to {}", self.name, image_content_bytes.len(), file_path.display());
 
         Ok(FrameDataBundle {
@@ -132,6 +438,8 @@ impl CaptureSource for IpCameraDevice {
                 path: file_path,
                 format: image_format_config.to_string(),
             }],
+            captured_at,
+            timings,
         })
     }
 }
\ No newline at end of file
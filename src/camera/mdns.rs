@@ -0,0 +1,171 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const MDNS_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+const UNICAST_RESPONSE: u16 = 0x8000; // QU bit: answer to the asking socket rather than the group
+
+/// Service types cameras announce over Bonjour: generic RTSP servers, Axis' own type, and
+/// PSIA (Hikvision and relatives).
+pub const CAMERA_SERVICES: &[&str] = &["_rtsp._tcp.local", "_axis-video._tcp.local", "_psia._tcp.local"];
+
+/// One announced service instance, e.g. "AXIS M3045-V - ACCC8E123456._axis-video._tcp.local".
+#[derive(Debug, Clone, Default)]
+pub struct MdnsService {
+    pub instance: String,
+    pub service: String, // e.g. "_rtsp._tcp.local"
+    pub host: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub port: Option<u16>,
+    pub txt: Vec<String>, // "key=value" entries
+}
+
+impl MdnsService {
+    /// The instance name without the service suffix.
+    pub fn label(&self) -> &str {
+        self.instance.strip_suffix(&format!(".{}", self.service)).unwrap_or(&self.instance)
+    }
+}
+
+/// Asks the local network who offers `services` and collects the answers for `timeout`. The
+/// query is sent from an ephemeral port, so responders answer this socket directly (RFC 6762
+/// legacy unicast) and nothing has to share port 5353 with a running mDNS daemon.
+pub async fn browse(services: &[&str], timeout: Duration) -> Result<Vec<MdnsService>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await.context("Failed to bind UDP socket for mDNS")?;
+    let query = build_query(services);
+    // Sent twice, since a lost multicast packet is not retransmitted.
+    for _ in 0..2 {
+        socket.send_to(&query, SocketAddr::from(MDNS_ADDR)).await.context("Failed to send mDNS query")?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let mut records = Records::default();
+    let mut buf = vec![0u8; 9000];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, from) = received.context("Failed to receive mDNS response")?;
+        if parse_response(&buf[..len], from.ip(), &mut records).is_none() {
+            debug!("mDNS: ignored a malformed response from {}", from);
+        }
+    }
+    Ok(records.services(services))
+}
+
+fn build_query(services: &[&str]) -> Vec<u8> {
+    let mut query = vec![0, 0, 0, 0, 0, services.len() as u8, 0, 0, 0, 0, 0, 0];
+    for service in services {
+        for label in service.split('.').filter(|l| !l.is_empty()) {
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.push(0);
+        query.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        query.extend_from_slice(&(CLASS_IN | UNICAST_RESPONSE).to_be_bytes());
+    }
+    query
+}
+
+/// Records gathered from every response; a responder may spread one service over several.
+#[derive(Default)]
+struct Records {
+    ptr: Vec<(String, String)>,                // service -> instance
+    srv: HashMap<String, (String, u16)>,       // instance -> (host, port)
+    txt: HashMap<String, Vec<String>>,         // instance -> entries
+    a: HashMap<String, IpAddr>,                // host -> address
+    sender: HashMap<String, IpAddr>,           // instance -> address the answer came from
+}
+
+impl Records {
+    fn services(self, wanted: &[&str]) -> Vec<MdnsService> {
+        let mut services: Vec<MdnsService> = Vec::new();
+        for (service, instance) in &self.ptr {
+            if !wanted.iter().any(|w| w.eq_ignore_ascii_case(service)) || services.iter().any(|s| &s.instance == instance) {
+                continue;
+            }
+            let srv = self.srv.get(instance);
+            let host = srv.map(|(host, _)| host.clone());
+            services.push(MdnsService {
+                instance: instance.clone(),
+                service: service.clone(),
+                ip: host.as_ref().and_then(|h| self.a.get(h)).or_else(|| self.sender.get(instance)).copied(),
+                host,
+                port: srv.map(|(_, port)| *port),
+                txt: self.txt.get(instance).cloned().unwrap_or_default(),
+            });
+        }
+        services
+    }
+}
+
+fn parse_response(msg: &[u8], from: IpAddr, records: &mut Records) -> Option<()> {
+    let count = |at: usize| -> Option<usize> { Some(u16::from_be_bytes([*msg.get(at)?, *msg.get(at + 1)?]) as usize) };
+    let (questions, answers) = (count(4)?, count(6)? + count(8)? + count(10)?);
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(msg, pos)?.1 + 4;
+    }
+    for _ in 0..answers {
+        let (name, after_name) = read_name(msg, pos)?;
+        let field = |offset: usize| count(after_name + offset);
+        let (rtype, rdlen) = (field(0)? as u16, field(8)?);
+        let rdata = after_name + 10;
+        let data = msg.get(rdata..rdata + rdlen)?;
+        match rtype {
+            TYPE_PTR => {
+                let instance = read_name(msg, rdata)?.0;
+                records.sender.insert(instance.clone(), from);
+                records.ptr.push((name, instance));
+            }
+            TYPE_SRV if data.len() >= 6 => {
+                let port = u16::from_be_bytes([data[4], data[5]]);
+                records.srv.insert(name, (read_name(msg, rdata + 6)?.0, port));
+            }
+            TYPE_TXT => {
+                let mut entries = Vec::new();
+                let mut at = 0;
+                while at < data.len() {
+                    let len = data[at] as usize;
+                    if let Some(entry) = data.get(at + 1..at + 1 + len) {
+                        entries.push(String::from_utf8_lossy(entry).into_owned());
+                    }
+                    at += 1 + len;
+                }
+                records.txt.insert(name, entries);
+            }
+            TYPE_A if data.len() == 4 => {
+                records.a.insert(name, IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3])));
+            }
+            _ => {}
+        }
+        pos = rdata + rdlen;
+    }
+    Some(())
+}
+
+/// Reads a (possibly compressed) domain name at `pos`; returns it and the position after it.
+fn read_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    for _ in 0..128 {
+        let len = *msg.get(pos)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(pos + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            end.get_or_insert(pos + 2);
+            pos = ((len & 0x3F) << 8) | *msg.get(pos + 1)? as usize;
+            continue;
+        }
+        labels.push(String::from_utf8_lossy(msg.get(pos + 1..pos + 1 + len)?).into_owned());
+        pos += 1 + len;
+    }
+    None // a pointer loop
+}
@@ -0,0 +1,40 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::process::Command;
+
+/// Muxes per-camera recordings into one Matroska file with one video track per camera, using the
+/// FFmpeg CLI. Streams are copied (no re-encode); since all recordings started at the same barrier,
+/// each track starts at t=0 on the shared timeline. Track titles are the camera names.
+pub async fn mux_session_mkv(ffmpeg_path: &str, tracks: &[(String, PathBuf)], output_path: &Path) -> Result<PathBuf> {
+    if tracks.is_empty() {
+        return Err(anyhow!("No recordings to mux into {}", output_path.display()));
+    }
+    let mux_start = Instant::now();
+    let mut cmd = Command::new(ffmpeg_path);
+    cmd.arg("-hide_banner").arg("-loglevel").arg("error").arg("-y");
+    for (_, path) in tracks {
+        cmd.arg("-i").arg(path);
+    }
+    for idx in 0..tracks.len() {
+        cmd.arg("-map").arg(format!("{}:v:0", idx));
+    }
+    cmd.arg("-c").arg("copy");
+    for (idx, (name, _)) in tracks.iter().enumerate() {
+        cmd.arg(format!("-metadata:s:v:{}", idx)).arg(format!("title={}", name));
+    }
+    cmd.arg(output_path);
+    debug!("MKV mux: running {:?}", cmd);
+
+    let output = cmd.output().await
+        .with_context(|| format!("Failed to run ffmpeg ('{}'); is it installed and on PATH?", ffmpeg_path))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg exited with {} while muxing {}: {}",
+            output.status, output_path.display(), String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    info!("🎞️ Muxed {} camera track(s) into {} in {:?}", tracks.len(), output_path.display(), mux_start.elapsed());
+    Ok(output_path.to_path_buf())
+}
@@ -1,4 +1,29 @@
+pub mod auth;
 pub mod camera_controller;
 pub mod camera_media;
+pub mod depth_points;
+pub mod discovery;
+pub mod mdns;
+pub mod frame_dedup;
+pub mod frame_export;
+pub mod frame_sink;
+pub mod http_fallback;
+pub mod image_metadata;
+pub mod image_quality;
+pub mod imaging;
 pub mod ip_camera_device;
-pub mod realsense_device;
\ No newline at end of file
+pub mod mkv_mux;
+pub mod net_probe;
+pub mod onvif;
+pub mod post_process;
+pub mod radiometric;
+pub mod realsense_device;
+pub mod realsense_bandwidth;
+pub mod realsense_hotplug;
+pub mod realsense_options;
+pub mod rtsp_grab;
+pub mod ssdp;
+pub mod stream_stats;
+pub mod thermal_device;
+pub mod vendors;
+pub mod virtual_camera;
@@ -0,0 +1,180 @@
+use log::debug;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+/// Round trips measured per ping and per port.
+pub const PROBE_COUNT: u32 = 4;
+
+/// Round-trip times of one kind of probe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttStats {
+    pub sent: u32,
+    pub received: u32,
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub mdev_ms: f64, // standard deviation, as ping reports it
+}
+
+impl RttStats {
+    fn from_samples(sent: u32, samples: &[f64]) -> Self {
+        let received = samples.len() as u32;
+        if samples.is_empty() {
+            return RttStats { sent, received, min_ms: 0.0, avg_ms: 0.0, max_ms: 0.0, mdev_ms: 0.0 };
+        }
+        let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|s| (s - avg).powi(2)).sum::<f64>() / samples.len() as f64;
+        RttStats {
+            sent,
+            received,
+            min_ms: samples.iter().cloned().fold(f64::INFINITY, f64::min),
+            avg_ms: avg,
+            max_ms: samples.iter().cloned().fold(0.0, f64::max),
+            mdev_ms: variance.sqrt(),
+        }
+    }
+
+    pub fn loss_percent(&self) -> f64 {
+        if self.sent == 0 { 0.0 } else { 100.0 * (self.sent - self.received) as f64 / self.sent as f64 }
+    }
+}
+
+impl fmt::Display for RttStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.received == 0 {
+            return write!(f, "0/{} answered", self.sent);
+        }
+        write!(f, "{}/{} answered, rtt {:.1}/{:.1}/{:.1} ms (min/avg/max), mdev {:.1} ms",
+            self.received, self.sent, self.min_ms, self.avg_ms, self.max_ms, self.mdev_ms)
+    }
+}
+
+/// TCP connects to one service port.
+#[derive(Debug, Clone)]
+pub struct PortProbe {
+    pub service: String, // "rtsp", "http", "https", "onvif"
+    pub port: u16,
+    pub required: bool,  // the camera cannot be used without it
+    pub rtt: RttStats,
+    pub error: Option<String>, // why the last attempt failed
+}
+
+impl PortProbe {
+    pub fn open(&self) -> bool {
+        self.rtt.received > 0
+    }
+}
+
+/// Connectivity to one camera, from name resolution up to its service ports.
+#[derive(Debug, Clone)]
+pub struct NetworkProbe {
+    pub host: String,
+    pub address: Option<IpAddr>,
+    pub dns_ms: Option<f64>, // None for a literal address
+    pub dns_error: Option<String>,
+    pub ping: Result<RttStats, String>, // Err when ping could not be run
+    pub ports: Vec<PortProbe>,
+}
+
+impl NetworkProbe {
+    /// The host resolved and every required port accepted a connection. ICMP is often
+    /// filtered, so an unanswered ping alone does not fail the probe.
+    pub fn reachable(&self) -> bool {
+        self.address.is_some() && self.ports.iter().filter(|p| p.required).all(PortProbe::open)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        match (&self.address, self.dns_ms, &self.dns_error) {
+            (_, _, Some(error)) => parts.push(format!("dns failed: {}", error)),
+            (Some(address), Some(ms), None) => parts.push(format!("dns {:.1} ms -> {}", ms, address)),
+            _ => {}
+        }
+        match &self.ping {
+            Ok(rtt) => parts.push(format!("ping {}", rtt)),
+            Err(error) => parts.push(format!("ping unavailable ({})", error)),
+        }
+        for port in &self.ports {
+            parts.push(match &port.error {
+                Some(error) if !port.open() => format!("{}:{} closed ({})", port.service, port.port, error),
+                _ => format!("{}:{} open, connect {:.1} ms avg", port.service, port.port, port.rtt.avg_ms),
+            });
+        }
+        parts.join(" | ")
+    }
+}
+
+/// Resolves `host`, pings it and connects to each of `ports` (service, port, required)
+/// `PROBE_COUNT` times. A port that refuses or times out on the first attempt is not retried.
+pub async fn probe(host: &str, ports: &[(&str, u16, bool)], timeout: Duration) -> NetworkProbe {
+    let mut probe = NetworkProbe { host: host.to_string(), address: None, dns_ms: None, dns_error: None, ping: Err("not run".to_string()), ports: Vec::new() };
+    match host.parse::<IpAddr>() {
+        Ok(address) => probe.address = Some(address),
+        Err(_) => {
+            let started = Instant::now();
+            match tokio::time::timeout(timeout, tokio::net::lookup_host((host, 0))).await {
+                Ok(Ok(mut addresses)) => {
+                    probe.dns_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+                    probe.address = addresses.next().map(|a| a.ip());
+                    if probe.address.is_none() {
+                        probe.dns_error = Some("no addresses".to_string());
+                    }
+                }
+                Ok(Err(e)) => probe.dns_error = Some(e.to_string()),
+                Err(_) => probe.dns_error = Some(format!("timed out after {:?}", timeout)),
+            }
+        }
+    }
+    let Some(address) = probe.address else {
+        return probe;
+    };
+
+    probe.ping = ping(address, timeout).await;
+    for (service, port, required) in ports {
+        let mut samples = Vec::new();
+        let mut error = None;
+        for _ in 0..PROBE_COUNT {
+            let started = Instant::now();
+            match tokio::time::timeout(timeout, TcpStream::connect(SocketAddr::new(address, *port))).await {
+                Ok(Ok(_)) => samples.push(started.elapsed().as_secs_f64() * 1000.0),
+                Ok(Err(e)) => error = Some(e.to_string()),
+                Err(_) => error = Some(format!("no answer within {:?}", timeout)),
+            }
+            if samples.is_empty() {
+                break;
+            }
+        }
+        let sent = if samples.is_empty() { 1 } else { PROBE_COUNT };
+        probe.ports.push(PortProbe { service: service.to_string(), port: *port, required: *required, rtt: RttStats::from_samples(sent, &samples), error });
+    }
+    probe
+}
+
+/// Runs the system `ping` (which holds the privilege raw ICMP sockets need) and reads the
+/// per-reply times from its output.
+async fn ping(address: IpAddr, timeout: Duration) -> Result<RttStats, String> {
+    let wait_secs = timeout.as_secs().max(1).to_string();
+    let count = PROBE_COUNT.to_string();
+    let run = Command::new("ping").args(["-n", "-c", &count, "-i", "0.2", "-W", &wait_secs, &address.to_string()]).output();
+    let limit = timeout * (PROBE_COUNT + 1);
+    let output = match tokio::time::timeout(limit, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("failed to run ping: {}", e)),
+        Err(_) => return Err(format!("ping did not finish within {:?}", limit)),
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    let samples: Vec<f64> = text.lines()
+        .filter_map(|line| line.split("time=").nth(1))
+        .filter_map(|time| time.split_whitespace().next()?.parse().ok())
+        .collect();
+    // Exit status 1 means no replies; anything else is ping itself failing, e.g. no permission.
+    if samples.is_empty() && output.status.code() != Some(1) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!("ping {} exited with {}: {}", address, output.status, stderr.trim());
+        return Err(stderr.lines().next().unwrap_or("ping failed").trim().to_string());
+    }
+    Ok(RttStats::from_samples(PROBE_COUNT, &samples))
+}
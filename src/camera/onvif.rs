@@ -0,0 +1,281 @@
+use crate::camera::auth::CameraAuth;
+use crate::camera::imaging::{GainRange, ImagingSettings};
+use crate::errors::{ErrorKind, ErrorKindExt, RcamError};
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info};
+use reqwest::Client;
+use std::fmt;
+use std::time::Instant;
+
+const MEDIA_SERVICE_PATH: &str = "/onvif/media_service";
+const DEVICE_SERVICE_PATH: &str = "/onvif/device_service";
+// Used when the device does not advertise its imaging service address.
+const DEFAULT_IMAGING_SERVICE_PATH: &str = "/onvif/imaging_service";
+
+#[derive(Debug, Clone)]
+pub struct OnvifProfile {
+    pub token: String,
+    pub name: String,
+}
+
+/// Identity reported by the ONVIF device service (GetDeviceInformation).
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub firmware_version: Option<String>,
+    pub serial_number: Option<String>,
+    pub hardware_id: Option<String>,
+}
+
+impl fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opt = |v: &Option<String>| v.clone().unwrap_or_else(|| "?".to_string());
+        write!(
+            f,
+            "{} {}, firmware {}, serial {}, hardware {}",
+            opt(&self.manufacturer),
+            opt(&self.model),
+            opt(&self.firmware_version),
+            opt(&self.serial_number),
+            opt(&self.hardware_id),
+        )
+    }
+}
+
+/// Stream selector from the per-camera `stream` config key or a `streams` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamSelector {
+    Main,
+    Sub,
+    ProfileToken(String),
+    Path(String), // an RTSP path on the camera, e.g. "/Streaming/Channels/103"
+}
+
+impl StreamSelector {
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim()) {
+            None | Some("") | Some("main") => StreamSelector::Main,
+            Some("sub") => StreamSelector::Sub,
+            Some(path) if path.starts_with('/') => StreamSelector::Path(path.to_string()),
+            Some(token) => StreamSelector::ProfileToken(token.to_string()),
+        }
+    }
+
+    /// Picks a profile: main is the first profile, sub the second, otherwise an exact token match.
+    pub fn select<'a>(&self, profiles: &'a [OnvifProfile]) -> Option<&'a OnvifProfile> {
+        match self {
+            StreamSelector::Main => profiles.first(),
+            StreamSelector::Sub => profiles.get(1),
+            StreamSelector::ProfileToken(token) => profiles.iter().find(|p| &p.token == token),
+            StreamSelector::Path(_) => None,
+        }
+    }
+}
+
+fn soap_envelope(body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:trt="http://www.onvif.org/ver10/media/wsdl" xmlns:tds="http://www.onvif.org/ver10/device/wsdl" xmlns:timg="http://www.onvif.org/ver20/imaging/wsdl" xmlns:tt="http://www.onvif.org/ver10/schema"><s:Body>{}</s:Body></s:Envelope>"#,
+        body
+    )
+}
+
+async fn soap_call(client: &Client, service_url: &str, auth: &CameraAuth, body: &str) -> Result<String> {
+    let start = Instant::now();
+    let request = client
+        .post(service_url)
+        .header("Content-Type", "application/soap+xml; charset=utf-8")
+        .body(soap_envelope(body));
+    let response = auth.send(request)
+        .await
+        .with_kind(ErrorKind::Network, || format!("ONVIF request to {} failed", service_url))?;
+    let status = response.status();
+    let text = response.text().await.context("Failed to read ONVIF response body")?;
+    debug!("ONVIF: {} responded {} in {:?}", service_url, status, start.elapsed());
+    if !status.is_success() {
+        let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+        return Err(RcamError::new(kind, format!("ONVIF request to {} failed with status {}", service_url, status)).into());
+    }
+    Ok(text)
+}
+
+/// Returns the inner text of every `<prefix:tag ...>` element, ignoring the namespace prefix.
+fn extract_elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let mut results = Vec::new();
+    let mut rest = xml;
+    while let Some(open_idx) = rest.find('<') {
+        rest = &rest[open_idx + 1..];
+        let Some(tag_end) = rest.find('>') else { break };
+        let open_tag = &rest[..tag_end];
+        let tag_name = open_tag.split_whitespace().next().unwrap_or("");
+        let local_name = tag_name.rsplit(':').next().unwrap_or(tag_name);
+        if local_name != tag || open_tag.starts_with('/') {
+            continue;
+        }
+        let after_open = &rest[tag_end + 1..];
+        let close_marker = format!("</{}>", tag_name);
+        let inner = match after_open.find(&close_marker) {
+            Some(close_idx) => &after_open[..close_idx],
+            None => "",
+        };
+        results.push((open_tag, inner));
+        rest = after_open;
+    }
+    results
+}
+
+fn attribute<'a>(open_tag: &'a str, name: &str) -> Option<&'a str> {
+    let marker = format!("{}=\"", name);
+    let start = open_tag.find(&marker)? + marker.len();
+    let end = open_tag[start..].find('"')? + start;
+    Some(&open_tag[start..end])
+}
+
+pub async fn get_profiles(client: &Client, base_url: &str, auth: &CameraAuth) -> Result<Vec<OnvifProfile>> {
+    let service_url = format!("{}{}", base_url, MEDIA_SERVICE_PATH);
+    let xml = soap_call(client, &service_url, auth, "<trt:GetProfiles/>").await?;
+    let profiles: Vec<OnvifProfile> = extract_elements(&xml, "Profiles")
+        .into_iter()
+        .filter_map(|(open_tag, inner)| {
+            let token = attribute(open_tag, "token")?.to_string();
+            let name = extract_elements(inner, "Name").first().map(|(_, n)| n.to_string()).unwrap_or_default();
+            Some(OnvifProfile { token, name })
+        })
+        .collect();
+    debug!("ONVIF: Found {} profile(s) at {}: {:?}", profiles.len(), service_url, profiles);
+    Ok(profiles)
+}
+
+pub async fn get_stream_uri(client: &Client, base_url: &str, auth: &CameraAuth, profile_token: &str) -> Result<String> {
+    let service_url = format!("{}{}", base_url, MEDIA_SERVICE_PATH);
+    let body = format!(
+        "<trt:GetStreamUri><trt:StreamSetup><tt:Stream>RTP-Unicast</tt:Stream><tt:Transport><tt:Protocol>RTSP</tt:Protocol></tt:Transport></trt:StreamSetup><trt:ProfileToken>{}</trt:ProfileToken></trt:GetStreamUri>",
+        profile_token
+    );
+    let xml = soap_call(client, &service_url, auth, &body).await?;
+    let uri = extract_elements(&xml, "Uri")
+        .first()
+        .map(|(_, uri)| uri.trim().replace("&amp;", "&"))
+        .ok_or_else(|| anyhow!("ONVIF GetStreamUri response for profile '{}' contained no Uri", profile_token))?;
+    info!("ONVIF: Resolved stream URI for profile '{}': {}", profile_token, uri);
+    Ok(uri)
+}
+
+/// Inserts `username:password@` into an RTSP URI returned by the device.
+pub fn with_credentials(uri: &str, username: &str, password: &str) -> String {
+    match uri.split_once("://") {
+        Some((scheme, rest)) if !rest.contains('@') => format!("{}://{}:{}@{}", scheme, username, password, rest),
+        _ => uri.to_string(),
+    }
+}
+
+/// Trimmed inner text of the first `tag` element; also used for UPnP device descriptions.
+pub(crate) fn first_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    extract_elements(xml, tag).first().map(|(_, inner)| inner.trim())
+}
+
+fn first_number(xml: &str, tag: &str) -> Option<f64> {
+    first_text(xml, tag).and_then(|v| v.parse().ok())
+}
+
+pub async fn get_device_information(client: &Client, base_url: &str, auth: &CameraAuth) -> Result<DeviceInfo> {
+    let service_url = format!("{}{}", base_url, DEVICE_SERVICE_PATH);
+    let xml = soap_call(client, &service_url, auth, "<tds:GetDeviceInformation/>").await?;
+    let text = |tag: &str| first_text(&xml, tag).filter(|v| !v.is_empty()).map(str::to_string);
+    let info = DeviceInfo {
+        manufacturer: text("Manufacturer"),
+        model: text("Model"),
+        firmware_version: text("FirmwareVersion"),
+        serial_number: text("SerialNumber"),
+        hardware_id: text("HardwareId"),
+    };
+    if info.manufacturer.is_none() && info.model.is_none() {
+        bail!("ONVIF GetDeviceInformation response from {} contained no device information", service_url);
+    }
+    Ok(info)
+}
+
+/// Address of the imaging service, as advertised by the device (host replaced by `base_url`,
+/// since devices often report an address that isn't reachable from here).
+pub async fn imaging_service_url(client: &Client, base_url: &str, auth: &CameraAuth) -> String {
+    let device_url = format!("{}{}", base_url, DEVICE_SERVICE_PATH);
+    let body = "<tds:GetCapabilities><tds:Category>Imaging</tds:Category></tds:GetCapabilities>";
+    let path = match soap_call(client, &device_url, auth, body).await {
+        Ok(xml) => first_text(&xml, "XAddr")
+            .and_then(|xaddr| xaddr.split_once("://").map(|(_, rest)| rest.to_string()))
+            .and_then(|rest| rest.find('/').map(|idx| rest[idx..].to_string())),
+        Err(e) => {
+            debug!("ONVIF: GetCapabilities failed at {}: {}. Assuming {}.", device_url, e, DEFAULT_IMAGING_SERVICE_PATH);
+            None
+        }
+    };
+    format!("{}{}", base_url, path.as_deref().unwrap_or(DEFAULT_IMAGING_SERVICE_PATH))
+}
+
+/// Token of the first video source; imaging settings are per source, not per profile.
+pub async fn get_video_source_token(client: &Client, base_url: &str, auth: &CameraAuth) -> Result<String> {
+    let service_url = format!("{}{}", base_url, MEDIA_SERVICE_PATH);
+    let xml = soap_call(client, &service_url, auth, "<trt:GetVideoSources/>").await?;
+    extract_elements(&xml, "VideoSources")
+        .into_iter()
+        .find_map(|(open_tag, _)| attribute(open_tag, "token").map(str::to_string))
+        .ok_or_else(|| anyhow!("ONVIF GetVideoSources response from {} contained no video source", service_url))
+}
+
+pub async fn get_imaging_settings(client: &Client, service_url: &str, auth: &CameraAuth, source_token: &str) -> Result<ImagingSettings> {
+    let body = format!("<timg:GetImagingSettings><timg:VideoSourceToken>{}</timg:VideoSourceToken></timg:GetImagingSettings>", source_token);
+    let xml = soap_call(client, service_url, auth, &body).await?;
+    let exposure = first_text(&xml, "Exposure").unwrap_or("");
+    let white_balance = first_text(&xml, "WhiteBalance").unwrap_or("");
+    Ok(ImagingSettings {
+        exposure_mode: first_text(exposure, "Mode").map(str::to_uppercase),
+        exposure_time_us: first_number(exposure, "ExposureTime"),
+        gain_db: first_number(exposure, "Gain"),
+        white_balance_mode: first_text(white_balance, "Mode").map(str::to_uppercase),
+        cr_gain: first_number(white_balance, "CrGain"),
+        cb_gain: first_number(white_balance, "CbGain"),
+    })
+}
+
+/// Cr and Cb gain ranges from the imaging options, if the device reports them.
+pub async fn get_white_balance_ranges(client: &Client, service_url: &str, auth: &CameraAuth, source_token: &str) -> Result<Option<(GainRange, GainRange)>> {
+    let body = format!("<timg:GetOptions><timg:VideoSourceToken>{}</timg:VideoSourceToken></timg:GetOptions>", source_token);
+    let xml = soap_call(client, service_url, auth, &body).await?;
+    let white_balance = first_text(&xml, "WhiteBalance").unwrap_or("");
+    // ImagingOptions20 names the ranges YrGain/YbGain.
+    let range = |tag: &str| {
+        let inner = first_text(white_balance, tag)?;
+        Some(GainRange { min: first_number(inner, "Min")?, max: first_number(inner, "Max")? })
+    };
+    Ok(range("YrGain").zip(range("YbGain")))
+}
+
+/// Writes exposure and white balance. Elements follow the ImagingSettings20 schema order.
+pub async fn set_imaging_settings(client: &Client, service_url: &str, auth: &CameraAuth, source_token: &str, settings: &ImagingSettings) -> Result<()> {
+    let element = |tag: &str, value: Option<String>| value.map(|v| format!("<tt:{0}>{1}</tt:{0}>", tag, v)).unwrap_or_default();
+    let exposure = match &settings.exposure_mode {
+        Some(mode) => format!(
+            "<tt:Exposure><tt:Mode>{}</tt:Mode>{}{}</tt:Exposure>",
+            mode,
+            element("ExposureTime", settings.exposure_time_us.map(|v| format!("{:.0}", v))),
+            element("Gain", settings.gain_db.map(|v| v.to_string())),
+        ),
+        None => String::new(),
+    };
+    let white_balance = match &settings.white_balance_mode {
+        Some(mode) => format!(
+            "<tt:WhiteBalance><tt:Mode>{}</tt:Mode>{}{}</tt:WhiteBalance>",
+            mode,
+            element("CrGain", settings.cr_gain.map(|v| v.to_string())),
+            element("CbGain", settings.cb_gain.map(|v| v.to_string())),
+        ),
+        None => String::new(),
+    };
+    let body = format!(
+        "<timg:SetImagingSettings><timg:VideoSourceToken>{}</timg:VideoSourceToken><timg:ImagingSettings>{}{}</timg:ImagingSettings><timg:ForcePersistence>true</timg:ForcePersistence></timg:SetImagingSettings>",
+        source_token, exposure, white_balance
+    );
+    soap_call(client, service_url, auth, &body).await?;
+    info!("ONVIF: Updated imaging settings of video source '{}' at {}", source_token, service_url);
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::config_loader::{OverlaySettings, PostProcessStep};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use opencv::{core as opencv_core, imgcodecs, imgproc, prelude::*};
+
+/// Text context for overlay steps. Geometric-only callers (e.g. depth frames) pass None and
+/// overlay steps are skipped, since drawing text would corrupt measurement data.
+pub struct OverlayInfo<'a> {
+    pub camera_name: &'a str,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Checks a chain once at config load, so capture-time errors are limited to image data.
+pub fn validate(steps: &[PostProcessStep]) -> Result<()> {
+    for step in steps {
+        match step {
+            PostProcessStep::Rotate(deg) if ![0, 90, 180, 270].contains(deg) => {
+                bail!("rotate must be 0, 90, 180 or 270 (got {})", deg)
+            }
+            PostProcessStep::Resize(size) => {
+                parse_size(size)?;
+            }
+            PostProcessStep::Crop(crop) if crop.width == 0 || crop.height == 0 => {
+                bail!("crop width and height must be positive")
+            }
+            PostProcessStep::Flip(axis) if !matches!(axis.as_str(), "horizontal" | "vertical" | "both") => {
+                bail!("flip must be horizontal, vertical or both (got '{}')", axis)
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn parse_size(size: &str) -> Result<(i32, i32)> {
+    let (w, h) = size.to_lowercase().split_once('x')
+        .map(|(w, h)| (w.trim().parse::<i32>(), h.trim().parse::<i32>()))
+        .with_context(|| format!("resize must be WIDTHxHEIGHT, e.g. 1280x720 (got '{}')", size))?;
+    match (w, h) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => bail!("resize must be WIDTHxHEIGHT with positive values (got '{}')", size),
+    }
+}
+
+fn draw_text(image: &mut opencv_core::Mat, text: &str, bottom: bool, font_scale: Option<f64>) -> Result<()> {
+    let scale = font_scale.unwrap_or_else(|| (image.cols() as f64 / 1280.0).max(0.4));
+    let thickness = (scale * 2.0).round().max(1.0) as i32;
+    let mut baseline = 0;
+    let text_size = imgproc::get_text_size(text, imgproc::FONT_HERSHEY_SIMPLEX, scale, thickness, &mut baseline)?;
+    let pad = (10.0 * scale) as i32;
+    let y = if bottom { image.rows() - pad - baseline } else { pad + text_size.height };
+    let origin = opencv_core::Point::new(pad, y);
+    // Dark outline under light text keeps it legible on any background.
+    imgproc::put_text(image, text, origin, imgproc::FONT_HERSHEY_SIMPLEX, scale,
+        opencv_core::Scalar::all(0.0), thickness + 2, imgproc::LINE_AA, false)?;
+    imgproc::put_text(image, text, origin, imgproc::FONT_HERSHEY_SIMPLEX, scale,
+        opencv_core::Scalar::all(255.0), thickness, imgproc::LINE_AA, false)?;
+    Ok(())
+}
+
+/// Applies the chain in order and returns the transformed image.
+pub fn apply(steps: &[PostProcessStep], image: &opencv_core::Mat, overlay: Option<&OverlayInfo>) -> Result<opencv_core::Mat> {
+    let mut current = image.try_clone()?;
+    let is_8bit = image.depth() == opencv_core::CV_8U;
+    for step in steps {
+        let mut next = opencv_core::Mat::default();
+        match step {
+            PostProcessStep::Rotate(0) => continue,
+            PostProcessStep::Rotate(deg) => {
+                let code = match deg {
+                    90 => opencv_core::ROTATE_90_CLOCKWISE,
+                    180 => opencv_core::ROTATE_180,
+                    270 => opencv_core::ROTATE_90_COUNTERCLOCKWISE,
+                    other => bail!("unsupported rotation {}", other),
+                };
+                opencv_core::rotate(&current, &mut next, code)?;
+            }
+            PostProcessStep::Resize(size) => {
+                let (w, h) = parse_size(size)?;
+                // Interpolating depth would invent distances between surfaces.
+                let interpolation = if is_8bit { imgproc::INTER_AREA } else { imgproc::INTER_NEAREST };
+                imgproc::resize(&current, &mut next, opencv_core::Size::new(w, h), 0.0, 0.0, interpolation)?;
+            }
+            PostProcessStep::Crop(crop) => {
+                let rect = opencv_core::Rect::new(crop.x as i32, crop.y as i32, crop.width as i32, crop.height as i32);
+                if rect.x + rect.width > current.cols() || rect.y + rect.height > current.rows() {
+                    bail!("crop {:?} exceeds image bounds {}x{}", crop, current.cols(), current.rows());
+                }
+                next = opencv_core::Mat::roi(&current, rect)?.try_clone()?;
+            }
+            PostProcessStep::Flip(axis) => {
+                let code = match axis.as_str() {
+                    "horizontal" => 1,
+                    "vertical" => 0,
+                    _ => -1,
+                };
+                opencv_core::flip(&current, &mut next, code)?;
+            }
+            PostProcessStep::TimestampOverlay(enabled) => {
+                let (Some(info), true, true) = (overlay, *enabled, is_8bit) else { continue };
+                let text = format!("{}  {}", info.camera_name, DateTime::<Local>::from(info.captured_at).format("%Y-%m-%d %H:%M:%S%.3f %Z"));
+                draw_text(&mut current, &text, true, None)?;
+                continue;
+            }
+            PostProcessStep::Annotate(text) => {
+                if overlay.is_none() || !is_8bit {
+                    continue;
+                }
+                draw_text(&mut current, text, false, None)?;
+                continue;
+            }
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+/// Burns the configured camera name and frame time into a video frame, in place.
+pub fn burn_in(frame: &mut opencv_core::Mat, settings: &OverlaySettings, camera_name: &str, captured_at: DateTime<Utc>) -> Result<()> {
+    let mut parts = Vec::new();
+    if settings.show_camera_name.unwrap_or(true) {
+        parts.push(camera_name.to_string());
+    }
+    if settings.show_timestamp.unwrap_or(true) {
+        let format = settings.timestamp_format.as_deref().unwrap_or("%Y-%m-%d %H:%M:%S%.3f %Z");
+        parts.push(if settings.utc.unwrap_or(false) {
+            captured_at.format(format).to_string()
+        } else {
+            DateTime::<Local>::from(captured_at).format(format).to_string()
+        });
+    }
+    if parts.is_empty() {
+        return Ok(());
+    }
+    let bottom = settings.position.as_deref().is_none_or(|p| p.eq_ignore_ascii_case("bottom"));
+    draw_text(frame, &parts.join("  "), bottom, settings.font_scale)
+}
+
+/// Wraps a packed, row-major pixel buffer (e.g. a RealSense frame) as an owned Mat.
+pub fn mat_from_slice<T: opencv_core::DataType>(data: &[T], rows: i32, channels: i32) -> Result<opencv_core::Mat> {
+    let flat = opencv_core::Mat::from_slice(data)?;
+    Ok(flat.reshape(channels, rows)?.try_clone()?)
+}
+
+/// Encoder parameters matching the application's image quality settings.
+pub fn encode_params(extension: &str, jpeg_quality: Option<u8>, png_compression: Option<u32>) -> opencv_core::Vector<i32> {
+    let mut params = opencv_core::Vector::<i32>::new();
+    match extension.to_lowercase().as_str() {
+        "jpg" | "jpeg" => {
+            params.push(imgcodecs::IMWRITE_JPEG_QUALITY);
+            params.push(jpeg_quality.unwrap_or(95) as i32);
+        }
+        "png" => {
+            params.push(imgcodecs::IMWRITE_PNG_COMPRESSION);
+            params.push(png_compression.unwrap_or(3) as i32);
+        }
+        _ => {}
+    }
+    params
+}
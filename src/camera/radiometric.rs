@@ -0,0 +1,510 @@
+//! Temperatures from thermal camera snapshots: the raw sensor image and Planck calibration a
+//! FLIR camera embeds in its radiometric JPEG (R-JPEG), and the temperature matrix a Hikvision
+//! thermal camera appends to its snapshot.
+//!
+//! FLIR temperatures use the camera's emissivity and reflected temperature but leave out
+//! atmospheric transmission, which is negligible at the few metres a rig camera looks over.
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Per-pixel temperatures in °C, row-major.
+#[derive(Debug, Clone)]
+pub struct Temperatures {
+    pub celsius: Vec<f32>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Temperatures {
+    /// Coldest and hottest finite temperature.
+    pub fn range(&self) -> Option<(f32, f32)> {
+        self.celsius.iter().copied().filter(|t| t.is_finite()).fold(None, |range, t| match range {
+            None => Some((t, t)),
+            Some((min, max)) => Some((min.min(t), max.max(t))),
+        })
+    }
+
+    /// Temperatures as 16-bit centikelvin (`value / 100 - 273.15` = °C), the usual lossless
+    /// radiometric TIFF encoding. Out-of-range and invalid pixels are clamped (NaN becomes 0).
+    pub fn centikelvin(&self) -> Vec<u16> {
+        self.celsius.iter().map(|&t| ((t as f64 + 273.15) * 100.0).round().clamp(0.0, u16::MAX as f64) as u16).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ByteOrder {
+    Big,
+    Little,
+}
+
+impl ByteOrder {
+    fn toggled(self) -> Self {
+        match self {
+            ByteOrder::Big => ByteOrder::Little,
+            ByteOrder::Little => ByteOrder::Big,
+        }
+    }
+
+    fn bytes<const N: usize>(self, data: &[u8], at: usize) -> Result<[u8; N]> {
+        let mut bytes: [u8; N] = data.get(at..at + N)
+            .with_context(|| format!("FLIR record truncated (needs {} bytes at offset {:#x}, has {})", N, at, data.len()))?
+            .try_into()
+            .expect("slice has N bytes");
+        if self == ByteOrder::Little {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    fn u16(self, data: &[u8], at: usize) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(data, at)?))
+    }
+
+    fn u32(self, data: &[u8], at: usize) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(data, at)?))
+    }
+
+    fn i32(self, data: &[u8], at: usize) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.bytes(data, at)?))
+    }
+
+    fn f32(self, data: &[u8], at: usize) -> Result<f32> {
+        Ok(f32::from_be_bytes(self.bytes(data, at)?))
+    }
+}
+
+// FFF record types in the index.
+const FFF_RAW_DATA: u16 = 0x01;
+const FFF_CAMERA_INFO: u16 = 0x20;
+
+/// Reassembles the FLIR FFF record that an R-JPEG splits over its "FLIR" APP1 segments.
+fn flir_fff(jpeg: &[u8]) -> Result<Vec<u8>> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        bail!("Not a JPEG image");
+    }
+    let mut chunks: Vec<(u8, &[u8])> = Vec::new();
+    let mut total = 0usize;
+    let mut pos = 2;
+    while pos + 4 <= jpeg.len() && jpeg[pos] == 0xFF {
+        let marker = jpeg[pos + 1];
+        if marker == 0xDA || marker == 0xD9 {
+            break; // image data follows; FLIR segments all come before it
+        }
+        if (0xD0..=0xD7).contains(&marker) || marker == 0x01 || marker == 0xFF {
+            pos += if marker == 0xFF { 1 } else { 2 };
+            continue;
+        }
+        let length = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        let payload = jpeg.get(pos + 4..pos + 2 + length).context("JPEG segment runs past the end of the file")?;
+        if marker == 0xE1 && payload.starts_with(b"FLIR\0") && payload.len() >= 8 {
+            total = payload[7] as usize + 1;
+            chunks.push((payload[6], &payload[8..]));
+        }
+        pos += 2 + length;
+    }
+    if chunks.is_empty() {
+        bail!("JPEG has no FLIR radiometric data (is radiometric JPEG enabled on the camera?)");
+    }
+    if chunks.len() != total {
+        bail!("FLIR radiometric data is incomplete ({} of {} segments)", chunks.len(), total);
+    }
+    chunks.sort_by_key(|(index, _)| *index);
+    Ok(chunks.into_iter().flat_map(|(_, data)| data.iter().copied()).collect())
+}
+
+/// The records of an FFF file by type.
+fn fff_records(fff: &[u8]) -> Result<(ByteOrder, Vec<(u16, &[u8])>)> {
+    if !fff.starts_with(b"FFF\0") {
+        bail!("FLIR radiometric data has no FFF header");
+    }
+    // The version (100..199) tells the byte order; the camera's own is normally big-endian.
+    let mut order = ByteOrder::Big;
+    if !(100..200).contains(&order.u32(fff, 0x14)?) {
+        order = order.toggled();
+        let version = order.u32(fff, 0x14)?;
+        if !(100..200).contains(&version) {
+            bail!("Unsupported FLIR FFF version {}", version);
+        }
+    }
+    let index_offset = order.u32(fff, 0x18)? as usize;
+    let entries = order.u32(fff, 0x1c)? as usize;
+    let mut records = Vec::new();
+    for entry in 0..entries {
+        let at = index_offset + entry * 32;
+        let kind = order.u16(fff, at)?;
+        let offset = order.u32(fff, at + 0x0c)? as usize;
+        let length = order.u32(fff, at + 0x10)? as usize;
+        if kind == 0 || length == 0 {
+            continue;
+        }
+        let record = fff.get(offset..offset + length)
+            .with_context(|| format!("FLIR FFF record {:#x} runs past the end of the data", kind))?;
+        records.push((kind, record));
+    }
+    Ok((order, records))
+}
+
+/// The raw sensor values and their dimensions from an FFF RawData record.
+fn raw_sensor_image(record: &[u8], order: ByteOrder) -> Result<(Vec<u16>, u32, u32)> {
+    // The record's byte order can differ from the index's; an implausible width gives it away.
+    let mut order = order;
+    if order.u16(record, 2)? > 0x0fff {
+        order = order.toggled();
+    }
+    let width = order.u16(record, 2)? as u32;
+    let height = order.u16(record, 4)? as u32;
+    let image = record.get(0x20..).context("FLIR RawData record has no image")?;
+    let pixels = (width * height) as usize;
+
+    let values: Vec<u16> = if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // FLIR writes the 16-bit PNG with its bytes swapped.
+        let png = image::load_from_memory_with_format(image, image::ImageFormat::Png)
+            .context("Failed to decode FLIR raw thermal PNG")?
+            .into_luma16();
+        png.into_raw().into_iter().map(u16::swap_bytes).collect()
+    } else if image.starts_with(b"II*\0") || image.starts_with(b"MM\0*") {
+        image::load_from_memory_with_format(image, image::ImageFormat::Tiff)
+            .context("Failed to decode FLIR raw thermal TIFF")?
+            .into_luma16()
+            .into_raw()
+    } else {
+        (0..pixels).map(|i| order.u16(image, i * 2)).collect::<Result<_>>()?
+    };
+    if values.len() != pixels {
+        bail!("FLIR raw thermal image has {} pixels, expected {}x{}", values.len(), width, height);
+    }
+    Ok((values, width, height))
+}
+
+/// Object parameters and Planck constants from an FFF CameraInfo record.
+struct FlirCalibration {
+    emissivity: f64,
+    reflected_kelvin: f64,
+    r1: f64,
+    r2: f64,
+    b: f64,
+    f: f64,
+    o: f64,
+}
+
+impl FlirCalibration {
+    fn parse(record: &[u8], order: ByteOrder) -> Result<Self> {
+        // The record starts with a 2 in its own byte order.
+        let order = if order.u16(record, 0)? == 2 { order } else { order.toggled() };
+        let calibration = FlirCalibration {
+            emissivity: order.f32(record, 0x20)? as f64,
+            reflected_kelvin: order.f32(record, 0x28)? as f64,
+            r1: order.f32(record, 0x58)? as f64,
+            b: order.f32(record, 0x5c)? as f64,
+            f: order.f32(record, 0x60)? as f64,
+            o: order.i32(record, 0x308)? as f64,
+            r2: order.f32(record, 0x30c)? as f64,
+        };
+        if !(calibration.emissivity > 0.0 && calibration.emissivity <= 1.0) {
+            bail!("FLIR emissivity {} is outside 0..1", calibration.emissivity);
+        }
+        if !(calibration.r1 > 0.0 && calibration.r2 > 0.0 && calibration.b > 0.0 && calibration.reflected_kelvin > 0.0) {
+            bail!("FLIR Planck calibration is missing or invalid");
+        }
+        Ok(calibration)
+    }
+
+    /// Converts raw sensor values to °C: removes the reflected radiation, divides by the
+    /// emissivity and inverts Planck's law.
+    fn celsius(&self, raw: &[u16]) -> Vec<f32> {
+        let raw_reflected = self.r1 / (self.r2 * ((self.b / self.reflected_kelvin).exp() - self.f)) - self.o;
+        raw.iter().map(|&value| {
+            let raw_object = (value as f64 - (1.0 - self.emissivity) * raw_reflected) / self.emissivity;
+            let kelvin = self.b / (self.r1 / (self.r2 * (raw_object + self.o)) + self.f).ln();
+            (kelvin - 273.15) as f32 // NaN where the value is outside the calibration
+        }).collect()
+    }
+}
+
+/// Temperatures from a FLIR radiometric JPEG.
+pub fn flir_temperatures(jpeg: &[u8]) -> Result<Temperatures> {
+    let fff = flir_fff(jpeg)?;
+    let (order, records) = fff_records(&fff)?;
+    let record = |kind: u16, what: &str| records.iter().find(|(k, _)| *k == kind).map(|(_, data)| *data)
+        .with_context(|| format!("FLIR radiometric data has no {} record", what));
+    let (raw, width, height) = raw_sensor_image(record(FFF_RAW_DATA, "RawData")?, order)?;
+    let calibration = FlirCalibration::parse(record(FFF_CAMERA_INFO, "CameraInfo")?, order)?;
+    Ok(Temperatures { celsius: calibration.celsius(&raw), width, height })
+}
+
+/// A Hikvision thermal snapshot: the visible JPEG and the temperature of every pixel.
+#[derive(Debug, Clone)]
+pub struct HikvisionSnapshot {
+    pub jpeg: Vec<u8>,
+    pub temperatures: Temperatures,
+}
+
+/// Parses the multipart reply of Hikvision's `jpegPicWithAppendData`: a JSON description,
+/// the JPEG and the temperature matrix (little-endian f32 °C, one per pixel).
+pub fn hikvision_snapshot(content_type: &str, body: &[u8]) -> Result<HikvisionSnapshot> {
+    let boundary = content_type.split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .with_context(|| format!("Expected a multipart reply, got '{}'", content_type))?;
+
+    let mut description: Option<serde_json::Value> = None;
+    let mut jpeg: Option<&[u8]> = None;
+    let mut matrix: Option<&[u8]> = None;
+    for (headers, data) in multipart_parts(body, boundary) {
+        let headers = headers.to_ascii_lowercase();
+        if headers.contains("application/json") {
+            description = Some(serde_json::from_slice(data).context("Invalid JSON part in thermal snapshot")?);
+        } else if headers.contains("image/") {
+            jpeg = Some(data);
+        } else if headers.contains("application/octet-stream") {
+            matrix = Some(data);
+        }
+    }
+    let description = description.context("Thermal snapshot has no JSON description")?;
+    let jpeg = jpeg.context("Thermal snapshot has no JPEG")?;
+    let matrix = matrix.context("Thermal snapshot has no temperature data")?;
+
+    let info = description.get("JpegPictureWithAppendData").unwrap_or(&description);
+    let number = |key: &str| info.get(key).and_then(serde_json::Value::as_u64);
+    let dimension = |key: &str| -> Result<u32> {
+        let value = number(key).with_context(|| format!("Thermal snapshot description has no {}", key))?;
+        u32::try_from(value).map_err(|_| anyhow!("Thermal snapshot {} {} is out of range", key, value))
+    };
+    let width = dimension("jpegPicWidth")?;
+    let height = dimension("jpegPicHeight")?;
+    let bytes_per_value = number("temperatureDataLength").unwrap_or(4);
+    if bytes_per_value != 4 {
+        bail!("Unsupported temperature data length {} (set the camera to report 4-byte temperatures)", bytes_per_value);
+    }
+    // The dimensions come from the camera; a bogus pair must not wrap around into a plausible size.
+    let expected = width.checked_mul(height)
+        .and_then(|pixels| (pixels as usize).checked_mul(4))
+        .with_context(|| format!("Thermal snapshot size {}x{} is out of range", width, height))?;
+    if matrix.len() != expected {
+        bail!("Temperature data has {} bytes, expected {} for {}x{}", matrix.len(), expected, width, height);
+    }
+    let celsius = matrix.chunks_exact(4).map(|v| f32::from_le_bytes([v[0], v[1], v[2], v[3]])).collect();
+    Ok(HikvisionSnapshot { jpeg: jpeg.to_vec(), temperatures: Temperatures { celsius, width, height } })
+}
+
+/// The (headers, body) of each part of a multipart body.
+fn multipart_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<(String, &'a [u8])> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let starts: Vec<usize> = body.windows(delimiter.len())
+        .enumerate()
+        .filter(|(_, window)| *window == delimiter.as_slice())
+        .map(|(at, _)| at)
+        .collect();
+    let mut parts = Vec::new();
+    for pair in starts.windows(2) {
+        let part = &body[pair[0] + delimiter.len()..pair[1]];
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        let Some(split) = part.windows(4).position(|window| window == b"\r\n\r\n") else { continue };
+        parts.push((String::from_utf8_lossy(&part[..split]).into_owned(), &part[split + 4..]));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `bytes` (big-endian) written at `at`, reversed for a little-endian buffer.
+    fn put(buffer: &mut Vec<u8>, at: usize, bytes: &[u8], order: ByteOrder) {
+        if buffer.len() < at + bytes.len() {
+            buffer.resize(at + bytes.len(), 0);
+        }
+        let mut bytes = bytes.to_vec();
+        if order == ByteOrder::Little {
+            bytes.reverse();
+        }
+        buffer[at..at + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    /// A "FLIR" APP1 segment carrying chunk `index` of `last + 1`.
+    fn flir_segment(index: u8, last: u8, data: &[u8]) -> Vec<u8> {
+        let mut payload = b"FLIR\0\x01".to_vec();
+        payload.extend([index, last]);
+        payload.extend_from_slice(data);
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend(((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend(payload);
+        segment
+    }
+
+    fn jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        segments.iter().for_each(|segment| jpeg.extend(segment));
+        jpeg.extend([0xFF, 0xD9]);
+        jpeg
+    }
+
+    /// An FFF file with a 0x40-byte header, the index and then the records.
+    fn fff(order: ByteOrder, records: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut fff = b"FFF\0".to_vec();
+        put(&mut fff, 0x14, &100u32.to_be_bytes(), order);
+        put(&mut fff, 0x18, &0x40u32.to_be_bytes(), order);
+        put(&mut fff, 0x1c, &(records.len() as u32).to_be_bytes(), order);
+        let mut offset = 0x40 + records.len() * 32;
+        for (entry, (kind, data)) in records.iter().enumerate() {
+            let at = 0x40 + entry * 32;
+            put(&mut fff, at, &kind.to_be_bytes(), order);
+            put(&mut fff, at + 0x0c, &(offset as u32).to_be_bytes(), order);
+            put(&mut fff, at + 0x10, &(data.len() as u32).to_be_bytes(), order);
+            offset += data.len();
+        }
+        fff.resize(0x40 + records.len() * 32, 0);
+        records.iter().for_each(|(_, data)| fff.extend_from_slice(data));
+        fff
+    }
+
+    /// An FFF RawData record holding `values` unencoded.
+    fn raw_record(width: u16, height: u16, values: &[u16], order: ByteOrder) -> Vec<u8> {
+        let mut record = Vec::new();
+        put(&mut record, 2, &width.to_be_bytes(), order);
+        put(&mut record, 4, &height.to_be_bytes(), order);
+        record.resize(0x20, 0);
+        for value in values {
+            let at = record.len();
+            put(&mut record, at, &value.to_be_bytes(), order);
+        }
+        record
+    }
+
+    fn multipart(boundary: &str, parts: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut body = b"preamble\r\n".to_vec();
+        for (content_type, data) in parts {
+            body.extend(format!("--{}\r\nContent-Type: {}\r\n\r\n", boundary, content_type).into_bytes());
+            body.extend_from_slice(data);
+            body.extend(b"\r\n");
+        }
+        body.extend(format!("--{}--\r\n", boundary).into_bytes());
+        body
+    }
+
+    fn matrix(celsius: &[f32]) -> Vec<u8> {
+        celsius.iter().flat_map(|t| t.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn flir_fff_reassembles_segments_in_index_order() {
+        let image = jpeg(&[flir_segment(1, 1, b"world"), vec![0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00], flir_segment(0, 1, b"hello ")]);
+        assert_eq!(flir_fff(&image).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn flir_fff_rejects_missing_segments_and_other_files() {
+        let incomplete = jpeg(&[flir_segment(0, 2, b"a"), flir_segment(2, 2, b"c")]);
+        assert!(flir_fff(&incomplete).unwrap_err().to_string().contains("2 of 3"));
+        assert!(flir_fff(&jpeg(&[])).unwrap_err().to_string().contains("no FLIR radiometric data"));
+        assert!(flir_fff(b"\x89PNG\r\n\x1a\n").is_err());
+    }
+
+    #[test]
+    fn flir_fff_rejects_a_segment_running_past_the_end() {
+        let mut image = jpeg(&[flir_segment(0, 0, b"data")]);
+        image.truncate(8);
+        assert!(flir_fff(&image).unwrap_err().to_string().contains("past the end"));
+    }
+
+    #[test]
+    fn fff_records_reads_either_byte_order() {
+        for order in [ByteOrder::Big, ByteOrder::Little] {
+            let data = fff(order, &[(FFF_RAW_DATA, b"raw"), (0, b"unused"), (FFF_CAMERA_INFO, b"info")]);
+            let (found, records) = fff_records(&data).unwrap();
+            assert_eq!(found, order);
+            assert_eq!(records, vec![(FFF_RAW_DATA, &b"raw"[..]), (FFF_CAMERA_INFO, &b"info"[..])]);
+        }
+    }
+
+    #[test]
+    fn fff_records_rejects_bad_headers_and_truncated_records() {
+        assert!(fff_records(b"JFIF").unwrap_err().to_string().contains("no FFF header"));
+        let mut data = fff(ByteOrder::Big, &[(FFF_RAW_DATA, b"raw")]);
+        put(&mut data, 0x14, &7u32.to_be_bytes(), ByteOrder::Big);
+        assert!(fff_records(&data).unwrap_err().to_string().contains("Unsupported FLIR FFF version"));
+        let mut data = fff(ByteOrder::Big, &[(FFF_RAW_DATA, b"raw")]);
+        data.pop();
+        assert!(fff_records(&data).unwrap_err().to_string().contains("runs past the end"));
+    }
+
+    #[test]
+    fn raw_sensor_image_reads_unencoded_values() {
+        let record = raw_record(2, 2, &[1, 2, 3, 0x4000], ByteOrder::Big);
+        assert_eq!(raw_sensor_image(&record, ByteOrder::Big).unwrap(), (vec![1, 2, 3, 0x4000], 2, 2));
+    }
+
+    #[test]
+    fn raw_sensor_image_detects_a_record_in_the_other_byte_order() {
+        // A width of 16 read in the wrong byte order is 0x1000, too wide for a thermal sensor.
+        let values: Vec<u16> = (0..16).collect();
+        let record = raw_record(16, 1, &values, ByteOrder::Little);
+        assert_eq!(raw_sensor_image(&record, ByteOrder::Big).unwrap(), (values, 16, 1));
+    }
+
+    #[test]
+    fn raw_sensor_image_rejects_short_images_and_huge_dimensions() {
+        let record = raw_record(2, 2, &[1, 2, 3], ByteOrder::Big);
+        assert!(raw_sensor_image(&record, ByteOrder::Big).unwrap_err().to_string().contains("truncated"));
+        // 0xffff x 0xffff pixels must fail on the missing data, not wrap around or allocate it.
+        let record = raw_record(0xffff, 0xffff, &[1], ByteOrder::Big);
+        assert!(raw_sensor_image(&record, ByteOrder::Big).is_err());
+    }
+
+    #[test]
+    fn hikvision_snapshot_reads_the_jpeg_and_temperatures() {
+        let description = br#"{"JpegPictureWithAppendData":{"jpegPicWidth":2,"jpegPicHeight":1,"temperatureDataLength":4}}"#;
+        let body = multipart("rcam", &[
+            ("application/json", description),
+            ("image/jpeg", b"\xFF\xD8jpeg\xFF\xD9"),
+            ("application/octet-stream", &matrix(&[21.5, -3.25])),
+        ]);
+        let snapshot = hikvision_snapshot("multipart/form-data; boundary=\"rcam\"", &body).unwrap();
+        assert_eq!(snapshot.jpeg, b"\xFF\xD8jpeg\xFF\xD9");
+        assert_eq!((snapshot.temperatures.width, snapshot.temperatures.height), (2, 1));
+        assert_eq!(snapshot.temperatures.celsius, vec![21.5, -3.25]);
+    }
+
+    #[test]
+    fn hikvision_snapshot_rejects_a_matrix_of_the_wrong_size() {
+        let body = multipart("rcam", &[
+            ("application/json", br#"{"jpegPicWidth":2,"jpegPicHeight":2}"#),
+            ("image/jpeg", b"jpeg"),
+            ("application/octet-stream", &matrix(&[20.0, 20.0, 20.0])),
+        ]);
+        let error = hikvision_snapshot("multipart/mixed; boundary=rcam", &body).unwrap_err();
+        assert!(error.to_string().contains("expected 16 for 2x2"), "{}", error);
+    }
+
+    #[test]
+    fn hikvision_snapshot_rejects_dimensions_whose_product_overflows() {
+        // 65536 x 65536 wraps to 0 pixels in u32, which an empty matrix would match.
+        let body = multipart("rcam", &[
+            ("application/json", br#"{"jpegPicWidth":65536,"jpegPicHeight":65536}"#),
+            ("image/jpeg", b"jpeg"),
+            ("application/octet-stream", b""),
+        ]);
+        let error = hikvision_snapshot("multipart/mixed; boundary=rcam", &body).unwrap_err();
+        assert!(error.to_string().contains("out of range"), "{}", error);
+    }
+
+    #[test]
+    fn hikvision_snapshot_requires_a_multipart_reply_with_every_part() {
+        assert!(hikvision_snapshot("image/jpeg", b"jpeg").unwrap_err().to_string().contains("Expected a multipart reply"));
+        let body = multipart("rcam", &[("application/json", br#"{"jpegPicWidth":1,"jpegPicHeight":1}"#), ("image/jpeg", b"jpeg")]);
+        let error = hikvision_snapshot("multipart/mixed; boundary=rcam", &body).unwrap_err();
+        assert!(error.to_string().contains("no temperature data"), "{}", error);
+    }
+
+    #[test]
+    fn multipart_parts_splits_headers_from_bodies() {
+        let mut body = multipart("rcam", &[("text/plain", b"first"), ("application/octet-stream", b"line\r\n\r\nbreaks")]);
+        body.extend(b"--rcam\r\nno header separator\r\n--rcam--");
+        let parts = multipart_parts(&body, "rcam");
+        assert_eq!(parts, vec![
+            ("Content-Type: text/plain".to_string(), &b"first"[..]),
+            ("Content-Type: application/octet-stream".to_string(), &b"line\r\n\r\nbreaks"[..]),
+        ]);
+    }
+}
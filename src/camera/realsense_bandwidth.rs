@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Practical (not theoretical) throughput for isochronous UVC traffic per host controller.
+pub const USB2_BUDGET_MBPS: f64 = 280.0;
+pub const USB3_BUDGET_MBPS: f64 = 3200.0;
+
+// Bandwidth currently reserved per USB controller: controller id -> (device name, Mbit/s).
+static CONTROLLER_LEDGER: Mutex<Option<HashMap<String, Vec<(String, f64)>>>> = Mutex::new(None);
+// Instant at which the most recent pipeline start was allowed to proceed.
+static LAST_PIPELINE_START: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Raw (uncompressed) bandwidth of a single stream in Mbit/s.
+pub fn estimate_stream_mbps(width: u32, height: u32, fps: u32, bytes_per_pixel: u32) -> f64 {
+    (width as f64 * height as f64 * fps as f64 * bytes_per_pixel as f64 * 8.0) / 1_000_000.0
+}
+
+/// Budget for a device based on the `UsbTypeDescriptor` reported by librealsense (e.g. "3.2", "2.1").
+pub fn usb_budget_mbps(usb_type_descriptor: Option<&str>) -> f64 {
+    match usb_type_descriptor {
+        Some(desc) if desc.starts_with('2') => USB2_BUDGET_MBPS,
+        Some(desc) if desc.starts_with('3') => USB3_BUDGET_MBPS,
+        _ => {
+            debug!("RS USB: Unknown USB type descriptor {:?}, assuming USB 3 budget.", usb_type_descriptor);
+            USB3_BUDGET_MBPS
+        }
+    }
+}
+
+/// Derives the host controller (bus) from a librealsense physical port path, e.g.
+/// `/sys/devices/pci0000:00/0000:00:14.0/usb2/2-3/2-3:1.0/video4linux/video0` -> `usb2`.
+pub fn controller_id_from_port(physical_port: &str) -> String {
+    physical_port
+        .split('/')
+        .find(|segment| segment.starts_with("usb") && segment[3..].chars().all(|c| c.is_ascii_digit()) && segment.len() > 3)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| physical_port.to_string())
+}
+
+/// Holds a bandwidth reservation on a USB controller until dropped.
+pub struct BandwidthReservation {
+    controller_id: String,
+    device_name: String,
+}
+
+impl Drop for BandwidthReservation {
+    fn drop(&mut self) {
+        let mut guard = CONTROLLER_LEDGER.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ledger) = guard.as_mut() {
+            if let Some(entries) = ledger.get_mut(&self.controller_id) {
+                entries.retain(|(name, _)| name != &self.device_name);
+            }
+        }
+        debug!("RS USB [{}]: Released bandwidth reservation on controller '{}'.", self.device_name, self.controller_id);
+    }
+}
+
+/// Reserves `required_mbps` on `controller_id`, failing with an error that names the saturated
+/// controller and the devices already streaming on it.
+pub fn reserve_bandwidth(
+    device_name: &str,
+    controller_id: &str,
+    usb_type_descriptor: Option<&str>,
+    required_mbps: f64,
+) -> Result<BandwidthReservation> {
+    let budget = usb_budget_mbps(usb_type_descriptor);
+    let mut guard = CONTROLLER_LEDGER.lock().unwrap_or_else(|e| e.into_inner());
+    let ledger = guard.get_or_insert_with(HashMap::new);
+    let entries = ledger.entry(controller_id.to_string()).or_default();
+    let in_use: f64 = entries.iter().map(|(_, mbps)| mbps).sum();
+
+    if in_use + required_mbps > budget {
+        let others: Vec<String> = entries.iter().map(|(name, mbps)| format!("{} ({:.0} Mbit/s)", name, mbps)).collect();
+        return Err(anyhow!(
+            "RS [{}]: USB controller '{}' (USB {}) is saturated: requested {:.0} Mbit/s + {:.0} Mbit/s already in use by [{}] exceeds budget of {:.0} Mbit/s. Lower resolution/fps or move the device to another controller.",
+            device_name,
+            controller_id,
+            usb_type_descriptor.unwrap_or("unknown"),
+            required_mbps,
+            in_use,
+            others.join(", "),
+            budget
+        ));
+    }
+
+    entries.push((device_name.to_string(), required_mbps));
+    info!(
+        "RS USB [{}]: Reserved {:.0} Mbit/s on controller '{}' ({:.0}/{:.0} Mbit/s in use).",
+        device_name, required_mbps, controller_id, in_use + required_mbps, budget
+    );
+    Ok(BandwidthReservation {
+        controller_id: controller_id.to_string(),
+        device_name: device_name.to_string(),
+    })
+}
+
+/// Blocks the calling (blocking) thread so consecutive pipeline starts are at least `stagger` apart.
+pub fn wait_for_start_slot(device_name: &str, stagger: Duration) {
+    if stagger.is_zero() {
+        return;
+    }
+    let mut last_start = LAST_PIPELINE_START.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    let slot = match *last_start {
+        Some(previous) if previous + stagger > now => previous + stagger,
+        _ => now,
+    };
+    *last_start = Some(slot);
+    drop(last_start);
+
+    let wait = slot.saturating_duration_since(now);
+    if !wait.is_zero() {
+        info!("RS USB [{}]: Staggering pipeline start by {:?}.", device_name, wait);
+        std::thread::sleep(wait);
+    }
+}
+
+/// Warns when the configured RealSense streams cannot fit on a single USB 3 controller.
+pub fn warn_if_aggregate_exceeds_budget(devices: &[(String, f64)]) {
+    let total: f64 = devices.iter().map(|(_, mbps)| mbps).sum();
+    if devices.len() > 1 && total > USB3_BUDGET_MBPS {
+        warn!(
+            "⚠️ Configured RealSense streams need {:.0} Mbit/s in total, more than one USB 3 controller provides ({:.0} Mbit/s). Devices sharing a controller will fail to start: {:?}",
+            total, USB3_BUDGET_MBPS, devices
+        );
+    }
+}
@@ -1,29 +1,56 @@
-use crate::config_loader::RealsenseSpecificConfig;
+use crate::config_loader::{AppSettings, PostProcessStep, RealsenseSpecificConfig};
+use crate::camera::frame_export;
+use crate::camera::post_process::{self, OverlayInfo};
+use crate::camera::realsense_bandwidth::{self, BandwidthReservation};
+use crate::camera::realsense_hotplug::{self, Presence};
+use crate::camera::realsense_options::{self, RealsenseOptions, RealsenseOptionsConfig};
+use crate::common::blocking_watchdog::{self, BlockingPhase, WatchdogSettings};
+use crate::common::{atomic_file, opencv_pool, sync_start};
+use crate::core::events::{self, Event};
 use crate::core::capture_source::{
-    CaptureSource, FrameData, FrameDataBundle, RsColorFrameData, RsDepthFrameData,
+    CaptureSource, CaptureTimings, FrameData, FrameDataBundle, RsColorFrameData, RsDepthFrameData, RsExtrinsics, RsIntrinsics,
+    VideoRecordRequest, VideoRecorder, VideoSource,
 };
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use image; // Using image crate for saving
-use log::{info, warn};
+use log::{debug, info, warn};
+use opencv::prelude::*;
 use realsense_rust::{
     config::Config as RsConfig,
     context::Context as RsContext,
     frame::{ColorFrame, CompositeFrame, DepthFrame, FrameEx}, // ImageFrame removed, specific frames used directly
-    kind::{Rs2CameraInfo, Rs2Format, Rs2StreamKind},
+    kind::{Rs2CameraInfo, Rs2Format, Rs2StreamKind, Rs2TimestampDomain},
     pipeline::{ActivePipeline as RsActivePipeline, InactivePipeline as RsInactivePipeline},
     stream_profile::StreamProfile,
 };
 use std::collections::HashSet;
 use std::ffi::CString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
 use std::time::Duration as StdDuration;
-use tokio::task;
+use tokio::sync::oneshot;
+use tokio_util::sync::{CancellationToken, DropGuard};
 
-#[derive(Debug, Clone)]
+/// Extension of librealsense recordings, written by `capture-video` and replayed via `bag_file`.
+pub const BAG_EXTENSION: &str = "bag";
+
+// Framesets a warm pipeline may have queued before a capture was requested; dropped so the
+// saved frame is taken after the request.
+const MAX_STALE_FRAMESETS: usize = 16;
+
+#[derive(Debug)]
 pub struct RealsenseDevice {
     pub name: String,
     pub config: RealsenseSpecificConfig,
+    pub start_stagger: StdDuration,
+    pub reconnect_wait: StdDuration,
+    pub watchdog: WatchdogSettings,
+    // None until the first capture or open registers the device for hot-plug tracking.
+    presence: Option<Presence>,
+    // Set between open() and close(): the thread that keeps the pipeline streaming.
+    warm: Option<WarmPipeline>,
 }
 
 #[async_trait]
@@ -36,224 +63,782 @@ impl CaptureSource for RealsenseDevice {
         "RealsenseCamera".to_string()
     }
 
+    /// Starts the pipeline and keeps it streaming on a thread of its own, so later captures skip
+    /// enumeration and pipeline start (~2-3 s) and auto exposure has already settled. The thread
+    /// is not one of the OpenCV pool's, so open pipelines leave the pool to the other cameras.
+    /// When the device is unplugged the pipeline stops, and it is reopened once it is back.
+    async fn open(&mut self) -> Result<()> {
+        if self.warm.is_some() {
+            return Ok(());
+        }
+        self.wait_until_plugged().await?;
+        let (requests, receiver) = mpsc::channel();
+        let settings = self.settings();
+        let thread_name = format!("rcam-rs-{}", self.name);
+        let done = opencv_pool::spawn_dedicated(thread_name, opencv_pool::pinned(self.config.cpu_affinity.clone(), move || serve_warm(settings, receiver)))
+            .with_context(|| format!("RS [{}]: Failed to start the pipeline thread", self.name))?;
+        let mut warm = WarmPipeline { requests, done, replugs: None };
+
+        let phase = BlockingPhase::new("enumerating RealSense devices");
+        let (reply, started) = oneshot::channel();
+        let _ = warm.requests.send(WarmRequest::Start { phase: phase.clone(), reply });
+        blocking_watchdog::supervise(&self.name, self.watchdog, phase, warm_reply(started)).await
+            .with_context(|| format!("RS [{}]: Failed to open the pipeline", self.name))?;
+        info!("🔥 RS [{}]: Pipeline open; it keeps streaming until closed.", self.name);
+        if let Some(presence) = self.track_hotplug() {
+            let stop = CancellationToken::new();
+            tokio::spawn(follow_replugs(self.name.clone(), self.watchdog, presence, warm.requests.clone(), stop.clone()));
+            warm.replugs = Some(stop.drop_guard());
+        }
+        self.warm = Some(warm);
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        let Some(warm) = self.warm.take() else {
+            return Ok(());
+        };
+        drop(warm.replugs); // ends the re-plug follower, which holds a sender too
+        drop(warm.requests); // the thread stops the pipeline once its queue is empty
+        let phase = BlockingPhase::new("stopping RealSense pipeline");
+        blocking_watchdog::supervise(&self.name, self.watchdog, phase, warm.done).await?;
+        info!("RS [{}]: Pipeline closed.", self.name);
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.warm.is_some()
+    }
+
+    /// With `record_bag`, librealsense records the device's streams to a .bag; otherwise the
+    /// camera is not recorded. The recording needs the device, so an open pipeline is an error.
+    async fn video_source(&mut self) -> Result<Option<VideoSource>> {
+        if !self.config.record_bag.unwrap_or(false) {
+            return Ok(None);
+        }
+        if self.warm.is_some() {
+            return Err(anyhow!("RS [{}]: The pipeline is open for captures; close it before recording a .bag", self.name));
+        }
+        let recorder = RealsenseDevice::new(self.name.clone(), self.config.clone(), self.start_stagger, self.reconnect_wait, self.watchdog);
+        Ok(Some(VideoSource::Local(Arc::new(recorder))))
+    }
+
     async fn capture_image(
         &mut self, 
         output_dir: &Path,
         timestamp_str: &str,
-        _image_format_config: &str,
+        image_format_config: &str,
         _jpeg_quality: Option<u8>,
         _png_compression: Option<u32>,
     ) -> Result<FrameDataBundle> {
-        self.capture_image_internal(output_dir, timestamp_str).await
+        let request = SaveRequest {
+            output_dir: output_dir.to_path_buf(),
+            timestamp_str: timestamp_str.to_string(),
+            // Color stays lossless PNG unless a raw export format is requested.
+            color_format: if frame_export::needs_local_encode(image_format_config) { image_format_config.to_lowercase() } else { "png".to_string() },
+        };
+        self.wait_until_plugged().await?;
+        match &self.warm {
+            Some(warm) => self.capture_warm(warm, request).await,
+            None => self.capture_image_internal(request).await,
+        }
+    }
+}
+
+#[async_trait]
+impl VideoRecorder for RealsenseDevice {
+    fn describe(&self) -> String {
+        match &self.config.serial_number {
+            Some(serial) => format!("RealSense S/N {} to .bag", serial),
+            None => "first RealSense found, to .bag".to_string(),
+        }
+    }
+
+    fn extension(&self, _app: &AppSettings) -> String {
+        BAG_EXTENSION.to_string()
+    }
+
+    async fn record(&self, path: &Path, request: &VideoRecordRequest) -> Result<(PathBuf, StdDuration)> {
+        self.record_bag(path, request.duration, request.start_at, request.cancel.clone()).await
     }
 }
 
 impl RealsenseDevice {
-    pub fn new(name: String, config: RealsenseSpecificConfig) -> Self {
-        Self { name, config }
+    pub fn new(name: String, config: RealsenseSpecificConfig, start_stagger: StdDuration, reconnect_wait: StdDuration, watchdog: WatchdogSettings) -> Self {
+        Self { name, config, start_stagger, reconnect_wait, watchdog, presence: None, warm: None }
     }
 
-    async fn capture_image_internal(
+    /// A device with the start stagger, reconnect wait and watchdog from the application settings.
+    pub fn from_app(name: String, config: RealsenseSpecificConfig, app: &AppSettings) -> Self {
+        let start_stagger = StdDuration::from_millis(app.realsense_start_stagger_ms.unwrap_or(500));
+        let reconnect_wait = StdDuration::from_secs_f32(app.realsense_reconnect_wait_secs.unwrap_or(3.0));
+        Self::new(name, config, start_stagger, reconnect_wait, WatchdogSettings::from_app(app))
+    }
+
+    /// Records the device's streams to a librealsense `.bag` at `output_path` for `duration`
+    /// (or until cancelled). Returns the path and how long was recorded.
+    pub async fn record_bag(
         &self,
-        output_dir: &Path,
-        timestamp_str: &str,
-    ) -> Result<FrameDataBundle> {
-        let name_clone = self.name.clone();
-        let config_clone = self.config.clone();
-        let output_dir_clone = output_dir.to_path_buf();
-        let timestamp_str_clone = timestamp_str.to_string();
-
-        task::spawn_blocking(move || -> Result<FrameDataBundle> {
-            info!("RS Blocking [{}]: Task started.", name_clone);
-            let mut active_pipeline_opt: Option<RsActivePipeline> = None;
-
-            let frame_data_bundle_result: Result<FrameDataBundle> = (|| {
-                let context = RsContext::new().context("RS: Failed to create Realsense context")?;
-                
-                let device_list = context.query_devices(HashSet::new());
-
-                if device_list.is_empty() {
-                    return Err(anyhow!("RS [{}]: No Realsense devices found.", name_clone));
-                }
+        output_path: &Path,
+        duration: StdDuration,
+        start_at: Option<DateTime<Utc>>,
+        cancel: Option<CancellationToken>,
+    ) -> Result<(PathBuf, StdDuration)> {
+        if let Some(at) = start_at {
+            let late = sync_start::sleep_until(at).await;
+            if !late.is_zero() {
+                warn!("⚠️ '{}': missed the scheduled start by {:?}; recording now.", self.name, late);
+            }
+        }
+        let settings = self.settings();
+        let output_path = output_path.to_path_buf();
+        let phase = BlockingPhase::new("enumerating RealSense devices");
+        let phase_clone = phase.clone();
+        let cpus = self.config.cpu_affinity.clone();
 
-                let device_serial_to_use: String;
-
-                if let Some(serial_to_find) = &config_clone.serial_number {
-                    info!("RS [{}]: Searching for device S/N: {}", name_clone, serial_to_find);
-                    let found_device = device_list.iter().find(|dev| {
-                        dev.info(Rs2CameraInfo::SerialNumber)
-                            .and_then(|cstr| cstr.to_str().ok())
-                            .map_or(false, |s| s == serial_to_find.as_str())
-                    });
-
-                    if let Some(dev) = found_device {
-                        let sn_cstr = dev.info(Rs2CameraInfo::SerialNumber)
-                            .ok_or_else(|| anyhow!("RS [{}]: Failed to get S/N CString for found device S/N '{}'", name_clone, serial_to_find))?;
-                        device_serial_to_use = sn_cstr.to_str()
-                            .map_err(|e| anyhow!("RS [{}]: Failed to convert S/N CString to str for found device: {}", name_clone, e))?
-                            .to_string();
-                        info!("RS [{}]: Found target device S/N: {}", name_clone, device_serial_to_use);
-                    } else {
-                        return Err(anyhow!("RS [{}]: Specified device S/N '{}' not found.", name_clone, serial_to_find));
+        blocking_watchdog::run_blocking(&self.name, self.watchdog, phase, opencv_pool::pinned(cpus, move || -> Result<(PathBuf, StdDuration)> {
+            let phase = phase_clone;
+            let mut streaming = start_streaming(&settings, &phase, Some(&output_path))?;
+            let started = std::time::Instant::now();
+            events::publish(Event::RecordingStarted { camera: settings.name.clone(), at: Utc::now(), path: output_path.clone() });
+            info!("🎥 RS [{}]: Recording to {} for {:?}.", settings.name, output_path.display(), duration);
+            // The recorder writes every frame the device delivers; waiting only keeps the pipeline drained.
+            phase.enter_unbounded("recording RealSense .bag");
+            let mut framesets = 0u64;
+            let mut result = Ok(());
+            while started.elapsed() < duration && !cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                match streaming.pipeline.wait(Some(StdDuration::from_secs(5))) {
+                    Ok(_) => framesets += 1,
+                    Err(e) => {
+                        result = Err(anyhow!("RS [{}]: No frames within 5s while recording ({}): {}", settings.name, streaming.usb_context, e));
+                        break;
                     }
-                } else {
-                    info!("RS [{}]: No S/N specified, using first available device.", name_clone);
-                    if let Some(dev) = device_list.first() {
-                        let sn_cstr = dev.info(Rs2CameraInfo::SerialNumber)
-                            .ok_or_else(|| anyhow!("RS [{}]: Failed to get S/N CString for first available device", name_clone))?;
-                        device_serial_to_use = sn_cstr.to_str()
-                            .map_err(|e| anyhow!("RS [{}]: Failed to convert S/N CString to str for first device: {}", name_clone, e))?
-                            .to_string();
-                        info!("RS [{}]: Using first device S/N: {}", name_clone, device_serial_to_use);
-                    } else {
-                        return Err(anyhow!("RS [{}]: Device list was empty when attempting to use first device (unexpected).", name_clone));
-                    }
-                }
-                
-                let inactive_pipeline = RsInactivePipeline::try_from(&context)
-                    .context("RS: Failed to create inactive pipeline from context")?;
-                
-                let mut rs_pipeline_config = RsConfig::new();
-                let c_device_serial = CString::new(device_serial_to_use.clone())
-                    .with_context(|| format!("RS [{}]: Failed to create CString from serial: {}", name_clone, device_serial_to_use))?;
-                
-                rs_pipeline_config.enable_device_from_serial(c_device_serial.as_c_str())
-                    .with_context(|| format!("RS [{}]: Failed to enable device S/N '{}' in config", name_clone, device_serial_to_use))?;
-                
-                rs_pipeline_config.disable_all_streams()
-                    .context("RS: Failed to disable all streams in config")?;
-
-                let mut color_stream_actually_enabled = false;
-                if config_clone.enable_color_stream.unwrap_or(true) {
-                    let w = config_clone.color_width.unwrap_or(640);
-                    let h = config_clone.color_height.unwrap_or(480);
-                    let fps = config_clone.color_fps.unwrap_or(30);
-                    rs_pipeline_config.enable_stream(Rs2StreamKind::Color, None, w as usize, h as usize, Rs2Format::Bgr8, fps as usize)
-                        .with_context(|| format!("RS [{}]: Failed to enable color stream ({}x{}@{} BGR8)", name_clone, w, h, fps))?;
-                    color_stream_actually_enabled = true;
-                    info!("RS [{}]: Color stream configured ({}x{}@{}fps BGR8).", name_clone, w, h, fps);
-                }
-
-                let mut depth_stream_actually_enabled = false;
-                if config_clone.enable_depth_stream.unwrap_or(true) {
-                    let w = config_clone.depth_width.unwrap_or(640);
-                    let h = config_clone.depth_height.unwrap_or(480);
-                    let fps = config_clone.depth_fps.unwrap_or(30);
-                    rs_pipeline_config.enable_stream(Rs2StreamKind::Depth, None, w as usize, h as usize, Rs2Format::Z16, fps as usize)
-                        .with_context(|| format!("RS [{}]: Failed to enable depth stream ({}x{}@{} Z16)", name_clone, w, h, fps))?;
-                    depth_stream_actually_enabled = true;
-                    info!("RS [{}]: Depth stream configured ({}x{}@{}fps Z16).", name_clone, w, h, fps);
                 }
+            }
+            let recorded_for = started.elapsed().min(duration);
+            // Stopping the pipeline closes and indexes the .bag.
+            streaming.stop(&settings.name, &phase);
+            result?;
+            info!("RS [{}]: Recorded {} frameset(s) in {:.1}s.", settings.name, framesets, recorded_for.as_secs_f64());
+            Ok((output_path, recorded_for))
+        })).await
+    }
 
-                if !color_stream_actually_enabled && !depth_stream_actually_enabled {
-                    return Err(anyhow!("RS [{}]: Both color and depth streams are disabled.", name_clone));
-                }
+    /// Streams for `duration` and returns the timestamp of every frameset, for comparing devices
+    /// that should be hardware-synced. Depth frames are used when enabled, as the sync cable
+    /// drives the depth sensor. The device must not be open.
+    pub async fn sample_frame_timestamps(&self, duration: StdDuration) -> Result<FrameTimestamps> {
+        if self.warm.is_some() {
+            return Err(anyhow!("RS [{}]: The pipeline is open; close it before sampling frame timestamps", self.name));
+        }
+        let settings = self.settings();
+        let phase = BlockingPhase::new("enumerating RealSense devices");
+        let phase_clone = phase.clone();
+        let cpus = self.config.cpu_affinity.clone();
 
-                info!("RS [{}]: Starting pipeline for S/N {}...", name_clone, device_serial_to_use);
-                let active_pipeline = inactive_pipeline.start(Some(rs_pipeline_config))
-                    .context("RS: Failed to start pipeline")?;
-                active_pipeline_opt = Some(active_pipeline);
-                let pipeline_ref = active_pipeline_opt.as_mut().unwrap();
-
-                info!("RS [{}]: Waiting for frameset...", name_clone);
-                let frameset: CompositeFrame = pipeline_ref.wait(Some(StdDuration::from_secs(5)))
-                    .context("RS: Wait for frames failed")?;
-                info!("RS [{}]: Frameset received with {} frames (API count).", name_clone, frameset.count());
-
-                let mut processed_color_data: Option<RsColorFrameData> = None;
-                let mut processed_depth_data: Option<RsDepthFrameData> = None;
-
-                if color_stream_actually_enabled {
-                    let color_frames: Vec<ColorFrame> = frameset.frames_of_type::<ColorFrame>();
-                    if let Some(color_frame) = color_frames.first() {
-                        let profile: &StreamProfile = color_frame.stream_profile();
-                        info!("RS [{}]: Processing ColorFrame. Format: {:?}, Res: {}x{}, BPP: {}, TS: {}, Domain: {:?}", 
-                            name_clone, profile.format(), color_frame.width(), color_frame.height(), 
-                            color_frame.bits_per_pixel(), color_frame.timestamp(), color_frame.timestamp_domain());
-
-                        let width = color_frame.width() as u32;
-                        let height = color_frame.height() as u32;
-                        let bpp_usize = color_frame.bits_per_pixel() / 8;
-                        if bpp_usize != 3 {
-                            return Err(anyhow!("RS [{}]: Color frame BPP is {}, expected 3 (BGR8).", name_clone, bpp_usize));
-                        }
-                        let data_size = width as usize * height as usize * bpp_usize;
-                        let raw_data_ptr: *const std::os::raw::c_void = unsafe { color_frame.get_data() };
-                        let color_data_slice = unsafe { std::slice::from_raw_parts(raw_data_ptr as *const u8, data_size) };
-
-                        let mut rgb_pixel_data = Vec::with_capacity(data_size);
-                        for chunk in color_data_slice.chunks_exact(3) {
-                            rgb_pixel_data.push(chunk[2]);
-                            rgb_pixel_data.push(chunk[1]);
-                            rgb_pixel_data.push(chunk[0]);
-                        }
-
-                        let color_filename = format!("{}_realsense_{}_color.png", timestamp_str_clone, name_clone.replace(" ", "_"));
-                        let color_path = output_dir_clone.join(&color_filename);
-                        image::save_buffer_with_format(&color_path, &rgb_pixel_data, width, height, image::ColorType::Rgb8, image::ImageFormat::Png)
-                            .with_context(|| format!("RS [{}]: Failed to save color image to {:?}", name_clone, color_path))?;
-                        info!("RS [{}]: Saved color image to {:?}", name_clone, color_path);
-                        processed_color_data = Some(RsColorFrameData { rgb_data: rgb_pixel_data, width, height });
-                    } else {
-                         warn!("RS [{}]: Color stream enabled, but no ColorFrame found in frameset.", name_clone);
+        blocking_watchdog::run_blocking(&self.name, self.watchdog, phase, opencv_pool::pinned(cpus, move || -> Result<FrameTimestamps> {
+            let phase = phase_clone;
+            let mut streaming = start_streaming(&settings, &phase, None)?;
+            phase.enter("sampling RealSense frame timestamps");
+            let started = std::time::Instant::now();
+            let mut sampled = FrameTimestamps { millis: Vec::new(), domain: String::new(), global_time: false };
+            let mut result = Ok(());
+            while started.elapsed() < duration {
+                let frameset = match streaming.pipeline.wait(Some(StdDuration::from_secs(5))) {
+                    Ok(frameset) => frameset,
+                    Err(e) => {
+                        result = Err(anyhow!("RS [{}]: No frames within 5s while sampling timestamps ({}): {}", settings.name, streaming.usb_context, e));
+                        break;
                     }
+                };
+                let stamp = frameset.frames_of_type::<DepthFrame>().first().map(|f| (f.timestamp(), f.timestamp_domain()))
+                    .or_else(|| frameset.frames_of_type::<ColorFrame>().first().map(|f| (f.timestamp(), f.timestamp_domain())));
+                if let Some((millis, domain)) = stamp {
+                    sampled.millis.push(millis);
+                    sampled.global_time = matches!(domain, Rs2TimestampDomain::GlobalTime);
+                    sampled.domain = format!("{:?}", domain);
                 }
+            }
+            streaming.stop(&settings.name, &phase);
+            result?;
+            debug!("RS [{}]: Sampled {} frame timestamp(s) ({}).", settings.name, sampled.millis.len(), sampled.domain);
+            Ok(sampled)
+        })).await
+    }
+
+    fn settings(&self) -> CaptureSettings {
+        CaptureSettings {
+            name: self.name.clone(),
+            config: self.config.clone(),
+            start_stagger: self.start_stagger,
+            reconnect_wait: self.reconnect_wait,
+            post_process_steps: self.config.post_process.clone().unwrap_or_default(),
+            depth_format: self.config.depth_format.as_deref().unwrap_or("png").to_lowercase(),
+        }
+    }
+
+    /// Registers the device for hot-plug tracking; None for a `.bag` playback, or when
+    /// librealsense cannot report device changes.
+    fn track_hotplug(&self) -> Option<Presence> {
+        if self.config.bag_file.is_some() {
+            return None;
+        }
+        realsense_hotplug::register(&self.name, self.config.serial_number.as_deref())
+    }
+
+    /// Returns once the device is plugged in. If the hot-plug watcher saw it unplugged, waits up
+    /// to `reconnect_wait` for it to come back and fails without touching librealsense otherwise.
+    async fn wait_until_plugged(&mut self) -> Result<()> {
+        if self.presence.is_none() {
+            self.presence = self.track_hotplug();
+        }
+        let Some(presence) = self.presence.as_mut() else {
+            return Ok(());
+        };
+        if presence.is_connected() {
+            return Ok(());
+        }
+        info!("🔌 RS [{}]: Device is unplugged; waiting up to {:?} for it to be plugged back in...", self.name, self.reconnect_wait);
+        if presence.wait_connected(self.reconnect_wait).await {
+            return Ok(());
+        }
+        Err(anyhow!("RS [{}]: Device is unplugged (not back within {:?})", self.name, self.reconnect_wait))
+    }
+
+    /// One capture on a closed device: enumerate, start the pipeline, save a frameset, stop.
+    async fn capture_image_internal(&self, request: SaveRequest) -> Result<FrameDataBundle> {
+        let settings = self.settings();
+        let phase = BlockingPhase::new("enumerating RealSense devices");
+        let phase_clone = phase.clone();
+        let cpus = self.config.cpu_affinity.clone();
 
-                if depth_stream_actually_enabled {
-                    let depth_frames: Vec<DepthFrame> = frameset.frames_of_type::<DepthFrame>();
-                    if let Some(depth_frame) = depth_frames.first() {
-                        let profile: &StreamProfile = depth_frame.stream_profile();
-                        let current_depth_units = depth_frame.depth_units()
-                            .context("RS: Failed to get depth units")?;
-                        info!("RS [{}]: Processing DepthFrame. Format: {:?}, Res: {}x{}, BPP: {}, TS: {}, Domain: {:?}, Units: {}",
-                            name_clone, profile.format(), depth_frame.width(), depth_frame.height(),
-                            depth_frame.bits_per_pixel(), depth_frame.timestamp(), depth_frame.timestamp_domain(), current_depth_units);
-
-                        let width = depth_frame.width() as u32;
-                        let height = depth_frame.height() as u32;
-                        let bpp_usize = depth_frame.bits_per_pixel() / 8;
-                        if bpp_usize != 2 {
-                            return Err(anyhow!("RS [{}]: Depth frame BPP is {}, expected 2 (Z16).", name_clone, bpp_usize));
-                        }
-                        let data_size_pixels = width as usize * height as usize;
-                        let raw_data_ptr: *const std::os::raw::c_void = unsafe { depth_frame.get_data() };
-                        let depth_data_slice_u16 = unsafe { std::slice::from_raw_parts(raw_data_ptr as *const u16, data_size_pixels) };
-                        
-                        let depth_filename = format!("{}_realsense_{}_depth.png", timestamp_str_clone, name_clone.replace(" ", "_"));
-                        let depth_path = output_dir_clone.join(&depth_filename);
-                        
-                        let depth_image_buffer: image::ImageBuffer<image::Luma<u16>, Vec<u16>> = 
-                            image::ImageBuffer::from_raw(width, height, depth_data_slice_u16.to_vec())
-                            .ok_or_else(|| anyhow!("RS [{}]: Could not create depth image buffer from raw data", name_clone))?;
-                        
-                        depth_image_buffer.save_with_format(&depth_path, image::ImageFormat::Png)
-                            .with_context(|| format!("RS [{}]: Failed to save depth image to {:?}", name_clone, depth_path))?;
-                        info!("RS [{}]: Saved depth image to {:?}", name_clone, depth_path);
-                        processed_depth_data = Some(RsDepthFrameData { depth_data: depth_data_slice_u16.to_vec(), depth_units: current_depth_units, width, height });
-                    } else {
-                        warn!("RS [{}]: Depth stream enabled, but no DepthFrame found in frameset.", name_clone);
+        blocking_watchdog::run_blocking(&self.name, self.watchdog, phase, opencv_pool::pinned(cpus, move || -> Result<FrameDataBundle> {
+            let phase = phase_clone;
+            info!("RS Blocking [{}]: Task started.", settings.name);
+            let mut streaming = start_streaming(&settings, &phase, None)?;
+            let timings = CaptureTimings { connect_ms: CaptureTimings::ms(streaming.started_in), ..Default::default() };
+            let result = capture_frameset(&mut streaming, &settings, &request, &phase, timings);
+            streaming.stop(&settings.name, &phase);
+            info!("RS Blocking [{}]: Task finished.", settings.name);
+            result
+        })).await
+    }
+
+    /// One capture from the open pipeline. It is restarted first if an earlier capture failed.
+    async fn capture_warm(&self, warm: &WarmPipeline, request: SaveRequest) -> Result<FrameDataBundle> {
+        let phase = BlockingPhase::new("waiting for the open RealSense pipeline");
+        let (reply, captured) = oneshot::channel();
+        warm.requests.send(WarmRequest::Capture { request, phase: phase.clone(), reply })
+            .map_err(|_| anyhow!("RS [{}]: The open pipeline has stopped", self.name))?;
+        blocking_watchdog::supervise(&self.name, self.watchdog, phase, warm_reply(captured)).await
+    }
+}
+
+/// Frame timestamps from `RealsenseDevice::sample_frame_timestamps`, in milliseconds.
+#[derive(Debug, Clone)]
+pub struct FrameTimestamps {
+    pub millis: Vec<f64>,
+    pub domain: String,    // librealsense timestamp domain of the frames
+    pub global_time: bool, // on the host clock, so comparable across devices
+}
+
+/// Everything a capture needs from the device, owned so it can move to a worker thread.
+#[derive(Clone)]
+struct CaptureSettings {
+    name: String,
+    config: RealsenseSpecificConfig,
+    start_stagger: StdDuration,
+    reconnect_wait: StdDuration,
+    post_process_steps: Vec<PostProcessStep>,
+    depth_format: String,
+}
+
+/// Where and how one capture's frames are saved.
+struct SaveRequest {
+    output_dir: PathBuf,
+    timestamp_str: String,
+    color_format: String,
+}
+
+/// A pipeline kept open by `RealsenseDevice::open`. The pipeline itself never leaves the thread
+/// serving it; captures are requests sent to that thread.
+struct WarmPipeline {
+    requests: mpsc::Sender<WarmRequest>,
+    done: opencv_pool::PoolTask<()>,
+    replugs: Option<DropGuard>, // stops `follow_replugs` when dropped
+}
+
+impl std::fmt::Debug for WarmPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WarmPipeline").finish_non_exhaustive()
+    }
+}
+
+enum WarmRequest {
+    Start { phase: BlockingPhase, reply: oneshot::Sender<Result<()>> },
+    Capture { request: SaveRequest, phase: BlockingPhase, reply: oneshot::Sender<Result<FrameDataBundle>> },
+    Unplugged,
+    Replugged { phase: BlockingPhase, reply: oneshot::Sender<Result<()>> },
+}
+
+async fn warm_reply<T>(reply: oneshot::Receiver<Result<T>>) -> Result<T> {
+    reply.await.map_err(|_| anyhow!("the open pipeline stopped before replying"))?
+}
+
+/// Follows an open pipeline's device through hot-plug events until `stop`: the pipeline is
+/// stopped when the device is unplugged and reopened as soon as it is plugged back in, rather
+/// than on the next capture.
+async fn follow_replugs(name: String, watchdog: WatchdogSettings, mut presence: Presence, requests: mpsc::Sender<WarmRequest>, stop: CancellationToken) {
+    loop {
+        let connected = tokio::select! {
+            _ = stop.cancelled() => return,
+            change = presence.next_change() => match change {
+                Some(connected) => connected,
+                None => return,
+            },
+        };
+        if !connected {
+            if requests.send(WarmRequest::Unplugged).is_err() {
+                return;
+            }
+            continue;
+        }
+        let phase = BlockingPhase::new("reopening RealSense pipeline");
+        let (reply, started) = oneshot::channel();
+        if requests.send(WarmRequest::Replugged { phase: phase.clone(), reply }).is_err() {
+            return;
+        }
+        match blocking_watchdog::supervise(&name, watchdog, phase, warm_reply(started)).await {
+            Ok(()) => info!("🔥 RS [{}]: Device is back; pipeline reopened.", name),
+            Err(e) => warn!("RS [{}]: Could not reopen the pipeline after the device came back ({:#}); the next capture tries again.", name, e),
+        }
+    }
+}
+
+/// Serves a warm pipeline's requests until the device closes it (drops the sender). A failed
+/// capture or an unplugged device stops the pipeline, so the next capture (or the device being
+/// plugged back in) starts it over, re-enumerating the device.
+fn serve_warm(settings: CaptureSettings, requests: mpsc::Receiver<WarmRequest>) {
+    let mut streaming: Option<Streaming> = None;
+    for request in requests {
+        match request {
+            WarmRequest::Start { phase, reply } => {
+                let result = ensure_streaming(&mut streaming, &settings, &phase).map(|_| ());
+                let _ = reply.send(result); // the caller may have stopped waiting (watchdog timeout)
+            }
+            WarmRequest::Capture { request, phase, reply } => {
+                let result = capture_warm_frameset(&mut streaming, &settings, &request, &phase);
+                if let Err(e) = &result {
+                    if let Some(failed) = streaming.take() {
+                        warn!("RS [{}]: Capture from the open pipeline failed ({:#}); restarting it for the next capture.", settings.name, e);
+                        failed.stop(&settings.name, &phase);
                     }
                 }
+                let _ = reply.send(result);
+            }
+            WarmRequest::Unplugged => {
+                if let Some(lost) = streaming.take() {
+                    lost.stop(&settings.name, &BlockingPhase::new("stopping RealSense pipeline"));
+                }
+            }
+            WarmRequest::Replugged { phase, reply } => {
+                // The old pipeline lost its device even if the unplug was missed.
+                if let Some(lost) = streaming.take() {
+                    lost.stop(&settings.name, &phase);
+                }
+                let result = ensure_streaming(&mut streaming, &settings, &phase).map(|_| ());
+                let _ = reply.send(result);
+            }
+        }
+    }
+    if let Some(streaming) = streaming.take() {
+        streaming.stop(&settings.name, &BlockingPhase::new("stopping RealSense pipeline"));
+    }
+}
+
+/// Starts the pipeline unless it is already streaming; returns how long starting took.
+fn ensure_streaming<'a>(streaming: &'a mut Option<Streaming>, settings: &CaptureSettings, phase: &BlockingPhase) -> Result<(&'a mut Streaming, Option<StdDuration>)> {
+    let mut started_in = None;
+    if streaming.is_none() {
+        let started = start_streaming(settings, phase, None)?;
+        started_in = Some(started.started_in);
+        *streaming = Some(started);
+    }
+    Ok((streaming.as_mut().expect("pipeline is streaming"), started_in))
+}
+
+fn capture_warm_frameset(streaming: &mut Option<Streaming>, settings: &CaptureSettings, request: &SaveRequest, phase: &BlockingPhase) -> Result<FrameDataBundle> {
+    let (streaming, started_in) = ensure_streaming(streaming, settings, phase)?;
+    if started_in.is_none() {
+        let stale = (0..MAX_STALE_FRAMESETS).take_while(|_| matches!(streaming.pipeline.poll(), Ok(Some(_)))).count();
+        debug!("RS [{}]: Dropped {} queued frameset(s).", settings.name, stale);
+    }
+    let timings = CaptureTimings { connect_ms: started_in.and_then(CaptureTimings::ms), ..Default::default() };
+    capture_frameset(streaming, settings, request, phase, timings)
+}
 
-                if processed_color_data.is_none() && processed_depth_data.is_none() && (color_stream_actually_enabled || depth_stream_actually_enabled) {
-                     let mut missing_streams = Vec::new();
-                     if color_stream_actually_enabled { missing_streams.push("color"); }
-                     if depth_stream_actually_enabled { missing_streams.push("depth"); }
-                    return Err(anyhow!("RS [{}]: No {} data was successfully captured from frameset despite being enabled.", name_clone, missing_streams.join(" or ")));
+/// A started pipeline and the USB bandwidth reserved for it (none for a `.bag` playback).
+struct Streaming {
+    pipeline: RsActivePipeline,
+    reservation: Option<BandwidthReservation>,
+    color_enabled: bool,
+    depth_enabled: bool,
+    usb_context: String,
+    started_in: StdDuration,
+}
+
+impl Streaming {
+    fn stop(self, name: &str, phase: &BlockingPhase) {
+        phase.enter("stopping RealSense pipeline");
+        info!("RS Blocking [{}]: Stopping pipeline...", name);
+        self.pipeline.stop();
+        info!("RS Blocking [{}]: Pipeline stopped.", name);
+        // Release the USB bandwidth only once the pipeline is no longer streaming.
+        drop(self.reservation);
+    }
+}
+
+/// Finds the configured device, reserves its USB bandwidth, applies its options and starts the
+/// pipeline, recording to `record_to` if given. A camera with a `bag_file` starts a playback of
+/// it instead.
+fn start_streaming(settings: &CaptureSettings, phase: &BlockingPhase, record_to: Option<&Path>) -> Result<Streaming> {
+    let name = settings.name.as_str();
+    let config = &settings.config;
+    if let Some(bag) = &config.bag_file {
+        return start_playback(settings, phase, Path::new(bag));
+    }
+    phase.enter("enumerating RealSense devices");
+    let context = RsContext::new().context("RS: Failed to create Realsense context")?;
+    let device_list = context.query_devices(HashSet::new());
+    if device_list.is_empty() {
+        return Err(anyhow!("RS [{}]: No Realsense devices found.", name));
+    }
+
+    let device_serial_to_use: String;
+    let usb_type_descriptor: Option<String>;
+    let physical_port: Option<String>;
+
+    if let Some(serial_to_find) = &config.serial_number {
+        info!("RS [{}]: Searching for device S/N: {}", name, serial_to_find);
+        let found_device = device_list.iter().find(|dev| {
+            dev.info(Rs2CameraInfo::SerialNumber)
+                .and_then(|cstr| cstr.to_str().ok())
+                .is_some_and(|s| s == serial_to_find.as_str())
+        });
+
+        if let Some(dev) = found_device {
+            let sn_cstr = dev.info(Rs2CameraInfo::SerialNumber)
+                .ok_or_else(|| anyhow!("RS [{}]: Failed to get S/N CString for found device S/N '{}'", name, serial_to_find))?;
+            device_serial_to_use = sn_cstr.to_str()
+                .map_err(|e| anyhow!("RS [{}]: Failed to convert S/N CString to str for found device: {}", name, e))?
+                .to_string();
+            usb_type_descriptor = dev.info(Rs2CameraInfo::UsbTypeDescriptor).and_then(|c| c.to_str().ok()).map(|s| s.to_string());
+            physical_port = dev.info(Rs2CameraInfo::PhysicalPort).and_then(|c| c.to_str().ok()).map(|s| s.to_string());
+            info!("RS [{}]: Found target device S/N: {}", name, device_serial_to_use);
+        } else {
+            return Err(anyhow!("RS [{}]: Specified device S/N '{}' not found.", name, serial_to_find));
+        }
+    } else {
+        info!("RS [{}]: No S/N specified, using first available device.", name);
+        if let Some(dev) = device_list.first() {
+            let sn_cstr = dev.info(Rs2CameraInfo::SerialNumber)
+                .ok_or_else(|| anyhow!("RS [{}]: Failed to get S/N CString for first available device", name))?;
+            device_serial_to_use = sn_cstr.to_str()
+                .map_err(|e| anyhow!("RS [{}]: Failed to convert S/N CString to str for first device: {}", name, e))?
+                .to_string();
+            usb_type_descriptor = dev.info(Rs2CameraInfo::UsbTypeDescriptor).and_then(|c| c.to_str().ok()).map(|s| s.to_string());
+            physical_port = dev.info(Rs2CameraInfo::PhysicalPort).and_then(|c| c.to_str().ok()).map(|s| s.to_string());
+            info!("RS [{}]: Using first device S/N: {}", name, device_serial_to_use);
+        } else {
+            return Err(anyhow!("RS [{}]: Device list was empty when attempting to use first device (unexpected).", name));
+        }
+    }
+
+    let controller_id = physical_port.as_deref()
+        .map(realsense_bandwidth::controller_id_from_port)
+        .unwrap_or_else(|| format!("unknown-controller-{}", device_serial_to_use));
+    let usb_context = format!("USB {} on controller '{}'", usb_type_descriptor.as_deref().unwrap_or("unknown"), controller_id);
+    let required_mbps = config.estimated_bandwidth_mbps();
+    info!("RS [{}]: Device is on {} and needs ~{:.0} Mbit/s.", name, usb_context, required_mbps);
+    let reservation = realsense_bandwidth::reserve_bandwidth(
+        name, &controller_id, usb_type_descriptor.as_deref(), required_mbps)?;
+    
+    let options = RealsenseOptions::parse(&RealsenseOptionsConfig::from_camera(config))
+        .with_context(|| format!("RS [{}]: Invalid device options", name))?;
+    if !options.is_empty() {
+        phase.enter("applying RealSense device options");
+        realsense_options::apply(&context, name, &device_serial_to_use, &options, settings.reconnect_wait)?;
+    }
+
+    let inactive_pipeline = RsInactivePipeline::try_from(&context)
+        .context("RS: Failed to create inactive pipeline from context")?;
+    
+    let mut rs_pipeline_config = RsConfig::new();
+    let c_device_serial = CString::new(device_serial_to_use.clone())
+        .with_context(|| format!("RS [{}]: Failed to create CString from serial: {}", name, device_serial_to_use))?;
+    
+    rs_pipeline_config.enable_device_from_serial(c_device_serial.as_c_str())
+        .with_context(|| format!("RS [{}]: Failed to enable device S/N '{}' in config", name, device_serial_to_use))?;
+    
+    rs_pipeline_config.disable_all_streams()
+        .context("RS: Failed to disable all streams in config")?;
+    if let Some(path) = record_to {
+        let c_path = CString::new(path.to_string_lossy().into_owned())
+            .with_context(|| format!("RS [{}]: Invalid recording path {}", name, path.display()))?;
+        rs_pipeline_config.enable_record_to_file(c_path.as_c_str())
+            .with_context(|| format!("RS [{}]: Failed to enable recording to {}", name, path.display()))?;
+    }
+
+    let mut color_enabled = false;
+    if config.enable_color_stream.unwrap_or(true) {
+        let w = config.color_width.unwrap_or(640);
+        let h = config.color_height.unwrap_or(480);
+        let fps = config.color_fps.unwrap_or(30);
+        rs_pipeline_config.enable_stream(Rs2StreamKind::Color, None, w as usize, h as usize, Rs2Format::Bgr8, fps as usize)
+            .with_context(|| format!("RS [{}]: Failed to enable color stream ({}x{}@{} BGR8)", name, w, h, fps))?;
+        color_enabled = true;
+        info!("RS [{}]: Color stream configured ({}x{}@{}fps BGR8).", name, w, h, fps);
+    }
+
+    let mut depth_enabled = false;
+    if config.enable_depth_stream.unwrap_or(true) {
+        let w = config.depth_width.unwrap_or(640);
+        let h = config.depth_height.unwrap_or(480);
+        let fps = config.depth_fps.unwrap_or(30);
+        rs_pipeline_config.enable_stream(Rs2StreamKind::Depth, None, w as usize, h as usize, Rs2Format::Z16, fps as usize)
+            .with_context(|| format!("RS [{}]: Failed to enable depth stream ({}x{}@{} Z16)", name, w, h, fps))?;
+        depth_enabled = true;
+        info!("RS [{}]: Depth stream configured ({}x{}@{}fps Z16).", name, w, h, fps);
+    }
+
+    if !color_enabled && !depth_enabled {
+        return Err(anyhow!("RS [{}]: Both color and depth streams are disabled.", name));
+    }
+
+    phase.enter("waiting for a pipeline start slot");
+    realsense_bandwidth::wait_for_start_slot(name, settings.start_stagger);
+    phase.enter(&format!("starting RealSense pipeline ({})", usb_context));
+    info!("RS [{}]: Starting pipeline for S/N {}...", name, device_serial_to_use);
+    let connect_start = std::time::Instant::now();
+    let pipeline = inactive_pipeline.start(Some(rs_pipeline_config))
+        .with_context(|| format!("RS [{}]: Failed to start pipeline ({})", name, usb_context))?;
+    Ok(Streaming { pipeline, reservation: Some(reservation), color_enabled, depth_enabled, usb_context, started_in: connect_start.elapsed() })
+}
+
+/// Starts a looping playback of a recorded `.bag`. Its frames go through the same saving as a
+/// device's; the recorded streams must have the formats a capture enables (BGR8 color, Z16 depth).
+fn start_playback(settings: &CaptureSettings, phase: &BlockingPhase, bag: &Path) -> Result<Streaming> {
+    let name = settings.name.as_str();
+    let config = &settings.config;
+    phase.enter(&format!("starting playback of {}", bag.display()));
+    let context = RsContext::new().context("RS: Failed to create Realsense context")?;
+    let inactive_pipeline = RsInactivePipeline::try_from(&context)
+        .context("RS: Failed to create inactive pipeline from context")?;
+    let mut rs_pipeline_config = RsConfig::new();
+    let c_path = CString::new(bag.to_string_lossy().into_owned())
+        .with_context(|| format!("RS [{}]: Invalid bag_file path {}", name, bag.display()))?;
+    rs_pipeline_config.enable_device_from_file_repeat_option(c_path.as_c_str(), true)
+        .with_context(|| format!("RS [{}]: Failed to open {} for playback", name, bag.display()))?;
+    rs_pipeline_config.disable_all_streams()
+        .context("RS: Failed to disable all streams in config")?;
+
+    // Any resolution and rate: the recording decides.
+    let color_enabled = config.enable_color_stream.unwrap_or(true);
+    if color_enabled {
+        rs_pipeline_config.enable_stream(Rs2StreamKind::Color, None, 0, 0, Rs2Format::Bgr8, 0)
+            .with_context(|| format!("RS [{}]: Failed to enable the recorded color stream (BGR8)", name))?;
+    }
+    let depth_enabled = config.enable_depth_stream.unwrap_or(true);
+    if depth_enabled {
+        rs_pipeline_config.enable_stream(Rs2StreamKind::Depth, None, 0, 0, Rs2Format::Z16, 0)
+            .with_context(|| format!("RS [{}]: Failed to enable the recorded depth stream (Z16)", name))?;
+    }
+    if !color_enabled && !depth_enabled {
+        return Err(anyhow!("RS [{}]: Both color and depth streams are disabled.", name));
+    }
+
+    info!("RS [{}]: Replaying {}...", name, bag.display());
+    let connect_start = std::time::Instant::now();
+    let usb_context = format!("playback of {}", bag.display());
+    let pipeline = inactive_pipeline.start(Some(rs_pipeline_config))
+        .with_context(|| format!("RS [{}]: Failed to start {} (recorded without BGR8 color or Z16 depth?)", name, usb_context))?;
+    Ok(Streaming { pipeline, reservation: None, color_enabled, depth_enabled, usb_context, started_in: connect_start.elapsed() })
+}
+
+/// Waits for the next frameset and saves its color and depth frames.
+fn capture_frameset(streaming: &mut Streaming, settings: &CaptureSettings, request: &SaveRequest, phase: &BlockingPhase, mut timings: CaptureTimings) -> Result<FrameDataBundle> {
+    let name = settings.name.as_str();
+    let first_frame_start = std::time::Instant::now();
+
+    phase.enter("waiting for the first frameset");
+    info!("RS [{}]: Waiting for frameset...", name);
+    let frameset: CompositeFrame = streaming.pipeline.wait(Some(StdDuration::from_secs(5)))
+        .with_context(|| format!(
+            "RS [{}]: No frames within 5s ({}). If several RealSense devices share this controller it may be saturated; lower resolution/fps or increase realsense_start_stagger_ms",
+            name, streaming.usb_context
+        ))?;
+    info!("RS [{}]: Frameset received with {} frames (API count).", name, frameset.count());
+
+    timings.first_frame_ms = CaptureTimings::ms(first_frame_start.elapsed());
+
+    phase.enter("converting and saving frames");
+    let captured_at = chrono::Utc::now();
+    let write_start = std::time::Instant::now();
+    let mut processed_color_data: Option<RsColorFrameData> = None;
+    let mut processed_depth_data: Option<RsDepthFrameData> = None;
+    let mut saved_paths = Vec::new();
+
+    if streaming.color_enabled {
+        let color_frames: Vec<ColorFrame> = frameset.frames_of_type::<ColorFrame>();
+        if let Some(color_frame) = color_frames.first() {
+            let profile: &StreamProfile = color_frame.stream_profile();
+            info!("RS [{}]: Processing ColorFrame. Format: {:?}, Res: {}x{}, BPP: {}, TS: {}, Domain: {:?}", 
+                name, profile.format(), color_frame.width(), color_frame.height(), 
+                color_frame.bits_per_pixel(), color_frame.timestamp(), color_frame.timestamp_domain());
+
+            let width = color_frame.width() as u32;
+            let height = color_frame.height() as u32;
+            let bpp_usize = color_frame.bits_per_pixel() / 8;
+            if bpp_usize != 3 {
+                return Err(anyhow!("RS [{}]: Color frame BPP is {}, expected 3 (BGR8).", name, bpp_usize));
+            }
+            let data_size = width as usize * height as usize * bpp_usize;
+            let raw_data_ptr: *const std::os::raw::c_void = unsafe { color_frame.get_data() };
+            let color_data_slice = unsafe { std::slice::from_raw_parts(raw_data_ptr as *const u8, data_size) };
+
+            // Post-processed pixels are read straight from the processed Mat rather than copied out first.
+            let processed = if settings.post_process_steps.is_empty() {
+                None
+            } else {
+                let bgr = post_process::mat_from_slice(color_data_slice, height as i32, 3)?;
+                let overlay = OverlayInfo { camera_name: name, captured_at };
+                Some(post_process::apply(&settings.post_process_steps, &bgr, Some(&overlay))
+                    .with_context(|| format!("RS [{}]: Color post-processing failed", name))?)
+            };
+            let (color_data_slice, width, height): (&[u8], u32, u32) = match &processed {
+                None => (color_data_slice, width, height),
+                Some(processed) => (processed.data_bytes()?, processed.cols() as u32, processed.rows() as u32),
+            };
+
+            // The one copy of the frame: BGR to RGB into the buffer shared with the Rerun logger.
+            let mut rgb_pixel_data = Vec::with_capacity(color_data_slice.len());
+            for chunk in color_data_slice.chunks_exact(3) {
+                rgb_pixel_data.push(chunk[2]);
+                rgb_pixel_data.push(chunk[1]);
+                rgb_pixel_data.push(chunk[0]);
+            }
+
+            let color_filename = format!("{}_realsense_{}_color.{}", request.timestamp_str, name.replace(" ", "_"), request.color_format);
+            let color_path = request.output_dir.join(&color_filename);
+            let color_path = atomic_file::write_with(&color_path, |part| {
+                if request.color_format == "png" {
+                    Ok(image::save_buffer_with_format(part, &rgb_pixel_data, width, height, image::ColorType::Rgb8, image::ImageFormat::Png)?)
+                } else {
+                    let bgr = post_process::mat_from_slice(color_data_slice, height as i32, 3)?;
+                    frame_export::write_mat(part, &bgr, &request.color_format, None, None)
                 }
+            }).with_context(|| format!("RS [{}]: Failed to save color image to {:?}", name, color_path))?;
+            info!("RS [{}]: Saved color image to {:?}", name, color_path);
+            saved_paths.push(color_path);
+            // Crops, resizes and rotations change the camera model, so intrinsics are only kept for unprocessed frames.
+            let intrinsics = if settings.post_process_steps.is_empty() { stream_intrinsics(profile) } else { None };
+            processed_color_data = Some(RsColorFrameData { rgb_data: rgb_pixel_data.into(), width, height, intrinsics });
+        } else {
+             warn!("RS [{}]: Color stream enabled, but no ColorFrame found in frameset.", name);
+        }
+    }
 
-                Ok(FrameDataBundle {
-                    frames: vec![FrameData::RealsenseFrames { name: name_clone.clone(), color_frame: processed_color_data, depth_frame: processed_depth_data }],
-                })
-            })();
+    if streaming.depth_enabled {
+        let depth_frames: Vec<DepthFrame> = frameset.frames_of_type::<DepthFrame>();
+        if let Some(depth_frame) = depth_frames.first() {
+            let profile: &StreamProfile = depth_frame.stream_profile();
+            let current_depth_units = depth_frame.depth_units()
+                .context("RS: Failed to get depth units")?;
+            info!("RS [{}]: Processing DepthFrame. Format: {:?}, Res: {}x{}, BPP: {}, TS: {}, Domain: {:?}, Units: {}",
+                name, profile.format(), depth_frame.width(), depth_frame.height(),
+                depth_frame.bits_per_pixel(), depth_frame.timestamp(), depth_frame.timestamp_domain(), current_depth_units);
 
-            if let Some(pipeline_to_stop) = active_pipeline_opt.take() {
-                info!("RS Blocking [{}]: Stopping pipeline...", name_clone);
-                pipeline_to_stop.stop();
-                info!("RS Blocking [{}]: Pipeline stopped.", name_clone);
+            let width = depth_frame.width() as u32;
+            let height = depth_frame.height() as u32;
+            let bpp_usize = depth_frame.bits_per_pixel() / 8;
+            if bpp_usize != 2 {
+                return Err(anyhow!("RS [{}]: Depth frame BPP is {}, expected 2 (Z16).", name, bpp_usize));
+            }
+            let data_size_pixels = width as usize * height as usize;
+            let raw_data_ptr: *const std::os::raw::c_void = unsafe { depth_frame.get_data() };
+            let depth_data_slice_u16 = unsafe { std::slice::from_raw_parts(raw_data_ptr as *const u16, data_size_pixels) };
+            // Only geometric steps apply to depth (no overlay passed), keeping color and depth aligned.
+            let (depth_data_slice_u16, width, height): (std::borrow::Cow<[u16]>, u32, u32) = if settings.post_process_steps.is_empty() {
+                (depth_data_slice_u16.into(), width, height)
+            } else {
+                let depth = post_process::mat_from_slice(depth_data_slice_u16, height as i32, 1)?;
+                let processed = post_process::apply(&settings.post_process_steps, &depth, None)
+                    .with_context(|| format!("RS [{}]: Depth post-processing failed", name))?;
+                (processed.data_typed::<u16>()?.to_vec().into(), processed.cols() as u32, processed.rows() as u32)
+            };
+            
+            let depth_filename = format!("{}_realsense_{}_depth.{}", request.timestamp_str, name.replace(" ", "_"), settings.depth_format);
+            let depth_path = request.output_dir.join(&depth_filename);
+
+            let depth_path = atomic_file::write_with(&depth_path, |part| {
+                frame_export::write_depth(part, &depth_data_slice_u16, width, height, current_depth_units, &settings.depth_format)
+            }).with_context(|| format!("RS [{}]: Failed to save depth image to {:?}", name, depth_path))?;
+            info!("RS [{}]: Saved depth image to {:?}", name, depth_path);
+            saved_paths.push(depth_path);
+            if let Some(preview) = &settings.config.depth_preview {
+                // Only for review: a failed preview is logged, the raw depth is what counts.
+                let preview_path = request.output_dir.join(format!("{}_realsense_{}_depth_preview.png", request.timestamp_str, name.replace(" ", "_")));
+                match atomic_file::write_with(&preview_path, |part| {
+                    frame_export::write_depth_preview(part, &depth_data_slice_u16, height, current_depth_units, preview)
+                }) {
+                    Ok(preview_path) => {
+                        debug!("RS [{}]: Saved depth preview to {:?}", name, preview_path);
+                        saved_paths.push(preview_path);
+                    }
+                    Err(e) => warn!("RS [{}]: Failed to save depth preview to {:?}: {:#}", name, preview_path, e),
+                }
             }
-            info!("RS Blocking [{}]: Task finished.", name_clone);
-            frame_data_bundle_result
-        }).await.map_err(|e| anyhow!("Realsense [{}]: spawn_blocking task panicked: {}", self.name, e))?
+            let (intrinsics, depth_to_color) = if settings.post_process_steps.is_empty() {
+                let depth_to_color = frameset.frames_of_type::<ColorFrame>().first()
+                    .and_then(|color_frame| profile.extrinsics(color_frame.stream_profile()).ok())
+                    .map(|e| RsExtrinsics { rotation: e.rotation(), translation: e.translation() });
+                (stream_intrinsics(profile), depth_to_color)
+            } else {
+                (None, None)
+            };
+            processed_depth_data = Some(RsDepthFrameData {
+                depth_data: depth_data_slice_u16.into_owned().into(), // post-processed depth is moved, not copied
+                depth_units: current_depth_units,
+                width,
+                height,
+                intrinsics,
+                depth_to_color,
+            });
+        } else {
+            warn!("RS [{}]: Depth stream enabled, but no DepthFrame found in frameset.", name);
+        }
     }
-}
\ No newline at end of file
+
+    if processed_color_data.is_none() && processed_depth_data.is_none() && (streaming.color_enabled || streaming.depth_enabled) {
+         let mut missing_streams = Vec::new();
+         if streaming.color_enabled { missing_streams.push("color"); }
+         if streaming.depth_enabled { missing_streams.push("depth"); }
+        return Err(anyhow!("RS [{}]: No {} data was successfully captured from frameset despite being enabled.", name, missing_streams.join(" or ")));
+    }
+
+    timings.write_ms = CaptureTimings::ms(write_start.elapsed());
+    Ok(FrameDataBundle {
+        frames: vec![FrameData::RealsenseFrames { name: name.to_string(), color_frame: processed_color_data, depth_frame: processed_depth_data, paths: saved_paths }],
+        captured_at,
+        timings,
+    })
+}
+
+/// Whether `path` is a RealSense recording rather than a video file.
+pub fn is_bag(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(BAG_EXTENSION))
+}
+
+fn stream_intrinsics(profile: &StreamProfile) -> Option<RsIntrinsics> {
+    profile.intrinsics().ok().map(|i| RsIntrinsics { fx: i.fx(), fy: i.fy(), ppx: i.ppx(), ppy: i.ppy() })
+}
@@ -0,0 +1,235 @@
+//! RealSense hot-plug notifications. One librealsense context per process registers a
+//! devices-changed callback (`rs2_set_devices_changed_callback`); whenever a device comes or goes,
+//! the registered cameras are matched against the devices now present, their connection state
+//! is updated and a `CameraConnected` / `CameraDisconnected` event is published. Captures wait on
+//! this state instead of polling the device list.
+
+use crate::core::events::{self, Event};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+use realsense_rust::{context::Context as RsContext, kind::Rs2CameraInfo};
+use realsense_sys as sys;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::sync::{mpsc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Serials of the devices present, and the registered cameras' state.
+struct State {
+    present: BTreeSet<String>,
+    cameras: BTreeMap<String, Tracked>,
+}
+
+struct Tracked {
+    serial: Option<String>, // None: the first device found
+    connected: bool,
+    plugs: u64, // times the device was plugged back in
+}
+
+static STATE: Mutex<State> = Mutex::new(State { present: BTreeSet::new(), cameras: BTreeMap::new() });
+
+// Whether the watcher thread is running, decided on first use.
+static WATCHING: OnceLock<bool> = OnceLock::new();
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Bumped after every device change, once the cameras' state is updated.
+fn changes() -> &'static watch::Sender<u64> {
+    static CHANGES: OnceLock<watch::Sender<u64>> = OnceLock::new();
+    CHANGES.get_or_init(|| watch::channel(0).0)
+}
+
+/// A registered camera's view of its device coming and going.
+#[derive(Debug)]
+pub struct Presence {
+    camera: String,
+    changes: watch::Receiver<u64>,
+    seen: (bool, u64), // connection state and plug count last returned by `next_change`
+}
+
+impl Presence {
+    pub fn is_connected(&self) -> bool {
+        self.current().0
+    }
+
+    fn current(&self) -> (bool, u64) {
+        state().cameras.get(&self.camera).map_or((true, 0), |c| (c.connected, c.plugs))
+    }
+
+    /// Waits up to `timeout` for the device to be plugged in; false if it was not.
+    pub async fn wait_connected(&mut self, timeout: Duration) -> bool {
+        let plugged = async {
+            while !self.is_connected() {
+                if self.changes.changed().await.is_err() {
+                    return false;
+                }
+            }
+            true
+        };
+        tokio::time::timeout(timeout, plugged).await.unwrap_or(false)
+    }
+
+    /// Waits until the device is unplugged or plugged back in, and returns whether it is
+    /// connected now. An unplug and re-plug between two calls is reported as a connect.
+    pub async fn next_change(&mut self) -> Option<bool> {
+        loop {
+            let current = self.current();
+            if current != self.seen {
+                self.seen = current;
+                return Some(current.0);
+            }
+            self.changes.changed().await.ok()?;
+        }
+    }
+}
+
+/// Registers `camera` (the device with `serial`, or the first found) for hot-plug tracking,
+/// starting the watcher on first use. None when librealsense cannot report device changes.
+pub fn register(camera: &str, serial: Option<&str>) -> Option<Presence> {
+    if !ensure_watching() {
+        return None;
+    }
+    let changes = changes().subscribe();
+    let seen = {
+        let mut state = state();
+        let connected = is_present(&state.present, serial);
+        let tracked = state.cameras.entry(camera.to_string())
+            .or_insert(Tracked { serial: serial.map(str::to_string), connected, plugs: 0 });
+        (tracked.connected, tracked.plugs)
+    };
+    Some(Presence { camera: camera.to_string(), changes, seen })
+}
+
+/// Whether `camera`'s device is plugged in; None unless it is a RealSense registered for
+/// hot-plug tracking.
+pub fn is_connected(camera: &str) -> Option<bool> {
+    state().cameras.get(camera).map(|c| c.connected)
+}
+
+fn is_present(present: &BTreeSet<String>, serial: Option<&str>) -> bool {
+    match serial {
+        Some(serial) => present.contains(serial),
+        None => !present.is_empty(),
+    }
+}
+
+fn ensure_watching() -> bool {
+    *WATCHING.get_or_init(|| {
+        let (ready, started) = mpsc::channel();
+        let result = std::thread::Builder::new()
+            .name("rcam-rs-hotplug".to_string())
+            .spawn(move || watch_devices(ready))
+            .context("Failed to start the hot-plug thread")
+            .and_then(|_| started.recv().unwrap_or_else(|_| Err(anyhow!("the hot-plug thread exited"))));
+        match result {
+            Ok(()) => {
+                info!("🔌 Watching for RealSense devices being plugged and unplugged.");
+                true
+            }
+            Err(e) => {
+                warn!("⚠️ RealSense hot-plug notifications are unavailable ({:#}); an unplugged device only shows as failed captures.", e);
+                false
+            }
+        }
+    })
+}
+
+/// Body of the watcher thread: registers the callback, then refreshes the cameras' state each
+/// time librealsense reports a change, for the rest of the process.
+fn watch_devices(ready: mpsc::Sender<Result<()>>) {
+    let (notify, notified) = mpsc::channel();
+    let setup = callback::register(notify).and_then(|()| RsContext::new().context("Failed to create a RealSense context"));
+    let context = match setup {
+        Ok(context) => context,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    refresh(&context);
+    let _ = ready.send(Ok(()));
+    while notified.recv().is_ok() {
+        // A hub being plugged in reports each of its devices; one query covers them all.
+        while notified.try_recv().is_ok() {}
+        refresh(&context);
+    }
+}
+
+/// Re-queries the devices present and publishes each registered camera's transition.
+fn refresh(context: &RsContext) {
+    let present: BTreeSet<String> = context.query_devices(HashSet::new()).iter()
+        .filter_map(|device| device.info(Rs2CameraInfo::SerialNumber).and_then(|s| s.to_str().ok()).map(str::to_string))
+        .collect();
+    debug!("🔌 RealSense devices present: {:?}", present);
+    let mut transitions = Vec::new();
+    {
+        let mut state = state();
+        for (name, camera) in state.cameras.iter_mut() {
+            let connected = is_present(&present, camera.serial.as_deref());
+            if connected != camera.connected {
+                camera.connected = connected;
+                camera.plugs += connected as u64;
+                transitions.push((name.clone(), connected));
+            }
+        }
+        state.present = present;
+    }
+    for (camera, connected) in transitions {
+        if connected {
+            info!("🔌 RS [{}]: Device plugged in.", camera);
+            events::publish(Event::CameraConnected { camera, at: Utc::now() });
+        } else {
+            warn!("🔌 RS [{}]: Device unplugged.", camera);
+            events::publish(Event::CameraDisconnected { camera, at: Utc::now() });
+        }
+    }
+    changes().send_modify(|generation| *generation += 1);
+}
+
+/// The librealsense side: realsense-rust does not wrap the devices-changed callback.
+mod callback {
+    use super::*;
+
+    /// Creates a context that lives for the rest of the process and has librealsense call
+    /// `on_devices_changed` on every change, which sends on `notify`.
+    pub fn register(notify: mpsc::Sender<()>) -> Result<()> {
+        let mut error = std::ptr::null_mut();
+        // SAFETY: plain constructor; the context is never deleted, as the callback needs it.
+        let context = unsafe { sys::rs2_create_context(sys::RS2_API_VERSION as i32, &mut error) };
+        check(error, "creating the hot-plug context")?;
+        // Leaked like the context: librealsense may call back at any time until the process exits.
+        let user = Box::into_raw(Box::new(notify)).cast::<c_void>();
+        // SAFETY: `context` is valid, and `user` points to a sender that is never freed.
+        unsafe { sys::rs2_set_devices_changed_callback(context, Some(on_devices_changed), user, &mut error) };
+        check(error, "registering the devices-changed callback")
+    }
+
+    unsafe extern "C" fn on_devices_changed(removed: *mut sys::rs2_device_list, added: *mut sys::rs2_device_list, user: *mut c_void) {
+        // SAFETY: `user` is the sender leaked by `register`. The callee owns both lists and must
+        // delete them; the watcher re-queries the devices instead of reading them.
+        unsafe {
+            sys::rs2_delete_device_list(removed);
+            sys::rs2_delete_device_list(added);
+        }
+        let notify = unsafe { &*user.cast::<mpsc::Sender<()>>() };
+        let _ = notify.send(());
+    }
+
+    fn check(error: *mut sys::rs2_error, what: &str) -> Result<()> {
+        if error.is_null() {
+            return Ok(());
+        }
+        // SAFETY: a non-null rs2_error is owned by us until freed.
+        let message = unsafe {
+            let message = CStr::from_ptr(sys::rs2_get_error_message(error)).to_string_lossy().into_owned();
+            sys::rs2_free_error(error);
+            message
+        };
+        bail!("librealsense: {} failed: {}", what, message)
+    }
+}
@@ -0,0 +1,306 @@
+//! Depth sensor options set on a RealSense device before its pipeline starts: a visual preset
+//! (built-in, or a RealSense Viewer JSON loaded through advanced mode), laser power, the IR
+//! emitter, exposure and the inter-camera sync mode. Unset options keep whatever the device has.
+
+use crate::camera::imaging::{self, ExposureRequest};
+use crate::config_loader::RealsenseSpecificConfig;
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info};
+use realsense_rust::{
+    context::Context as RsContext,
+    device::Device,
+    kind::{Rs2CameraInfo, Rs2Option},
+    sensor::Sensor,
+};
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// D400 built-in presets (`rs2_rs400_visual_preset`).
+const BUILTIN_PRESETS: &[(&str, f32)] = &[
+    ("custom", 0.0),
+    ("default", 1.0),
+    ("hand", 2.0),
+    ("high_accuracy", 3.0),
+    ("high_density", 4.0),
+    ("medium_density", 5.0),
+];
+
+/// D4xx inter-camera sync modes (`RS2_OPTION_INTER_CAM_SYNC_MODE`). A master drives the sync
+/// cable; slaves and genlocked units follow its trigger.
+pub const SYNC_MODES: &[(&str, f32)] = &[
+    ("default", 0.0),
+    ("master", 1.0),
+    ("slave", 2.0),
+    ("full_slave", 3.0),
+    ("genlock", 4.0),
+];
+
+/// A device switching into advanced mode restarts; allow at least this long to re-enumerate.
+const ADVANCED_MODE_RESTART: Duration = Duration::from_secs(10);
+
+/// Requested options in config syntax, from a camera's config or `rcam set` flags.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RealsenseOptionsConfig {
+    pub visual_preset: Option<String>,
+    pub laser_power: Option<f32>,
+    pub emitter_enabled: Option<bool>,
+    pub exposure: Option<String>,
+    pub inter_cam_sync_mode: Option<String>,
+}
+
+impl RealsenseOptionsConfig {
+    pub fn from_camera(config: &RealsenseSpecificConfig) -> Self {
+        RealsenseOptionsConfig {
+            visual_preset: config.visual_preset.clone(),
+            laser_power: config.laser_power,
+            emitter_enabled: config.emitter_enabled,
+            exposure: config.exposure.clone(),
+            inter_cam_sync_mode: config.inter_cam_sync_mode.clone(),
+        }
+    }
+
+    /// Each option from `self`, else from `fallback`.
+    pub fn or(self, fallback: Self) -> Self {
+        RealsenseOptionsConfig {
+            visual_preset: self.visual_preset.or(fallback.visual_preset),
+            laser_power: self.laser_power.or(fallback.laser_power),
+            emitter_enabled: self.emitter_enabled.or(fallback.emitter_enabled),
+            exposure: self.exposure.or(fallback.exposure),
+            inter_cam_sync_mode: self.inter_cam_sync_mode.or(fallback.inter_cam_sync_mode),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VisualPreset {
+    Builtin { name: &'static str, value: f32 },
+    Json(PathBuf),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RealsenseOptions {
+    pub preset: Option<VisualPreset>,
+    pub laser_power: Option<f32>,
+    pub emitter_enabled: Option<bool>,
+    pub exposure: Option<ExposureRequest>,
+    pub sync_mode: Option<(&'static str, f32)>,
+}
+
+impl RealsenseOptions {
+    pub fn parse(config: &RealsenseOptionsConfig) -> Result<Self> {
+        let preset = match config.visual_preset.as_deref() {
+            None => None,
+            Some(value) => match BUILTIN_PRESETS.iter().find(|(name, _)| name.eq_ignore_ascii_case(value.trim())) {
+                Some((name, preset)) => Some(VisualPreset::Builtin { name: *name, value: *preset }),
+                None if Path::new(value).is_file() => Some(VisualPreset::Json(PathBuf::from(value))),
+                None => bail!("Unknown visual_preset '{}' (use {} or a RealSense Viewer JSON file)", value,
+                    BUILTIN_PRESETS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")),
+            },
+        };
+        if let Some(power) = config.laser_power {
+            if !power.is_finite() || power < 0.0 {
+                bail!("laser_power must be 0 mW or more (got {})", power);
+            }
+        }
+        let sync_mode = config.inter_cam_sync_mode.as_deref().map(parse_sync_mode).transpose()?;
+        Ok(RealsenseOptions {
+            preset,
+            laser_power: config.laser_power,
+            emitter_enabled: config.emitter_enabled,
+            exposure: config.exposure.as_deref().map(imaging::parse_exposure).transpose()?,
+            sync_mode,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.preset.is_none() && self.laser_power.is_none() && self.emitter_enabled.is_none() && self.exposure.is_none()
+            && self.sync_mode.is_none()
+    }
+
+    /// Whether the device drives the sync cable for the others.
+    pub fn is_sync_master(&self) -> bool {
+        self.sync_mode.is_some_and(|(name, _)| name == "master")
+    }
+}
+
+fn parse_sync_mode(value: &str) -> Result<(&'static str, f32)> {
+    match SYNC_MODES.iter().find(|(name, _)| name.eq_ignore_ascii_case(value.trim())) {
+        Some((name, mode)) => Ok((*name, *mode)),
+        None => bail!("Unknown inter_cam_sync_mode '{}' (use {})", value,
+            SYNC_MODES.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Applies `options` to the device with `serial`. A JSON preset goes first, since it rewrites
+/// most depth settings; the individual options are set on top of it. Enabling advanced mode
+/// for a JSON preset restarts the device once; it is waited for (at least 10 s).
+pub fn apply(context: &RsContext, camera_name: &str, serial: &str, options: &RealsenseOptions, reconnect_wait: Duration) -> Result<()> {
+    let mut device = find_device(context, serial)
+        .ok_or_else(|| anyhow!("RS [{}]: Device S/N '{}' disappeared before its options could be set", camera_name, serial))?;
+
+    if let Some(VisualPreset::Json(path)) = &options.preset {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("RS [{}]: Failed to read visual preset {}", camera_name, path.display()))?;
+        if !advanced_mode::is_enabled(&device)? {
+            info!("RS [{}]: Enabling advanced mode for the JSON preset; the device restarts once.", camera_name);
+            advanced_mode::enable(&device)?;
+            drop(device);
+            device = wait_for_device(context, serial, reconnect_wait.max(ADVANCED_MODE_RESTART))
+                .with_context(|| format!("RS [{}]: Device did not come back after enabling advanced mode", camera_name))?;
+        }
+        advanced_mode::load_json(&device, &json)
+            .with_context(|| format!("RS [{}]: Failed to load visual preset {}", camera_name, path.display()))?;
+        info!("RS [{}]: Loaded visual preset {}.", camera_name, path.display());
+    }
+
+    let mut sensor = depth_sensor(&device)
+        .ok_or_else(|| anyhow!("RS [{}]: Device has no depth sensor to set options on", camera_name))?;
+    if let Some(VisualPreset::Builtin { name, value }) = &options.preset {
+        set_option(&mut sensor, camera_name, Rs2Option::VisualPreset, name, *value)?;
+    }
+    if let Some(enabled) = options.emitter_enabled {
+        set_option(&mut sensor, camera_name, Rs2Option::EmitterEnabled, "emitter_enabled", if enabled { 1.0 } else { 0.0 })?;
+    }
+    if let Some(power) = options.laser_power {
+        set_option(&mut sensor, camera_name, Rs2Option::LaserPower, "laser_power", power)?;
+    }
+    match &options.exposure {
+        Some(ExposureRequest::Auto) => set_option(&mut sensor, camera_name, Rs2Option::EnableAutoExposure, "auto exposure", 1.0)?,
+        Some(ExposureRequest::Manual { time_us }) => {
+            set_option(&mut sensor, camera_name, Rs2Option::EnableAutoExposure, "auto exposure", 0.0)?;
+            set_option(&mut sensor, camera_name, Rs2Option::Exposure, "exposure (us)", *time_us as f32)?;
+        }
+        None => {}
+    }
+    if let Some((name, mode)) = options.sync_mode {
+        set_option(&mut sensor, camera_name, Rs2Option::InterCamSyncMode, &format!("inter-camera sync mode ({})", name), mode)?;
+    }
+    Ok(())
+}
+
+/// The depth sensor's current values of the options rcam manages, for logging.
+pub fn describe(device: &Device) -> Vec<String> {
+    let Some(sensor) = depth_sensor(device) else {
+        return Vec::new();
+    };
+    let mut parts = Vec::new();
+    if let Some(value) = sensor.get_option(Rs2Option::VisualPreset) {
+        let name = BUILTIN_PRESETS.iter().find(|(_, preset)| *preset == value).map_or("unknown", |(name, _)| *name);
+        parts.push(format!("visual preset {}", name));
+    }
+    if let Some(value) = sensor.get_option(Rs2Option::EmitterEnabled) {
+        parts.push(format!("emitter {}", if value > 0.0 { "on" } else { "off" }));
+    }
+    if let Some(value) = sensor.get_option(Rs2Option::LaserPower) {
+        parts.push(format!("laser power {:.0} mW", value));
+    }
+    match sensor.get_option(Rs2Option::EnableAutoExposure) {
+        Some(auto) if auto > 0.0 => parts.push("exposure auto".to_string()),
+        _ => parts.extend(sensor.get_option(Rs2Option::Exposure).map(|us| format!("exposure {:.0} us", us))),
+    }
+    if let Some(value) = sensor.get_option(Rs2Option::InterCamSyncMode) {
+        // Genlock modes above 4 carry a burst count.
+        let name = SYNC_MODES.iter().rev().find(|(_, mode)| *mode <= value).map_or("unknown", |(name, _)| *name);
+        parts.push(format!("sync {}", name));
+    }
+    parts
+}
+
+/// Sets (unless `options` is None) and then reads a device's options, outside any pipeline.
+/// Used by `rcam set`; blocking.
+pub fn configure(camera_name: &str, serial: Option<&str>, options: Option<&RealsenseOptions>, reconnect_wait: Duration) -> Result<Vec<String>> {
+    let context = RsContext::new().context("RS: Failed to create Realsense context")?;
+    let serial = match serial {
+        Some(serial) => serial.to_string(),
+        None => context.query_devices(HashSet::new()).first()
+            .and_then(|device| device_serial(device))
+            .ok_or_else(|| anyhow!("RS [{}]: No Realsense devices found", camera_name))?,
+    };
+    if let Some(options) = options {
+        apply(&context, camera_name, &serial, options, reconnect_wait)?;
+    }
+    let device = find_device(&context, &serial)
+        .ok_or_else(|| anyhow!("RS [{}]: Device S/N '{}' not found", camera_name, serial))?;
+    Ok(describe(&device))
+}
+
+fn device_serial(device: &Device) -> Option<String> {
+    device.info(Rs2CameraInfo::SerialNumber).and_then(|cstr| cstr.to_str().ok()).map(str::to_string)
+}
+
+fn find_device(context: &RsContext, serial: &str) -> Option<Device> {
+    context.query_devices(HashSet::new()).into_iter().find(|device| device_serial(device).as_deref() == Some(serial))
+}
+
+fn wait_for_device(context: &RsContext, serial: &str, wait: Duration) -> Result<Device> {
+    let deadline = Instant::now() + wait;
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        if let Some(device) = find_device(context, serial) {
+            return Ok(device);
+        }
+        if Instant::now() >= deadline {
+            bail!("Device S/N '{}' not enumerated within {:?}", serial, wait);
+        }
+    }
+}
+
+/// The stereo module: the sensor that reports depth units.
+fn depth_sensor(device: &Device) -> Option<Sensor> {
+    device.sensors().into_iter().find(|sensor| sensor.supports_option(Rs2Option::DepthUnits))
+}
+
+fn set_option(sensor: &mut Sensor, camera_name: &str, option: Rs2Option, label: &str, value: f32) -> Result<()> {
+    if !sensor.supports_option(option) {
+        bail!("RS [{}]: The depth sensor does not support {}", camera_name, label);
+    }
+    sensor.set_option(option, value)
+        .map_err(|e| anyhow!("RS [{}]: Failed to set {} to {}: {}", camera_name, label, value, e))?;
+    debug!("RS [{}]: Set {} to {}.", camera_name, label, value);
+    Ok(())
+}
+
+/// librealsense's advanced-mode calls, which realsense-rust does not wrap.
+mod advanced_mode {
+    use super::*;
+    use realsense_sys as sys;
+    use std::os::raw::{c_int, c_uint};
+
+    fn check(error: *mut sys::rs2_error, what: &str) -> Result<()> {
+        if error.is_null() {
+            return Ok(());
+        }
+        // SAFETY: a non-null rs2_error is owned by us until freed.
+        let message = unsafe {
+            let message = CStr::from_ptr(sys::rs2_get_error_message(error)).to_string_lossy().into_owned();
+            sys::rs2_free_error(error);
+            message
+        };
+        bail!("librealsense: {} failed: {}", what, message)
+    }
+
+    pub fn is_enabled(device: &Device) -> Result<bool> {
+        let mut enabled: c_int = 0;
+        let mut error = std::ptr::null_mut();
+        // SAFETY: the device handle is valid for the lifetime of `device`.
+        unsafe { sys::rs2_is_enabled(device.get_raw().as_ptr(), &mut enabled, &mut error) };
+        check(error, "reading advanced mode")?;
+        Ok(enabled != 0)
+    }
+
+    pub fn enable(device: &Device) -> Result<()> {
+        let mut error = std::ptr::null_mut();
+        // SAFETY: as above. The device restarts afterwards, so the handle must not be reused.
+        unsafe { sys::rs2_toggle_advanced_mode(device.get_raw().as_ptr(), 1, &mut error) };
+        check(error, "enabling advanced mode")
+    }
+
+    pub fn load_json(device: &Device, json: &str) -> Result<()> {
+        let mut error = std::ptr::null_mut();
+        // SAFETY: librealsense copies the buffer during the call.
+        unsafe { sys::rs2_load_json(device.get_raw().as_ptr(), json.as_ptr().cast(), json.len() as c_uint, &mut error) };
+        check(error, "loading the JSON preset")
+    }
+}
@@ -0,0 +1,147 @@
+use crate::config_loader::{AppSettings, IpCameraSpecificConfig};
+use crate::errors::{ErrorKind, RcamError};
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, warn};
+use opencv::{core as opencv_core, prelude::*, videoio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const FFMPEG_OPTIONS_VAR: &str = "OPENCV_FFMPEG_CAPTURE_OPTIONS";
+
+/// How a still is taken from an RTSP stream. Decoders and network stacks buffer frames, so the
+/// first frame read from a stream (or from a cached one later) can be seconds old.
+#[derive(Debug, Clone, Copy)]
+pub struct RtspGrab {
+    pub flush_frames: u32, // at most this many buffered frames are dropped before the still; 0 disables the flush
+}
+
+impl RtspGrab {
+    pub const DEFAULT_FLUSH_FRAMES: u32 = 30;
+
+    pub fn resolve(app: &AppSettings, camera: Option<&IpCameraSpecificConfig>) -> Self {
+        RtspGrab {
+            flush_frames: camera.and_then(|c| c.rtsp_flush_frames)
+                .or(app.rtsp_flush_frames)
+                .unwrap_or(Self::DEFAULT_FLUSH_FRAMES),
+        }
+    }
+
+    /// Asks the backend for a one-frame buffer. FFmpeg ignores this, hence the flush as well.
+    pub fn configure(cap: &mut videoio::VideoCapture) {
+        if !cap.set(videoio::CAP_PROP_BUFFERSIZE, 1.0).unwrap_or(false) {
+            debug!("RTSP: backend does not support CAP_PROP_BUFFERSIZE; relying on the frame flush.");
+        }
+    }
+
+    /// Drops buffered frames until one has to be waited for, i.e. the stream has caught up with
+    /// the camera. A buffered frame comes back at once and its stream timestamp runs ahead of the
+    /// wall clock; a live one takes about a frame interval. Returns whether the stream is live.
+    pub fn flush(&self, cap: &mut videoio::VideoCapture, camera_name: &str) -> Result<bool> {
+        if self.flush_frames == 0 {
+            return Ok(false);
+        }
+        let fps = cap.get(videoio::CAP_PROP_FPS).ok().filter(|fps| fps.is_finite() && *fps > 0.0).unwrap_or(25.0);
+        let frame_interval = Duration::from_secs_f64(1.0 / fps);
+        let mut previous_pos_ms = cap.get(videoio::CAP_PROP_POS_MSEC).unwrap_or(0.0);
+        for dropped in 0..self.flush_frames {
+            let grab_start = Instant::now();
+            if !cap.grab().with_context(|| format!("OpenCV: Grab failed for '{}'", camera_name))? {
+                return Err(anyhow!("Stream ended while flushing buffered frames for '{}'", camera_name));
+            }
+            let waited = grab_start.elapsed();
+            let pos_ms = cap.get(videoio::CAP_PROP_POS_MSEC).unwrap_or(0.0);
+            let stream_step = Duration::from_secs_f64(((pos_ms - previous_pos_ms) / 1000.0).max(0.0));
+            previous_pos_ms = pos_ms;
+            // Waiting for half a frame interval, or half of what the stream timestamp advanced, means the frame was not buffered.
+            if waited >= frame_interval / 2 || (stream_step > Duration::ZERO && waited >= stream_step / 2) {
+                debug!("RTSP [{}]: live after dropping {} buffered frame(s) (grab waited {:?}).", camera_name, dropped, waited);
+                return Ok(true);
+            }
+        }
+        warn!("⚠️ RTSP [{}]: still reading buffered frames after dropping {}; the still may be stale (raise rtsp_flush_frames).",
+            camera_name, self.flush_frames);
+        Ok(false)
+    }
+
+    /// Flushes the buffer and decodes the last frame grabbed, the first live one.
+    pub fn grab_latest(&self, cap: &mut videoio::VideoCapture, camera_name: &str) -> Result<opencv_core::Mat> {
+        if self.flush_frames == 0 {
+            return read_frame(cap, camera_name);
+        }
+        self.flush(cap, camera_name)?;
+        let mut frame = opencv_core::Mat::default();
+        if !cap.retrieve(&mut frame, 0).with_context(|| format!("OpenCV: Retrieve failed for '{}'", camera_name))? || frame.empty() {
+            return Err(RcamError::for_camera(ErrorKind::Media, camera_name, format!("No frame received from RTSP stream for '{}'", camera_name)).into());
+        }
+        Ok(frame)
+    }
+}
+
+/// Reads and decodes the next frame.
+pub fn read_frame(cap: &mut videoio::VideoCapture, camera_name: &str) -> Result<opencv_core::Mat> {
+    let mut frame = opencv_core::Mat::default();
+    if !cap.read(&mut frame).with_context(|| format!("OpenCV: Read failed for '{}'", camera_name))? || frame.empty() {
+        return Err(RcamError::for_camera(ErrorKind::Media, camera_name, format!("No frame received from RTSP stream for '{}'", camera_name)).into());
+    }
+    Ok(frame)
+}
+
+/// How an RTSP stream's media is carried. Without one, FFmpeg tries UDP and falls back to TCP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtspTransport {
+    Tcp,       // interleaved in the RTSP connection; survives packet loss on wireless links
+    Udp,
+    Multicast,
+}
+
+impl RtspTransport {
+    pub fn parse(value: Option<&str>) -> Result<Option<Self>> {
+        match value.map(|v| v.to_lowercase()).as_deref() {
+            None => Ok(None),
+            Some("tcp") => Ok(Some(RtspTransport::Tcp)),
+            Some("udp") => Ok(Some(RtspTransport::Udp)),
+            Some("multicast") => Ok(Some(RtspTransport::Multicast)),
+            Some(other) => bail!("Unknown RTSP transport '{}' (supported: tcp, udp, multicast)", other),
+        }
+    }
+
+    /// The camera's `rtsp_transport`, else the application default. Both are validated at load.
+    pub fn resolve(app: &AppSettings, camera: Option<&IpCameraSpecificConfig>) -> Option<Self> {
+        let value = camera.and_then(|c| c.rtsp_transport.as_deref()).or(app.rtsp_transport.as_deref());
+        Self::parse(value).ok().flatten()
+    }
+
+    fn ffmpeg_value(self) -> &'static str {
+        match self {
+            RtspTransport::Tcp => "tcp",
+            RtspTransport::Udp => "udp",
+            RtspTransport::Multicast => "udp_multicast",
+        }
+    }
+}
+
+/// Opens a stream with the given OpenCV open params and RTSP transport. FFmpeg only takes the
+/// transport through `OPENCV_FFMPEG_CAPTURE_OPTIONS`, which is process-wide and read while a
+/// stream opens, so opens are serialized and each sets the variable for its own camera (on top
+/// of whatever options the variable held when rcam started).
+pub fn open_stream(url: &str, open_params: &opencv_core::Vector<i32>, transport: Option<RtspTransport>) -> opencv::Result<videoio::VideoCapture> {
+    static OPEN_LOCK: Mutex<()> = Mutex::new(());
+    static BASE_OPTIONS: OnceLock<Option<String>> = OnceLock::new();
+    let _open = OPEN_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let base = BASE_OPTIONS.get_or_init(|| std::env::var(FFMPEG_OPTIONS_VAR).ok().filter(|v| !v.is_empty()));
+    // FFmpeg options are "key;value" pairs separated by '|'.
+    let mut options: Vec<String> = base.iter()
+        .flat_map(|b| b.split('|'))
+        .filter(|option| transport.is_none() || !option.starts_with("rtsp_transport;"))
+        .map(str::to_string)
+        .collect();
+    if let Some(transport) = transport {
+        options.push(format!("rtsp_transport;{}", transport.ffmpeg_value()));
+    }
+    if options.is_empty() {
+        std::env::remove_var(FFMPEG_OPTIONS_VAR);
+    } else {
+        std::env::set_var(FFMPEG_OPTIONS_VAR, options.join("|"));
+    }
+    videoio::VideoCapture::from_file_with_params(url, videoio::CAP_ANY, open_params)
+}
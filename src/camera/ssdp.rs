@@ -0,0 +1,121 @@
+use crate::camera::onvif;
+use anyhow::{Context, Result};
+use log::debug;
+use reqwest::Client;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const SSDP_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(239, 255, 255, 250), 1900);
+const MX_SECS: u64 = 2; // responders wait a random 0..MX seconds before answering
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Words in a UPnP description or SERVER header that mark a device as a camera rather than a
+/// router, TV or media server.
+const CAMERA_HINTS: &[&str] = &[
+    "camera", "ipcam", "ipc", "nvr", "dvr", "network video", "webcam",
+    "axis", "hikvision", "dahua", "amcrest", "reolink", "foscam", "lorex", "uniview",
+];
+
+/// One answer to an M-SEARCH.
+#[derive(Debug, Clone)]
+pub struct SsdpResponse {
+    pub ip: IpAddr,
+    pub location: Option<String>, // URL of the UPnP device description
+    pub server: Option<String>,
+    pub st: Option<String>,
+    pub usn: Option<String>,
+}
+
+/// Identity from a UPnP device description (the XML at LOCATION).
+#[derive(Debug, Clone, Default)]
+pub struct DeviceDescription {
+    pub friendly_name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model_name: Option<String>,
+    pub device_type: Option<String>,
+    pub presentation_url: Option<String>,
+}
+
+impl DeviceDescription {
+    /// Whether the description or SERVER header mentions a camera or a camera maker.
+    pub fn looks_like_camera(&self, server: Option<&str>) -> bool {
+        [&self.friendly_name, &self.manufacturer, &self.model_name, &self.device_type]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .chain(server)
+            .any(|text| {
+                let text = text.to_lowercase();
+                CAMERA_HINTS.iter().any(|hint| text.contains(hint))
+            })
+    }
+}
+
+/// Multicasts an `ssdp:all` M-SEARCH and collects the answers for `timeout`, one per device
+/// (a device answers once per service it offers).
+pub async fn search(timeout: Duration) -> Result<Vec<SsdpResponse>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await.context("Failed to bind UDP socket for SSDP")?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}:{}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: ssdp:all\r\n\r\n",
+        SSDP_ADDR.0, SSDP_ADDR.1, MX_SECS
+    );
+    // Sent twice, since a lost multicast packet is not retransmitted.
+    for _ in 0..2 {
+        socket.send_to(request.as_bytes(), SocketAddr::from(SSDP_ADDR)).await.context("Failed to send SSDP M-SEARCH")?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let mut responses: Vec<SsdpResponse> = Vec::new();
+    let mut buf = vec![0u8; 4096];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, from) = received.context("Failed to receive SSDP response")?;
+        let Some(response) = parse_response(&String::from_utf8_lossy(&buf[..len]), from.ip()) else {
+            debug!("SSDP: ignored a malformed response from {}", from);
+            continue;
+        };
+        if !responses.iter().any(|known| known.ip == response.ip) {
+            responses.push(response);
+        }
+    }
+    Ok(responses)
+}
+
+fn parse_response(text: &str, ip: IpAddr) -> Option<SsdpResponse> {
+    let mut lines = text.lines();
+    if !lines.next()?.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    let mut response = SsdpResponse { ip, location: None, server: None, st: None, usn: None };
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        match key.trim().to_ascii_uppercase().as_str() {
+            "LOCATION" => response.location = value,
+            "SERVER" => response.server = value,
+            "ST" => response.st = value,
+            "USN" => response.usn = value,
+            _ => {}
+        }
+    }
+    Some(response)
+}
+
+/// Fetches and reads the UPnP device description at `location`.
+pub async fn describe(client: &Client, location: &str) -> Result<DeviceDescription> {
+    let xml = client.get(location).timeout(DESCRIPTION_TIMEOUT).send().await
+        .with_context(|| format!("Failed to fetch UPnP description {}", location))?
+        .error_for_status()
+        .with_context(|| format!("UPnP description {} was refused", location))?
+        .text().await
+        .with_context(|| format!("Failed to read UPnP description {}", location))?;
+    let text = |tag: &str| onvif::first_text(&xml, tag).filter(|v| !v.is_empty()).map(str::to_string);
+    Ok(DeviceDescription {
+        friendly_name: text("friendlyName"),
+        manufacturer: text("manufacturer"),
+        model_name: text("modelName"),
+        device_type: text("deviceType"),
+        presentation_url: text("presentationURL"),
+    })
+}
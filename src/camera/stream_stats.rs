@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Rates are measured over windows this long; encoders write in bursts (one per GOP), so
+/// shorter windows would swing between zero and several times the real bitrate.
+pub const WINDOW: Duration = Duration::from_secs(5);
+
+/// What a stream delivered over its last window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StreamRate {
+    pub at: DateTime<Utc>, // end of the window
+    pub fps: f64,          // frames read from the camera per second
+    pub kbps: f64,         // kilobits per second written to the recording
+}
+
+/// A recording's rates over its whole run, kept in the session manifest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamSummary {
+    pub avg_fps: f64,
+    pub avg_kbps: f64,
+    pub min_kbps: f64, // lowest window
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub degraded_secs: u64, // time spent below min_stream_kbps
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Counts one stream's frames and bytes and turns them into a rate once per `WINDOW`.
+/// OpenCV decodes inside FFmpeg and never shows the received bytes, so the bitrate is that of
+/// the file being written; a link that delivers fewer or emptier frames shows up in both.
+pub struct StreamMeter {
+    min_kbps: Option<f64>,
+    window_start: Instant,
+    window_frames: u64,
+    window_bytes: u64, // total bytes written when the window started
+    measured: Duration,
+    frames: u64,
+    bytes: u64,
+    lowest_kbps: Option<f64>,
+    degraded: bool,
+    degraded_for: Duration,
+}
+
+impl StreamMeter {
+    pub fn new(min_kbps: Option<f64>) -> Self {
+        StreamMeter {
+            min_kbps,
+            window_start: Instant::now(),
+            window_frames: 0,
+            window_bytes: 0,
+            measured: Duration::ZERO,
+            frames: 0,
+            bytes: 0,
+            lowest_kbps: None,
+            degraded: false,
+            degraded_for: Duration::ZERO,
+        }
+    }
+
+    pub fn frame(&mut self) {
+        self.window_frames += 1;
+    }
+
+    /// Closes the window once it is `WINDOW` long; `bytes` is everything written so far.
+    pub fn sample(&mut self, bytes: u64) -> Option<StreamRate> {
+        let elapsed = self.window_start.elapsed();
+        if elapsed < WINDOW {
+            return None;
+        }
+        let secs = elapsed.as_secs_f64();
+        let window_bytes = bytes.saturating_sub(self.window_bytes);
+        let rate = StreamRate { at: Utc::now(), fps: self.window_frames as f64 / secs, kbps: window_bytes as f64 * 8.0 / 1000.0 / secs };
+        self.measured += elapsed;
+        self.frames += self.window_frames;
+        self.bytes += window_bytes;
+        self.lowest_kbps = Some(self.lowest_kbps.map_or(rate.kbps, |lowest| lowest.min(rate.kbps)));
+        if self.degraded {
+            self.degraded_for += elapsed;
+        }
+        self.restart(bytes);
+        Some(rate)
+    }
+
+    /// Starts a new window without measuring the current one, e.g. after a pause, so idle time
+    /// does not read as a collapsed stream.
+    pub fn restart(&mut self, bytes: u64) {
+        self.window_start = Instant::now();
+        self.window_frames = 0;
+        self.window_bytes = bytes;
+    }
+
+    /// Compares a rate against `min_kbps`: Some(true) when the stream just fell below it,
+    /// Some(false) when it just recovered, None otherwise.
+    pub fn check(&mut self, rate: &StreamRate) -> Option<bool> {
+        let low = self.min_kbps.is_some_and(|min| rate.kbps < min);
+        if low == self.degraded {
+            return None;
+        }
+        self.degraded = low;
+        Some(low)
+    }
+
+    pub fn min_kbps(&self) -> Option<f64> {
+        self.min_kbps
+    }
+
+    /// Averages over the measured windows; None if the recording ended within the first one.
+    pub fn summary(&self) -> Option<StreamSummary> {
+        let secs = self.measured.as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+        Some(StreamSummary {
+            avg_fps: self.frames as f64 / secs,
+            avg_kbps: self.bytes as f64 * 8.0 / 1000.0 / secs,
+            min_kbps: self.lowest_kbps.unwrap_or_default(),
+            degraded_secs: self.degraded_for.as_secs(),
+        })
+    }
+}
+
+// Process-wide like `recording_control`: the agent API reports the rates of the recordings
+// in progress, which run on the blocking pool.
+static CURRENT: Mutex<BTreeMap<String, StreamRate>> = Mutex::new(BTreeMap::new());
+
+fn current_rates() -> MutexGuard<'static, BTreeMap<String, StreamRate>> {
+    CURRENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Records a camera's latest rate.
+pub fn update(camera: &str, rate: StreamRate) {
+    current_rates().insert(camera.to_string(), rate);
+}
+
+/// Forgets a camera's rate once its recording ended.
+pub fn clear(camera: &str) {
+    current_rates().remove(camera);
+}
+
+/// Latest rate of every camera that is recording.
+pub fn current() -> BTreeMap<String, StreamRate> {
+    current_rates().clone()
+}
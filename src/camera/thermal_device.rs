@@ -0,0 +1,228 @@
+use crate::camera::auth::{AuthScheme, CameraAuth};
+use crate::camera::frame_export;
+use crate::camera::ip_camera_device;
+use crate::camera::radiometric::{self, Temperatures};
+use crate::common::{atomic_file, opencv_pool};
+use crate::config_loader::{CameraTimeouts, ThermalCameraSpecificConfig};
+use crate::core::capture_source::{CaptureSource, CaptureTimings, FrameData, FrameDataBundle, ThermalFrameData};
+use crate::errors::{ErrorKind, ErrorKindExt, RcamError};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use reqwest::Client;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermalVendor {
+    Flir,
+    Hikvision,
+}
+
+impl ThermalVendor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThermalVendor::Flir => "flir",
+            ThermalVendor::Hikvision => "hikvision",
+        }
+    }
+
+    /// Snapshot endpoint: FLIR's current radiometric JPEG, or Hikvision's JPEG with the
+    /// temperature matrix appended.
+    fn snapshot_path(&self, channel: u32) -> String {
+        match self {
+            ThermalVendor::Flir => "/api/image/current?imgformat=JPEG".to_string(),
+            ThermalVendor::Hikvision => format!("/ISAPI/Thermal/channels/{}/thermometry/jpegPicWithAppendData?format=json", channel),
+        }
+    }
+}
+
+/// What a thermal capture writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermalExport {
+    Rjpeg, // the camera's JPEG as received (radiometric for FLIR)
+    Tiff,  // 16-bit centikelvin TIFF
+    Both,
+}
+
+impl ThermalExport {
+    fn jpeg(&self) -> bool {
+        matches!(self, ThermalExport::Rjpeg | ThermalExport::Both)
+    }
+
+    fn tiff(&self) -> bool {
+        matches!(self, ThermalExport::Tiff | ThermalExport::Both)
+    }
+}
+
+/// The validated vendor and export of a thermal camera.
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalSettings {
+    pub vendor: ThermalVendor,
+    pub export: ThermalExport,
+}
+
+impl ThermalSettings {
+    pub fn parse(config: &ThermalCameraSpecificConfig) -> Result<Self> {
+        let vendor = match config.vendor.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("flir") => ThermalVendor::Flir,
+            Some("hikvision") => ThermalVendor::Hikvision,
+            Some(other) => bail!("Unknown thermal vendor '{}' (supported: flir, hikvision)", other),
+        };
+        let export = match config.export.as_deref().map(str::to_lowercase).as_deref() {
+            None if vendor == ThermalVendor::Hikvision => ThermalExport::Tiff,
+            None | Some("rjpeg") => ThermalExport::Rjpeg,
+            Some("tiff") => ThermalExport::Tiff,
+            Some("both") => ThermalExport::Both,
+            Some(other) => bail!("Unknown export '{}' (supported: rjpeg, tiff, both)", other),
+        };
+        if vendor == ThermalVendor::Hikvision && export == ThermalExport::Rjpeg {
+            // Its JPEG carries no temperatures; they only come with the appended matrix.
+            bail!("Hikvision thermal JPEGs are not radiometric; use export \"tiff\" or \"both\"");
+        }
+        Ok(ThermalSettings { vendor, export })
+    }
+}
+
+pub struct ThermalDevice {
+    pub name: String,
+    pub config: ThermalCameraSpecificConfig,
+    pub settings: ThermalSettings,
+    pub timeouts: CameraTimeouts,
+}
+
+impl ThermalDevice {
+    pub fn new(name: String, config: ThermalCameraSpecificConfig, timeouts: CameraTimeouts) -> Result<Self> {
+        let settings = ThermalSettings::parse(&config)
+            .with_context(|| format!("Invalid thermal camera settings for '{}'", name))?;
+        debug!("Thermal Cam [{}]: {} camera, exporting {:?}.", name, settings.vendor.name(), settings.export);
+        Ok(Self { name, config, settings, timeouts })
+    }
+
+    pub fn snapshot_url(&self) -> String {
+        let scheme = self.config.http_scheme.as_deref().unwrap_or("http").to_lowercase();
+        let default_port = if scheme == "https" { 443 } else { 80 };
+        let channel = self.config.channel.unwrap_or(2);
+        let path = match &self.config.snapshot_path {
+            Some(custom) => custom.replace("{channel}", &channel.to_string()),
+            None => self.settings.vendor.snapshot_path(channel),
+        };
+        format!("{}://{}:{}{}", scheme, self.config.ip, self.config.http_port.unwrap_or(default_port), path)
+    }
+
+    fn http_auth(&self) -> Result<CameraAuth> {
+        let scheme = AuthScheme::parse(self.config.auth.as_deref())
+            .with_context(|| format!("Invalid auth for camera '{}'", self.name))?;
+        CameraAuth::from_env(&self.name, scheme, self.config.username.as_deref())
+    }
+
+    fn build_http_client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.timeouts.connect)
+            .timeout(self.timeouts.http);
+        if let Some(tls) = &self.config.tls {
+            builder = ip_camera_device::apply_tls(builder, tls, &self.name)?;
+        }
+        builder.build()
+            .with_context(|| format!("Failed to build HTTP client for camera '{}'", self.name))
+    }
+}
+
+#[async_trait]
+impl CaptureSource for ThermalDevice {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_type(&self) -> String {
+        "thermal-camera".to_string()
+    }
+
+    async fn capture_image(
+        &mut self,
+        output_dir: &Path,
+        timestamp_str: &str,
+        _image_format_config: &str, // thermal cameras write their own formats (see `export`)
+        _jpeg_quality: Option<u8>,
+        _png_compression: Option<u32>,
+    ) -> Result<FrameDataBundle> {
+        let client = self.build_http_client()?;
+        let auth = self.http_auth()?;
+        let url = self.snapshot_url();
+        info!("Thermal Cam [{}]: Requesting radiometric snapshot from {} ({:?} auth)", self.name, url, auth.scheme);
+
+        let request_start = Instant::now();
+        let response = auth.send(client.get(&url)).await
+            .with_kind(ErrorKind::Network, || format!("Thermal snapshot request failed for '{}'", self.name))?;
+        let mut timings = CaptureTimings { connect_ms: CaptureTimings::ms(request_start.elapsed()), ..Default::default() };
+        let status = response.status();
+        if !status.is_success() {
+            let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+            return Err(RcamError::for_camera(kind, self.name.as_str(), format!("Thermal snapshot request for '{}' returned {}", self.name, status)).into());
+        }
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body_start = Instant::now();
+        let body = response.bytes().await
+            .with_kind(ErrorKind::Network, || format!("Failed to read thermal snapshot from '{}'", self.name))?
+            .to_vec();
+        let captured_at = chrono::Utc::now();
+        timings.first_frame_ms = CaptureTimings::ms(body_start.elapsed());
+        debug!("Thermal Cam [{}]: Received {} bytes ({}).", self.name, body.len(), content_type);
+
+        let write_start = Instant::now();
+        let name = self.name.clone();
+        let settings = self.settings;
+        let jpeg_path = output_dir.join(format!("{}_{}.jpg", self.name, timestamp_str));
+        let tiff_path = output_dir.join(format!("{}_{}.tiff", self.name, timestamp_str));
+        let (paths, temperatures) = opencv_pool::spawn(opencv_pool::pinned(self.config.cpu_affinity.clone(), move || -> Result<(Vec<PathBuf>, Option<Temperatures>)> {
+            let (jpeg, temperatures) = match settings.vendor {
+                ThermalVendor::Flir => {
+                    let temperatures = match radiometric::flir_temperatures(&body) {
+                        Ok(temperatures) => Some(temperatures),
+                        Err(e) if !settings.export.tiff() => {
+                            // The R-JPEG is still written as received; only the temperatures are missing.
+                            warn!("⚠️ Thermal Cam [{}]: could not read temperatures from the R-JPEG: {:#}", name, e);
+                            None
+                        }
+                        Err(e) => return Err(e.context(RcamError::for_camera(ErrorKind::Media, name.as_str(),
+                            format!("Failed to read radiometric data from '{}'", name)))),
+                    };
+                    (body, temperatures)
+                }
+                ThermalVendor::Hikvision => {
+                    let snapshot = radiometric::hikvision_snapshot(&content_type, &body)
+                        .with_kind(ErrorKind::Media, || format!("Failed to parse thermal snapshot from '{}'", name))?;
+                    (snapshot.jpeg, Some(snapshot.temperatures))
+                }
+            };
+            let mut paths = Vec::new();
+            if settings.export.jpeg() {
+                paths.push(atomic_file::write_bytes(&jpeg_path, &jpeg)
+                    .with_kind(ErrorKind::Storage, || format!("Failed to write thermal JPEG for '{}'", name))?);
+            }
+            if let (true, Some(temperatures)) = (settings.export.tiff(), &temperatures) {
+                paths.push(atomic_file::write_with(&tiff_path, |part| frame_export::write_temperatures(part, temperatures))
+                    .with_kind(ErrorKind::Storage, || format!("Failed to write temperature TIFF for '{}'", name))?);
+            }
+            Ok((paths, temperatures))
+        })).await??;
+        timings.write_ms = CaptureTimings::ms(write_start.elapsed());
+
+        match temperatures.as_ref().and_then(Temperatures::range) {
+            Some((min, max)) => info!("✅ Thermal Cam [{}]: Saved {} file(s), {:.1} °C .. {:.1} °C.", self.name, paths.len(), min, max),
+            None => info!("✅ Thermal Cam [{}]: Saved {} file(s).", self.name, paths.len()),
+        }
+        Ok(FrameDataBundle {
+            frames: vec![FrameData::ThermalImage {
+                name: self.name.clone(),
+                temperatures: temperatures.map(|t| ThermalFrameData { celsius: t.celsius.into(), width: t.width, height: t.height }),
+                paths,
+            }],
+            captured_at,
+            timings,
+        })
+    }
+}
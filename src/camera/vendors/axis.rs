@@ -0,0 +1,150 @@
+use super::{VendorContext, VendorDriver};
+use crate::camera::imaging::DayNightMode;
+use crate::errors::{ErrorKind, RcamError};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// Axis VAPIX API (`/axis-cgi/*.cgi`).
+pub struct AxisDriver;
+
+#[derive(Serialize)]
+struct TimeRequest<'a> {
+    #[serde(rename = "apiVersion")]
+    api_version: &'a str,
+    method: &'a str,
+}
+
+#[derive(Deserialize)]
+struct TimeResponse {
+    data: Option<TimeResponseData>,
+    error: Option<VapixError>,
+}
+
+#[derive(Deserialize)]
+struct TimeResponseData {
+    #[serde(rename = "dateTime")]
+    date_time: String,
+}
+
+#[derive(Deserialize)]
+struct VapixError {
+    code: i64,
+    message: String,
+}
+
+impl AxisDriver {
+    async fn get_text(&self, ctx: &VendorContext<'_>, path: &str) -> Result<String> {
+        self.get_url(ctx, &format!("{}{}", ctx.base_url, path)).await
+    }
+
+    /// GET of `path` with `query` percent-encoded, so keys and values may hold `&`, `=`, spaces or brackets.
+    async fn get_query(&self, ctx: &VendorContext<'_>, path: &str, query: &[(&str, &str)]) -> Result<String> {
+        let mut url = Url::parse(&format!("{}{}", ctx.base_url, path))
+            .with_context(|| format!("Invalid HTTP endpoint for camera '{}'", ctx.camera_name))?;
+        url.query_pairs_mut().extend_pairs(query);
+        self.get_url(ctx, url.as_str()).await
+    }
+
+    async fn get_url(&self, ctx: &VendorContext<'_>, url: &str) -> Result<String> {
+        debug!("Axis [{}]: GET {}", ctx.camera_name, url);
+        let response = ctx.send(ctx.client.get(url))
+            .await
+            .map_err(|e| anyhow!("HTTP GET {} failed for '{}': {}", url, ctx.camera_name, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+            return Err(RcamError::for_camera(kind, ctx.camera_name, format!("HTTP GET {} failed for '{}' with status {}", url, ctx.camera_name, status)).into());
+        }
+        response.text().await.with_context(|| format!("Failed to read response body from {}", url))
+    }
+}
+
+#[async_trait]
+impl VendorDriver for AxisDriver {
+    fn name(&self) -> &'static str {
+        "axis"
+    }
+
+    fn snapshot_path(&self, channel: u32) -> String {
+        format!("/axis-cgi/jpg/image.cgi?camera={}", channel)
+    }
+
+    async fn get_time(&self, ctx: &VendorContext<'_>) -> Result<DateTime<Utc>> {
+        let url = format!("{}/axis-cgi/time.cgi", ctx.base_url);
+        debug!("Axis [{}]: POST {} (getDateTimeInfo)", ctx.camera_name, url);
+        let response = ctx.send(ctx.client.post(&url)
+            .json(&TimeRequest { api_version: "1.0", method: "getDateTimeInfo" }))
+            .await
+            .map_err(|e| anyhow!("HTTP POST {} failed for '{}': {}", url, ctx.camera_name, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+            return Err(RcamError::for_camera(kind, ctx.camera_name, format!("HTTP POST {} failed for '{}' with status {}", url, ctx.camera_name, status)).into());
+        }
+        let parsed: TimeResponse = response.json().await
+            .with_context(|| format!("Failed to parse VAPIX time response from '{}'", ctx.camera_name))?;
+        if let Some(err) = parsed.error {
+            bail!("VAPIX getDateTimeInfo failed for '{}': {} (code {})", ctx.camera_name, err.message, err.code);
+        }
+        let date_time = parsed.data
+            .ok_or_else(|| anyhow!("VAPIX time response for '{}' contained no data", ctx.camera_name))?
+            .date_time;
+        DateTime::parse_from_rfc3339(&date_time)
+            .map(|dt| dt.with_timezone(&Utc))
+            .with_context(|| format!("Failed to parse VAPIX dateTime '{}' for camera '{}'", date_time, ctx.camera_name))
+    }
+
+    async fn get_parameter(&self, ctx: &VendorContext<'_>, key: &str) -> Result<String> {
+        // param.cgi answers "root.Group.Param=value" lines.
+        let body = self.get_query(ctx, "/axis-cgi/param.cgi", &[("action", "list"), ("group", key)]).await?;
+        if body.trim_start().starts_with("# Error") {
+            bail!("VAPIX param list '{}' failed for '{}': {}", key, ctx.camera_name, body.trim());
+        }
+        Ok(body.trim().to_string())
+    }
+
+    async fn set_parameter(&self, ctx: &VendorContext<'_>, key: &str, value: &str) -> Result<()> {
+        let body = self.get_query(ctx, "/axis-cgi/param.cgi", &[("action", "update"), (key, value)]).await?;
+        if !body.trim().eq_ignore_ascii_case("OK") {
+            bail!("VAPIX param update {}={} rejected by '{}': {}", key, value, ctx.camera_name, body.trim());
+        }
+        Ok(())
+    }
+
+    async fn reboot(&self, ctx: &VendorContext<'_>) -> Result<()> {
+        // restart.cgi answers with a plain-text notice; a non-2xx status is the failure signal.
+        self.get_text(ctx, "/axis-cgi/restart.cgi").await?;
+        Ok(())
+    }
+
+    fn supports_day_night(&self) -> bool {
+        true
+    }
+
+    async fn get_day_night(&self, ctx: &VendorContext<'_>) -> Result<DayNightMode> {
+        // IrCutFilter: "yes" = filter in (day), "no" = filter out (night), "auto".
+        let body = self.get_parameter(ctx, "ImageSource.I0.DayNight.IrCutFilter").await?;
+        let value = body.lines()
+            .find_map(|line| line.trim().strip_prefix("root.ImageSource.I0.DayNight.IrCutFilter="))
+            .ok_or_else(|| anyhow!("Camera '{}' did not report ImageSource.I0.DayNight.IrCutFilter", ctx.camera_name))?;
+        match value.trim() {
+            "yes" => Ok(DayNightMode::Day),
+            "no" => Ok(DayNightMode::Night),
+            "auto" => Ok(DayNightMode::Auto),
+            other => bail!("Unknown IrCutFilter '{}' from camera '{}'", other, ctx.camera_name),
+        }
+    }
+
+    async fn set_day_night(&self, ctx: &VendorContext<'_>, mode: DayNightMode) -> Result<()> {
+        let value = match mode {
+            DayNightMode::Day => "yes",
+            DayNightMode::Night => "no",
+            DayNightMode::Auto => "auto",
+        };
+        self.set_parameter(ctx, "ImageSource.I0.DayNight.IrCutFilter", value).await
+    }
+}
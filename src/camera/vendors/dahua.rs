@@ -0,0 +1,125 @@
+use super::{VendorContext, VendorDriver};
+use crate::camera::imaging::DayNightMode;
+use crate::errors::{ErrorKind, RcamError};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::debug;
+use reqwest::Url;
+
+/// Dahua/Amcrest CGI API (`/cgi-bin/*.cgi`).
+pub struct DahuaDriver;
+
+impl DahuaDriver {
+    async fn get_text(&self, ctx: &VendorContext<'_>, path: &str) -> Result<String> {
+        self.get_url(ctx, &format!("{}{}", ctx.base_url, path)).await
+    }
+
+    /// GET of `path` with `query` percent-encoded, so keys and values may hold `&`, `=`, spaces or brackets.
+    async fn get_query(&self, ctx: &VendorContext<'_>, path: &str, query: &[(&str, &str)]) -> Result<String> {
+        let mut url = Url::parse(&format!("{}{}", ctx.base_url, path))
+            .with_context(|| format!("Invalid HTTP endpoint for camera '{}'", ctx.camera_name))?;
+        url.query_pairs_mut().extend_pairs(query);
+        self.get_url(ctx, url.as_str()).await
+    }
+
+    async fn get_url(&self, ctx: &VendorContext<'_>, url: &str) -> Result<String> {
+        debug!("Dahua [{}]: GET {}", ctx.camera_name, url);
+        let response = ctx.send(ctx.client.get(url))
+            .await
+            .map_err(|e| anyhow!("HTTP GET {} failed for '{}': {}", url, ctx.camera_name, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let kind = if status.as_u16() == 401 || status.as_u16() == 403 { ErrorKind::Auth } else { ErrorKind::Network };
+            return Err(RcamError::for_camera(kind, ctx.camera_name, format!("HTTP GET {} failed for '{}' with status {}", url, ctx.camera_name, status)).into());
+        }
+        response.text().await.with_context(|| format!("Failed to read response body from {}", url))
+    }
+}
+
+#[async_trait]
+impl VendorDriver for DahuaDriver {
+    fn name(&self) -> &'static str {
+        "dahua"
+    }
+
+    fn snapshot_path(&self, channel: u32) -> String {
+        format!("/cgi-bin/snapshot.cgi?channel={}", channel)
+    }
+
+    async fn get_time(&self, ctx: &VendorContext<'_>) -> Result<DateTime<Utc>> {
+        // Response: "result=2023-10-27 10:30:00", in the camera's local time zone. The API has no
+        // offset to convert it with, so it is read as UTC: cameras must be set to UTC (GMT+00:00)
+        // for verify-times to compare them with the host and with each other.
+        let body = self.get_text(ctx, "/cgi-bin/global.cgi?action=getCurrentTime").await?;
+        let value = body.trim().split_once('=').map(|(_, v)| v.trim()).unwrap_or(body.trim());
+        let parsed = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+            .with_context(|| format!("Failed to parse Dahua time '{}' for camera '{}'", body.trim(), ctx.camera_name))?;
+        Ok(DateTime::from_naive_utc_and_offset(parsed, Utc))
+    }
+
+    async fn get_parameter(&self, ctx: &VendorContext<'_>, key: &str) -> Result<String> {
+        // configManager returns "table.<Name>[0].<Field>=value" lines; the requested key is the config name.
+        let body = self.get_query(ctx, "/cgi-bin/configManager.cgi", &[("action", "getConfig"), ("name", key)]).await?;
+        Ok(body.trim().to_string())
+    }
+
+    async fn set_parameter(&self, ctx: &VendorContext<'_>, key: &str, value: &str) -> Result<()> {
+        let body = self.get_query(ctx, "/cgi-bin/configManager.cgi", &[("action", "setConfig"), (key, value)]).await?;
+        if !body.trim().eq_ignore_ascii_case("OK") {
+            bail!("Dahua setConfig {}={} rejected by '{}': {}", key, value, ctx.camera_name, body.trim());
+        }
+        Ok(())
+    }
+
+    async fn reboot(&self, ctx: &VendorContext<'_>) -> Result<()> {
+        let body = self.get_text(ctx, "/cgi-bin/magicBox.cgi?action=reboot").await?;
+        if !body.trim().eq_ignore_ascii_case("OK") {
+            bail!("Dahua reboot rejected by '{}': {}", ctx.camera_name, body.trim());
+        }
+        Ok(())
+    }
+
+    async fn get_stream_enabled(&self, ctx: &VendorContext<'_>) -> Result<bool> {
+        let body = self.get_parameter(ctx, "Encode").await?;
+        let value = body.lines()
+            .find_map(|line| line.trim().strip_prefix("table.Encode[0].MainFormat[0].VideoEnable="))
+            .ok_or_else(|| anyhow!("Camera '{}' did not report Encode[0].MainFormat[0].VideoEnable", ctx.camera_name))?;
+        match value.trim() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            other => bail!("Unknown VideoEnable '{}' from camera '{}'", other, ctx.camera_name),
+        }
+    }
+
+    async fn set_stream_enabled(&self, ctx: &VendorContext<'_>, enabled: bool) -> Result<()> {
+        self.set_parameter(ctx, "Encode[0].MainFormat[0].VideoEnable", if enabled { "true" } else { "false" }).await
+    }
+
+    fn supports_day_night(&self) -> bool {
+        true
+    }
+
+    async fn get_day_night(&self, ctx: &VendorContext<'_>) -> Result<DayNightMode> {
+        // DayNightColor: 0 = always colour, 1 = auto by brightness, 2 = always black and white.
+        let body = self.get_parameter(ctx, "VideoInOptions").await?;
+        let value = body.lines()
+            .find_map(|line| line.trim().strip_prefix("table.VideoInOptions[0].DayNightColor="))
+            .ok_or_else(|| anyhow!("Camera '{}' did not report VideoInOptions[0].DayNightColor", ctx.camera_name))?;
+        match value.trim() {
+            "0" => Ok(DayNightMode::Day),
+            "1" => Ok(DayNightMode::Auto),
+            "2" => Ok(DayNightMode::Night),
+            other => bail!("Unknown DayNightColor '{}' from camera '{}'", other, ctx.camera_name),
+        }
+    }
+
+    async fn set_day_night(&self, ctx: &VendorContext<'_>, mode: DayNightMode) -> Result<()> {
+        let value = match mode {
+            DayNightMode::Day => "0",
+            DayNightMode::Auto => "1",
+            DayNightMode::Night => "2",
+        };
+        self.set_parameter(ctx, "VideoInOptions[0].DayNightColor", value).await
+    }
+}
@@ -0,0 +1,35 @@
+use super::{VendorContext, VendorDriver};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Devices that only expose an RTSP stream (no CGI/HTTP API). Snapshots are grabbed from the
+/// stream instead, and HTTP-only features (time query, parameters) are reported as unsupported.
+pub struct GenericRtspDriver;
+
+#[async_trait]
+impl VendorDriver for GenericRtspDriver {
+    fn name(&self) -> &'static str {
+        "rtsp"
+    }
+
+    fn has_http_api(&self) -> bool {
+        false
+    }
+
+    fn snapshot_path(&self, _channel: u32) -> String {
+        String::new()
+    }
+
+    async fn get_time(&self, ctx: &VendorContext<'_>) -> Result<DateTime<Utc>> {
+        bail!("Camera '{}' is RTSP-only and has no HTTP API to query its time", ctx.camera_name)
+    }
+
+    async fn get_parameter(&self, ctx: &VendorContext<'_>, key: &str) -> Result<String> {
+        bail!("Camera '{}' is RTSP-only; cannot read parameter '{}'", ctx.camera_name, key)
+    }
+
+    async fn set_parameter(&self, ctx: &VendorContext<'_>, key: &str, _value: &str) -> Result<()> {
+        bail!("Camera '{}' is RTSP-only; cannot set parameter '{}'", ctx.camera_name, key)
+    }
+}
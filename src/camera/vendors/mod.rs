@@ -0,0 +1,83 @@
+pub mod axis;
+pub mod dahua;
+pub mod generic_rtsp;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::camera::auth::CameraAuth;
+use crate::camera::imaging::DayNightMode;
+use reqwest::{Client, RequestBuilder, Response};
+
+/// Everything a vendor driver needs to talk to one camera over HTTP.
+pub struct VendorContext<'a> {
+    pub camera_name: &'a str,
+    pub client: &'a Client,
+    pub base_url: String, // e.g. "http://192.168.1.91:80"
+    pub auth: &'a CameraAuth,
+}
+
+impl VendorContext<'_> {
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        self.auth.send(request).await
+    }
+}
+
+/// Vendor-specific HTTP/CGI API of an IP camera.
+#[async_trait]
+pub trait VendorDriver: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// False for devices that only expose RTSP; callers then skip or replace HTTP features.
+    fn has_http_api(&self) -> bool {
+        true
+    }
+
+    /// Path (with query) of the JPEG snapshot endpoint for the given channel.
+    fn snapshot_path(&self, channel: u32) -> String;
+
+    async fn get_time(&self, ctx: &VendorContext<'_>) -> Result<DateTime<Utc>>;
+
+    async fn get_parameter(&self, ctx: &VendorContext<'_>, key: &str) -> Result<String>;
+
+    async fn set_parameter(&self, ctx: &VendorContext<'_>, key: &str, value: &str) -> Result<()>;
+
+    /// Restarts the camera. Returns once the camera has accepted the request, not once it is back.
+    async fn reboot(&self, ctx: &VendorContext<'_>) -> Result<()> {
+        bail!("The '{}' driver cannot reboot camera '{}'", self.name(), ctx.camera_name)
+    }
+
+    /// Whether the camera's main video stream is encoded and served.
+    async fn get_stream_enabled(&self, ctx: &VendorContext<'_>) -> Result<bool> {
+        bail!("The '{}' driver cannot read the stream state of camera '{}'", self.name(), ctx.camera_name)
+    }
+
+    async fn set_stream_enabled(&self, ctx: &VendorContext<'_>, _enabled: bool) -> Result<()> {
+        bail!("The '{}' driver cannot enable or disable the stream of camera '{}'", self.name(), ctx.camera_name)
+    }
+
+    /// True if the driver implements `get_day_night`/`set_day_night`.
+    fn supports_day_night(&self) -> bool {
+        false
+    }
+
+    async fn get_day_night(&self, ctx: &VendorContext<'_>) -> Result<DayNightMode> {
+        bail!("The '{}' driver cannot read the day/night mode of camera '{}'", self.name(), ctx.camera_name)
+    }
+
+    async fn set_day_night(&self, ctx: &VendorContext<'_>, _mode: DayNightMode) -> Result<()> {
+        bail!("The '{}' driver cannot set the day/night mode of camera '{}'", self.name(), ctx.camera_name)
+    }
+}
+
+/// Returns the driver for the per-camera `vendor` config key (default: "dahua", which also covers Amcrest).
+pub fn driver_for(vendor: Option<&str>) -> Result<Box<dyn VendorDriver>> {
+    match vendor.map(|v| v.to_lowercase()).as_deref() {
+        None | Some("dahua") | Some("amcrest") => Ok(Box::new(dahua::DahuaDriver)),
+        Some("axis") => Ok(Box::new(axis::AxisDriver)),
+        Some("rtsp") | Some("generic-rtsp") => Ok(Box::new(generic_rtsp::GenericRtspDriver)),
+        Some(other) => bail!("Unknown camera vendor '{}' (supported: {})", other, SUPPORTED_VENDORS.join(", ")),
+    }
+}
+
+pub const SUPPORTED_VENDORS: &[&str] = &["dahua", "amcrest", "axis", "rtsp"];
@@ -0,0 +1,308 @@
+//! A camera without hardware: SMPTE colour bars stamped with each frame's time and number, or
+//! the frames of a video or image file. Stills and recordings go through the same writers as
+//! real cameras, so CI and developers without cameras can run the whole capture, recording and
+//! Rerun pipeline.
+
+use crate::camera::camera_media;
+use crate::camera::frame_export;
+use crate::camera::frame_sink::FrameSink;
+use crate::camera::post_process::{self, OverlayInfo};
+use crate::common::{atomic_file, opencv_pool, sync_start};
+use crate::config_loader::{AppSettings, PostProcessStep, VirtualCameraSpecificConfig};
+use crate::core::capture_source::{
+    CaptureSource, CaptureTimings, FrameData, FrameDataBundle, VideoRecordRequest, VideoRecorder, VideoSource,
+};
+use crate::core::events::{self, Event};
+use crate::errors::{ErrorKind, RcamError};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use opencv::{core as opencv_core, imgcodecs, imgproc, prelude::*, videoio};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// `source` value for generated colour bars (the default).
+pub const TEST_PATTERN: &str = "test_pattern";
+const DEFAULT_WIDTH: u32 = 1280;
+const DEFAULT_HEIGHT: u32 = 720;
+
+/// Where a virtual camera's frames come from. A file is played in a loop.
+pub enum FrameSource {
+    TestPattern { size: opencv_core::Size, frames: u64 },
+    Image { frame: opencv_core::Mat },
+    Video { path: PathBuf, capture: Option<videoio::VideoCapture> },
+}
+
+impl FrameSource {
+    pub fn from_config(config: &VirtualCameraSpecificConfig) -> Result<Self> {
+        match config.source.as_deref().unwrap_or(TEST_PATTERN) {
+            TEST_PATTERN => Ok(FrameSource::TestPattern {
+                size: opencv_core::Size::new(config.width.unwrap_or(DEFAULT_WIDTH) as i32, config.height.unwrap_or(DEFAULT_HEIGHT) as i32),
+                frames: 0,
+            }),
+            file if is_image_file(Path::new(file)) => {
+                let frame = imgcodecs::imread(file, imgcodecs::IMREAD_COLOR)?;
+                if frame.empty() {
+                    bail!("Could not read image '{}'", file);
+                }
+                Ok(FrameSource::Image { frame })
+            }
+            file => Ok(FrameSource::Video { path: PathBuf::from(file), capture: None }),
+        }
+    }
+
+    /// The next frame (BGR). Test pattern frames carry `at` and their number.
+    pub fn next_frame(&mut self, at: DateTime<Utc>) -> Result<opencv_core::Mat> {
+        match self {
+            FrameSource::TestPattern { size, frames } => {
+                *frames += 1;
+                test_pattern(*size, *frames, at)
+            }
+            FrameSource::Image { frame } => Ok(frame.try_clone()?),
+            FrameSource::Video { path, capture } => {
+                let mut frame = opencv_core::Mat::default();
+                for attempt in 0..2 {
+                    if capture.is_none() {
+                        let path_str = path.to_str().context("Video path is not UTF-8")?;
+                        let opened = videoio::VideoCapture::from_file(path_str, videoio::CAP_ANY)?;
+                        if !opened.is_opened()? {
+                            bail!("Could not open video '{}'", path.display());
+                        }
+                        *capture = Some(opened);
+                    }
+                    let video = capture.as_mut().expect("opened above");
+                    if video.read(&mut frame)? && !frame.empty() {
+                        return Ok(frame);
+                    }
+                    if attempt == 0 {
+                        debug!("Reached the end of '{}'; starting over.", path.display());
+                        *capture = None;
+                    }
+                }
+                bail!("Video '{}' has no readable frames", path.display())
+            }
+        }
+    }
+}
+
+/// Human-readable source, for listings and logs.
+pub fn describe(config: &VirtualCameraSpecificConfig) -> String {
+    match config.source.as_deref().unwrap_or(TEST_PATTERN) {
+        TEST_PATTERN => format!("test pattern {}x{}", config.width.unwrap_or(DEFAULT_WIDTH), config.height.unwrap_or(DEFAULT_HEIGHT)),
+        file => format!("file {}", file),
+    }
+}
+
+fn is_image_file(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "bmp" | "webp" | "tif" | "tiff")
+}
+
+/// SMPTE-style colour bars with the frame's UTC time and number, plus a bar sweeping across
+/// so consecutive frames always differ.
+pub(crate) fn test_pattern(size: opencv_core::Size, number: u64, at: DateTime<Utc>) -> Result<opencv_core::Mat> {
+    let bgr = |b: f64, g: f64, r: f64| opencv_core::Scalar::new(b, g, r, 0.0);
+    let mut image = opencv_core::Mat::new_size_with_default(size, opencv_core::CV_8UC3, bgr(0.0, 0.0, 0.0))?;
+    let (width, height) = (size.width, size.height);
+    let fill = |image: &mut opencv_core::Mat, x0: i32, x1: i32, y0: i32, y1: i32, color: opencv_core::Scalar| {
+        imgproc::rectangle(image, opencv_core::Rect::new(x0, y0, (x1 - x0).max(0), (y1 - y0).max(0)), color, imgproc::FILLED, imgproc::LINE_8, 0)
+    };
+    // 75% bars: grey, yellow, cyan, green, magenta, red, blue; under them the reversed
+    // castellations; at the bottom -I, white, +Q and black.
+    let bars = [bgr(191.0, 191.0, 191.0), bgr(0.0, 191.0, 191.0), bgr(191.0, 191.0, 0.0), bgr(0.0, 191.0, 0.0),
+        bgr(191.0, 0.0, 191.0), bgr(0.0, 0.0, 191.0), bgr(191.0, 0.0, 0.0)];
+    let castellations = [bgr(191.0, 0.0, 0.0), bgr(0.0, 0.0, 0.0), bgr(191.0, 0.0, 191.0), bgr(0.0, 0.0, 0.0),
+        bgr(191.0, 191.0, 0.0), bgr(0.0, 0.0, 0.0), bgr(191.0, 191.0, 191.0)];
+    let bar_x = |i: i32| i * width / 7;
+    let (top, middle) = (height * 2 / 3, height * 3 / 4);
+    for i in 0..7 {
+        fill(&mut image, bar_x(i), bar_x(i + 1), 0, top, bars[i as usize])?;
+        fill(&mut image, bar_x(i), bar_x(i + 1), top, middle, castellations[i as usize])?;
+    }
+    let quarter = width * 5 / 28;
+    fill(&mut image, 0, quarter, middle, height, bgr(76.0, 33.0, 0.0))?;
+    fill(&mut image, quarter, quarter * 2, middle, height, bgr(255.0, 255.0, 255.0))?;
+    fill(&mut image, quarter * 2, quarter * 3, middle, height, bgr(106.0, 0.0, 50.0))?;
+
+    let sweep_x = (number as i64 * 8 % width.max(1) as i64) as i32;
+    fill(&mut image, sweep_x, sweep_x + (width / 160).max(2), top, middle, bgr(255.0, 255.0, 255.0))?;
+
+    let text = format!("{}  #{}", at.format("%Y-%m-%d %H:%M:%S%.3f UTC"), number);
+    let scale = (width as f64 / 1280.0).max(0.4);
+    let thickness = (scale * 2.0).round().max(1.0) as i32;
+    let mut baseline = 0;
+    let text_size = imgproc::get_text_size(&text, imgproc::FONT_HERSHEY_SIMPLEX, scale, thickness, &mut baseline)?;
+    let pad = (10.0 * scale) as i32;
+    let box_y = top / 2 - text_size.height / 2 - pad;
+    fill(&mut image, width / 2 - text_size.width / 2 - pad, width / 2 + text_size.width / 2 + pad,
+        box_y, box_y + text_size.height + baseline + pad * 2, bgr(0.0, 0.0, 0.0))?;
+    imgproc::put_text(&mut image, &text, opencv_core::Point::new(width / 2 - text_size.width / 2, box_y + pad + text_size.height),
+        imgproc::FONT_HERSHEY_SIMPLEX, scale, opencv_core::Scalar::all(255.0), thickness, imgproc::LINE_AA, false)?;
+    Ok(image)
+}
+
+pub struct VirtualCameraDevice {
+    pub name: String,
+    pub config: VirtualCameraSpecificConfig,
+    source: Option<FrameSource>, // opened on the first capture, then kept so files keep playing
+}
+
+impl VirtualCameraDevice {
+    pub fn new(name: String, config: VirtualCameraSpecificConfig) -> Self {
+        debug!("Virtual Cam [{}]: {}.", name, describe(&config));
+        Self { name, config, source: None }
+    }
+}
+
+/// Takes one frame, post-processes it and writes it in `format`.
+fn capture_still(
+    source: &mut FrameSource,
+    name: &str,
+    path: &Path,
+    format: &str,
+    steps: &[PostProcessStep],
+    jpeg_quality: Option<u8>,
+    png_compression: Option<u32>,
+) -> Result<(PathBuf, DateTime<Utc>, Duration)> {
+    let captured_at = Utc::now();
+    let mut frame = source.next_frame(captured_at)?;
+    let write_start = Instant::now();
+    if !steps.is_empty() {
+        let overlay = OverlayInfo { camera_name: name, captured_at };
+        frame = post_process::apply(steps, &frame, Some(&overlay))
+            .with_context(|| format!("Post-processing failed for camera '{}'", name))?;
+    }
+    let path = atomic_file::write_with(path, |part| frame_export::write_mat(part, &frame, format, jpeg_quality, png_compression))
+        .with_context(|| format!("Failed to write image for camera '{}'", name))?;
+    Ok((path, captured_at, write_start.elapsed()))
+}
+
+#[async_trait]
+impl CaptureSource for VirtualCameraDevice {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_type(&self) -> String {
+        "virtual-camera".to_string()
+    }
+
+    async fn capture_image(
+        &mut self,
+        output_dir: &Path,
+        timestamp_str: &str,
+        image_format_config: &str,
+        jpeg_quality: Option<u8>,
+        png_compression: Option<u32>,
+    ) -> Result<FrameDataBundle> {
+        let mut source = match self.source.take() {
+            Some(source) => source,
+            None => FrameSource::from_config(&self.config)
+                .map_err(|e| e.context(RcamError::for_camera(ErrorKind::Media, self.name.as_str(), format!("Failed to open source of '{}'", self.name))))?,
+        };
+        let name = self.name.clone();
+        let format = image_format_config.to_string();
+        let path = output_dir.join(format!("{}_{}.{}", self.name, timestamp_str, image_format_config));
+        let steps = self.config.post_process.clone().unwrap_or_default();
+        let frame_start = Instant::now();
+        let (source, result) = opencv_pool::spawn(opencv_pool::pinned(self.config.cpu_affinity.clone(), move || {
+            let result = capture_still(&mut source, &name, &path, &format, &steps, jpeg_quality, png_compression);
+            (source, result)
+        })).await?;
+        self.source = Some(source);
+        let (path, captured_at, write_time) = result?;
+
+        info!("✅ Virtual Cam [{}]: Saved {} still to {}", self.name, image_format_config, path.display());
+        Ok(FrameDataBundle {
+            frames: vec![FrameData::IpCameraImage { name: self.name.clone(), path, format: image_format_config.to_string() }],
+            captured_at,
+            timings: CaptureTimings {
+                first_frame_ms: CaptureTimings::ms(frame_start.elapsed().saturating_sub(write_time)),
+                write_ms: CaptureTimings::ms(write_time),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Recordings open their own frame source, so a file restarts from its first frame.
+    async fn video_source(&mut self) -> Result<Option<VideoSource>> {
+        Ok(Some(VideoSource::Local(Arc::new(VirtualRecording { name: self.name.clone(), config: self.config.clone() }))))
+    }
+}
+
+/// A virtual camera's `capture-video` recording, written at `video_fps` with the configured codec.
+struct VirtualRecording {
+    name: String,
+    config: VirtualCameraSpecificConfig,
+}
+
+#[async_trait]
+impl VideoRecorder for VirtualRecording {
+    fn describe(&self) -> String {
+        describe(&self.config)
+    }
+
+    fn extension(&self, app: &AppSettings) -> String {
+        app.video_format.clone()
+    }
+
+    async fn record(&self, path: &Path, request: &VideoRecordRequest) -> Result<(PathBuf, Duration)> {
+        let fourcc = camera_media::writer_fourcc(&request.app, &self.name)?;
+        let fps = request.app.video_fps.unwrap_or(30.0) as f64;
+        record(self.name.clone(), self.config.clone(), path, fourcc, fps, request.duration, request.start_at, request.cancel.clone()).await
+    }
+}
+
+/// Records a virtual camera to `output_path` at `fps` for `duration`, producing frames in real
+/// time so the file plays like a camera's. Returns the file and how long it covers.
+#[allow(clippy::too_many_arguments)]
+pub async fn record(
+    name: String,
+    config: VirtualCameraSpecificConfig,
+    output_path: &Path,
+    fourcc: i32,
+    fps: f64,
+    duration: Duration,
+    start_at: Option<DateTime<Utc>>,
+    cancel: Option<CancellationToken>,
+) -> Result<(PathBuf, Duration)> {
+    let mut source = FrameSource::from_config(&config)
+        .map_err(|e| e.context(RcamError::for_camera(ErrorKind::Media, name.as_str(), format!("Failed to open source of '{}'", name))))?;
+    if let Some(at) = start_at {
+        let late = sync_start::sleep_until(at).await;
+        if !late.is_zero() {
+            warn!("⚠️ '{}': missed the scheduled start by {:?}; recording now.", name, late);
+        }
+    }
+    info!("🧪 '{}': recording {} at {} fps for {:?}.", name, describe(&config), fps, duration);
+    let output_path = output_path.to_path_buf();
+    opencv_pool::spawn(opencv_pool::pinned(config.cpu_affinity.clone(), move || -> Result<(PathBuf, Duration)> {
+        let total_frames = (duration.as_secs_f64() * fps).round().max(1.0) as u64;
+        let started = Instant::now();
+        let mut sink: Option<FrameSink> = None;
+        for index in 0..total_frames {
+            if cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                break;
+            }
+            let due = Duration::from_secs_f64(index as f64 / fps);
+            std::thread::sleep(due.saturating_sub(started.elapsed()));
+            let now = Utc::now();
+            let frame = source.next_frame(now)?;
+            if sink.is_none() {
+                let opened = FrameSink::opencv(&output_path, fourcc, fps, frame.size()?, false)
+                    .with_context(|| format!("Failed to open VideoWriter for '{}' at {}", name, output_path.display()))?;
+                events::publish(Event::RecordingStarted { camera: name.clone(), at: now, path: opened.path().to_path_buf() });
+                sink = Some(opened);
+            }
+            sink.as_mut().expect("opened above").write(&frame, now)?;
+        }
+        let recorded_for = started.elapsed().min(duration);
+        let mut sink = sink.ok_or_else(|| RcamError::for_camera(ErrorKind::Media, name.as_str(), format!("'{}' was stopped before its first frame", name)))?;
+        sink.release()?;
+        info!("🧪 '{}': recording saved to {} ({:.1}s).", name, sink.path().display(), recorded_for.as_secs_f64());
+        Ok((sink.path().to_path_buf(), recorded_for))
+    })).await?
+}
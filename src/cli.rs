@@ -1,53 +1,683 @@
-use clap::{Arg, Command, ArgAction};
-use log::debug;
-use std::time::Instant;
-
-pub fn build_cli() -> Command {
-    debug!("⚙️ Building CLI interface...");
-    let start_time = Instant::now();
-    let cmd = Command::new("rcam")
-        .version("0.1.0")
-        .author("RCam Developers")
-        .about("A Rust application for recording images and videos from multiple IP cameras.")
-        .arg(
-            Arg::new("config")
-                .short('c')
-                .long("config")
-                .value_name("FILE")
-                .help("Sets a custom configuration file")
-                .action(ArgAction::Set)
-        )
-        .arg(
-            Arg::new("debug")
-                .short('d')
-                .long("debug")
-                .help("Enable debug logging")
-                .action(ArgAction::SetTrue)
-        )
-        .subcommand(
-            Command::new("capture-image")
-                .about("Captures a single image from specified or all cameras")
-                .arg(Arg::new("cameras").long("cameras").value_name("CAM_NAMES").help("Comma-separated list of camera names to capture from (default: all)").action(ArgAction::Set))
-                .arg(Arg::new("delay").long("delay").value_name("SECONDS").help("Delay in seconds before capturing image").value_parser(clap::value_parser!(u64)).action(ArgAction::Set))
-                .arg(Arg::new("output").short('o').long("output").value_name("DIR").help("Output directory for images").action(ArgAction::Set))
-                .arg(Arg::new("rerun").long("rerun").help("Enable Rerun logging for this capture").action(ArgAction::SetTrue))
-        )
-        .subcommand(
-            Command::new("capture-video")
-                .about("Records a video segment from specified or all cameras")
-                .arg(Arg::new("cameras").long("cameras").value_name("CAM_NAMES").help("Comma-separated list of camera names to record from (default: all)").action(ArgAction::Set))
-                .arg(Arg::new("duration").long("duration").value_name("SECONDS").help("Duration of the video recording in seconds").value_parser(clap::value_parser!(u64)).action(ArgAction::Set))
-                .arg(Arg::new("output").short('o').long("output").value_name("DIR").help("Output directory for videos").action(ArgAction::Set))
-                .arg(Arg::new("rerun").long("rerun").help("Enable Rerun logging for this recording").action(ArgAction::SetTrue))
-        )
-        .subcommand(
-            Command::new("verify-times")
-                .about("Verifies time synchronization across all cameras")
-        )
-        .subcommand(
-            Command::new("test")
-                .about("Runs a diagnostic test suite")
-        );
-    debug!("✅ CLI interface built in {:?}", start_time.elapsed());
-    cmd
-} 
\ No newline at end of file
+use crate::camera::imaging::DayNightMode;
+use crate::operations::control_op::ControlAction;
+use clap::{ArgAction, ArgGroup, Args, Parser, Subcommand};
+
+/// Command line of the `rcam` binary. Each subcommand's arguments are a typed struct that the
+/// matching operation turns into its `XxxParams`, so a renamed or missing flag is a compile error.
+#[derive(Parser, Debug)]
+#[command(name = "rcam", version = "0.1.0", author = "RCam Developers")]
+#[command(about = "A Rust application for recording images and videos from multiple IP cameras.")]
+pub struct Cli {
+    /// Sets a custom configuration file
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<String>,
+
+    /// Named entry of the config's `profiles` to apply (overrides application.default_profile)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Override a config setting after loading, e.g. application.video_fps=15 or cameras.cam1.rtsp_path=/stream2 (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+
+    /// Enable debug logging (same as -v)
+    #[arg(short, long)]
+    pub debug: bool,
+
+    /// More log output: -v for debug, -vv for trace
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Log errors only, for scripts; also quiets OpenCV and FFmpeg
+    #[arg(short, long, conflicts_with_all = ["debug", "verbose"])]
+    pub quiet: bool,
+
+    /// Also log to this file, rotated per log_rotation (overrides application.log_file)
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<String>,
+
+    /// Log line format: text or json (overrides application.log_format)
+    #[arg(long, value_name = "FORMAT")]
+    pub log_format: Option<String>,
+
+    /// Write the per-camera results of capture-image, capture-video, set, control and coordinator as JSON to FILE ("-" for stdout)
+    #[arg(long, value_name = "FILE")]
+    pub summary_json: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Lists configured devices with endpoints, credential status and last known health
+    List(ListArgs),
+    /// Finds cameras on the local network that announce themselves over mDNS or SSDP
+    Discover(DiscoverArgs),
+    /// Captures a single image from specified or all cameras
+    CaptureImage(CaptureImageArgs),
+    /// Records a video segment from specified or all cameras
+    CaptureVideo(RecordVideoArgs),
+    /// Sets exposure, gain, white balance and day/night mode on IP cameras
+    Set(SetImagingArgs),
+    /// Runs maintenance actions on IP cameras (reboot, device info, stream enable/disable, day/night mode)
+    Control(ControlArgs),
+    /// Shows a live focus score and brightness for one camera while its lens is adjusted
+    Aim(AimArgs),
+    /// Verifies time synchronization across all cameras
+    VerifyTimes(VerifyTimesArgs),
+    /// Runs a diagnostic test suite
+    Test(DiagnosticArgs),
+    /// Camera calibration tools
+    Calibrate(CalibrateArgs),
+    /// Batch converts previously captured images to another format
+    Convert(ConvertArgs),
+    /// Registers previously captured media as a session so it can be reported on alongside new captures
+    Import(ImportArgs),
+    /// Writes a self-contained HTML QA report for a capture session
+    Report(ReportArgs),
+    /// Analyzes the rig's combined field-of-view coverage of the configured workspace
+    Coverage(CoverageArgs),
+    /// Runs continuously, taking snapshots on each camera's configured schedule
+    Daemon(DaemonArgs),
+    /// Validates the configuration and shows what changed relative to another config or session
+    Validate(ValidateArgs),
+    /// Re-hashes saved media and reports files that changed or went missing since capture
+    VerifyArchive(VerifyArchiveArgs),
+    /// Serves capture requests from a coordinator over HTTP
+    Agent(AgentArgs),
+    /// Runs a capture on several remote agents at one shared instant and collects their results
+    Coordinator(CoordinatorArgs),
+    /// Pauses the recordings of a running agent; each camera finalizes its current file
+    Pause(RecordingControlArgs),
+    /// Resumes paused recordings of a running agent, each in a new file
+    Resume(RecordingControlArgs),
+    /// Ends in-progress recordings of a running agent early; files and metadata are still finalized
+    StopRecording(RecordingControlArgs),
+}
+
+impl Commands {
+    /// Subcommand name as typed on the command line, for logging.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Commands::List(_) => "list",
+            Commands::Discover(_) => "discover",
+            Commands::CaptureImage(_) => "capture-image",
+            Commands::CaptureVideo(_) => "capture-video",
+            Commands::Set(_) => "set",
+            Commands::Control(_) => "control",
+            Commands::Aim(_) => "aim",
+            Commands::VerifyTimes(_) => "verify-times",
+            Commands::Test(_) => "test",
+            Commands::Calibrate(_) => "calibrate",
+            Commands::Convert(_) => "convert",
+            Commands::Import(_) => "import",
+            Commands::Report(_) => "report",
+            Commands::Coverage(_) => "coverage",
+            Commands::Daemon(_) => "daemon",
+            Commands::Validate(_) => "validate",
+            Commands::VerifyArchive(_) => "verify-archive",
+            Commands::Agent(_) => "agent",
+            Commands::Coordinator(_) => "coordinator",
+            Commands::Pause(_) => "pause",
+            Commands::Resume(_) => "resume",
+            Commands::StopRecording(_) => "stop-recording",
+        }
+    }
+}
+
+/// Rerun flags shared by the operations that can log to a viewer.
+#[derive(Args, Debug, Clone, Default)]
+pub struct RerunArgs {
+    /// Enable Rerun logging
+    #[arg(long)]
+    pub rerun: bool,
+
+    /// Rerun application id (overrides config)
+    #[arg(long, value_name = "ID")]
+    pub rerun_app_id: Option<String>,
+
+    /// Rerun recording id, to group logs from several runs into one recording
+    #[arg(long, value_name = "ID")]
+    pub rerun_recording_id: Option<String>,
+
+    /// Log to a running Rerun viewer (host:port or URL) instead of spawning one; implies --rerun
+    #[arg(long, value_name = "ADDR", conflicts_with = "rerun_save")]
+    pub rerun_connect: Option<String>,
+
+    /// Write the Rerun log to an .rrd file instead of spawning a viewer; implies --rerun
+    #[arg(long, value_name = "FILE")]
+    pub rerun_save: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CaptureImageArgs {
+    /// Comma-separated list of camera names to capture from (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Delay in seconds before capturing image
+    #[arg(long, value_name = "SECONDS")]
+    pub delay: Option<u64>,
+
+    /// Output directory for images
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<String>,
+
+    /// Capture a burst of N frames per camera
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+
+    /// Time between burst frames in milliseconds (0: as fast as possible)
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub interval: u64,
+
+    /// Do not save frames that look the same as the camera's previous one
+    #[arg(long)]
+    pub skip_unchanged: bool,
+
+    /// Re-attempt cameras that failed, up to N times after the batch (default: capture_retries)
+    #[arg(long, value_name = "N")]
+    pub retries: Option<u32>,
+
+    /// Named stream to grab RTSP stills from: main, sub or one of the camera's `streams` (default: operation_streams)
+    #[arg(long, value_name = "NAME")]
+    pub stream: Option<String>,
+
+    /// Start at an absolute UTC instant so several hosts capture together: next-second, next-minute, next-<N>s or an RFC 3339 time (default: start_at)
+    #[arg(long, value_name = "WHEN")]
+    pub start_at: Option<String>,
+
+    /// Milliseconds added to the --start-at instant (default: start_offset_ms)
+    #[arg(long, value_name = "MS")]
+    pub start_offset_ms: Option<u64>,
+
+    /// Print a per-camera table of connect, first-frame and write times and store it in the session manifest
+    #[arg(long)]
+    pub timing_report: bool,
+
+    #[command(flatten)]
+    pub rerun: RerunArgs,
+
+    /// With --rerun, also log a colored point cloud for each RealSense capture
+    #[arg(long)]
+    pub rerun_points: bool,
+
+    /// Fail if any selected camera cannot be captured
+    #[arg(long)]
+    pub require_all: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RecordVideoArgs {
+    /// Comma-separated list of camera names to record from (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Duration of the video recording in seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub duration: Option<u64>,
+
+    /// Output directory for videos
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<String>,
+
+    /// Named stream to record: main, sub or one of the camera's `streams` (default: operation_streams)
+    #[arg(long, value_name = "NAME")]
+    pub stream: Option<String>,
+
+    #[command(flatten)]
+    pub rerun: RerunArgs,
+
+    /// With --rerun, stream frames while recording instead of replaying the files afterwards
+    #[arg(long)]
+    pub rerun_live: bool,
+
+    /// Frames per second per camera sent to Rerun in live mode (default 2)
+    #[arg(long, value_name = "FPS")]
+    pub rerun_live_fps: Option<f32>,
+
+    /// Fail if any selected camera cannot be recorded
+    #[arg(long)]
+    pub require_all: bool,
+
+    /// Also mux all recordings into one multi-track MKV (requires ffmpeg)
+    #[arg(long)]
+    pub mkv: bool,
+
+    /// Write variable frame rate MKV files with each frame's real capture time (requires ffmpeg)
+    #[arg(long)]
+    pub vfr: bool,
+
+    /// Start at an absolute UTC instant so several hosts capture together: next-second, next-minute, next-<N>s or an RFC 3339 time (default: start_at)
+    #[arg(long, value_name = "WHEN")]
+    pub start_at: Option<String>,
+
+    /// Milliseconds added to the --start-at instant (default: start_offset_ms)
+    #[arg(long, value_name = "MS")]
+    pub start_offset_ms: Option<u64>,
+
+    /// Print a per-camera table of connect, first-frame and write times and store it in the session manifest
+    #[arg(long)]
+    pub timing_report: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SetImagingArgs {
+    /// Comma-separated list of IP and RealSense cameras (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// "auto", or a fixed exposure: 1/250, 4ms, 4000us or seconds (RealSense: the depth sensor)
+    #[arg(long, value_name = "TIME")]
+    pub exposure: Option<String>,
+
+    /// Fixed sensor gain in dB (implies manual exposure)
+    #[arg(long, value_name = "DB", allow_negative_numbers = true)]
+    pub gain: Option<f64>,
+
+    /// White balance: "auto", a colour temperature like 5600K, or <cr>,<cb> gains
+    #[arg(long, value_name = "WB")]
+    pub wb: Option<String>,
+
+    /// Day/night mode: on (IR-cut filter in, colour), off (night, monochrome) or auto
+    #[arg(long, value_name = "MODE", value_parser = ["on", "off", "auto", "day", "night"])]
+    pub ir: Option<String>,
+
+    /// RealSense visual preset: default, hand, high_accuracy, high_density, medium_density or a JSON file
+    #[arg(long, value_name = "PRESET")]
+    pub preset: Option<String>,
+
+    /// RealSense IR projector power in mW
+    #[arg(long, value_name = "MW")]
+    pub laser_power: Option<f32>,
+
+    /// RealSense IR emitter on or off
+    #[arg(long, value_name = "STATE", value_parser = ["on", "off"])]
+    pub emitter: Option<String>,
+
+    /// RealSense inter-camera sync mode: default, master, slave, full_slave or genlock
+    #[arg(long, value_name = "MODE", value_parser = ["default", "master", "slave", "full_slave", "genlock"])]
+    pub sync_mode: Option<String>,
+
+    /// Only print the current settings
+    #[arg(long)]
+    pub show: bool,
+
+    /// Fail if any selected camera cannot be set
+    #[arg(long)]
+    pub require_all: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("what").required(true).args(["action", "ir"])))]
+pub struct ControlArgs {
+    /// Action to run
+    #[arg(long, value_name = "ACTION", value_enum)]
+    pub action: Option<ControlAction>,
+
+    /// Set the day/night mode instead: on (IR-cut filter in, colour), off (night, monochrome) or auto
+    #[arg(long, value_name = "MODE", value_parser = DayNightMode::parse)]
+    pub ir: Option<DayNightMode>,
+
+    /// Comma-separated list of IP cameras (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Do not ask for confirmation before disruptive actions
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Fail if the action fails on any selected camera
+    #[arg(long)]
+    pub require_all: bool,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct VerifyTimesArgs {}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct DiagnosticArgs {
+    /// Comma-separated tests to run: time, network, daynight, image, video, sync, tasks (default: all)
+    #[arg(long, value_name = "TESTS")]
+    pub tests: Option<String>,
+
+    /// Comma-separated list of cameras to test (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Run a soak test for this long instead (e.g. 24h, 90m): repeated snapshots and short recordings
+    #[arg(long, value_name = "DURATION")]
+    pub soak: Option<String>,
+
+    /// Time from one soak round to the next
+    #[arg(long, value_name = "DURATION", default_value = "5m")]
+    pub soak_interval: String,
+
+    /// Keep the files of soak rounds that passed (by default only failed rounds are kept)
+    #[arg(long)]
+    pub soak_keep: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CalibrateArgs {
+    #[command(subcommand)]
+    pub command: CalibrateCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CalibrateCommand {
+    /// Renders a printable calibration board at exact physical dimensions
+    PrintBoard(PrintBoardArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PrintBoardArgs {
+    /// Board type: charuco or chessboard
+    #[arg(long = "type", value_name = "TYPE", default_value = "charuco")]
+    pub board_type: String,
+
+    /// Number of squares, e.g. 7x5
+    #[arg(long, value_name = "COLSxROWS", default_value = "7x5")]
+    pub squares: String,
+
+    /// Square side length in millimetres
+    #[arg(long, value_name = "MM", default_value_t = 30.0)]
+    pub square_mm: f64,
+
+    /// ArUco marker side length in millimetres (default 75% of the square)
+    #[arg(long, value_name = "MM")]
+    pub marker_mm: Option<f64>,
+
+    /// ArUco dictionary, e.g. 4x4_50, 5x5_100, 6x6_250
+    #[arg(long, value_name = "DICT", default_value = "5x5_100")]
+    pub dictionary: String,
+
+    /// Render resolution
+    #[arg(long, value_name = "DPI", default_value_t = 300)]
+    pub dpi: u32,
+
+    /// White margin around the board
+    #[arg(long, value_name = "MM", default_value_t = 10.0)]
+    pub margin_mm: f64,
+
+    /// png, pdf or both
+    #[arg(long, value_name = "FORMAT", default_value = "both")]
+    pub format: String,
+
+    /// Output directory (default: <output_directory_base>/calibration)
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConvertArgs {
+    /// Directory of images to convert
+    #[arg(long, value_name = "DIR")]
+    pub input: String,
+
+    /// Target format: jpg, png or webp
+    #[arg(long, value_name = "FORMAT")]
+    pub to: String,
+
+    /// Quality for jpg/webp (default: jpeg_quality from config)
+    #[arg(long, value_name = "1-100")]
+    pub quality: Option<u8>,
+
+    /// Write converted images (and sidecar files) here instead of next to the originals
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<String>,
+
+    /// Also convert images in subdirectories
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Delete each original after it was converted
+    #[arg(long)]
+    pub remove_source: bool,
+
+    /// Replace converted images (and sidecar files) that already exist instead of skipping them
+    #[arg(long)]
+    pub overwrite: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ImportArgs {
+    /// Directory of existing media
+    #[arg(long, value_name = "DIR")]
+    pub input: String,
+
+    /// Filename template, e.g. "{camera}-{timestamp}.{ext}" (repeatable; rcam's own layouts are always tried last)
+    #[arg(long = "pattern", value_name = "TEMPLATE")]
+    pub patterns: Vec<String>,
+
+    /// chrono format of {timestamp} (default: filename_timestamp_format)
+    #[arg(long, value_name = "FORMAT")]
+    pub timestamp_format: Option<String>,
+
+    /// Session id to register under (default: import_<now>)
+    #[arg(long, value_name = "ID")]
+    pub session: Option<String>,
+
+    /// Also scan subdirectories
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReportArgs {
+    /// Session id, e.g. image_20250101_120000
+    #[arg(long, value_name = "ID", default_value = "latest")]
+    pub session: String,
+
+    /// Report path (default: report.html in the session directory)
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<String>,
+
+    /// Longest thumbnail side in pixels
+    #[arg(long, value_name = "PX", default_value_t = 320)]
+    pub thumbnail_px: u32,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CoverageArgs {
+    /// Comma-separated list of cameras to include (default: all with extrinsics)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Sampling resolution (overrides coverage_workspace.voxel_size_m)
+    #[arg(long = "voxel-size", value_name = "METRES")]
+    pub voxel_size_m: Option<f64>,
+
+    /// Cameras that must see a voxel for it to count as covered (default 2)
+    #[arg(long, value_name = "N")]
+    pub min_cameras: Option<u32>,
+
+    /// Exit with an error if any workspace voxel is seen by no camera
+    #[arg(long)]
+    pub fail_on_blind_spots: bool,
+
+    #[command(flatten)]
+    pub rerun: RerunArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DaemonArgs {
+    /// Comma-separated list of cameras to schedule (default: all with a schedule)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Set up and tear down each camera for every snapshot instead of keeping it open between
+    /// snapshots (an open RealSense keeps streaming, with its laser on)
+    #[arg(long)]
+    pub cold: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ValidateArgs {
+    /// Compare with this config file, e.g. the one currently deployed
+    #[arg(long, value_name = "FILE", conflicts_with = "session")]
+    pub against: Option<String>,
+
+    /// Compare with the configuration a session ran with (session id or "latest")
+    #[arg(long, value_name = "ID")]
+    pub session: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct VerifyArchiveArgs {
+    /// Output directory to check (default: output_directory_base)
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<String>,
+
+    /// Only check this session (id or "latest")
+    #[arg(long, value_name = "ID")]
+    pub session: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct AgentArgs {
+    /// Address to serve the API on (default: agent.listen, then 0.0.0.0:8700)
+    #[arg(long, value_name = "ADDR:PORT")]
+    pub listen: Option<std::net::SocketAddr>,
+
+    /// Also serve the gRPC API on this address (default: agent.grpc_listen, else no gRPC)
+    #[arg(long, value_name = "ADDR:PORT")]
+    pub grpc_listen: Option<std::net::SocketAddr>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CoordinatorArgs {
+    #[command(subcommand)]
+    pub command: CoordinatorCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CoordinatorCommand {
+    /// Captures images on the agents' cameras at one shared instant
+    CaptureImage(CoordinatedImageArgs),
+    /// Records video on the agents' cameras from one shared instant
+    CaptureVideo(CoordinatedVideoArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CoordinatorSharedArgs {
+    /// Comma-separated list of agent names to use (default: all configured agents)
+    #[arg(long, value_name = "AGENTS")]
+    pub agents: Option<String>,
+
+    /// Comma-separated list of camera names, on whichever agent has them (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Shared start: next-second, next-minute, next-<N>s or an RFC 3339 time (default: start_at, then next-second)
+    #[arg(long, value_name = "WHEN")]
+    pub start_at: Option<String>,
+
+    /// Milliseconds added to the --start-at instant (default: start_offset_ms)
+    #[arg(long, value_name = "MS")]
+    pub start_offset_ms: Option<u64>,
+
+    /// Output directory for the combined session manifest
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<String>,
+
+    /// Fail if any agent or camera does not succeed
+    #[arg(long)]
+    pub require_all: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CoordinatedImageArgs {
+    #[command(flatten)]
+    pub shared: CoordinatorSharedArgs,
+
+    /// Capture a burst of N frames per camera
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: Option<u32>,
+
+    /// Time between burst frames in milliseconds
+    #[arg(long, value_name = "MS")]
+    pub interval: Option<u64>,
+
+    /// Re-attempt failed cameras on each agent, up to N times (default: the agent's capture_retries)
+    #[arg(long, value_name = "N")]
+    pub retries: Option<u32>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CoordinatedVideoArgs {
+    #[command(flatten)]
+    pub shared: CoordinatorSharedArgs,
+
+    /// Duration of the video recording in seconds (default: this host's video_duration_default_seconds)
+    #[arg(long, value_name = "SECONDS")]
+    pub duration: Option<u64>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct RecordingControlArgs {
+    /// Base URL of the agent running the recordings (default: this host's agent, from agent.listen)
+    #[arg(long, value_name = "URL")]
+    pub agent: Option<String>,
+
+    /// stop-recording only: comma-separated cameras to stop, or "all" (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct ListArgs {
+    /// Comma-separated list of devices to show (default: all)
+    #[arg(long, value_name = "CAM_NAMES")]
+    pub cameras: Option<String>,
+
+    /// Print the list as JSON on stdout instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct AimArgs {
+    /// Camera to aim (an IP camera)
+    #[arg(long, value_name = "CAM_NAME")]
+    pub camera: String,
+
+    /// Milliseconds between readings
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub interval_ms: u64,
+
+    /// Stop after this many seconds (default: run until Ctrl-C)
+    #[arg(long, value_name = "SECONDS")]
+    pub duration: Option<u64>,
+
+    /// Named stream to read: main, sub or one of the camera's `streams` (default: operation_streams)
+    #[arg(long, value_name = "NAME")]
+    pub stream: Option<String>,
+
+    #[command(flatten)]
+    pub rerun: RerunArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DiscoverArgs {
+    /// Browse mDNS (Bonjour) for RTSP, Axis and PSIA services
+    #[arg(long)]
+    pub mdns: bool,
+
+    /// Send an SSDP (UPnP) search; without --mdns or --ssdp both are used
+    #[arg(long)]
+    pub ssdp: bool,
+
+    /// Seconds to listen for answers
+    #[arg(long, value_name = "SECONDS", default_value_t = 3)]
+    pub timeout_secs: u64,
+
+    /// Also list SSDP devices that do not look like cameras (routers, TVs, ...)
+    #[arg(long)]
+    pub all: bool,
+
+    /// Print ready-to-paste camera entries for the config file on stdout
+    #[arg(long)]
+    pub yaml: bool,
+}
@@ -0,0 +1,118 @@
+use anyhow::{bail, Context, Result};
+use log::{debug, warn};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Names tried with a `-2`, `-3`, ... suffix before giving up on a colliding file name.
+const MAX_SUFFIX: u32 = 1000;
+
+/// A capture file being written under a temporary name (`cam1_<ts>.part.png`) and moved to its
+/// final name only once complete, so a crash or failed write never leaves a truncated file that
+/// looks finished. The extension is kept last because OpenCV and FFmpeg pick the encoder from it.
+/// Dropping an uncommitted file deletes the temporary one.
+#[derive(Debug)]
+pub struct PartFile {
+    path: PathBuf,
+    part_path: PathBuf,
+    committed: bool,
+}
+
+impl PartFile {
+    /// Claims `path`, or `<stem>-2.<ext>`, `<stem>-3.<ext>`, ... if that name is already taken
+    /// (two captures within the same formatted timestamp), by atomically creating the
+    /// temporary file. Parallel writers therefore never get the same name.
+    pub fn reserve(path: &Path) -> Result<Self> {
+        for attempt in 1..=MAX_SUFFIX {
+            let candidate = with_suffix(path, attempt);
+            if candidate.exists() {
+                continue;
+            }
+            let part_path = part_path(&candidate);
+            match OpenOptions::new().write(true).create_new(true).open(&part_path) {
+                Ok(_) => {
+                    if attempt > 1 {
+                        debug!("{} exists; writing to {} instead.", path.display(), candidate.display());
+                    }
+                    return Ok(PartFile { path: candidate, part_path, committed: false });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e).with_context(|| format!("Failed to create {}", part_path.display())),
+            }
+        }
+        bail!("No free file name for {} after {} attempts", path.display(), MAX_SUFFIX)
+    }
+
+    /// The name the file gets once committed.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where the data is written until then.
+    pub fn part_path(&self) -> &Path {
+        &self.part_path
+    }
+
+    /// Moves the temporary file to its final name and returns that name.
+    pub fn commit(mut self) -> Result<PathBuf> {
+        fs::rename(&self.part_path, &self.path)
+            .with_context(|| format!("Failed to move {} into place at {}", self.part_path.display(), self.path.display()))?;
+        self.committed = true;
+        Ok(self.path.clone())
+    }
+}
+
+impl Drop for PartFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(e) = fs::remove_file(&self.part_path) {
+                if e.kind() != ErrorKind::NotFound {
+                    warn!("⚠️ Could not remove incomplete file {}: {}", self.part_path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Writes a file through `write` (given the temporary path) and commits it; returns the final
+/// path, which differs from `path` if that name was taken.
+pub fn write_with(path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<PathBuf> {
+    let file = PartFile::reserve(path)?;
+    write(file.part_path())?;
+    file.commit()
+}
+
+/// `write_with` for bytes that are already encoded, e.g. an HTTP snapshot.
+pub fn write_bytes(path: &Path, bytes: &[u8]) -> Result<PathBuf> {
+    write_with(path, |part| fs::write(part, bytes).with_context(|| format!("Failed to write {}", part.display())))
+}
+
+/// Replaces an existing file's content through a temporary file, so readers see the old or
+/// the new bytes but never a partial write.
+pub fn replace_bytes(path: &Path, bytes: &[u8]) -> Result<()> {
+    let part = part_path(path);
+    fs::write(&part, bytes).with_context(|| format!("Failed to write {}", part.display()))?;
+    fs::rename(&part, path).with_context(|| {
+        let _ = fs::remove_file(&part);
+        format!("Failed to move {} into place at {}", part.display(), path.display())
+    })
+}
+
+fn with_suffix(path: &Path, attempt: u32) -> PathBuf {
+    if attempt == 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}-{}.{}", stem, attempt, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}-{}", stem, attempt)),
+    }
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.part.{}", stem, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.part", stem)),
+    }
+}
@@ -0,0 +1,123 @@
+use crate::common::{logging_setup, opencv_pool};
+use crate::config_loader::AppSettings;
+use anyhow::{anyhow, Result};
+use log::{debug, error, info};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Heartbeat interval and hard timeout applied to blocking camera work.
+#[derive(Debug, Clone, Copy)]
+pub struct WatchdogSettings {
+    pub heartbeat: Duration,
+    pub timeout: Duration,
+}
+
+impl WatchdogSettings {
+    pub const DEFAULT_HEARTBEAT_SECS: f32 = 15.0;
+    pub const DEFAULT_TIMEOUT_SECS: f32 = 60.0;
+
+    pub fn from_app(app: &AppSettings) -> Self {
+        WatchdogSettings {
+            heartbeat: Duration::from_secs_f32(app.heartbeat_interval_secs.unwrap_or(Self::DEFAULT_HEARTBEAT_SECS)),
+            timeout: Duration::from_secs_f32(app.blocking_timeout_secs.unwrap_or(Self::DEFAULT_TIMEOUT_SECS)),
+        }
+    }
+}
+
+struct PhaseState {
+    label: String,
+    entered_at: Instant,
+    bounded: bool,
+}
+
+/// What a blocking task is currently doing. Updated from inside the blocking closure and read
+/// by the async watchdog for heartbeat and timeout messages. The hard timeout is measured per
+/// phase, so long but healthy work (e.g. a recording loop) can opt out with `enter_unbounded`.
+#[derive(Clone)]
+pub struct BlockingPhase(Arc<Mutex<PhaseState>>);
+
+impl BlockingPhase {
+    pub fn new(initial: &str) -> Self {
+        BlockingPhase(Arc::new(Mutex::new(PhaseState {
+            label: initial.to_string(),
+            entered_at: Instant::now(),
+            bounded: true,
+        })))
+    }
+
+    pub fn new_unbounded(initial: &str) -> Self {
+        let phase = Self::new(initial);
+        phase.enter_unbounded(initial);
+        phase
+    }
+
+    /// Enters a phase that is subject to the hard timeout.
+    pub fn enter(&self, label: &str) {
+        self.set(label, true);
+    }
+
+    /// Enters a phase that only gets heartbeats; it is expected to bound itself.
+    pub fn enter_unbounded(&self, label: &str) {
+        self.set(label, false);
+    }
+
+    fn set(&self, label: &str, bounded: bool) {
+        let mut state = self.0.lock().unwrap_or_else(|p| p.into_inner());
+        state.label = label.to_string();
+        state.entered_at = Instant::now();
+        state.bounded = bounded;
+    }
+
+    fn snapshot(&self) -> (String, Duration, bool) {
+        let state = self.0.lock().unwrap_or_else(|p| p.into_inner());
+        (state.label.clone(), state.entered_at.elapsed(), state.bounded)
+    }
+}
+
+/// Runs `work` on the OpenCV worker pool, logging a heartbeat while it runs and failing with a
+/// camera-attributed error if a bounded phase exceeds the timeout. A timed-out thread cannot be
+/// cancelled; it is detached and its eventual result is discarded.
+pub async fn run_blocking<T, F>(camera_name: &str, settings: WatchdogSettings, phase: BlockingPhase, work: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let handle = {
+        let _camera = logging_setup::camera_span(camera_name).entered();
+        opencv_pool::spawn(work)
+    };
+    let joined = async {
+        handle.await.map_err(|e| anyhow!("Camera '{}': blocking {}", camera_name, e))?
+    };
+    supervise(camera_name, settings, phase, joined).await
+}
+
+/// Waits for blocking work that is already running elsewhere (e.g. on a thread that keeps a
+/// device open), with the same heartbeat and per-phase timeout as `run_blocking`.
+pub async fn supervise<T, F>(camera_name: &str, settings: WatchdogSettings, phase: BlockingPhase, work: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let started = Instant::now();
+    tokio::pin!(work);
+    loop {
+        match tokio::time::timeout(settings.heartbeat, &mut work).await {
+            Ok(result) => {
+                debug!("Blocking task for '{}' finished in {:?}.", camera_name, started.elapsed());
+                return result;
+            }
+            Err(_) => {
+                let (label, in_phase, bounded) = phase.snapshot();
+                if bounded && in_phase >= settings.timeout {
+                    error!(
+                        "⏰ Camera '{}': gave up after {:?} while {} (limit {:?}). The blocking thread is left to finish in the background.",
+                        camera_name, in_phase, label, settings.timeout
+                    );
+                    return Err(anyhow!("Camera '{}': timed out after {:.0}s while {}", camera_name, in_phase.as_secs_f32(), label));
+                }
+                info!("⏳ Still waiting on '{}': {}, {:.0}s elapsed.", camera_name, label, in_phase.as_secs_f32());
+            }
+        }
+    }
+}
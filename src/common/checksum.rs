@@ -0,0 +1,70 @@
+use crate::common::session::SessionManifest;
+use crate::config_loader::AppSettings;
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// SHA-256 of a file as lowercase hex, read in chunks so large videos don't need to fit in memory.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// With `application.checksums` enabled, hashes every file the session's cameras produced and
+/// records the digests in the manifest's `checksums`. Runs before the manifest is finished, so
+/// hooks and uploads see them. A file that can't be hashed is noted in the journal.
+pub async fn record_session_checksums(app: &AppSettings, session: &mut SessionManifest) {
+    if !app.checksums.unwrap_or(false) {
+        return;
+    }
+    let pending: Vec<PathBuf> = session.cameras.iter()
+        .flat_map(|camera| camera.files.iter())
+        .filter(|path| !session.checksums.contains_key(*path))
+        .cloned()
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+    let start = Instant::now();
+    let count = pending.len();
+    let hashed = tokio::task::spawn_blocking(move || {
+        pending.into_iter().map(|path| {
+            let digest = sha256_file(&path);
+            (path, digest)
+        }).collect::<Vec<_>>()
+    }).await;
+    let hashed = match hashed {
+        Ok(hashed) => hashed,
+        Err(e) => {
+            warn!("⚠️ Checksum task failed: {}", e);
+            session.event("warn", None, format!("Checksums not computed: {}", e));
+            return;
+        }
+    };
+    for (path, digest) in hashed {
+        match digest {
+            Ok(digest) => {
+                debug!("SHA-256 {} {}", digest, path.display());
+                session.checksums.insert(path, digest);
+            }
+            Err(e) => {
+                warn!("⚠️ Could not checksum {}: {:#}", path.display(), e);
+                session.event("warn", None, format!("Could not checksum {}: {:#}", path.display(), e));
+            }
+        }
+    }
+    info!("🔏 SHA-256 recorded for {} file(s) in {:?}.", count, start.elapsed());
+}
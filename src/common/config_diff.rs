@@ -0,0 +1,171 @@
+use crate::config_loader::MasterConfig;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// One setting that differs between two configurations. `None` means unset.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingChange {
+    pub path: String, // "application.video_fps", "cameras.cam1.rtsp_path", ...
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// What changed from one configuration to another, for `rcam validate` and config reloads.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigDiff {
+    pub added_cameras: Vec<String>,
+    pub removed_cameras: Vec<String>,
+    pub changed: Vec<SettingChange>,
+}
+
+impl ConfigDiff {
+    pub fn between(old: &MasterConfig, new: &MasterConfig) -> Result<Self> {
+        let mut diff = ConfigDiff::default();
+        let old_app = serde_json::to_value(&old.application).context("Failed to serialize the old configuration")?;
+        let new_app = serde_json::to_value(&new.application).context("Failed to serialize the new configuration")?;
+        diff_values("application", Some(&old_app), Some(&new_app), &mut diff.changed);
+        if old.active_profile != new.active_profile {
+            diff.changed.push(SettingChange {
+                path: "profile".to_string(),
+                old: old.active_profile.clone().map(Value::from),
+                new: new.active_profile.clone().map(Value::from),
+            });
+        }
+
+        let old_cameras = cameras_by_name(old)?;
+        let new_cameras = cameras_by_name(new)?;
+        for (name, (old_kind, old_settings)) in &old_cameras {
+            match new_cameras.get(name) {
+                None => diff.removed_cameras.push(name.clone()),
+                Some((new_kind, new_settings)) => {
+                    if old_kind != new_kind {
+                        diff.changed.push(SettingChange {
+                            path: format!("cameras.{}.type", name),
+                            old: Some(Value::from(old_kind.as_str())),
+                            new: Some(Value::from(new_kind.as_str())),
+                        });
+                    }
+                    diff_values(&format!("cameras.{}", name), Some(old_settings), Some(new_settings), &mut diff.changed);
+                }
+            }
+        }
+        diff.added_cameras = new_cameras.keys().filter(|name| !old_cameras.contains_key(*name)).cloned().collect();
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_cameras.is_empty() && self.removed_cameras.is_empty() && self.changed.is_empty()
+    }
+
+    /// The parts of rcam the changes touch, so an operator can tell what a reload restarts.
+    pub fn affected_subsystems(&self) -> Vec<String> {
+        let mut affected = BTreeSet::new();
+        for name in self.added_cameras.iter().chain(&self.removed_cameras) {
+            affected.insert(format!("camera '{}'", name));
+        }
+        for change in &self.changed {
+            affected.insert(subsystem(&change.path));
+        }
+        affected.into_iter().collect()
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "  (no changes)");
+        }
+        for name in &self.added_cameras {
+            writeln!(f, "  + camera '{}'", name)?;
+        }
+        for name in &self.removed_cameras {
+            writeln!(f, "  - camera '{}'", name)?;
+        }
+        let show = |value: &Option<Value>| value.as_ref().map_or("(unset)".to_string(), |v| v.to_string());
+        for change in &self.changed {
+            writeln!(f, "  ~ {}: {} -> {}", change.path, show(&change.old), show(&change.new))?;
+        }
+        Ok(())
+    }
+}
+
+/// Camera name -> (device type, settings). Cameras serialize as `{"IpCamera": {...}}`.
+fn cameras_by_name(config: &MasterConfig) -> Result<BTreeMap<String, (String, Value)>> {
+    let mut cameras = BTreeMap::new();
+    for camera in &config.cameras {
+        let value = serde_json::to_value(camera).with_context(|| format!("Failed to serialize camera '{}'", camera.get_name()))?;
+        let (kind, settings) = match value {
+            Value::Object(map) if map.len() == 1 => map.into_iter().next().unwrap_or_default(),
+            other => (String::new(), other),
+        };
+        cameras.insert(camera.get_name().clone(), (kind, settings));
+    }
+    Ok(cameras)
+}
+
+/// Records every leaf that differs. Maps are compared key by key; lists and scalars as a
+/// whole. A null and a missing key are the same thing (an unset `Option`).
+fn diff_values(path: &str, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<SettingChange>) {
+    let old = old.filter(|v| !v.is_null());
+    let new = new.filter(|v| !v.is_null());
+    match (old, new) {
+        (Some(Value::Object(old_map)), Some(Value::Object(new_map))) => {
+            let keys: BTreeSet<&String> = old_map.keys().chain(new_map.keys()).collect();
+            for key in keys {
+                diff_values(&format!("{}.{}", path, key), old_map.get(key), new_map.get(key), changes);
+            }
+        }
+        // A whole section appearing or disappearing is reported per setting.
+        (Some(Value::Object(old_map)), None) => {
+            for (key, value) in old_map {
+                diff_values(&format!("{}.{}", path, key), Some(value), None, changes);
+            }
+        }
+        (None, Some(Value::Object(new_map))) => {
+            for (key, value) in new_map {
+                diff_values(&format!("{}.{}", path, key), None, Some(value), changes);
+            }
+        }
+        (old, new) if old != new => {
+            changes.push(SettingChange { path: path.to_string(), old: old.cloned(), new: new.cloned() });
+        }
+        _ => {}
+    }
+}
+
+/// Subsystem a setting belongs to, by its path.
+fn subsystem(path: &str) -> String {
+    let mut parts = path.split('.');
+    let (section, key) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    if section == "cameras" {
+        let setting = parts.next().unwrap_or_default();
+        return if setting == "schedule" { "daemon schedules".to_string() } else { format!("camera '{}'", key) };
+    }
+    if section == "profile" {
+        return "profile".to_string();
+    }
+    let name = match key {
+        k if k.starts_with("rerun_") => "Rerun logging",
+        "storage" => "remote storage uploads",
+        "post_capture_hooks" => "post-capture hooks",
+        "supervisor" => "task supervisor",
+        "opencv_threads" => "OpenCV worker pool (on restart)",
+        "max_concurrent_streams" | "capture_retries" | "capture_retry_delay_secs" | "start_at" | "start_offset_ms" => "capture scheduling",
+        "latitude" | "longitude" => "daemon schedules",
+        "connect_timeout_secs" | "read_timeout_secs" | "http_timeout_secs" | "blocking_timeout_secs" | "heartbeat_interval_secs" | "rtsp_flush_frames"
+        | "rtsp_transport" | "operation_streams" | "realsense_start_stagger_ms" | "realsense_reconnect_wait_secs" => "camera connections",
+        "image_format" | "jpeg_quality" | "png_compression" | "skip_unchanged" | "unchanged_max_distance"
+        | "embed_metadata" | "quality_checks" => "image capture",
+        k if k.starts_with("video_") => "video recording",
+        "max_file_size_mb" | "stall_timeout_secs" | "max_reconnects" | "min_stream_kbps" | "http_fallback" | "frame_timestamps" | "overlay" | "session_mkv" | "ffmpeg_path" => "video recording",
+        "ntp_server" | "require_host_clock_sync" | "time_sync_tolerance_seconds" => "time sync",
+        "output_directory_base" | "filename_timestamp_format" | "min_free_disk_mb" => "output files",
+        "log_level" | "log_levels" | "log_format" | "log_file" | "log_rotation" | "log_max_files" | "log_max_size_mb" => "logging",
+        "agent" | "coordinator" => "multi-host coordination",
+        _ => "general settings",
+    };
+    name.to_string()
+}
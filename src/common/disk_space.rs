@@ -0,0 +1,43 @@
+use crate::config_loader::AppSettings;
+use crate::core::events::{self, Event};
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Free space below which captures refuse to start and recordings stop.
+pub const DEFAULT_MIN_FREE_DISK_MB: u64 = 1024;
+
+const MB: u64 = 1024 * 1024;
+
+/// Configured free-space floor in bytes; 0 disables the guard.
+pub fn min_free_bytes(app: &AppSettings) -> u64 {
+    app.min_free_disk_mb.unwrap_or(DEFAULT_MIN_FREE_DISK_MB) * MB
+}
+
+/// Bytes available to this user on the filesystem holding `path`. The path does not need to
+/// exist yet; its nearest existing ancestor is measured.
+pub fn available_bytes(path: &Path) -> Result<u64> {
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(Path::new("."));
+    fs2::available_space(existing).with_context(|| format!("Failed to query free disk space for {}", existing.display()))
+}
+
+/// Fails when less than `min_free_bytes` is available under `path`, so a capture never starts
+/// on a nearly full disk.
+pub fn ensure_free_space(path: &Path, min_free_bytes: u64) -> Result<()> {
+    if min_free_bytes == 0 {
+        return Ok(());
+    }
+    let available = available_bytes(path)?;
+    if available < min_free_bytes {
+        events::publish(Event::DiskLow {
+            at: chrono::Utc::now(),
+            path: path.to_path_buf(),
+            available_mb: available / MB,
+            min_free_mb: min_free_bytes / MB,
+        });
+        bail!(
+            "Only {} MB free on the disk holding {} (min_free_disk_mb is {} MB); free up space or change the output directory",
+            available / MB, path.display(), min_free_bytes / MB
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use crate::config_loader::AppSettings;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::process::Command;
+
+const FFMPEG_TIMEOUT: Duration = Duration::from_secs(5);
+
+// librealsense is linked through realsense-rust; only the version query is needed here.
+extern "C" {
+    fn rs2_get_api_version(error: *mut *mut std::ffi::c_void) -> std::ffi::c_int;
+}
+
+/// The software rcam runs on, for attaching to support tickets.
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvironmentInfo {
+    pub rcam_version: String,
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    pub opencv_version: String,
+    pub opencv_ffmpeg: Option<String>, // the FFMPEG line of the OpenCV build information, e.g. "YES (58.134.100)"
+    pub ffmpeg: String, // `ffmpeg -version` first line, or why it could not be run
+    pub librealsense_version: String,
+}
+
+impl EnvironmentInfo {
+    pub async fn collect(app_config: &AppSettings) -> Self {
+        let build_info = opencv::core::get_build_information().unwrap_or_default();
+        EnvironmentInfo {
+            rcam_version: env!("CARGO_PKG_VERSION").to_string(),
+            hostname: crate::server::hostname(),
+            os: os_release().unwrap_or_else(|| std::env::consts::OS.to_string()),
+            arch: std::env::consts::ARCH.to_string(),
+            opencv_version: opencv::core::get_version_string().unwrap_or_else(|e| format!("unknown ({})", e)),
+            opencv_ffmpeg: build_info.lines()
+                .find(|line| line.trim_start().starts_with("FFMPEG:"))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, value)| value.trim().to_string()),
+            ffmpeg: ffmpeg_version(app_config.ffmpeg_path.as_deref().unwrap_or("ffmpeg")).await
+                .unwrap_or_else(|e| format!("unavailable: {}", e)),
+            librealsense_version: librealsense_version(),
+        }
+    }
+
+    /// Name/value pairs in display order.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("rcam", self.rcam_version.clone()),
+            ("host", self.hostname.clone()),
+            ("os", format!("{} ({})", self.os, self.arch)),
+            ("OpenCV", self.opencv_version.clone()),
+            ("OpenCV FFmpeg backend", self.opencv_ffmpeg.clone().unwrap_or_else(|| "not reported".to_string())),
+            ("ffmpeg", self.ffmpeg.clone()),
+            ("librealsense", self.librealsense_version.clone()),
+        ]
+    }
+}
+
+fn os_release() -> Option<String> {
+    let text = std::fs::read_to_string("/etc/os-release").ok()?;
+    text.lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+async fn ffmpeg_version(ffmpeg_path: &str) -> Result<String, String> {
+    let output = tokio::time::timeout(FFMPEG_TIMEOUT, Command::new(ffmpeg_path).arg("-version").output())
+        .await
+        .map_err(|_| format!("'{}' did not answer within {:?}", ffmpeg_path, FFMPEG_TIMEOUT))?
+        .map_err(|e| format!("'{}': {}", ffmpeg_path, e))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    match text.lines().next() {
+        Some(first) if output.status.success() => Ok(first.trim().to_string()),
+        _ => Err(format!("'{} -version' exited with {}", ffmpeg_path, output.status)),
+    }
+}
+
+fn librealsense_version() -> String {
+    let mut error = std::ptr::null_mut();
+    // SAFETY: takes only an out-pointer for an error, which stays null on success.
+    let version = unsafe { rs2_get_api_version(&mut error) };
+    if !error.is_null() || version <= 0 {
+        return "unknown".to_string();
+    }
+    // Encoded as major * 10000 + minor * 100 + patch.
+    format!("{}.{}.{}", version / 10000, (version / 100) % 100, version % 100)
+}
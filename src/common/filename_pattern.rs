@@ -0,0 +1,105 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// A filename template such as `{camera}_{timestamp}.{ext}`.
+///
+/// Placeholders: `{camera}`, `{timestamp}` (parsed with the given chrono format, local time),
+/// `{ext}` and `{*}` (anything). Everything else must match literally.
+#[derive(Debug, Clone)]
+pub struct FilenamePattern {
+    pub source: String,
+    tokens: Vec<Token>,
+    timestamp_format: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    Camera,
+    Timestamp,
+    Ext,
+    Any,
+}
+
+/// Fields extracted from a matching filename.
+#[derive(Debug, Clone, Default)]
+pub struct FilenameMatch {
+    pub camera: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub ext: Option<String>,
+}
+
+impl FilenamePattern {
+    pub fn parse(pattern: &str, timestamp_format: &str) -> Result<Self> {
+        let mut tokens = Vec::new();
+        let mut rest = pattern;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                tokens.push(Token::Literal(rest[..open].to_string()));
+            }
+            let Some(close) = rest[open..].find('}') else { bail!("Unclosed '{{' in filename pattern '{}'", pattern) };
+            tokens.push(match &rest[open + 1..open + close] {
+                "camera" => Token::Camera,
+                "timestamp" => Token::Timestamp,
+                "ext" => Token::Ext,
+                "*" => Token::Any,
+                other => bail!("Unknown placeholder '{{{}}}' in filename pattern '{}' (use camera, timestamp, ext or *)", other, pattern),
+            });
+            rest = &rest[open + close + 1..];
+        }
+        if !rest.is_empty() {
+            tokens.push(Token::Literal(rest.to_string()));
+        }
+        if tokens.windows(2).any(|w| !matches!(w[0], Token::Literal(_)) && !matches!(w[1], Token::Literal(_))) {
+            bail!("Placeholders in filename pattern '{}' must be separated by literal text", pattern);
+        }
+        Ok(FilenamePattern { source: pattern.to_string(), tokens, timestamp_format: timestamp_format.to_string() })
+    }
+
+    /// Matches a bare filename. Placeholders take the shortest text that lets the rest match,
+    /// so camera names may contain the separator as long as the timestamp still parses.
+    pub fn matches(&self, filename: &str) -> Option<FilenameMatch> {
+        let mut found = FilenameMatch::default();
+        self.match_from(0, filename, &mut found).then_some(found)
+    }
+
+    fn match_from(&self, index: usize, text: &str, found: &mut FilenameMatch) -> bool {
+        let Some(token) = self.tokens.get(index) else { return text.is_empty() };
+        if let Token::Literal(literal) = token {
+            return text.strip_prefix(literal.as_str()).is_some_and(|rest| self.match_from(index + 1, rest, found));
+        }
+        for end in (1..=text.len()).filter(|&end| text.is_char_boundary(end)) {
+            let value = &text[..end];
+            let mut attempt = found.clone();
+            let accepted = match token {
+                Token::Camera => {
+                    attempt.camera = Some(value.to_string());
+                    true
+                }
+                Token::Timestamp => match self.parse_timestamp(value) {
+                    Some(ts) => {
+                        attempt.timestamp = Some(ts);
+                        true
+                    }
+                    None => false,
+                },
+                Token::Ext => {
+                    attempt.ext = Some(value.to_lowercase());
+                    true
+                }
+                _ => true,
+            };
+            if accepted && self.match_from(index + 1, &text[end..], &mut attempt) {
+                *found = attempt;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn parse_timestamp(&self, value: &str) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(value, &self.timestamp_format).ok()
+            .or_else(|| NaiveDate::parse_from_str(value, &self.timestamp_format).ok().and_then(|d| d.and_hms_opt(0, 0, 0)))?;
+        Local.from_local_datetime(&naive).earliest().map(|ts| ts.with_timezone(&Utc))
+    }
+}
@@ -0,0 +1,96 @@
+use crate::common::session::SessionManifest;
+use crate::config_loader::{AppSettings, PostCaptureHook};
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, error, info, warn};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+const DEFAULT_HOOK_TIMEOUT_SECS: f32 = 30.0;
+
+/// Runs the configured `post_capture_hooks` that apply to `kind` ("image" or "video") after a
+/// session has finished, before any upload. Each hook gets the session's files and metadata:
+/// a command runs under `sh -c` with the file paths as `$1..$n` and `RCAM_*` environment
+/// variables; a URL receives the same data as a JSON POST. Outcomes are added to the session
+/// journal. A failing hook is only a warning unless it sets `required: true`.
+pub async fn run_post_capture_hooks(app: &AppSettings, session: &mut SessionManifest, kind: &str) -> Result<()> {
+    let Some(hooks) = &app.post_capture_hooks else {
+        return Ok(());
+    };
+    let hooks: Vec<&PostCaptureHook> = hooks.iter().filter(|hook| hook.applies_to(kind)).collect();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let mut required_failures = Vec::new();
+    for (idx, hook) in hooks.into_iter().enumerate() {
+        let label = hook.name.clone().unwrap_or_else(|| format!("hook #{}", idx + 1));
+        let timeout = Duration::from_secs_f32(hook.timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+        let hook_start = Instant::now();
+        let outcome = match tokio::time::timeout(timeout, run_hook(hook, session, kind)).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!("timed out after {:?}", timeout)),
+        };
+        match outcome {
+            Ok(()) => {
+                info!("🪝 Post-capture hook '{}' finished in {:?}.", label, hook_start.elapsed());
+                session.event("info", None, format!("Post-capture hook '{}' succeeded", label));
+            }
+            Err(e) if hook.required.unwrap_or(false) => {
+                error!("❌ Required post-capture hook '{}' failed: {:#}", label, e);
+                session.event("error", None, format!("Post-capture hook '{}' failed: {:#}", label, e));
+                required_failures.push(label);
+            }
+            Err(e) => {
+                warn!("⚠️ Post-capture hook '{}' failed: {:#}", label, e);
+                session.event("warn", None, format!("Post-capture hook '{}' failed: {:#}", label, e));
+            }
+        }
+    }
+    session.write()?;
+    if !required_failures.is_empty() {
+        bail!("Required post-capture hook(s) failed: {}", required_failures.join(", "));
+    }
+    Ok(())
+}
+
+async fn run_hook(hook: &PostCaptureHook, session: &SessionManifest, kind: &str) -> Result<()> {
+    let files: Vec<(&str, String)> = session.cameras.iter()
+        .flat_map(|camera| camera.files.iter().map(move |path| (camera.name.as_str(), path.display().to_string())))
+        .collect();
+
+    if let Some(command) = &hook.command {
+        let cameras: Vec<&str> = session.cameras.iter().filter(|c| !c.files.is_empty()).map(|c| c.name.as_str()).collect();
+        let mut cmd = Command::new("sh");
+        // "rcam" becomes $0, so the files are $1..$n.
+        cmd.arg("-c").arg(command).arg("rcam").args(files.iter().map(|(_, path)| path));
+        cmd.env("RCAM_KIND", kind)
+            .env("RCAM_SESSION_ID", &session.session_id)
+            .env("RCAM_SESSION_DIR", &session.output_dir)
+            .env("RCAM_MANIFEST", session.manifest_path())
+            .env("RCAM_CAMERAS", cameras.join(","))
+            .env("RCAM_FILES", files.iter().map(|(_, path)| path.as_str()).collect::<Vec<_>>().join("\n"))
+            .kill_on_drop(true); // a timed-out hook is killed rather than left running
+        debug!("Post-capture hook: running {:?}", cmd);
+        let output = cmd.output().await.context("Failed to start sh")?;
+        if !output.status.success() {
+            bail!("command exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+        }
+    }
+
+    if let Some(url) = &hook.url {
+        let body = json!({
+            "kind": kind,
+            "session_id": session.session_id,
+            "session_dir": session.output_dir,
+            "manifest": session.manifest_path(),
+            "files": files.iter().map(|(camera, path)| json!({ "camera": camera, "path": path })).collect::<Vec<_>>(),
+        });
+        let response = reqwest::Client::new().post(url).json(&body).send().await
+            .with_context(|| format!("POST to {} failed", url))?;
+        if !response.status().is_success() {
+            bail!("POST to {} returned HTTP {}", url, response.status());
+        }
+    }
+    Ok(())
+}
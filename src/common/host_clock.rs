@@ -0,0 +1,156 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::debug;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::process::Command;
+
+// Seconds between the NTP epoch (1900) and the Unix epoch (1970).
+const NTP_UNIX_EPOCH_DELTA: f64 = 2_208_988_800.0;
+
+/// Health of the host clock, the reference `verify-times` compares cameras against.
+#[derive(Debug, Clone)]
+pub struct HostClockStatus {
+    pub source: String,               // how the status was determined ("sntp <server>", "timedatectl", "chronyc", "unknown")
+    pub synchronized: Option<bool>,   // None when no source could tell
+    pub offset_secs: Option<f64>,     // host minus reference, when the source reports it
+    pub detail: String,
+}
+
+impl HostClockStatus {
+    fn unknown(detail: String) -> Self {
+        HostClockStatus { source: "unknown".to_string(), synchronized: None, offset_secs: None, detail }
+    }
+}
+
+/// Determines host clock health. With `ntp_server` configured, the host is queried against it
+/// directly over SNTP; otherwise timedatectl and then chronyc are asked.
+pub async fn check_host_clock(ntp_server: Option<&str>, max_offset: Duration, timeout: Duration) -> HostClockStatus {
+    if let Some(server) = ntp_server {
+        return match sntp_offset(server, timeout).await {
+            Ok(offset) => HostClockStatus {
+                source: format!("sntp {}", server),
+                synchronized: Some(offset.abs() <= max_offset.as_secs_f64()),
+                offset_secs: Some(offset),
+                detail: format!("offset {:+.3}s (limit ±{:.3}s)", offset, max_offset.as_secs_f64()),
+            },
+            Err(e) => HostClockStatus::unknown(format!("SNTP query to {} failed: {:#}", server, e)),
+        };
+    }
+
+    match timedatectl_status(timeout).await {
+        Ok(status) => return status,
+        Err(e) => debug!("Host clock: timedatectl unavailable: {:#}", e),
+    }
+    match chronyc_status(max_offset, timeout).await {
+        Ok(status) => return status,
+        Err(e) => debug!("Host clock: chronyc unavailable: {:#}", e),
+    }
+    HostClockStatus::unknown("neither timedatectl nor chronyc is available and no ntp_server is configured".to_string())
+}
+
+async fn run_command(program: &str, args: &[&str], timeout: Duration) -> Result<String> {
+    let output = tokio::time::timeout(timeout, Command::new(program).args(args).output())
+        .await
+        .map_err(|_| anyhow!("{} timed out after {:?}", program, timeout))?
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!("{} exited with {}", program, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+async fn timedatectl_status(timeout: Duration) -> Result<HostClockStatus> {
+    let value = run_command("timedatectl", &["show", "--property=NTPSynchronized", "--value"], timeout).await?;
+    let synchronized = match value.trim() {
+        "yes" => true,
+        "no" => false,
+        other => bail!("unexpected NTPSynchronized value '{}'", other),
+    };
+    Ok(HostClockStatus {
+        source: "timedatectl".to_string(),
+        synchronized: Some(synchronized),
+        offset_secs: None,
+        detail: format!("NTPSynchronized={}", value.trim()),
+    })
+}
+
+async fn chronyc_status(max_offset: Duration, timeout: Duration) -> Result<HostClockStatus> {
+    // Relevant lines: "System time     : 0.000012345 seconds fast of NTP time", "Leap status     : Normal"
+    let text = run_command("chronyc", &["tracking"], timeout).await?;
+    let field = |name: &str| {
+        text.lines()
+            .find(|l| l.trim_start().starts_with(name))
+            .and_then(|l| l.split_once(':'))
+            .map(|(_, v)| v.trim().to_string())
+    };
+    let leap_status = field("Leap status").ok_or_else(|| anyhow!("no 'Leap status' in chronyc output"))?;
+    let offset_secs = field("System time").and_then(|v| {
+        let mut parts = v.split_whitespace();
+        let magnitude: f64 = parts.next()?.parse().ok()?;
+        let direction = parts.nth(1)?;
+        Some(if direction == "slow" { -magnitude } else { magnitude })
+    });
+    let synchronized = leap_status != "Not synchronised"
+        && offset_secs.is_none_or(|o| o.abs() <= max_offset.as_secs_f64());
+    Ok(HostClockStatus {
+        source: "chronyc".to_string(),
+        synchronized: Some(synchronized),
+        offset_secs,
+        detail: format!("leap status '{}'", leap_status),
+    })
+}
+
+fn unix_now_secs() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0)
+}
+
+fn read_ntp_timestamp(bytes: &[u8]) -> f64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as f64 / 4_294_967_296.0;
+    secs + frac - NTP_UNIX_EPOCH_DELTA
+}
+
+/// Resolves `server` to a socket address, defaulting to the NTP port. Accepts "host", "host:port",
+/// bare IPv4/IPv6 literals and "[v6]:port".
+async fn sntp_address(server: &str) -> Result<SocketAddr> {
+    if let Ok(address) = server.parse::<SocketAddr>() {
+        return Ok(address);
+    }
+    let bare = server.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, 123));
+    }
+    let host = if server.contains(':') { server.to_string() } else { format!("{}:123", server) };
+    lookup_host(&host).await
+        .with_context(|| format!("Failed to resolve {}", host))?
+        .next()
+        .ok_or_else(|| anyhow!("{} did not resolve to any address", host))
+}
+
+/// Single SNTP (RFC 4330) exchange; returns the host clock offset in seconds (positive = host ahead).
+async fn sntp_offset(server: &str, timeout: Duration) -> Result<f64> {
+    let address = sntp_address(server).await?;
+    let local = if address.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+    let socket = UdpSocket::bind(local).await.context("Failed to bind UDP socket")?;
+    socket.connect(address).await.with_context(|| format!("Failed to connect to {}", address))?;
+
+    let mut request = [0u8; 48];
+    request[0] = 0x1B; // LI = 0, VN = 3, Mode = 3 (client)
+    let t1 = unix_now_secs();
+    socket.send(&request).await.context("Failed to send SNTP request")?;
+
+    let mut response = [0u8; 48];
+    let received = tokio::time::timeout(timeout, socket.recv(&mut response))
+        .await
+        .map_err(|_| anyhow!("no SNTP response from {} within {:?}", address, timeout))??;
+    let t4 = unix_now_secs();
+    if received < 48 {
+        bail!("short SNTP response ({} bytes)", received);
+    }
+    let t2 = read_ntp_timestamp(&response[32..40]);
+    let t3 = read_ntp_timestamp(&response[40..48]);
+    // Server-minus-host offset per RFC 4330; negate so positive means the host is ahead.
+    let server_offset = ((t2 - t1) + (t3 - t4)) / 2.0;
+    Ok(-server_offset)
+}
@@ -1,36 +1,273 @@
-use env_logger::Builder;
-use log::LevelFilter;
 use crate::config_loader::MasterConfig;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use opencv::core::LogLevel;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::Span;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Directive;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
 
-pub fn initialize_logging(config: Option<&MasterConfig>, cli_matches: &clap::ArgMatches) -> Result<()> {
-    let mut builder = Builder::new();
+/// `log_levels` keys that set the level of a native library rather than of a Rust module.
+const OPENCV_TARGET: &str = "opencv";
+const FFMPEG_TARGET: &str = "ffmpeg";
 
-    // Configure logger to include timestamps
-    builder.format_timestamp_micros();
+/// Rotated log files kept when `log_max_files` is not set.
+const DEFAULT_MAX_LOG_FILES: usize = 14;
 
+/// Logging choices from the command line; each overrides the matching config setting.
+#[derive(Debug, Clone, Default)]
+pub struct LoggingOptions {
+    pub verbose: u8, // -v: debug, -vv: trace
+    pub quiet: bool, // errors only, including from OpenCV and FFmpeg
+    pub log_file: Option<String>,
+    pub log_format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json, // one object per line, with the active operation and camera spans
+}
+
+impl LogFormat {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            None | Some("text") => Ok(LogFormat::Text),
+            Some("json") => Ok(LogFormat::Json),
+            Some(other) => bail!("Unknown log format '{}'; expected \"text\" or \"json\"", other),
+        }
+    }
+}
+
+/// Checks a `log_level`/`log_levels` value and returns it in lower case.
+pub fn parse_level(value: &str) -> Result<String> {
+    let level = value.trim().to_lowercase();
+    match level.as_str() {
+        "off" | "error" | "warn" | "info" | "debug" | "trace" => Ok(level),
+        _ => bail!("Unknown log level '{}'; expected off, error, warn, info, debug or trace", value),
+    }
+}
+
+/// The filter directive for one `log_levels` entry, e.g. `rcam::camera=debug`. None for the
+/// native library keys, which are applied by `apply_native_levels`.
+pub fn module_directive(target: &str, level: &str) -> Result<Option<Directive>> {
+    let level = parse_level(level)?;
+    let target = target.trim();
+    if target.is_empty() {
+        bail!("Empty module name");
+    }
+    if target == OPENCV_TARGET || target == FFMPEG_TARGET {
+        return Ok(None);
+    }
+    let directive = format!("{}={}", target, level).parse::<Directive>()
+        .with_context(|| format!("'{}' is not a module path", target))?;
+    Ok(Some(directive))
+}
+
+/// Parses `log_rotation`: "daily" (default), "hourly" or "never".
+pub fn parse_rotation(value: Option<&str>) -> Result<Rotation> {
+    match value.map(|v| v.trim().to_lowercase()).as_deref() {
+        None | Some("daily") => Ok(Rotation::DAILY),
+        Some("hourly") => Ok(Rotation::HOURLY),
+        Some("never") => Ok(Rotation::NEVER),
+        Some(other) => bail!("Unknown log rotation '{}'; expected \"daily\", \"hourly\" or \"never\"", other),
+    }
+}
+
+/// Installs the `tracing` subscriber. Everything logged through the `log` macros is forwarded
+/// to it, so each line carries the operation and camera spans it was logged in. Logs go to
+/// stderr and, with a log file, also to that file, rotated by `log_rotation`.
+///
+/// The level is `log_level` (or debug with `-v`, trace with `-vv`), refined per module by
+/// `log_levels`. A non-empty `RUST_LOG` replaces both, as with any Rust program. `--quiet`
+/// overrides all of them: only errors are logged, from rcam and from the native libraries.
+pub fn initialize_logging(config: Option<&MasterConfig>, options: &LoggingOptions) -> Result<()> {
+    let app = config.map(|c| &c.application);
     // Determine log level from CLI, then config, then default
-    let log_level_str = if cli_matches.get_flag("debug") {
+    let level = if options.quiet {
+        "error".to_string()
+    } else if options.verbose >= 2 {
+        "trace".to_string()
+    } else if options.verbose == 1 {
         "debug".to_string()
     } else {
-        config.map_or_else(
-            || "info".to_string(), // Default if config is None
-            |c| c.application.log_level.clone().unwrap_or_else(|| "info".to_string()) // Use log_level from config if Some, else default to "info"
-        )
+        app.and_then(|a| a.log_level.clone()).unwrap_or_else(|| "info".to_string()).to_lowercase()
     };
-
-    match log_level_str.to_lowercase().as_str() {
-        "error" => builder.filter_level(LevelFilter::Error),
-        "warn" => builder.filter_level(LevelFilter::Warn),
-        "info" => builder.filter_level(LevelFilter::Info),
-        "debug" => builder.filter_level(LevelFilter::Debug),
-        "trace" => builder.filter_level(LevelFilter::Trace),
-        s => {
-            log::warn!("Unrecognized log level '{}', defaulting to info.", s);
-            builder.filter_level(LevelFilter::Info)
-        }
+    let (mut filter, unknown_level) = match parse_level(&level) {
+        Ok(level) => (EnvFilter::new(level), None),
+        Err(_) => (EnvFilter::new("info"), Some(level.clone())),
     };
+    let quiet_levels: BTreeMap<String, String> = [OPENCV_TARGET, FFMPEG_TARGET].iter().map(|t| (t.to_string(), "error".to_string())).collect();
+    let log_levels = if options.quiet { Some(&quiet_levels) } else { app.and_then(|a| a.log_levels.as_ref()) };
+    let rust_log = std::env::var("RUST_LOG").ok().filter(|v| !v.trim().is_empty() && !options.quiet);
+    match &rust_log {
+        Some(directives) => filter = EnvFilter::try_new(directives).with_context(|| format!("Invalid RUST_LOG '{}'", directives))?,
+        None => {
+            for (target, level) in log_levels.into_iter().flatten() {
+                if let Some(directive) = module_directive(target, level)? {
+                    filter = filter.add_directive(directive);
+                }
+            }
+        }
+    }
+    if let Some(levels) = log_levels {
+        apply_native_levels(levels);
+    }
+    let format = LogFormat::parse(options.log_format.as_deref().or(app.and_then(|a| a.log_format.as_deref())))?;
 
-    builder.try_init().context("Failed to initialize logger")?;
+    let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = vec![match format {
+        LogFormat::Text => fmt::layer().with_writer(std::io::stderr).boxed(),
+        LogFormat::Json => fmt::layer().json().with_current_span(true).with_span_list(true).with_writer(std::io::stderr).boxed(),
+    }];
+    let log_file = options.log_file.as_deref().or(app.and_then(|a| a.log_file.as_deref()));
+    if let Some(path) = log_file {
+        let max_files = app.and_then(|a| a.log_max_files).unwrap_or(DEFAULT_MAX_LOG_FILES);
+        let writer = match app.and_then(|a| a.log_max_size_mb) {
+            Some(max_mb) => BoxMakeWriter::new(Mutex::new(SizeRotatingFile::open(Path::new(path), max_mb * 1024 * 1024, max_files)?)),
+            None => BoxMakeWriter::new(file_appender(Path::new(path), parse_rotation(app.and_then(|a| a.log_rotation.as_deref()))?, max_files)?),
+        };
+        layers.push(match format {
+            LogFormat::Text => fmt::layer().with_ansi(false).with_writer(writer).boxed(),
+            LogFormat::Json => fmt::layer().json().with_current_span(true).with_span_list(true).with_writer(writer).boxed(),
+        });
+    }
+
+    tracing_subscriber::registry().with(layers).with(filter).try_init().context("Failed to initialize logger")?;
+    if let Some(level) = unknown_level {
+        log::warn!("Unrecognized log level '{}', defaulting to info.", level);
+    }
+    if let Some(directives) = rust_log {
+        log::debug!("Log levels taken from RUST_LOG ({}); log_level and log_levels are ignored.", directives);
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// OpenCV and FFmpeg log straight to stderr, past the subscriber. OpenCV's level is set through
+/// its API; FFmpeg's is read by OpenCV from `OPENCV_FFMPEG_LOGLEVEL` when its first stream
+/// opens, which is after logging is set up.
+fn apply_native_levels(levels: &BTreeMap<String, String>) {
+    if let Some(level) = levels.get(OPENCV_TARGET).and_then(|l| parse_level(l).ok()) {
+        let opencv_level = match level.as_str() {
+            "off" => LogLevel::LOG_LEVEL_SILENT,
+            "error" => LogLevel::LOG_LEVEL_ERROR,
+            "warn" => LogLevel::LOG_LEVEL_WARNING,
+            "info" => LogLevel::LOG_LEVEL_INFO,
+            "debug" => LogLevel::LOG_LEVEL_DEBUG,
+            _ => LogLevel::LOG_LEVEL_VERBOSE,
+        };
+        if let Err(e) = opencv::core::set_log_level(opencv_level) {
+            eprintln!("Failed to set the OpenCV log level: {}", e);
+        }
+    }
+    if let Some(level) = levels.get(FFMPEG_TARGET).and_then(|l| parse_level(l).ok()) {
+        // AV_LOG_QUIET, AV_LOG_ERROR, AV_LOG_WARNING, AV_LOG_INFO, AV_LOG_DEBUG, AV_LOG_TRACE
+        let av_level = match level.as_str() {
+            "off" => -8,
+            "error" => 16,
+            "warn" => 24,
+            "info" => 32,
+            "debug" => 48,
+            _ => 56,
+        };
+        std::env::set_var("OPENCV_FFMPEG_LOGLEVEL", av_level.to_string());
+    }
+}
+
+/// Appender writing `<dir>/<stem>.<date>.<ext>` (just `path` with rotation "never") and
+/// deleting all but the newest `max_files`. Writes are unbuffered, so nothing is lost when
+/// the process exits with `std::process::exit`.
+fn file_appender(path: &Path, rotation: Rotation, max_files: usize) -> Result<RollingFileAppender> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).ok_or_else(|| anyhow!("Log file '{}' has no file name", path.display()))?;
+    let mut builder = RollingFileAppender::builder().rotation(rotation).filename_prefix(stem).max_log_files(max_files.max(1));
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        builder = builder.filename_suffix(ext);
+    }
+    builder.build(dir).with_context(|| format!("Failed to open log file {}", path.display()))
+}
+
+/// Log file rotated by size: when a line would take `path` past `max_bytes`, it is renamed to
+/// `<stem>.1.<ext>`, older files move up one number, and all but the newest `max_files`
+/// (counting `path` itself) are deleted. Suits daemons, whose logs grow with activity rather
+/// than with time.
+struct SizeRotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    written: u64,
+}
+
+impl SizeRotatingFile {
+    fn open(path: &Path, max_bytes: u64, max_files: usize) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(SizeRotatingFile { path: path.to_path_buf(), max_bytes, max_files: max_files.max(1), file, written })
+    }
+
+    /// `path` with `index` before the extension: logs/rcam.log -> logs/rcam.3.log.
+    fn numbered(&self, index: usize) -> PathBuf {
+        let stem = self.path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let name = match self.path.extension() {
+            Some(ext) => format!("{}.{}.{}", stem, index, ext.to_string_lossy()),
+            None => format!("{}.{}", stem, index),
+        };
+        self.path.with_file_name(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let keep = self.max_files - 1; // rotated files besides the active one
+        let _ = std::fs::remove_file(self.numbered(keep.max(1)));
+        for index in (1..keep).rev() {
+            let _ = std::fs::rename(self.numbered(index), self.numbered(index + 1));
+        }
+        if keep > 0 {
+            std::fs::rename(&self.path, self.numbered(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Lines are written whole, so a line never straddles two files.
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            if let Err(e) = self.rotate() {
+                eprintln!("Failed to rotate log file {}: {}", self.path.display(), e);
+            }
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Span for one run of an operation, e.g. `op{name=capture-video}`.
+pub fn operation_span(name: &str) -> Span {
+    tracing::info_span!("op", name = %name)
+}
+
+/// Span for the work on one camera, e.g. `camera{camera=cam3}`. Lines logged inside it are
+/// attributed to the camera even when several cameras log at once.
+pub fn camera_span(camera: &str) -> Span {
+    tracing::info_span!("camera", camera = %camera)
+}
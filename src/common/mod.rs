@@ -1 +1,20 @@
-pub mod logging_setup;
\ No newline at end of file
+pub mod atomic_file;
+pub mod blocking_watchdog;
+pub mod checksum;
+pub mod config_diff;
+pub mod disk_space;
+pub mod environment;
+pub mod filename_pattern;
+pub mod hooks;
+pub mod host_clock;
+pub mod logging_setup;
+pub mod opencv_pool;
+pub mod path_template;
+pub mod recording_control;
+pub mod rerun_setup;
+pub mod schedule;
+pub mod session;
+pub mod shutdown;
+pub mod sun;
+pub mod sync_start;
+pub mod timing_report;
@@ -0,0 +1,205 @@
+use crate::config_loader::MasterConfig;
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context as TaskContext, Poll};
+use tokio::sync::oneshot;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Fixed set of threads that run blocking OpenCV and RealSense work, so many cameras don't
+/// fan out over tokio's shared (512-thread) blocking pool. A recording holds its thread for its
+/// whole duration, so the pool also bounds how many cameras can record at once.
+struct OpenCvPool {
+    sender: Mutex<mpsc::Sender<Job>>,
+    threads: usize,
+}
+
+static POOL: OnceLock<OpenCvPool> = OnceLock::new();
+
+impl OpenCvPool {
+    fn start(threads: usize) -> Self {
+        let threads = threads.max(1);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for idx in 0..threads {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("rcam-opencv-{}", idx))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap_or_else(|p| p.into_inner()).recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break, // pool dropped
+                    }
+                })
+                .expect("failed to spawn OpenCV worker thread");
+        }
+        debug!("🧵 Started {} OpenCV worker thread(s).", threads);
+        OpenCvPool { sender: Mutex::new(sender), threads }
+    }
+}
+
+/// Threads used when `opencv_threads` is not set: one per configured camera, since every
+/// recording needs its own, but never fewer than the machine's cores.
+pub fn default_threads(master_config: &MasterConfig) -> usize {
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    master_config.cameras.len().max(cores)
+}
+
+/// Sizes the pool from the configuration. Only the first call takes effect; the pool is
+/// process-wide and its threads live until exit.
+pub fn configure(master_config: &MasterConfig) {
+    let threads = master_config.application.opencv_threads.unwrap_or_else(|| default_threads(master_config));
+    let mut started = false;
+    let pool = POOL.get_or_init(|| {
+        started = true;
+        OpenCvPool::start(threads)
+    });
+    if started {
+        info!("🧵 OpenCV worker pool: {} thread(s).", pool.threads);
+    } else if pool.threads != threads {
+        warn!("🧵 OpenCV worker pool already runs {} thread(s); ignoring opencv_threads = {}.", pool.threads, threads);
+    }
+}
+
+fn pool() -> &'static OpenCvPool {
+    POOL.get_or_init(|| OpenCvPool::start(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)))
+}
+
+/// Number of worker threads, i.e. how many blocking camera tasks can run at the same time.
+pub fn threads() -> usize {
+    pool().threads
+}
+
+/// Result of a job queued with `spawn` or `spawn_dedicated`; resolves to an error if the job panicked.
+pub struct PoolTask<T>(oneshot::Receiver<std::thread::Result<T>>);
+
+impl<T> Future for PoolTask<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx).map(|received| match received {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(panic)) => {
+                let message = panic.downcast_ref::<&str>().map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(anyhow!("task panicked: {}", message))
+            }
+            Err(_) => Err(anyhow!("OpenCV worker pool shut down before the task finished")),
+        })
+    }
+}
+
+/// Queues `work` on the pool. Jobs beyond the pool size wait for a free thread. The work runs
+/// in the span that is current when it is queued.
+pub fn spawn<T, F>(work: F) -> PoolTask<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (result_tx, result_rx) = oneshot::channel();
+    let span = tracing::Span::current();
+    let job: Job = Box::new(move || {
+        let _entered = span.enter();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(work));
+        let _ = result_tx.send(result); // the caller may have stopped waiting (watchdog timeout)
+    });
+    let sent = pool().sender.lock().unwrap_or_else(|p| p.into_inner()).send(job);
+    if sent.is_err() {
+        warn!("🧵 OpenCV worker pool is gone; task dropped.");
+    }
+    PoolTask(result_rx)
+}
+
+/// Runs `work` on a thread of its own, outside the pool, for jobs that hold their thread until
+/// told to stop (an open RealSense pipeline). Such a job would otherwise take a worker away from
+/// every other camera for as long as it runs. The thread is named `name` and exits with the job.
+pub fn spawn_dedicated<T, F>(name: String, work: F) -> Result<PoolTask<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (result_tx, result_rx) = oneshot::channel();
+    let span = tracing::Span::current();
+    std::thread::Builder::new()
+        .name(name.clone())
+        .spawn(move || {
+            let _entered = span.enter();
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(work));
+            let _ = result_tx.send(result);
+        })
+        .map_err(|e| anyhow!("Failed to spawn thread '{}': {}", name, e))?;
+    Ok(PoolTask(result_rx))
+}
+
+/// Wraps `work` so it runs with the worker thread pinned to `cpus` (a camera's `cpu_affinity`),
+/// e.g. the cores of the NUMA node its NIC or USB controller is attached to. The thread's
+/// previous affinity is restored afterwards, since pool threads are shared between cameras.
+pub fn pinned<T, F>(cpus: Option<Vec<usize>>, work: F) -> impl FnOnce() -> T + Send + 'static
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    move || match cpus {
+        Some(cpus) if !cpus.is_empty() => {
+            let previous = affinity::pin_current_thread(&cpus);
+            let result = work();
+            if let Some(previous) = previous {
+                affinity::restore(previous);
+            }
+            result
+        }
+        _ => work(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod affinity {
+    use log::{debug, warn};
+
+    /// Pins the calling thread to `cpus` and returns its previous CPU mask.
+    pub fn pin_current_thread(cpus: &[usize]) -> Option<libc::cpu_set_t> {
+        // SAFETY: cpu_set_t is plain data; the libc calls only read/write the sets passed in.
+        unsafe {
+            let mut previous: libc::cpu_set_t = std::mem::zeroed();
+            if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut previous) != 0 {
+                warn!("🧵 Could not read the thread's CPU affinity: {}", std::io::Error::last_os_error());
+                return None;
+            }
+            let mut wanted: libc::cpu_set_t = std::mem::zeroed();
+            for &cpu in cpus {
+                libc::CPU_SET(cpu, &mut wanted);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &wanted) != 0 {
+                warn!("🧵 Could not pin thread to CPUs {:?}: {}", cpus, std::io::Error::last_os_error());
+                return None;
+            }
+            debug!("🧵 Pinned {:?} to CPUs {:?}", std::thread::current().name(), cpus);
+            Some(previous)
+        }
+    }
+
+    pub fn restore(previous: libc::cpu_set_t) {
+        // SAFETY: as above.
+        if unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &previous) } != 0 {
+            warn!("🧵 Could not restore the thread's CPU affinity: {}", std::io::Error::last_os_error());
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod affinity {
+    use log::warn;
+
+    pub fn pin_current_thread(cpus: &[usize]) -> Option<()> {
+        warn!("🧵 cpu_affinity {:?} ignored: CPU pinning is only supported on Linux.", cpus);
+        None
+    }
+
+    pub fn restore(_previous: ()) {}
+}
@@ -0,0 +1,55 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use std::path::PathBuf;
+
+/// Layout used when `path_template` is not set: one folder per camera inside the session folder.
+pub const DEFAULT_TEMPLATE: &str = "{output_base}/{session}/{camera}";
+
+const PLACEHOLDERS: &[&str] = &["output_base", "session", "camera", "type", "date", "time", "year", "month", "day", "hour"];
+
+/// Values substituted into a path template for one camera of one session.
+#[derive(Debug, Clone)]
+pub struct PathValues<'a> {
+    pub output_base: &'a str, // the operation's output directory (--output or output_directory_base)
+    pub session: &'a str,
+    pub camera: &'a str,
+    pub kind: &'a str, // "image" or "video", written as {type}
+    pub at: DateTime<Local>, // session start
+}
+
+/// Checks that every `{placeholder}` in `template` is known.
+pub fn validate(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else { bail!("Unclosed '{{' in path template '{}'", template) };
+        let name = &rest[open + 1..open + close];
+        if !PLACEHOLDERS.contains(&name) {
+            bail!("Unknown placeholder '{{{}}}' in path template '{}' (use {})", name, template, PLACEHOLDERS.join(", "));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    if !template.contains("{camera}") {
+        bail!("Path template '{}' must contain {{camera}} so cameras don't write into the same folder", template);
+    }
+    Ok(())
+}
+
+/// Expands `template` (e.g. `{output_base}/{date}/{camera}/{type}`) into a directory path.
+/// Substituted values are made filesystem safe, so a camera name can't add path levels.
+pub fn expand(template: &str, values: &PathValues<'_>) -> PathBuf {
+    let safe = |text: &str| -> String {
+        text.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+    };
+    let expanded = template
+        .replace("{output_base}", values.output_base.trim_end_matches('/'))
+        .replace("{session}", &safe(values.session))
+        .replace("{camera}", &safe(values.camera))
+        .replace("{type}", &safe(values.kind))
+        .replace("{date}", &values.at.format("%Y-%m-%d").to_string())
+        .replace("{time}", &values.at.format("%H%M%S").to_string())
+        .replace("{year}", &values.at.format("%Y").to_string())
+        .replace("{month}", &values.at.format("%m").to_string())
+        .replace("{day}", &values.at.format("%d").to_string())
+        .replace("{hour}", &values.at.format("%H").to_string());
+    PathBuf::from(expanded)
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use opencv::core::Mat;
+use opencv::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// Process-wide like `shutdown::token()`: the agent API and embedding programs control
+// recordings in progress, and the recording loops check between frames.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Pauses every recording in progress, and any started while paused, until `resume`. Each
+/// recording finalizes its current segment, so the file is complete, and continues in a new
+/// segment on resume; the gap is recorded in the session manifest. Returns false if already paused.
+pub fn pause() -> bool {
+    let changed = !PAUSED.swap(true, Ordering::SeqCst);
+    if changed {
+        info!("⏸️ Recordings paused.");
+    }
+    changed
+}
+
+/// Resumes paused recordings. Returns false if they were not paused.
+pub fn resume() -> bool {
+    let changed = PAUSED.swap(false, Ordering::SeqCst);
+    if changed {
+        info!("▶️ Recordings resumed.");
+    }
+    changed
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/// A camera's recording in progress, as seen from outside its loop.
+#[derive(Default)]
+struct Recording {
+    stop: bool,                          // asked to end now
+    frame_wanted: Option<DateTime<Utc>>, // a snapshot waits for a frame read after this
+    frame: Option<(Mat, DateTime<Utc>)>, // the last frame shared, and when it was read
+}
+
+/// Recordings in progress, by camera.
+static RECORDINGS: Mutex<BTreeMap<String, Recording>> = Mutex::new(BTreeMap::new());
+
+fn recordings() -> MutexGuard<'static, BTreeMap<String, Recording>> {
+    RECORDINGS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Registration of a camera's recording in progress, so `stop` can reach it. Unregisters on drop.
+pub struct ActiveRecording {
+    camera: String,
+}
+
+impl ActiveRecording {
+    pub fn register(camera: &str) -> Self {
+        recordings().insert(camera.to_string(), Recording::default());
+        ActiveRecording { camera: camera.to_string() }
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        recordings().get(&self.camera).is_some_and(|r| r.stop)
+    }
+
+    /// True while a snapshot of this camera waits for `share_frame`.
+    pub fn frame_wanted(&self) -> bool {
+        recordings().get(&self.camera).is_some_and(|r| r.frame_wanted.is_some())
+    }
+
+    /// Hands a copy of the frame just read, read at `read_at`, to the snapshots waiting for one.
+    pub fn share_frame(&self, frame: &Mat, read_at: DateTime<Utc>) -> Result<()> {
+        let copy = frame.try_clone()?;
+        if let Some(recording) = recordings().get_mut(&self.camera) {
+            if recording.frame_wanted.is_some_and(|since| read_at >= since) {
+                recording.frame_wanted = None;
+            }
+            recording.frame = Some((copy, read_at));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ActiveRecording {
+    fn drop(&mut self) {
+        recordings().remove(&self.camera);
+    }
+}
+
+/// Cameras currently recording.
+pub fn active() -> Vec<String> {
+    recordings().keys().cloned().collect()
+}
+
+/// Asks the recordings of `cameras` (None = all) to end now. They finalize their files and
+/// report what they recorded, as when the duration is up. Returns the cameras asked to stop;
+/// cameras that are not recording are ignored.
+pub fn stop(cameras: Option<&[String]>) -> Vec<String> {
+    let mut recordings = recordings();
+    let mut stopped = Vec::new();
+    for (camera, recording) in recordings.iter_mut() {
+        if cameras.is_none_or(|cameras| cameras.contains(camera)) {
+            recording.stop = true;
+            stopped.push(camera.clone());
+        }
+    }
+    if !stopped.is_empty() {
+        info!("⏹️ Stopping the recordings of {}.", stopped.join(", "));
+    }
+    stopped
+}
+
+pub fn is_recording(camera: &str) -> bool {
+    recordings().contains_key(camera)
+}
+
+/// The next frame the recording of `camera` reads, for a snapshot taken while it records: the
+/// camera's stream is already open in the recording, and many cameras refuse a second one.
+/// Blocks until the frame arrives, at most `timeout`. None if the camera is not recording (or
+/// its recording ended while waiting), so the caller opens the stream itself.
+pub fn shared_frame(camera: &str, timeout: Duration) -> Result<Option<(Mat, DateTime<Utc>)>> {
+    let requested_at = Utc::now();
+    match recordings().get_mut(camera) {
+        Some(recording) => recording.frame_wanted = Some(recording.frame_wanted.map_or(requested_at, |since| since.min(requested_at))),
+        None => return Ok(None),
+    }
+    debug!("Waiting for the recording of '{}' to share a frame", camera);
+    let started = Instant::now();
+    loop {
+        match recordings().get(camera) {
+            None => return Ok(None),
+            Some(Recording { frame: Some((frame, read_at)), .. }) if *read_at >= requested_at => {
+                return Ok(Some((frame.try_clone()?, *read_at)));
+            }
+            Some(_) => {}
+        }
+        if started.elapsed() >= timeout {
+            let reason = if is_paused() { " (recordings are paused)" } else { "" };
+            return Err(anyhow!("The recording of '{}' read no frame within {:?}{}", camera, timeout, reason));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::config_loader::AppSettings;
+use crate::cli::RerunArgs;
+use crate::core::capture_source::FrameBuffer;
+use crate::core::events::{self, Event, Listener};
+use log::{debug, error, info};
+use rerun::{RecordingStream, RecordingStreamBuilder};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Port the Rerun viewer listens on for gRPC connections by default.
+const DEFAULT_VIEWER_PORT: u16 = 9876;
+
+/// Rerun options of an operation, as given on the CLI or by a programmatic caller.
+#[derive(Debug, Clone, Default)]
+pub struct RerunParams {
+    pub enabled: bool,
+    pub application_id: Option<String>,
+    pub recording_id: Option<String>,
+    pub connect: Option<String>, // viewer address; None = AppSettings.rerun_connect_addr
+    pub save: Option<PathBuf>,   // .rrd file; None = AppSettings.rerun_save_path
+}
+
+impl RerunParams {
+    pub fn from_args(args: &RerunArgs) -> Self {
+        let connect = args.rerun_connect.clone();
+        let save = args.rerun_save.as_ref().map(PathBuf::from);
+        RerunParams {
+            // Naming a destination implies logging.
+            enabled: args.rerun || connect.is_some() || save.is_some(),
+            application_id: args.rerun_app_id.clone(),
+            recording_id: args.rerun_recording_id.clone(),
+            connect,
+            save,
+        }
+    }
+}
+
+/// Where a recording stream sends its data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RerunSink {
+    Spawn,           // start a local viewer
+    Connect(String), // stream to a running viewer at this gRPC URL
+    Save(PathBuf),   // write an .rrd file
+}
+
+impl RerunSink {
+    /// Turns `host:port` into the viewer's gRPC URL; full URLs are kept as given.
+    fn connect_url(addr: &str) -> String {
+        if addr.contains("://") {
+            addr.to_string()
+        } else if addr.contains(':') {
+            format!("rerun+http://{}/proxy", addr)
+        } else {
+            format!("rerun+http://{}:{}/proxy", addr, DEFAULT_VIEWER_PORT)
+        }
+    }
+}
+
+/// Identifiers and destination used when creating a Rerun recording stream.
+#[derive(Debug, Clone)]
+pub struct RerunIdentity {
+    pub application_id: String,
+    pub recording_id: Option<String>,
+    pub sink: RerunSink,
+}
+
+impl RerunIdentity {
+    /// Resolves the ids from the operation params (CLI), then config, then the operation's
+    /// default application id.
+    pub fn resolve(app_config: &AppSettings, params: &RerunParams, default_application_id: &str) -> Self {
+        let application_id = params.application_id.clone()
+            .or_else(|| app_config.rerun_application_id.clone())
+            .unwrap_or_else(|| default_application_id.to_string());
+        let recording_id = params.recording_id.clone()
+            .or_else(|| app_config.rerun_recording_id.clone());
+        // A destination given on the CLI wins over any from the config; a file beats a viewer.
+        let sink = match (&params.save, &params.connect) {
+            (Some(path), _) => RerunSink::Save(path.clone()),
+            (None, Some(addr)) => RerunSink::Connect(RerunSink::connect_url(addr)),
+            (None, None) => match (&app_config.rerun_save_path, &app_config.rerun_connect_addr) {
+                (Some(path), _) => RerunSink::Save(PathBuf::from(path)),
+                (None, Some(addr)) => RerunSink::Connect(RerunSink::connect_url(addr)),
+                (None, None) => RerunSink::Spawn,
+            },
+        };
+        RerunIdentity { application_id, recording_id, sink }
+    }
+}
+
+/// Creates the recording stream for the identity's sink (spawned viewer, remote viewer or
+/// .rrd file), or returns None (after logging) on failure.
+pub fn init_recording_stream(app_config: &AppSettings, identity: &RerunIdentity, operation_display_name: &str) -> Option<RecordingStream> {
+    let flush_timeout_secs = app_config.rerun_flush_timeout_secs.unwrap_or(10.0);
+    let mut opts = rerun::SpawnOptions::default();
+
+    let mut applied_memory_limit = "default".to_string();
+    if let Some(limit) = &app_config.rerun_memory_limit {
+        opts.memory_limit = limit.clone().into();
+        applied_memory_limit = limit.clone();
+        debug!("Rerun: Setting memory limit to: {}", limit);
+    } else {
+        debug!("Rerun: Using default memory limit.");
+    }
+
+    let mut applied_latency_config = "not set".to_string();
+    if let Some(latency_str) = &app_config.rerun_drop_at_latency {
+        opts.extra_args.push("--drop-at-latency".into());
+        opts.extra_args.push(latency_str.clone().into());
+        applied_latency_config = latency_str.clone();
+        debug!("Rerun: Setting drop-at-latency to: {}", latency_str);
+    } else {
+        debug!("Rerun: drop-at-latency not configured.");
+    }
+
+    let mut builder = RecordingStreamBuilder::new(identity.application_id.as_str());
+    if let Some(recording_id) = &identity.recording_id {
+        builder = builder.recording_id(recording_id.clone());
+    }
+
+    let flush_timeout = Some(Duration::from_secs_f32(flush_timeout_secs));
+    let (stream_result, destination) = match &identity.sink {
+        RerunSink::Spawn => (builder.spawn_opts(&opts, flush_timeout), "spawned viewer".to_string()),
+        RerunSink::Connect(url) => (builder.connect_grpc_opts(url.clone(), flush_timeout), format!("viewer at {}", url)),
+        RerunSink::Save(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                if let Err(e) = std::fs::create_dir_all(parent) {
+                    error!("Failed to create directory {} for Rerun recording: {}. Continuing without Rerun.", parent.display(), e);
+                    return None;
+                }
+            }
+            (builder.save(path), format!("file {}", path.display()))
+        }
+    };
+
+    match stream_result {
+        Ok(stream) => {
+            info!(
+                "Rerun recording stream initialized for '{}' -> {} (AppId: {}, RecordingId: {}, FlushTimeout: {}s, MemoryLimit: {}, DropAtLatency: {}).",
+                operation_display_name,
+                destination,
+                identity.application_id,
+                identity.recording_id.as_deref().unwrap_or("auto"),
+                flush_timeout_secs,
+                applied_memory_limit,
+                applied_latency_config
+            );
+            Some(stream)
+        }
+        Err(e) => {
+            error!("Failed to initialize Rerun recording stream ({}): {}. Continuing without Rerun.", destination, e);
+            if identity.sink == RerunSink::Spawn {
+                error!("On a headless host, use --rerun-save <file.rrd> or --rerun-connect <host:port> instead of spawning a viewer.");
+            }
+            None
+        }
+    }
+}
+
+/// Rerun image of an OpenCV BGR frame, logged as BGR so there is no colour conversion. The Mat
+/// owns its pixels, so they are copied once into the image's buffer.
+pub fn bgr_image(bgr_frame: &opencv::core::Mat) -> anyhow::Result<rerun::archetypes::Image> {
+    use opencv::prelude::*;
+    let shape = vec![bgr_frame.rows() as u64, bgr_frame.cols() as u64, bgr_frame.channels() as u64];
+    let pixels: FrameBuffer<u8> = bgr_frame.data_bytes()?.to_vec().into();
+    let tensor_data = rerun::datatypes::TensorData::new(shape, rerun::datatypes::TensorBuffer::U8(pixels.into()));
+    rerun::archetypes::Image::from_color_model_and_tensor(rerun::datatypes::ColorModel::BGR, tensor_data)
+        .map_err(|e| anyhow::anyhow!("{:?}", e))
+}
+
+/// Mirrors the event bus into the recording as text logs (`cameras/<camera>/events`, or
+/// `events` for rig-wide ones), so connects, recordings and disk warnings show up next to the frames.
+pub fn log_events(stream: RecordingStream) -> Listener {
+    events::listen("rerun", move |event| {
+        if matches!(event, Event::RecordingProgress { .. }) {
+            return; // once a second per camera; too chatty for the log view
+        }
+        stream.set_timestamp_secs_since_epoch("capture_time", event.at().timestamp_micros() as f64 / 1e6);
+        let entity = match event.camera() {
+            Some(camera) => format!("cameras/{}/events", camera),
+            None => "events".to_string(),
+        };
+        let level = if event.is_warning() { rerun::TextLogLevel::WARN } else { rerun::TextLogLevel::INFO };
+        if let Err(e) = stream.log(entity, &rerun::TextLog::new(event.to_string()).with_level(level)) {
+            debug!("Rerun: Failed to log event '{}': {}", event.name(), e);
+        }
+    })
+}
+
+/// Tees recorded frames to a Rerun stream while a recording is running, at most `fps`
+/// frames per second per camera.
+#[derive(Clone)]
+pub struct LivePreview {
+    pub stream: RecordingStream,
+    pub fps: f32,
+}
+
+impl LivePreview {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.fps)
+    }
+
+    /// Logs a BGR frame to `cameras/<camera>/video` at `captured_at` on the capture_time timeline.
+    /// Rerun's time is per thread, so each recording thread keeps its own clock.
+    pub fn log_bgr_frame(&self, camera_name: &str, bgr_frame: &opencv::core::Mat, captured_at: chrono::DateTime<chrono::Utc>) -> anyhow::Result<()> {
+        let image = bgr_image(bgr_frame)?;
+        self.stream.set_timestamp_secs_since_epoch("capture_time", captured_at.timestamp_micros() as f64 / 1e6);
+        self.stream.log(format!("cameras/{}/video", camera_name), &image)?;
+        Ok(())
+    }
+}
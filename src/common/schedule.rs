@@ -0,0 +1,224 @@
+use crate::common::sun::{self, SunTimes};
+use crate::config_loader::ScheduleConfig;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveTime, Timelike, Utc};
+
+/// One cron field as the set of values it allows.
+#[derive(Debug, Clone)]
+struct CronField {
+    allowed: Vec<bool>,
+    min: u32,
+    restricted: bool, // false for "*", which matters for the day-of-month/day-of-week rule
+}
+
+impl CronField {
+    fn parse(text: &str, min: u32, max: u32, name: &str) -> Result<Self> {
+        let mut allowed = vec![false; (max - min + 1) as usize];
+        for part in text.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    let step: u32 = step.parse().with_context(|| format!("Invalid step '{}' in cron {} field", step, name))?;
+                    if step == 0 {
+                        bail!("Step 0 in cron {} field", name);
+                    }
+                    (range, step)
+                }
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((a, b)) = range.split_once('-') {
+                (parse_value(a, name)?, parse_value(b, name)?)
+            } else {
+                let value = parse_value(range, name)?;
+                // "5/15" means from 5 to the end in steps of 15.
+                (value, if part.contains('/') { max } else { value })
+            };
+            if start < min || end > max || start > end {
+                bail!("Cron {} field '{}' is out of range ({}-{})", name, part, min, max);
+            }
+            for value in (start..=end).step_by(step as usize) {
+                allowed[(value - min) as usize] = true;
+            }
+        }
+        Ok(CronField { allowed, min, restricted: text != "*" })
+    }
+
+    fn matches(&self, value: u32) -> bool {
+        value >= self.min && self.allowed.get((value - self.min) as usize).copied().unwrap_or(false)
+    }
+}
+
+fn parse_value(text: &str, name: &str) -> Result<u32> {
+    text.trim().parse().with_context(|| format!("Invalid value '{}' in cron {} field", text, name))
+}
+
+/// A standard five-field cron expression (minute hour day-of-month month day-of-week),
+/// evaluated in local time. As in cron, a restricted day-of-month and day-of-week match if either does.
+#[derive(Debug, Clone)]
+pub struct CronExpression {
+    minute: CronField,
+    hour: CronField,
+    day_of_month: CronField,
+    month: CronField,
+    day_of_week: CronField,
+}
+
+impl CronExpression {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            bail!("Cron expression '{}' must have 5 fields (minute hour day-of-month month day-of-week)", expression);
+        }
+        let mut day_of_week = CronField::parse(fields[4], 0, 7, "day-of-week")?;
+        if day_of_week.allowed[7] {
+            day_of_week.allowed[0] = true; // 7 is Sunday too
+        }
+        Ok(CronExpression {
+            minute: CronField::parse(fields[0], 0, 59, "minute")?,
+            hour: CronField::parse(fields[1], 0, 23, "hour")?,
+            day_of_month: CronField::parse(fields[2], 1, 31, "day-of-month")?,
+            month: CronField::parse(fields[3], 1, 12, "month")?,
+            day_of_week,
+        })
+    }
+
+    pub fn matches(&self, at: DateTime<Local>) -> bool {
+        let dom = self.day_of_month.matches(at.day());
+        let dow = self.day_of_week.matches(at.weekday().num_days_from_sunday());
+        let day = match (self.day_of_month.restricted, self.day_of_week.restricted) {
+            (true, true) => dom || dow,
+            _ => dom && dow,
+        };
+        self.minute.matches(at.minute()) && self.hour.matches(at.hour()) && self.month.matches(at.month()) && day
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SunEvent {
+    Sunrise,
+    Sunset,
+}
+
+/// A capture at sunrise or sunset, shifted by an offset: "sunrise", "sunset-30m", "sunrise+1h".
+#[derive(Debug, Clone, Copy)]
+struct SunTrigger {
+    event: SunEvent,
+    offset_minutes: i64,
+}
+
+impl SunTrigger {
+    fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().to_lowercase();
+        let (event, rest) = if let Some(rest) = text.strip_prefix("sunrise") {
+            (SunEvent::Sunrise, rest)
+        } else if let Some(rest) = text.strip_prefix("sunset") {
+            (SunEvent::Sunset, rest)
+        } else {
+            bail!("Invalid trigger '{}' (use sunrise or sunset, optionally with an offset like sunset-30m)", text);
+        };
+        let offset_minutes = if rest.is_empty() {
+            0
+        } else {
+            let (sign, amount) = match rest.split_at(1) {
+                ("+", amount) => (1, amount),
+                ("-", amount) => (-1, amount),
+                _ => bail!("Invalid offset in trigger '{}' (e.g. sunrise+15m)", text),
+            };
+            let (number, unit_minutes) = if let Some(h) = amount.strip_suffix('h') {
+                (h, 60)
+            } else {
+                (amount.strip_suffix('m').unwrap_or(amount), 1)
+            };
+            let number: i64 = number.trim().parse().with_context(|| format!("Invalid offset in trigger '{}'", text))?;
+            sign * number * unit_minutes
+        };
+        Ok(SunTrigger { event, offset_minutes })
+    }
+
+    fn matches(&self, at: DateTime<Local>, sun: SunTimes) -> bool {
+        let SunTimes::Normal { sunrise, sunset } = sun else {
+            return false; // no sunrise or sunset today
+        };
+        let base = if self.event == SunEvent::Sunrise { sunrise } else { sunset };
+        let due = base + chrono::Duration::minutes(self.offset_minutes);
+        due.timestamp().div_euclid(60) == at.timestamp().div_euclid(60)
+    }
+}
+
+/// Local time-of-day window, possibly across midnight ("22:00-06:00").
+#[derive(Debug, Clone, Copy)]
+struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    fn parse(text: &str) -> Result<Self> {
+        let (start, end) = text.split_once('-').with_context(|| format!("Invalid quiet_hours '{}' (e.g. 22:00-06:00)", text))?;
+        let time = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").with_context(|| format!("Invalid time '{}' in quiet_hours", s.trim()));
+        Ok(QuietHours { start: time(start)?, end: time(end)? })
+    }
+
+    fn contains(&self, at: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= at && at < self.end
+        } else {
+            at >= self.start || at < self.end
+        }
+    }
+}
+
+/// When a camera takes snapshots in daemon mode: cron times and/or sunrise/sunset triggers,
+/// minus quiet hours and, for daylight-only cameras, the night.
+#[derive(Debug, Clone)]
+pub struct CameraSchedule {
+    cron: Option<CronExpression>,
+    triggers: Vec<SunTrigger>,
+    quiet_hours: Option<QuietHours>,
+    daylight_only: bool,
+}
+
+impl CameraSchedule {
+    pub fn parse(config: &ScheduleConfig) -> Result<Self> {
+        let (cron, triggers, quiet_hours, daylight_only) = match config {
+            // A plain string is a cron expression or a single sun trigger.
+            ScheduleConfig::Expression(text) if text.trim().to_lowercase().starts_with("sun") => (None, vec![text.clone()], None, false),
+            ScheduleConfig::Expression(text) => (Some(text.clone()), Vec::new(), None, false),
+            ScheduleConfig::Detailed(detailed) => (
+                detailed.cron.clone(),
+                detailed.triggers.clone().unwrap_or_default(),
+                detailed.quiet_hours.clone(),
+                detailed.daylight_only.unwrap_or(false),
+            ),
+        };
+        if cron.is_none() && triggers.is_empty() {
+            bail!("A schedule needs a cron expression or at least one sunrise/sunset trigger");
+        }
+        Ok(CameraSchedule {
+            cron: cron.as_deref().map(CronExpression::parse).transpose()?,
+            triggers: triggers.iter().map(|t| SunTrigger::parse(t)).collect::<Result<_>>()?,
+            quiet_hours: quiet_hours.as_deref().map(QuietHours::parse).transpose()?,
+            daylight_only,
+        })
+    }
+
+    /// True if the schedule needs sunrise/sunset times, i.e. latitude and longitude.
+    pub fn needs_location(&self) -> bool {
+        self.daylight_only || !self.triggers.is_empty()
+    }
+
+    /// Whether a snapshot is due in the minute containing `at`. `location` is (latitude,
+    /// longitude); without it, sun-relative rules never fire.
+    pub fn is_due(&self, at: DateTime<Local>, location: Option<(f64, f64)>) -> bool {
+        let sun = location.map(|(lat, lon)| sun::sun_times(at.date_naive(), lat, lon));
+        if self.quiet_hours.is_some_and(|quiet| quiet.contains(at.time())) {
+            return false;
+        }
+        if self.daylight_only && !sun.is_some_and(|sun| sun.is_daylight(at.with_timezone(&Utc))) {
+            return false;
+        }
+        self.cron.as_ref().is_some_and(|cron| cron.matches(at))
+            || sun.is_some_and(|sun| self.triggers.iter().any(|trigger| trigger.matches(at, sun)))
+    }
+}
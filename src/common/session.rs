@@ -0,0 +1,301 @@
+use crate::camera::discovery::MacResolution;
+use crate::camera::image_quality::ImageQuality;
+use crate::camera::stream_stats::StreamSummary;
+use crate::config_loader::{AppSettings, MasterConfig};
+use crate::core::capture_source::CaptureTimings;
+use anyhow::{bail, Context, Result};
+use crate::common::path_template::{self, PathValues};
+use chrono::{DateTime, Local, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Every session directory holds its manifest under this name.
+pub const MANIFEST_FILE: &str = "session.json";
+
+/// Session directories are created at most this many times with a numeric suffix when
+/// parallel invocations start within the same timestamp.
+const MAX_ID_SUFFIX: u32 = 100;
+
+/// Per-camera outcome of a capture session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCamera {
+    pub name: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub files: Vec<PathBuf>,
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32, // retry rounds before this outcome (capture_retries)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub out_of_sync: bool, // captured on a retry, so not at the same moment as the other cameras
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<CaptureTimings>, // connect / first frame / write breakdown, with --timing-report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recorded_secs: Option<f64>, // how long a recording actually ran, pauses excluded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<RecordingGap>, // pauses; each resume starts a new file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamSummary>, // a recording's frame rate and bitrate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degraded: Option<String>, // why the files are of lower quality than configured, e.g. an HTTP snapshot fallback
+}
+
+/// A stretch of a recording with no frames because it was paused.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordingGap {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>, // resumed, or the recording ended while paused
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// One entry of the session's event journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEvent {
+    pub at: DateTime<Utc>,
+    pub level: String, // "info", "warn" or "error"
+    pub camera: Option<String>,
+    pub message: String,
+}
+
+/// A capture session: one invocation's directory (`<base>/<session_id>/`, by default with one
+/// subfolder per camera, see `path_template`) and its `session.json` manifest. The manifest is written when the session
+/// starts and again when it ends, so an interrupted run still leaves a record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionManifest {
+    pub session_id: String,
+    #[serde(default)]
+    pub kind: String, // "image" or "video"
+    pub operation: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub output_dir: PathBuf, // the session directory
+    pub config: Option<MasterConfig>, // snapshot of the configuration the session ran with
+    pub cameras: Vec<SessionCamera>,
+    pub events: Vec<SessionEvent>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<PathBuf, String>, // SHA-256 (hex) per file, with application.checksums
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub quality: BTreeMap<PathBuf, ImageQuality>, // per-image metrics, with application.quality_checks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resolved_ips: BTreeMap<String, String>, // address each camera with a mac_address was found at
+}
+
+impl SessionManifest {
+    /// Creates `<base_dir>/<kind>_<timestamp>/` and writes the initial manifest. Creation is
+    /// atomic, so a parallel invocation that picked the same id gets a `-2`, `-3`, ... suffix.
+    pub fn create(master_config: &MasterConfig, base_dir: &Path, kind: &str, operation: &str) -> Result<Self> {
+        let base_id = format!("{}_{}", kind, chrono::Local::now().format(&master_config.application.filename_timestamp_format));
+        Self::create_named(master_config, base_dir, &base_id, kind, operation)
+    }
+
+    /// Like `create`, with a caller-chosen id (still suffixed if taken).
+    pub fn create_named(master_config: &MasterConfig, base_dir: &Path, base_id: &str, kind: &str, operation: &str) -> Result<Self> {
+        let started_at = Utc::now();
+        fs::create_dir_all(base_dir).with_context(|| format!("Failed to create output directory {}", base_dir.display()))?;
+
+        let mut attempt = 1;
+        let (session_id, output_dir) = loop {
+            let id = if attempt == 1 { base_id.to_string() } else { format!("{}-{}", base_id, attempt) };
+            let dir = base_dir.join(&id);
+            match fs::create_dir(&dir) {
+                Ok(()) => break (id, dir),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists && attempt < MAX_ID_SUFFIX => attempt += 1,
+                Err(e) => return Err(e).with_context(|| format!("Failed to create session directory {}", dir.display())),
+            }
+        };
+
+        let manifest = SessionManifest {
+            session_id,
+            kind: kind.to_string(),
+            operation: operation.to_string(),
+            started_at,
+            finished_at: None,
+            output_dir,
+            config: Some(master_config.clone()),
+            cameras: Vec::new(),
+            events: Vec::new(),
+            checksums: BTreeMap::new(),
+            quality: BTreeMap::new(),
+            resolved_ips: BTreeMap::new(),
+        };
+        manifest.write()?;
+        debug!("Session '{}' started for '{}' in {}.", manifest.session_id, operation, manifest.output_dir.display());
+        Ok(manifest)
+    }
+
+    /// Where the camera's files go, created on first use: `application.path_template` expanded
+    /// for this session, by default the camera's subfolder of the session directory.
+    pub fn camera_dir(&self, camera: &str) -> Result<PathBuf> {
+        let template = self.config.as_ref()
+            .and_then(|config| config.application.path_template.as_deref())
+            .unwrap_or(path_template::DEFAULT_TEMPLATE);
+        let dir = path_template::expand(template, &PathValues {
+            output_base: &self.base_dir().to_string_lossy(),
+            session: &self.session_id,
+            camera,
+            kind: &self.kind,
+            at: self.started_at.with_timezone(&Local),
+        });
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create camera directory {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    /// The output directory the session directory was created in.
+    pub fn base_dir(&self) -> &Path {
+        self.output_dir.parent().unwrap_or(Path::new("."))
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.output_dir.join(MANIFEST_FILE)
+    }
+
+    pub fn event(&mut self, level: &str, camera: Option<&str>, message: impl Into<String>) {
+        self.events.push(SessionEvent { at: Utc::now(), level: level.to_string(), camera: camera.map(str::to_string), message: message.into() });
+    }
+
+    /// Records a camera's outcome; failures are also added to the event journal.
+    pub fn record_camera(&mut self, name: &str, started_at: Option<DateTime<Utc>>, files: Vec<PathBuf>, error: Option<String>) {
+        if let Some(e) = &error {
+            self.event("error", Some(name), e.clone());
+        }
+        self.cameras.push(SessionCamera {
+            name: name.to_string(), started_at, finished_at: Some(Utc::now()), files, error, retries: 0, out_of_sync: false, timings: None, recorded_secs: None, gaps: Vec::new(), stream: None, degraded: None,
+        });
+    }
+
+    /// Marks the camera's recorded outcome as coming from retry round `retries`; files captured
+    /// then are flagged as out of sync with the rest of the batch.
+    pub fn mark_retried(&mut self, name: &str, retries: u32) {
+        let Some(camera) = self.cameras.iter_mut().rev().find(|camera| camera.name == name) else {
+            return;
+        };
+        camera.retries = retries;
+        camera.out_of_sync = !camera.files.is_empty();
+        if camera.out_of_sync {
+            self.event("warn", Some(name), format!("captured on retry {}; frames are out of sync with the batch", retries));
+        }
+    }
+
+    /// Attaches the timing breakdown to the camera's recorded outcome.
+    pub fn set_timings(&mut self, name: &str, timings: CaptureTimings) {
+        if let Some(camera) = self.cameras.iter_mut().rev().find(|camera| camera.name == name) {
+            camera.timings = Some(timings);
+        }
+    }
+
+    /// Records where a camera was found by its MAC address, noting a move in the journal.
+    pub fn set_resolved_ip(&mut self, resolution: &MacResolution) {
+        if resolution.moved() {
+            self.event("warn", Some(&resolution.camera), format!("found at {} by MAC address (configured ip {})", resolution.ip, resolution.configured_ip));
+        }
+        self.resolved_ips.insert(resolution.camera.clone(), resolution.ip.clone());
+    }
+
+    /// Attaches a recording's stream rates to the camera's recorded outcome, noting time spent
+    /// below `min_stream_kbps` in the journal.
+    pub fn set_stream(&mut self, name: &str, stream: StreamSummary) {
+        if stream.degraded_secs > 0 {
+            self.event("warn", Some(name), format!("bitrate below min_stream_kbps for {}s (lowest {:.0} kbps)", stream.degraded_secs, stream.min_kbps));
+        }
+        if let Some(camera) = self.cameras.iter_mut().rev().find(|camera| camera.name == name) {
+            camera.stream = Some(stream);
+        }
+    }
+
+    /// Marks the camera's recorded outcome as degraded, and journals why.
+    pub fn set_degraded(&mut self, name: &str, reason: String) {
+        self.event("warn", Some(name), format!("degraded: {}", reason));
+        if let Some(camera) = self.cameras.iter_mut().rev().find(|camera| camera.name == name) {
+            camera.degraded = Some(reason);
+        }
+    }
+
+    /// Attaches how long the camera actually recorded, and its pauses, to its recorded outcome.
+    pub fn set_recorded(&mut self, name: &str, recorded_secs: f64, gaps: Vec<RecordingGap>) {
+        for gap in &gaps {
+            let seconds = (gap.to - gap.from).num_milliseconds() as f64 / 1000.0;
+            self.event("info", Some(name), format!("paused from {} to {} ({:.1}s gap)", gap.from.to_rfc3339(), gap.to.to_rfc3339(), seconds));
+        }
+        if let Some(camera) = self.cameras.iter_mut().rev().find(|camera| camera.name == name) {
+            camera.recorded_secs = Some(recorded_secs);
+            camera.gaps = gaps;
+        }
+    }
+
+    /// Stamps the end time and writes the manifest; returns its path.
+    pub fn finish(&mut self) -> Result<PathBuf> {
+        self.finished_at = Some(Utc::now());
+        let path = self.write()?;
+        info!("🗂️ Session '{}' manifest written to {}", self.session_id, path.display());
+        Ok(path)
+    }
+
+    /// Writes the manifest via a temporary file, so readers never see a partial one.
+    pub fn write(&self) -> Result<PathBuf> {
+        let path = self.manifest_path();
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize session manifest")?;
+        fs::write(&tmp_path, json).with_context(|| format!("Failed to write session manifest {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path).with_context(|| format!("Failed to move session manifest into place at {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Loads `<output_directory_base>/<session_id>/session.json`; "latest" picks the most
+    /// recently updated session there.
+    pub fn load(app: &AppSettings, session_id: &str) -> Result<Self> {
+        let base = Path::new(&app.output_directory_base);
+        let path = if session_id == "latest" {
+            let mut manifests: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(base)
+                .with_context(|| format!("Failed to read output directory {}", base.display()))?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path().join(MANIFEST_FILE))
+                .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+                .collect();
+            manifests.sort();
+            match manifests.pop() {
+                Some((_, path)) => path,
+                None => bail!("No sessions found in {}", base.display()),
+            }
+        } else {
+            base.join(session_id).join(MANIFEST_FILE)
+        };
+        Self::read(&path)
+    }
+
+    /// Session manifests in `dir` itself or its immediate subdirectories (the session folders),
+    /// with the folder each was found in. Unreadable manifests are skipped with a warning.
+    pub fn find_all(dir: &Path) -> Result<Vec<(PathBuf, SessionManifest)>> {
+        let mut dirs = vec![dir.to_path_buf()];
+        dirs.extend(fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir()));
+        dirs.sort();
+        let mut manifests = Vec::new();
+        for session_dir in dirs {
+            let path = session_dir.join(MANIFEST_FILE);
+            if !path.is_file() {
+                continue;
+            }
+            let json = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            match serde_json::from_str::<SessionManifest>(&json) {
+                Ok(manifest) => manifests.push((session_dir, manifest)),
+                Err(e) => warn!("⚠️ Skipping unreadable manifest {}: {}", path.display(), e),
+            }
+        }
+        Ok(manifests)
+    }
+
+    /// Reads the manifest at `path`.
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path).with_context(|| format!("Session manifest {} not found", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse session manifest {}", path.display()))
+    }
+}
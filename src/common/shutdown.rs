@@ -0,0 +1,42 @@
+use log::{error, warn};
+use std::sync::OnceLock;
+use tokio_util::sync::CancellationToken;
+
+static SHUTDOWN: OnceLock<CancellationToken> = OnceLock::new();
+
+/// Process-wide shutdown token. It is cancelled by the first Ctrl-C, or by an embedding
+/// program that wants running operations to stop early.
+pub fn token() -> CancellationToken {
+    SHUTDOWN.get_or_init(CancellationToken::new).clone()
+}
+
+/// Cancels `token()` on the first Ctrl-C (or SIGTERM, e.g. from `kill` or systemd) so
+/// recordings can stop and finalize their files. A second one exits immediately, without
+/// finalizing anything.
+pub fn install_ctrl_c_handler() {
+    let token = token();
+    tokio::spawn(async move {
+        let signal = match interrupted().await {
+            Ok(signal) => signal,
+            Err(e) => {
+                warn!("⚠️ Could not listen for Ctrl-C: {}. Interrupting will not finalize recordings.", e);
+                return;
+            }
+        };
+        warn!("🛑 {} received: stopping and finalizing files in progress. Press Ctrl-C again to exit immediately.", signal);
+        token.cancel();
+        if let Ok(signal) = interrupted().await {
+            error!("🛑 Second {} received: exiting without finalizing.", signal);
+            std::process::exit(130);
+        }
+    });
+}
+
+/// Waits for Ctrl-C or SIGTERM; returns which one arrived.
+async fn interrupted() -> std::io::Result<&'static str> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result.map(|_| "Ctrl-C"),
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
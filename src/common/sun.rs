@@ -0,0 +1,51 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+/// Sunrise and sunset on one day at one place.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SunTimes {
+    Normal { sunrise: DateTime<Utc>, sunset: DateTime<Utc> },
+    /// Polar day: the sun stays above the horizon.
+    AlwaysUp,
+    /// Polar night: the sun stays below the horizon.
+    AlwaysDown,
+}
+
+impl SunTimes {
+    pub fn is_daylight(&self, at: DateTime<Utc>) -> bool {
+        match self {
+            SunTimes::Normal { sunrise, sunset } => *sunrise <= at && at < *sunset,
+            SunTimes::AlwaysUp => true,
+            SunTimes::AlwaysDown => false,
+        }
+    }
+}
+
+/// Sunrise and sunset for `date` at `latitude`/`longitude` (degrees, north and east positive),
+/// using the standard sunrise equation with atmospheric refraction. Accurate to about a minute,
+/// which is plenty for scheduling captures.
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> SunTimes {
+    const J2000: f64 = 2451545.0;
+    const UNIX_EPOCH_JD: f64 = 2440587.5;
+    let days_since_unix = date.signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days() as f64;
+    let noon_jd = UNIX_EPOCH_JD + days_since_unix + 0.5;
+    let day = (noon_jd - J2000 + 0.0008).round();
+
+    let mean_solar_noon = day - longitude / 360.0;
+    let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_noon).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * mean_anomaly.sin() + 0.02 * (2.0 * mean_anomaly).sin() + 0.0003 * (3.0 * mean_anomaly).sin();
+    let ecliptic_longitude = (mean_anomaly.to_degrees() + center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = J2000 + mean_solar_noon + 0.0053 * mean_anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+
+    let lat = latitude.to_radians();
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - lat.sin() * declination.sin()) / (lat.cos() * declination.cos());
+    if cos_hour_angle > 1.0 {
+        return SunTimes::AlwaysDown;
+    }
+    if cos_hour_angle < -1.0 {
+        return SunTimes::AlwaysUp;
+    }
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    let to_utc = |jd: f64| Utc.timestamp_opt(((jd - UNIX_EPOCH_JD) * 86400.0).round() as i64, 0).single().unwrap_or_default();
+    SunTimes::Normal { sunrise: to_utc(transit - half_day), sunset: to_utc(transit + half_day) }
+}
@@ -0,0 +1,147 @@
+use crate::common::host_clock;
+use crate::config_loader::{AppSettings, CameraTimeouts};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{info, warn};
+use std::time::Duration;
+
+/// A scheduled start is at least this far away, so every camera has time to get ready.
+const MIN_LEAD: Duration = Duration::from_millis(500);
+
+/// The last stretch before a scheduled start is busy-waited; sleeping is only accurate to a
+/// millisecond or two.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Which instants a capture may start at.
+#[derive(Debug, Clone, Copy)]
+pub enum StartAlign {
+    Every(Duration),   // the next multiple of this period since the Unix epoch
+    At(DateTime<Utc>), // one absolute instant
+}
+
+/// Capture start scheduled at an absolute UTC instant instead of "as soon as ready". Separate
+/// rcam instances with NTP-synced clocks and the same schedule start in the same instant.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncStart {
+    pub align: StartAlign,
+    pub offset: Duration,          // added to the aligned instant, e.g. to stagger flashes
+    pub clock_correction_secs: f64, // host minus reference clock (from SNTP or chrony); the host waits this much longer
+}
+
+impl SyncStart {
+    /// Parses `next-second`, `next-minute`, `next-<N>s` or an RFC 3339 instant.
+    pub fn parse(spec: &str, offset: Duration) -> Result<Self> {
+        let spec = spec.trim();
+        let align = match spec.to_lowercase().as_str() {
+            "next-second" => StartAlign::Every(Duration::from_secs(1)),
+            "next-minute" => StartAlign::Every(Duration::from_secs(60)),
+            other => match other.strip_prefix("next-").and_then(|rest| rest.strip_suffix('s')) {
+                Some(secs) => {
+                    let secs: u64 = secs.parse().with_context(|| format!("Invalid start_at '{}': expected next-<N>s with a whole number of seconds", spec))?;
+                    if secs == 0 {
+                        bail!("Invalid start_at '{}': the period must be at least 1s", spec);
+                    }
+                    StartAlign::Every(Duration::from_secs(secs))
+                }
+                None => StartAlign::At(DateTime::parse_from_rfc3339(spec)
+                    .with_context(|| format!("Invalid start_at '{}': expected next-second, next-minute, next-<N>s or an RFC 3339 time", spec))?
+                    .with_timezone(&Utc)),
+            },
+        };
+        Ok(SyncStart { align, offset, clock_correction_secs: 0.0 })
+    }
+
+    /// The start instant, in reference (NTP) time: for a period, the first aligned instant at
+    /// least half a second after `now` (host time).
+    pub fn next_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.next_start_after(now, MIN_LEAD)
+    }
+
+    /// Like `next_start`, with the aligned instant at least `lead` after `now`.
+    pub fn next_start_after(&self, now: DateTime<Utc>, lead: Duration) -> DateTime<Utc> {
+        let offset = chrono::Duration::from_std(self.offset).unwrap_or_default();
+        match self.align {
+            StartAlign::At(at) => at + offset,
+            StartAlign::Every(period) => {
+                let reference_now = now - seconds(self.clock_correction_secs);
+                let earliest = reference_now + chrono::Duration::from_std(lead).unwrap_or_default() - offset;
+                let earliest_ns = earliest.timestamp_nanos_opt().unwrap_or_default() as i128;
+                let period_ns = period.as_nanos() as i128;
+                let aligned_ns = (earliest_ns + period_ns - 1).div_euclid(period_ns) * period_ns;
+                DateTime::from_timestamp_nanos(aligned_ns as i64) + offset
+            }
+        }
+    }
+
+    /// When the host clock reads `start` (reference time).
+    pub fn host_time(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        start + seconds(self.clock_correction_secs)
+    }
+
+    /// The next start (reference time), logged for the operator.
+    pub fn schedule(&self, operation: &str) -> DateTime<Utc> {
+        let start = self.next_start(Utc::now());
+        info!("🕰️ '{}' starts at {} UTC (in {:?}).", operation, start.to_rfc3339_opts(SecondsFormat::Millis, true),
+            (self.host_time(start) - Utc::now()).to_std().unwrap_or_default());
+        start
+    }
+}
+
+fn seconds(secs: f64) -> chrono::Duration {
+    chrono::Duration::nanoseconds((secs * 1e9) as i64)
+}
+
+/// The scheduled start for a capture: `start_at` from the command line or the config, with the
+/// host clock checked against NTP. An offset the check measured is corrected for; a host that
+/// cannot be verified is only warned about, since it still starts in sync with itself.
+pub async fn resolve(app: &AppSettings, start_at: Option<&str>, offset_ms: Option<u64>) -> Result<Option<SyncStart>> {
+    let Some(spec) = start_at.or(app.start_at.as_deref()) else {
+        return Ok(None);
+    };
+    let mut sync = SyncStart::parse(spec, Duration::from_millis(offset_ms.or(app.start_offset_ms).unwrap_or(0)))?;
+
+    let tolerance = Duration::from_secs_f32(app.time_sync_tolerance_seconds.unwrap_or(1.0).max(0.0));
+    let status = host_clock::check_host_clock(app.ntp_server.as_deref(), tolerance, CameraTimeouts::from_app(app).http).await;
+    match (status.offset_secs, status.synchronized) {
+        (Some(offset), _) => {
+            sync.clock_correction_secs = offset;
+            info!("🕰️ Host clock is {:+.3}s off the reference ({}); the scheduled start is corrected for it.", offset, status.source);
+        }
+        (None, Some(true)) => info!("🕰️ Host clock is synchronized ({}: {}).", status.source, status.detail),
+        (None, Some(false)) => warn!("⚠️ Host clock is NOT synchronized ({}: {}); other hosts will not start in the same instant.", status.source, status.detail),
+        (None, None) => warn!("⚠️ Could not check the host clock ({}); set ntp_server so the scheduled start can be corrected.", status.detail),
+    }
+    Ok(Some(sync))
+}
+
+/// Blocks the thread until the host clock reads `at`. Returns how late the call already was
+/// (zero when on time). The wall clock is re-read while waiting, so NTP slewing is followed.
+pub fn sleep_until_blocking(at: DateTime<Utc>) -> Duration {
+    let late = (Utc::now() - at).to_std().unwrap_or_default();
+    loop {
+        let Ok(remaining) = (at - Utc::now()).to_std() else { break };
+        if remaining.is_zero() {
+            break;
+        }
+        if remaining > SPIN_MARGIN {
+            std::thread::sleep((remaining - SPIN_MARGIN).min(Duration::from_millis(100)));
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+    late
+}
+
+/// `sleep_until_blocking` for async tasks: sleeps on the runtime and busy-waits only the last
+/// few milliseconds, on a blocking thread.
+pub async fn sleep_until(at: DateTime<Utc>) -> Duration {
+    let late = (Utc::now() - at).to_std().unwrap_or_default();
+    while let Ok(remaining) = (at - Utc::now()).to_std() {
+        if remaining <= SPIN_MARGIN * 2 {
+            break;
+        }
+        tokio::time::sleep((remaining - SPIN_MARGIN * 2).min(Duration::from_millis(100))).await;
+    }
+    let _ = tokio::task::spawn_blocking(move || sleep_until_blocking(at)).await;
+    late
+}
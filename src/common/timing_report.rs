@@ -0,0 +1,23 @@
+use crate::core::capture_source::CaptureTimings;
+use log::info;
+
+/// Logs the per-camera timing breakdown as a table (`--timing-report`), slowest camera first.
+/// Cameras without timings (failed before connecting) are listed with dashes.
+pub fn log_table(operation: &str, rows: &[(String, Option<CaptureTimings>)]) {
+    let mut rows: Vec<&(String, Option<CaptureTimings>)> = rows.iter().collect();
+    rows.sort_by(|a, b| {
+        let total = |row: &(String, Option<CaptureTimings>)| row.1.map_or(-1.0, |t| t.total_ms());
+        total(b).total_cmp(&total(a))
+    });
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max("camera".len());
+    info!("⏱️ Timing report for '{}' (ms):", operation);
+    info!("  {:<width$} | {:>9} | {:>11} | {:>9} | {:>9}", "camera", "connect", "first frame", "write", "total", width = width);
+    for (name, timings) in rows {
+        let cell = |ms: Option<f64>| ms.map_or_else(|| "-".to_string(), |ms| format!("{:.1}", ms));
+        match timings {
+            Some(t) => info!("  {:<width$} | {:>9} | {:>11} | {:>9} | {:>9.1}",
+                name, cell(t.connect_ms), cell(t.first_frame_ms), cell(t.write_ms), t.total_ms(), width = width),
+            None => info!("  {:<width$} | {:>9} | {:>11} | {:>9} | {:>9}", name, "-", "-", "-", "-", width = width),
+        }
+    }
+}
@@ -1,12 +1,14 @@
+use crate::camera::imaging::ImagingRequestConfig;
+use crate::errors::{ErrorKind, ErrorKindExt};
 use serde::{Deserialize, Serialize};
 use serde_yaml;
 use std::fs;
 use std::path::Path;
 use anyhow::{Result, Context, bail};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::net::IpAddr;
-use log::{debug, info};
-use std::time::Instant;
+use log::{debug, info, warn};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppSettings {
@@ -22,19 +24,350 @@ pub struct AppSettings {
     pub video_duration_default_seconds: u32,
     pub time_sync_tolerance_seconds: Option<f32>,
     pub log_level: Option<String>,
+    pub log_levels: Option<BTreeMap<String, String>>, // per module, e.g. {"rcam::camera": "debug", reqwest: "warn"}; "opencv" and "ffmpeg" set the native libraries' levels
+    pub log_format: Option<String>,   // "text" (default) or "json", one object per line with its spans
+    pub log_file: Option<String>,     // also log to this file (e.g. "logs/rcam.log"), rotated per log_rotation
+    pub log_rotation: Option<String>, // "daily" (default), "hourly" or "never"
+    pub log_max_files: Option<usize>, // rotated log files kept (default 14)
+    pub log_max_size_mb: Option<u64>, // rotate the log file by size instead of by time
     pub enable_gui: Option<bool>,
     pub rerun_flush_timeout_secs: Option<f32>,
     pub rerun_memory_limit: Option<String>,
     pub rerun_drop_at_latency: Option<String>,
+    pub rerun_application_id: Option<String>,
+    pub rerun_recording_id: Option<String>,
+    pub rerun_connect_addr: Option<String>, // stream to a running viewer ("host:port" or URL) instead of spawning one
+    pub rerun_save_path: Option<String>,    // write an .rrd file instead of spawning a viewer
+    pub rerun_live_video: Option<bool>, // stream capture-video frames to Rerun while recording
+    pub rerun_live_fps: Option<f32>,    // frames per second per camera for the live stream
+    pub rerun_point_cloud: Option<bool>,      // log a colored point cloud per RealSense capture
+    pub rerun_point_cloud_stride: Option<u32>, // use every n-th depth pixel in each direction
+    pub connect_timeout_secs: Option<f32>,
+    pub read_timeout_secs: Option<f32>,
+    pub http_timeout_secs: Option<f32>,
+    pub rtsp_flush_frames: Option<u32>, // buffered frames dropped at most before an RTSP still, so it is current (default 30, 0 disables)
+    pub rtsp_transport: Option<String>, // "tcp", "udp" or "multicast" for every IP camera; default: FFmpeg's (UDP, falling back to TCP)
+    pub realsense_start_stagger_ms: Option<u64>,
+    pub realsense_reconnect_wait_secs: Option<f32>,
+    pub heartbeat_interval_secs: Option<f32>,
+    pub blocking_timeout_secs: Option<f32>,
+    pub session_mkv: Option<bool>,
+    pub skip_unchanged: Option<bool>,          // drop snapshots that match the camera's previous one
+    pub unchanged_max_distance: Option<u32>,   // perceptual hash bits (of 64) that may differ, default 4
+    pub stall_timeout_secs: Option<f32>, // reconnect a recording that wrote no frame for this long (default 10)
+    pub max_reconnects: Option<u32>,     // reconnects per camera and recording before giving up (default 3)
+    pub min_stream_kbps: Option<f64>,    // warn when a recording's bitrate falls below this (a degraded link); default: no check
+    pub http_fallback: Option<bool>,     // record from the HTTP snapshot endpoint when RTSP cannot be opened (default false)
+    pub operation_streams: Option<BTreeMap<String, String>>, // stream each operation reads, e.g. {test: sub, capture-video: main}; cameras can override
+    pub capture_retries: Option<u32>,           // re-attempt cameras that failed, after the rest of the batch (default 0)
+    pub capture_retry_delay_secs: Option<f32>,  // wait before each retry round (default 2)
+    pub start_at: Option<String>,       // start captures at an absolute UTC instant: "next-second", "next-minute", "next-<N>s" or RFC 3339
+    pub start_offset_ms: Option<u64>,   // added to the start_at instant (default 0)
+    pub max_concurrent_streams: Option<usize>, // cameras captured at once; the rest queue and run in later waves (default: all)
+    pub opencv_threads: Option<usize>, // threads for blocking OpenCV/RealSense work (default: one per camera, at least one per core)
+    pub video_timing: Option<String>,    // "cfr" (default, paced to video_fps) or "vfr" (FFmpeg, real per-frame timestamps)
+    pub frame_timestamps: Option<bool>,  // write <video>.timestamps.csv with each frame's capture time (default: on for vfr)
+    pub max_file_size_mb: Option<u64>, // roll recordings over to `_part2`, `_part3`, ... files before this size
+    pub min_free_disk_mb: Option<u64>, // default 1024; captures refuse to start and recordings stop below this, 0 disables
+    pub ntp_server: Option<String>,
+    pub require_host_clock_sync: Option<bool>,
+    pub ffmpeg_path: Option<String>,
+    pub coverage_workspace: Option<CoverageWorkspace>,
+    pub overlay: Option<OverlaySettings>,
+    pub import_patterns: Option<Vec<String>>, // filename templates for `rcam import`, e.g. "{camera}-{timestamp}.{ext}"
+    pub storage: Option<StorageConfig>, // upload finished sessions to a share, S3 or SFTP
+    pub post_capture_hooks: Option<Vec<PostCaptureHook>>, // commands/URLs told about every finished session
+    pub agent: Option<AgentConfig>,             // `rcam agent`: serve captures to a coordinator over HTTP
+    pub coordinator: Option<CoordinatorConfig>, // `rcam coordinator`: the agents this instance drives
+    pub supervisor: Option<SupervisorConfig>, // restart policy for long-lived per-camera tasks
+    pub checksums: Option<bool>, // record a SHA-256 of every saved file in the session manifest
+    pub quality_checks: Option<QualityChecks>, // measure focus, brightness and clipping of every snapshot
+    pub embed_metadata: Option<bool>, // default true; write capture time, camera name and location into JPEG/PNG snapshots
+    pub path_template: Option<String>, // camera output folder, e.g. "{output_base}/{date}/{camera}/{type}" (default "{output_base}/{session}/{camera}")
+    pub default_profile: Option<String>, // entry of `profiles` used when --profile is not given
+    pub latitude: Option<f64>,  // site location for sunrise/sunset schedule triggers, degrees north
+    pub longitude: Option<f64>, // degrees east
+}
+
+/// How the supervisor restarts long-lived per-camera tasks that end.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SupervisorConfig {
+    pub restart: Option<String>,          // "never", "always", "on-failure" (default) or "backoff"
+    pub restart_delay_secs: Option<f32>,  // delay before a restart, the first one for backoff (default 1)
+    pub max_backoff_secs: Option<f32>,    // longest backoff delay (default 60)
+}
+
+/// A command or HTTP endpoint notified after each capture session with the produced files.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PostCaptureHook {
+    pub name: Option<String>,
+    pub command: Option<String>,   // run with `sh -c`; files as $1..$n, metadata in RCAM_* env vars
+    pub url: Option<String>,       // receives the same data as a JSON POST
+    pub on: Option<String>,        // "image", "video" or "all" (default)
+    pub timeout_secs: Option<f32>, // default 30
+    pub required: Option<bool>,    // default false; if true, a failure fails the capture
+}
+
+impl PostCaptureHook {
+    pub fn applies_to(&self, kind: &str) -> bool {
+        match self.on.as_deref() {
+            None | Some("all") => true,
+            Some(on) => on.eq_ignore_ascii_case(kind),
+        }
+    }
+}
+
+/// HTTP and gRPC servers of `rcam agent`. The bearer token is read from RCAM_AGENT_TOKEN.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AgentConfig {
+    pub listen: Option<String>,      // address to listen on, default "0.0.0.0:8700"
+    pub grpc_listen: Option<String>, // also serve the gRPC API (proto/rcam.proto) here, e.g. "0.0.0.0:8701"
+}
+
+/// The remote rcam agents a coordinator runs captures on.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CoordinatorConfig {
+    pub agents: Vec<RemoteAgent>,
+    pub start_lead_secs: Option<f32>,      // the shared start is at least this far away, so every agent gets the request in time (default 5)
+    pub request_timeout_secs: Option<f32>, // per request, on top of the lead and any recording duration (default 30)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemoteAgent {
+    pub name: String,
+    pub url: String, // e.g. "http://10.0.0.11:8700"
+}
+
+/// Where finished capture sessions are uploaded.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StorageConfig {
+    pub backend: String,            // "local" (a directory, e.g. an NFS mount), "s3" or "sftp"
+    pub delete_local: Option<bool>, // default false; delete local media once uploaded (the manifest is kept)
+    pub path: Option<String>,       // destination directory for the local backend
+    pub s3: Option<S3StorageConfig>,
+    pub sftp: Option<SftpStorageConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct S3StorageConfig {
+    pub bucket: String,
+    pub region: Option<String>,   // default "us-east-1"
+    pub endpoint: Option<String>, // S3-compatible store, e.g. "http://minio.local:9000"
+    pub prefix: Option<String>,   // key prefix inside the bucket
+    pub path_style: Option<bool>, // default true with a custom endpoint
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SftpStorageConfig {
+    pub host: String,
+    pub port: Option<u16>, // default 22
+    pub username: Option<String>,
+    pub remote_dir: String,
+    pub identity_file: Option<String>, // private key; default: ssh agent and ~/.ssh/config
+}
+
+/// Text burned into every recorded video frame.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub show_timestamp: Option<bool>,   // default true; wall-clock time the frame was read
+    pub show_camera_name: Option<bool>, // default true
+    pub timestamp_format: Option<String>, // chrono format, default "%Y-%m-%d %H:%M:%S%.3f %Z"
+    pub utc: Option<bool>,              // default false (local time)
+    pub position: Option<String>,       // "top" or "bottom" (default)
+    pub font_scale: Option<f64>,        // default scales with frame width
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IpCameraSpecificConfig {
     pub ip: String,
+    pub mac_address: Option<String>, // found by MAC (ARP) before each capture, so a new DHCP lease does not lose the camera
+    pub vendor: Option<String>, // "dahua" (default, also Amcrest) or "axis"
     pub username: Option<String>,
-    pub http_port: Option<u16>,
+    pub auth: Option<String>, // "digest" (default), "basic", "bearer" ({NAME}_TOKEN env var) or "none"
+    pub http_scheme: Option<String>, // "http" (default) or "https"
+    pub http_port: Option<u16>,      // default 80 for http, 443 for https
+    pub snapshot_path: Option<String>, // overrides the vendor snapshot endpoint; "{channel}" is substituted
+    pub snapshot_channel: Option<u32>, // default 1; NVRs expose one channel per attached camera
+    pub tls: Option<TlsConfig>,
+    pub post_process: Option<Vec<PostProcessStep>>,
+    pub cpu_affinity: Option<Vec<usize>>, // CPUs this camera's OpenCV work is pinned to (Linux), e.g. its NUMA node's cores
+    pub schedule: Option<ScheduleConfig>, // when `rcam daemon` takes snapshots
+    pub imaging: Option<ImagingRequestConfig>, // exposure/gain/white balance applied by `rcam set`
+    pub extrinsics: Option<CameraExtrinsics>,
+    pub field_of_view: Option<FieldOfView>,
+    pub location: Option<GeoLocation>, // embedded in snapshots; default: application latitude/longitude
     pub rtsp_port: Option<u16>,
     pub rtsp_path: Option<String>,
+    pub rtsp_flush_frames: Option<u32>, // buffered frames dropped at most before an RTSP still (overrides the application default)
+    pub rtsp_transport: Option<String>, // "tcp", "udp" or "multicast" (overrides the application default)
+    pub min_stream_kbps: Option<f64>, // recording bitrate floor (overrides the application default)
+    pub http_fallback: Option<bool>, // snapshot fallback when RTSP fails (overrides the application default)
+    pub stream: Option<String>, // default stream: "main", "sub", an ONVIF profile token or an RTSP path ("/...")
+    pub streams: Option<BTreeMap<String, String>>, // named streams, e.g. {third: "/Streaming/Channels/103"}; values as for `stream`
+    pub operation_streams: Option<BTreeMap<String, String>>, // stream name per operation (overrides the application's)
+    pub onvif_port: Option<u16>,
+    pub connect_timeout_secs: Option<f32>,
+    pub read_timeout_secs: Option<f32>,
+    pub http_timeout_secs: Option<f32>,
+}
+
+/// One step of a per-camera image transform chain, written in YAML as a single-key map,
+/// e.g. `post_process: [{rotate: 180}, {resize: 1280x720}, {timestamp_overlay: true}]`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostProcessStep {
+    Rotate(u32),     // clockwise degrees: 90, 180 or 270
+    Resize(String),  // "WIDTHxHEIGHT"
+    Crop(CropRect),
+    Flip(String),    // "horizontal", "vertical" or "both"
+    TimestampOverlay(bool),
+    Annotate(String), // fixed text drawn in the top-left corner
+}
+
+/// A camera's snapshot schedule for daemon mode: either a single cron expression or sun
+/// trigger (`"*/5 * * * *"`, `"sunset-30m"`), or the detailed form.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ScheduleConfig {
+    Expression(String),
+    Detailed(DetailedSchedule),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DetailedSchedule {
+    pub cron: Option<String>,          // five-field cron expression in local time
+    pub triggers: Option<Vec<String>>, // "sunrise", "sunset", optionally offset: "sunrise+15m", "sunset-1h"
+    pub quiet_hours: Option<String>,   // local "HH:MM-HH:MM" window with no captures, may wrap midnight
+    pub daylight_only: Option<bool>,   // skip captures between sunset and sunrise
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Calibrated camera-to-rig pose: a rig point is `rotation * camera_point + translation`.
+/// Camera axes follow OpenCV (+X right, +Y down, +Z forward).
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct CameraExtrinsics {
+    pub translation: [f64; 3], // metres, in the rig frame
+    pub rotation: [f64; 4],    // unit quaternion [x, y, z, w]
+}
+
+/// Viewing frustum of a camera, used for coverage analysis.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct FieldOfView {
+    pub horizontal_deg: f64,
+    pub vertical_deg: f64,
+    pub min_range_m: Option<f64>, // default 0.1
+    pub max_range_m: Option<f64>, // default 10.0; a RealSense depth range is usually much shorter
+}
+
+/// Thresholds for the per-snapshot quality metrics; the metrics are recorded in the session
+/// manifest whenever this section is present.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QualityChecks {
+    pub min_sharpness: Option<f64>,       // variance of the Laplacian; depends on scene and resolution, start around 100
+    pub min_brightness: Option<f64>,      // mean grey level, 0-255
+    pub max_brightness: Option<f64>,
+    pub max_clipped_percent: Option<f64>, // pixels at 0 or 255
+    pub on_fail: Option<String>,          // "warn" (default) or "fail" (the camera's capture counts as failed)
+}
+
+impl QualityChecks {
+    pub fn fails_capture(&self) -> bool {
+        self.on_fail.as_deref().is_some_and(|action| action.eq_ignore_ascii_case("fail"))
+    }
+}
+
+/// Where a camera is, written into the GPS fields of its snapshots.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct GeoLocation {
+    pub latitude: f64,  // degrees north
+    pub longitude: f64, // degrees east
+    pub altitude_m: Option<f64>, // metres above sea level
+}
+
+/// Axis-aligned box in the rig frame that a capture campaign needs to see.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct CoverageWorkspace {
+    pub min: [f64; 3], // metres
+    pub max: [f64; 3],
+    pub voxel_size_m: f64,
+    pub min_cameras: Option<u32>, // cameras that must see a voxel for it to count as covered (default 2)
+}
+
+/// TLS options for `https` camera endpoints.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TlsConfig {
+    pub verify: Option<bool>,        // default true; false accepts self-signed/invalid certificates
+    pub ca_cert: Option<String>,     // PEM file with an extra root certificate to trust
+    pub client_cert: Option<String>, // PEM client certificate for mutual TLS
+    pub client_key: Option<String>,  // PEM (PKCS#8) key for client_cert
+}
+
+/// Effective network timeouts for a single camera, resolved from the per-camera
+/// config first, then the application defaults, then built-in fallbacks.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraTimeouts {
+    pub connect: Duration,
+    pub read: Duration,
+    pub http: Duration,
+}
+
+impl CameraTimeouts {
+    pub const DEFAULT_CONNECT_SECS: f32 = 5.0;
+    pub const DEFAULT_READ_SECS: f32 = 5.0;
+    pub const DEFAULT_HTTP_SECS: f32 = 10.0;
+
+    pub fn from_app(app: &AppSettings) -> Self {
+        Self::resolve(app, None)
+    }
+
+    pub fn resolve(app: &AppSettings, camera: Option<&IpCameraSpecificConfig>) -> Self {
+        let pick = |cam: Option<f32>, app: Option<f32>, default: f32| {
+            Duration::from_secs_f32(cam.or(app).unwrap_or(default))
+        };
+        CameraTimeouts {
+            connect: pick(camera.and_then(|c| c.connect_timeout_secs), app.connect_timeout_secs, Self::DEFAULT_CONNECT_SECS),
+            read: pick(camera.and_then(|c| c.read_timeout_secs), app.read_timeout_secs, Self::DEFAULT_READ_SECS),
+            http: pick(camera.and_then(|c| c.http_timeout_secs), app.http_timeout_secs, Self::DEFAULT_HTTP_SECS),
+        }
+    }
+
+    /// Timeouts for a thermal camera, which only talks HTTP.
+    pub fn resolve_thermal(app: &AppSettings, camera: &ThermalCameraSpecificConfig) -> Self {
+        let mut timeouts = Self::from_app(app);
+        if let Some(secs) = camera.connect_timeout_secs {
+            timeouts.connect = Duration::from_secs_f32(secs);
+        }
+        if let Some(secs) = camera.http_timeout_secs {
+            timeouts.http = Duration::from_secs_f32(secs);
+        }
+        timeouts
+    }
+}
+
+/// Operations that read an RTSP stream, the keys of `operation_streams`.
+pub const STREAM_OPERATIONS: [&str; 5] = ["capture-image", "capture-video", "aim", "test", "daemon"];
+
+impl IpCameraSpecificConfig {
+    /// The stream `operation` reads: this camera's `operation_streams` entry, then the
+    /// application's. None means the default `stream`.
+    pub fn stream_for(&self, app: &AppSettings, operation: &str) -> Option<String> {
+        self.operation_streams.as_ref().and_then(|streams| streams.get(operation))
+            .or_else(|| app.operation_streams.as_ref().and_then(|streams| streams.get(operation)))
+            .cloned()
+    }
+
+    /// True if `name` is one of this camera's streams: a `streams` entry, "main" or "sub".
+    pub fn has_stream(&self, name: &str) -> bool {
+        name == "main" || name == "sub" || self.streams.as_ref().is_some_and(|streams| streams.contains_key(name))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -48,6 +381,106 @@ pub struct RealsenseSpecificConfig {
     pub depth_fps: Option<u32>,
     pub enable_color_stream: Option<bool>,
     pub enable_depth_stream: Option<bool>,
+    pub post_process: Option<Vec<PostProcessStep>>, // color gets the full chain, depth only geometric steps
+    pub cpu_affinity: Option<Vec<usize>>, // CPUs the capture thread is pinned to (Linux), e.g. the USB controller's NUMA node
+    pub schedule: Option<ScheduleConfig>, // when `rcam daemon` takes snapshots
+    pub depth_format: Option<String>, // "png" (default, 16-bit), "tiff", "npy", "raw" or "exr" (float metres)
+    pub depth_preview: Option<DepthPreviewConfig>, // also save a colorized 8-bit PNG of each depth frame
+    pub visual_preset: Option<String>, // "default", "hand", "high_accuracy", "high_density", "medium_density" or a RealSense Viewer JSON file (advanced mode)
+    pub laser_power: Option<f32>,      // IR projector power in mW (D4xx: 0-360)
+    pub emitter_enabled: Option<bool>,
+    pub exposure: Option<String>,      // depth sensor: "auto" or a fixed time ("8ms", "8000us", "1/125")
+    pub inter_cam_sync_mode: Option<String>, // "default", "master", "slave", "full_slave" or "genlock" (D4xx sync cable)
+    pub record_bag: Option<bool>,      // `capture-video` records this camera to a librealsense .bag (default false)
+    pub bag_file: Option<String>,      // replay this .bag (looping) instead of opening a device
+    pub extrinsics: Option<CameraExtrinsics>,
+    pub field_of_view: Option<FieldOfView>,
+    pub location: Option<GeoLocation>, // embedded in snapshots; default: application latitude/longitude
+}
+
+/// A colorized 8-bit PNG saved next to each RealSense depth frame, for a quick look without a
+/// 16-bit viewer. Pixels without depth are black.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DepthPreviewConfig {
+    pub colormap: Option<String>, // "turbo" (default), "jet", "inferno", "magma", "plasma", "viridis" or "bone"
+    pub min_m: Option<f32>,       // depth at the start of the colormap; default: nearest pixel of each frame
+    pub max_m: Option<f32>,       // depth at its end; default: farthest pixel of each frame
+}
+
+impl RealsenseSpecificConfig {
+    /// Raw USB bandwidth (Mbit/s) needed by the enabled streams, using the same defaults as capture.
+    pub fn estimated_bandwidth_mbps(&self) -> f64 {
+        let mut total = 0.0;
+        if self.enable_color_stream.unwrap_or(true) {
+            total += crate::camera::realsense_bandwidth::estimate_stream_mbps(
+                self.color_width.unwrap_or(640), self.color_height.unwrap_or(480), self.color_fps.unwrap_or(30), 3);
+        }
+        if self.enable_depth_stream.unwrap_or(true) {
+            total += crate::camera::realsense_bandwidth::estimate_stream_mbps(
+                self.depth_width.unwrap_or(640), self.depth_height.unwrap_or(480), self.depth_fps.unwrap_or(30), 2);
+        }
+        total
+    }
+}
+
+/// A radiometric thermal camera, captured over its vendor's HTTP API.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ThermalCameraSpecificConfig {
+    pub ip: String,
+    pub vendor: Option<String>, // "flir" (default) or "hikvision"
+    pub username: Option<String>,
+    pub auth: Option<String>, // "digest" (default), "basic", "bearer" ({NAME}_TOKEN env var) or "none"
+    pub http_scheme: Option<String>, // "http" (default) or "https"
+    pub http_port: Option<u16>,      // default 80 for http, 443 for https
+    pub tls: Option<TlsConfig>,      // only used with http_scheme "https"
+    pub export: Option<String>, // "rjpeg" (FLIR default), "tiff" (Hikvision default: 16-bit centikelvin) or "both"
+    pub channel: Option<u32>, // Hikvision thermal channel (default 2; 1 is the visible sensor)
+    pub snapshot_path: Option<String>, // overrides the vendor endpoint; "{channel}" is substituted
+    pub cpu_affinity: Option<Vec<usize>>, // CPUs this camera's decoding and TIFF encoding is pinned to (Linux)
+    pub schedule: Option<ScheduleConfig>, // when `rcam daemon` takes snapshots
+    pub extrinsics: Option<CameraExtrinsics>,
+    pub field_of_view: Option<FieldOfView>,
+    pub location: Option<GeoLocation>, // default: application latitude/longitude
+    pub connect_timeout_secs: Option<f32>,
+    pub http_timeout_secs: Option<f32>,
+}
+
+/// A camera without hardware, for tests and demos: generated colour bars or a looped file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VirtualCameraSpecificConfig {
+    pub source: Option<String>, // "test_pattern" (default) or a video/image file
+    pub width: Option<u32>,     // test pattern size, default 1280x720
+    pub height: Option<u32>,
+    pub post_process: Option<Vec<PostProcessStep>>, // applied to stills, as for IP cameras
+    pub cpu_affinity: Option<Vec<usize>>,
+    pub schedule: Option<ScheduleConfig>, // when `rcam daemon` takes snapshots
+    pub extrinsics: Option<CameraExtrinsics>,
+    pub field_of_view: Option<FieldOfView>,
+    pub location: Option<GeoLocation>, // default: application latitude/longitude
+}
+
+/// Settings of a `!Custom` device. rcam reads the shared keys; everything else is left in
+/// `settings` for the registered factory.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CustomDeviceConfig {
+    pub post_process: Option<Vec<PostProcessStep>>,
+    pub cpu_affinity: Option<Vec<usize>>,
+    pub schedule: Option<ScheduleConfig>, // when `rcam daemon` takes snapshots
+    pub extrinsics: Option<CameraExtrinsics>,
+    pub field_of_view: Option<FieldOfView>,
+    pub location: Option<GeoLocation>, // default: application latitude/longitude
+    #[serde(flatten)]
+    pub settings: BTreeMap<String, serde_yaml::Value>, // the device type's own keys
+}
+
+impl CustomDeviceConfig {
+    /// The type's own keys as a typed struct, e.g. `specifics.settings_as::<GigeSettings>()`.
+    pub fn settings_as<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        let mapping: serde_yaml::Mapping = self.settings.iter()
+            .map(|(key, value)| (serde_yaml::Value::String(key.clone()), value.clone()))
+            .collect();
+        serde_yaml::from_value(serde_yaml::Value::Mapping(mapping)).context("Invalid custom device settings")
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -62,6 +495,24 @@ pub enum CaptureDeviceConfig {
         #[serde(flatten)]
         specifics: RealsenseSpecificConfig,
     },
+    ThermalCamera {
+        name: String,
+        #[serde(flatten)]
+        specifics: ThermalCameraSpecificConfig,
+    },
+    VirtualCamera {
+        name: String,
+        #[serde(flatten)]
+        specifics: VirtualCameraSpecificConfig,
+    },
+    /// A device type registered by the program embedding rcam (see `core::source_registry`).
+    Custom {
+        name: String,
+        #[serde(rename = "type")]
+        device_type: String,
+        #[serde(flatten)]
+        specifics: CustomDeviceConfig,
+    },
 }
 
 impl CaptureDeviceConfig {
@@ -69,37 +520,337 @@ impl CaptureDeviceConfig {
         match self {
             CaptureDeviceConfig::IpCamera { name, .. } => name,
             CaptureDeviceConfig::RealsenseCamera { name, .. } => name,
+            CaptureDeviceConfig::ThermalCamera { name, .. } => name,
+            CaptureDeviceConfig::VirtualCamera { name, .. } => name,
+            CaptureDeviceConfig::Custom { name, .. } => name,
         }
     }
+
+    /// The type the device's capture source is registered under: "ip-camera",
+    /// "realsense-camera", "thermal-camera", "virtual-camera" or a custom entry's `type`.
+    pub fn device_type(&self) -> &str {
+        match self {
+            CaptureDeviceConfig::IpCamera { .. } => crate::core::source_registry::IP_CAMERA,
+            CaptureDeviceConfig::RealsenseCamera { .. } => crate::core::source_registry::REALSENSE_CAMERA,
+            CaptureDeviceConfig::ThermalCamera { .. } => crate::core::source_registry::THERMAL_CAMERA,
+            CaptureDeviceConfig::VirtualCamera { .. } => crate::core::source_registry::VIRTUAL_CAMERA,
+            CaptureDeviceConfig::Custom { device_type, .. } => device_type,
+        }
+    }
+
+    pub fn post_process(&self) -> &[PostProcessStep] {
+        let steps = match self {
+            CaptureDeviceConfig::IpCamera { specifics, .. } => &specifics.post_process,
+            CaptureDeviceConfig::RealsenseCamera { specifics, .. } => &specifics.post_process,
+            CaptureDeviceConfig::ThermalCamera { .. } => return &[], // transforming would break the radiometric data
+            CaptureDeviceConfig::VirtualCamera { specifics, .. } => &specifics.post_process,
+            CaptureDeviceConfig::Custom { specifics, .. } => &specifics.post_process,
+        };
+        steps.as_deref().unwrap_or(&[])
+    }
+
+    pub fn cpu_affinity(&self) -> Option<&[usize]> {
+        match self {
+            CaptureDeviceConfig::IpCamera { specifics, .. } => specifics.cpu_affinity.as_deref(),
+            CaptureDeviceConfig::RealsenseCamera { specifics, .. } => specifics.cpu_affinity.as_deref(),
+            CaptureDeviceConfig::ThermalCamera { specifics, .. } => specifics.cpu_affinity.as_deref(),
+            CaptureDeviceConfig::VirtualCamera { specifics, .. } => specifics.cpu_affinity.as_deref(),
+            CaptureDeviceConfig::Custom { specifics, .. } => specifics.cpu_affinity.as_deref(),
+        }
+    }
+
+    pub fn schedule(&self) -> Option<&ScheduleConfig> {
+        match self {
+            CaptureDeviceConfig::IpCamera { specifics, .. } => specifics.schedule.as_ref(),
+            CaptureDeviceConfig::RealsenseCamera { specifics, .. } => specifics.schedule.as_ref(),
+            CaptureDeviceConfig::ThermalCamera { specifics, .. } => specifics.schedule.as_ref(),
+            CaptureDeviceConfig::VirtualCamera { specifics, .. } => specifics.schedule.as_ref(),
+            CaptureDeviceConfig::Custom { specifics, .. } => specifics.schedule.as_ref(),
+        }
+    }
+
+    pub fn location(&self) -> Option<GeoLocation> {
+        match self {
+            CaptureDeviceConfig::IpCamera { specifics, .. } => specifics.location,
+            CaptureDeviceConfig::RealsenseCamera { specifics, .. } => specifics.location,
+            CaptureDeviceConfig::ThermalCamera { specifics, .. } => specifics.location,
+            CaptureDeviceConfig::VirtualCamera { specifics, .. } => specifics.location,
+            CaptureDeviceConfig::Custom { specifics, .. } => specifics.location,
+        }
+    }
+
+    /// Calibrated pose and frustum, if both are configured.
+    pub fn pose(&self) -> Option<(CameraExtrinsics, FieldOfView)> {
+        let (extrinsics, field_of_view) = match self {
+            CaptureDeviceConfig::IpCamera { specifics, .. } => (specifics.extrinsics, specifics.field_of_view),
+            CaptureDeviceConfig::RealsenseCamera { specifics, .. } => (specifics.extrinsics, specifics.field_of_view),
+            CaptureDeviceConfig::ThermalCamera { specifics, .. } => (specifics.extrinsics, specifics.field_of_view),
+            CaptureDeviceConfig::VirtualCamera { specifics, .. } => (specifics.extrinsics, specifics.field_of_view),
+            CaptureDeviceConfig::Custom { specifics, .. } => (specifics.extrinsics, specifics.field_of_view),
+        };
+        extrinsics.zip(field_of_view)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MasterConfig {
     pub application: AppSettings,
     pub cameras: Vec<CaptureDeviceConfig>, // Now a list of different device types
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>, // set at load time to the profile merged into `application` and `cameras`
+}
+
+/// Adjustments applied while loading, on top of what the file says.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub profile: Option<String>, // entry of `profiles` to merge in; falls back to application.default_profile
+    pub set: Vec<String>,        // "path.to.key=value" assignments applied last, e.g. from `--set`
 }
 
 pub fn load_config(path: &str) -> Result<MasterConfig> {
+    load_config_with(path, &ConfigOverrides::default())
+}
+
+/// Loads the configuration with a named entry of its top-level `profiles` map merged in.
+/// `profile` falls back to `application.default_profile`; with neither, profiles are ignored.
+pub fn load_config_with_profile(path: &str, profile: Option<&str>) -> Result<MasterConfig> {
+    load_config_with(path, &ConfigOverrides { profile: profile.map(str::to_string), ..Default::default() })
+}
+
+/// Loads the configuration as `rcam` does: camera include files, then the profile, then the
+/// `set` overrides, and validates the result.
+pub fn load_config_with(path: &str, overrides: &ConfigOverrides) -> Result<MasterConfig> {
     debug!("📄 Attempting to load config from: {}", path);
     let start_time = Instant::now();
 
     let config_str = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read configuration file \'{}\'. 📖", path))?;
+        .with_kind(ErrorKind::Config, || format!("Failed to read configuration file \'{}\'. 📖", path))?;
     debug!("Read config file in {:?}", start_time.elapsed());
     
     let parse_start_time = Instant::now();
-    let config: MasterConfig = serde_yaml::from_str(&config_str)
-        .with_context(|| format!("Failed to parse YAML configuration from \'{}\'. 💔", path))?;
+    let mut document: serde_yaml::Value = serde_yaml::from_str(&config_str)
+        .with_kind(ErrorKind::Config, || format!("Failed to parse YAML configuration from \'{}\'. 💔", path))?;
+    include_camera_files(&mut document, Path::new(path).parent().unwrap_or(Path::new(".")))
+        .with_kind(ErrorKind::Config, || format!("Failed to include camera files from \'{}\'", path))?;
+    let active_profile = apply_profile(&mut document, overrides.profile.as_deref())
+        .with_kind(ErrorKind::Config, || format!("Failed to apply profile from \'{}\'", path))?;
+    for assignment in &overrides.set {
+        apply_override(&mut document, assignment).with_kind(ErrorKind::Config, || format!("❌ Invalid override '{}'", assignment))?;
+    }
+    let mut config: MasterConfig = serde_yaml::from_value(document)
+        .with_kind(ErrorKind::Config, || format!("Failed to parse YAML configuration from \'{}\'. 💔", path))?;
+    config.active_profile = active_profile;
     debug!("Parsed YAML in {:?}", parse_start_time.elapsed());
 
     let validate_start_time = Instant::now();
-    validate_master_config(&config).with_context(|| "Master configuration validation failed 👎")?;
+    validate_master_config(&config).with_kind(ErrorKind::Config, || "Master configuration validation failed 👎")?;
     debug!("Validated master config in {:?}", validate_start_time.elapsed());
 
     info!("✅ Successfully loaded and validated configuration from \'{}\' in {:?}", path, start_time.elapsed());
     Ok(config)
 }
 
+/// Appends the cameras from the files matched by the top-level `cameras_include` glob patterns
+/// (relative to the config file's directory) to `cameras`, so each camera can live in its own
+/// file. A file holds one camera or a list of them, in the same `!IpCamera`/`!RealsenseCamera`
+/// form as the main file. Matches are added in path order.
+fn include_camera_files(document: &mut serde_yaml::Value, config_dir: &Path) -> Result<()> {
+    use serde_yaml::Value;
+    let Some(root) = document.as_mapping_mut() else {
+        return Ok(());
+    };
+    let patterns: Vec<String> = match root.remove("cameras_include") {
+        Some(value @ (Value::Sequence(_) | Value::String(_))) => serde_yaml::from_value::<OneOrMany>(value)?.into_vec(),
+        Some(Value::Null) | None => return Ok(()),
+        Some(_) => bail!("❌ 'cameras_include' must be a glob pattern or a list of them."),
+    };
+
+    let mut sources: HashMap<String, String> = HashMap::new();
+    let cameras = root.entry(Value::from("cameras")).or_insert_with(|| Value::Sequence(Vec::new()));
+    let cameras = cameras.as_sequence_mut().context("❌ 'cameras' must be a list")?;
+    for camera in cameras.iter() {
+        if let Some(name) = camera_name(camera) {
+            sources.insert(name, "the main config".to_string());
+        }
+    }
+    for pattern in patterns {
+        let full_pattern = config_dir.join(&pattern);
+        let mut files: Vec<_> = glob::glob(&full_pattern.to_string_lossy())
+            .with_context(|| format!("❌ Invalid cameras_include pattern '{}'", pattern))?
+            .collect::<std::result::Result<_, _>>()
+            .with_context(|| format!("❌ Failed to list files for cameras_include pattern '{}'", pattern))?;
+        if files.is_empty() {
+            bail!("❌ cameras_include pattern '{}' matches no files (looked for {}).", pattern, full_pattern.display());
+        }
+        files.sort();
+        for file in files {
+            let text = fs::read_to_string(&file).with_context(|| format!("Failed to read camera file {}", file.display()))?;
+            let included: Value = serde_yaml::from_str(&text).with_context(|| format!("Failed to parse camera file {}", file.display()))?;
+            let included = match included {
+                Value::Sequence(list) => list,
+                Value::Null => Vec::new(),
+                single => vec![single],
+            };
+            for camera in included {
+                let name = camera_name(&camera).with_context(|| format!("❌ A camera in {} has no name", file.display()))?;
+                if let Some(previous) = sources.insert(name.clone(), file.display().to_string()) {
+                    bail!("❌ Camera '{}' in {} is already defined in {}.", name, file.display(), previous);
+                }
+                cameras.push(camera);
+            }
+            debug!("Included camera file {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+/// Name of a camera entry, looking inside its `!IpCamera`/`!RealsenseCamera` tag.
+fn camera_name(camera: &serde_yaml::Value) -> Option<String> {
+    let settings = match camera {
+        serde_yaml::Value::Tagged(tagged) => &tagged.value,
+        other => other,
+    };
+    settings.get("name").and_then(serde_yaml::Value::as_str).map(str::to_string)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// Applies one `path.to.key=value` override. The value is parsed as YAML, so numbers, booleans
+/// and `[lists]` keep their types. `cameras.<name>` selects a camera by name (`cameras.*` every
+/// camera); missing keys are created, so optional settings can be set too.
+fn apply_override(document: &mut serde_yaml::Value, assignment: &str) -> Result<()> {
+    use serde_yaml::Value;
+    let (path, raw_value) = assignment.split_once('=').context("expected KEY=VALUE")?;
+    let keys: Vec<&str> = path.trim().split('.').collect();
+    if keys.iter().any(|key| key.is_empty()) {
+        bail!("'{}' is not a dotted key path", path);
+    }
+    let value: Value = serde_yaml::from_str(raw_value).with_context(|| format!("value '{}' is not valid YAML", raw_value))?;
+
+    if keys[0] == "cameras" {
+        let [_, camera, rest @ ..] = keys.as_slice() else {
+            bail!("use cameras.<name>.<key> to override a camera setting");
+        };
+        if rest.is_empty() {
+            bail!("use cameras.<name>.<key> to override a camera setting");
+        }
+        let cameras = document.get_mut("cameras").and_then(Value::as_sequence_mut).context("configuration has no 'cameras' list")?;
+        let mut found = false;
+        for entry in cameras.iter_mut() {
+            if *camera == "*" || camera_name(entry).as_deref() == Some(*camera) {
+                let settings = match entry {
+                    Value::Tagged(tagged) => &mut tagged.value,
+                    other => other,
+                };
+                set_yaml_path(settings, rest, value.clone())?;
+                found = true;
+            }
+        }
+        if !found {
+            bail!("camera '{}' is not configured", camera);
+        }
+    } else {
+        set_yaml_path(document, &keys, value)?;
+    }
+    info!("🔧 Config override: {} = {}", path.trim(), raw_value.trim());
+    Ok(())
+}
+
+fn set_yaml_path(mut node: &mut serde_yaml::Value, keys: &[&str], value: serde_yaml::Value) -> Result<()> {
+    for (idx, key) in keys.iter().enumerate() {
+        let mapping = node.as_mapping_mut().with_context(|| format!("'{}' is not a map", keys[..idx].join(".")))?;
+        let key = serde_yaml::Value::from(*key);
+        if idx == keys.len() - 1 {
+            mapping.insert(key, value);
+            return Ok(());
+        }
+        node = mapping.entry(key).or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+    }
+    Ok(())
+}
+
+/// Removes `profiles` from the document and merges the selected one into it: the profile's
+/// `application` map over `application`, and its `cameras` maps over each camera, `"*"` first
+/// and then the camera's own entry. Maps merge key by key; any other value replaces.
+fn apply_profile(document: &mut serde_yaml::Value, requested: Option<&str>) -> Result<Option<String>> {
+    use serde_yaml::Value;
+    let profiles = match document.as_mapping_mut().and_then(|root| root.remove("profiles")) {
+        Some(Value::Mapping(profiles)) => profiles,
+        Some(Value::Null) | None => serde_yaml::Mapping::new(),
+        Some(_) => bail!("❌ 'profiles' must map profile names to settings."),
+    };
+    let default_profile = document.get("application").and_then(|app| app.get("default_profile")).and_then(Value::as_str).map(str::to_string);
+    let Some(name) = requested.map(str::to_string).or(default_profile) else {
+        return Ok(None);
+    };
+    let Some(profile) = profiles.get(name.as_str()) else {
+        let mut available: Vec<&str> = profiles.keys().filter_map(Value::as_str).collect();
+        available.sort();
+        bail!("❌ Unknown profile '{}' (available: {})", name, if available.is_empty() { "none".to_string() } else { available.join(", ") });
+    };
+
+    if let Some(overrides) = profile.get("application") {
+        let application = document.get_mut("application").context("❌ Configuration has no 'application' section")?;
+        merge_yaml(application, overrides);
+    }
+    if let Some(camera_overrides) = profile.get("cameras") {
+        let overrides = camera_overrides.as_mapping().context("❌ A profile's 'cameras' must map camera names (or \"*\") to settings")?;
+        let cameras = document.get_mut("cameras").and_then(Value::as_sequence_mut).context("❌ Configuration has no 'cameras' list")?;
+        let mut matched = HashSet::new();
+        for camera in cameras.iter_mut() {
+            // Cameras are written with a `!IpCamera`/`!RealsenseCamera` tag; the settings are inside it.
+            let settings = match camera {
+                Value::Tagged(tagged) => &mut tagged.value,
+                other => other,
+            };
+            let camera_name = camera_name(settings).unwrap_or_default();
+            for key in ["*", camera_name.as_str()] {
+                if let Some(camera_override) = overrides.get(key) {
+                    merge_yaml(settings, camera_override);
+                    matched.insert(key.to_string());
+                }
+            }
+        }
+        for key in overrides.keys().filter_map(Value::as_str) {
+            if key != "*" && !matched.contains(key) {
+                bail!("❌ Profile '{}' overrides camera '{}', which is not configured.", name, key);
+            }
+        }
+    }
+    info!("🎛️ Using configuration profile '{}'.", name);
+    Ok(Some(name))
+}
+
+fn merge_yaml(base: &mut serde_yaml::Value, overrides: &serde_yaml::Value) {
+    match (base, overrides) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
 fn validate_master_config(config: &MasterConfig) -> Result<()> {
     debug!("🕵️ Validating master configuration...");
     let validation_start_time = Instant::now();
@@ -127,11 +878,191 @@ fn validate_master_config(config: &MasterConfig) -> Result<()> {
         bail!("❌ Application video_format cannot be empty.");
     }
 
+    validate_timeouts("application", config.application.connect_timeout_secs, config.application.read_timeout_secs, config.application.http_timeout_secs)?;
+    for (key, value) in [("heartbeat_interval_secs", config.application.heartbeat_interval_secs), ("blocking_timeout_secs", config.application.blocking_timeout_secs), ("stall_timeout_secs", config.application.stall_timeout_secs)] {
+        if let Some(secs) = value {
+            if !secs.is_finite() || secs <= 0.0 {
+                bail!("❌ Application {} must be a positive number of seconds (got {}).", key, secs);
+            }
+        }
+    }
+    for (key, value) in [
+        ("realsense_reconnect_wait_secs", config.application.realsense_reconnect_wait_secs),
+        ("capture_retry_delay_secs", config.application.capture_retry_delay_secs),
+        ("time_sync_tolerance_seconds", config.application.time_sync_tolerance_seconds),
+    ] {
+        if let Some(secs) = value {
+            if !secs.is_finite() || secs < 0.0 {
+                bail!("❌ Application {} must be a non-negative number of seconds (got {}).", key, secs);
+            }
+        }
+    }
+
+    crate::camera::rtsp_grab::RtspTransport::parse(config.application.rtsp_transport.as_deref())
+        .context("❌ Invalid application rtsp_transport")?;
+    validate_operation_streams("application", config.application.operation_streams.as_ref())?;
+    for (target, level) in config.application.log_levels.iter().flatten() {
+        crate::common::logging_setup::module_directive(target, level)
+            .with_context(|| format!("❌ Invalid application log_levels entry '{}'", target))?;
+    }
+    crate::common::logging_setup::LogFormat::parse(config.application.log_format.as_deref())
+        .context("❌ Invalid application log_format")?;
+    crate::common::logging_setup::parse_rotation(config.application.log_rotation.as_deref())
+        .context("❌ Invalid application log_rotation")?;
+    if config.application.log_max_files == Some(0) {
+        bail!("❌ Application log_max_files must be at least 1.");
+    }
+    if config.application.log_max_size_mb == Some(0) {
+        bail!("❌ Application log_max_size_mb must be at least 1.");
+    }
+    if config.application.log_max_size_mb.is_some() && config.application.log_rotation.is_some() {
+        bail!("❌ Application log_max_size_mb and log_rotation are exclusive; a log file rotates either by size or by time.");
+    }
+    if let Some(kbps) = config.application.min_stream_kbps {
+        if !(kbps.is_finite() && kbps > 0.0) {
+            bail!("❌ Application min_stream_kbps must be positive (got {}).", kbps);
+        }
+    }
+
+    if let Some(fps) = config.application.rerun_live_fps {
+        if !(fps.is_finite() && fps > 0.0) {
+            bail!("❌ Application rerun_live_fps must be positive (got {}).", fps);
+        }
+    }
+
+    if config.application.unchanged_max_distance.is_some_and(|d| d > 63) {
+        bail!("❌ Application unchanged_max_distance must be between 0 and 63 bits.");
+    }
+
+    if let Some(timing) = &config.application.video_timing {
+        if !matches!(timing.to_lowercase().as_str(), "cfr" | "vfr") {
+            bail!("❌ Application video_timing must be \"cfr\" or \"vfr\" (got '{}').", timing);
+        }
+    }
+
+    if let Some(start_at) = &config.application.start_at {
+        if let Err(e) = crate::common::sync_start::SyncStart::parse(start_at, Duration::ZERO) {
+            bail!("❌ Application start_at: {:#}", e);
+        }
+    }
+
+    if config.application.max_concurrent_streams == Some(0) {
+        bail!("❌ Application max_concurrent_streams must be at least 1.");
+    }
+    if let Some(supervisor) = &config.application.supervisor {
+        crate::core::supervisor::RestartPolicy::from_config(Some(supervisor)).context("❌ Invalid supervisor.restart")?;
+        for (key, value) in [("restart_delay_secs", supervisor.restart_delay_secs), ("max_backoff_secs", supervisor.max_backoff_secs)] {
+            if let Some(secs) = value {
+                if !secs.is_finite() || secs < 0.0 {
+                    bail!("❌ supervisor.{} must be a non-negative number of seconds (got {}).", key, secs);
+                }
+            }
+        }
+    }
+
+    if let Some(checks) = &config.application.quality_checks {
+        if let Some(action) = &checks.on_fail {
+            if !matches!(action.to_lowercase().as_str(), "warn" | "fail") {
+                bail!("❌ quality_checks.on_fail must be \"warn\" or \"fail\" (got '{}').", action);
+            }
+        }
+        for (key, value, max) in [
+            ("min_sharpness", checks.min_sharpness, f64::INFINITY),
+            ("min_brightness", checks.min_brightness, 255.0),
+            ("max_brightness", checks.max_brightness, 255.0),
+            ("max_clipped_percent", checks.max_clipped_percent, 100.0),
+        ] {
+            if let Some(value) = value {
+                if !(0.0..=max).contains(&value) {
+                    bail!("❌ quality_checks.{} must be between 0 and {} (got {}).", key, max, value);
+                }
+            }
+        }
+    }
+
+    if let Some(template) = &config.application.path_template {
+        crate::common::path_template::validate(template).context("❌ Invalid application path_template")?;
+    }
+
+    if config.application.opencv_threads == Some(0) {
+        bail!("❌ Application opencv_threads must be at least 1.");
+    }
+
+    match (config.application.latitude, config.application.longitude) {
+        (Some(lat), Some(lon)) => {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                bail!("❌ Application latitude must be within ±90 and longitude within ±180 degrees (got {}, {}).", lat, lon);
+            }
+        }
+        (None, None) => {}
+        _ => bail!("❌ Application latitude and longitude must be set together."),
+    }
+
+    if let Some(mb) = config.application.max_file_size_mb {
+        if mb < 10 {
+            bail!("❌ Application max_file_size_mb must be at least 10 (got {}).", mb);
+        }
+    }
+
+    if config.application.rerun_point_cloud_stride == Some(0) {
+        bail!("❌ Application rerun_point_cloud_stride must be 1 or greater.");
+    }
+
+    if let Some(overlay) = &config.application.overlay {
+        if let Some(position) = &overlay.position {
+            if !matches!(position.to_lowercase().as_str(), "top" | "bottom") {
+                bail!("❌ overlay.position must be \"top\" or \"bottom\" (got '{}').", position);
+            }
+        }
+        if let Some(scale) = overlay.font_scale {
+            if !(scale.is_finite() && scale > 0.0) {
+                bail!("❌ overlay.font_scale must be positive (got {}).", scale);
+            }
+        }
+    }
+    if let Some(workspace) = &config.application.coverage_workspace {
+        validate_coverage_workspace(workspace)?;
+    }
+    for (idx, hook) in config.application.post_capture_hooks.iter().flatten().enumerate() {
+        let label = hook.name.clone().unwrap_or_else(|| format!("#{}", idx + 1));
+        if hook.command.is_none() && hook.url.is_none() {
+            bail!("❌ post_capture_hooks {} needs a command or a url.", label);
+        }
+        if let Some(on) = &hook.on {
+            if !["image", "video", "all"].contains(&on.to_lowercase().as_str()) {
+                bail!("❌ post_capture_hooks {}: on must be \"image\", \"video\" or \"all\" (got '{}').", label, on);
+            }
+        }
+        if let Some(secs) = hook.timeout_secs {
+            if !(secs.is_finite() && secs > 0.0) {
+                bail!("❌ post_capture_hooks {}: timeout_secs must be positive (got {}).", label, secs);
+            }
+        }
+    }
+    if let Some(agent) = &config.application.agent {
+        for (key, listen) in [("listen", &agent.listen), ("grpc_listen", &agent.grpc_listen)] {
+            if let Some(listen) = listen {
+                if listen.parse::<std::net::SocketAddr>().is_err() {
+                    bail!("❌ agent.{} must be an address like \"0.0.0.0:8700\" (got '{}').", key, listen);
+                }
+            }
+        }
+    }
+    if let Some(coordinator) = &config.application.coordinator {
+        validate_coordinator(coordinator)?;
+    }
+
+    if let Some(storage) = &config.application.storage {
+        validate_storage(storage)?;
+    }
+
     if config.cameras.is_empty() {
         bail!("❌ No cameras defined in the configuration. This might be intentional for some operations.");
     }
 
     let mut camera_names = HashSet::new();
+    let mut realsense_bandwidth = Vec::new();
+    let mut sync_masters = Vec::new();
     for (idx, camera) in config.cameras.iter().enumerate() {
         debug!("Validating camera #{}: {}", idx + 1, camera.get_name());
         if camera.get_name().is_empty() {
@@ -149,6 +1080,59 @@ fn validate_master_config(config: &MasterConfig) -> Result<()> {
                 if specifics.ip.parse::<IpAddr>().is_err() {
                     bail!("❌ Invalid IP address format '{}' for camera '{}'.", specifics.ip, name);
                 }
+                if let Some(scheme) = &specifics.http_scheme {
+                    if !matches!(scheme.to_lowercase().as_str(), "http" | "https") {
+                        bail!("❌ Invalid http_scheme '{}' for camera '{}' (expected \"http\" or \"https\").", scheme, name);
+                    }
+                }
+                if let Some(path) = &specifics.snapshot_path {
+                    if !path.starts_with('/') {
+                        bail!("❌ snapshot_path '{}' for camera '{}' must start with '/'.", path, name);
+                    }
+                }
+                if let Some(mac) = &specifics.mac_address {
+                    crate::camera::discovery::normalize_mac(mac)
+                        .with_context(|| format!("❌ Invalid mac_address for camera '{}'", name))?;
+                }
+                crate::camera::auth::AuthScheme::parse(specifics.auth.as_deref())
+                    .with_context(|| format!("❌ Invalid auth for camera '{}'", name))?;
+                crate::camera::rtsp_grab::RtspTransport::parse(specifics.rtsp_transport.as_deref())
+                    .with_context(|| format!("❌ Invalid rtsp_transport for camera '{}'", name))?;
+                if let Some(kbps) = specifics.min_stream_kbps {
+                    if !(kbps.is_finite() && kbps > 0.0) {
+                        bail!("❌ min_stream_kbps for camera '{}' must be positive (got {}).", name, kbps);
+                    }
+                }
+                if let Some(tls) = &specifics.tls {
+                    validate_tls(name, tls)?;
+                }
+                if let Some(imaging) = &specifics.imaging {
+                    crate::camera::imaging::ImagingRequest::parse(imaging)
+                        .with_context(|| format!("❌ Invalid imaging settings for camera '{}'", name))?;
+                }
+                if specifics.snapshot_channel == Some(0) {
+                    bail!("❌ snapshot_channel for camera '{}' must be 1 or greater.", name);
+                }
+                let driver = crate::camera::vendors::driver_for(specifics.vendor.as_deref())
+                    .with_context(|| format!("❌ Invalid vendor for camera '{}'", name))?;
+                if !driver.has_http_api() && specifics.rtsp_path.as_deref().is_none_or(str::is_empty) {
+                    bail!("❌ RTSP-only camera '{}' (vendor '{}') requires rtsp_path, as there is no HTTP API to discover the stream.", name, driver.name());
+                }
+                validate_timeouts(&format!("camera '{}'", name), specifics.connect_timeout_secs, specifics.read_timeout_secs, specifics.http_timeout_secs)?;
+                for (stream, source) in specifics.streams.iter().flatten() {
+                    if stream.trim().is_empty() || source.trim().is_empty() {
+                        bail!("❌ streams of camera '{}' must map non-empty names to non-empty sources.", name);
+                    }
+                }
+                validate_operation_streams(&format!("camera '{}'", name), specifics.operation_streams.as_ref())?;
+                for operation in STREAM_OPERATIONS {
+                    if let Some(stream) = specifics.stream_for(&config.application, operation) {
+                        if !specifics.has_stream(&stream) {
+                            bail!("❌ Camera '{}' has no stream named '{}' for {} (streams: main, sub{}).", name, stream, operation,
+                                specifics.streams.iter().flatten().map(|(stream, _)| format!(", {}", stream)).collect::<String>());
+                        }
+                    }
+                }
                 // Username is optional for IpCamera, but if it's None and a password env var exists,
                 // it might be an issue for some auth. The warning is in load_master_config.
                 // Here, we could choose to enforce it if desired, but current logic makes it optional.
@@ -156,13 +1140,229 @@ fn validate_master_config(config: &MasterConfig) -> Result<()> {
                 // The load_master_config already warns if username is None.
             }
             CaptureDeviceConfig::RealsenseCamera { name, specifics } => {
-                // Add any Realsense specific validations here if needed.
-                // For example, check if resolution/fps values are within supported ranges if known.
-                debug!("Realsense camera '{}' (Serial: {:?}) specific config validated (currently no specific checks).", name, specifics.serial_number);
+                if let Some(format) = &specifics.depth_format {
+                    if !crate::camera::frame_export::DEPTH_FORMATS.contains(&format.to_lowercase().as_str()) {
+                        bail!("❌ Invalid depth_format '{}' for camera '{}' (supported: {}).", format, name, crate::camera::frame_export::DEPTH_FORMATS.join(", "));
+                    }
+                }
+                if let Some(preview) = &specifics.depth_preview {
+                    if let Some(colormap) = &preview.colormap {
+                        if crate::camera::frame_export::colormap(colormap).is_none() {
+                            bail!("❌ Unknown depth_preview colormap '{}' for camera '{}' (supported: {}).", colormap, name,
+                                crate::camera::frame_export::COLORMAPS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", "));
+                        }
+                    }
+                    if [preview.min_m, preview.max_m].iter().flatten().any(|m| !m.is_finite() || *m < 0.0) {
+                        bail!("❌ depth_preview min_m and max_m for camera '{}' must be 0 m or more.", name);
+                    }
+                    if let (Some(min), Some(max)) = (preview.min_m, preview.max_m) {
+                        if min >= max {
+                            bail!("❌ depth_preview min_m ({}) must be smaller than max_m ({}) for camera '{}'.", min, max, name);
+                        }
+                    }
+                }
+                if let Some(bag) = &specifics.bag_file {
+                    if specifics.serial_number.is_some() || specifics.record_bag == Some(true) {
+                        bail!("❌ Realsense camera '{}' replays {}; it cannot also have serial_number or record_bag.", name, bag);
+                    }
+                    if !Path::new(bag).is_file() {
+                        bail!("❌ bag_file '{}' of camera '{}' does not exist.", bag, name);
+                    }
+                    // A playback needs no USB bandwidth and has no device options to set.
+                    debug!("Realsense camera '{}' replays {}.", name, bag);
+                } else {
+                    // Add any Realsense specific validations here if needed.
+                    // For example, check if resolution/fps values are within supported ranges if known.
+                    let mbps = specifics.estimated_bandwidth_mbps();
+                    if mbps > crate::camera::realsense_bandwidth::USB3_BUDGET_MBPS {
+                        bail!("❌ Realsense camera '{}' requests {:.0} Mbit/s, more than a USB 3 controller can carry. Lower resolution/fps.", name, mbps);
+                    }
+                    let options = crate::camera::realsense_options::RealsenseOptionsConfig::from_camera(specifics);
+                    let options = crate::camera::realsense_options::RealsenseOptions::parse(&options)
+                        .with_context(|| format!("❌ Invalid RealSense options for camera '{}'", name))?;
+                    if options.is_sync_master() {
+                        sync_masters.push(name.clone());
+                    }
+                    realsense_bandwidth.push((name.clone(), mbps));
+                    debug!("Realsense camera '{}' (Serial: {:?}) needs ~{:.0} Mbit/s of USB bandwidth.", name, specifics.serial_number, mbps);
+                }
+            }
+            CaptureDeviceConfig::ThermalCamera { name, specifics } => {
+                if specifics.ip.parse::<IpAddr>().is_err() {
+                    bail!("❌ Invalid IP address format '{}' for camera '{}'.", specifics.ip, name);
+                }
+                if let Some(scheme) = &specifics.http_scheme {
+                    if !matches!(scheme.to_lowercase().as_str(), "http" | "https") {
+                        bail!("❌ Invalid http_scheme '{}' for camera '{}' (expected \"http\" or \"https\").", scheme, name);
+                    }
+                }
+                if let Some(path) = &specifics.snapshot_path {
+                    if !path.starts_with('/') {
+                        bail!("❌ snapshot_path '{}' for camera '{}' must start with '/'.", path, name);
+                    }
+                }
+                if specifics.channel == Some(0) {
+                    bail!("❌ channel for camera '{}' must be 1 or greater.", name);
+                }
+                crate::camera::auth::AuthScheme::parse(specifics.auth.as_deref())
+                    .with_context(|| format!("❌ Invalid auth for camera '{}'", name))?;
+                if let Some(tls) = &specifics.tls {
+                    validate_tls(name, tls)?;
+                }
+                crate::camera::thermal_device::ThermalSettings::parse(specifics)
+                    .with_context(|| format!("❌ Invalid thermal camera settings for '{}'", name))?;
+                validate_timeouts(&format!("camera '{}'", name), specifics.connect_timeout_secs, None, specifics.http_timeout_secs)?;
+            }
+            CaptureDeviceConfig::VirtualCamera { name, specifics } => {
+                match specifics.source.as_deref() {
+                    None | Some(crate::camera::virtual_camera::TEST_PATTERN) => {
+                        if specifics.width == Some(0) || specifics.height == Some(0) {
+                            bail!("❌ width and height of virtual camera '{}' must be positive.", name);
+                        }
+                    }
+                    Some(file) if !Path::new(file).is_file() => {
+                        bail!("❌ source '{}' of virtual camera '{}' is not a file (or \"{}\").", file, name, crate::camera::virtual_camera::TEST_PATTERN);
+                    }
+                    Some(_) => {}
+                }
+            }
+            CaptureDeviceConfig::Custom { name, .. } => {
+                crate::core::source_registry::validate(camera)
+                    .with_context(|| format!("❌ Invalid custom device '{}'", name))?;
+            }
+        }
+        if let Some((extrinsics, field_of_view)) = camera.pose() {
+            crate::calibration::coverage::CameraFrustum::new(camera.get_name(), &extrinsics, &field_of_view)
+                .with_context(|| format!("❌ Invalid extrinsics/field_of_view for camera '{}'", camera.get_name()))?;
+        }
+        crate::camera::post_process::validate(camera.post_process())
+            .with_context(|| format!("❌ Invalid post_process for camera '{}'", camera.get_name()))?;
+        if let Some(cpus) = camera.cpu_affinity() {
+            if cpus.is_empty() || cpus.iter().any(|&cpu| cpu >= 1024) {
+                bail!("❌ cpu_affinity for camera '{}' must list CPU numbers between 0 and 1023 (got {:?}).", camera.get_name(), cpus);
+            }
+        }
+        if let Some(location) = camera.location() {
+            if !(-90.0..=90.0).contains(&location.latitude) || !(-180.0..=180.0).contains(&location.longitude) {
+                bail!("❌ location for camera '{}' must have latitude within ±90 and longitude within ±180 degrees (got {}, {}).",
+                    camera.get_name(), location.latitude, location.longitude);
+            }
+        }
+        if let Some(schedule) = camera.schedule() {
+            let schedule = crate::common::schedule::CameraSchedule::parse(schedule)
+                .with_context(|| format!("❌ Invalid schedule for camera '{}'", camera.get_name()))?;
+            if schedule.needs_location() && config.application.latitude.is_none() {
+                bail!("❌ Camera '{}' has a sunrise/sunset schedule; set application latitude and longitude.", camera.get_name());
             }
         }
         debug!("Camera '{}' validated successfully.", camera.get_name());
     }
+    if sync_masters.len() > 1 {
+        bail!("❌ RealSense cameras {} are all inter_cam_sync_mode \"master\"; one camera drives the sync cable.", sync_masters.join(", "));
+    }
+    crate::camera::realsense_bandwidth::warn_if_aggregate_exceeds_budget(&realsense_bandwidth);
     info!("👍 Master configuration validated successfully in {:?}.", validation_start_time.elapsed());
     Ok(())
-} 
\ No newline at end of file
+}
+
+fn validate_coverage_workspace(workspace: &CoverageWorkspace) -> Result<()> {
+    if !(workspace.voxel_size_m.is_finite() && workspace.voxel_size_m > 0.0) {
+        bail!("❌ coverage_workspace.voxel_size_m must be positive (got {}).", workspace.voxel_size_m);
+    }
+    for axis in 0..3 {
+        if !(workspace.min[axis] < workspace.max[axis]) {
+            bail!("❌ coverage_workspace.min must be smaller than max on every axis (got {:?} .. {:?}).", workspace.min, workspace.max);
+        }
+    }
+    if workspace.min_cameras == Some(0) {
+        bail!("❌ coverage_workspace.min_cameras must be 1 or greater.");
+    }
+    Ok(())
+}
+
+fn validate_coordinator(coordinator: &CoordinatorConfig) -> Result<()> {
+    let mut names = HashSet::new();
+    for agent in &coordinator.agents {
+        if !names.insert(agent.name.as_str()) {
+            bail!("❌ Duplicate coordinator agent name '{}'.", agent.name);
+        }
+        if !(agent.url.starts_with("http://") || agent.url.starts_with("https://")) {
+            bail!("❌ Coordinator agent '{}' url must start with http:// or https:// (got '{}').", agent.name, agent.url);
+        }
+    }
+    for (key, value) in [("start_lead_secs", coordinator.start_lead_secs), ("request_timeout_secs", coordinator.request_timeout_secs)] {
+        if value.is_some_and(|secs| !(secs.is_finite() && secs > 0.0)) {
+            bail!("❌ coordinator.{} must be positive.", key);
+        }
+    }
+    Ok(())
+}
+
+fn validate_storage(storage: &StorageConfig) -> Result<()> {
+    match storage.backend.to_lowercase().as_str() {
+        "local" | "nfs" => {
+            if storage.path.as_deref().is_none_or(str::is_empty) {
+                bail!("❌ storage.path is required for the '{}' storage backend.", storage.backend);
+            }
+        }
+        "s3" => match &storage.s3 {
+            Some(s3) if !s3.bucket.is_empty() => {}
+            _ => bail!("❌ storage.s3.bucket is required for the s3 storage backend."),
+        },
+        "sftp" => match &storage.sftp {
+            Some(sftp) if !sftp.host.is_empty() && !sftp.remote_dir.is_empty() => {
+                if let Some(identity) = &sftp.identity_file {
+                    if !Path::new(identity).is_file() {
+                        bail!("❌ storage.sftp.identity_file '{}' does not exist or is not a file.", identity);
+                    }
+                }
+            }
+            _ => bail!("❌ storage.sftp.host and storage.sftp.remote_dir are required for the sftp storage backend."),
+        },
+        other => bail!(
+            "❌ Unknown storage backend '{}' (supported: {}).",
+            other, crate::storage::SUPPORTED_BACKENDS.join(", ")
+        ),
+    }
+    if storage.delete_local == Some(true) {
+        info!("ℹ️ Local copies of captures will be deleted once uploaded to {} storage.", storage.backend);
+    }
+    Ok(())
+}
+
+fn validate_operation_streams(scope: &str, streams: Option<&BTreeMap<String, String>>) -> Result<()> {
+    for operation in streams.into_iter().flat_map(|streams| streams.keys()) {
+        if !STREAM_OPERATIONS.contains(&operation.as_str()) {
+            bail!("❌ Unknown operation '{}' in operation_streams of {} (expected one of: {}).", operation, scope, STREAM_OPERATIONS.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn validate_tls(camera_name: &str, tls: &TlsConfig) -> Result<()> {
+    for (key, path) in [("ca_cert", &tls.ca_cert), ("client_cert", &tls.client_cert), ("client_key", &tls.client_key)] {
+        if let Some(path) = path {
+            if !Path::new(path).is_file() {
+                bail!("❌ tls.{} '{}' for camera '{}' does not exist or is not a file.", key, path, camera_name);
+            }
+        }
+    }
+    if tls.client_cert.is_some() != tls.client_key.is_some() {
+        bail!("❌ tls.client_cert and tls.client_key for camera '{}' must be set together.", camera_name);
+    }
+    if tls.verify == Some(false) {
+        warn!("⚠️ TLS certificate verification is disabled for camera '{}'.", camera_name);
+    }
+    Ok(())
+}
+
+fn validate_timeouts(scope: &str, connect: Option<f32>, read: Option<f32>, http: Option<f32>) -> Result<()> {
+    for (key, value) in [("connect_timeout_secs", connect), ("read_timeout_secs", read), ("http_timeout_secs", http)] {
+        if let Some(secs) = value {
+            if !secs.is_finite() || secs <= 0.0 {
+                bail!("❌ {} for {} must be a positive number of seconds (got {}).", key, scope, secs);
+            }
+        }
+    }
+    Ok(())
+}
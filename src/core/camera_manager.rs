@@ -1,7 +1,7 @@
-use crate::config_loader::{MasterConfig, CaptureDeviceConfig};
+use crate::config_loader::MasterConfig;
 use crate::core::capture_source::CaptureSource;
-use crate::camera::ip_camera_device::IpCameraDevice;
-use crate::camera::realsense_device::RealsenseDevice;
+use crate::core::source_registry;
+use crate::core::supervisor::{RestartPolicy, Supervisor};
 use anyhow::{Result, bail};
 use log::{info, debug, warn};
 use std::collections::HashMap;
@@ -9,9 +9,13 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::time::Instant;
 
+/// Clones share the devices and the supervisor, e.g. for tasks that outlive a borrow of the manager.
+#[derive(Clone)]
 pub struct CameraManager {
     // Stores different types of camera devices that implement the CaptureSource trait
     cameras: HashMap<String, Arc<Mutex<dyn CaptureSource + Send>>>,
+    // Long-lived per-camera tasks and their restart state.
+    supervisor: Supervisor,
 }
 
 impl CameraManager {
@@ -20,6 +24,8 @@ impl CameraManager {
         let start_time = Instant::now();
         let mut cameras: HashMap<String, Arc<Mutex<dyn CaptureSource + Send>>> = HashMap::new();
 
+        crate::common::opencv_pool::configure(master_config);
+
         if master_config.cameras.is_empty() {
             warn!("CameraManager: No cameras defined in the configuration. Manager will be empty.");
         }
@@ -32,20 +38,9 @@ impl CameraManager {
                 bail!("❌ Duplicate camera/device name found in configuration: {}", device_name);
             }
 
-            let capture_source_device: Arc<Mutex<dyn CaptureSource + Send>> = match device_config {
-                CaptureDeviceConfig::IpCamera { name, specifics } => {
-                    info!("    Type: IP Camera. Creating IpCameraDevice for '{}' with IP {}", name, specifics.ip);
-                    let ip_cam_device = IpCameraDevice::new(name.clone(), specifics.clone());
-                    Arc::new(Mutex::new(ip_cam_device))
-                }
-                CaptureDeviceConfig::RealsenseCamera { name, specifics } => {
-                    info!("    Type: Realsense Camera. Creating RealsenseDevice for '{}'. Serial: {:?}", 
-                           name, specifics.serial_number.as_deref().unwrap_or("any"));
-                    let rs_device = RealsenseDevice::new(name.clone(), specifics.clone());
-                    Arc::new(Mutex::new(rs_device))
-                }
-            };
-            
+            debug!("    Type: '{}'", device_config.device_type());
+            let capture_source_device = source_registry::create(device_config, master_config)?;
+
             cameras.insert(device_name.clone(), capture_source_device);
             debug!("  Added device '{}' to manager.", device_name);
         }
@@ -56,7 +51,12 @@ impl CameraManager {
             cameras.keys().collect::<Vec<&String>>(), // Log names of initialized devices
             start_time.elapsed()
         );
-        Ok(CameraManager { cameras })
+        let supervisor = Supervisor::new(RestartPolicy::from_config(master_config.application.supervisor.as_ref())?);
+        Ok(CameraManager { cameras, supervisor })
+    }
+
+    pub fn supervisor(&self) -> &Supervisor {
+        &self.supervisor
     }
 
     pub async fn get_all_devices(&self) -> Vec<Arc<Mutex<dyn CaptureSource + Send>>> {
@@ -1,28 +1,66 @@
+use crate::config_loader::AppSettings;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
 // --- Data structures for frame information ---
 
+/// Immutable, reference-counted pixel buffer. Cloning only bumps a count, and Rerun logs it
+/// without copying, so one allocation per frame is shared by the capture worker, the file
+/// encoder and the Rerun logger.
+pub type FrameBuffer<T> = rerun::external::arrow::buffer::ScalarBuffer<T>;
+
+/// Pinhole intrinsics of a RealSense stream, in pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct RsIntrinsics {
+    pub fx: f32,
+    pub fy: f32,
+    pub ppx: f32,
+    pub ppy: f32,
+}
+
+/// Rigid transform between two RealSense streams (librealsense layout: column-major rotation, metres).
+#[derive(Debug, Clone, Copy)]
+pub struct RsExtrinsics {
+    pub rotation: [f32; 9],
+    pub translation: [f32; 3],
+}
+
 #[derive(Debug, Clone)]
 pub struct RsColorFrameData {
-    pub rgb_data: Vec<u8>,    // Raw RGB8 data
+    pub rgb_data: FrameBuffer<u8>, // Raw RGB8 data
     pub width: u32,
     pub height: u32,
+    pub intrinsics: Option<RsIntrinsics>, // None if unavailable or invalidated by post-processing
 }
 
 #[derive(Debug, Clone)]
 pub struct RsDepthFrameData {
-    pub depth_data: Vec<u16>, // Raw Z16 depth data
+    pub depth_data: FrameBuffer<u16>, // Raw Z16 depth data
     pub depth_units: f32,     // Depth units in meters per step
     pub width: u32,
     pub height: u32,
+    pub intrinsics: Option<RsIntrinsics>, // None if unavailable or invalidated by post-processing
+    pub depth_to_color: Option<RsExtrinsics>,
+}
+
+/// Per-pixel temperatures of a thermal camera.
+#[derive(Debug, Clone)]
+pub struct ThermalFrameData {
+    pub celsius: FrameBuffer<f32>, // row-major, NaN where the camera's calibration does not apply
+    pub width: u32,
+    pub height: u32,
 }
 
 // Enum to hold different types of image data results from a capture operation
 #[derive(Debug, Clone)]
 pub enum FrameData {
-    IpCameraImage {
+    IpCameraImage { // an encoded image file: IP camera snapshots and virtual camera stills
         name: String, // Name of the camera that produced this image
         path: PathBuf, // Path to the saved image file
         format: String, // Image format, e.g., "jpg", "png"
@@ -31,8 +69,38 @@ pub enum FrameData {
         name: String, // Name of the Realsense device
         color_frame: Option<RsColorFrameData>,
         depth_frame: Option<RsDepthFrameData>,
+        paths: Vec<PathBuf>, // Files written for this capture (color and/or depth)
     },
-    // Could add other types like Thermal, etc. in the future
+    ThermalImage {
+        name: String,
+        temperatures: Option<ThermalFrameData>, // None if an R-JPEG's radiometric data could not be read
+        paths: Vec<PathBuf>, // radiometric JPEG and/or temperature TIFF
+    },
+}
+
+/// Where the time of one capture went, in milliseconds. A phase a source does not have is None.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureTimings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<f64>, // HTTP request until the response headers / RTSP stream open / RealSense pipeline start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_frame_ms: Option<f64>, // from connected until the frame is on the host (body read, buffer flush, first frameset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_ms: Option<f64>, // decoding, post-processing and writing the file(s)
+}
+
+impl CaptureTimings {
+    pub fn ms(duration: Duration) -> Option<f64> {
+        Some(duration.as_secs_f64() * 1000.0)
+    }
+
+    pub fn total_ms(&self) -> f64 {
+        [self.connect_ms, self.first_frame_ms, self.write_ms].iter().flatten().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connect_ms.is_none() && self.first_frame_ms.is_none() && self.write_ms.is_none()
+    }
 }
 
 // A bundle that can contain multiple FrameData, e.g., color and depth from one Realsense
@@ -40,6 +108,64 @@ pub enum FrameData {
 pub struct FrameDataBundle {
     pub frames: Vec<FrameData>, // For a single Realsense, this might contain one RealsenseFrames variant
                                 // For an IP camera, it would contain one IpCameraImage variant
+    pub captured_at: DateTime<Utc>, // When the frame(s) reached the host
+    pub timings: CaptureTimings,
+}
+
+impl FrameDataBundle {
+    /// All files written for this bundle.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.frames.iter().flat_map(|frame| match frame {
+            FrameData::IpCameraImage { path, .. } => vec![path.clone()],
+            FrameData::RealsenseFrames { paths, .. } => paths.clone(),
+            FrameData::ThermalImage { paths, .. } => paths.clone(),
+        }).collect()
+    }
+}
+
+// --- Video recording ---
+
+/// How `capture-video` records a source.
+#[derive(Clone)]
+pub enum VideoSource {
+    /// A stream URL (RTSP, or anything FFmpeg opens), recorded through OpenCV with the other
+    /// streams: synchronized start, reconnects, segments, live preview.
+    Stream(String),
+    /// The source writes the recording itself (virtual cameras, RealSense .bag files). It runs
+    /// alongside the stream recordings of its wave.
+    Local(Arc<dyn VideoRecorder>),
+}
+
+impl std::fmt::Debug for VideoSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VideoSource::Stream(_) => f.write_str("Stream(..)"), // the URL may carry credentials
+            VideoSource::Local(recorder) => f.debug_tuple("Local").field(&recorder.describe()).finish(),
+        }
+    }
+}
+
+/// Everything a local recording gets from `capture-video` besides its output path.
+#[derive(Debug, Clone)]
+pub struct VideoRecordRequest {
+    pub app: AppSettings,
+    pub duration: Duration,
+    pub start_at: Option<DateTime<Utc>>,   // scheduled start (start_at); None = now
+    pub cancel: Option<CancellationToken>, // stops the recording early; the file is finalized
+}
+
+/// A recording a source runs itself, returned in `VideoSource::Local`.
+#[async_trait]
+pub trait VideoRecorder: Send + Sync {
+    /// What is recorded, for logs (e.g. "test pattern 1280x720").
+    fn describe(&self) -> String;
+
+    /// Extension of the file written, without the dot.
+    fn extension(&self, app: &AppSettings) -> String;
+
+    /// Records to `path` (its directory exists) until the duration is up or the request is
+    /// cancelled. Returns the file written and how long it covers.
+    async fn record(&self, path: &Path, request: &VideoRecordRequest) -> Result<(PathBuf, Duration)>;
 }
 
 // --- The CaptureSource Trait ---
@@ -47,7 +173,39 @@ pub struct FrameDataBundle {
 #[async_trait]
 pub trait CaptureSource {
     fn get_name(&self) -> String;
-    fn get_type(&self) -> String; // e.g., "ip-camera", "realsense-camera"
+    fn get_type(&self) -> String; // e.g., "ip-camera", "realsense-camera", "thermal-camera", "virtual-camera"
+
+    /// Picks the named stream later captures read from (None = the source's default). Sources
+    /// with a single stream ignore it.
+    fn select_stream(&mut self, _stream: Option<&str>) {}
+
+    /// Keeps the source ready between captures (e.g. a RealSense pipeline left streaming) until
+    /// `close`. Optional: `capture_image` works on a closed source by setting up and tearing
+    /// down around each frame. Sources with nothing to keep open ignore it.
+    async fn open(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Releases whatever `open` kept. Closing a closed source does nothing.
+    async fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        false
+    }
+
+    /// The stream `capture-video` records (e.g. the RTSP URL of the selected stream), for
+    /// sources that have one. None = no stream.
+    async fn stream_url(&mut self) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// How `capture-video` records the source; None leaves it out of recordings. The default
+    /// records `stream_url` through OpenCV, so a source with a stream only implements that.
+    async fn video_source(&mut self) -> Result<Option<VideoSource>> {
+        Ok(self.stream_url().await?.map(VideoSource::Stream))
+    }
 
     // Captures one or more images (e.g., color and depth for Realsense)
     // Saves them to the output_dir with filenames derived from timestamp_str
@@ -60,9 +218,4 @@ pub trait CaptureSource {
         jpeg_quality: Option<u8>,
         png_compression: Option<u32>,
     ) -> Result<FrameDataBundle>;
-
-    // Future methods for video might look like:
-    // async fn start_video_stream(&mut self, config: VideoStreamConfig) -> Result<()>;
-    // async fn stop_video_stream(&mut self) -> Result<PathBuf>; // Returns path to saved video
-    // fn get_stream_capabilities(&self) -> Vec<StreamProfile>;
 } 
\ No newline at end of file
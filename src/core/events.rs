@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Events buffered per subscriber; a subscriber that falls further behind skips the oldest.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Something that happened to a camera or capture. Published by the capture code as it
+/// happens, so integrations (Rerun, webhooks, MQTT, metrics, ...) subscribe here instead of
+/// being wired into every operation.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A camera stream was opened, or a RealSense device was plugged (back) in.
+    CameraConnected { camera: String, at: DateTime<Utc> },
+    /// A RealSense device was unplugged.
+    CameraDisconnected { camera: String, at: DateTime<Utc> },
+    /// A still frame was captured (and saved to `path`, if it was kept).
+    FrameCaptured { camera: String, at: DateTime<Utc>, path: Option<PathBuf> },
+    /// A motion detector saw movement; `score` is detector specific.
+    MotionDetected { camera: String, at: DateTime<Utc>, score: f32 },
+    /// A camera started writing frames to `path`.
+    RecordingStarted { camera: String, at: DateTime<Utc>, path: PathBuf },
+    /// A recording's progress, about once a second: frames in the video so far, of `total_frames`,
+    /// bytes written across its segments, and the stream's rates over the last window (once one passed).
+    RecordingProgress { camera: String, at: DateTime<Utc>, frames: u64, total_frames: u64, bytes: u64, fps: Option<f64>, kbps: Option<f64> },
+    /// A recording's bitrate fell below `min_stream_kbps`, usually a degraded link.
+    StreamDegraded { camera: String, at: DateTime<Utc>, kbps: f64, fps: f64, min_kbps: f64 },
+    /// A stalled recording reopened its stream (attempt `attempt`); `error` if reopening failed.
+    StreamReconnected { camera: String, at: DateTime<Utc>, attempt: u32, error: Option<String> },
+    /// A recording was paused; its current file is finalized.
+    RecordingPaused { camera: String, at: DateTime<Utc> },
+    /// A paused recording continued, writing to `path`.
+    RecordingResumed { camera: String, at: DateTime<Utc>, path: PathBuf },
+    /// A camera's recording ended, with the files it wrote or the error that ended it.
+    RecordingStopped { camera: String, at: DateTime<Utc>, files: Vec<PathBuf>, error: Option<String> },
+    /// Free space under `path` fell below `min_free_disk_mb`.
+    DiskLow { at: DateTime<Utc>, path: PathBuf, available_mb: u64, min_free_mb: u64 },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::CameraConnected { .. } => "camera_connected",
+            Event::CameraDisconnected { .. } => "camera_disconnected",
+            Event::FrameCaptured { .. } => "frame_captured",
+            Event::MotionDetected { .. } => "motion_detected",
+            Event::RecordingStarted { .. } => "recording_started",
+            Event::RecordingProgress { .. } => "recording_progress",
+            Event::StreamDegraded { .. } => "stream_degraded",
+            Event::StreamReconnected { .. } => "stream_reconnected",
+            Event::RecordingPaused { .. } => "recording_paused",
+            Event::RecordingResumed { .. } => "recording_resumed",
+            Event::RecordingStopped { .. } => "recording_stopped",
+            Event::DiskLow { .. } => "disk_low",
+        }
+    }
+
+    /// Camera the event is about; None for rig-wide events.
+    pub fn camera(&self) -> Option<&str> {
+        match self {
+            Event::CameraConnected { camera, .. }
+            | Event::CameraDisconnected { camera, .. }
+            | Event::FrameCaptured { camera, .. }
+            | Event::MotionDetected { camera, .. }
+            | Event::RecordingStarted { camera, .. }
+            | Event::RecordingProgress { camera, .. }
+            | Event::StreamDegraded { camera, .. }
+            | Event::StreamReconnected { camera, .. }
+            | Event::RecordingPaused { camera, .. }
+            | Event::RecordingResumed { camera, .. }
+            | Event::RecordingStopped { camera, .. } => Some(camera),
+            Event::DiskLow { .. } => None,
+        }
+    }
+
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            Event::CameraConnected { at, .. }
+            | Event::CameraDisconnected { at, .. }
+            | Event::FrameCaptured { at, .. }
+            | Event::MotionDetected { at, .. }
+            | Event::RecordingStarted { at, .. }
+            | Event::RecordingProgress { at, .. }
+            | Event::StreamDegraded { at, .. }
+            | Event::StreamReconnected { at, .. }
+            | Event::RecordingPaused { at, .. }
+            | Event::RecordingResumed { at, .. }
+            | Event::RecordingStopped { at, .. }
+            | Event::DiskLow { at, .. } => *at,
+        }
+    }
+
+    /// True for events that report a problem.
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Event::CameraDisconnected { .. } | Event::DiskLow { .. } | Event::StreamDegraded { .. } | Event::StreamReconnected { .. } | Event::RecordingStopped { error: Some(_), .. })
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::CameraConnected { camera, .. } => write!(f, "'{}' connected", camera),
+            Event::CameraDisconnected { camera, .. } => write!(f, "'{}' disconnected", camera),
+            Event::FrameCaptured { camera, path: Some(path), .. } => write!(f, "'{}' captured {}", camera, path.display()),
+            Event::FrameCaptured { camera, path: None, .. } => write!(f, "'{}' captured a frame", camera),
+            Event::MotionDetected { camera, score, .. } => write!(f, "'{}' detected motion (score {:.2})", camera, score),
+            Event::RecordingStarted { camera, path, .. } => write!(f, "'{}' started recording to {}", camera, path.display()),
+            Event::RecordingProgress { camera, frames, total_frames, bytes, fps, kbps, .. } => {
+                write!(f, "'{}' recorded {} / {} frames ({:.1} MB)", camera, frames, total_frames, *bytes as f64 / (1024.0 * 1024.0))?;
+                match (fps, kbps) {
+                    (Some(fps), Some(kbps)) => write!(f, " at {:.1} fps, {:.0} kbps", fps, kbps),
+                    _ => Ok(()),
+                }
+            }
+            Event::StreamDegraded { camera, kbps, fps, min_kbps, .. } => {
+                write!(f, "'{}' stream dropped to {:.0} kbps at {:.1} fps (minimum {:.0} kbps)", camera, kbps, fps, min_kbps)
+            }
+            Event::StreamReconnected { camera, attempt, error: None, .. } => write!(f, "'{}' reconnected its stream (attempt {})", camera, attempt),
+            Event::StreamReconnected { camera, attempt, error: Some(error), .. } => write!(f, "'{}' failed to reconnect its stream (attempt {}): {}", camera, attempt, error),
+            Event::RecordingPaused { camera, .. } => write!(f, "'{}' paused recording", camera),
+            Event::RecordingResumed { camera, path, .. } => write!(f, "'{}' resumed recording to {}", camera, path.display()),
+            Event::RecordingStopped { camera, error: Some(error), .. } => write!(f, "'{}' recording failed: {}", camera, error),
+            Event::RecordingStopped { camera, files, .. } => write!(f, "'{}' stopped recording ({} file(s))", camera, files.len()),
+            Event::DiskLow { path, available_mb, min_free_mb, .. } => {
+                write!(f, "only {} MB free on the disk holding {} (minimum {} MB)", available_mb, path.display(), min_free_mb)
+            }
+        }
+    }
+}
+
+fn sender() -> &'static broadcast::Sender<Event> {
+    static BUS: OnceLock<broadcast::Sender<Event>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Publishes an event to every current subscriber. Never blocks, so it is safe to call from
+/// the blocking capture threads; without subscribers the event is dropped.
+pub fn publish(event: Event) {
+    debug!("📣 Event: {}", event);
+    let _ = sender().send(event);
+}
+
+/// Receives every event published from now on.
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    sender().subscribe()
+}
+
+/// A background task feeding events to a handler; see `listen`.
+pub struct Listener {
+    name: String,
+    stop: CancellationToken,
+    handle: JoinHandle<()>,
+}
+
+impl Listener {
+    /// Handles the events already published, then stops the listener.
+    pub async fn finish(self) {
+        self.stop.cancel();
+        if let Err(e) = self.handle.await {
+            warn!("📣 Event listener '{}' ended abnormally: {}", self.name, e);
+        }
+    }
+}
+
+/// Calls `handler` for each event published until the returned listener is finished.
+/// This is the hook for integrations: subscribe once instead of being called from every operation.
+pub fn listen<F>(name: &str, mut handler: F) -> Listener
+where
+    F: FnMut(&Event) + Send + 'static,
+{
+    let mut receiver = subscribe();
+    let stop = CancellationToken::new();
+    let stop_clone = stop.clone();
+    let listener_name = name.to_string();
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                received = receiver.recv() => match received {
+                    Ok(event) => handler(&event),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("📣 Event listener '{}' fell behind and skipped {} event(s).", listener_name, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = stop_clone.cancelled() => {
+                    while let Ok(event) = receiver.try_recv() {
+                        handler(&event);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+    Listener { name: name.to_string(), stop, handle }
+}
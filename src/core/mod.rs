@@ -1,2 +1,5 @@
 pub mod camera_manager;
-pub mod capture_source;
\ No newline at end of file
+pub mod capture_source;
+pub mod events;
+pub mod source_registry;
+pub mod supervisor;
@@ -0,0 +1,173 @@
+//! Builds capture sources by device type. The built-in types are registered up front; a program
+//! embedding rcam can add its own (e.g. GigE Vision) with `register` before creating the
+//! `CameraManager`, and configure them as `!Custom` entries:
+//!
+//! ```yaml
+//! - !Custom
+//!   name: line-scan
+//!   type: gige
+//!   serial: "21345678" # anything else is the type's own settings
+//! ```
+
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::realsense_device::RealsenseDevice;
+use crate::camera::rtsp_grab::{RtspGrab, RtspTransport};
+use crate::camera::thermal_device::ThermalDevice;
+use crate::camera::virtual_camera::{self, VirtualCameraDevice};
+use crate::config_loader::{CameraTimeouts, CaptureDeviceConfig, MasterConfig};
+use crate::core::capture_source::CaptureSource;
+use anyhow::{anyhow, bail, Result};
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use tokio::sync::Mutex;
+
+pub type SharedCaptureSource = Arc<Mutex<dyn CaptureSource + Send>>;
+
+/// Creates the capture source for one configured device of the type it is registered under.
+/// Any `Fn(&CaptureDeviceConfig, &MasterConfig) -> Result<SharedCaptureSource>` is a factory.
+pub trait CaptureSourceFactory: Send + Sync {
+    fn create(&self, device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource>;
+
+    /// Checks the device's settings when the config is loaded, before anything is created.
+    fn validate(&self, _device: &CaptureDeviceConfig) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<F> CaptureSourceFactory for F
+where
+    F: Fn(&CaptureDeviceConfig, &MasterConfig) -> Result<SharedCaptureSource> + Send + Sync,
+{
+    fn create(&self, device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+        self(device, master_config)
+    }
+}
+
+/// Factories by device type (`CaptureDeviceConfig::device_type`).
+pub struct SourceRegistry {
+    factories: BTreeMap<String, Arc<dyn CaptureSourceFactory>>,
+}
+
+impl SourceRegistry {
+    /// A registry holding only the built-in types.
+    pub fn with_builtins() -> Self {
+        let mut factories: BTreeMap<String, Arc<dyn CaptureSourceFactory>> = BTreeMap::new();
+        factories.insert(IP_CAMERA.to_string(), Arc::new(create_ip_camera));
+        factories.insert(REALSENSE_CAMERA.to_string(), Arc::new(create_realsense));
+        factories.insert(THERMAL_CAMERA.to_string(), Arc::new(create_thermal));
+        factories.insert(VIRTUAL_CAMERA.to_string(), Arc::new(create_virtual));
+        SourceRegistry { factories }
+    }
+
+    /// Adds a device type. Types are unique; a built-in cannot be replaced.
+    pub fn register(&mut self, device_type: &str, factory: Arc<dyn CaptureSourceFactory>) -> Result<()> {
+        if device_type.trim().is_empty() {
+            bail!("A capture source type needs a name");
+        }
+        if self.factories.contains_key(device_type) {
+            bail!("Capture source type '{}' is already registered", device_type);
+        }
+        self.factories.insert(device_type.to_string(), factory);
+        debug!("Registered capture source type '{}'.", device_type);
+        Ok(())
+    }
+
+    pub fn types(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+
+    fn factory(&self, device: &CaptureDeviceConfig) -> Result<Arc<dyn CaptureSourceFactory>> {
+        self.factories.get(device.device_type()).cloned().ok_or_else(|| anyhow!(
+            "Device '{}' has type '{}', which is not registered (known types: {})",
+            device.get_name(), device.device_type(), self.types().join(", ")))
+    }
+
+    pub fn create(&self, device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+        self.factory(device)?.create(device, master_config)
+    }
+
+    pub fn validate(&self, device: &CaptureDeviceConfig) -> Result<()> {
+        self.factory(device)?.validate(device)
+    }
+}
+
+pub const IP_CAMERA: &str = "ip-camera";
+pub const REALSENSE_CAMERA: &str = "realsense-camera";
+pub const THERMAL_CAMERA: &str = "thermal-camera";
+pub const VIRTUAL_CAMERA: &str = "virtual-camera";
+
+static REGISTRY: OnceLock<RwLock<SourceRegistry>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<SourceRegistry> {
+    REGISTRY.get_or_init(|| RwLock::new(SourceRegistry::with_builtins()))
+}
+
+/// Registers a device type process-wide, for every `CameraManager` created afterwards and for
+/// config validation. Call it before loading a config that uses the type.
+pub fn register(device_type: &str, factory: impl CaptureSourceFactory + 'static) -> Result<()> {
+    registry().write().unwrap_or_else(|p| p.into_inner()).register(device_type, Arc::new(factory))
+}
+
+/// Every registered device type, built-ins included.
+pub fn registered_types() -> Vec<String> {
+    registry().read().unwrap_or_else(|p| p.into_inner()).types()
+}
+
+/// Creates a device's capture source from the process-wide registry.
+pub fn create(device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+    let factory = registry().read().unwrap_or_else(|p| p.into_inner()).factory(device)?;
+    factory.create(device, master_config)
+}
+
+/// Validates a device against the process-wide registry.
+pub fn validate(device: &CaptureDeviceConfig) -> Result<()> {
+    let factory = registry().read().unwrap_or_else(|p| p.into_inner()).factory(device)?;
+    factory.validate(device)
+}
+
+fn wrong_config(device: &CaptureDeviceConfig, expected: &str) -> anyhow::Error {
+    anyhow!("Device '{}' was routed to the {} factory but is configured as '{}'", device.get_name(), expected, device.device_type())
+}
+
+fn create_ip_camera(device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+    let CaptureDeviceConfig::IpCamera { name, specifics } = device else {
+        return Err(wrong_config(device, IP_CAMERA));
+    };
+    info!("    Type: IP Camera. Creating IpCameraDevice for '{}' with IP {}", name, specifics.ip);
+    let timeouts = CameraTimeouts::resolve(&master_config.application, Some(specifics));
+    debug!("    Timeouts for '{}': {:?}", name, timeouts);
+    let mut ip_cam_device = IpCameraDevice::new(name.clone(), specifics.clone(), timeouts)?;
+    ip_cam_device.rtsp_grab = RtspGrab::resolve(&master_config.application, Some(specifics));
+    ip_cam_device.rtsp_transport = RtspTransport::resolve(&master_config.application, Some(specifics));
+    Ok(Arc::new(Mutex::new(ip_cam_device)))
+}
+
+fn create_realsense(device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+    let CaptureDeviceConfig::RealsenseCamera { name, specifics } = device else {
+        return Err(wrong_config(device, REALSENSE_CAMERA));
+    };
+    match &specifics.bag_file {
+        Some(bag) => info!("    Type: Realsense Camera. Creating RealsenseDevice for '{}'. Playback of {}", name, bag),
+        None => info!("    Type: Realsense Camera. Creating RealsenseDevice for '{}'. Serial: {:?}",
+                      name, specifics.serial_number.as_deref().unwrap_or("any")),
+    }
+    Ok(Arc::new(Mutex::new(RealsenseDevice::from_app(name.clone(), specifics.clone(), &master_config.application))))
+}
+
+fn create_thermal(device: &CaptureDeviceConfig, master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+    let CaptureDeviceConfig::ThermalCamera { name, specifics } = device else {
+        return Err(wrong_config(device, THERMAL_CAMERA));
+    };
+    info!("    Type: Thermal Camera. Creating ThermalDevice for '{}' with IP {}", name, specifics.ip);
+    let timeouts = CameraTimeouts::resolve_thermal(&master_config.application, specifics);
+    Ok(Arc::new(Mutex::new(ThermalDevice::new(name.clone(), specifics.clone(), timeouts)?)))
+}
+
+fn create_virtual(device: &CaptureDeviceConfig, _master_config: &MasterConfig) -> Result<SharedCaptureSource> {
+    let CaptureDeviceConfig::VirtualCamera { name, specifics } = device else {
+        return Err(wrong_config(device, VIRTUAL_CAMERA));
+    };
+    info!("    Type: Virtual Camera. Creating VirtualCameraDevice for '{}' ({})", name, virtual_camera::describe(specifics));
+    Ok(Arc::new(Mutex::new(VirtualCameraDevice::new(name.clone(), specifics.clone()))))
+}
@@ -0,0 +1,334 @@
+use crate::config_loader::SupervisorConfig;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub const RESTART_POLICIES: &[&str] = &["never", "always", "on-failure", "backoff"];
+pub const DEFAULT_RESTART_DELAY_SECS: f32 = 1.0;
+pub const DEFAULT_MAX_BACKOFF_SECS: f32 = 60.0;
+/// Camera name of supervised tasks that serve the whole rig rather than one camera (the
+/// agent's servers, a soak test).
+pub const RIG: &str = "rig";
+
+/// What happens when a supervised task ends. A panic counts as a failure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    Never,
+    /// Restart after `delay` however the task ended.
+    Always { delay: Duration },
+    /// Restart after `delay` if the task failed or panicked; a clean exit is final.
+    OnFailure { delay: Duration },
+    /// Like `OnFailure`, but the delay doubles after every consecutive failure up to `max`.
+    Backoff { initial: Duration, max: Duration },
+}
+
+impl RestartPolicy {
+    /// Policy from the `supervisor` config section; `on-failure` without it.
+    pub fn from_config(config: Option<&SupervisorConfig>) -> Result<Self> {
+        let delay = Duration::from_secs_f32(config.and_then(|c| c.restart_delay_secs).unwrap_or(DEFAULT_RESTART_DELAY_SECS));
+        let policy = config.and_then(|c| c.restart.as_deref()).unwrap_or("on-failure");
+        Ok(match policy.to_lowercase().as_str() {
+            "never" => RestartPolicy::Never,
+            "always" => RestartPolicy::Always { delay },
+            "on-failure" | "on_failure" => RestartPolicy::OnFailure { delay },
+            "backoff" => RestartPolicy::Backoff {
+                initial: delay,
+                max: Duration::from_secs_f32(config.and_then(|c| c.max_backoff_secs).unwrap_or(DEFAULT_MAX_BACKOFF_SECS)),
+            },
+            other => bail!("Unknown restart policy '{}' (supported: {})", other, RESTART_POLICIES.join(", ")),
+        })
+    }
+
+    /// This policy for a task that ends cleanly once its work is done (a soak test): `always`
+    /// becomes `on-failure`, so a finished task is not started over.
+    pub fn on_failure_only(self) -> Self {
+        match self {
+            RestartPolicy::Always { delay } => RestartPolicy::OnFailure { delay },
+            policy => policy,
+        }
+    }
+
+    /// Delay before the next run, or None to stop. `consecutive_failures` includes this run.
+    fn next_delay(&self, failed: bool, consecutive_failures: u32) -> Option<Duration> {
+        match *self {
+            RestartPolicy::Never => None,
+            RestartPolicy::Always { delay } => Some(delay),
+            RestartPolicy::OnFailure { delay } => failed.then_some(delay),
+            RestartPolicy::Backoff { initial, max } => failed.then(|| {
+                let factor = 2u32.saturating_pow(consecutive_failures.saturating_sub(1));
+                initial.saturating_mul(factor).min(max)
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum TaskState {
+    Running,
+    /// Waiting to be restarted after the last run ended.
+    Restarting { delay_secs: f32 },
+    /// Ended cleanly and is not restarted.
+    Finished,
+    /// Failed and its policy gave up on it.
+    Failed,
+    /// Stopped by `Supervisor::shutdown`.
+    Stopped,
+}
+
+impl fmt::Display for TaskState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskState::Running => f.write_str("running"),
+            TaskState::Restarting { delay_secs } => write!(f, "restarting in {:.1}s", delay_secs),
+            TaskState::Finished => f.write_str("finished"),
+            TaskState::Failed => f.write_str("failed"),
+            TaskState::Stopped => f.write_str("stopped"),
+        }
+    }
+}
+
+/// Snapshot of one supervised task, for diagnostics and API callers.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub camera: String,
+    pub task: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub started_at: DateTime<Utc>, // start of the current (or last) run
+}
+
+impl TaskStatus {
+    /// False once a task is down for good or keeps failing.
+    pub fn is_healthy(&self) -> bool {
+        match self.state {
+            TaskState::Running | TaskState::Finished | TaskState::Stopped => true,
+            TaskState::Restarting { .. } | TaskState::Failed => false,
+        }
+    }
+}
+
+type StatusMap = Arc<Mutex<BTreeMap<(String, String), TaskStatus>>>;
+
+/// Owns long-lived per-camera tasks (schedule loops, recordings, servers, health checks, ...) and
+/// restarts them by their `RestartPolicy`, so a task that fails or panics is brought back or
+/// at least shows up as failed instead of the camera silently going quiet.
+#[derive(Clone)]
+pub struct Supervisor {
+    statuses: StatusMap,
+    handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    cancel: CancellationToken,
+    default_policy: RestartPolicy,
+}
+
+impl Supervisor {
+    pub fn new(default_policy: RestartPolicy) -> Self {
+        Supervisor {
+            statuses: Arc::new(Mutex::new(BTreeMap::new())),
+            handles: Arc::new(Mutex::new(Vec::new())),
+            cancel: CancellationToken::new(),
+            default_policy,
+        }
+    }
+
+    pub fn default_policy(&self) -> RestartPolicy {
+        self.default_policy
+    }
+
+    /// A supervisor for tasks that belong to one run of an operation (a daemon, an agent, a
+    /// soak). Its tasks show up in this supervisor's `status()`, and shutting this supervisor
+    /// down stops them too, but its own `shutdown` and `wait` only concern the tasks spawned
+    /// through it.
+    pub fn scoped(&self) -> Supervisor {
+        Supervisor {
+            statuses: self.statuses.clone(),
+            handles: Arc::new(Mutex::new(Vec::new())),
+            cancel: self.cancel.child_token(),
+            default_policy: self.default_policy,
+        }
+    }
+
+    /// Runs `task` under the supervisor's default policy. See `spawn_with_policy`.
+    pub fn spawn<F, Fut>(&self, camera: &str, task_name: &str, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.spawn_with_policy(camera, task_name, self.default_policy, task);
+    }
+
+    /// Runs `task` for `camera`, calling it again for every restart. The token it is given is
+    /// cancelled on shutdown; tasks should return promptly once it is. A task registered again
+    /// under the same camera and name replaces the old status entry.
+    pub fn spawn_with_policy<F, Fut>(&self, camera: &str, task_name: &str, policy: RestartPolicy, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let key = (camera.to_string(), task_name.to_string());
+        let statuses = self.statuses.clone();
+        let cancel = self.cancel.child_token();
+        set_status(&statuses, &key, |status| *status = Some(TaskStatus {
+            camera: key.0.clone(),
+            task: key.1.clone(),
+            state: TaskState::Running,
+            restarts: 0,
+            last_error: None,
+            started_at: Utc::now(),
+        }));
+        info!("🛡️ Supervising '{}' task for camera '{}' ({:?}).", key.1, key.0, policy);
+
+        let handle = tokio::spawn(async move {
+            let mut consecutive_failures = 0u32;
+            loop {
+                // Each run is its own tokio task, so a panic ends the run instead of the supervisor.
+                let run = tokio::spawn(task(cancel.clone()));
+                let outcome = match run.await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(format!("{:#}", e)),
+                    Err(join_error) if join_error.is_panic() => Some(format!("panicked: {}", join_error)),
+                    Err(join_error) => Some(format!("aborted: {}", join_error)),
+                };
+                if cancel.is_cancelled() {
+                    set_status(&statuses, &key, |status| if let Some(s) = status { s.state = TaskState::Stopped });
+                    debug!("Supervised task '{}' for '{}' stopped.", key.1, key.0);
+                    return;
+                }
+
+                let failed = outcome.is_some();
+                consecutive_failures = if failed { consecutive_failures + 1 } else { 0 };
+                match &outcome {
+                    Some(error) => error!("💥 Camera '{}': task '{}' failed: {}", key.0, key.1, error),
+                    None => debug!("Camera '{}': task '{}' exited cleanly.", key.0, key.1),
+                }
+                let Some(delay) = policy.next_delay(failed, consecutive_failures) else {
+                    if failed {
+                        error!("🛑 Camera '{}': task '{}' is not restarted ({:?}); the camera is degraded.", key.0, key.1, policy);
+                    }
+                    set_status(&statuses, &key, |status| if let Some(s) = status {
+                        s.state = if failed { TaskState::Failed } else { TaskState::Finished };
+                        if outcome.is_some() {
+                            s.last_error = outcome.clone();
+                        }
+                    });
+                    return;
+                };
+
+                warn!("🔁 Camera '{}': restarting task '{}' in {:?}.", key.0, key.1, delay);
+                set_status(&statuses, &key, |status| if let Some(s) = status {
+                    s.state = TaskState::Restarting { delay_secs: delay.as_secs_f32() };
+                    if outcome.is_some() {
+                        s.last_error = outcome.clone();
+                    }
+                });
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        set_status(&statuses, &key, |status| if let Some(s) = status { s.state = TaskState::Stopped });
+                        return;
+                    }
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                set_status(&statuses, &key, |status| if let Some(s) = status {
+                    s.state = TaskState::Running;
+                    s.restarts += 1;
+                    s.started_at = Utc::now();
+                });
+            }
+        });
+        self.handles.lock().unwrap_or_else(|p| p.into_inner()).push(handle);
+    }
+
+    /// Runs `task` once, for work that produces a result (a recording). It shows up in `status()`
+    /// like any supervised task but is never restarted, since starting it over would not give
+    /// its caller the result it waits for. A panic resolves the returned task to an error.
+    pub fn spawn_once<T, Fut>(&self, camera: &str, task_name: &str, task: Fut) -> OnceTask<T>
+    where
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (result_tx, result_rx) = oneshot::channel();
+        let pending = Mutex::new(Some((task, result_tx)));
+        self.spawn_with_policy(camera, task_name, RestartPolicy::Never, move |_cancel| {
+            let pending = pending.lock().unwrap_or_else(|p| p.into_inner()).take();
+            async move {
+                let Some((task, result_tx)) = pending else {
+                    return Ok(());
+                };
+                let result = task.await;
+                let outcome = result.as_ref().map(|_| ()).map_err(|e| anyhow!("{:#}", e));
+                let _ = result_tx.send(result); // the caller may have stopped waiting
+                outcome
+            }
+        });
+        OnceTask(result_rx)
+    }
+
+    /// Current state of every supervised task, ordered by camera and task name.
+    pub fn status(&self) -> Vec<TaskStatus> {
+        self.statuses.lock().unwrap_or_else(|p| p.into_inner()).values().cloned().collect()
+    }
+
+    pub fn camera_status(&self, camera: &str) -> Vec<TaskStatus> {
+        self.status().into_iter().filter(|s| s.camera == camera).collect()
+    }
+
+    /// Waits until every task has ended by itself (finished, or failed for good), or until
+    /// `stop` is cancelled, in which case the tasks are cancelled and waited for.
+    pub async fn wait(&self, stop: &CancellationToken) {
+        let handles: Vec<JoinHandle<()>> = std::mem::take(&mut *self.handles.lock().unwrap_or_else(|p| p.into_inner()));
+        let all = futures::future::join_all(handles);
+        tokio::pin!(all);
+        tokio::select! {
+            _ = &mut all => return,
+            _ = stop.cancelled() => {}
+        }
+        self.cancel.cancel();
+        all.await;
+    }
+
+    /// Cancels every task and waits for them to return.
+    pub async fn shutdown(&self) {
+        self.cancel.cancel();
+        let handles: Vec<JoinHandle<()>> = std::mem::take(&mut *self.handles.lock().unwrap_or_else(|p| p.into_inner()));
+        if !handles.is_empty() {
+            info!("🛡️ Stopping {} supervised task(s)...", handles.len());
+        }
+        for handle in handles {
+            let _ = handle.await;
+        }
+    }
+}
+
+/// Result of a task started with `Supervisor::spawn_once`.
+pub struct OnceTask<T>(oneshot::Receiver<Result<T>>);
+
+impl<T> Future for OnceTask<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx).map(|received| {
+            received.unwrap_or_else(|_| Err(anyhow!("task panicked or was stopped before it finished")))
+        })
+    }
+}
+
+fn set_status(statuses: &StatusMap, key: &(String, String), update: impl FnOnce(&mut Option<TaskStatus>)) {
+    let mut map = statuses.lock().unwrap_or_else(|p| p.into_inner());
+    let mut entry = map.remove(key);
+    update(&mut entry);
+    if let Some(status) = entry {
+        map.insert(key.clone(), status);
+    }
+}
@@ -0,0 +1,137 @@
+//! Error kinds for API and JSON consumers.
+//!
+//! rcam passes errors around as `anyhow::Error`. Where the kind of a failure is known (a bad
+//! config, a camera refusing credentials, a stream that cannot be decoded), the error carries an
+//! `RcamError` in its context chain, and `classify` recovers the kind from any error so callers
+//! can branch on it instead of parsing messages.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What went wrong, independent of the operation that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Config,  // invalid or missing configuration, unknown camera names
+    Network, // host unreachable, connection refused or timed out
+    Auth,    // missing credentials, or the device rejected them
+    Media,   // stream could not be opened or decoded, no frames, encoder failures
+    Storage, // writing, reading or uploading files
+    Other,
+}
+
+impl ErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Config => "config",
+            ErrorKind::Network => "network",
+            ErrorKind::Auth => "auth",
+            ErrorKind::Media => "media",
+            ErrorKind::Storage => "storage",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A failure of a known kind, optionally tied to one camera. Its `Display` is just the message,
+/// so tagging an error does not change how it is logged.
+#[derive(Debug, Clone)]
+pub struct RcamError {
+    pub kind: ErrorKind,
+    pub camera: Option<String>,
+    pub message: String,
+}
+
+impl RcamError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        RcamError { kind, camera: None, message: message.into() }
+    }
+
+    pub fn for_camera(kind: ErrorKind, camera: impl Into<String>, message: impl Into<String>) -> Self {
+        RcamError { kind, camera: Some(camera.into()), message: message.into() }
+    }
+}
+
+impl fmt::Display for RcamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RcamError {}
+
+/// `anyhow::Context` with a kind: the message becomes an `RcamError` in the error's chain.
+pub trait ErrorKindExt<T> {
+    fn kind_context(self, kind: ErrorKind, message: impl Into<String>) -> anyhow::Result<T>;
+
+    fn with_kind<F, M>(self, kind: ErrorKind, message: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> M,
+        M: Into<String>;
+}
+
+impl<T, E> ErrorKindExt<T> for Result<T, E>
+where
+    E: Into<anyhow::Error>,
+{
+    fn kind_context(self, kind: ErrorKind, message: impl Into<String>) -> anyhow::Result<T> {
+        self.map_err(|e| e.into().context(RcamError::new(kind, message)))
+    }
+
+    fn with_kind<F, M>(self, kind: ErrorKind, message: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> M,
+        M: Into<String>,
+    {
+        self.map_err(|e| e.into().context(RcamError::new(kind, message())))
+    }
+}
+
+/// The kind of `error`: the outermost `RcamError` in its chain, otherwise guessed from the
+/// library error at its root (reqwest, I/O, OpenCV, YAML).
+pub fn classify(error: &anyhow::Error) -> ErrorKind {
+    if let Some(tagged) = error.downcast_ref::<RcamError>() {
+        return tagged.kind;
+    }
+    for cause in error.chain() {
+        if let Some(tagged) = cause.downcast_ref::<RcamError>() {
+            return tagged.kind;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return match e.status().map(|s| s.as_u16()) {
+                Some(401) | Some(403) => ErrorKind::Auth,
+                _ => ErrorKind::Network,
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind as Io;
+            return match e.kind() {
+                Io::ConnectionRefused | Io::ConnectionReset | Io::ConnectionAborted | Io::NotConnected
+                | Io::AddrNotAvailable | Io::TimedOut => ErrorKind::Network,
+                Io::PermissionDenied | Io::NotFound | Io::AlreadyExists | Io::WriteZero | Io::UnexpectedEof | Io::Other => ErrorKind::Storage,
+                _ => ErrorKind::Other,
+            };
+        }
+        if cause.downcast_ref::<opencv::Error>().is_some() {
+            return ErrorKind::Media;
+        }
+        if cause.downcast_ref::<serde_yaml::Error>().is_some() {
+            return ErrorKind::Config;
+        }
+    }
+    ErrorKind::Other
+}
+
+/// The camera the error was tagged with, if any.
+pub fn camera_of(error: &anyhow::Error) -> Option<&str> {
+    error.chain()
+        .filter_map(|cause| cause.downcast_ref::<RcamError>())
+        .find_map(|tagged| tagged.camera.as_deref())
+        .or_else(|| error.downcast_ref::<RcamError>().and_then(|tagged| tagged.camera.as_deref()))
+}
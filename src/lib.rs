@@ -0,0 +1,69 @@
+//! rcam: multi-camera capture for IP cameras and Intel RealSense devices.
+//!
+//! The `rcam` binary is a thin CLI over this crate. Programs that want to drive the cameras
+//! directly (e.g. a robot controller) can embed it instead of shelling out:
+//!
+//! ```no_run
+//! use rcam::{CaptureImageParams, Rcam};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let rcam = Rcam::from_config_file("config/tatbot.yaml")?;
+//! let report = rcam.capture_images(&CaptureImageParams::default()).await?;
+//! println!("{} camera(s) captured", report.succeeded());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every operation takes a typed `XxxParams` struct; the `from_args` constructors on those
+//! structs are only the CLI's way of filling them in. Each operation and camera runs in its own
+//! `tracing` span (`op{name=...}`, `camera{camera=...}`), and installing a subscriber is left to
+//! the embedding program. Use a `tracing` subscriber that also takes `log` records (as
+//! tracing-subscriber's `init` does); a `log`-only logger gets the messages without the camera
+//! and operation they belong to. `common::logging_setup::initialize_logging` installs the one
+//! the CLI uses.
+
+pub mod api;
+pub mod calibration;
+pub mod camera;
+pub mod cli;
+pub mod common;
+pub mod config_loader;
+pub mod core;
+pub mod errors;
+pub mod operations;
+pub mod server;
+pub mod storage;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+pub use api::Rcam;
+pub use common::config_diff::{ConfigDiff, SettingChange};
+pub use common::rerun_setup::RerunParams;
+pub use config_loader::{load_config, load_config_with, load_config_with_profile, ConfigOverrides, AppSettings, CaptureDeviceConfig, CustomDeviceConfig, IpCameraSpecificConfig, MasterConfig, RealsenseSpecificConfig};
+pub use core::camera_manager::CameraManager;
+pub use core::capture_source::{CaptureSource, FrameData, FrameDataBundle, VideoRecordRequest, VideoRecorder, VideoSource};
+pub use core::events::Event;
+pub use core::source_registry::{register as register_capture_source, CaptureSourceFactory, SharedCaptureSource};
+pub use core::supervisor::{OnceTask, RestartPolicy, Supervisor, TaskState, TaskStatus};
+pub use errors::{ErrorKind, RcamError};
+pub use operations::agent_op::AgentParams;
+pub use operations::aim_op::{AimParams, AimSummary};
+pub use server::grpc::RcamControlClient;
+pub use operations::control_op::{ControlAction, ControlParams};
+pub use operations::coordinator_op::{CoordinatedOperation, CoordinatorParams};
+pub use operations::coverage_op::CoverageParams;
+pub use operations::daemon_op::DaemonParams;
+pub use operations::diagnostic_op::{DiagnosticParams, DiagnosticReport, DiagnosticResult, DiagnosticTest};
+pub use operations::discover_op::{DiscoverParams, DiscoveredDevice};
+pub use operations::soak_op::{SoakParams, SoakReport};
+pub use operations::validate_op::ValidateParams;
+pub use operations::verify_archive_op::{ArchiveVerification, VerifyArchiveParams};
+pub use operations::image_capture_op::CaptureImageParams;
+pub use operations::imaging_op::SetImagingParams;
+pub use operations::import_op::ImportParams;
+pub use operations::list_op::{DeviceListing, LastHealth, ListParams};
+pub use operations::op_context::OperationContext;
+pub use operations::op_result::{CameraResult, OperationReport, OperationStatus, RequireAllFailed};
+pub use operations::recording_control_op::{RecordingAction, RecordingControlParams};
+pub use operations::report_op::ReportParams;
+pub use operations::video_record_op::RecordVideoParams;
@@ -1,38 +1,45 @@
-mod cli;
-mod config_loader;
-mod camera;
-mod core;
-mod operations;
-mod common;
-
-use common::logging_setup;
-use core::camera_manager::CameraManager;
-use log::{info, error, debug};
-use anyhow::{Context, Result, bail};
+use clap::Parser;
+use rcam::cli::{Cli, Commands};
+use rcam::common::{logging_setup, shutdown};
+use rcam::config_loader;
+use rcam::core::camera_manager::CameraManager;
+use rcam::operations;
+use rcam::operations::op_result::{OperationReport, OperationStatus, RequireAllFailed};
+use rcam::operations::recording_control_op::RecordingAction;
+use log::{info, warn, error, debug};
+use anyhow::{Context, Result};
 use std::time::Instant;
+use tracing::Instrument;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let main_start_time = Instant::now();
     // Parse CLI arguments early for potential use in logging or config path
-    let matches = cli::build_cli().get_matches();
+    let cli = Cli::parse();
 
     // Determine the configuration file path
-    let config_path = matches.get_one::<String>("config").map(|s| s.as_str()).unwrap_or("config/tatbot.yaml");
+    let config_path = cli.config.as_deref().unwrap_or("config/tatbot.yaml");
     
     debug!("Attempting to load configuration from: {}", config_path);
     let config_load_start_time = Instant::now();
     // Attempt to load the full configuration
-    let master_config = match config_loader::load_config(config_path) {
+    let logging_options = logging_setup::LoggingOptions {
+        verbose: cli.verbose.max(cli.debug as u8),
+        quiet: cli.quiet,
+        log_file: cli.log_file.clone(),
+        log_format: cli.log_format.clone(),
+    };
+    let overrides = config_loader::ConfigOverrides { profile: cli.profile.clone(), set: cli.set.clone() };
+    let master_config = match config_loader::load_config_with(config_path, &overrides) {
         Ok(cfg) => {
-            logging_setup::initialize_logging(Some(&cfg), &matches)
+            logging_setup::initialize_logging(Some(&cfg), &logging_options)
                 .context("Failed to initialize logging with full config")?;
             info!("✅ Full configuration loaded successfully from: {} in {:?}", config_path, config_load_start_time.elapsed());
             cfg
         }
         Err(e) => {
             // Try to initialize logging with CLI args only, or defaults
-            logging_setup::initialize_logging(None, &matches)
+            logging_setup::initialize_logging(None, &logging_options)
                 .context("Failed to initialize logging with basic settings after config load failure")?;
             error!("❌ Failed to load master configuration from '{}': {:#}. Exiting.", config_path, e);
             // Attach context to the existing anyhow::Error
@@ -48,36 +55,125 @@ async fn main() -> Result<()> {
         .context("Failed to initialize CameraManager")?;
     debug!("✅ CameraManager initialized in {:?}.", cm_init_start_time.elapsed());
 
+    // From here on Ctrl-C stops operations gracefully instead of killing the process mid-write.
+    shutdown::install_ctrl_c_handler();
+
     // Dispatch based on subcommand
-    if let Some(subcommand_matches) = matches.subcommand() {
-        let operation_name = subcommand_matches.0;
+    if let Some(command) = &cli.command {
+        let operation_name = command.name();
         debug!("🎬 Dispatching to subcommand: {}", operation_name);
         let op_start_time = Instant::now();
 
-        let op_result: Result<()> = match subcommand_matches.0 {
-            "capture-image" => {
-                operations::image_capture_op::handle_capture_image_cli(&master_config, &camera_manager, subcommand_matches.1).await
+        // Capture operations return a per-camera report that decides the exit code;
+        // the other operations only succeed or fail as a whole.
+        // Every line logged during the operation carries its span, e.g. `op{name=capture-video}`.
+        let op_result: Result<Option<OperationReport>> = async {
+            match command {
+                Commands::List(args) => {
+                    operations::list_op::handle_list_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Discover(args) => {
+                    operations::discover_op::handle_discover_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::CaptureImage(args) => {
+                    operations::image_capture_op::handle_capture_image_cli(&master_config, &camera_manager, args).await.map(Some)
+                }
+                Commands::CaptureVideo(args) => {
+                    operations::video_record_op::handle_record_video_cli(&master_config, &camera_manager, args).await.map(Some)
+                }
+                Commands::Set(args) => {
+                    operations::imaging_op::handle_set_imaging_cli(&master_config, &camera_manager, args).await.map(Some)
+                }
+                Commands::Control(args) => {
+                    operations::control_op::handle_control_cli(&master_config, &camera_manager, args).await.map(Some)
+                }
+                Commands::Aim(args) => {
+                    operations::aim_op::handle_aim_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::VerifyTimes(args) => {
+                    operations::time_sync_op::handle_verify_times_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Test(args) => {
+                    operations::diagnostic_op::handle_diagnostic_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Calibrate(args) => {
+                    operations::calibrate_op::handle_calibrate_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Convert(args) => {
+                    operations::convert_op::handle_convert_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Import(args) => {
+                    operations::import_op::handle_import_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Report(args) => {
+                    operations::report_op::handle_report_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Coverage(args) => {
+                    operations::coverage_op::handle_coverage_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Daemon(args) => {
+                    operations::daemon_op::handle_daemon_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Validate(args) => {
+                    operations::validate_op::handle_validate_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::VerifyArchive(args) => {
+                    operations::verify_archive_op::handle_verify_archive_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Agent(args) => {
+                    operations::agent_op::handle_agent_cli(&master_config, &camera_manager, args).await.map(|_| None)
+                }
+                Commands::Coordinator(args) => {
+                    operations::coordinator_op::handle_coordinator_cli(&master_config, &camera_manager, args).await.map(Some)
+                }
+                Commands::Pause(args) => {
+                    operations::recording_control_op::handle_recording_control_cli(&master_config, &camera_manager, RecordingAction::Pause, args).await.map(|_| None)
+                }
+                Commands::Resume(args) => {
+                    operations::recording_control_op::handle_recording_control_cli(&master_config, &camera_manager, RecordingAction::Resume, args).await.map(|_| None)
+                }
+                Commands::StopRecording(args) => {
+                    operations::recording_control_op::handle_recording_control_cli(&master_config, &camera_manager, RecordingAction::Stop, args).await.map(|_| None)
+                }
+            }
+        }
+        .instrument(logging_setup::operation_span(operation_name))
+        .await;
+
+        // A --require-all failure still carries the report, so scripts get the summary either way.
+        let summary = match &op_result {
+            Ok(report) => report.as_ref(),
+            Err(e) => e.downcast_ref::<RequireAllFailed>().map(|failed| &failed.report),
+        };
+        if let (Some(report), Some(path)) = (summary, &cli.summary_json) {
+            if let Err(e) = report.write_json(path) {
+                error!("❌ Failed to write the summary to '{}': {:#}", path, e);
+            }
+        }
+
+        match op_result {
+            Err(e) => {
+                error!("❌ Operation '{}' failed after {:?}: {:#}", operation_name, op_start_time.elapsed(), e);
+                return Err(e);
             }
-            "capture-video" => {
-                operations::video_record_op::handle_record_video_cli(&master_config, &camera_manager, subcommand_matches.1).await
+            Ok(Some(report)) if report.status() == OperationStatus::PartialSuccess => {
+                warn!(
+                    "⚠️ Operation '{}' partially succeeded in {:?} ({} of {} camera(s) failed).",
+                    operation_name, op_start_time.elapsed(), report.failed(), report.results.len()
+                );
+                std::process::exit(report.status().exit_code());
             }
-            "verify-times" => {
-                operations::time_sync_op::handle_verify_times_cli(&master_config, &camera_manager, subcommand_matches.1).await
+            Ok(Some(report)) if report.status() == OperationStatus::TotalFailure => {
+                error!("❌ Operation '{}' failed for all {} camera(s) after {:?}.", operation_name, report.results.len(), op_start_time.elapsed());
+                std::process::exit(report.status().exit_code());
             }
-            "test" => {
-                operations::diagnostic_op::handle_diagnostic_cli(&master_config, &camera_manager, subcommand_matches.1).await
+            Ok(_) if shutdown::token().is_cancelled() => {
+                warn!("🛑 Operation '{}' was interrupted after {:?}; finished files were kept.", operation_name, op_start_time.elapsed());
+                std::process::exit(130);
             }
-            _ => {
-                let sub_cmd_name = subcommand_matches.0;
-                bail!("Subcommand '{}' not implemented.", sub_cmd_name)
+            Ok(_) => {
+                info!("✅ Operation '{}' completed successfully in {:?}.", operation_name, op_start_time.elapsed());
             }
-        };
-
-        if let Err(e) = op_result {
-            error!("❌ Operation '{}' failed after {:?}: {:#}", subcommand_matches.0, op_start_time.elapsed(), e);
-            return Err(e);
-        } else {
-            info!("✅ Operation '{}' completed successfully in {:?}.", operation_name, op_start_time.elapsed());
         }
 
     } else {
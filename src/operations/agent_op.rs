@@ -0,0 +1,80 @@
+use crate::api::Rcam;
+use crate::cli::AgentArgs;
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::core::supervisor::{TaskState, RIG};
+use crate::operations::op_context::OperationContext;
+use crate::server::{self, protocol::AGENT_TOKEN_ENV, ApiState};
+use anyhow::{bail, Context, Result};
+use log::{debug, info, warn};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+pub const DEFAULT_LISTEN: &str = "0.0.0.0:8700";
+// Names of the servers' supervised tasks.
+const HTTP_TASK: &str = "agent-http";
+const GRPC_TASK: &str = "agent-grpc";
+
+/// Typed parameters for `rcam agent`.
+#[derive(Debug, Clone, Default)]
+pub struct AgentParams {
+    pub listen: Option<SocketAddr>,      // None = agent.listen, then DEFAULT_LISTEN
+    pub grpc_listen: Option<SocketAddr>, // None = agent.grpc_listen, else no gRPC server
+}
+
+impl AgentParams {
+    pub fn from_args(args: &AgentArgs) -> Self {
+        AgentParams { listen: args.listen, grpc_listen: args.grpc_listen }
+    }
+}
+
+pub async fn handle_agent_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &AgentArgs,
+) -> Result<()> {
+    let params = AgentParams::from_args(args);
+    debug!("Agent CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+/// Serves capture requests from a coordinator until cancelled (Ctrl-C), over HTTP and, when
+/// configured, gRPC. Each server runs under the camera manager's supervisor, so one that fails
+/// is restarted by the `supervisor` policy; the agent fails once a server is given up on.
+pub async fn execute(params: &AgentParams, ctx: &OperationContext<'_>) -> Result<()> {
+    let agent_config = ctx.master_config.application.agent.as_ref();
+    let configured = agent_config.and_then(|agent| agent.listen.as_deref());
+    let listen = match params.listen {
+        Some(listen) => listen,
+        None => configured.unwrap_or(DEFAULT_LISTEN).parse()
+            .with_context(|| format!("Invalid agent listen address '{}'", configured.unwrap_or(DEFAULT_LISTEN)))?,
+    };
+    let grpc_listen = match params.grpc_listen {
+        Some(listen) => Some(listen),
+        None => agent_config.and_then(|agent| agent.grpc_listen.as_deref())
+            .map(|listen| listen.parse().with_context(|| format!("Invalid agent gRPC listen address '{}'", listen)))
+            .transpose()?,
+    };
+    let token = std::env::var(AGENT_TOKEN_ENV).ok().filter(|token| !token.is_empty());
+    match &token {
+        Some(_) => info!("🔒 Requests must carry the bearer token from {}.", AGENT_TOKEN_ENV),
+        None if !listen.ip().is_loopback() || grpc_listen.is_some_and(|grpc| !grpc.ip().is_loopback()) => warn!("⚠️ {} is not set; anyone who can reach this host can trigger captures.", AGENT_TOKEN_ENV),
+        None => {}
+    }
+    let rcam = Rcam::new(ctx.master_config.clone())?;
+    let state = Arc::new(ApiState::new(rcam, token));
+    let supervisor = ctx.camera_manager.supervisor().scoped();
+    let http_state = state.clone();
+    supervisor.spawn_with_policy(RIG, HTTP_TASK, supervisor.default_policy(), move |cancel| server::serve(listen, http_state.clone(), cancel));
+    if let Some(grpc_listen) = grpc_listen {
+        supervisor.spawn_with_policy(RIG, GRPC_TASK, supervisor.default_policy(), move |cancel| server::grpc::serve(grpc_listen, state.clone(), cancel));
+    }
+    supervisor.wait(&ctx.cancel).await;
+    let failed = supervisor.camera_status(RIG).into_iter()
+        .find(|status| [HTTP_TASK, GRPC_TASK].contains(&status.task.as_str()) && status.state == TaskState::Failed);
+    if let Some(failed) = failed {
+        bail!("Agent {} failed: {}", failed.task, failed.last_error.unwrap_or_default());
+    }
+    info!("🛰️ Agent stopped.");
+    Ok(())
+}
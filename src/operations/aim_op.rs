@@ -0,0 +1,180 @@
+use crate::camera::image_quality::{self, ImageQuality};
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::rtsp_grab::{self, RtspGrab, RtspTransport};
+use crate::cli::AimArgs;
+use crate::common::opencv_pool;
+use crate::common::rerun_setup::{self, RerunIdentity, RerunParams};
+use crate::config_loader::{CameraTimeouts, CaptureDeviceConfig, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info, warn};
+use opencv::{core as opencv_core, prelude::*, videoio};
+use rerun::RecordingStream;
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 20;
+
+/// Typed parameters for `rcam aim`.
+#[derive(Debug, Clone)]
+pub struct AimParams {
+    pub camera: String,
+    pub interval: Duration,         // time between readings
+    pub duration: Option<Duration>, // None = until Ctrl-C
+    pub rerun: RerunParams,
+    pub stream: Option<String>,     // None = operation_streams, else the camera's default stream
+}
+
+impl Default for AimParams {
+    fn default() -> Self {
+        AimParams { camera: String::new(), interval: Duration::from_millis(500), duration: None, rerun: RerunParams::default(), stream: None }
+    }
+}
+
+impl AimParams {
+    pub fn from_args(args: &AimArgs) -> Self {
+        AimParams {
+            camera: args.camera.clone(),
+            interval: Duration::from_millis(args.interval_ms.max(50)),
+            duration: args.duration.map(Duration::from_secs),
+            rerun: RerunParams::from_args(&args.rerun),
+            stream: args.stream.clone(),
+        }
+    }
+}
+
+/// What an aim session saw: the last reading and the sharpest one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AimSummary {
+    pub readings: u64,
+    pub last: Option<ImageQuality>,
+    pub best: Option<ImageQuality>,
+}
+
+pub async fn handle_aim_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &AimArgs,
+) -> Result<()> {
+    let params = AimParams::from_args(args);
+    debug!("Aim CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await.map(|_| ())
+}
+
+/// Reads frames from one camera's stream and logs a focus score (variance of the Laplacian)
+/// and brightness every `interval`, against the sharpest reading so far, while an installer
+/// turns the lens. With Rerun enabled the frames and both metrics are plotted as well.
+pub async fn execute(params: &AimParams, ctx: &OperationContext<'_>) -> Result<AimSummary> {
+    let app_config = &ctx.master_config.application;
+    let camera = ctx.master_config.cameras.iter()
+        .find(|camera| camera.get_name() == &params.camera)
+        .ok_or_else(|| anyhow!("'{}' is not a configured camera", params.camera))?;
+    let specifics = match camera {
+        CaptureDeviceConfig::IpCamera { specifics, .. } => specifics,
+        CaptureDeviceConfig::RealsenseCamera { .. } => bail!("aim reads an RTSP stream; '{}' is a RealSense camera", params.camera),
+        CaptureDeviceConfig::ThermalCamera { .. } => bail!("aim reads an RTSP stream; '{}' is a thermal camera", params.camera),
+        CaptureDeviceConfig::VirtualCamera { .. } => bail!("aim reads an RTSP stream; '{}' is a virtual camera", params.camera),
+        CaptureDeviceConfig::Custom { device_type, .. } => bail!("aim reads an RTSP stream; '{}' is a custom '{}' device", params.camera, device_type),
+    };
+    let timeouts = CameraTimeouts::resolve(app_config, Some(specifics));
+    let device = IpCameraDevice::new(params.camera.clone(), specifics.clone(), timeouts)?;
+    let transport = RtspTransport::resolve(app_config, Some(specifics));
+    let stream = params.stream.clone().or_else(|| specifics.stream_for(app_config, "aim"));
+    let rtsp_url = device.get_rtsp_url(stream.as_deref()).await?;
+
+    let rec_stream = if params.rerun.enabled {
+        let identity = RerunIdentity::resolve(app_config, &params.rerun, "rcam_aim");
+        rerun_setup::init_recording_stream(app_config, &identity, "Aim")
+    } else {
+        None
+    };
+
+    info!("🎯 Aiming '{}': adjust focus until the score peaks{}.", params.camera,
+        params.duration.map(|d| format!(" ({:?})", d)).unwrap_or_else(|| " (Ctrl-C to stop)".to_string()));
+    let name = params.camera.clone();
+    let interval = params.interval;
+    let duration = params.duration;
+    let cancel = ctx.cancel.clone();
+    let stream = rec_stream.clone();
+    let summary = opencv_pool::spawn(opencv_pool::pinned(specifics.cpu_affinity.clone(), move || -> Result<AimSummary> {
+        let mut open_params = opencv_core::Vector::<i32>::new();
+        open_params.push(videoio::CAP_PROP_OPEN_TIMEOUT_MSEC);
+        open_params.push(timeouts.connect.as_millis() as i32);
+        open_params.push(videoio::CAP_PROP_READ_TIMEOUT_MSEC);
+        open_params.push(timeouts.read.as_millis() as i32);
+        let mut cap = rtsp_grab::open_stream(&rtsp_url, &open_params, transport)
+            .with_context(|| format!("OpenCV: Failed to create VideoCapture for '{}'", name))?;
+        if !cap.is_opened()? {
+            return Err(anyhow!("Failed to open RTSP stream for '{}'", name));
+        }
+        RtspGrab::configure(&mut cap);
+
+        let started = Instant::now();
+        let mut next_reading = started;
+        let mut summary = AimSummary::default();
+        while !cancel.is_cancelled() && duration.is_none_or(|d| started.elapsed() < d) {
+            // Every frame is read so the decoder never falls behind; only some are measured.
+            let frame = rtsp_grab::read_frame(&mut cap, &name)?;
+            if Instant::now() < next_reading {
+                continue;
+            }
+            next_reading = Instant::now() + interval;
+            let quality = image_quality::measure_frame(&frame)?;
+            let previous = summary.last.replace(quality);
+            if summary.best.is_none_or(|best| quality.sharpness > best.sharpness) {
+                summary.best = Some(quality);
+            }
+            summary.readings += 1;
+            log_reading(&name, &quality, previous.as_ref(), summary.best.as_ref());
+            if let Some(stream) = &stream {
+                if let Err(e) = log_to_rerun(stream, &name, &frame, &quality) {
+                    debug!("Rerun: Failed to log aim reading for '{}': {:#}", name, e);
+                }
+            }
+        }
+        Ok(summary)
+    })).await.map_err(|e| anyhow!("Aim task for '{}': {}", params.camera, e))??;
+
+    if let Some(stream) = rec_stream {
+        stream.flush_blocking();
+    }
+    match summary.best {
+        Some(best) => info!("✅ Aim finished after {} reading(s); best focus score {:.1} (brightness {:.0}).",
+            summary.readings, best.sharpness, best.brightness),
+        None => warn!("⚠️ Aim stopped before the first reading of '{}'.", params.camera),
+    }
+    Ok(summary)
+}
+
+/// One line per reading: the focus score as a bar relative to the best so far, whether it went
+/// up or down since the last reading, and the exposure.
+fn log_reading(camera: &str, quality: &ImageQuality, previous: Option<&ImageQuality>, best: Option<&ImageQuality>) {
+    let best_sharpness = best.map_or(quality.sharpness, |b| b.sharpness);
+    let ratio = if best_sharpness > 0.0 { (quality.sharpness / best_sharpness).clamp(0.0, 1.0) } else { 0.0 };
+    let filled = (ratio * BAR_WIDTH as f64).round() as usize;
+    let trend = match previous {
+        Some(p) if quality.sharpness > p.sharpness * 1.02 => "↑",
+        Some(p) if quality.sharpness < p.sharpness * 0.98 => "↓",
+        Some(_) => "=",
+        None => " ",
+    };
+    let exposure = if quality.clipped_bright_percent > 5.0 {
+        " ⚠️ overexposed"
+    } else if quality.clipped_dark_percent > 5.0 {
+        " ⚠️ underexposed"
+    } else {
+        ""
+    };
+    info!("🎯 [{}] focus {:>9.1} {} {}{} {:>3.0}% of best | brightness {:>5.1} | clipped {:.1}% dark, {:.1}% bright{}",
+        camera, quality.sharpness, trend, "█".repeat(filled), "░".repeat(BAR_WIDTH - filled), ratio * 100.0,
+        quality.brightness, quality.clipped_dark_percent, quality.clipped_bright_percent, exposure);
+}
+
+fn log_to_rerun(stream: &RecordingStream, camera: &str, frame: &opencv_core::Mat, quality: &ImageQuality) -> Result<()> {
+    let now = chrono::Utc::now();
+    stream.set_timestamp_secs_since_epoch("capture_time", now.timestamp_micros() as f64 / 1e6);
+    stream.log(format!("aim/{}/image", camera), &rerun_setup::bgr_image(frame)?)?;
+    stream.log(format!("aim/{}/focus", camera), &rerun::Scalars::single(quality.sharpness))?;
+    stream.log(format!("aim/{}/brightness", camera), &rerun::Scalars::single(quality.brightness))?;
+    Ok(())
+}
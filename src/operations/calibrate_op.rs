@@ -0,0 +1,118 @@
+use crate::calibration::board::{self, BoardSpec, BoardType};
+use crate::calibration::pdf;
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use anyhow::{anyhow, bail, Context, Result};
+use crate::cli::{CalibrateArgs, CalibrateCommand, PrintBoardArgs};
+use log::{debug, info};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Typed parameters for `calibrate print-board`.
+#[derive(Debug, Clone)]
+pub struct PrintBoardParams {
+    pub spec: BoardSpec,
+    pub dpi: u32,
+    pub margin_mm: f64,
+    pub format: String, // "png", "pdf" or "both"
+    pub output_dir: Option<PathBuf>,
+}
+
+impl PrintBoardParams {
+    pub fn from_args(args: &PrintBoardArgs) -> Result<Self> {
+        let board_type = BoardType::parse(&args.board_type)?;
+        let (squares_x, squares_y) = board::parse_squares(&args.squares)?;
+        Ok(PrintBoardParams {
+            spec: BoardSpec {
+                board_type,
+                squares_x,
+                squares_y,
+                square_mm: args.square_mm,
+                marker_mm: args.marker_mm.unwrap_or(args.square_mm * 0.75),
+                dictionary: args.dictionary.clone(),
+            },
+            dpi: args.dpi,
+            margin_mm: args.margin_mm,
+            format: args.format.to_lowercase(),
+            output_dir: args.output.as_ref().map(PathBuf::from),
+        })
+    }
+}
+
+pub async fn handle_calibrate_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &CalibrateArgs,
+) -> Result<()> {
+    let ctx = OperationContext::new(master_config, camera_manager);
+    match &args.command {
+        CalibrateCommand::PrintBoard(sub_args) => {
+            let params = PrintBoardParams::from_args(sub_args)?;
+            debug!("Calibrate print-board CLI parsed into: {:?}", params);
+            execute_print_board(&params, &ctx).await.map(|_| ())
+        }
+    }
+}
+
+/// Renders the board and writes the requested files; returns their paths.
+pub async fn execute_print_board(params: &PrintBoardParams, ctx: &OperationContext<'_>) -> Result<Vec<PathBuf>> {
+    let op_start_time = Instant::now();
+    let operation_display_name = "Calibration Board Export";
+    if !matches!(params.format.as_str(), "png" | "pdf" | "both") {
+        bail!("Unsupported board format '{}' (supported: png, pdf, both)", params.format);
+    }
+    if params.margin_mm < 0.0 {
+        bail!("Margin must not be negative (got {} mm)", params.margin_mm);
+    }
+    let output_dir = op_helper::determine_operation_output_dir(
+        ctx.master_config,
+        params.output_dir.as_deref(),
+        Some("calibration"),
+        operation_display_name,
+    )?;
+
+    let params_clone = params.clone();
+    let (pixels, width, height) = tokio::task::spawn_blocking(move || board::render(&params_clone.spec, params_clone.dpi))
+        .await
+        .map_err(|e| anyhow!("Board rendering task panicked: {}", e))??;
+
+    let stem = format!("{}_{}x{}_{}mm", params.spec.board_type.name(), params.spec.squares_x, params.spec.squares_y, params.spec.square_mm);
+    let description = params.spec.description();
+    let mut written = Vec::new();
+
+    if params.format == "png" || params.format == "both" {
+        let path = output_dir.join(format!("{}.png", stem));
+        write_png_with_margin(&path, &pixels, width, height, params)?;
+        info!("🖨️ Wrote {} ({}x{} px at {} DPI; print at 100% scale).", path.display(), width, height, params.dpi);
+        written.push(path);
+    }
+    if params.format == "pdf" || params.format == "both" {
+        let path = output_dir.join(format!("{}.pdf", stem));
+        pdf::write_grayscale_pdf(
+            &path, &pixels, width, height,
+            params.spec.width_mm(), params.spec.height_mm(), params.margin_mm, &description,
+        )?;
+        info!(
+            "🖨️ Wrote {} (board {:.1} x {:.1} mm; print at 'actual size').",
+            path.display(), params.spec.width_mm(), params.spec.height_mm()
+        );
+        written.push(path);
+    }
+    info!("✅ {} exported in {:?}: {}", description, op_start_time.elapsed(), written.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "));
+    Ok(written)
+}
+
+/// PNG has no reliable physical size, so the white margin is baked into the pixels.
+fn write_png_with_margin(path: &Path, pixels: &[u8], width: u32, height: u32, params: &PrintBoardParams) -> Result<()> {
+    let margin_px = (params.margin_mm * params.dpi as f64 / 25.4).round() as u32;
+    let full_width = width + 2 * margin_px;
+    let full_height = height + 2 * margin_px;
+    let mut canvas = image::GrayImage::from_pixel(full_width, full_height, image::Luma([255u8]));
+    let board_image = image::GrayImage::from_raw(width, height, pixels.to_vec())
+        .ok_or_else(|| anyhow!("Board pixel buffer does not match {}x{}", width, height))?;
+    image::imageops::replace(&mut canvas, &board_image, margin_px as i64, margin_px as i64);
+    canvas.save_with_format(path, image::ImageFormat::Png)
+        .with_context(|| format!("Failed to write board PNG {}", path.display()))
+}
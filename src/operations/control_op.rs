@@ -0,0 +1,162 @@
+use crate::camera::camera_controller::CameraController;
+use crate::camera::imaging::DayNightMode;
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::common::logging_setup;
+use crate::config_loader::{CameraTimeouts, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use crate::operations::op_result::{CameraResult, OperationReport};
+use anyhow::Result;
+use clap::builder::PossibleValue;
+use crate::errors::{ErrorKind, RcamError};
+use crate::cli::ControlArgs;
+use futures::future::join_all;
+use log::{debug, info, warn};
+use std::time::Instant;
+use tracing::Instrument;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlAction {
+    Reboot,
+    GetDeviceInfo,
+    Enable,
+    Disable,
+    DayNight(DayNightMode),
+}
+
+// `--action` takes the unit actions; the day/night mode has its own flag, `--ir <MODE>`.
+impl clap::ValueEnum for ControlAction {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ControlAction::Reboot, ControlAction::GetDeviceInfo, ControlAction::Enable, ControlAction::Disable]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            ControlAction::Reboot => Some(PossibleValue::new("reboot")),
+            ControlAction::GetDeviceInfo => Some(PossibleValue::new("get-device-info")),
+            ControlAction::Enable => Some(PossibleValue::new("enable")),
+            ControlAction::Disable => Some(PossibleValue::new("disable")),
+            ControlAction::DayNight(_) => None,
+        }
+    }
+}
+
+impl ControlAction {
+    /// Actions that disrupt a running capture and therefore ask before running.
+    fn needs_confirmation(&self) -> bool {
+        matches!(self, ControlAction::Reboot | ControlAction::Disable)
+    }
+}
+
+/// Typed parameters for `rcam control`.
+#[derive(Debug, Clone)]
+pub struct ControlParams {
+    pub cameras: Option<Vec<String>>, // None = all configured IP cameras
+    pub action: ControlAction,
+    pub assume_yes: bool, // skip the confirmation prompt for disruptive actions
+    pub require_all: bool,
+}
+
+impl ControlParams {
+    pub fn from_args(args: &ControlArgs) -> Self {
+        ControlParams {
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+            action: args.ir.map(ControlAction::DayNight).or(args.action).expect("clap requires --action or --ir"),
+            assume_yes: args.yes,
+            require_all: args.require_all,
+        }
+    }
+}
+
+pub async fn handle_control_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &ControlArgs,
+) -> Result<OperationReport> {
+    let params = ControlParams::from_args(args);
+    debug!("Control CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+/// Runs a maintenance action (reboot, device info, stream enable/disable, day/night mode) on the selected IP cameras in parallel.
+pub async fn execute(params: &ControlParams, ctx: &OperationContext<'_>) -> Result<OperationReport> {
+    let master_config = ctx.master_config;
+    let op_start_time = Instant::now();
+    let operation_display_name = match params.action {
+        ControlAction::Reboot => "Reboot",
+        ControlAction::GetDeviceInfo => "Get Device Info",
+        ControlAction::Enable => "Enable Stream",
+        ControlAction::Disable => "Disable Stream",
+        ControlAction::DayNight(_) => "Set Day/Night Mode",
+    };
+    let mut report = OperationReport::new(operation_display_name);
+
+    let targets = op_helper::select_ip_cameras(master_config, params.cameras.as_ref())?;
+    if targets.is_empty() {
+        info!("No IP cameras selected. Nothing to do.");
+        return Ok(report);
+    }
+
+    if params.action.needs_confirmation() && !params.assume_yes {
+        let names: Vec<&str> = targets.iter().map(|(name, _)| name.as_str()).collect();
+        let prompt = format!("⚠️ {} {} camera(s): {}?", operation_display_name, names.len(), names.join(", "));
+        if !op_helper::confirm(&prompt)? {
+            warn!("🛑 '{}' cancelled; no camera was touched.", operation_display_name);
+            return Ok(report);
+        }
+    }
+
+    let controller = CameraController::new();
+    let tasks = targets.into_iter().map(|(name, specifics)| {
+        let controller = controller.clone();
+        let timeouts = CameraTimeouts::resolve(&master_config.application, Some(&specifics));
+        let action = params.action;
+        let span = logging_setup::camera_span(&name);
+        async move {
+            let started = Instant::now();
+            let result: Result<Option<String>> = async {
+                let device = IpCameraDevice::new(name.clone(), specifics, timeouts)?;
+                // At most a write and a read-back, each bounded by the HTTP timeout.
+                let deadline = timeouts.http * 3;
+                let work = async {
+                    match action {
+                        ControlAction::Reboot => controller.reboot(&device).await.map(|_| None),
+                        ControlAction::GetDeviceInfo => controller.get_device_info(&device).await.map(|info| Some(info.to_string())),
+                        ControlAction::Enable | ControlAction::Disable => controller
+                            .set_camera_enabled(&device, action == ControlAction::Enable).await
+                            .map(|enabled| Some(format!("stream {}", if enabled { "enabled" } else { "disabled" }))),
+                        ControlAction::DayNight(mode) => controller.set_day_night(&device, mode).await.map(|mode| Some(format!("day/night {}", mode))),
+                    }
+                };
+                tokio::time::timeout(deadline, work).await
+                    .map_err(|_| RcamError::for_camera(ErrorKind::Network, name.as_str(), format!("Timed out after {:?} talking to camera '{}'", deadline, name)))?
+            }.await;
+            (name, result, started.elapsed())
+        }
+        .instrument(span)
+    });
+
+    for (name, result, elapsed) in join_all(tasks).await {
+        match result {
+            Ok(details) => {
+                if let Some(details) = details {
+                    info!("📷 '{}': {}", name, details);
+                }
+                report.push(CameraResult::success(name).took(elapsed));
+            }
+            Err(e) => {
+                debug!("'{}' failed: {:#}", name, e);
+                report.push(CameraResult::from_error(name, &e).took(elapsed));
+            }
+        }
+    }
+
+    if params.action == ControlAction::Reboot && report.succeeded() > 0 {
+        info!("⏳ Rebooting cameras are typically back on the network within one to two minutes.");
+    }
+    info!("🔧 '{}' finished in {:?}.", operation_display_name, op_start_time.elapsed());
+    report.log_summary();
+    report.enforce_require_all(params.require_all)?;
+    Ok(report)
+}
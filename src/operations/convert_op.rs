@@ -0,0 +1,214 @@
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use anyhow::{anyhow, bail, Context, Result};
+use crate::cli::ConvertArgs;
+use log::{debug, error, info, warn};
+use opencv::{core as opencv_core, imgcodecs, prelude::*};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "tif", "tiff"];
+const TARGET_FORMATS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Typed parameters for batch image conversion.
+#[derive(Debug, Clone)]
+pub struct ConvertParams {
+    pub input_dir: PathBuf,
+    pub output_dir: Option<PathBuf>, // None = write next to the originals
+    pub to: String,                  // target extension: jpg, png or webp
+    pub quality: Option<u8>,         // jpg/webp quality (1-100); None = AppSettings.jpeg_quality or codec default
+    pub recursive: bool,
+    pub remove_source: bool,
+    pub overwrite: bool,             // replace existing targets instead of skipping them
+}
+
+impl ConvertParams {
+    pub fn from_args(args: &ConvertArgs) -> Self {
+        ConvertParams {
+            input_dir: PathBuf::from(&args.input),
+            output_dir: args.output.as_ref().map(PathBuf::from),
+            to: args.to.to_lowercase(),
+            quality: args.quality,
+            recursive: args.recursive,
+            remove_source: args.remove_source,
+            overwrite: args.overwrite,
+        }
+    }
+}
+
+pub async fn handle_convert_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &ConvertArgs,
+) -> Result<()> {
+    let params = ConvertParams::from_args(args);
+    debug!("Convert CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+pub async fn execute(params: &ConvertParams, ctx: &OperationContext<'_>) -> Result<()> {
+    let op_start_time = Instant::now();
+    if !TARGET_FORMATS.contains(&params.to.as_str()) {
+        bail!("Unsupported target format '{}' (supported: {})", params.to, TARGET_FORMATS.join(", "));
+    }
+    if let Some(q) = params.quality {
+        if q == 0 || q > 100 {
+            bail!("--quality must be between 1 and 100 (got {})", q);
+        }
+    }
+    if !params.input_dir.is_dir() {
+        bail!("Input directory '{}' does not exist or is not a directory", params.input_dir.display());
+    }
+
+    let quality = params.quality.or(ctx.master_config.application.jpeg_quality);
+    let png_compression = ctx.master_config.application.png_compression;
+    let params_clone = params.clone();
+
+    let (converted, skipped, failed) = tokio::task::spawn_blocking(move || {
+        convert_tree(&params_clone, quality, png_compression)
+    }).await.map_err(|e| anyhow!("Conversion task panicked: {}", e))??;
+
+    info!(
+        "🔁 Converted {} image(s) to {} in {:?} ({} skipped, {} failed).",
+        converted, params.to, op_start_time.elapsed(), skipped, failed
+    );
+    if failed > 0 && converted == 0 {
+        bail!("All {} image conversion(s) failed", failed);
+    }
+    if failed > 0 {
+        warn!("⚠️ {} image(s) could not be converted. Check logs above.", failed);
+    }
+    Ok(())
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase())
+}
+
+fn is_image(path: &Path) -> bool {
+    extension_of(path).is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Returns (converted, skipped, failed).
+fn convert_tree(params: &ConvertParams, quality: Option<u8>, png_compression: Option<u32>) -> Result<(usize, usize, usize)> {
+    let mut files = Vec::new();
+    op_helper::collect_files(&params.input_dir, params.recursive, &mut files)?;
+    files.sort();
+
+    let target_ext = if params.to == "jpeg" { "jpg".to_string() } else { params.to.clone() };
+    let mut write_params = opencv_core::Vector::<i32>::new();
+    match target_ext.as_str() {
+        "jpg" => if let Some(q) = quality {
+            write_params.push(imgcodecs::IMWRITE_JPEG_QUALITY);
+            write_params.push(q as i32);
+        },
+        "webp" => if let Some(q) = quality {
+            write_params.push(imgcodecs::IMWRITE_WEBP_QUALITY);
+            write_params.push(q as i32);
+        },
+        "png" => if let Some(c) = png_compression {
+            write_params.push(imgcodecs::IMWRITE_PNG_COMPRESSION);
+            write_params.push(c as i32);
+        },
+        _ => {}
+    }
+
+    let (mut converted, mut skipped, mut failed) = (0, 0, 0);
+    let mut converted_stems: HashSet<PathBuf> = HashSet::new();
+    for src in files.iter().filter(|p| is_image(p)) {
+        let src_ext = extension_of(src).unwrap_or_default();
+        if src_ext == target_ext || (src_ext == "jpeg" && target_ext == "jpg") {
+            debug!("Skipping {} (already {}).", src.display(), target_ext);
+            skipped += 1;
+            continue;
+        }
+        let dest_dir = destination_dir(params, src);
+        let dest = dest_dir.join(src.with_extension(&target_ext).file_name().unwrap_or_default());
+        // Also catches two originals sharing a stem (e.g. a.png and a.webp -> a.jpg) in one run.
+        if dest.exists() && !params.overwrite {
+            warn!("Skipping {}: {} already exists (use --overwrite to replace it).", src.display(), dest.display());
+            skipped += 1;
+            continue;
+        }
+        match convert_one(src, &dest, &write_params) {
+            Ok(true) => {
+                converted += 1;
+                converted_stems.insert(src.with_extension(""));
+                if params.remove_source {
+                    if let Err(e) = fs::remove_file(src) {
+                        warn!("Converted {} but could not remove the original: {}", src.display(), e);
+                    }
+                }
+            }
+            Ok(false) => skipped += 1,
+            Err(e) => {
+                error!("❌ Failed to convert {}: {:#}", src.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    // Sidecars (e.g. metadata JSON) share the image's stem; carry them along when writing elsewhere.
+    if params.output_dir.is_some() {
+        for sidecar in files.iter().filter(|p| !is_image(p)) {
+            if !converted_stems.contains(&sidecar.with_extension("")) {
+                continue;
+            }
+            let dest = destination_dir(params, sidecar).join(sidecar.file_name().unwrap_or_default());
+            if dest.exists() && !params.overwrite {
+                debug!("Not copying sidecar {}: {} already exists.", sidecar.display(), dest.display());
+                continue;
+            }
+            if let Err(e) = fs::copy(sidecar, &dest).map_err(anyhow::Error::from).and_then(|_| copy_mtime(sidecar, &dest)) {
+                warn!("Could not copy sidecar {} to {}: {:#}", sidecar.display(), dest.display(), e);
+            }
+        }
+    }
+    Ok((converted, skipped, failed))
+}
+
+fn destination_dir(params: &ConvertParams, src: &Path) -> PathBuf {
+    match &params.output_dir {
+        Some(out) => {
+            let relative_parent = src.parent()
+                .and_then(|p| p.strip_prefix(&params.input_dir).ok())
+                .unwrap_or_else(|| Path::new(""));
+            out.join(relative_parent)
+        }
+        None => src.parent().map(Path::to_path_buf).unwrap_or_default(),
+    }
+}
+
+/// Returns Ok(false) if the image was skipped because the target format cannot hold it.
+fn convert_one(src: &Path, dest: &Path, write_params: &opencv_core::Vector<i32>) -> Result<bool> {
+    let src_str = src.to_str().context("Source path is not UTF-8")?;
+    let image = imgcodecs::imread(src_str, imgcodecs::IMREAD_UNCHANGED)?;
+    if image.empty() {
+        bail!("could not decode image");
+    }
+    // 16-bit depth PNGs would be silently truncated by 8-bit formats.
+    if image.depth() == opencv_core::CV_16U && !dest.extension().is_some_and(|e| e == "png") {
+        warn!("Skipping 16-bit image {} (e.g. depth); target format would lose precision.", src.display());
+        return Ok(false);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let dest_str = dest.to_str().context("Destination path is not UTF-8")?;
+    if !imgcodecs::imwrite(dest_str, &image, write_params)? {
+        bail!("OpenCV could not write {}", dest.display());
+    }
+    copy_mtime(src, dest)?;
+    debug!("Converted {} -> {}", src.display(), dest.display());
+    Ok(true)
+}
+
+fn copy_mtime(src: &Path, dest: &Path) -> Result<()> {
+    let modified = fs::metadata(src)?.modified()?;
+    fs::File::options().write(true).open(dest)?.set_modified(modified)
+        .with_context(|| format!("Failed to preserve modification time on {}", dest.display()))
+}
@@ -0,0 +1,316 @@
+use crate::cli::{CoordinatorArgs, CoordinatorCommand};
+use crate::common::session::SessionManifest;
+use crate::common::sync_start;
+use crate::config_loader::{AppSettings, MasterConfig, RemoteAgent};
+use crate::errors::{ErrorKind, RcamError};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use crate::operations::op_result::{CameraResult, OperationReport};
+use crate::server::protocol::{
+    AgentStatus, CaptureImageRequest, ErrorBody, OperationResponse, RecordVideoRequest, AGENT_TOKEN_ENV, API_PREFIX,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use log::{debug, error, info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// What the agents are asked to do.
+#[derive(Debug, Clone)]
+pub enum CoordinatedOperation {
+    CaptureImage { count: Option<u32>, interval_ms: Option<u64>, retries: Option<u32> }, // retries: None = capture_retries
+    CaptureVideo { duration: Option<Duration> }, // None = video_duration_default_seconds
+}
+
+impl CoordinatedOperation {
+    /// Fills in the defaults from this host's configuration. The agents are sent the result, so
+    /// the coordinator knows how long they take instead of relying on their own defaults.
+    fn resolved(&self, app: &AppSettings) -> Self {
+        match self {
+            CoordinatedOperation::CaptureImage { count, interval_ms, retries } => CoordinatedOperation::CaptureImage {
+                count: *count,
+                interval_ms: *interval_ms,
+                retries: Some(retries.or(app.capture_retries).unwrap_or(0)),
+            },
+            CoordinatedOperation::CaptureVideo { duration } => CoordinatedOperation::CaptureVideo {
+                duration: Some(duration.unwrap_or_else(|| Duration::from_secs(app.video_duration_default_seconds as u64))),
+            },
+        }
+    }
+
+    /// How long an agent's operation runs past its start, beyond the `per_attempt` a single
+    /// capture may take: a burst's intervals and each retry round, or the recording.
+    fn run_time(&self, app: &AppSettings, per_attempt: Duration) -> Duration {
+        match self {
+            CoordinatedOperation::CaptureImage { count, interval_ms, retries } => {
+                let burst = Duration::from_millis(interval_ms.unwrap_or(0)) * count.unwrap_or(1).saturating_sub(1);
+                let retry_delay = Duration::from_secs_f32(app.capture_retry_delay_secs.unwrap_or(2.0));
+                burst + (retry_delay + burst + per_attempt) * retries.unwrap_or(0)
+            }
+            CoordinatedOperation::CaptureVideo { duration } => duration.unwrap_or_default(),
+        }
+    }
+}
+
+/// Typed parameters for `rcam coordinator`.
+#[derive(Debug, Clone)]
+pub struct CoordinatorParams {
+    pub operation: CoordinatedOperation,
+    pub agents: Option<Vec<String>>,  // None = every configured agent
+    pub cameras: Option<Vec<String>>, // None = every camera of the selected agents
+    pub start_at: Option<String>,     // None = AppSettings.start_at, then "next-second"
+    pub start_offset_ms: Option<u64>,
+    pub output_dir: Option<PathBuf>,  // where the combined session goes
+    pub require_all: bool,
+}
+
+impl CoordinatorParams {
+    pub fn from_args(args: &CoordinatorArgs) -> Self {
+        let (shared, operation) = match &args.command {
+            CoordinatorCommand::CaptureImage(a) => (&a.shared, CoordinatedOperation::CaptureImage {
+                count: a.count,
+                interval_ms: a.interval,
+                retries: a.retries,
+            }),
+            CoordinatorCommand::CaptureVideo(a) => (&a.shared, CoordinatedOperation::CaptureVideo {
+                duration: a.duration.map(Duration::from_secs),
+            }),
+        };
+        CoordinatorParams {
+            operation,
+            agents: op_helper::parse_camera_selection(shared.agents.as_ref()),
+            cameras: op_helper::parse_camera_selection(shared.cameras.as_ref()),
+            start_at: shared.start_at.clone(),
+            start_offset_ms: shared.start_offset_ms,
+            output_dir: shared.output.as_ref().map(PathBuf::from),
+            require_all: shared.require_all,
+        }
+    }
+}
+
+pub async fn handle_coordinator_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &CoordinatorArgs,
+) -> Result<OperationReport> {
+    let params = CoordinatorParams::from_args(args);
+    debug!("Coordinator CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+/// Client for one agent's API.
+struct AgentClient<'a> {
+    agent: &'a RemoteAgent,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl AgentClient<'_> {
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}{}/{}", self.agent.url.trim_end_matches('/'), API_PREFIX, endpoint)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder, timeout: Duration) -> Result<T> {
+        let request = match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        let response = request.timeout(timeout).send().await
+            .with_context(|| format!("Agent '{}' ({}) is unreachable", self.agent.name, self.agent.url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.json::<ErrorBody>().await.map(|body| body.error).unwrap_or_default();
+            bail!("Agent '{}' answered {}: {}", self.agent.name, status, detail);
+        }
+        response.json().await.with_context(|| format!("Agent '{}' sent an unreadable reply", self.agent.name))
+    }
+
+    async fn status(&self, timeout: Duration) -> Result<AgentStatus> {
+        self.send(self.http.get(self.url("status")), timeout).await
+    }
+
+    async fn run<B: Serialize>(&self, endpoint: &str, body: &B, timeout: Duration) -> Result<OperationResponse> {
+        self.send(self.http.post(self.url(endpoint)).json(body), timeout).await
+    }
+}
+
+/// Runs one capture on several rcam agents at the same instant and gathers their results into
+/// a combined session: one report entry per `agent/camera`, and each agent's own manifest
+/// saved under `agents/` in the coordinator's session directory.
+pub async fn execute(params: &CoordinatorParams, ctx: &OperationContext<'_>) -> Result<OperationReport> {
+    let master_config = ctx.master_config;
+    let op_start_time = Instant::now();
+    let operation = params.operation.resolved(&master_config.application);
+    let (operation_display_name, kind, endpoint) = match &operation {
+        CoordinatedOperation::CaptureImage { .. } => ("Coordinated Image Capture", "image", "capture-image"),
+        CoordinatedOperation::CaptureVideo { .. } => ("Coordinated Video Recording", "video", "capture-video"),
+    };
+    let mut report = OperationReport::new(operation_display_name);
+
+    let coordinator = master_config.application.coordinator.as_ref()
+        .ok_or_else(|| anyhow!("No `coordinator` section in the configuration; list the agents under application.coordinator.agents"))?;
+    let agents: Vec<&RemoteAgent> = coordinator.agents.iter()
+        .filter(|agent| params.agents.as_ref().is_none_or(|selected| selected.contains(&agent.name)))
+        .collect();
+    if let Some(selected) = &params.agents {
+        for name in selected.iter().filter(|name| !coordinator.agents.iter().any(|agent| &agent.name == *name)) {
+            report.push(CameraResult::failure(name.clone(), ErrorKind::Config, "not a configured coordinator agent"));
+        }
+    }
+    if agents.is_empty() {
+        bail!("No coordinator agents selected");
+    }
+    let lead = Duration::from_secs_f32(coordinator.start_lead_secs.unwrap_or(5.0));
+    let request_timeout = Duration::from_secs_f32(coordinator.request_timeout_secs.unwrap_or(30.0));
+    let token = std::env::var(AGENT_TOKEN_ENV).ok().filter(|token| !token.is_empty());
+    let http = reqwest::Client::new();
+    let clients: Vec<AgentClient> = agents.iter().map(|&agent| AgentClient { agent, http: http.clone(), token: token.clone() }).collect();
+
+    // 1. Which agents are up, idle, and have the requested cameras.
+    info!("🛰️ '{}': checking {} agent(s)...", operation_display_name, clients.len());
+    let tolerance = master_config.application.time_sync_tolerance_seconds.unwrap_or(1.0) as f64;
+    let statuses = join_all(clients.iter().map(|client| async move {
+        let sent_at = Utc::now();
+        let status = client.status(request_timeout).await;
+        (status, sent_at + (Utc::now() - sent_at) / 2)
+    })).await;
+    let mut ready = Vec::new();
+    for (client, (status, midpoint)) in clients.iter().zip(statuses) {
+        let name = &client.agent.name;
+        let status = match status {
+            Ok(status) if status.busy => Err(anyhow!("agent is busy with another operation")),
+            other => other,
+        };
+        match status {
+            Ok(status) => {
+                let skew = (status.time - midpoint).num_milliseconds() as f64 / 1000.0;
+                if skew.abs() > tolerance {
+                    warn!("⚠️ Agent '{}' ({}) clock is about {:+.3}s off this host; check NTP on both.", name, status.host, skew);
+                }
+                let cameras: Vec<String> = status.cameras.iter()
+                    .filter(|camera| params.cameras.as_ref().is_none_or(|selected| selected.contains(camera)))
+                    .cloned()
+                    .collect();
+                if cameras.is_empty() {
+                    debug!("Agent '{}' has none of the selected cameras; skipping it.", name);
+                    continue;
+                }
+                info!("  🛰️ {} ({}, rcam {}): {}", name, status.host, status.version, cameras.join(", "));
+                ready.push((client, cameras));
+            }
+            Err(e) => {
+                error!("❌ Agent '{}': {:#}", name, e);
+                report.push(CameraResult::from_error(name.clone(), &e));
+            }
+        }
+    }
+    if let Some(selected) = &params.cameras {
+        for camera in selected.iter().filter(|camera| !ready.iter().any(|(_, cameras)| cameras.contains(camera))) {
+            report.push(CameraResult::failure(camera.clone(), ErrorKind::Network, "not on any reachable agent"));
+        }
+    }
+    report.enforce_require_all(params.require_all)?;
+    if ready.is_empty() {
+        report.log_summary();
+        bail!("No agent is ready to capture");
+    }
+
+    // 2. One start instant for every agent, far enough out for the requests to arrive.
+    let spec = params.start_at.as_deref().or(master_config.application.start_at.as_deref()).unwrap_or("next-second");
+    let sync = sync_start::resolve(&master_config.application, Some(spec), params.start_offset_ms).await?
+        .context("No start instant")?;
+    let start = sync.next_start_after(Utc::now(), lead);
+    let wait = (sync.host_time(start) - Utc::now()).to_std().unwrap_or_default();
+    info!("🕰️ Agents start at {} UTC (in {:?}).", start.to_rfc3339(), wait);
+    if wait.is_zero() {
+        warn!("⚠️ The start instant {} has already passed; agents will start late.", start.to_rfc3339());
+    }
+
+    let base_output_dir = op_helper::determine_operation_output_dir(master_config, params.output_dir.as_deref(), None, operation_display_name)?;
+    let mut session = SessionManifest::create(master_config, &base_output_dir, kind, operation_display_name)?;
+    report.session = Some(session.output_dir.clone());
+    session.event("info", None, format!("start at {} on {}", start.to_rfc3339(), ready.iter().map(|(c, _)| c.agent.name.as_str()).collect::<Vec<_>>().join(", ")));
+
+    // 3. Run it everywhere; each agent answers once its operation has finished.
+    let timeout = wait + operation.run_time(&master_config.application, request_timeout) + request_timeout;
+    let responses = join_all(ready.iter().map(|(client, cameras)| {
+        request(client, endpoint, &operation, cameras.clone(), start, timeout)
+    })).await;
+
+    // 4. Gather the results.
+    let agents_dir = session.output_dir.join("agents");
+    std::fs::create_dir_all(&agents_dir).with_context(|| format!("Failed to create {}", agents_dir.display()))?;
+    for ((client, _), response) in ready.iter().zip(responses) {
+        let name = &client.agent.name;
+        let response = match response.and_then(|r| match r.error.clone() {
+            Some(e) => Err(anyhow!(RcamError::new(r.error_kind.unwrap_or(ErrorKind::Other), e))).context(format!("on {}", r.host)),
+            None => Ok(r),
+        }) {
+            Ok(response) => response,
+            Err(e) => {
+                error!("❌ Agent '{}': {:#}", name, e);
+                session.record_camera(name, None, Vec::new(), Some(format!("{:#}", e)));
+                report.push(CameraResult::from_error(name.clone(), &e));
+                continue;
+            }
+        };
+        for camera in &response.cameras {
+            let qualified = format!("{}/{}", name, camera.name);
+            report.push(match &camera.error {
+                None => CameraResult::success(qualified),
+                Some(e) => CameraResult::failure(qualified, camera.error_kind.unwrap_or(ErrorKind::Other), e),
+            });
+        }
+        if let Some(manifest) = &response.manifest {
+            for camera in &manifest.cameras {
+                // Paths are the agent's; the files stay on that host.
+                let mut camera = camera.clone();
+                camera.name = format!("{}/{}", name, camera.name);
+                session.cameras.push(camera);
+            }
+            for event in &manifest.events {
+                let mut event = event.clone();
+                event.camera = Some(event.camera.map_or_else(|| name.clone(), |camera| format!("{}/{}", name, camera)));
+                session.events.push(event);
+            }
+            let path = agents_dir.join(format!("{}.json", name));
+            let json = serde_json::to_string_pretty(manifest).context("Failed to serialize agent manifest")?;
+            if let Err(e) = std::fs::write(&path, json) {
+                warn!("⚠️ Could not save the manifest of agent '{}' to {}: {}", name, path.display(), e);
+            }
+            info!("  📥 {}: session '{}' on {}", name, manifest.session_id, response.host);
+        }
+    }
+    if let Err(e) = session.finish() {
+        warn!("⚠️ Could not write session manifest: {:#}", e);
+    }
+
+    info!("🛰️ '{}' finished in {:?}.", operation_display_name, op_start_time.elapsed());
+    report.log_summary();
+    report.enforce_require_all(params.require_all)?;
+    Ok(report)
+}
+
+async fn request(
+    client: &AgentClient<'_>,
+    endpoint: &str,
+    operation: &CoordinatedOperation,
+    cameras: Vec<String>,
+    start: DateTime<Utc>,
+    timeout: Duration,
+) -> Result<OperationResponse> {
+    match operation {
+        CoordinatedOperation::CaptureImage { count, interval_ms, retries } => {
+            let body = CaptureImageRequest { cameras: Some(cameras), start_at: Some(start), count: *count, interval_ms: *interval_ms, retries: *retries, stream: None };
+            client.run(endpoint, &body, timeout).await
+        }
+        CoordinatedOperation::CaptureVideo { duration } => {
+            let body = RecordVideoRequest { cameras: Some(cameras), start_at: Some(start), duration_secs: duration.map(|d| d.as_secs()), stream: None };
+            client.run(endpoint, &body, timeout).await
+        }
+    }
+}
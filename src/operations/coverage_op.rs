@@ -0,0 +1,175 @@
+use crate::calibration::coverage::{self, CameraFrustum, CoverageGrid};
+use crate::common::rerun_setup::{self, RerunIdentity, RerunParams};
+use crate::config_loader::{CoverageWorkspace, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use anyhow::{anyhow, bail, Result};
+use crate::cli::CoverageArgs;
+use log::{debug, info, warn};
+use rerun::RecordingStream;
+use std::time::Instant;
+
+/// Typed parameters for a rig coverage analysis.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageParams {
+    pub cameras: Option<Vec<String>>, // None = all configured cameras
+    pub voxel_size_m: Option<f64>,    // overrides coverage_workspace.voxel_size_m
+    pub min_cameras: Option<u32>,     // overrides coverage_workspace.min_cameras
+    pub rerun: RerunParams,
+    pub fail_on_blind_spots: bool,
+}
+
+impl CoverageParams {
+    pub fn from_args(args: &CoverageArgs) -> Self {
+        CoverageParams {
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+            voxel_size_m: args.voxel_size_m,
+            min_cameras: args.min_cameras,
+            rerun: RerunParams::from_args(&args.rerun),
+            fail_on_blind_spots: args.fail_on_blind_spots,
+        }
+    }
+}
+
+pub async fn handle_coverage_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &CoverageArgs,
+) -> Result<()> {
+    let params = CoverageParams::from_args(args);
+    debug!("Coverage CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await.map(|_| ())
+}
+
+/// Computes which parts of `coverage_workspace` are seen by how many cameras, from the
+/// configured extrinsics and fields of view, and optionally visualizes the result in Rerun.
+pub async fn execute(params: &CoverageParams, ctx: &OperationContext<'_>) -> Result<CoverageGrid> {
+    let op_start_time = Instant::now();
+    let operation_display_name = "Rig Coverage Analysis";
+    let app_config = &ctx.master_config.application;
+    info!("🧭 '{}' operation started.", operation_display_name);
+
+    let workspace: CoverageWorkspace = app_config.coverage_workspace
+        .ok_or_else(|| anyhow!("No coverage_workspace configured; add min/max/voxel_size_m under 'application'"))?;
+    let voxel_size_m = params.voxel_size_m.unwrap_or(workspace.voxel_size_m);
+    if !(voxel_size_m.is_finite() && voxel_size_m > 0.0) {
+        bail!("Voxel size must be positive (got {})", voxel_size_m);
+    }
+    let min_cameras = params.min_cameras.or(workspace.min_cameras).unwrap_or(2).max(1) as usize;
+
+    let mut frustums = Vec::new();
+    for camera in &ctx.master_config.cameras {
+        let name = camera.get_name();
+        if let Some(selected) = &params.cameras {
+            if !selected.contains(name) {
+                continue;
+            }
+        }
+        match camera.pose() {
+            Some((extrinsics, field_of_view)) => frustums.push(CameraFrustum::new(name, &extrinsics, &field_of_view)?),
+            None => warn!("⚠️ Camera '{}' has no extrinsics/field_of_view configured; it is left out of the analysis.", name),
+        }
+    }
+    if let Some(selected) = &params.cameras {
+        for name in selected {
+            if !ctx.master_config.cameras.iter().any(|c| c.get_name() == name) {
+                warn!("⚠️ Camera '{}' is not in the configuration.", name);
+            }
+        }
+    }
+    if frustums.is_empty() {
+        bail!("No selected camera has calibrated extrinsics and a field_of_view; nothing to analyze");
+    }
+
+    let frustums_clone = frustums.clone();
+    let grid = tokio::task::spawn_blocking(move || coverage::compute(&workspace, voxel_size_m, min_cameras, &frustums_clone))
+        .await
+        .map_err(|e| anyhow!("Coverage task panicked: {}", e))??;
+
+    let total = grid.centers.len();
+    let percent = |n: usize| 100.0 * n as f64 / total as f64;
+    info!(
+        "🧭 {} voxels of {} m from {} camera(s): {:.1}% covered by >= {} camera(s), {:.1}% under-covered, {:.1}% blind.",
+        total, voxel_size_m, frustums.len(), percent(grid.covered()), min_cameras, percent(grid.under_covered()), percent(grid.blind())
+    );
+    for (index, frustum) in frustums.iter().enumerate() {
+        let seen = grid.seen_by.iter().filter(|s| s.contains(&index)).count();
+        info!(
+            "  📷 {}: sees {:.1}% of the workspace; {:.1}% would drop below {} camera(s) without it.",
+            frustum.name, percent(seen), percent(grid.critical_for(index)), min_cameras
+        );
+    }
+    if let Some((min, max)) = grid.blind_bounds() {
+        warn!(
+            "⚠️ Blind spots span [{:.2}, {:.2}, {:.2}] .. [{:.2}, {:.2}, {:.2}] m (rig frame).",
+            min[0], min[1], min[2], max[0], max[1], max[2]
+        );
+    }
+
+    if params.rerun.enabled {
+        let identity = RerunIdentity::resolve(app_config, &params.rerun, "rcam_coverage");
+        if let Some(rec_stream) = rerun_setup::init_recording_stream(app_config, &identity, operation_display_name) {
+            log_to_rerun(&rec_stream, &workspace, voxel_size_m, &frustums, &grid);
+            info!("Rerun: Attempting to flush all logged data...");
+            rec_stream.flush_blocking();
+        }
+    }
+
+    info!("✅ '{}' finished in {:?}.", operation_display_name, op_start_time.elapsed());
+    if params.fail_on_blind_spots && grid.blind() > 0 {
+        bail!("{} of {} workspace voxels are not seen by any camera", grid.blind(), total);
+    }
+    Ok(grid)
+}
+
+/// Logs the workspace box, each camera frustum and the voxels split by coverage class,
+/// so blind spots can be toggled on their own in the viewer.
+fn log_to_rerun(rec: &RecordingStream, workspace: &CoverageWorkspace, voxel_size_m: f64, frustums: &[CameraFrustum], grid: &CoverageGrid) {
+    let to_f32 = |v: [f64; 3]| [v[0] as f32, v[1] as f32, v[2] as f32];
+    let size = [workspace.max[0] - workspace.min[0], workspace.max[1] - workspace.min[1], workspace.max[2] - workspace.min[2]];
+    let mut log_errors = Vec::new();
+
+    if let Err(e) = rec.log_static("coverage/workspace", &rerun::Boxes3D::from_mins_and_sizes([to_f32(workspace.min)], [to_f32(size)])) {
+        log_errors.push(e);
+    }
+
+    for frustum in frustums {
+        let entity = format!("rig/{}", frustum.name);
+        let rotation = frustum.rotation;
+        let transform = rerun::Transform3D::from_translation_rotation(
+            to_f32(frustum.translation),
+            rerun::Quaternion::from_xyzw([rotation[0] as f32, rotation[1] as f32, rotation[2] as f32, rotation[3] as f32]),
+        );
+        let aspect = ((frustum.horizontal_deg.to_radians() / 2.0).tan() / (frustum.vertical_deg.to_radians() / 2.0).tan()) as f32;
+        let pinhole = rerun::Pinhole::from_fov_and_aspect_ratio(frustum.vertical_deg.to_radians() as f32, aspect)
+            .with_image_plane_distance(frustum.max_range_m.min(2.0) as f32);
+        for result in [rec.log_static(entity.as_str(), &transform), rec.log_static(entity.as_str(), &pinhole)] {
+            if let Err(e) = result {
+                log_errors.push(e);
+            }
+        }
+    }
+
+    let classes: [(&str, [u8; 3], Box<dyn Fn(usize) -> bool>); 3] = [
+        ("coverage/covered", [60, 200, 80], Box::new(|n| n >= grid.min_cameras)),
+        ("coverage/under_covered", [240, 190, 40], Box::new(|n| n > 0 && n < grid.min_cameras)),
+        ("coverage/blind_spots", [230, 40, 40], Box::new(|n| n == 0)),
+    ];
+    for (entity, color, matches) in classes {
+        let positions: Vec<[f32; 3]> = (0..grid.centers.len())
+            .filter(|&i| matches(grid.count(i)))
+            .map(|i| to_f32(grid.centers[i]))
+            .collect();
+        let points = rerun::Points3D::new(positions)
+            .with_colors([rerun::Color::from_rgb(color[0], color[1], color[2])])
+            .with_radii([(voxel_size_m * 0.35) as f32]);
+        if let Err(e) = rec.log_static(entity, &points) {
+            log_errors.push(e);
+        }
+    }
+
+    for e in log_errors {
+        warn!("Rerun: Failed to log coverage data: {}", e);
+    }
+}
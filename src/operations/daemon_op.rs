@@ -0,0 +1,155 @@
+use crate::cli::DaemonArgs;
+use crate::common::schedule::CameraSchedule;
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::core::source_registry::SharedCaptureSource;
+use crate::operations::image_capture_op::{self, CaptureImageParams};
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use anyhow::{bail, Context, Result};
+use chrono::{Local, Timelike};
+use log::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Typed parameters for daemon mode.
+#[derive(Debug, Clone, Default)]
+pub struct DaemonParams {
+    pub cameras: Option<Vec<String>>, // None = every camera with a schedule
+    pub keep_open: bool,              // keep sources open (RealSense pipelines streaming) between snapshots
+}
+
+impl DaemonParams {
+    pub fn from_args(args: &DaemonArgs) -> Self {
+        DaemonParams { cameras: op_helper::parse_camera_selection(args.cameras.as_ref()), keep_open: !args.cold }
+    }
+}
+
+pub async fn handle_daemon_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &DaemonArgs,
+) -> Result<()> {
+    let params = DaemonParams::from_args(args);
+    debug!("Daemon CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+/// Runs until cancelled, taking a snapshot from each camera whenever its `schedule` is due.
+/// Every camera's schedule is its own loop under the camera manager's supervisor, checked once
+/// per minute at the start of the minute. A failed capture is logged and the loop keeps going;
+/// a loop that panics is restarted by the `supervisor` policy without holding up the others.
+/// With `keep_open`, each camera is opened when its loop starts and closed when the daemon stops.
+pub async fn execute(params: &DaemonParams, ctx: &OperationContext<'_>) -> Result<()> {
+    let app_config = &ctx.master_config.application;
+    let location = app_config.latitude.zip(app_config.longitude);
+
+    let mut schedules = Vec::new();
+    for camera in &ctx.master_config.cameras {
+        let name = camera.get_name();
+        if params.cameras.as_ref().is_some_and(|selected| !selected.contains(name)) {
+            continue;
+        }
+        match camera.schedule() {
+            Some(config) => {
+                let schedule = CameraSchedule::parse(config).with_context(|| format!("Invalid schedule for camera '{}'", name))?;
+                schedules.push((name.clone(), schedule));
+            }
+            None if params.cameras.is_some() => warn!("⚠️ Camera '{}' has no schedule; the daemon never captures it.", name),
+            None => {}
+        }
+    }
+    if schedules.is_empty() {
+        bail!("No selected camera has a schedule; add `schedule:` to the cameras the daemon should capture");
+    }
+    info!("⏰ Daemon started for {} scheduled camera(s): {}.", schedules.len(),
+        schedules.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", "));
+
+    // The loops outlive this borrow of the context; clones share the same devices.
+    let master_config = Arc::new(ctx.master_config.clone());
+    let camera_manager = Arc::new(ctx.camera_manager.clone());
+    let supervisor = ctx.camera_manager.supervisor().scoped();
+    for (name, schedule) in schedules {
+        let kept_open = if params.keep_open { ctx.camera_manager.get_devices_by_names(std::slice::from_ref(&name)).await.pop() } else { None };
+        let camera = Arc::new(ScheduleLoop {
+            name: name.clone(),
+            schedule,
+            location,
+            kept_open,
+            master_config: master_config.clone(),
+            camera_manager: camera_manager.clone(),
+        });
+        supervisor.spawn_with_policy(&name, "schedule", supervisor.default_policy(), move |cancel| {
+            let camera = camera.clone();
+            async move { camera.run(cancel).await }
+        });
+    }
+    supervisor.wait(&ctx.cancel).await;
+    info!("🛑 Daemon stopped.");
+    Ok(())
+}
+
+/// One camera's schedule, run by the supervisor until the daemon stops.
+struct ScheduleLoop {
+    name: String,
+    schedule: CameraSchedule,
+    location: Option<(f64, f64)>,
+    kept_open: Option<SharedCaptureSource>, // Some with keep_open
+    master_config: Arc<MasterConfig>,
+    camera_manager: Arc<CameraManager>,
+}
+
+impl ScheduleLoop {
+    async fn run(&self, cancel: CancellationToken) -> Result<()> {
+        let ctx = OperationContext { master_config: &self.master_config, camera_manager: &self.camera_manager, cancel: cancel.clone() };
+        self.open_source(true).await;
+        loop {
+            let now = Local::now();
+            let into_minute = Duration::from_secs(now.second() as u64) + Duration::from_nanos(now.nanosecond().min(999_999_999) as u64);
+            let until_next_minute = Duration::from_secs(60).saturating_sub(into_minute);
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(until_next_minute) => {}
+            }
+
+            // A little past the boundary; the minute we woke in is the one that is due.
+            let at = Local::now();
+            if !self.schedule.is_due(at, self.location) {
+                continue;
+            }
+            info!("⏰ {} snapshot due for '{}'.", at.format("%H:%M"), self.name);
+            // A source that failed to open (e.g. unplugged) is tried again; captures work either way.
+            self.open_source(false).await;
+            let capture = CaptureImageParams { cameras: Some(vec![self.name.clone()]), stream_key: Some("daemon"), ..Default::default() };
+            match image_capture_op::execute(&capture, &ctx).await {
+                Ok(report) if report.failed() > 0 => warn!("⚠️ Scheduled capture of '{}' failed.", self.name),
+                Ok(_) => {}
+                Err(e) => error!("❌ Scheduled capture of '{}' failed: {:#}", self.name, e),
+            }
+        }
+
+        if let Some(device) = &self.kept_open {
+            if let Err(e) = device.lock().await.close().await {
+                warn!("⚠️ Failed to close '{}': {:#}", self.name, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens the kept-open source unless it is open already. Failures are only warned about on the first pass.
+    async fn open_source(&self, first: bool) {
+        let Some(device) = &self.kept_open else {
+            return;
+        };
+        let mut device = device.lock().await;
+        if device.is_open() {
+            return;
+        }
+        match device.open().await {
+            Ok(()) => {}
+            Err(e) if first => warn!("⚠️ Could not open '{}' ({:#}); its snapshots set up their own capture until it opens.", self.name, e),
+            Err(e) => debug!("Still could not open '{}': {:#}", self.name, e),
+        }
+    }
+}
@@ -1,50 +1,202 @@
-use crate::config_loader::MasterConfig;
+use crate::camera::camera_controller::CameraController;
+use crate::camera::imaging::DayNightMode;
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::net_probe;
+use crate::camera::realsense_device::RealsenseDevice;
+use crate::common::atomic_file;
+use crate::common::environment::EnvironmentInfo;
+use crate::config_loader::{CameraTimeouts, CaptureDeviceConfig, MasterConfig};
 use crate::core::camera_manager::CameraManager;
-use anyhow::{Result, Context};
-use clap::ArgMatches;
+use anyhow::{bail, Result, Context};
+use chrono::{DateTime, Local, Utc};
+use crate::cli::DiagnosticArgs;
+use futures::future::join_all;
 use log::{info, warn, error, debug};
-use std::path::PathBuf;
-use std::time::Instant;
-use crate::config_loader::AppSettings;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-// Import operation handlers
-use crate::camera::camera_media::CameraMediaManager; 
-use super::time_sync_op;
+use super::image_capture_op::{self, CaptureImageParams};
+use super::op_context::OperationContext;
+use super::op_helper;
+use super::op_result::OperationReport;
+use super::report_op;
+use super::soak_op::{self, SoakParams};
+use super::time_sync_op::{self, VerifyTimesParams};
+use super::video_record_op::{self, RecordVideoParams};
 
-struct DiagnosticResult {
-    test_name: String,
-    success: bool,
-    details: String,
+/// Length of the per-camera video test.
+const DIAGNOSTIC_VIDEO_SECS: u64 = 5;
+/// How long each hardware-synced RealSense camera streams for the sync test.
+const DIAGNOSTIC_SYNC_SECS: u64 = 3;
+/// Largest median offset to the reference camera's frames, and spread around it, that still
+/// counts as synced. Unsynced D4xx units at 30 fps are typically several milliseconds apart.
+const SYNC_TOLERANCE_MS: f64 = 2.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticResult {
+    pub test_name: String,
+    pub success: bool,
+    pub details: String,
+}
+
+/// Everything a `rcam test` run found, written next to its outputs as JSON and HTML.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub passed: bool,
+    pub environment: EnvironmentInfo,
+    pub results: Vec<DiagnosticResult>,
+}
+
+/// One part of the suite, selectable with `--tests`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticTest {
+    Time,
+    Network,
+    DayNight,
+    Image,
+    Video,
+    Sync,
+    Tasks,
+}
+
+impl DiagnosticTest {
+    pub const ALL: [DiagnosticTest; 7] = [
+        DiagnosticTest::Time,
+        DiagnosticTest::Network,
+        DiagnosticTest::DayNight,
+        DiagnosticTest::Image,
+        DiagnosticTest::Video,
+        DiagnosticTest::Sync,
+        DiagnosticTest::Tasks,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiagnosticTest::Time => "time",
+            DiagnosticTest::Network => "network",
+            DiagnosticTest::DayNight => "daynight",
+            DiagnosticTest::Image => "image",
+            DiagnosticTest::Video => "video",
+            DiagnosticTest::Sync => "sync",
+            DiagnosticTest::Tasks => "tasks",
+        }
+    }
+
+    /// Parses a `--tests` value; None (or "all") selects every test.
+    pub fn parse_list(value: Option<&str>) -> Result<Vec<Self>> {
+        match value.filter(|t| !t.trim().eq_ignore_ascii_case("all")) {
+            Some(list) => list.split(',').filter(|t| !t.trim().is_empty()).map(DiagnosticTest::parse).collect(),
+            None => Ok(DiagnosticTest::ALL.to_vec()),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim().to_lowercase();
+        match DiagnosticTest::ALL.iter().find(|test| test.name() == value) {
+            Some(test) => Ok(*test),
+            None => bail!("Unknown diagnostic test '{}'; expected one of: {}", value,
+                DiagnosticTest::ALL.iter().map(|t| t.name()).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+/// Typed parameters for the diagnostic suite.
+#[derive(Debug, Clone)]
+pub struct DiagnosticParams {
+    pub tests: Vec<DiagnosticTest>,   // which parts of the suite to run
+    pub cameras: Option<Vec<String>>, // None = every configured camera
+}
+
+impl Default for DiagnosticParams {
+    fn default() -> Self {
+        DiagnosticParams { tests: DiagnosticTest::ALL.to_vec(), cameras: None }
+    }
+}
+
+impl DiagnosticParams {
+    pub fn from_args(args: &DiagnosticArgs) -> Result<Self> {
+        let tests = DiagnosticTest::parse_list(args.tests.as_deref())?;
+        Ok(DiagnosticParams { tests, cameras: op_helper::parse_camera_selection(args.cameras.as_ref()) })
+    }
+
+    fn runs(&self, test: DiagnosticTest) -> bool {
+        self.tests.contains(&test)
+    }
+
+    fn includes(&self, camera: &str) -> bool {
+        self.cameras.as_ref().is_none_or(|selected| selected.iter().any(|name| name == camera))
+    }
 }
 
 pub async fn handle_diagnostic_cli(
     master_config: &MasterConfig,
     camera_manager: &CameraManager,
-    _args: &ArgMatches, // CLI args for diagnostics, if any are added later
+    args: &DiagnosticArgs,
 ) -> Result<()> {
+    let ctx = OperationContext::new(master_config, camera_manager);
+    if args.soak.is_some() {
+        let params = SoakParams::from_args(args)?;
+        debug!("Soak CLI parsed into: {:?}", params);
+        let report = soak_op::execute(&params, &ctx).await?;
+        if !report.passed {
+            bail!("Soak test failed: {} of {} round(s) had failures{}", report.failed_rounds, report.rounds,
+                if report.leak_suspected { " and memory kept growing" } else { "" });
+        }
+        return Ok(());
+    }
+    let params = DiagnosticParams::from_args(args)?;
+    debug!("Diagnostic CLI parsed into: {:?}", params);
+    let report = execute(&params, &ctx).await?;
+    if !report.passed {
+        let failed = report.results.iter().filter(|r| !r.success).count();
+        bail!("{} of {} diagnostic test(s) failed", failed, report.results.len());
+    }
+    Ok(())
+}
+
+/// Runs the suite and writes `diagnostic_report_<timestamp>.json` and `.html` into the
+/// diagnostics output directory. Test failures are in the report, not the returned error.
+pub async fn execute(params: &DiagnosticParams, ctx: &OperationContext<'_>) -> Result<DiagnosticReport> {
+    let master_config = ctx.master_config;
+    let camera_manager = ctx.camera_manager;
     let overall_diag_start_time = Instant::now();
-    info!("🩺 Starting diagnostic test suite...");
+    let started_at = Utc::now();
+    info!("🩺 Starting diagnostic test suite ({})...", params.tests.iter().map(|t| t.name()).collect::<Vec<_>>().join(", "));
+    let environment = EnvironmentInfo::collect(&master_config.application).await;
+    for (name, value) in environment.rows() {
+        info!("  ℹ️ {:<22} {}", name, value);
+    }
     let mut results: Vec<DiagnosticResult> = Vec::new();
 
-    // 1. Test time synchronization for all cameras
-    info!("  DIAGNOSTIC [Global]: Running time synchronization test... ⏱️");
-    let time_sync_test_start = Instant::now();
-    match time_sync_op::handle_verify_times_cli(master_config, camera_manager, _args).await {
-        Ok(_) => {
-            info!("    DIAGNOSTIC [Global]: Time Synchronization test completed in {:?}. Check logs for details.", time_sync_test_start.elapsed());
-            results.push(DiagnosticResult {
-                test_name: "Time Synchronization (All Cameras)".to_string(),
-                success: true,
-                details: "Completed. Check logs for sync status.".to_string(),
-            });
-        },
-        Err(e) => {
-            error!("    DIAGNOSTIC [Global]: Time Synchronization test FAILED in {:?}: {:#}", time_sync_test_start.elapsed(), e);
-            results.push(DiagnosticResult {
-                test_name: "Time Synchronization (All Cameras)".to_string(),
-                success: false,
-                details: format!("Failed: {:#}", e),
-            });
+    // 1. Test time synchronization for the selected cameras
+    if params.runs(DiagnosticTest::Time) {
+        let time_test_name = match &params.cameras {
+            Some(selected) => format!("Time Synchronization ({})", selected.join(", ")),
+            None => "Time Synchronization (All Cameras)".to_string(),
+        };
+        info!("  DIAGNOSTIC [Global]: Running time synchronization test... ⏱️");
+        let time_sync_test_start = Instant::now();
+        match time_sync_op::execute(&VerifyTimesParams { cameras: params.cameras.clone() }, ctx).await {
+            Ok(_) => {
+                info!("    DIAGNOSTIC [Global]: Time Synchronization test completed in {:?}. Check logs for details.", time_sync_test_start.elapsed());
+                results.push(DiagnosticResult {
+                    test_name: time_test_name.clone(),
+                    success: true,
+                    details: "Completed. Check logs for sync status.".to_string(),
+                });
+            },
+            Err(e) => {
+                error!("    DIAGNOSTIC [Global]: Time Synchronization test FAILED in {:?}: {:#}", time_sync_test_start.elapsed(), e);
+                results.push(DiagnosticResult {
+                    test_name: time_test_name.clone(),
+                    success: false,
+                    details: format!("Failed: {:#}", e),
+                });
+            }
         }
     }
 
@@ -64,157 +216,107 @@ pub async fn handle_diagnostic_cli(
     info!("💾 Diagnostic outputs will be saved to: {}", diagnostic_output_dir.display());
 
     let cameras_fetch_start = Instant::now();
-    let all_cameras = camera_manager.get_all_devices().await;
+    let mut all_cameras = Vec::new();
+    for cam_arc in camera_manager.get_all_devices().await {
+        let (cam_name, cam_type) = {
+            let locked_device = cam_arc.lock().await;
+            (locked_device.get_name(), locked_device.get_type())
+        };
+        if params.includes(&cam_name) {
+            all_cameras.push((cam_name, cam_type));
+        }
+    }
     debug!("Fetched {} cameras for per-camera diagnostics in {:?}.", all_cameras.len(), cameras_fetch_start.elapsed());
+    if let Some(selected) = &params.cameras {
+        for name in selected.iter().filter(|name| !all_cameras.iter().any(|(cam_name, _)| cam_name == *name)) {
+            warn!("⚠️ DIAGNOSTIC: '{}' is not a configured camera.", name);
+        }
+    }
 
     if all_cameras.is_empty() {
-        warn!("⚠️ DIAGNOSTIC: No cameras configured. Skipping per-camera tests.");
+        warn!("⚠️ DIAGNOSTIC: No cameras selected. Skipping per-camera tests.");
     }
 
-    for cam_arc in &all_cameras {
-        let cam_entity_lock_start = Instant::now();
-        let locked_device = cam_arc.lock().await;
-        let cam_name = locked_device.get_name();
-        let cam_type = locked_device.get_type();
-        debug!("  Locked camera entity for '{}' for diagnostics in {:?}.", cam_name, cam_entity_lock_start.elapsed());
+    for (cam_name, cam_type) in &all_cameras {
+        let (cam_name, cam_type) = (cam_name.clone(), cam_type.as_str());
         info!("  DIAGNOSTIC [{}]: Running tests...", cam_name);
+        // Each test writes into its own session directory under diagnostics/<camera>/.
+        let camera_diag_dir = diagnostic_output_dir.join(&cam_name);
 
-        // Define video_duration_secs here so it's in scope for the else block log message
-        let video_duration_secs: u64 = 5; // 5 second video for diagnostics
-
-        // Check if it's an IP Camera for tests requiring RTSP URLs (image/video capture via CameraMediaManager)
-        if cam_type == "ip-camera" {
-            info!("    DIAGNOSTIC [{}]: Is IP Camera. Image/video tests will proceed (may require IpCameraDevice.get_rtsp_url()).", cam_name);
-
-            // 2. Test single image capture per camera
-            let img_diag_dir_create_start = Instant::now();
-            let image_diag_output_dir = diagnostic_output_dir.join(&cam_name).join("image");
-            if let Err(e) = std::fs::create_dir_all(&image_diag_output_dir)
-                .with_context(|| format!("Failed to create image diagnostic dir for {}: {}", cam_name, image_diag_output_dir.display())) {
-                error!("❌ Could not create image diagnostic directory for '{}' ({}): {:#}. Image test may fail to save.", cam_name, image_diag_output_dir.display(), e);
-            } else {
-                debug!("  Ensured image diagnostic directory for '{}' exists ({}) in {:?}.", cam_name, image_diag_output_dir.display(), img_diag_dir_create_start.elapsed());
-            }        
+        if cam_type == "ip-camera" && params.runs(DiagnosticTest::Network) {
+            info!("    DIAGNOSTIC [{}]: Probing network and service ports... 🌐", cam_name);
+            results.push(check_network(master_config, &cam_name).await);
+        }
+        if cam_type == "ip-camera" && params.runs(DiagnosticTest::DayNight) {
+            info!("    DIAGNOSTIC [{}]: Reading day/night mode... 🌗", cam_name);
+            results.push(check_day_night(master_config, &cam_name).await);
+        }
+
+        // 2. Test single image capture per camera, through the same path as `rcam capture-image`.
+        if params.runs(DiagnosticTest::Image) {
             info!("    DIAGNOSTIC [{}]: Running image capture test... 🖼️", cam_name);
             let img_test_start = Instant::now();
-            let media_manager_img = CameraMediaManager::new();
-            let app_config_img_clone: AppSettings = master_config.application.clone();
-            
-            let image_capture_future = async {
-                let _cam_name_for_closure = cam_name.clone();
-                let _app_config_for_closure = app_config_img_clone.clone();
-                let _output_dir_for_closure = image_diag_output_dir.clone();
-
-                warn!("DIAGNOSTIC [{}]: Image test RTSP URL retrieval logic pending IpCameraDevice method.", cam_name);
-                let cameras_info_for_img_capture: Vec<(String, String)> = Vec::new();
-                media_manager_img.capture_image(&cameras_info_for_img_capture, &app_config_img_clone, image_diag_output_dir.clone()).await
+            let image_params = CaptureImageParams {
+                cameras: Some(vec![cam_name.clone()]),
+                output_dir: Some(camera_diag_dir.join("image")),
+                retries: Some(0), // report the camera's first attempt
+                stream_key: Some("test"),
+                ..Default::default()
             };
-            
-            match image_capture_future.await {
-                Ok(paths) => {
-                    if let Some(path) = paths.first() {
-                        info!("    DIAGNOSTIC [{}]: Image Capture test PASSED in {:?}. Image: {}", cam_name, img_test_start.elapsed(), path.display());
-                        results.push(DiagnosticResult {
-                            test_name: format!("Image Capture ('{}')", cam_name),
-                            success: true,
-                            details: format!("Completed. Image saved in {}", path.display()),
-                        });
-                    } else {
-                        error!("    DIAGNOSTIC [{}]: Image Capture test did not produce a file, though the operation succeeded, in {:?}.", cam_name, img_test_start.elapsed());
-                        results.push(DiagnosticResult {
-                            test_name: format!("Image Capture ('{}')", cam_name),
-                            success: false,
-                            details: "Operation succeeded but no image file was created.".to_string(),
-                        });
-                    }
-                },
-                Err(e) => {
-                    error!("    DIAGNOSTIC [{}]: Image Capture test FAILED in {:?}: {:#}", cam_name, img_test_start.elapsed(), e);
-                    results.push(DiagnosticResult {
-                        test_name: format!("Image Capture ('{}')", cam_name),
-                        success: false,
-                        details: format!("Failed: {:#}", e),
-                    });
-                },
-            }
+            let image_outcome = image_capture_op::execute(&image_params, ctx).await;
+            results.push(report_result(format!("Image Capture ('{}')", cam_name), &cam_name, image_outcome, img_test_start));
+        }
 
-            // 3. Test short video capture per camera
-            let vid_diag_dir_create_start = Instant::now();
-            let video_diag_output_dir = diagnostic_output_dir.join(&cam_name).join("video");
-            if let Err(e) = std::fs::create_dir_all(&video_diag_output_dir)
-                .with_context(|| format!("Failed to create video diagnostic dir for {}: {}", cam_name, video_diag_output_dir.display())) {
-                error!("❌ Could not create video diagnostic directory for '{}' ({}): {:#}. Video test may fail to save.", cam_name, video_diag_output_dir.display(), e);
-            } else {
-                 debug!("  Ensured video diagnostic directory for '{}' exists ({}) in {:?}.", cam_name, video_diag_output_dir.display(), vid_diag_dir_create_start.elapsed());
-            }
-            info!("    DIAGNOSTIC [{}]: Running short video capture test ({}s)... 📹", cam_name, video_duration_secs);
+        // 3. Test short video capture per camera, through the same path as `rcam capture-video`.
+        let video_test_name = format!("Video Record ('{}', {}s)", cam_name, DIAGNOSTIC_VIDEO_SECS);
+        if !params.runs(DiagnosticTest::Video) {
+            debug!("    DIAGNOSTIC [{}]: Video test not selected.", cam_name);
+        } else if matches!(cam_type, "ip-camera" | "virtual-camera") {
+            info!("    DIAGNOSTIC [{}]: Running short video capture test ({}s)... 📹", cam_name, DIAGNOSTIC_VIDEO_SECS);
             let vid_test_start = Instant::now();
-            let media_manager_vid = CameraMediaManager::new();
-            let app_config_vid_clone: AppSettings = master_config.application.clone();
-
-            let video_record_future = async {
-                let _cam_name_for_closure = cam_name.clone();
-                let _app_config_for_closure = app_config_vid_clone.clone();
-                let _output_dir_for_closure = video_diag_output_dir.clone();
-
-                warn!("DIAGNOSTIC [{}]: Video test RTSP URL retrieval logic pending IpCameraDevice method.", cam_name);
-                let cameras_info_for_sync: Vec<(String, String)> = Vec::new();
-                let recording_duration = std::time::Duration::from_secs(video_duration_secs);
-
-                media_manager_vid.record_video(
-                    &cameras_info_for_sync,
-                    &app_config_vid_clone, 
-                    video_diag_output_dir.clone(), 
-                    recording_duration
-                ).await
+            let video_params = RecordVideoParams {
+                cameras: Some(vec![cam_name.clone()]),
+                duration: Some(Duration::from_secs(DIAGNOSTIC_VIDEO_SECS)),
+                output_dir: Some(camera_diag_dir.join("video")),
+                stream_key: Some("test"),
+                ..Default::default()
             };
-
-            match video_record_future.await {
-                Ok(paths) => {
-                    if let Some(path) = paths.first() {
-                        info!("    DIAGNOSTIC [{}]: Video Record test ({}s) PASSED in {:?}. Video: {}", cam_name, video_duration_secs, vid_test_start.elapsed(), path.display());
-                        results.push(DiagnosticResult {
-                            test_name: format!("Video Record ('{}', {}s)", cam_name, video_duration_secs),
-                            success: true,
-                            details: format!("Completed. Video saved in {}", path.display()),
-                        });
-                    } else {
-                        error!("    DIAGNOSTIC [{}]: Video Record test ({}s) did not produce a file, though the operation succeeded, in {:?}.", cam_name, video_duration_secs, vid_test_start.elapsed());
-                        results.push(DiagnosticResult {
-                            test_name: format!("Video Record ('{}', {}s)", cam_name, video_duration_secs),
-                            success: false,
-                            details: "Operation succeeded but no video file was created.".to_string(),
-                        });
-                    }
-                },
-                Err(e) => {
-                    error!("    DIAGNOSTIC [{}]: Video Record test ({}s) FAILED in {:?}: {:#}", cam_name, video_duration_secs, vid_test_start.elapsed(), e);
-                    results.push(DiagnosticResult {
-                        test_name: format!("Video Record ('{}', {}s)", cam_name, video_duration_secs),
-                        success: false,
-                        details: format!("Failed: {:#}", e),
-                    });
-                },
-            }
+            let video_outcome = video_record_op::execute(&video_params, ctx).await;
+            results.push(report_result(video_test_name, &cam_name, video_outcome, vid_test_start));
         } else {
-            info!("    DIAGNOSTIC [{}]: Is {} device. Skipping IP camera specific tests (image/video capture via RTSP).", cam_name, cam_type);
+            info!("    DIAGNOSTIC [{}]: Is {} device. Skipping video test (IP and virtual cameras only).", cam_name, cam_type);
             results.push(DiagnosticResult {
-                test_name: format!("Image Capture ('{}')", cam_name),
+                test_name: video_test_name,
                 success: true,
-                details: "Skipped (not an IP camera type for this test).".to_string(),
-            });
-            results.push(DiagnosticResult {
-                test_name: format!("Video Record ('{}', {}s)", cam_name, video_duration_secs),
-                success: true,
-                details: "Skipped (not an IP camera type for this test).".to_string(),
+                details: "Skipped (the video test covers IP and virtual cameras).".to_string(),
             });
         }
+
         info!("  DIAGNOSTIC [{}]: Finished all tests for this camera.", cam_name);
     }
 
+    // 4. Frame timestamps of RealSense cameras on a sync cable, streaming together.
+    if params.runs(DiagnosticTest::Sync) {
+        results.extend(check_realsense_sync(master_config, params).await);
+    }
+
+    // 5. Long-lived tasks an embedding program runs under the supervisor.
+    let task_statuses = if params.runs(DiagnosticTest::Tasks) { camera_manager.supervisor().status() } else { Vec::new() };
+    for status in task_statuses.into_iter().filter(|status| params.includes(&status.camera)) {
+        let details = match &status.last_error {
+            Some(error) => format!("{}, {} restart(s), last error: {}", status.state, status.restarts, error),
+            None => format!("{}, {} restart(s)", status.state, status.restarts),
+        };
+        results.push(DiagnosticResult {
+            test_name: format!("Task '{}' ('{}')", status.task, status.camera),
+            success: status.is_healthy(),
+            details,
+        });
+    }
+
     info!("\n\n📋 ----- Diagnostic Test Summary (Total Suite Time: {:?}) -----", overall_diag_start_time.elapsed());
     let mut overall_success = true;
-    for result in results {
+    for result in &results {
         let status_emoji = if result.success { "✅ PASS" } else { "❌ FAIL" };
         info!("Test: {:<40} | Status: {:<10} | Details: {}", result.test_name, status_emoji, result.details);
         if !result.success {
@@ -227,6 +329,240 @@ pub async fn handle_diagnostic_cli(
     } else {
         error!("🔥 One or more critical diagnostic tests failed. Please review logs above.");
     }
+
+    let report = DiagnosticReport { started_at, finished_at: Utc::now(), passed: overall_success, environment, results };
+    match write_report(&report, &diagnostic_output_dir) {
+        Ok((json_path, html_path)) => info!("📄 Diagnostic report written to {} and {}", json_path.display(), html_path.display()),
+        Err(e) => error!("❌ Failed to write the diagnostic report: {:#}", e),
+    }
     info!("🏁 Diagnostic test suite finished in {:?}.", overall_diag_start_time.elapsed());
-    Ok(())
-} 
\ No newline at end of file
+    Ok(report)
+}
+
+fn write_report(report: &DiagnosticReport, dir: &Path) -> Result<(PathBuf, PathBuf)> {
+    let stem = format!("diagnostic_report_{}", DateTime::<Local>::from(report.started_at).format("%Y%m%d_%H%M%S"));
+    let json = serde_json::to_vec_pretty(report).context("Failed to serialize the diagnostic report")?;
+    let json_path = atomic_file::write_bytes(&dir.join(format!("{}.json", stem)), &json)?;
+    let html_path = atomic_file::write_bytes(&dir.join(format!("{}.html", stem)), render_html(report).as_bytes())?;
+    Ok((json_path, html_path))
+}
+
+fn render_html(report: &DiagnosticReport) -> String {
+    let failed = report.results.iter().filter(|r| !r.success).count();
+    let mut html = String::new();
+    let _ = write!(html, r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>rcam diagnostics {start}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}
+.ok {{ color: #1a7f37; }} .failed {{ color: #c62828; }}
+</style></head><body>
+<h1>Diagnostics on {host}</h1>
+<p>{start} &rarr; {end} ({secs:.1} s)</p>
+<p class="{class}"><b>{passed}</b> of {total} test(s) passed, <b>{failed}</b> failed.</p>
+<h2>Environment</h2>
+<table>
+"#,
+        host = report_op::escape(&report.environment.hostname),
+        start = report_op::local(report.started_at),
+        end = report_op::local(report.finished_at),
+        secs = (report.finished_at - report.started_at).num_milliseconds() as f64 / 1000.0,
+        class = if report.passed { "ok" } else { "failed" },
+        passed = report.results.len() - failed,
+        total = report.results.len(),
+        failed = failed,
+    );
+    for (name, value) in report.environment.rows() {
+        let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, report_op::escape(&value));
+    }
+    html.push_str("</table>\n<h2>Tests</h2>\n<table><tr><th>Test</th><th>Status</th><th>Details</th></tr>\n");
+    for result in &report.results {
+        let (class, status) = if result.success { ("ok", "PASS") } else { ("failed", "FAIL") };
+        let _ = writeln!(html, "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>",
+            report_op::escape(&result.test_name), class, status, report_op::escape(&result.details));
+    }
+    html.push_str("</table>\n</body></html>\n");
+    html
+}
+
+/// Checks basic connectivity before anything camera-specific: name resolution, ping round
+/// trips, and TCP connects to the RTSP, HTTP(S) and ONVIF ports. Fails when a port the camera
+/// needs is closed; an unanswered ping is only reported, as ICMP is often filtered.
+async fn check_network(master_config: &MasterConfig, cam_name: &str) -> DiagnosticResult {
+    let test_name = format!("Network ('{}')", cam_name);
+    let specifics = master_config.cameras.iter().find_map(|cfg| match cfg {
+        CaptureDeviceConfig::IpCamera { name, specifics, .. } if name == cam_name => Some(specifics.clone()),
+        _ => None,
+    });
+    let Some(specifics) = specifics else {
+        return DiagnosticResult { test_name, success: false, details: "No IP camera configuration found.".to_string() };
+    };
+    let timeouts = CameraTimeouts::resolve(&master_config.application, Some(&specifics));
+    let device = match IpCameraDevice::new(cam_name.to_string(), specifics.clone(), timeouts) {
+        Ok(device) => device,
+        Err(e) => return DiagnosticResult { test_name, success: false, details: format!("Failed: {:#}", e) },
+    };
+    let scheme = device.http_scheme();
+    let http_port = specifics.http_port.unwrap_or(if scheme == "https" { 443 } else { 80 });
+    let mut ports = vec![("rtsp", specifics.rtsp_port.unwrap_or(554), true), (scheme.as_str(), http_port, device.driver.has_http_api())];
+    if let Some(onvif_port) = specifics.onvif_port.filter(|port| *port != http_port) {
+        ports.push(("onvif", onvif_port, specifics.rtsp_path.is_none()));
+    }
+
+    let probe = net_probe::probe(&device.ip(), &ports, timeouts.connect).await;
+    let details = probe.summary();
+    if !probe.reachable() {
+        error!("    DIAGNOSTIC [{}]: Network test FAILED: {}", cam_name, details);
+        return DiagnosticResult { test_name, success: false, details };
+    }
+    if let Ok(ping) = &probe.ping {
+        if ping.loss_percent() > 0.0 {
+            warn!("    DIAGNOSTIC [{}]: {:.0}% ping loss (ICMP may be filtered).", cam_name, ping.loss_percent());
+        }
+    }
+    info!("    DIAGNOSTIC [{}]: Network OK: {}", cam_name, details);
+    DiagnosticResult { test_name, success: true, details }
+}
+
+/// Reports the camera's day/night (IR-cut) mode. Only an unreadable mode on a driver that
+/// supports it fails; night mode is passed with a warning, since it may be intended.
+async fn check_day_night(master_config: &MasterConfig, cam_name: &str) -> DiagnosticResult {
+    let test_name = format!("Day/Night Mode ('{}')", cam_name);
+    let specifics = master_config.cameras.iter().find_map(|cfg| match cfg {
+        CaptureDeviceConfig::IpCamera { name, specifics, .. } if name == cam_name => Some(specifics.clone()),
+        _ => None,
+    });
+    let Some(specifics) = specifics else {
+        return DiagnosticResult { test_name, success: false, details: "No IP camera configuration found.".to_string() };
+    };
+    let timeouts = CameraTimeouts::resolve(&master_config.application, Some(&specifics));
+    let device = match IpCameraDevice::new(cam_name.to_string(), specifics, timeouts) {
+        Ok(device) => device,
+        Err(e) => return DiagnosticResult { test_name, success: false, details: format!("Failed: {:#}", e) },
+    };
+    if !device.driver.supports_day_night() {
+        return DiagnosticResult { test_name, success: true, details: format!("Skipped (not supported by the '{}' driver).", device.driver.name()) };
+    }
+    match CameraController::new().get_day_night(&device).await {
+        Ok(DayNightMode::Night) => {
+            warn!("    DIAGNOSTIC [{}]: Camera is forced into night mode; images will be monochrome.", cam_name);
+            DiagnosticResult { test_name, success: true, details: format!("{} (images are monochrome)", DayNightMode::Night) }
+        }
+        Ok(mode) => DiagnosticResult { test_name, success: true, details: mode.to_string() },
+        Err(e) => {
+            error!("    DIAGNOSTIC [{}]: Reading day/night mode FAILED: {:#}", cam_name, e);
+            DiagnosticResult { test_name, success: false, details: format!("Failed: {:#}", e) }
+        }
+    }
+}
+
+/// Streams every selected RealSense camera that has an inter-camera sync mode at once and
+/// compares frame timestamps against the master (or, with only slaves on an external trigger,
+/// the first camera). One result per compared camera; nothing when fewer than two are synced.
+async fn check_realsense_sync(master_config: &MasterConfig, params: &DiagnosticParams) -> Vec<DiagnosticResult> {
+    let is_mode = |mode: Option<&str>, wanted: &str| mode.is_some_and(|mode| mode.trim().eq_ignore_ascii_case(wanted));
+    let cameras: Vec<_> = op_helper::select_realsense_cameras(master_config, params.cameras.as_ref()).into_iter()
+        .filter(|(_, specifics)| specifics.inter_cam_sync_mode.is_some() && !is_mode(specifics.inter_cam_sync_mode.as_deref(), "default"))
+        .collect();
+    if cameras.len() < 2 {
+        debug!("  DIAGNOSTIC [Global]: {} RealSense camera(s) with an inter_cam_sync_mode selected; skipping the sync test.", cameras.len());
+        return Vec::new();
+    }
+    let reference = cameras.iter().position(|(_, specifics)| is_mode(specifics.inter_cam_sync_mode.as_deref(), "master")).unwrap_or(0);
+    let reference_name = cameras[reference].0.clone();
+    info!("  DIAGNOSTIC [Global]: Sampling frame timestamps of {} synced RealSense cameras for {}s (reference '{}')... 🔗",
+        cameras.len(), DIAGNOSTIC_SYNC_SECS, reference_name);
+    let started = Instant::now();
+    let samples = join_all(cameras.iter().map(|(name, specifics)| {
+        let device = RealsenseDevice::from_app(name.clone(), specifics.clone(), &master_config.application);
+        async move { device.sample_frame_timestamps(Duration::from_secs(DIAGNOSTIC_SYNC_SECS)).await }
+    })).await;
+
+    let reference_frames = match &samples[reference] {
+        Ok(sampled) if !sampled.global_time => Err(format!(
+            "'{}' timestamps are in the {} domain, which cannot be compared across devices; enable global time", reference_name, sampled.domain)),
+        Ok(sampled) => {
+            let mut millis = sampled.millis.clone();
+            millis.sort_by(f64::total_cmp);
+            Ok(millis)
+        }
+        Err(e) => Err(format!("Reference '{}' failed: {:#}", reference_name, e)),
+    };
+    let mut results = Vec::new();
+    for (index, ((name, _), sampled)) in cameras.iter().zip(&samples).enumerate() {
+        if index == reference {
+            continue;
+        }
+        let test_name = format!("RealSense Sync ('{}' vs '{}')", name, reference_name);
+        let outcome = match (&reference_frames, sampled) {
+            (Err(e), _) => Err(e.clone()),
+            (_, Err(e)) => Err(format!("Failed: {:#}", e)),
+            (_, Ok(sampled)) if !sampled.global_time => Err(format!(
+                "Timestamps are in the {} domain, which cannot be compared across devices; enable global time", sampled.domain)),
+            (Ok(reference_frames), Ok(sampled)) => sync_offsets(reference_frames, &sampled.millis),
+        };
+        let result = match outcome {
+            Ok((median, spread, frames)) => {
+                let success = median.abs() <= SYNC_TOLERANCE_MS && spread <= SYNC_TOLERANCE_MS;
+                let details = format!("median offset {:+.2} ms, spread {:.2} ms over {} frame(s) (tolerance {} ms)", median, spread, frames, SYNC_TOLERANCE_MS);
+                if success {
+                    info!("    DIAGNOSTIC [{}]: In sync with '{}': {}", name, reference_name, details);
+                } else {
+                    error!("    DIAGNOSTIC [{}]: NOT in sync with '{}': {}. Check the sync cable and that '{}' is the master.", name, reference_name, details, reference_name);
+                }
+                DiagnosticResult { test_name, success, details }
+            }
+            Err(details) => {
+                error!("    DIAGNOSTIC [{}]: Sync test FAILED: {}", name, details);
+                DiagnosticResult { test_name, success: false, details }
+            }
+        };
+        results.push(result);
+    }
+    info!("  DIAGNOSTIC [Global]: Sync test finished in {:?}.", started.elapsed());
+    results
+}
+
+/// Median offset (ms) from each frame to the nearest reference frame, the largest deviation
+/// from that median, and how many frames were compared. Only frames while both cameras were
+/// streaming count, as pipeline starts are staggered.
+fn sync_offsets(reference: &[f64], frames: &[f64]) -> std::result::Result<(f64, f64, usize), String> {
+    let (Some(first), Some(last)) = (reference.first(), reference.last()) else {
+        return Err("The reference camera delivered no frames".to_string());
+    };
+    let mut offsets: Vec<f64> = frames.iter()
+        .filter(|t| (*first..=*last).contains(*t))
+        .map(|t| {
+            let next = reference.partition_point(|r| r < t);
+            let before = next.checked_sub(1).map(|i| t - reference[i]);
+            let after = reference.get(next).map(|r| t - r);
+            [before, after].into_iter().flatten().min_by(|a, b| a.abs().total_cmp(&b.abs())).unwrap_or(f64::NAN)
+        })
+        .collect();
+    if offsets.is_empty() {
+        return Err("No frames overlapped with the reference camera's".to_string());
+    }
+    offsets.sort_by(f64::total_cmp);
+    let median = offsets[offsets.len() / 2];
+    let spread = offsets.iter().map(|offset| (offset - median).abs()).fold(0.0, f64::max);
+    Ok((median, spread, offsets.len()))
+}
+
+/// Turns a single-camera operation outcome into a diagnostic line.
+fn report_result(test_name: String, cam_name: &str, outcome: Result<OperationReport>, started: Instant) -> DiagnosticResult {
+    let failure = match outcome {
+        Ok(report) => report.results.into_iter().find_map(|r| r.error),
+        Err(e) => Some(format!("{:#}", e)),
+    };
+    match failure {
+        None => {
+            info!("    DIAGNOSTIC [{}]: {} PASSED in {:?}.", cam_name, test_name, started.elapsed());
+            DiagnosticResult { test_name, success: true, details: "Completed. See the diagnostics output directory.".to_string() }
+        }
+        Some(error) => {
+            error!("    DIAGNOSTIC [{}]: {} FAILED in {:?}: {}", cam_name, test_name, started.elapsed(), error);
+            DiagnosticResult { test_name, success: false, details: format!("Failed: {}", error) }
+        }
+    }
+}
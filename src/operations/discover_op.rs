@@ -0,0 +1,308 @@
+use crate::camera::discovery;
+use crate::camera::mdns::{self, MdnsService};
+use crate::camera::ssdp;
+use crate::cli::DiscoverArgs;
+use crate::config_loader::{CaptureDeviceConfig, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use anyhow::{bail, Context, Result};
+use futures::future::join_all;
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Typed parameters for `rcam discover`.
+#[derive(Debug, Clone)]
+pub struct DiscoverParams {
+    pub mdns: bool,
+    pub ssdp: bool,
+    pub timeout: Duration, // how long to listen for answers
+    pub all: bool,         // also list SSDP devices that do not look like cameras
+    pub yaml: bool,        // print camera entries for the config file to stdout
+}
+
+impl Default for DiscoverParams {
+    fn default() -> Self {
+        DiscoverParams { mdns: true, ssdp: true, timeout: Duration::from_secs(3), all: false, yaml: false }
+    }
+}
+
+impl DiscoverParams {
+    pub fn from_args(args: &DiscoverArgs) -> Self {
+        // Neither flag means both protocols.
+        let both = !args.mdns && !args.ssdp;
+        DiscoverParams {
+            mdns: args.mdns || both,
+            ssdp: args.ssdp || both,
+            timeout: Duration::from_secs(args.timeout_secs.max(1)),
+            all: args.all,
+            yaml: args.yaml,
+        }
+    }
+}
+
+/// A device that announced itself, merged across protocols by address.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredDevice {
+    pub ip: IpAddr,
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub sources: BTreeSet<String>, // "mdns", "ssdp"
+    pub services: Vec<String>,     // e.g. "_rtsp._tcp:554" or the UPnP device type
+    pub rtsp_port: Option<u16>,
+    pub http_port: Option<u16>,
+    pub mac_address: Option<String>,   // from the ARP table, if the kernel knows it
+    pub configured_as: Option<String>, // the configured camera at this address
+}
+
+impl DiscoveredDevice {
+    fn new(ip: IpAddr) -> Self {
+        DiscoveredDevice {
+            ip,
+            name: None,
+            manufacturer: None,
+            model: None,
+            sources: BTreeSet::new(),
+            services: Vec::new(),
+            rtsp_port: None,
+            http_port: None,
+            mac_address: None,
+            configured_as: None,
+        }
+    }
+
+    /// The config `vendor` for this device, when its maker is one rcam has a driver for.
+    pub fn vendor(&self) -> Option<&'static str> {
+        let text = [&self.manufacturer, &self.model, &self.name]
+            .into_iter()
+            .flatten()
+            .chain(self.services.iter())
+            .map(|s| s.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.contains("axis") {
+            Some("axis")
+        } else if text.contains("dahua") || text.contains("amcrest") {
+            Some("dahua")
+        } else {
+            None
+        }
+    }
+}
+
+pub async fn handle_discover_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &DiscoverArgs,
+) -> Result<()> {
+    let params = DiscoverParams::from_args(args);
+    debug!("Discover CLI parsed into: {:?}", params);
+    let devices = execute(&params, &OperationContext::new(master_config, camera_manager)).await?;
+    if params.yaml {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(config_entries(&devices, master_config).as_bytes()).context("Failed to write camera entries")?;
+    }
+    Ok(())
+}
+
+/// Lists the cameras on the local network that announce themselves over mDNS (Bonjour) or
+/// SSDP (UPnP), which many consumer cameras do without implementing ONVIF discovery. Devices
+/// are matched against the configured cameras by address.
+pub async fn execute(params: &DiscoverParams, ctx: &OperationContext<'_>) -> Result<Vec<DiscoveredDevice>> {
+    if !params.mdns && !params.ssdp {
+        bail!("Nothing to discover: enable mDNS, SSDP or both.");
+    }
+    let protocols: Vec<&str> = [("mDNS", params.mdns), ("SSDP", params.ssdp)].into_iter().filter(|(_, on)| *on).map(|(p, _)| p).collect();
+    info!("📡 Listening for {} announcements for {:?}...", protocols.join(" and "), params.timeout);
+
+    let (mdns_result, ssdp_result) = tokio::join!(
+        async { if params.mdns { mdns::browse(mdns::CAMERA_SERVICES, params.timeout).await.map(Some) } else { Ok(None) } },
+        async { if params.ssdp { ssdp_devices(params).await.map(Some) } else { Ok(None) } },
+    );
+
+    let mut devices: BTreeMap<IpAddr, DiscoveredDevice> = BTreeMap::new();
+    match mdns_result {
+        Ok(Some(services)) => {
+            debug!("mDNS: {} camera service(s) announced.", services.len());
+            for service in services {
+                merge_mdns(&mut devices, service);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("⚠️ mDNS discovery failed: {:#}", e),
+    }
+    match ssdp_result {
+        Ok(Some(found)) => {
+            debug!("SSDP: {} device(s) kept.", found.len());
+            for device in found {
+                let entry = devices.entry(device.ip).or_insert_with(|| DiscoveredDevice::new(device.ip));
+                entry.sources.extend(device.sources);
+                entry.name = entry.name.take().or(device.name);
+                entry.manufacturer = entry.manufacturer.take().or(device.manufacturer);
+                entry.model = entry.model.take().or(device.model);
+                entry.http_port = entry.http_port.or(device.http_port);
+                entry.services.extend(device.services);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("⚠️ SSDP discovery failed: {:#}", e),
+    }
+
+    let arp = discovery::arp_table().unwrap_or_default();
+    let mut devices: Vec<DiscoveredDevice> = devices.into_values().collect();
+    for device in &mut devices {
+        device.mac_address = arp.iter().find(|(_, ip)| IpAddr::V4(**ip) == device.ip).map(|(mac, _)| mac.to_uppercase());
+        device.configured_as = ctx.master_config.cameras.iter()
+            .find(|camera| match camera {
+                CaptureDeviceConfig::IpCamera { specifics, .. } => {
+                    specifics.ip.parse::<IpAddr>().ok() == Some(device.ip)
+                        || discovery::current_ip(camera.get_name(), &specifics.ip).parse::<IpAddr>().ok() == Some(device.ip)
+                        || (device.mac_address.is_some() && specifics.mac_address.as_deref()
+                            .and_then(|mac| discovery::normalize_mac(mac).ok())
+                            .map(|mac| mac.to_uppercase()) == device.mac_address)
+                }
+                CaptureDeviceConfig::ThermalCamera { specifics, .. } => specifics.ip.parse::<IpAddr>().ok() == Some(device.ip),
+                CaptureDeviceConfig::RealsenseCamera { .. } | CaptureDeviceConfig::VirtualCamera { .. } | CaptureDeviceConfig::Custom { .. } => false,
+            })
+            .map(|camera| camera.get_name().clone());
+    }
+    log_table(&devices);
+    Ok(devices)
+}
+
+fn merge_mdns(devices: &mut BTreeMap<IpAddr, DiscoveredDevice>, service: MdnsService) {
+    let Some(ip) = service.ip else {
+        debug!("mDNS: no address for '{}'", service.instance);
+        return;
+    };
+    let entry = devices.entry(ip).or_insert_with(|| DiscoveredDevice::new(ip));
+    entry.sources.insert("mdns".to_string());
+    let kind = service.service.trim_end_matches(".local");
+    entry.services.push(match service.port {
+        Some(port) => format!("{}:{}", kind, port),
+        None => kind.to_string(),
+    });
+    if kind == "_rtsp._tcp" {
+        entry.rtsp_port = entry.rtsp_port.or(service.port);
+    } else {
+        entry.http_port = entry.http_port.or(service.port);
+    }
+    let txt = |key: &str| service.txt.iter()
+        .find_map(|entry| entry.split_once('=').filter(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.to_string()))
+        .filter(|v| !v.is_empty());
+    entry.model = entry.model.take().or_else(|| txt("model")).or_else(|| txt("md"));
+    entry.manufacturer = entry.manufacturer.take().or_else(|| txt("manufacturer")).or_else(|| txt("vendor"));
+    if entry.name.is_none() {
+        entry.name = Some(service.label().to_string());
+    }
+    if kind == "_axis-video._tcp" && entry.manufacturer.is_none() {
+        entry.manufacturer = Some("AXIS".to_string());
+    }
+}
+
+/// SSDP responders with their UPnP descriptions; without `all`, only those that look like cameras.
+async fn ssdp_devices(params: &DiscoverParams) -> Result<Vec<DiscoveredDevice>> {
+    let responses = ssdp::search(params.timeout).await?;
+    debug!("SSDP: {} device(s) answered.", responses.len());
+    let client = reqwest::Client::new();
+    let described = join_all(responses.iter().map(|response| {
+        let client = &client;
+        async move {
+            match &response.location {
+                Some(location) => ssdp::describe(client, location).await
+                    .map_err(|e| debug!("SSDP: {:#}", e))
+                    .unwrap_or_default(),
+                None => ssdp::DeviceDescription::default(),
+            }
+        }
+    })).await;
+
+    let mut devices = Vec::new();
+    for (response, description) in responses.into_iter().zip(described) {
+        if !params.all && !description.looks_like_camera(response.server.as_deref()) {
+            debug!("SSDP: skipping {} ({}), not a camera", response.ip,
+                description.friendly_name.as_deref().or(response.server.as_deref()).unwrap_or("unknown"));
+            continue;
+        }
+        let mut device = DiscoveredDevice::new(response.ip);
+        device.sources.insert("ssdp".to_string());
+        device.http_port = description.presentation_url.as_deref()
+            .or(response.location.as_deref())
+            .and_then(|url| reqwest::Url::parse(url).ok())
+            .and_then(|url| url.port_or_known_default());
+        device.services.push(description.device_type.clone().or(response.st.clone()).unwrap_or_else(|| "upnp".to_string()));
+        device.name = description.friendly_name.or(response.server);
+        device.manufacturer = description.manufacturer;
+        device.model = description.model_name;
+        devices.push(device);
+    }
+    Ok(devices)
+}
+
+fn log_table(devices: &[DiscoveredDevice]) {
+    if devices.is_empty() {
+        warn!("⚠️ No cameras announced themselves. Cameras that only implement ONVIF or have announcements disabled will not show up here.");
+        return;
+    }
+    info!("📡 {} device(s) found:", devices.len());
+    for device in devices {
+        let identity = [device.manufacturer.as_deref(), device.model.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+        let configured = match &device.configured_as {
+            Some(name) => format!("✅ configured as '{}'", name),
+            None => "🆕 not configured".to_string(),
+        };
+        info!("  {:<15} | {:<32} | {:<24} | {:<9} | {}",
+            device.ip, device.name.as_deref().unwrap_or("-"), if identity.is_empty() { "-" } else { &identity },
+            device.sources.iter().cloned().collect::<Vec<_>>().join("+"), configured);
+        info!("  {:<15}   ↳ {}{}", "", device.services.join(", "),
+            device.mac_address.as_deref().map(|mac| format!(" (MAC {})", mac)).unwrap_or_default());
+    }
+}
+
+/// `- !IpCamera` entries for the cameras that are not configured yet, ready to paste under
+/// `cameras:` in the config file.
+fn config_entries(devices: &[DiscoveredDevice], master_config: &MasterConfig) -> String {
+    let mut taken: BTreeSet<String> = master_config.cameras.iter().map(|camera| camera.get_name().clone()).collect();
+    let mut yaml = String::from("# Cameras found by `rcam discover`; paste under `cameras:` and set each {NAME}_PASSWORD.\n");
+    let new: Vec<&DiscoveredDevice> = devices.iter().filter(|d| d.configured_as.is_none()).collect();
+    if new.is_empty() {
+        yaml.push_str("# (every camera found is already configured)\n");
+    }
+    for device in new {
+        let base = match device.ip {
+            IpAddr::V4(ip) => format!("camera_{}", ip.octets()[3]),
+            IpAddr::V6(_) => "camera".to_string(),
+        };
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while taken.contains(&name) {
+            name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        taken.insert(name.clone());
+
+        let about = [device.name.as_deref(), device.manufacturer.as_deref(), device.model.as_deref()]
+            .into_iter().flatten().collect::<Vec<_>>().join(", ");
+        yaml.push_str(&format!("\n  - !IpCamera # {}\n", about));
+        yaml.push_str(&format!("    name: \"{}\"\n    type: \"ip_camera\"\n    ip: \"{}\"\n    username: \"admin\"\n", name, device.ip));
+        if let Some(mac) = &device.mac_address {
+            yaml.push_str(&format!("    mac_address: \"{}\"\n", mac));
+        }
+        match device.vendor() {
+            Some(vendor) => yaml.push_str(&format!("    vendor: \"{}\"\n", vendor)),
+            None => yaml.push_str("    # vendor: \"rtsp\" # unknown maker: set \"dahua\" or \"axis\" if it is one, else \"rtsp\" with rtsp_path\n"),
+        }
+        if let Some(port) = device.http_port.filter(|port| *port != 80) {
+            yaml.push_str(&format!("    # http_port: {} # port the device announced; check it serves the camera API\n", port));
+        }
+        if let Some(port) = device.rtsp_port.filter(|port| *port != 554) {
+            yaml.push_str(&format!("    rtsp_port: {}\n", port));
+        }
+        yaml.push_str("    # rtsp_path: \"/stream1\" # needed when the stream cannot be discovered over ONVIF or the vendor API\n");
+    }
+    yaml
+}
@@ -1,151 +1,505 @@
-use crate::config_loader::MasterConfig;
+use crate::config_loader::{CaptureDeviceConfig, GeoLocation, MasterConfig, QualityChecks};
 use crate::core::camera_manager::CameraManager;
-use crate::core::capture_source::{FrameData, FrameDataBundle};
+use crate::camera::depth_points;
+use crate::camera::frame_dedup;
+use crate::camera::{image_metadata, image_quality};
+use crate::core::capture_source::{CaptureSource, FrameData, FrameDataBundle, RsIntrinsics};
+use crate::camera::image_quality::ImageQuality;
+use crate::core::events::{self, Event};
 use anyhow::{Result, anyhow};
 use crate::operations::op_helper;
-use clap::ArgMatches;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_result::{CameraResult, OperationReport};
+use crate::cli::CaptureImageArgs;
+use crate::errors::ErrorKind;
 use log::{info, error, debug, warn};
-use std::time::Instant;
-use rerun::RecordingStreamBuilder;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::common::{checksum, disk_space, hooks, logging_setup, sync_start, timing_report};
+use crate::common::rerun_setup::{self, RerunIdentity, RerunParams};
+use crate::common::session::SessionManifest;
+use crate::storage;
 use rerun::datatypes::{TensorData, TensorBuffer, ColorModel};
 use rerun::archetypes::Image as RerunImage;
 use rerun::archetypes::EncodedImage as RerunEncodedImage;
 use rerun::archetypes::DepthImage as RerunDepthImage;
 use image;
 use image::ImageFormat as ImageCrateFormat;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures::future::join_all;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::{JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use rerun::RecordingStream;
 
+/// Typed parameters for an image capture, independent of the CLI.
+#[derive(Debug, Clone)]
+pub struct CaptureImageParams {
+    pub cameras: Option<Vec<String>>, // None = all configured cameras
+    pub output_dir: Option<PathBuf>,
+    pub rerun: RerunParams,
+    pub require_all: bool,
+    pub rerun_points: Option<bool>, // None = AppSettings.rerun_point_cloud
+    pub count: u32,                 // frames per camera; 1 = single shot
+    pub interval: Duration,         // time between burst frames, from one start to the next
+    pub skip_unchanged: Option<bool>, // None = AppSettings.skip_unchanged
+    pub retries: Option<u32>,         // None = AppSettings.capture_retries
+    pub timing_report: bool,          // log a per-camera timing table and store it in the manifest
+    pub start_at: Option<String>,     // None = AppSettings.start_at
+    pub start_offset_ms: Option<u64>, // None = AppSettings.start_offset_ms
+    pub stream: Option<String>,       // named stream for every camera; None = per operation_streams
+    pub stream_key: Option<&'static str>, // operation_streams entry to use; None = "capture-image"
+}
+
+impl Default for CaptureImageParams {
+    fn default() -> Self {
+        CaptureImageParams {
+            cameras: None,
+            output_dir: None,
+            rerun: RerunParams::default(),
+            require_all: false,
+            rerun_points: None,
+            count: 1,
+            interval: Duration::ZERO,
+            skip_unchanged: None,
+            retries: None,
+            timing_report: false,
+            start_at: None,
+            start_offset_ms: None,
+            stream: None,
+            stream_key: None,
+        }
+    }
+}
+
+impl CaptureImageParams {
+    pub fn from_args(args: &CaptureImageArgs) -> Self {
+        CaptureImageParams {
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+            output_dir: args.output.as_ref().map(PathBuf::from),
+            rerun: RerunParams::from_args(&args.rerun),
+            require_all: args.require_all,
+            rerun_points: args.rerun_points.then_some(true),
+            count: args.count,
+            interval: Duration::from_millis(args.interval),
+            skip_unchanged: args.skip_unchanged.then_some(true),
+            retries: args.retries,
+            timing_report: args.timing_report,
+            start_at: args.start_at.clone(),
+            start_offset_ms: args.start_offset_ms,
+            stream: args.stream.clone(),
+            stream_key: None,
+        }
+    }
+}
+
 pub async fn handle_capture_image_cli(
     master_config: &MasterConfig,
     camera_manager: &CameraManager,
-    args: &ArgMatches,
-) -> Result<()> {
-    let op_start_time = Instant::now();
-    let operation_display_name = "Image Capture (Unified)";
+    args: &CaptureImageArgs,
+) -> Result<OperationReport> {
+    if args.delay.is_some() {
+        warn!("⚠️ The --delay argument is ignored for image capture as it is now operationally synchronized.");
+    }
+    let params = CaptureImageParams::from_args(args);
+    debug!("Capture image CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
 
-    info!("🖼️ '{}' operation started.", operation_display_name);
+/// Settings shared by every camera's capture task in one run.
+struct BatchSettings {
+    count: u32,
+    interval: Duration,
+    timestamp_format: String,
+    image_format: String,
+    jpeg_quality: Option<u8>,
+    png_compression: Option<u32>,
+    embed_metadata: bool,
+    quality_checks: Option<QualityChecks>,
+    dedup_max_distance: Option<u32>,
+    base_output_dir: PathBuf,
+    session_id: String,
+    cancel: CancellationToken,
+    stream_limit: Option<Arc<Semaphore>>,
+}
 
-    let enable_rerun = args.get_one::<bool>("rerun").copied().unwrap_or(false);
-    let mut rec_stream_opt: Option<RecordingStream> = None;
+/// One camera of the batch; kept for the whole run so failed cameras can be captured again.
+struct DeviceJob {
+    name: String,
+    device: Arc<Mutex<dyn CaptureSource + Send>>,
+    output_dir: PathBuf,
+    location: Option<GeoLocation>,
+}
 
-    if enable_rerun {
-        let flush_timeout_secs = master_config.application.rerun_flush_timeout_secs.unwrap_or(10.0);
-        let mut opts = rerun::SpawnOptions::default();
-        if let Some(limit) = &master_config.application.rerun_memory_limit {
-            opts.memory_limit = limit.clone().into();
-            debug!("Rerun: Setting memory limit to: {}", limit);
+/// What one camera's capture task produced.
+struct DeviceCapture {
+    started_at: DateTime<Utc>,
+    bundles: Vec<FrameDataBundle>,
+    skipped: Vec<String>,                             // journal entries for frames dropped as duplicates
+    qualities: Vec<(PathBuf, ImageQuality, Vec<String>)>, // measured image, metrics, missed thresholds
+    error: Option<anyhow::Error>,
+    elapsed: Duration, // from acquiring the stream slot to the last frame
+}
+
+/// A camera is retried if its task panicked or it failed without saving anything.
+fn needs_retry(outcome: &std::result::Result<DeviceCapture, JoinError>) -> bool {
+    match outcome {
+        Ok(capture) => capture.error.is_some() && capture.bundles.is_empty(),
+        Err(_) => true,
+    }
+}
+
+/// Starts the capture (one frame or a burst) of one camera; frames are named with `ts_str`.
+/// With `start_at` (host time) the capture waits for that instant.
+fn spawn_capture(job: &DeviceJob, ts_str: &str, settings: Arc<BatchSettings>, start_at: Option<DateTime<Utc>>) -> JoinHandle<DeviceCapture> {
+    let device_arc = job.device.clone();
+    let output_dir = job.output_dir.clone();
+    let location = job.location;
+    let ts_str = ts_str.to_string();
+    let mut dedup = settings.dedup_max_distance.map(|max_distance| {
+        let seed = frame_dedup::previous_capture(&settings.base_output_dir, &settings.session_id, &job.name);
+        frame_dedup::DedupFilter::new(max_distance, seed.as_deref())
+    });
+
+    let span = logging_setup::camera_span(&job.name);
+    tokio::spawn(async move {
+        let _permit = match settings.stream_limit.clone() {
+            Some(limit) => limit.acquire_owned().await.ok(),
+            None => None,
+        };
+        let started_at = Utc::now();
+        let started = Instant::now();
+        let mut device_locked = device_arc.lock().await;
+        let device_name = device_locked.get_name();
+        let device_type = device_locked.get_type();
+        info!("Initiating capture for device: '{}' (Type: {})", device_name, device_type);
+        // A burst keeps the source open between frames (a RealSense pipeline stays streaming),
+        // unless the caller already holds it open, as the daemon does.
+        let opened_here = settings.count > 1 && !device_locked.is_open();
+        if opened_here {
+            if let Err(e) = device_locked.open().await {
+                warn!("⚠️ '{}': could not open for the burst ({:#}); each frame sets up its own capture.", device_name, e);
+            }
         }
-        if let Some(latency_str) = &master_config.application.rerun_drop_at_latency {
-            opts.extra_args.push("--drop-at-latency".into());
-            opts.extra_args.push(latency_str.clone().into());
-            debug!("Rerun: Setting drop-at-latency to: {}", latency_str);
+        if let Some(at) = start_at {
+            let late = sync_start::sleep_until(at).await;
+            if !late.is_zero() {
+                warn!("⚠️ '{}': missed the scheduled start by {:?}; capturing now.", device_name, late);
+            }
         }
-        match RecordingStreamBuilder::new("rcam_image_capture_unified")
-            .spawn_opts(&opts, Some(std::time::Duration::from_secs_f32(flush_timeout_secs)))
-        {
-            Ok(stream) => {
-                info!("Rerun recording stream initialized for '{}' (FlushTimeout: {}s).", operation_display_name, flush_timeout_secs);
-                rec_stream_opt = Some(stream);
+
+        let count = settings.count;
+        let interval = settings.interval;
+        // The device stays locked for the whole burst. Frames are scheduled from the burst
+        // start so a slow capture doesn't shift every later frame.
+        let burst_start = tokio::time::Instant::now();
+        let mut bundles = Vec::new();
+        let mut error_opt = None;
+        let mut skipped = Vec::new();
+        let mut qualities = Vec::new();
+        for seq in 0..count {
+            tokio::select! {
+                biased;
+                _ = settings.cancel.cancelled(), if seq > 0 => {
+                    warn!("🛑 '{}': burst stopped after {} of {} frame(s).", device_name, seq, count);
+                    break;
+                }
+                _ = tokio::time::sleep_until(burst_start + interval * seq) => {}
             }
-            Err(e) => {
-                error!("Failed to initialize Rerun recording stream: {}. Continuing without Rerun.", e);
+            // Burst frames carry their own timestamp and a sequence number in the filename.
+            let frame_ts_str = if count == 1 {
+                ts_str.clone()
+            } else {
+                format!("{}_{:04}", Utc::now().format(&settings.timestamp_format), seq)
+            };
+            match device_locked.capture_image(
+                &output_dir,
+                &frame_ts_str,
+                &settings.image_format,
+                settings.jpeg_quality,
+                settings.png_compression,
+            ).await {
+                Ok(bundle) => match dedup.as_mut().and_then(|filter| filter.check(&bundle)) {
+                    Some(distance) => {
+                        info!("♻️ '{}': frame {} of {} unchanged (hash distance {}); not saved.", device_name, seq + 1, count, distance);
+                        frame_dedup::discard(&bundle);
+                        skipped.push(format!("skipped (duplicate): frame {} at {} (hash distance {})", seq + 1, bundle.captured_at.to_rfc3339(), distance));
+                    }
+                    None => {
+                        if settings.embed_metadata {
+                            image_metadata::embed_in_bundle(&bundle, &device_name, location).await;
+                        }
+                        if let Some(checks) = &settings.quality_checks {
+                            if let Some((path, quality)) = image_quality::measure_bundle(&bundle).await {
+                                let violations = quality.violations(checks);
+                                if !violations.is_empty() {
+                                    warn!("🔍 '{}': frame {} of {} failed quality checks: {}", device_name, seq + 1, count, violations.join("; "));
+                                }
+                                qualities.push((path, quality, violations));
+                            }
+                        }
+                        bundles.push(bundle);
+                    }
+                },
+                Err(e) => {
+                    error!("Capture failed for device '{}' (frame {} of {}): {}", device_name, seq + 1, count, e);
+                    error_opt = Some(if count == 1 { e } else { e.context(format!("burst stopped after {} of {} frame(s)", seq, count)) });
+                    break;
+                }
+            }
+            if count > 1 && burst_start.elapsed() > interval * (seq + 1) && seq + 1 < count {
+                warn!("⚠️ '{}': burst frame {} took longer than the {:?} interval; the next frame starts late.", device_name, seq + 1, interval);
             }
         }
-    }
+        if opened_here {
+            if let Err(e) = device_locked.close().await {
+                warn!("⚠️ '{}': failed to close after the burst: {:#}", device_name, e);
+            }
+        }
+        DeviceCapture { started_at, bundles, skipped, qualities, error: error_opt, elapsed: started.elapsed() }
+    }.instrument(span))
+}
+
+pub async fn execute(params: &CaptureImageParams, ctx: &OperationContext<'_>) -> Result<OperationReport> {
+    let master_config = ctx.master_config;
+    let op_start_time = Instant::now();
+    let operation_display_name = "Image Capture (Unified)";
+    let require_all = params.require_all;
+    let mut report = OperationReport::new(operation_display_name);
+
+    info!("🖼️ '{}' operation started.", operation_display_name);
+
+    let rec_stream_opt: Option<RecordingStream> = if params.rerun.enabled {
+        let identity = RerunIdentity::resolve(&master_config.application, &params.rerun, "rcam_image_capture_unified");
+        rerun_setup::init_recording_stream(&master_config.application, &identity, operation_display_name)
+    } else {
+        None
+    };
 
-    if args.contains_id("delay") {
-        warn!("⚠️ The --delay argument is ignored for image capture as it is now operationally synchronized.");
-    }
-    debug!(
-        "Capture image CLI: devices_arg: {:?}, output_arg: {:?}",
-        args.get_one::<String>("cameras"), args.get_one::<String>("output")
-    );
-    
     let target_devices = op_helper::determine_target_devices(
-        camera_manager,
-        args.get_one::<String>("cameras"),
-        operation_display_name
+        ctx.camera_manager,
+        params.cameras.as_deref(),
+        operation_display_name,
+        require_all,
     ).await?;
 
     if target_devices.is_empty() {
         info!("No devices selected or available for image capture. Exiting.");
-        return Ok(());
+        return Ok(report);
     }
     info!("🖼️ Preparing to capture images from {} specified device(s).", target_devices.len());
 
-    let output_dir = op_helper::determine_operation_output_dir(
+    let base_output_dir = op_helper::determine_operation_output_dir(
         master_config,
-        args,
-        "output",
-        Some("images_unified"),
+        params.output_dir.as_deref(),
+        None,
         operation_display_name
     )?;
+    disk_space::ensure_free_space(&base_output_dir, disk_space::min_free_bytes(&master_config.application))?;
+    let mut session = SessionManifest::create(master_config, &base_output_dir, "image", operation_display_name)?;
+    report.session = Some(session.output_dir.clone());
+    info!("🗂️ Session '{}': saving to {}", session.session_id, session.output_dir.display());
+    let mut target_names = Vec::new();
+    for device_arc in &target_devices {
+        target_names.push(device_arc.lock().await.get_name());
+    }
+    for resolution in op_helper::resolve_by_mac(master_config, &target_names).await {
+        session.set_resolved_ip(&resolution);
+    }
     
-    let ts_str = Utc::now().format(&master_config.application.filename_timestamp_format).to_string();
-    let mut capture_handles = Vec::new();
+    if params.count == 0 {
+        return Err(anyhow!("--count must be 1 or greater"));
+    }
+    if params.count > 1 {
+        info!("📸 Burst mode: {} frame(s) per camera every {:?}.", params.count, params.interval);
+    }
+    let count = params.count;
+    let interval = params.interval;
+    let dedup_max_distance = params.skip_unchanged.or(master_config.application.skip_unchanged).unwrap_or(false)
+        .then(|| master_config.application.unchanged_max_distance.unwrap_or(frame_dedup::DEFAULT_MAX_DISTANCE));
+    let timestamp_format = master_config.application.filename_timestamp_format.clone();
+    // With a scheduled start, every host names the batch after the same instant.
+    let sync_start = sync_start::resolve(&master_config.application, params.start_at.as_deref(), params.start_offset_ms).await?;
+    let scheduled = sync_start.map(|sync| (sync.schedule(operation_display_name), sync));
+    if let Some((start, _)) = scheduled {
+        session.event("info", None, format!("scheduled start at {}", start.to_rfc3339()));
+    }
+    let start_at = scheduled.map(|(start, sync)| sync.host_time(start));
+    let ts_str = scheduled.map_or_else(Utc::now, |(start, _)| start).format(&timestamp_format).to_string();
+    let retries = params.retries.or(master_config.application.capture_retries).unwrap_or(0);
+    let retry_delay = Duration::from_secs_f32(master_config.application.capture_retry_delay_secs.unwrap_or(2.0));
+    // Cameras beyond max_concurrent_streams wait here for a running capture to finish.
+    let stream_limit = master_config.application.max_concurrent_streams
+        .filter(|limit| *limit < target_devices.len())
+        .map(|limit| {
+            info!("🚦 Capturing at most {} of {} cameras at once (max_concurrent_streams).", limit, target_devices.len());
+            Arc::new(Semaphore::new(limit))
+        });
+    let settings = Arc::new(BatchSettings {
+        count,
+        interval,
+        timestamp_format: timestamp_format.clone(),
+        image_format: master_config.application.image_format.clone(),
+        jpeg_quality: master_config.application.jpeg_quality,
+        png_compression: master_config.application.png_compression,
+        embed_metadata: master_config.application.embed_metadata.unwrap_or(true),
+        quality_checks: master_config.application.quality_checks.clone(),
+        dedup_max_distance,
+        base_output_dir: base_output_dir.clone(),
+        session_id: session.session_id.clone(),
+        cancel: ctx.cancel.clone(),
+        stream_limit,
+    });
 
+    let mut jobs = Vec::new();
+    let stream_key = params.stream_key.unwrap_or("capture-image");
     for device_arc in target_devices {
-        let output_dir_clone = output_dir.clone();
-        let ts_str_clone = ts_str.clone();
-        let image_format_for_device = master_config.application.image_format.clone();
-        let jpeg_quality_clone = master_config.application.jpeg_quality;
-        let png_compression_clone = master_config.application.png_compression;
-
-        capture_handles.push(tokio::spawn(async move {
-            let mut device_locked = device_arc.lock().await;
-            let device_name = device_locked.get_name();
-            let device_type = device_locked.get_type();
-            info!("Initiating capture for device: '{}' (Type: {})", device_name, device_type);
-            
-            device_locked.capture_image(
-                &output_dir_clone,
-                &ts_str_clone,
-                &image_format_for_device,
-                jpeg_quality_clone,
-                png_compression_clone,
-            ).await
-             .map_err(|e| {
-                error!("Capture failed for device '{}': {}", device_name, e);
-                e
-            })
-        }));
+        let name = {
+            let mut device = device_arc.lock().await;
+            let name = device.get_name();
+            let stream = match master_config.cameras.iter().find(|camera| *camera.get_name() == name) {
+                Some(CaptureDeviceConfig::IpCamera { specifics, .. }) => params.stream.clone().or_else(|| specifics.stream_for(&master_config.application, stream_key)),
+                _ => None,
+            };
+            device.select_stream(stream.as_deref());
+            name
+        };
+        jobs.push(DeviceJob {
+            output_dir: session.camera_dir(&name)?,
+            location: image_metadata::location_for(master_config, &name),
+            name,
+            device: device_arc,
+        });
     }
+    let mut outcomes = join_all(jobs.iter().map(|job| spawn_capture(job, &ts_str, settings.clone(), start_at))).await;
 
-    let capture_results_outer = join_all(capture_handles).await;
+    // Cameras that produced nothing are tried again once the batch is done. Their frames get
+    // their own timestamp and are marked out of sync in the manifest.
+    let mut retries_used = vec![0u32; jobs.len()];
+    for retry in 1..=retries {
+        let failed: Vec<usize> = (0..jobs.len()).filter(|&idx| needs_retry(&outcomes[idx])).collect();
+        if failed.is_empty() || ctx.cancel.is_cancelled() {
+            break;
+        }
+        for &idx in &failed {
+            let reason = match &outcomes[idx] {
+                Ok(capture) => capture.error.as_ref().map(|e| format!("{:#}", e)).unwrap_or_default(),
+                Err(e) => format!("capture task panicked: {}", e),
+            };
+            session.event("warn", Some(&jobs[idx].name), format!("attempt {} failed: {}; retrying", retry, reason));
+        }
+        info!("🔁 Retrying {} failed camera(s) in {:?} (retry {} of {})...", failed.len(), retry_delay, retry, retries);
+        tokio::select! {
+            _ = ctx.cancel.cancelled() => break,
+            _ = tokio::time::sleep(retry_delay) => {}
+        }
+        let retry_ts_str = Utc::now().format(&timestamp_format).to_string();
+        let retried = join_all(failed.iter().map(|&idx| spawn_capture(&jobs[idx], &retry_ts_str, settings.clone(), None))).await;
+        for (idx, outcome) in failed.into_iter().zip(retried) {
+            outcomes[idx] = outcome;
+            retries_used[idx] = retry;
+        }
+    }
     
     let mut successful_frame_data_bundles: Vec<FrameDataBundle> = Vec::new();
     let mut capture_errors_count = 0;
+    let mut timing_rows = Vec::new();
+    let event_log = rec_stream_opt.clone().map(rerun_setup::log_events);
 
-    for (idx, join_handle_result) in capture_results_outer.into_iter().enumerate() {
-        match join_handle_result {
-            Ok(capture_result_inner) => {
-                match capture_result_inner {
-                    Ok(frame_data_bundle) => {
-                        info!("Successfully captured data for device (index {}) -> {} frame(s) in bundle.", idx, frame_data_bundle.frames.len());
-                        successful_frame_data_bundles.push(frame_data_bundle);
+    for (idx, outcome) in outcomes.into_iter().enumerate() {
+        let device_name = jobs[idx].name.clone();
+        match outcome {
+            Ok(DeviceCapture { started_at, bundles, skipped, qualities, error: error_opt, elapsed }) => {
+                for message in skipped {
+                    session.event("info", Some(&device_name), message);
+                }
+                let mut quality_failures = Vec::new();
+                for (path, quality, violations) in qualities {
+                    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    for violation in &violations {
+                        session.event("warn", Some(&device_name), format!("quality: {}: {}", file_name, violation));
+                    }
+                    if !violations.is_empty() {
+                        quality_failures.push(format!("{}: {}", file_name, violations.join(", ")));
+                    }
+                    session.quality.insert(path, quality);
+                }
+                let fail_on_quality = settings.quality_checks.as_ref().is_some_and(|checks| checks.fails_capture());
+                let error_opt = match error_opt {
+                    None if fail_on_quality && !quality_failures.is_empty() => {
+                        Some(anyhow!("quality checks failed: {}", quality_failures.join("; ")))
+                    }
+                    other => other,
+                };
+                // Frames saved before a burst failed are kept and listed with the error.
+                let paths: Vec<PathBuf> = bundles.iter().flat_map(|b| b.paths()).collect();
+                // A burst reports its first frame, the only one that includes connecting.
+                let timings = bundles.first().map(|bundle| bundle.timings).filter(|timings| !timings.is_empty());
+                for bundle in &bundles {
+                    for path in bundle.paths() {
+                        events::publish(Event::FrameCaptured { camera: device_name.clone(), at: bundle.captured_at, path: Some(path) });
                     }
-                    Err(e) => {
-                        error!("Error during capture for device (index {}): {:?}", idx, e);
+                }
+                match error_opt {
+                    None => {
+                        info!("Successfully captured data for device '{}' -> {} capture(s), {} file(s).", device_name, bundles.len(), paths.len());
+                        session.record_camera(&device_name, Some(started_at), paths, None);
+                        report.push(CameraResult::success(device_name.clone()).took(elapsed));
+                    }
+                    Some(e) => {
+                        error!("Error during capture for device '{}': {:?}", device_name, e);
                         capture_errors_count += 1;
+                        session.record_camera(&device_name, Some(started_at), paths, Some(format!("{:#}", e)));
+                        report.push(CameraResult::from_error(device_name.clone(), &e).took(elapsed));
+                    }
+                }
+                if params.timing_report {
+                    if let Some(timings) = timings {
+                        session.set_timings(&device_name, timings);
                     }
+                    timing_rows.push((device_name.clone(), timings));
                 }
+                successful_frame_data_bundles.extend(bundles);
             }
             Err(e) => {
-                error!("JoinError for capture task (device index {}): {:?}", idx, e);
+                error!("JoinError for capture task (device '{}'): {:?}", device_name, e);
                 capture_errors_count += 1;
+                session.record_camera(&device_name, None, Vec::new(), Some(format!("capture task panicked: {}", e)));
+                report.push(CameraResult::failure(device_name.clone(), ErrorKind::Other, format!("capture task panicked: {}", e)));
+                if params.timing_report {
+                    timing_rows.push((device_name.clone(), None));
+                }
             }
         }
+        if retries_used[idx] > 0 {
+            session.mark_retried(&device_name, retries_used[idx]);
+        }
     }
+    if let Some(listener) = event_log {
+        listener.finish().await;
+    }
+    if params.timing_report {
+        timing_report::log_table(operation_display_name, &timing_rows);
+    }
+    checksum::record_session_checksums(&master_config.application, &mut session).await;
+    if let Err(e) = session.finish() {
+        warn!("⚠️ Could not write session manifest: {:#}", e);
+    }
+    let hooks_result = hooks::run_post_capture_hooks(&master_config.application, &mut session, "image").await;
     
     if capture_errors_count > 0 {
         warn!("Encountered {} error(s) during image capture from devices.", capture_errors_count);
     }
     if successful_frame_data_bundles.is_empty() && capture_errors_count > 0 {
         error!("All image capture attempts failed. Nothing to log to Rerun.");
-        return Err(anyhow!("All image capture attempts failed."));
+        report.log_summary();
+        // The report says why (status TotalFailure); the CLI exits with 1.
+        report.enforce_require_all(require_all)?;
+        return Ok(report);
     }
+    report.enforce_require_all(require_all)?;
     if successful_frame_data_bundles.is_empty() {
         info!("No images were successfully captured from any device. Nothing to log to Rerun.");
     }
@@ -157,13 +511,20 @@ pub async fn handle_capture_image_cli(
             info!("Rerun: Processing {} successful frame data bundle(s) for logging...", successful_frame_data_bundles.len());
         }
 
-        rec_stream.set_duration_secs("capture_op_time", op_start_time.elapsed().as_secs_f64());
+        let log_points = params.rerun_points.or(master_config.application.rerun_point_cloud).unwrap_or(false);
+        let point_stride = master_config.application.rerun_point_cloud_stride.unwrap_or(2);
 
         for frame_bundle in successful_frame_data_bundles {
+            // Frames sit on the wall-clock time they were captured, so runs and cameras line up.
+            rec_stream.set_timestamp_secs_since_epoch("capture_time", frame_bundle.captured_at.timestamp_micros() as f64 / 1e6);
             for frame_data_item in frame_bundle.frames {
                 match frame_data_item {
                     FrameData::IpCameraImage { name, path, format } => {
-                        let entity_path_str = format!("device/{}/image", name);
+                        let entity_path_str = format!("cameras/{}/image", name);
+                        if !matches!(format.to_lowercase().as_str(), "jpg" | "jpeg" | "png") {
+                            info!("Rerun: Skipping IP camera image for '{}' ({} is not an encoded image format Rerun can display).", name, format);
+                            continue;
+                        }
                         debug!("Rerun: Attempting to log IP camera image {} to entity path: {}", path.display(), entity_path_str);
                         
                         match std::fs::read(&path) {
@@ -192,10 +553,28 @@ pub async fn handle_capture_image_cli(
                             }
                         }
                     }
-                    FrameData::RealsenseFrames { name, color_frame, depth_frame } => {
+                    FrameData::RealsenseFrames { name, color_frame, depth_frame, .. } => {
+                        if let (true, Some(depth_info)) = (log_points, &depth_frame) {
+                            let entity_path_str = format!("cameras/{}/points", name);
+                            match depth_points::depth_to_points(depth_info, color_frame.as_ref(), point_stride) {
+                                Some((points, colors)) => {
+                                    let _ = rec_stream.log_static(format!("cameras/{}", name), &rerun::ViewCoordinates::RDF());
+                                    let point_count = points.len();
+                                    let cloud = rerun::Points3D::new(points).with_colors(colors.into_iter().map(|[r, g, b]| rerun::Color::from_rgb(r, g, b)));
+                                    if let Err(e) = rec_stream.log(&*entity_path_str, &cloud) {
+                                        error!("Rerun: Failed to log Realsense point cloud for {}: {}", name, e);
+                                    } else {
+                                        info!("Rerun: Logged Realsense point cloud for '{}' ({} points)", name, point_count);
+                                    }
+                                }
+                                None => warn!("Rerun: No depth intrinsics for '{}' (unavailable or post-processed); skipping point cloud.", name),
+                            }
+                        }
+
                         if let Some(color_info) = color_frame {
-                            let entity_path_str = format!("device/{}/rgb_image", name);
+                            let entity_path_str = format!("cameras/{}/color", name);
                             debug!("Rerun: Logging Realsense color image for '{}' to entity path: {}", name, entity_path_str);
+                            log_pinhole(rec_stream, &entity_path_str, color_info.intrinsics, color_info.width, color_info.height);
                             let tensor_data = TensorData::new(
                                 vec![color_info.height as u64, color_info.width as u64, 3],
                                 TensorBuffer::U8(color_info.rgb_data.into()),
@@ -213,8 +592,9 @@ pub async fn handle_capture_image_cli(
                         }
 
                         if let Some(depth_info) = depth_frame {
-                            let entity_path_str = format!("device/{}/depth_image", name);
+                            let entity_path_str = format!("cameras/{}/depth", name);
                             debug!("Rerun: Logging Realsense depth image for '{}' to entity path: {}", name, entity_path_str);
+                            log_pinhole(rec_stream, &entity_path_str, depth_info.intrinsics, depth_info.width, depth_info.height);
                             let tensor_data = TensorData::new(
                                 vec![
                                     depth_info.height.into(),
@@ -225,7 +605,8 @@ pub async fn handle_capture_image_cli(
 
                             match RerunDepthImage::try_from(tensor_data) {
                                 Ok(depth_archetype) => {
-                                    let depth_archetype_with_meter = depth_archetype.with_meter(depth_info.depth_units);
+                                    // Rerun's meter is depth steps per metre, the inverse of RealSense depth units.
+                                    let depth_archetype_with_meter = depth_archetype.with_meter(1.0 / depth_info.depth_units);
                                     
                                     if let Err(e) = rec_stream.log(&*entity_path_str, &depth_archetype_with_meter) {
                                         error!("Rerun: Failed to log Realsense depth image for {}: {}", name, e);
@@ -239,6 +620,35 @@ pub async fn handle_capture_image_cli(
                             }
                         }
                     }
+                    FrameData::ThermalImage { name, temperatures, paths } => {
+                        if let Some(jpeg_path) = paths.iter().find(|path| path.extension().is_some_and(|ext| ext == "jpg")) {
+                            match std::fs::read(jpeg_path) {
+                                Ok(image_bytes) => {
+                                    if let Err(e) = rec_stream.log(format!("cameras/{}/image", name), &RerunEncodedImage::from_file_contents(image_bytes)) {
+                                        error!("Rerun: Failed to log thermal image for {}: {}", name, e);
+                                    }
+                                }
+                                Err(e) => error!("Rerun: Failed to read thermal image file {} for logging: {}", jpeg_path.display(), e),
+                            }
+                        }
+                        if let Some(thermal) = temperatures {
+                            // Temperatures in °C; Rerun maps the float image through a colormap.
+                            let tensor_data = TensorData::new(
+                                vec![thermal.height as u64, thermal.width as u64],
+                                TensorBuffer::F32(thermal.celsius.into()),
+                            );
+                            match RerunImage::from_color_model_and_tensor(ColorModel::L, tensor_data) {
+                                Ok(rerun_image) => {
+                                    if let Err(e) = rec_stream.log(format!("cameras/{}/thermal", name), &rerun_image) {
+                                        error!("Rerun: Failed to log temperatures for {}: {}", name, e);
+                                    } else {
+                                        info!("Rerun: Logged temperatures for '{}'", name);
+                                    }
+                                }
+                                Err(e) => error!("Rerun: Failed to create thermal RerunImage for {}: {:?}", name, e),
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -248,9 +658,26 @@ pub async fn handle_capture_image_cli(
         info!("Rerun: Flush completed.");
     }
 
+    // Uploaded last: with storage.delete_local the files are gone afterwards, and Rerun reads them from disk.
+    if let Err(e) = storage::upload_session(&master_config.application, &mut session).await {
+        warn!("⚠️ Upload to remote storage incomplete: {:#}", e);
+    }
+
     info!("🖼️ All image capture operations completed in {:?}.", op_start_time.elapsed());
     if capture_errors_count > 0 {
          warn!("Finished with {} capture error(s). Please check logs.", capture_errors_count);
     }
-    Ok(())
-} 
\ No newline at end of file
+    report.log_summary();
+    hooks_result?;
+    Ok(report)
+} 
+
+/// Logs the camera model for an image entity so Rerun can place it in 3D; no-op without intrinsics.
+fn log_pinhole(rec_stream: &RecordingStream, entity_path: &str, intrinsics: Option<RsIntrinsics>, width: u32, height: u32) {
+    let Some(intr) = intrinsics else { return };
+    let pinhole = rerun::Pinhole::from_focal_length_and_resolution([intr.fx, intr.fy], [width as f32, height as f32])
+        .with_principal_point([intr.ppx, intr.ppy]);
+    if let Err(e) = rec_stream.log(entity_path, &pinhole) {
+        warn!("Rerun: Failed to log camera model for {}: {}", entity_path, e);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::camera::camera_controller::CameraController;
+use crate::camera::imaging::{DayNightMode, ImagingRequest, ImagingRequestConfig, ImagingSettings};
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::realsense_options::{self, RealsenseOptions, RealsenseOptionsConfig};
+use crate::common::{logging_setup, opencv_pool};
+use crate::config_loader::{CameraTimeouts, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use crate::operations::op_result::{CameraResult, OperationReport};
+use anyhow::Result;
+use crate::errors::{ErrorKind, RcamError};
+use crate::cli::SetImagingArgs;
+use futures::future::join_all;
+use log::{debug, info};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// Typed parameters for `rcam set`.
+#[derive(Debug, Clone, Default)]
+pub struct SetImagingParams {
+    pub cameras: Option<Vec<String>>, // None = all configured IP and RealSense cameras
+    pub overrides: ImagingRequestConfig, // applied on top of each camera's `imaging` config
+    pub realsense: RealsenseOptionsConfig, // applied on top of each RealSense camera's options
+    pub show_only: bool,
+    pub require_all: bool,
+}
+
+impl SetImagingParams {
+    pub fn from_args(args: &SetImagingArgs) -> Self {
+        SetImagingParams {
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+            overrides: ImagingRequestConfig {
+                exposure: args.exposure.clone(),
+                gain_db: args.gain,
+                white_balance: args.wb.clone(),
+                day_night: args.ir.clone(),
+            },
+            realsense: RealsenseOptionsConfig {
+                visual_preset: args.preset.clone(),
+                laser_power: args.laser_power,
+                emitter_enabled: args.emitter.as_deref().map(|state| state == "on"),
+                exposure: args.exposure.clone(),
+                inter_cam_sync_mode: args.sync_mode.clone(),
+            },
+            show_only: args.show,
+            require_all: args.require_all,
+        }
+    }
+}
+
+pub async fn handle_set_imaging_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &SetImagingArgs,
+) -> Result<OperationReport> {
+    let params = SetImagingParams::from_args(args);
+    debug!("Set imaging CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+/// Applies exposure/gain/white balance (ONVIF) and day/night mode (vendor driver) to IP cameras,
+/// and visual preset, laser power, emitter, exposure and sync mode to RealSense cameras, flags over
+/// per-camera config, and logs what each camera reports afterwards. Cameras with nothing to
+/// apply are only read.
+pub async fn execute(params: &SetImagingParams, ctx: &OperationContext<'_>) -> Result<OperationReport> {
+    let master_config = ctx.master_config;
+    let op_start_time = Instant::now();
+    let operation_display_name = "Set Imaging";
+    let mut report = OperationReport::new(operation_display_name);
+
+    // Validate the flags once up front rather than once per camera.
+    ImagingRequest::parse(&params.overrides)?;
+    RealsenseOptions::parse(&params.realsense)?;
+
+    let realsense_targets = op_helper::select_realsense_cameras(master_config, params.cameras.as_ref());
+    let ip_selection = params.cameras.as_ref().map(|names| names.iter()
+        .filter(|name| !realsense_targets.iter().any(|(rs_name, _)| rs_name == *name))
+        .cloned()
+        .collect::<Vec<_>>());
+    let targets = op_helper::select_ip_cameras(master_config, ip_selection.as_ref())?;
+    if targets.is_empty() && realsense_targets.is_empty() {
+        info!("No IP or RealSense cameras selected. Nothing to do.");
+        return Ok(report);
+    }
+
+    let controller = CameraController::new();
+    let tasks = targets.into_iter().map(|(name, specifics)| {
+        let controller = controller.clone();
+        let timeouts = CameraTimeouts::resolve(&master_config.application, Some(&specifics));
+        let configured = specifics.imaging.clone().unwrap_or_default();
+        let merged = ImagingRequestConfig {
+            exposure: params.overrides.exposure.clone().or(configured.exposure),
+            gain_db: params.overrides.gain_db.or(configured.gain_db),
+            white_balance: params.overrides.white_balance.clone().or(configured.white_balance),
+            day_night: params.overrides.day_night.clone().or(configured.day_night),
+        };
+        // Cameras with nothing to apply are only read; an invalid request fails below.
+        let read_only = params.show_only || ImagingRequest::parse(&merged).is_ok_and(|r| r.is_empty());
+        let span = logging_setup::camera_span(&name);
+        async move {
+            let started = Instant::now();
+            let result: Result<(Option<ImagingSettings>, Option<DayNightMode>)> = async {
+                let request = ImagingRequest::parse(&merged)?;
+                let device = IpCameraDevice::new(name.clone(), specifics, timeouts)?;
+                // Several SOAP round trips, each bounded by the HTTP timeout.
+                let deadline = timeouts.http * 6;
+                let work = async {
+                    let settings = if !read_only && request.has_onvif_changes() {
+                        Some(controller.set_imaging_settings(&device, &request).await?)
+                    } else if read_only {
+                        Some(controller.get_imaging_settings(&device).await?)
+                    } else {
+                        None
+                    };
+                    let day_night = match request.day_night {
+                        Some(mode) if !read_only => Some(controller.set_day_night(&device, mode).await?),
+                        _ if device.driver.supports_day_night() => Some(controller.get_day_night(&device).await?),
+                        _ => None,
+                    };
+                    Ok::<_, anyhow::Error>((settings, day_night))
+                };
+                tokio::time::timeout(deadline, work).await
+                    .map_err(|_| RcamError::for_camera(ErrorKind::Network, name.as_str(), format!("Timed out after {:?} talking to camera '{}'", deadline, name)))?
+            }.await;
+            (name, read_only, result, started.elapsed())
+        }
+        .instrument(span)
+    });
+
+    let reconnect_wait = Duration::from_secs_f32(master_config.application.realsense_reconnect_wait_secs.unwrap_or(3.0));
+    let realsense_tasks = realsense_targets.into_iter().map(|(name, specifics)| {
+        let merged = params.realsense.clone().or(RealsenseOptionsConfig::from_camera(&specifics));
+        let read_only = params.show_only || RealsenseOptions::parse(&merged).is_ok_and(|o| o.is_empty());
+        let span = logging_setup::camera_span(&name);
+        async move {
+            let started = Instant::now();
+            let result: Result<Vec<String>> = async {
+                let options = RealsenseOptions::parse(&merged)?;
+                let (camera_name, serial) = (name.clone(), specifics.serial_number.clone());
+                // Options go to the device directly, outside a pipeline; librealsense blocks.
+                opencv_pool::spawn(opencv_pool::pinned(specifics.cpu_affinity.clone(), move || {
+                    realsense_options::configure(&camera_name, serial.as_deref(), (!read_only).then_some(&options), reconnect_wait)
+                })).await?
+            }.await;
+            (name, read_only, result, started.elapsed())
+        }
+        .instrument(span)
+    });
+
+    let (ip_results, realsense_results) = futures::join!(join_all(tasks), join_all(realsense_tasks));
+    for (name, read_only, result, elapsed) in realsense_results {
+        match result {
+            Ok(parts) => {
+                info!("📷 '{}'{}: {}", name, if read_only { "" } else { " updated" }, parts.join(", "));
+                report.push(CameraResult::success(name).took(elapsed));
+            }
+            Err(e) => {
+                debug!("'{}' failed: {:#}", name, e);
+                report.push(CameraResult::from_error(name, &e).took(elapsed));
+            }
+        }
+    }
+    for (name, read_only, result, elapsed) in ip_results {
+        match result {
+            Ok((settings, day_night)) => {
+                let mut parts: Vec<String> = settings.iter().map(|s| s.to_string()).collect();
+                parts.extend(day_night.map(|mode| format!("day/night {}", mode)));
+                info!("📷 '{}'{}: {}", name, if read_only { "" } else { " updated" }, parts.join(", "));
+                report.push(CameraResult::success(name).took(elapsed));
+            }
+            Err(e) => {
+                debug!("'{}' failed: {:#}", name, e);
+                report.push(CameraResult::from_error(name, &e).took(elapsed));
+            }
+        }
+    }
+
+    info!("🔧 '{}' finished in {:?}.", operation_display_name, op_start_time.elapsed());
+    report.log_summary();
+    report.enforce_require_all(params.require_all)?;
+    Ok(report)
+}
@@ -0,0 +1,158 @@
+use crate::common::filename_pattern::FilenamePattern;
+use crate::common::session::{SessionCamera, SessionManifest};
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use crate::cli::ImportArgs;
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Filename layouts written by rcam itself; tried after any user patterns.
+const BUILTIN_PATTERNS: &[&str] = &[
+    "{timestamp}_realsense_{camera}_{*}.{ext}", // RealSense color/depth
+    "{camera}_{timestamp}.{ext}",               // IP camera images and videos
+    "{timestamp}_{*}_realsense_{camera}_{*}.{ext}", // RealSense burst frames
+    "{camera}_{timestamp}_{*}.{ext}",           // IP camera burst frames
+];
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "bmp", "tif", "tiff", "exr", "npy", "raw", "mp4", "avi", "mkv", "mov", "bag",
+];
+/// Unmatched files listed individually in the journal before they are only counted.
+const MAX_LOGGED_SKIPS: usize = 50;
+
+/// Typed parameters for importing existing media into the session index.
+#[derive(Debug, Clone)]
+pub struct ImportParams {
+    pub input_dir: PathBuf,
+    pub patterns: Vec<String>,            // tried in order before the built-in rcam layouts
+    pub timestamp_format: Option<String>, // None = AppSettings.filename_timestamp_format
+    pub session_id: Option<String>,       // None = "import_<now>"; suffixed if taken
+    pub recursive: bool,
+}
+
+impl ImportParams {
+    pub fn from_args(args: &ImportArgs) -> Self {
+        ImportParams {
+            input_dir: PathBuf::from(&args.input),
+            patterns: args.patterns.clone(),
+            timestamp_format: args.timestamp_format.clone(),
+            session_id: args.session.clone(),
+            recursive: args.recursive,
+        }
+    }
+}
+
+pub async fn handle_import_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &ImportArgs,
+) -> Result<()> {
+    let params = ImportParams::from_args(args);
+    debug!("Import CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await.map(|_| ())
+}
+
+/// Scans `input_dir` for media, attributes each file to a camera and time via the filename
+/// patterns (falling back to the file's modification time), and writes one session manifest.
+pub async fn execute(params: &ImportParams, ctx: &OperationContext<'_>) -> Result<SessionManifest> {
+    let op_start_time = Instant::now();
+    let app_config = &ctx.master_config.application;
+    if !params.input_dir.is_dir() {
+        bail!("Input directory {} does not exist", params.input_dir.display());
+    }
+    let timestamp_format = params.timestamp_format.as_deref().unwrap_or(&app_config.filename_timestamp_format);
+    let pattern_sources = params.patterns.iter().map(String::as_str)
+        .chain(app_config.import_patterns.iter().flatten().map(String::as_str))
+        .chain(BUILTIN_PATTERNS.iter().copied());
+    let patterns = pattern_sources
+        .map(|p| FilenamePattern::parse(p, timestamp_format))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut files = Vec::new();
+    op_helper::collect_files(&params.input_dir, params.recursive, &mut files)?;
+    files.retain(|path| path.extension().and_then(|e| e.to_str())
+        .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str())));
+    files.sort();
+    info!("📥 Importing {} media file(s) from {}.", files.len(), params.input_dir.display());
+
+    let base_dir = Path::new(&app_config.output_directory_base);
+    let mut manifest = match &params.session_id {
+        Some(id) => SessionManifest::create_named(ctx.master_config, base_dir, id, "import", "Import")?,
+        None => SessionManifest::create(ctx.master_config, base_dir, "import", "Import")?,
+    };
+    manifest.event("info", None, format!("Imported from {}; files stay in place", params.input_dir.display()));
+    let mut per_camera: BTreeMap<String, Vec<(DateTime<Utc>, PathBuf)>> = BTreeMap::new();
+    let mut skipped = 0usize;
+    let mut mtime_fallbacks = 0usize;
+
+    for path in files {
+        let filename = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let matched = patterns.iter().find_map(|p| p.matches(&filename).filter(|m| m.camera.is_some()));
+        let Some(matched) = matched else {
+            if skipped < MAX_LOGGED_SKIPS {
+                manifest.event("warn", None, format!("No pattern matched {}", path.display()));
+            }
+            skipped += 1;
+            continue;
+        };
+        let timestamp = match matched.timestamp {
+            Some(ts) => ts,
+            None => {
+                mtime_fallbacks += 1;
+                std::fs::metadata(&path).and_then(|m| m.modified()).map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now())
+            }
+        };
+        per_camera.entry(matched.camera.unwrap_or_default()).or_default().push((timestamp, path));
+    }
+
+    let known: Vec<&String> = ctx.master_config.cameras.iter().map(|c| c.get_name()).collect();
+    for (camera, mut entries) in per_camera {
+        entries.sort();
+        if !known.contains(&&camera) {
+            manifest.event("info", Some(&camera), "Camera is not in the current configuration");
+        }
+        info!("  📷 {}: {} file(s), {} .. {}", camera, entries.len(), entries[0].0, entries[entries.len() - 1].0);
+        manifest.cameras.push(SessionCamera {
+            name: camera,
+            started_at: entries.first().map(|(ts, _)| *ts),
+            finished_at: entries.last().map(|(ts, _)| *ts),
+            files: entries.into_iter().map(|(_, path)| path).collect(),
+            error: None,
+            retries: 0,
+            out_of_sync: false,
+            timings: None,
+            recorded_secs: None,
+            gaps: Vec::new(),
+            stream: None,
+            degraded: None,
+        });
+    }
+    if skipped > 0 {
+        warn!("⚠️ {} file(s) matched no filename pattern and were not imported.", skipped);
+        manifest.event("warn", None, format!("{} file(s) matched no filename pattern", skipped));
+    }
+    if mtime_fallbacks > 0 {
+        manifest.event("info", None, format!("{} file(s) had no timestamp in the name; used modification time", mtime_fallbacks));
+    }
+    if manifest.cameras.is_empty() {
+        let _ = std::fs::remove_dir_all(&manifest.output_dir);
+        bail!("No files in {} matched a filename pattern; pass --pattern (e.g. \"{{camera}}-{{timestamp}}.{{ext}}\")", params.input_dir.display());
+    }
+
+    // The session spans the imported media, not the time the import ran.
+    if let Some(first) = manifest.cameras.iter().filter_map(|c| c.started_at).min() {
+        manifest.started_at = first;
+    }
+    manifest.finished_at = manifest.cameras.iter().filter_map(|c| c.finished_at).max();
+    manifest.write()?;
+    info!(
+        "✅ Imported {} file(s) from {} camera(s) as session '{}' in {:?}.",
+        manifest.cameras.iter().map(|c| c.files.len()).sum::<usize>(), manifest.cameras.len(), manifest.session_id, op_start_time.elapsed()
+    );
+    Ok(manifest)
+}
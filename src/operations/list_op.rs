@@ -0,0 +1,275 @@
+use crate::camera::auth::{self, AuthScheme};
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::stream_stats::StreamSummary;
+use crate::camera::thermal_device::ThermalDevice;
+use crate::camera::virtual_camera;
+use crate::cli::ListArgs;
+use crate::common::session::SessionManifest;
+use crate::config_loader::{CameraTimeouts, CaptureDeviceConfig, IpCameraSpecificConfig, MasterConfig, ThermalCameraSpecificConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+/// Typed parameters for `rcam list`.
+#[derive(Debug, Clone, Default)]
+pub struct ListParams {
+    pub cameras: Option<Vec<String>>, // None = every configured device
+    pub json: bool,                   // print JSON to stdout instead of logging a table
+}
+
+impl ListParams {
+    pub fn from_args(args: &ListArgs) -> Self {
+        ListParams { cameras: op_helper::parse_camera_selection(args.cameras.as_ref()), json: args.json }
+    }
+}
+
+/// One configured device as rcam sees it, without contacting it.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceListing {
+    pub name: String,
+    pub kind: String,           // "ip", "realsense", "thermal", "virtual" or a custom device's type
+    pub vendor: Option<String>, // IP and thermal cameras: the vendor driver in use
+    pub endpoints: Vec<String>, // RTSP and HTTP URLs (passwords masked), the RealSense serial or a virtual camera's source
+    pub credentials: String,    // which secrets are needed and whether they were found
+    pub credentials_ok: bool,
+    pub last_health: Option<LastHealth>,
+    pub last_stream: Option<StreamSummary>, // frame rate and bitrate of the newest recording
+}
+
+/// The device's outcome in the most recent session that included it.
+#[derive(Debug, Clone, Serialize)]
+pub struct LastHealth {
+    pub ok: bool,
+    pub at: DateTime<Utc>,
+    pub session: String,
+    pub error: Option<String>,
+}
+
+pub async fn handle_list_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &ListArgs,
+) -> Result<()> {
+    let params = ListParams::from_args(args);
+    debug!("List CLI parsed into: {:?}", params);
+    let listings = execute(&params, &OperationContext::new(master_config, camera_manager)).await?;
+    if params.json {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &listings).context("Failed to write the device list")?;
+        writeln!(stdout).context("Failed to write the device list")?;
+    }
+    Ok(())
+}
+
+/// Lists the configured devices with their endpoints, credential status and last known health
+/// (from the session manifests under the output directory). Nothing is contacted.
+pub async fn execute(params: &ListParams, ctx: &OperationContext<'_>) -> Result<Vec<DeviceListing>> {
+    let master_config = ctx.master_config;
+    let (health, streams) = history(Path::new(&master_config.application.output_directory_base));
+    let listings: Vec<DeviceListing> = master_config.cameras.iter()
+        .filter(|camera| params.cameras.as_ref().is_none_or(|selected| selected.contains(camera.get_name())))
+        .map(|camera| {
+            let name = camera.get_name().clone();
+            let mut listing = match camera {
+                CaptureDeviceConfig::IpCamera { specifics, .. } => ip_camera_listing(&name, specifics, master_config),
+                CaptureDeviceConfig::ThermalCamera { specifics, .. } => thermal_camera_listing(&name, specifics, master_config),
+                CaptureDeviceConfig::VirtualCamera { specifics, .. } => DeviceListing {
+                    name: name.clone(),
+                    kind: "virtual".to_string(),
+                    vendor: None,
+                    endpoints: vec![virtual_camera::describe(specifics)],
+                    credentials: "not needed".to_string(),
+                    credentials_ok: true,
+                    last_health: None,
+                    last_stream: None,
+                },
+                CaptureDeviceConfig::Custom { device_type, .. } => DeviceListing {
+                    name: name.clone(),
+                    kind: device_type.clone(),
+                    vendor: None,
+                    endpoints: Vec::new(), // only the registered factory knows
+                    credentials: "not checked".to_string(),
+                    credentials_ok: true,
+                    last_health: None,
+                    last_stream: None,
+                },
+                CaptureDeviceConfig::RealsenseCamera { specifics, .. } => DeviceListing {
+                    name: name.clone(),
+                    kind: "realsense".to_string(),
+                    vendor: None,
+                    endpoints: vec![match (&specifics.bag_file, &specifics.serial_number) {
+                        (Some(bag), _) => format!("playback of {}", bag),
+                        (None, Some(serial)) => format!("usb serial {}", serial),
+                        (None, None) => "usb (first RealSense found)".to_string(),
+                    }],
+                    credentials: "not needed".to_string(),
+                    credentials_ok: true,
+                    last_health: None,
+                    last_stream: None,
+                },
+            };
+            listing.last_health = health.get(&name).cloned();
+            listing.last_stream = streams.get(&name).map(|(_, stream)| *stream);
+            listing
+        })
+        .collect();
+    if let Some(selected) = &params.cameras {
+        for name in selected.iter().filter(|name| !listings.iter().any(|l| &l.name == *name)) {
+            warn!("⚠️ '{}' is not a configured device.", name);
+        }
+    }
+    if !params.json {
+        log_table(&listings);
+    }
+    Ok(listings)
+}
+
+fn thermal_camera_listing(name: &str, specifics: &ThermalCameraSpecificConfig, master_config: &MasterConfig) -> DeviceListing {
+    let timeouts = CameraTimeouts::resolve_thermal(&master_config.application, specifics);
+    let device = ThermalDevice::new(name.to_string(), specifics.clone(), timeouts);
+    let scheme = AuthScheme::parse(specifics.auth.as_deref()).unwrap_or(AuthScheme::Digest);
+    // Thermal cameras only speak HTTP, so the auth scheme decides which secret is needed.
+    let needed = match scheme {
+        AuthScheme::None => None,
+        AuthScheme::Bearer => Some(auth::token_env_var(name)),
+        AuthScheme::Digest | AuthScheme::Basic => Some(auth::password_env_var(name)),
+    };
+    let mut problems = Vec::new();
+    if specifics.username.is_none() && matches!(scheme, AuthScheme::Digest | AuthScheme::Basic) {
+        problems.push("no username".to_string());
+    }
+    if let Some(var) = needed.as_ref().filter(|var| !std::env::var(var).is_ok_and(|value| !value.is_empty())) {
+        problems.push(format!("{} missing", var));
+    }
+    let credentials = match (&needed, problems.is_empty()) {
+        (_, false) => problems.join(", "),
+        (Some(var), true) => format!("ok ({})", var),
+        (None, true) => "not needed".to_string(),
+    };
+
+    DeviceListing {
+        name: name.to_string(),
+        kind: "thermal".to_string(),
+        vendor: device.as_ref().ok().map(|d| d.settings.vendor.name().to_string()),
+        endpoints: device.as_ref().map(|d| vec![d.snapshot_url()]).unwrap_or_default(),
+        credentials_ok: problems.is_empty(),
+        credentials,
+        last_health: None,
+        last_stream: None,
+    }
+}
+
+fn ip_camera_listing(name: &str, specifics: &IpCameraSpecificConfig, master_config: &MasterConfig) -> DeviceListing {
+    let timeouts = CameraTimeouts::resolve(&master_config.application, Some(specifics));
+    let device = IpCameraDevice::new(name.to_string(), specifics.clone(), timeouts);
+    let has_http_api = device.as_ref().is_none_or(|d| d.driver.has_http_api());
+
+    let port = specifics.rtsp_port.unwrap_or(554);
+    let user = specifics.username.as_deref().map(|u| format!("{}:***@", u)).unwrap_or_default();
+    let mut endpoints = vec![match specifics.rtsp_path.as_deref().filter(|p| !p.is_empty()) {
+        Some(path) => format!("rtsp://{}{}:{}{}{}", user, specifics.ip, port, if path.starts_with('/') { "" } else { "/" }, path),
+        None => format!("rtsp://{}{}:{} (path from ONVIF, stream '{}')", user, specifics.ip, port, specifics.stream.as_deref().unwrap_or("main")),
+    }];
+    if let Ok(device) = &device {
+        if has_http_api {
+            endpoints.push(device.snapshot_url());
+        }
+    }
+
+    // RTSP always needs the password; HTTP needs what the auth scheme asks for.
+    let mut needed = vec![auth::password_env_var(name)];
+    let scheme = AuthScheme::parse(specifics.auth.as_deref()).unwrap_or(AuthScheme::Digest);
+    if has_http_api && scheme == AuthScheme::Bearer {
+        needed.push(auth::token_env_var(name));
+    }
+    let mut problems = Vec::new();
+    if specifics.username.is_none() {
+        problems.push("no username".to_string());
+    }
+    let mut found = Vec::new();
+    for var in needed {
+        if std::env::var(&var).is_ok_and(|value| !value.is_empty()) {
+            found.push(var);
+        } else {
+            problems.push(format!("{} missing", var));
+        }
+    }
+    let credentials = if problems.is_empty() {
+        format!("ok ({})", found.join(", "))
+    } else {
+        problems.join(", ")
+    };
+
+    DeviceListing {
+        name: name.to_string(),
+        kind: "ip".to_string(),
+        vendor: device.as_ref().ok().map(|d| d.driver.name().to_string()),
+        endpoints,
+        credentials_ok: problems.is_empty(),
+        credentials,
+        last_health: None,
+        last_stream: None,
+    }
+}
+
+/// Each camera's outcome in the newest session that included it, and the stream rates of its
+/// newest recording.
+fn history(output_dir: &Path) -> (HashMap<String, LastHealth>, HashMap<String, (DateTime<Utc>, StreamSummary)>) {
+    let manifests = match SessionManifest::find_all(output_dir) {
+        Ok(manifests) => manifests,
+        Err(e) => {
+            debug!("No session history for health: {:#}", e);
+            return (HashMap::new(), HashMap::new());
+        }
+    };
+    let mut health: HashMap<String, LastHealth> = HashMap::new();
+    let mut streams: HashMap<String, (DateTime<Utc>, StreamSummary)> = HashMap::new();
+    for (_, manifest) in manifests {
+        for camera in &manifest.cameras {
+            let at = camera.finished_at.or(manifest.finished_at).unwrap_or(manifest.started_at);
+            if let Some(stream) = camera.stream {
+                if streams.get(&camera.name).is_none_or(|(known, _)| *known < at) {
+                    streams.insert(camera.name.clone(), (at, stream));
+                }
+            }
+            if health.get(&camera.name).is_none_or(|known| known.at < at) {
+                health.insert(camera.name.clone(), LastHealth {
+                    ok: camera.error.is_none(),
+                    at,
+                    session: manifest.session_id.clone(),
+                    error: camera.error.clone(),
+                });
+            }
+        }
+    }
+    (health, streams)
+}
+
+fn log_table(listings: &[DeviceListing]) {
+    info!("📋 {} configured device(s):", listings.len());
+    let width = listings.iter().map(|l| l.name.len()).max().unwrap_or(0).max("device".len());
+    info!("  {:<width$} | {:<9} | {:<10} | {:<32} | {}", "device", "type", "vendor", "credentials", "last health", width = width);
+    for listing in listings {
+        let health = match &listing.last_health {
+            None => "unknown (no session yet)".to_string(),
+            Some(h) if h.ok => format!("✅ ok at {} ({})", h.at.format("%Y-%m-%d %H:%M:%S UTC"), h.session),
+            Some(h) => format!("❌ {} at {} ({})", h.error.as_deref().unwrap_or("failed"), h.at.format("%Y-%m-%d %H:%M:%S UTC"), h.session),
+        };
+        let credentials = if listing.credentials_ok { listing.credentials.clone() } else { format!("⚠️ {}", listing.credentials) };
+        info!("  {:<width$} | {:<9} | {:<10} | {:<32} | {}", listing.name, listing.kind, listing.vendor.as_deref().unwrap_or("-"), credentials, health, width = width);
+        for endpoint in &listing.endpoints {
+            info!("  {:<width$}   ↳ {}", "", endpoint, width = width);
+        }
+        if let Some(stream) = &listing.last_stream {
+            let degraded = if stream.degraded_secs > 0 { format!(", ⚠️ {}s below min_stream_kbps", stream.degraded_secs) } else { String::new() };
+            info!("  {:<width$}   ↳ last recording: {:.1} fps, {:.0} kbps (lowest {:.0}){}", "", stream.avg_fps, stream.avg_kbps, stream.min_kbps, degraded, width = width);
+        }
+    }
+}
@@ -2,4 +2,23 @@ pub mod image_capture_op;
 pub mod video_record_op;
 pub mod time_sync_op;
 pub mod diagnostic_op;
-pub mod op_helper;
\ No newline at end of file
+pub mod soak_op;
+pub mod convert_op;
+pub mod coverage_op;
+pub mod import_op;
+pub mod list_op;
+pub mod discover_op;
+pub mod imaging_op;
+pub mod control_op;
+pub mod calibrate_op;
+pub mod report_op;
+pub mod daemon_op;
+pub mod validate_op;
+pub mod verify_archive_op;
+pub mod agent_op;
+pub mod aim_op;
+pub mod coordinator_op;
+pub mod recording_control_op;
+pub mod op_context;
+pub mod op_helper;
+pub mod op_result;
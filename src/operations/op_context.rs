@@ -0,0 +1,19 @@
+use crate::common::shutdown;
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use tokio_util::sync::CancellationToken;
+
+/// Shared state every operation runs against, independent of how it was invoked
+/// (CLI, diagnostics, or a future API/scheduler).
+#[derive(Clone)]
+pub struct OperationContext<'a> {
+    pub master_config: &'a MasterConfig,
+    pub camera_manager: &'a CameraManager,
+    pub cancel: CancellationToken, // cancelled on Ctrl-C; long-running loops stop early and finalize
+}
+
+impl<'a> OperationContext<'a> {
+    pub fn new(master_config: &'a MasterConfig, camera_manager: &'a CameraManager) -> Self {
+        OperationContext { master_config, camera_manager, cancel: shutdown::token() }
+    }
+}
@@ -1,51 +1,95 @@
-use crate::config_loader::MasterConfig;
+use crate::camera::discovery::{self, MacResolution};
+use crate::config_loader::{CaptureDeviceConfig, IpCameraSpecificConfig, MasterConfig, RealsenseSpecificConfig};
 use crate::core::camera_manager::CameraManager;
 use crate::core::capture_source::CaptureSource;
-use anyhow::{Context, Result};
-use clap::ArgMatches;
+use anyhow::{Context, Result, bail};
 use log::{info, debug, warn};
-use std::path::PathBuf;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::time::Instant;
 
-/// Determines the target cameras based on CLI arguments or all available cameras.
+/// Parses a `--cameras` value into a list of names. `None` (or "all") selects every camera.
+pub fn parse_camera_selection(specific_devices_arg: Option<&String>) -> Option<Vec<String>> {
+    let specific_names_str = specific_devices_arg?;
+    if specific_names_str.to_lowercase() == "all" {
+        return None;
+    }
+    Some(
+        specific_names_str
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    )
+}
+
+/// Parses a duration such as "24h", "90m", "1h30m", "45s" or "2d"; a bare number is seconds.
+pub fn parse_duration(value: &str) -> Result<std::time::Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(std::time::Duration::from_secs(secs));
+    }
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => bail!("'{}' is not a duration (use e.g. 24h, 90m, 1h30m or 45s)", value),
+        };
+        let amount: u64 = number.parse().with_context(|| format!("'{}' is not a duration (a number must precede '{}')", value, c))?;
+        total += amount * unit;
+        number.clear();
+    }
+    if !number.is_empty() || total == 0 {
+        bail!("'{}' is not a duration (use e.g. 24h, 90m, 1h30m or 45s)", value);
+    }
+    Ok(std::time::Duration::from_secs(total))
+}
+
+/// Determines the target cameras from an explicit selection or all available cameras.
 pub async fn determine_target_devices(
     camera_manager: &CameraManager,
-    specific_devices_arg: Option<&String>,
+    camera_selection: Option<&[String]>,
     operation_display_name: &str,
+    require_all: bool,
 ) -> Result<Vec<Arc<Mutex<dyn CaptureSource + Send>>>> {
     debug!(
-        "Determining target devices for '{}'. Specific devices arg: {:?}",
+        "Determining target devices for '{}'. Camera selection: {:?}",
         operation_display_name,
-        specific_devices_arg
+        camera_selection
     );
 
     let devices_to_target: Vec<Arc<Mutex<dyn CaptureSource + Send>>>;
 
-    if let Some(specific_names_str) = specific_devices_arg {
-        if specific_names_str.to_lowercase() == "all" {
-            info!(
-                "Targeting all available/configured devices for '{}'.",
-                operation_display_name
-            );
-            devices_to_target = camera_manager.get_all_devices().await;
-        } else {
-            let device_names: Vec<String> = specific_names_str
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            info!(
-                "Targeting specific devices for '{}': {:?}",
+    if let Some(device_names) = camera_selection {
+        info!(
+            "Targeting specific devices for '{}': {:?}",
+            operation_display_name,
+            device_names
+        );
+        devices_to_target = camera_manager.get_devices_by_names(device_names).await;
+        if require_all && devices_to_target.len() != device_names.len() {
+            bail!(
+                "--require-all: only {} of {} requested device(s) are configured for '{}' ({:?})",
+                devices_to_target.len(),
+                device_names.len(),
                 operation_display_name,
                 device_names
             );
-            devices_to_target = camera_manager.get_devices_by_names(&device_names).await;
         }
     } else {
-        warn!(
-            "No specific devices argument provided for '{}'. Defaulting to all available devices.",
+        info!(
+            "No specific devices selected for '{}'. Targeting all available/configured devices.",
             operation_display_name
         );
         devices_to_target = camera_manager.get_all_devices().await;
@@ -69,16 +113,15 @@ pub async fn determine_target_devices(
 /// Determines and creates the operation's base output directory.
 pub fn determine_operation_output_dir(
     master_config: &MasterConfig,
-    args: &ArgMatches,
-    output_cli_arg_key: &str,
+    output_override: Option<&Path>,
     default_output_subdir: Option<&str>,
     operation_display_name: &str, // For logging context
 ) -> Result<PathBuf> {
     let output_dir_determine_start = Instant::now();
-    let operation_base_output_dir: PathBuf = match args.get_one::<String>(output_cli_arg_key) {
-        Some(path_str) => {
-            debug!("  Output directory specified via CLI for '{}': {}", operation_display_name, path_str);
-            PathBuf::from(path_str)
+    let operation_base_output_dir: PathBuf = match output_override {
+        Some(path) => {
+            debug!("  Output directory specified for '{}': {}", operation_display_name, path.display());
+            path.to_path_buf()
         }
         None => {
             let mut dir = PathBuf::from(&master_config.application.output_directory_base);
@@ -106,4 +149,75 @@ pub fn determine_operation_output_dir(
         info!("ℹ️ Using existing output directory: {} for '{}'", operation_base_output_dir.display(), operation_display_name);
     }
     Ok(operation_base_output_dir)
-}
\ No newline at end of file
+}
+
+/// Lists files under `dir`, descending into subdirectories when `recursive`.
+pub fn collect_files(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                collect_files(&path, recursive, out)?;
+            }
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Configured IP cameras matching `selection` (None = all). Unknown names are an error rather
+/// than silently skipped, since these operations change camera state.
+pub fn select_ip_cameras(master_config: &MasterConfig, selection: Option<&Vec<String>>) -> Result<Vec<(String, IpCameraSpecificConfig)>> {
+    let mut targets = Vec::new();
+    for camera in &master_config.cameras {
+        let selected = selection.is_none_or(|names| names.contains(camera.get_name()));
+        if let (true, CaptureDeviceConfig::IpCamera { name, specifics, .. }) = (selected, camera) {
+            targets.push((name.clone(), specifics.clone()));
+        }
+    }
+    if let Some(names) = selection {
+        let unknown: Vec<&String> = names.iter().filter(|n| !targets.iter().any(|(t, _)| t == *n)).collect();
+        if !unknown.is_empty() {
+            bail!("No IP camera named {:?} in the configuration", unknown);
+        }
+    }
+    Ok(targets)
+}
+
+/// The selected RealSense devices (not `.bag` playbacks); names of other kinds are left to other selectors.
+pub fn select_realsense_cameras(master_config: &MasterConfig, selection: Option<&Vec<String>>) -> Vec<(String, RealsenseSpecificConfig)> {
+    master_config.cameras.iter()
+        .filter(|camera| selection.is_none_or(|names| names.contains(camera.get_name())))
+        .filter_map(|camera| match camera {
+            CaptureDeviceConfig::RealsenseCamera { name, specifics } if specifics.bag_file.is_none() => Some((name.clone(), specifics.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the named IP cameras that have a `mac_address` on the network, so a camera whose
+/// DHCP lease changed is still reached. Record the result with `SessionManifest::set_resolved_ip`.
+pub async fn resolve_by_mac(master_config: &MasterConfig, names: &[String]) -> Vec<MacResolution> {
+    let cameras: Vec<(String, IpCameraSpecificConfig)> = master_config.cameras.iter()
+        .filter(|camera| names.contains(camera.get_name()))
+        .filter_map(|camera| match camera {
+            CaptureDeviceConfig::IpCamera { name, specifics } if specifics.mac_address.is_some() => Some((name.clone(), specifics.clone())),
+            _ => None,
+        })
+        .collect();
+    discovery::resolve_cameras(&cameras).await
+}
+
+/// Asks a yes/no question on the terminal. Without a terminal there is nobody to ask,
+/// so this fails and points at `--yes`.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!("Refusing to continue without confirmation: stdin is not a terminal. Pass --yes to skip the prompt.");
+    }
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,179 @@
+use crate::common::atomic_file;
+use crate::errors::{self, ErrorKind};
+use anyhow::{Context, Result};
+use log::{info, warn, error};
+use serde::{Serialize, Serializer};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Outcome of an operation for a single camera/device.
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraResult {
+    pub name: String,
+    pub error: Option<String>,
+    #[serde(rename = "error_kind")]
+    pub kind: Option<ErrorKind>, // set with `error`
+    #[serde(rename = "duration_secs", serialize_with = "serialize_secs")]
+    pub duration: Option<Duration>, // this camera's part of the operation, where measured
+}
+
+fn serialize_secs<S: Serializer>(duration: &Option<Duration>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    duration.map(|d| d.as_secs_f64()).serialize(serializer)
+}
+
+impl CameraResult {
+    pub fn success(name: impl Into<String>) -> Self {
+        CameraResult { name: name.into(), error: None, kind: None, duration: None }
+    }
+
+    pub fn failure(name: impl Into<String>, kind: ErrorKind, error: impl std::fmt::Display) -> Self {
+        CameraResult { name: name.into(), error: Some(format!("{:#}", error)), kind: Some(kind), duration: None }
+    }
+
+    pub fn took(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// A failure whose kind is read from the error.
+    pub fn from_error(name: impl Into<String>, error: &anyhow::Error) -> Self {
+        Self::failure(name, errors::classify(error), error)
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    AllSucceeded,
+    PartialSuccess,
+    TotalFailure,
+}
+
+impl OperationStatus {
+    /// Process exit code for this status: 0 all succeeded, 2 partial success, 1 total failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            OperationStatus::AllSucceeded => 0,
+            OperationStatus::PartialSuccess => 2,
+            OperationStatus::TotalFailure => 1,
+        }
+    }
+}
+
+/// Structured per-camera results of a capture operation.
+#[derive(Debug, Clone)]
+pub struct OperationReport {
+    pub operation: String,
+    pub results: Vec<CameraResult>,
+    pub session: Option<PathBuf>, // session directory the operation wrote, if any
+}
+
+impl OperationReport {
+    pub fn new(operation: impl Into<String>) -> Self {
+        OperationReport { operation: operation.into(), results: Vec::new(), session: None }
+    }
+
+    pub fn push(&mut self, result: CameraResult) {
+        self.results.push(result);
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|r| r.is_success()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.succeeded()
+    }
+
+    pub fn status(&self) -> OperationStatus {
+        match (self.succeeded(), self.failed()) {
+            (_, 0) => OperationStatus::AllSucceeded,
+            (0, _) => OperationStatus::TotalFailure,
+            _ => OperationStatus::PartialSuccess,
+        }
+    }
+
+    /// Logs one row per camera (outcome, error kind, duration, error), then the overall status.
+    pub fn log_summary(&self) {
+        if self.results.is_empty() {
+            return;
+        }
+        let width = self.results.iter().map(|r| r.name.len()).max().unwrap_or(0).max(6);
+        info!("📋 ----- {} Summary -----", self.operation);
+        info!("     {:<width$} | {:<7} | {:>8} | error", "camera", "result", "time", width = width);
+        for result in &self.results {
+            let time = result.duration.map(|d| format!("{:.1}s", d.as_secs_f64())).unwrap_or_else(|| "-".to_string());
+            match &result.error {
+                None => info!("  ✅ {:<width$} | {:<7} | {:>8} |", result.name, "ok", time, width = width),
+                Some(e) => error!("  ❌ {:<width$} | {:<7} | {:>8} | {}", result.name,
+                    result.kind.unwrap_or(ErrorKind::Other).name(), time, e, width = width),
+            }
+        }
+        match self.status() {
+            OperationStatus::AllSucceeded => info!("'{}': all {} camera(s) succeeded.", self.operation, self.results.len()),
+            OperationStatus::PartialSuccess => warn!("'{}': partial success, {} of {} camera(s) failed.", self.operation, self.failed(), self.results.len()),
+            OperationStatus::TotalFailure => error!("'{}': all {} camera(s) failed.", self.operation, self.results.len()),
+        }
+    }
+
+    /// Writes the report as JSON to `path`, or to stdout for "-", for scripts that wrap rcam.
+    pub fn write_json(&self, path: &str) -> Result<()> {
+        #[derive(Serialize)]
+        struct Summary<'a> {
+            operation: &'a str,
+            status: OperationStatus,
+            succeeded: usize,
+            failed: usize,
+            cameras: &'a [CameraResult],
+            session: Option<&'a Path>,
+        }
+        let summary = Summary {
+            operation: &self.operation,
+            status: self.status(),
+            succeeded: self.succeeded(),
+            failed: self.failed(),
+            cameras: &self.results,
+            session: self.session.as_deref(),
+        };
+        let mut json = serde_json::to_vec_pretty(&summary).context("Failed to serialize the operation summary")?;
+        json.push(b'\n');
+        if path == "-" {
+            std::io::stdout().write_all(&json).context("Failed to write the operation summary to stdout")?;
+        } else {
+            atomic_file::write_bytes(Path::new(path), &json)?;
+        }
+        Ok(())
+    }
+
+    /// Errors out when `--require-all` is set and any camera failed. The error is a
+    /// `RequireAllFailed` carrying the report, so callers can still write the summary.
+    pub fn enforce_require_all(&self, require_all: bool) -> Result<()> {
+        if require_all && self.failed() > 0 {
+            return Err(RequireAllFailed { report: self.clone() }.into());
+        }
+        Ok(())
+    }
+}
+
+/// `--require-all` was set and some cameras failed; `report` has the per-camera results.
+#[derive(Debug)]
+pub struct RequireAllFailed {
+    pub report: OperationReport,
+}
+
+impl std::fmt::Display for RequireAllFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let failed_names: Vec<&str> = self.report.results.iter()
+            .filter(|r| !r.is_success())
+            .map(|r| r.name.as_str())
+            .collect();
+        write!(f, "--require-all: {} camera(s) failed in '{}': {:?}", failed_names.len(), self.report.operation, failed_names)
+    }
+}
+
+impl std::error::Error for RequireAllFailed {}
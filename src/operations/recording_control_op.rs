@@ -0,0 +1,106 @@
+use crate::cli::RecordingControlArgs;
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::operations::agent_op::DEFAULT_LISTEN;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use crate::server::protocol::{ErrorBody, PauseState, StopReply, StopRequest, AGENT_TOKEN_ENV, API_PREFIX};
+use anyhow::{bail, Context, Result};
+use log::{debug, info, warn};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordingAction {
+    Pause,
+    Resume,
+    Stop,
+}
+
+impl RecordingAction {
+    fn endpoint(&self) -> &'static str {
+        match self {
+            RecordingAction::Pause => "pause",
+            RecordingAction::Resume => "resume",
+            RecordingAction::Stop => "stop-recording",
+        }
+    }
+}
+
+/// Typed parameters for `rcam pause` / `rcam resume` / `rcam stop-recording`.
+#[derive(Debug, Clone)]
+pub struct RecordingControlParams {
+    pub action: RecordingAction,
+    pub agent: Option<String>,        // agent base URL; None = this host's agent (agent.listen)
+    pub cameras: Option<Vec<String>>, // stop only; None = all recordings
+}
+
+impl RecordingControlParams {
+    pub fn from_args(action: RecordingAction, args: &RecordingControlArgs) -> Self {
+        RecordingControlParams {
+            action,
+            agent: args.agent.clone(),
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+        }
+    }
+}
+
+pub async fn handle_recording_control_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    action: RecordingAction,
+    args: &RecordingControlArgs,
+) -> Result<()> {
+    let params = RecordingControlParams::from_args(action, args);
+    debug!("Recording control CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+/// The running agent on this host, reached over loopback when it listens on all interfaces.
+fn local_agent_url(master_config: &MasterConfig) -> Result<String> {
+    let listen = master_config.application.agent.as_ref().and_then(|agent| agent.listen.as_deref()).unwrap_or(DEFAULT_LISTEN);
+    let mut addr: SocketAddr = listen.parse().with_context(|| format!("Invalid agent listen address '{}'", listen))?;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+    }
+    Ok(format!("http://{}", addr))
+}
+
+/// Pauses, resumes or stops the recordings of a running `rcam agent`. Recordings act between
+/// frames: the current file is finalized, and a resume continues in a new file.
+pub async fn execute(params: &RecordingControlParams, ctx: &OperationContext<'_>) -> Result<()> {
+    let base = match &params.agent {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => local_agent_url(ctx.master_config)?,
+    };
+    let url = format!("{}{}/{}", base, API_PREFIX, params.action.endpoint());
+    let mut request = reqwest::Client::new().post(&url).timeout(Duration::from_secs(10))
+        .json(&StopRequest { cameras: params.cameras.clone() });
+    if let Some(token) = std::env::var(AGENT_TOKEN_ENV).ok().filter(|token| !token.is_empty()) {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.with_context(|| format!("No rcam agent answered at {}", base))?;
+    let status = response.status();
+    if !status.is_success() {
+        let detail = response.json::<ErrorBody>().await.map(|body| body.error).unwrap_or_default();
+        bail!("Agent at {} answered {}: {}", base, status, detail);
+    }
+    if params.action == RecordingAction::Stop {
+        let reply: StopReply = response.json().await.context("Unreadable reply from the agent")?;
+        if reply.stopped.is_empty() {
+            warn!("⏹️ No matching recording is in progress on {}.", base);
+        } else {
+            info!("⏹️ Stopping {} on {}; files are finalized and the recorded duration is in session.json.", reply.stopped.join(", "), base);
+        }
+        return Ok(());
+    }
+    let state: PauseState = response.json().await.context("Unreadable reply from the agent")?;
+    match (params.action, state.changed) {
+        (RecordingAction::Pause, true) => info!("⏸️ Recordings on {} paused; each camera finalizes its current file.", base),
+        (RecordingAction::Pause, false) => info!("⏸️ Recordings on {} were already paused.", base),
+        (RecordingAction::Resume, true) => info!("▶️ Recordings on {} resumed; each camera continues in a new file.", base),
+        (RecordingAction::Resume, false) => info!("▶️ Recordings on {} were not paused.", base),
+        (RecordingAction::Stop, _) => {}
+    }
+    Ok(())
+}
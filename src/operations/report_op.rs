@@ -0,0 +1,265 @@
+use crate::common::session::{SessionCamera, SessionManifest};
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use crate::cli::ReportArgs;
+use log::{debug, info, warn};
+use opencv::{core as opencv_core, imgcodecs, imgproc, prelude::*, videoio};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Typed parameters for `rcam report`.
+#[derive(Debug, Clone)]
+pub struct ReportParams {
+    pub session: String, // session id, or "latest"
+    pub output: Option<PathBuf>,
+    pub thumbnail_px: u32,
+}
+
+impl Default for ReportParams {
+    fn default() -> Self {
+        ReportParams { session: "latest".to_string(), output: None, thumbnail_px: 320 }
+    }
+}
+
+impl ReportParams {
+    pub fn from_args(args: &ReportArgs) -> Self {
+        ReportParams {
+            session: args.session.clone(),
+            output: args.output.as_ref().map(PathBuf::from),
+            thumbnail_px: args.thumbnail_px,
+        }
+    }
+}
+
+pub async fn handle_report_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &ReportArgs,
+) -> Result<()> {
+    let params = ReportParams::from_args(args);
+    debug!("Report CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await.map(|_| ())
+}
+
+/// Renders a self-contained HTML report (thumbnails inlined) for a session manifest.
+pub async fn execute(params: &ReportParams, ctx: &OperationContext<'_>) -> Result<PathBuf> {
+    let op_start_time = Instant::now();
+    let app_config = &ctx.master_config.application;
+    let manifest = SessionManifest::load(app_config, &params.session)?;
+    info!("📝 Building report for session '{}' ({} camera(s), {} event(s)).", manifest.session_id, manifest.cameras.len(), manifest.events.len());
+
+    let output = params.output.clone()
+        .unwrap_or_else(|| manifest.output_dir.join("report.html"));
+    let thumbnail_px = params.thumbnail_px.max(32);
+    let manifest_clone = manifest.clone();
+    let html = tokio::task::spawn_blocking(move || render_html(&manifest_clone, thumbnail_px))
+        .await
+        .map_err(|e| anyhow!("Report rendering task panicked: {}", e))?;
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&output, html).with_context(|| format!("Failed to write report {}", output.display()))?;
+    info!("✅ Report for session '{}' written to {} in {:?}.", manifest.session_id, output.display(), op_start_time.elapsed());
+    Ok(output)
+}
+
+fn render_html(manifest: &SessionManifest, thumbnail_px: u32) -> String {
+    let failed = manifest.cameras.iter().filter(|c| c.error.is_some()).count();
+    let finished_at = manifest.finished_at.unwrap_or(manifest.started_at);
+    let mut html = String::new();
+    let _ = write!(html, r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>rcam session {id}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}
+.ok {{ color: #1a7f37; }} .failed, .error {{ color: #c62828; }} .warn {{ color: #b26a00; }}
+.thumbs {{ display: flex; flex-wrap: wrap; gap: 12px; }}
+figure {{ margin: 0; }} figcaption {{ font-size: 0.8em; max-width: {px}px; word-break: break-all; }}
+</style></head><body>
+<h1>Session {id}</h1>
+<p>{op} &middot; {start} &rarr; {end} ({secs:.1} s) &middot; output <code>{out}</code></p>
+<p><b>{ok}</b> of {total} camera(s) succeeded, <b class="{fail_class}">{failed}</b> failed.</p>
+"#,
+        id = escape(&manifest.session_id),
+        px = thumbnail_px,
+        op = escape(&manifest.operation),
+        start = local(manifest.started_at),
+        end = local(finished_at),
+        secs = (finished_at - manifest.started_at).num_milliseconds() as f64 / 1000.0,
+        out = escape(&manifest.output_dir.display().to_string()),
+        ok = manifest.cameras.len() - failed,
+        total = manifest.cameras.len(),
+        fail_class = if failed > 0 { "failed" } else { "ok" },
+        failed = failed,
+    );
+
+    html.push_str("<h2>Cameras</h2>\n<table><tr><th>Camera</th><th>Status</th><th>Files</th><th>Size</th><th>Duration</th><th>Error</th></tr>\n");
+    for camera in &manifest.cameras {
+        let bytes: u64 = camera.files.iter().filter_map(|f| fs::metadata(f).ok()).map(|m| m.len()).sum();
+        let duration = match (camera.started_at, camera.finished_at) {
+            (Some(start), Some(end)) => format!("{:.2} s", (end - start).num_milliseconds() as f64 / 1000.0),
+            _ => "&ndash;".to_string(),
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{:.1} MB</td><td>{}</td><td>{}</td></tr>",
+            escape(&camera.name),
+            if camera.error.is_some() { "failed" } else { "ok" },
+            if camera.error.is_some() { "failed" } else { "ok" },
+            camera.files.len(),
+            bytes as f64 / 1_000_000.0,
+            duration,
+            camera.error.as_deref().map(escape).unwrap_or_default(),
+        );
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Timing</h2>\n");
+    html.push_str(&timing_svg(manifest, finished_at));
+
+    html.push_str("<h2>Captures</h2>\n<div class=\"thumbs\">\n");
+    for camera in &manifest.cameras {
+        for file in &camera.files {
+            let caption = format!("{}: {}", camera.name, file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default());
+            match thumbnail_data_uri(file, thumbnail_px) {
+                Some(uri) => {
+                    let _ = writeln!(html, "<figure><img src=\"{}\" alt=\"{}\"><figcaption>{}</figcaption></figure>", uri, escape(&caption), escape(&caption));
+                }
+                None => {
+                    let _ = writeln!(html, "<figure><div>(no preview)</div><figcaption>{}</figcaption></figure>", escape(&caption));
+                }
+            }
+        }
+    }
+    html.push_str("</div>\n");
+
+    html.push_str("<h2>Events</h2>\n");
+    if manifest.events.is_empty() {
+        html.push_str("<p>No events recorded.</p>\n");
+    } else {
+        html.push_str("<table><tr><th>Time</th><th>Level</th><th>Camera</th><th>Message</th></tr>\n");
+        for event in &manifest.events {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td></tr>",
+                local(event.at),
+                escape(&event.level),
+                escape(&event.level),
+                event.camera.as_deref().map(escape).unwrap_or_default(),
+                escape(&event.message),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// One bar per camera from its start to its end, on the session's time axis.
+fn timing_svg(manifest: &SessionManifest, finished_at: DateTime<Utc>) -> String {
+    const WIDTH: f64 = 800.0;
+    const LABEL: f64 = 160.0;
+    const ROW: f64 = 22.0;
+    let span_ms = (finished_at - manifest.started_at).num_milliseconds().max(1) as f64;
+    let x = |at: DateTime<Utc>| LABEL + (at - manifest.started_at).num_milliseconds().max(0) as f64 / span_ms * (WIDTH - LABEL);
+    let rows: Vec<&SessionCamera> = manifest.cameras.iter().filter(|c| c.started_at.is_some()).collect();
+    if rows.is_empty() {
+        return "<p>No timing data recorded.</p>\n".to_string();
+    }
+
+    let height = ROW * rows.len() as f64 + 24.0;
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-size=\"12\">\n", WIDTH, height);
+    for (i, camera) in rows.iter().enumerate() {
+        let (Some(start), end) = (camera.started_at, camera.finished_at.unwrap_or(finished_at)) else { continue };
+        let y = i as f64 * ROW;
+        let x0 = x(start);
+        let bar = (x(end) - x0).max(2.0);
+        let color = if camera.error.is_some() { "#c62828" } else { "#1a7f37" };
+        let _ = writeln!(svg, "<text x=\"0\" y=\"{:.1}\">{}</text>", y + 15.0, escape(&camera.name));
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{:.3} s &rarr; {:.3} s</title></rect>",
+            x0, y + 3.0, bar, ROW - 6.0, color,
+            (start - manifest.started_at).num_milliseconds() as f64 / 1000.0,
+            (end - manifest.started_at).num_milliseconds() as f64 / 1000.0,
+        );
+    }
+    let axis_y = ROW * rows.len() as f64 + 16.0;
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{:.1}\">0 s</text>", LABEL, axis_y);
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{:.2} s</text>", WIDTH, axis_y, span_ms / 1000.0);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// JPEG thumbnail of an image (or a video's first frame) as a data URI; None if it cannot be read.
+fn thumbnail_data_uri(path: &Path, max_px: u32) -> Option<String> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let mut frame = match extension.as_str() {
+        "jpg" | "jpeg" | "png" | "tif" | "tiff" | "webp" | "bmp" => imgcodecs::imread(path.to_str()?, imgcodecs::IMREAD_UNCHANGED).ok()?,
+        "mp4" | "avi" | "mkv" | "mov" => {
+            let mut cap = videoio::VideoCapture::from_file(path.to_str()?, videoio::CAP_ANY).ok()?;
+            let mut frame = opencv_core::Mat::default();
+            if !cap.read(&mut frame).ok()? {
+                return None;
+            }
+            frame
+        }
+        _ => return None,
+    };
+    if frame.empty() {
+        return None;
+    }
+    if frame.depth() == opencv_core::CV_16U {
+        // Depth: stretch to 8 bits and colorize so structure is visible.
+        let mut scaled = opencv_core::Mat::default();
+        opencv_core::normalize(&frame, &mut scaled, 0.0, 255.0, opencv_core::NORM_MINMAX, opencv_core::CV_8U, &opencv_core::no_array()).ok()?;
+        let mut colored = opencv_core::Mat::default();
+        imgproc::apply_color_map(&scaled, &mut colored, imgproc::COLORMAP_JET).ok()?;
+        frame = colored;
+    } else if frame.channels() == 4 {
+        let mut bgr = opencv_core::Mat::default();
+        imgproc::cvt_color_def(&frame, &mut bgr, imgproc::COLOR_BGRA2BGR).ok()?;
+        frame = bgr;
+    }
+
+    let scale = (max_px as f64 / frame.cols().max(frame.rows()) as f64).min(1.0);
+    let mut thumb = opencv_core::Mat::default();
+    let size = opencv_core::Size::new(((frame.cols() as f64 * scale) as i32).max(1), ((frame.rows() as f64 * scale) as i32).max(1));
+    imgproc::resize(&frame, &mut thumb, size, 0.0, 0.0, imgproc::INTER_AREA).ok()?;
+    let mut encoded = opencv_core::Vector::<u8>::new();
+    let params = opencv_core::Vector::<i32>::from_slice(&[imgcodecs::IMWRITE_JPEG_QUALITY, 80]);
+    if !imgcodecs::imencode(".jpg", &thumb, &mut encoded, &params).ok()? {
+        warn!("Could not encode thumbnail for {}", path.display());
+        return None;
+    }
+    Some(format!("data:image/jpeg;base64,{}", base64(encoded.as_slice())))
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        out.push(TABLE[(n >> 18) as usize & 63] as char);
+        out.push(TABLE[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { TABLE[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { TABLE[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub(crate) fn local(at: DateTime<Utc>) -> String {
+    DateTime::<Local>::from(at).format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
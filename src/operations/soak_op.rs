@@ -0,0 +1,471 @@
+use crate::cli::DiagnosticArgs;
+use crate::common::atomic_file;
+use crate::common::environment::EnvironmentInfo;
+use crate::config_loader::{CaptureDeviceConfig, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::core::events::{self, Event};
+use crate::core::supervisor::RIG;
+use crate::operations::diagnostic_op::DiagnosticTest;
+use crate::operations::image_capture_op::{self, CaptureImageParams};
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_helper;
+use crate::operations::op_result::OperationReport;
+use crate::operations::report_op;
+use crate::operations::video_record_op::{self, RecordVideoParams};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio_util::sync::CancellationToken;
+
+/// Length of each round's recording.
+const SOAK_VIDEO_SECS: u64 = 10;
+/// RSS this much above its level after the first round (which warms caches and pools) counts
+/// as a suspected leak.
+const LEAK_GROWTH_FACTOR: f64 = 1.5;
+
+/// Typed parameters for `rcam test --soak`.
+#[derive(Debug, Clone)]
+pub struct SoakParams {
+    pub duration: Duration,
+    pub interval: Duration, // from the start of one round to the start of the next
+    pub snapshots: bool,
+    pub recordings: bool,
+    pub cameras: Option<Vec<String>>, // None = every configured camera
+    pub keep_outputs: bool,           // keep the files of rounds that passed
+}
+
+impl SoakParams {
+    pub fn from_args(args: &DiagnosticArgs) -> Result<Self> {
+        let duration = op_helper::parse_duration(args.soak.as_deref().unwrap_or_default())?;
+        let interval = op_helper::parse_duration(&args.soak_interval)?;
+        let tests = DiagnosticTest::parse_list(args.tests.as_deref())?;
+        let params = SoakParams {
+            duration,
+            interval,
+            snapshots: tests.contains(&DiagnosticTest::Image),
+            recordings: tests.contains(&DiagnosticTest::Video),
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+            keep_outputs: args.soak_keep,
+        };
+        if !params.snapshots && !params.recordings {
+            bail!("A soak test runs snapshots (image) and/or recordings (video); --tests selects neither.");
+        }
+        Ok(params)
+    }
+}
+
+/// One camera's tally over the soak.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SoakCamera {
+    pub snapshots_ok: u32,
+    pub snapshots_failed: u32,
+    pub recordings_ok: u32,
+    pub recordings_failed: u32,
+    pub reconnects: u32,
+    pub unplugs: u32, // RealSense devices unplugged, from hot-plug events
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+}
+
+impl SoakCamera {
+    pub fn failures(&self) -> u32 {
+        self.snapshots_failed + self.recordings_failed
+    }
+}
+
+/// Memory use of this process at the end of a round.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RssSample {
+    pub at: DateTime<Utc>,
+    pub round: u32,
+    pub rss_kb: u64,
+}
+
+/// The stability report a soak ends with, written as JSON and HTML.
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub planned_secs: u64,
+    pub interrupted: bool,
+    pub rounds: u32,
+    pub failed_rounds: u32,
+    pub cameras: BTreeMap<String, SoakCamera>,
+    pub rss: Vec<RssSample>,
+    pub rss_growth_kb_per_hour: Option<f64>, // least-squares slope over the samples after the first round
+    pub leak_suspected: bool,
+    pub passed: bool,
+    pub environment: EnvironmentInfo,
+}
+
+/// Runs snapshot and short recording rounds every `interval` until `duration` has passed or
+/// the process is interrupted. Each camera's failures, stream reconnects and unplugs are
+/// tallied and the process RSS is sampled after each round. Files of failed rounds are kept
+/// for inspection.
+/// The rounds run under the camera manager's supervisor: a round that panics counts as failed
+/// and the soak carries on with the next one, as the `supervisor` policy allows.
+pub async fn execute(params: &SoakParams, ctx: &OperationContext<'_>) -> Result<SoakReport> {
+    let master_config = ctx.master_config;
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let environment = EnvironmentInfo::collect(&master_config.application).await;
+    let soak_dir = PathBuf::from(&master_config.application.output_directory_base)
+        .join("diagnostics")
+        .join(format!("soak_{}", DateTime::<Local>::from(started_at).format("%Y%m%d_%H%M%S")));
+    std::fs::create_dir_all(&soak_dir).with_context(|| format!("Failed to create soak directory {}", soak_dir.display()))?;
+
+    let selected: Vec<&CaptureDeviceConfig> = master_config.cameras.iter()
+        .filter(|camera| params.cameras.as_ref().is_none_or(|names| names.contains(camera.get_name())))
+        .collect();
+    if let Some(names) = &params.cameras {
+        for name in names.iter().filter(|name| !selected.iter().any(|c| c.get_name() == *name)) {
+            warn!("⚠️ SOAK: '{}' is not a configured camera.", name);
+        }
+    }
+    if selected.is_empty() {
+        bail!("No cameras selected for the soak test.");
+    }
+    let all_names: Vec<String> = selected.iter().map(|c| c.get_name().clone()).collect();
+    // Video recording is RTSP only.
+    let video_names: Vec<String> = selected.iter()
+        .filter(|c| matches!(c, CaptureDeviceConfig::IpCamera { .. }))
+        .map(|c| c.get_name().clone())
+        .collect();
+
+    info!("🔁 Soak test of {} camera(s) for {:?}, a round every {:?}; report and failed rounds go to {}.",
+        all_names.len(), params.duration, params.interval, soak_dir.display());
+    let progress = SoakProgress {
+        cameras: all_names.iter().map(|n| (n.clone(), SoakCamera::default())).collect(),
+        ..Default::default()
+    };
+    // The round loop outlives this borrow of the context; clones share the same devices.
+    let soak = Arc::new(SoakRun {
+        params: params.clone(),
+        soak_dir: soak_dir.clone(),
+        all_names,
+        video_names,
+        started,
+        master_config: Arc::new(master_config.clone()),
+        camera_manager: Arc::new(ctx.camera_manager.clone()),
+        progress: Mutex::new(progress),
+    });
+    let supervisor = ctx.camera_manager.supervisor().scoped();
+    let task_soak = soak.clone();
+    supervisor.spawn_with_policy(RIG, "soak", supervisor.default_policy().on_failure_only(), move |cancel| {
+        let soak = task_soak.clone();
+        async move { soak.run_rounds(cancel).await }
+    });
+    supervisor.wait(&ctx.cancel).await;
+
+    let mut progress = std::mem::take(&mut *soak.progress());
+    progress.close_unfinished_round(); // the supervisor gave up after a panic
+    let SoakProgress { cameras, rss, rounds, failed_rounds, .. } = progress;
+    let rss_growth_kb_per_hour = growth_per_hour(&rss);
+    let baseline = rss.first().map(|s| s.rss_kb as f64);
+    let leak_suspected = match (baseline, rss.last()) {
+        (Some(first), Some(last)) if rss.len() > 2 => last.rss_kb as f64 > first * LEAK_GROWTH_FACTOR,
+        _ => false,
+    };
+    let report = SoakReport {
+        started_at,
+        finished_at: Utc::now(),
+        planned_secs: params.duration.as_secs(),
+        interrupted: ctx.cancel.is_cancelled(),
+        rounds,
+        failed_rounds,
+        passed: failed_rounds == 0 && !leak_suspected,
+        cameras,
+        rss,
+        rss_growth_kb_per_hour,
+        leak_suspected,
+        environment,
+    };
+    log_summary(&report);
+    match write_report(&report, &soak_dir) {
+        Ok((json_path, html_path)) => info!("📄 Soak report written to {} and {}", json_path.display(), html_path.display()),
+        Err(e) => error!("❌ Failed to write the soak report: {:#}", e),
+    }
+    Ok(report)
+}
+
+/// Tallies kept outside the round loop, so they survive it being restarted.
+#[derive(Debug, Default)]
+struct SoakProgress {
+    cameras: BTreeMap<String, SoakCamera>,
+    rss: Vec<RssSample>,
+    rounds: u32,
+    failed_rounds: u32,
+    in_round: bool, // still set when the loop starts again: the last round panicked
+}
+
+impl SoakProgress {
+    fn close_unfinished_round(&mut self) {
+        if std::mem::take(&mut self.in_round) {
+            warn!("⚠️ SOAK: round {} panicked; its files are kept.", self.rounds);
+            self.failed_rounds += 1;
+        }
+    }
+}
+
+/// One soak test, shared by every run of its supervised round loop.
+struct SoakRun {
+    params: SoakParams,
+    soak_dir: PathBuf,
+    all_names: Vec<String>,
+    video_names: Vec<String>,
+    started: Instant,
+    master_config: Arc<MasterConfig>,
+    camera_manager: Arc<CameraManager>,
+    progress: Mutex<SoakProgress>,
+}
+
+impl SoakRun {
+    fn progress(&self) -> MutexGuard<'_, SoakProgress> {
+        self.progress.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    async fn run_rounds(&self, cancel: CancellationToken) -> Result<()> {
+        let ctx = OperationContext { master_config: &self.master_config, camera_manager: &self.camera_manager, cancel };
+        let params = &self.params;
+        let mut bus = events::subscribe();
+        self.progress().close_unfinished_round();
+
+        while self.started.elapsed() < params.duration && !ctx.cancel.is_cancelled() {
+            let round_start = Instant::now();
+            let round = {
+                let mut progress = self.progress();
+                progress.rounds += 1;
+                progress.in_round = true;
+                progress.rounds
+            };
+            let round_dir = self.soak_dir.join(format!("round_{:04}", round));
+            let mut round_failed = false;
+
+            if params.snapshots {
+                let image_params = CaptureImageParams {
+                    cameras: Some(self.all_names.clone()),
+                    output_dir: Some(round_dir.join("image")),
+                    retries: Some(0), // every failure counts
+                    stream_key: Some("test"),
+                    ..Default::default()
+                };
+                let outcome = image_capture_op::execute(&image_params, &ctx).await;
+                round_failed |= tally(&mut self.progress().cameras, &self.all_names, outcome, |c, ok| if ok { c.snapshots_ok += 1 } else { c.snapshots_failed += 1 });
+            }
+            if params.recordings && !self.video_names.is_empty() && !ctx.cancel.is_cancelled() {
+                let video_params = RecordVideoParams {
+                    cameras: Some(self.video_names.clone()),
+                    duration: Some(Duration::from_secs(SOAK_VIDEO_SECS)),
+                    output_dir: Some(round_dir.join("video")),
+                    stream_key: Some("test"),
+                    ..Default::default()
+                };
+                let outcome = video_record_op::execute(&video_params, &ctx).await;
+                round_failed |= tally(&mut self.progress().cameras, &self.video_names, outcome, |c, ok| if ok { c.recordings_ok += 1 } else { c.recordings_failed += 1 });
+            }
+
+            // No await while the tallies are locked.
+            {
+                let mut progress = self.progress();
+                loop {
+                    match bus.try_recv() {
+                        Ok(Event::StreamReconnected { camera, .. }) => {
+                            if let Some(tally) = progress.cameras.get_mut(&camera) {
+                                tally.reconnects += 1;
+                            }
+                        }
+                        Ok(Event::CameraDisconnected { camera, .. }) => {
+                            if let Some(tally) = progress.cameras.get_mut(&camera) {
+                                tally.unplugs += 1;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(TryRecvError::Lagged(skipped)) => debug!("SOAK: skipped {} event(s) while counting reconnects and unplugs", skipped),
+                        Err(_) => break,
+                    }
+                }
+                if let Some(rss_kb) = current_rss_kb() {
+                    progress.rss.push(RssSample { at: Utc::now(), round, rss_kb });
+                }
+                progress.in_round = false;
+                if round_failed {
+                    progress.failed_rounds += 1;
+                } else if !params.keep_outputs {
+                    if let Err(e) = std::fs::remove_dir_all(&round_dir) {
+                        debug!("SOAK: could not remove {}: {}", round_dir.display(), e);
+                    }
+                }
+                let failures: u32 = progress.cameras.values().map(SoakCamera::failures).sum();
+                let reconnects: u32 = progress.cameras.values().map(|c| c.reconnects).sum();
+                info!("🔁 Soak round {} {} in {:?} | {} failure(s), {} reconnect(s) so far | RSS {} | {:?} of {:?} elapsed",
+                    round, if round_failed { "❌ had failures" } else { "✅ passed" }, round_start.elapsed(), failures, reconnects,
+                    progress.rss.last().map(|s| format!("{:.1} MB", s.rss_kb as f64 / 1024.0)).unwrap_or_else(|| "unknown".to_string()),
+                    Duration::from_secs(self.started.elapsed().as_secs()), params.duration);
+            }
+
+            let next_round = round_start + params.interval;
+            let remaining = params.duration.saturating_sub(self.started.elapsed());
+            let wait = next_round.saturating_duration_since(Instant::now()).min(remaining);
+            tokio::select! {
+                _ = ctx.cancel.cancelled() => {}
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds one operation's per-camera outcome to the tallies; true if any camera failed.
+fn tally(cameras: &mut BTreeMap<String, SoakCamera>, names: &[String], outcome: Result<OperationReport>, mut count: impl FnMut(&mut SoakCamera, bool)) -> bool {
+    let now = Utc::now();
+    let errors: BTreeMap<String, Option<String>> = match outcome {
+        Ok(report) => report.results.into_iter().map(|r| (r.name, r.error)).collect(),
+        // The operation as a whole failed: every camera in it counts as failed.
+        Err(e) => names.iter().map(|n| (n.clone(), Some(format!("{:#}", e)))).collect(),
+    };
+    let mut any_failed = false;
+    for (name, error) in errors {
+        let Some(camera) = cameras.get_mut(&name) else { continue };
+        count(camera, error.is_none());
+        if let Some(error) = error {
+            warn!("⚠️ SOAK [{}]: {}", name, error);
+            camera.last_error = Some(error);
+            camera.last_error_at = Some(now);
+            any_failed = true;
+        }
+    }
+    any_failed
+}
+
+/// Resident set size of this process (Linux).
+fn current_rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status.lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+}
+
+/// Least-squares slope of RSS over time, leaving out the first round's warm-up.
+fn growth_per_hour(samples: &[RssSample]) -> Option<f64> {
+    let samples = samples.get(1..).filter(|s| s.len() >= 2)?;
+    let t0 = samples[0].at;
+    let points: Vec<(f64, f64)> = samples.iter()
+        .map(|s| ((s.at - t0).num_milliseconds() as f64 / 3_600_000.0, s.rss_kb as f64))
+        .collect();
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_r = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let var_t: f64 = points.iter().map(|p| (p.0 - mean_t).powi(2)).sum();
+    if var_t <= 0.0 {
+        return None;
+    }
+    Some(points.iter().map(|p| (p.0 - mean_t) * (p.1 - mean_r)).sum::<f64>() / var_t)
+}
+
+fn log_summary(report: &SoakReport) {
+    info!("📋 ----- Soak Test Summary ({} round(s) in {:.1} h{}) -----", report.rounds,
+        (report.finished_at - report.started_at).num_seconds() as f64 / 3600.0,
+        if report.interrupted { ", interrupted" } else { "" });
+    for (name, camera) in &report.cameras {
+        let status = if camera.failures() == 0 { "✅" } else { "❌" };
+        info!("{} {:<20} | snapshots {} ok / {} failed | recordings {} ok / {} failed | {} reconnect(s) | {} unplug(s){}",
+            status, name, camera.snapshots_ok, camera.snapshots_failed, camera.recordings_ok, camera.recordings_failed, camera.reconnects, camera.unplugs,
+            camera.last_error.as_deref().map(|e| format!(" | last error: {}", e)).unwrap_or_default());
+    }
+    if let (Some(first), Some(last)) = (report.rss.first(), report.rss.last()) {
+        info!("🧠 RSS {:.1} MB after round 1, {:.1} MB at the end, peak {:.1} MB{}",
+            first.rss_kb as f64 / 1024.0, last.rss_kb as f64 / 1024.0,
+            report.rss.iter().map(|s| s.rss_kb).max().unwrap_or(0) as f64 / 1024.0,
+            report.rss_growth_kb_per_hour.map(|g| format!(", trend {:+.2} MB/h", g / 1024.0)).unwrap_or_default());
+    }
+    if report.leak_suspected {
+        warn!("⚠️ Memory grew by more than {:.0}% over the soak; a leak is suspected.", (LEAK_GROWTH_FACTOR - 1.0) * 100.0);
+    }
+    if report.passed {
+        info!("🎉 Soak test passed: {} round(s) without failures.", report.rounds);
+    } else {
+        error!("🔥 Soak test failed: {} of {} round(s) had failures{}.", report.failed_rounds, report.rounds,
+            if report.leak_suspected { ", and memory kept growing" } else { "" });
+    }
+}
+
+fn write_report(report: &SoakReport, dir: &Path) -> Result<(PathBuf, PathBuf)> {
+    let json = serde_json::to_vec_pretty(report).context("Failed to serialize the soak report")?;
+    let json_path = atomic_file::write_bytes(&dir.join("soak_report.json"), &json)?;
+    let html_path = atomic_file::write_bytes(&dir.join("soak_report.html"), render_html(report).as_bytes())?;
+    Ok((json_path, html_path))
+}
+
+fn render_html(report: &SoakReport) -> String {
+    let mut html = String::new();
+    let _ = write!(html, r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>rcam soak test {start}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}
+.ok {{ color: #1a7f37; }} .failed {{ color: #c62828; }}
+</style></head><body>
+<h1>Soak test on {host}</h1>
+<p>{start} &rarr; {end} ({hours:.1} of {planned:.1} h{interrupted})</p>
+<p class="{class}"><b>{verdict}</b>: {failed} of {rounds} round(s) had failures{leak}.</p>
+<h2>Cameras</h2>
+<table><tr><th>Camera</th><th>Snapshots ok</th><th>Snapshots failed</th><th>Recordings ok</th><th>Recordings failed</th><th>Reconnects</th><th>Unplugs</th><th>Last error</th></tr>
+"#,
+        host = report_op::escape(&report.environment.hostname),
+        start = report_op::local(report.started_at),
+        end = report_op::local(report.finished_at),
+        hours = (report.finished_at - report.started_at).num_seconds() as f64 / 3600.0,
+        planned = report.planned_secs as f64 / 3600.0,
+        interrupted = if report.interrupted { ", interrupted" } else { "" },
+        class = if report.passed { "ok" } else { "failed" },
+        verdict = if report.passed { "Passed" } else { "Failed" },
+        failed = report.failed_rounds,
+        rounds = report.rounds,
+        leak = if report.leak_suspected { "; memory kept growing (leak suspected)" } else { "" },
+    );
+    for (name, camera) in &report.cameras {
+        let last_error = match (&camera.last_error, camera.last_error_at) {
+            (Some(error), Some(at)) => format!("{} ({})", report_op::escape(error), report_op::local(at)),
+            _ => String::new(),
+        };
+        let _ = writeln!(html, "<tr><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            if camera.failures() == 0 { "ok" } else { "failed" }, report_op::escape(name),
+            camera.snapshots_ok, camera.snapshots_failed, camera.recordings_ok, camera.recordings_failed, camera.reconnects, camera.unplugs, last_error);
+    }
+    html.push_str("</table>\n<h2>Memory (RSS)</h2>\n");
+    html.push_str(&rss_svg(&report.rss));
+    if let Some(growth) = report.rss_growth_kb_per_hour {
+        let _ = writeln!(html, "<p>Trend after the first round: {:+.2} MB/h.</p>", growth / 1024.0);
+    }
+    html.push_str("<h2>Environment</h2>\n<table>\n");
+    for (name, value) in report.environment.rows() {
+        let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, report_op::escape(&value));
+    }
+    html.push_str("</table>\n</body></html>\n");
+    html
+}
+
+/// RSS per round as a simple line chart.
+fn rss_svg(samples: &[RssSample]) -> String {
+    const WIDTH: f64 = 800.0;
+    const HEIGHT: f64 = 200.0;
+    if samples.len() < 2 {
+        return "<p>Not enough samples for a chart.</p>\n".to_string();
+    }
+    let max = samples.iter().map(|s| s.rss_kb).max().unwrap_or(1).max(1) as f64;
+    let last_round = samples.last().map(|s| s.round).unwrap_or(1).max(2) as f64;
+    let points: Vec<String> = samples.iter()
+        .map(|s| format!("{:.1},{:.1}", (s.round as f64 - 1.0) / (last_round - 1.0) * WIDTH, HEIGHT - s.rss_kb as f64 / max * HEIGHT))
+        .collect();
+    format!("<svg width=\"{w}\" height=\"{h}\" style=\"border:1px solid #ccc\"><polyline fill=\"none\" stroke=\"#1565c0\" stroke-width=\"2\" points=\"{p}\"/>\
+<text x=\"4\" y=\"14\" font-size=\"12\">{max:.1} MB</text></svg>\n",
+        w = WIDTH, h = HEIGHT, p = points.join(" "), max = max / 1024.0)
+}
@@ -1,23 +1,65 @@
-use crate::config_loader::MasterConfig;
+use crate::cli::VerifyTimesArgs;
+use crate::config_loader::{MasterConfig, CameraTimeouts};
 use crate::core::camera_manager::CameraManager;
 use crate::camera::camera_controller::CameraController;
-// use crate::errors::AppError; // AppError might be replaced by anyhow
+use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::operations::op_context::OperationContext;
+use crate::common::host_clock;
 use anyhow::Result; // Import anyhow::Result
 use chrono::{Utc, DateTime};
 use log::{info, warn, error, debug};
 use futures::future::join_all;
 use tokio::task::JoinHandle; // For explicit JoinHandle type
-use std::time::Instant; // Added Instant
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 use anyhow::anyhow; // Import anyhow::anyhow
 
+/// Typed parameters for time verification.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyTimesParams {
+    pub cameras: Option<Vec<String>>, // None = every configured camera
+}
+
+impl VerifyTimesParams {
+    pub fn from_args(_args: &VerifyTimesArgs) -> Self {
+        VerifyTimesParams { cameras: None }
+    }
+}
+
 pub async fn handle_verify_times_cli(
     master_config: &MasterConfig,
     camera_manager: &CameraManager,
-    _args: &clap::ArgMatches, // Prefixed with underscore as it's unused
+    args: &VerifyTimesArgs,
 ) -> Result<()> {
+    let params = VerifyTimesParams::from_args(args);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+pub async fn execute(params: &VerifyTimesParams, ctx: &OperationContext<'_>) -> Result<()> {
+    let master_config = ctx.master_config;
+    let camera_manager = ctx.camera_manager;
     info!("Verifying camera time synchronization...");
     let verify_start_time = Instant::now();
 
+    // The host clock is the reference for every comparison below, so check it first.
+    // One tolerance for the host clock and the camera comparisons; validated as finite and non-negative at load.
+    let tolerance_seconds = master_config.application.time_sync_tolerance_seconds.unwrap_or(1.0);
+    let host_clock = host_clock::check_host_clock(
+        master_config.application.ntp_server.as_deref(),
+        Duration::from_secs_f32(tolerance_seconds),
+        CameraTimeouts::from_app(&master_config.application).http,
+    ).await;
+    match host_clock.synchronized {
+        Some(true) => info!("🖥️ Host clock is synchronized ({}: {}).", host_clock.source, host_clock.detail),
+        Some(false) => {
+            error!("🖥️ Host clock is NOT synchronized ({}: {}). Camera comparisons against it are meaningless.", host_clock.source, host_clock.detail);
+            if master_config.application.require_host_clock_sync.unwrap_or(true) {
+                return Err(anyhow!("Host clock is not synchronized ({}: {}); fix host NTP before verifying cameras", host_clock.source, host_clock.detail));
+            }
+        }
+        None => warn!("🖥️ Host clock sync status unknown ({}). Camera comparisons assume the host is correct.", host_clock.detail),
+    }
+
     let system_time_now = Utc::now();
     info!("Current system time (UTC): {}", system_time_now.to_rfc3339());
 
@@ -25,7 +67,16 @@ pub async fn handle_verify_times_cli(
     debug!("CameraController initialized for time verification in {:?}.", verify_start_time.elapsed());
 
     let cameras_fetch_start = Instant::now();
-    let cameras_to_target = camera_manager.get_all_devices().await;
+    let mut cameras_to_target = camera_manager.get_all_devices().await;
+    if let Some(selected) = &params.cameras {
+        let mut kept = Vec::new();
+        for camera in cameras_to_target {
+            if selected.contains(&camera.lock().await.get_name()) {
+                kept.push(camera);
+            }
+        }
+        cameras_to_target = kept;
+    }
     debug!("Fetched {} cameras to target in {:?}.", cameras_to_target.len(), cameras_fetch_start.elapsed());
 
     if cameras_to_target.is_empty() {
@@ -33,7 +84,8 @@ pub async fn handle_verify_times_cli(
         return Ok(());
     }
 
-    let mut time_check_tasks: Vec<JoinHandle<Result<(String, DateTime<Utc>)>>> = Vec::new();
+    // Ok(None) means the camera was skipped because it cannot report its time.
+    let mut time_check_tasks: Vec<JoinHandle<Result<Option<(String, DateTime<Utc>)>>>> = Vec::new();
     let master_config_clone = master_config.clone(); // Clone master_config for static lifetime
 
     for cam_entity_arc in cameras_to_target {
@@ -65,17 +117,27 @@ pub async fn handle_verify_times_cli(
                     });
 
                 if let Some(specifics) = ip_camera_details {
-                    let username_str = specifics.username.as_deref().unwrap_or("");
-                    let password_env_var_placeholder = ""; 
-
-                    match controller_clone.get_camera_time(&cam_name_clone, &specifics.ip, username_str, password_env_var_placeholder, &app_settings_task_clone).await {
+                    let timeouts = CameraTimeouts::resolve(&app_settings_task_clone, Some(&specifics));
+                    let http_timeout = timeouts.http;
+                    let time_result = match IpCameraDevice::new(cam_name_clone.clone(), specifics.clone(), timeouts) {
+                        Ok(ip_device) if !ip_device.driver.has_http_api() => {
+                            info!("  ⏭️ Skipping time synchronization for RTSP-only camera '{}' (no HTTP API).", cam_name_clone);
+                            return Ok(None);
+                        }
+                        Ok(ip_device) => match tokio::time::timeout(http_timeout, controller_clone.get_camera_time(&ip_device)).await {
+                            Ok(inner) => inner,
+                            Err(_) => Err(anyhow!("Timed out after {:?} querying time from camera '{}'", http_timeout, cam_name_clone)),
+                        },
+                        Err(e) => Err(e),
+                    };
+                    match time_result {
                         Ok(camera_time) => {
                             let time_diff = camera_time.timestamp_millis() - current_system_time_clone.timestamp_millis();
                             info!(
                                 "  ✅ IP Camera '{}' time (UTC): {}. System time (UTC): {}. Difference: {}ms. Fetched in {:?}.",
                                 cam_name_clone, camera_time.to_rfc3339(), current_system_time_clone.to_rfc3339(), time_diff, get_time_start.elapsed()
                             );
-                            Ok((cam_name_clone, camera_time))
+                            Ok(Some((cam_name_clone, camera_time)))
                         }
                         Err(e) => {
                             error!("  ❌ Failed to get time for IP camera '{}' after {:?}: {:#}", cam_name_clone, get_time_start.elapsed(), e);
@@ -87,14 +149,14 @@ pub async fn handle_verify_times_cli(
                     Err(anyhow!("Missing IP camera config for time sync: {}", cam_name_clone))
                 }
             } else {
-                warn!(
-                    "  Skipping time synchronization for non-IP camera '{}' (Type: {}). HTTP time sync not applicable.",
+                info!(
+                    "  ⏭️ Skipping time synchronization for non-IP camera '{}' (Type: {}). HTTP time sync not applicable.",
                     cam_name_clone,
                     cam_type_clone
                 );
-                Err(anyhow!("Time sync not applicable for device type {}: {}", cam_type_clone, cam_name_clone))
+                Ok(None)
             }
-        });
+        }.in_current_span());
         time_check_tasks.push(handle);
         debug!("  Spawned time check task for a camera in {:?}. Total tasks: {}", task_spawn_start.elapsed(), time_check_tasks.len());
     }
@@ -106,10 +168,12 @@ pub async fn handle_verify_times_cli(
 
     let mut successful_times: Vec<(String, DateTime<Utc>)> = Vec::new();
     let mut task_errors = 0;
+    let mut skipped = 0;
 
     for result in results { // result is Result<Result<(String, DateTime<Utc>), anyhow::Error>, JoinError>
         match result {
-            Ok(Ok(time_data)) => successful_times.push(time_data),
+            Ok(Ok(Some(time_data))) => successful_times.push(time_data),
+            Ok(Ok(None)) => skipped += 1,
             Ok(Err(_op_err)) => { // op_err is anyhow::Error, already logged by the task
                 task_errors += 1;
                 debug!("  Encountered an operation error within a task.");
@@ -121,6 +185,10 @@ pub async fn handle_verify_times_cli(
         }
     }
 
+    if skipped > 0 {
+        info!("ℹ️ {} camera(s) skipped as they cannot report their time.", skipped);
+    }
+
     if successful_times.is_empty() {
         if task_errors > 0 {
             warn!("⚠️ Could not retrieve time from any camera due to {} errors. Operation finished in {:?}.", task_errors, verify_start_time.elapsed());
@@ -132,14 +200,13 @@ pub async fn handle_verify_times_cli(
         return Ok(());
     }
 
-    let tolerance_seconds = master_config.application.time_sync_tolerance_seconds.unwrap_or(0.0) as i64; // Ensure there is a default if None
     info!("🕒 Time synchronization tolerance: {} seconds", tolerance_seconds);
 
     let mut all_synced_system = true;
     let system_sync_check_start = Instant::now();
     for (name, cam_time) in &successful_times {
         let diff_seconds = (cam_time.timestamp() - system_time_now.timestamp()).abs();
-        if diff_seconds > tolerance_seconds {
+        if diff_seconds as f32 > tolerance_seconds {
             warn!(
                 "❌ Camera '{}' time ({}) is OUT OF SYNC with system time ({}) by {} seconds (tolerance: {}s).",
                 name, cam_time.to_rfc3339(), system_time_now.to_rfc3339(), diff_seconds, tolerance_seconds
@@ -169,7 +236,7 @@ pub async fn handle_verify_times_cli(
                 let (name1, time1) = &successful_times[i];
                 let (name2, time2) = &successful_times[j];
                 let diff_seconds = (time1.timestamp() - time2.timestamp()).abs();
-                if diff_seconds > tolerance_seconds {
+                if diff_seconds as f32 > tolerance_seconds {
                     warn!(
                         "❌ Camera '{}' time ({}) is OUT OF SYNC with camera '{}' time ({}) by {} seconds (tolerance: {}s).",
                         name1, time1.to_rfc3339(), name2, time2.to_rfc3339(), diff_seconds, tolerance_seconds
@@ -193,6 +260,12 @@ pub async fn handle_verify_times_cli(
         info!("ℹ️ Only one camera time successfully retrieved, skipping inter-camera sync check.");
     }
 
+    info!(
+        "🖥️ Host clock: {} via {}{}.",
+        match host_clock.synchronized { Some(true) => "synchronized", Some(false) => "NOT synchronized", None => "unknown" },
+        host_clock.source,
+        host_clock.offset_secs.map(|o| format!(", offset {:+.3}s", o)).unwrap_or_default()
+    );
     info!("🏁 Verify-times operation finished in {:?}.", verify_start_time.elapsed());
     Ok(())
 } 
\ No newline at end of file
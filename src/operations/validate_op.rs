@@ -0,0 +1,66 @@
+use crate::cli::ValidateArgs;
+use crate::common::config_diff::ConfigDiff;
+use crate::common::session::SessionManifest;
+use crate::config_loader::{self, MasterConfig};
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use std::path::PathBuf;
+
+/// Typed parameters for `rcam validate`. The configuration itself is loaded and validated
+/// before any operation runs; this compares it with an earlier one.
+#[derive(Debug, Clone, Default)]
+pub struct ValidateParams {
+    pub against: Option<PathBuf>, // another config file, e.g. the one currently deployed
+    pub session: Option<String>,  // or the configuration a session ran with ("latest" allowed)
+}
+
+impl ValidateParams {
+    pub fn from_args(args: &ValidateArgs) -> Self {
+        ValidateParams { against: args.against.as_ref().map(PathBuf::from), session: args.session.clone() }
+    }
+}
+
+pub async fn handle_validate_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &ValidateArgs,
+) -> Result<()> {
+    let params = ValidateParams::from_args(args);
+    debug!("Validate CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await.map(|_| ())
+}
+
+/// Reports what the loaded configuration changes relative to `against` or a session's
+/// configuration: added/removed cameras, changed settings and the subsystems they affect.
+/// Returns None when there is nothing to compare with.
+pub async fn execute(params: &ValidateParams, ctx: &OperationContext<'_>) -> Result<Option<ConfigDiff>> {
+    let config = ctx.master_config;
+    info!("✅ Configuration is valid: {} camera(s){}.", config.cameras.len(),
+        config.active_profile.as_ref().map(|p| format!(", profile '{}'", p)).unwrap_or_default());
+
+    let (baseline, label) = if let Some(path) = &params.against {
+        let path_str = path.to_string_lossy();
+        (config_loader::load_config(&path_str)?, path.display().to_string())
+    } else if let Some(session) = &params.session {
+        let manifest = SessionManifest::load(&config.application, session)?;
+        let baseline = manifest.config
+            .ok_or_else(|| anyhow!("Session '{}' did not record its configuration", manifest.session_id))?;
+        (baseline, format!("session '{}'", manifest.session_id))
+    } else {
+        return Ok(None);
+    };
+
+    let diff = ConfigDiff::between(&baseline, config)?;
+    info!("🔍 Changes from {}: {} camera(s) added, {} removed, {} setting(s) changed.",
+        label, diff.added_cameras.len(), diff.removed_cameras.len(), diff.changed.len());
+    for line in diff.to_string().lines() {
+        info!("{}", line);
+    }
+    let affected = diff.affected_subsystems();
+    if !affected.is_empty() {
+        info!("🧩 Affected: {}", affected.join(", "));
+    }
+    Ok(Some(diff))
+}
@@ -0,0 +1,140 @@
+use crate::cli::VerifyArchiveArgs;
+use crate::common::checksum;
+use crate::common::session::SessionManifest;
+use crate::config_loader::MasterConfig;
+use crate::core::camera_manager::CameraManager;
+use crate::operations::op_context::OperationContext;
+use anyhow::{bail, Context, Result};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Typed parameters for `rcam verify-archive`.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyArchiveParams {
+    pub dir: Option<PathBuf>,    // None = AppSettings.output_directory_base
+    pub session: Option<String>, // only this session (id or "latest")
+}
+
+impl VerifyArchiveParams {
+    pub fn from_args(args: &VerifyArchiveArgs) -> Self {
+        VerifyArchiveParams { dir: args.dir.as_ref().map(PathBuf::from), session: args.session.clone() }
+    }
+}
+
+/// Outcome of re-hashing an archive against its session manifests.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveVerification {
+    pub sessions: usize,
+    pub verified: usize,
+    pub mismatched: Vec<PathBuf>, // content changed since capture
+    pub missing: Vec<PathBuf>,    // listed in a manifest but not found
+    pub unhashed: usize,          // files captured without application.checksums
+}
+
+impl ArchiveVerification {
+    pub fn is_intact(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
+    }
+}
+
+pub async fn handle_verify_archive_cli(
+    master_config: &MasterConfig,
+    camera_manager: &CameraManager,
+    args: &VerifyArchiveArgs,
+) -> Result<()> {
+    let params = VerifyArchiveParams::from_args(args);
+    debug!("Verify-archive CLI parsed into: {:?}", params);
+    let verification = execute(&params, &OperationContext::new(master_config, camera_manager)).await?;
+    if !verification.is_intact() {
+        bail!("Archive verification failed: {} corrupted and {} missing file(s)", verification.mismatched.len(), verification.missing.len());
+    }
+    Ok(())
+}
+
+/// Re-hashes every file recorded in the session manifests under the directory and compares
+/// it with the SHA-256 stored at capture time. The archive may have been moved: paths are
+/// also looked up relative to each manifest's directory.
+pub async fn execute(params: &VerifyArchiveParams, ctx: &OperationContext<'_>) -> Result<ArchiveVerification> {
+    let op_start_time = Instant::now();
+    let base = params.dir.clone().unwrap_or_else(|| PathBuf::from(&ctx.master_config.application.output_directory_base));
+    let manifests: Vec<(PathBuf, SessionManifest)> = match &params.session {
+        Some(session) => {
+            let mut app = ctx.master_config.application.clone();
+            app.output_directory_base = base.to_string_lossy().into_owned();
+            let manifest = SessionManifest::load(&app, session)?;
+            vec![(base.join(&manifest.session_id), manifest)]
+        }
+        None => SessionManifest::find_all(&base)?,
+    };
+    if manifests.is_empty() {
+        bail!("No session manifests found under {}", base.display());
+    }
+    info!("🔏 Verifying {} session(s) under {}...", manifests.len(), base.display());
+
+    let cancel = ctx.cancel.clone();
+    let verification = tokio::task::spawn_blocking(move || {
+        let mut verification = ArchiveVerification { sessions: manifests.len(), ..Default::default() };
+        for (session_dir, manifest) in &manifests {
+            if cancel.is_cancelled() {
+                warn!("🛑 Verification interrupted.");
+                break;
+            }
+            let files: Vec<&PathBuf> = manifest.cameras.iter().flat_map(|camera| camera.files.iter()).collect();
+            for recorded in files {
+                let Some(expected) = manifest.checksums.get(recorded) else {
+                    verification.unhashed += 1;
+                    continue;
+                };
+                let Some(path) = locate(recorded, &manifest.output_dir, session_dir) else {
+                    error!("❓ Missing: {} (session '{}')", recorded.display(), manifest.session_id);
+                    verification.missing.push(recorded.clone());
+                    continue;
+                };
+                match checksum::sha256_file(&path) {
+                    Ok(actual) if actual == *expected => verification.verified += 1,
+                    Ok(actual) => {
+                        error!("💥 Checksum mismatch: {} (session '{}'): expected {}, got {}", path.display(), manifest.session_id, expected, actual);
+                        verification.mismatched.push(path);
+                    }
+                    Err(e) => {
+                        error!("❓ Unreadable: {}: {:#}", path.display(), e);
+                        verification.missing.push(path);
+                    }
+                }
+            }
+        }
+        verification
+    }).await.context("Verification task failed")?;
+
+    if verification.unhashed > 0 {
+        warn!("⚠️ {} file(s) have no recorded checksum (captured without application.checksums) and were not checked.", verification.unhashed);
+    }
+    let summary = format!("{} verified, {} corrupted, {} missing across {} session(s) in {:?}",
+        verification.verified, verification.mismatched.len(), verification.missing.len(), verification.sessions, op_start_time.elapsed());
+    if verification.is_intact() {
+        info!("✅ Archive intact: {}.", summary);
+    } else {
+        error!("❌ Archive damaged: {}.", summary);
+    }
+    Ok(verification)
+}
+
+/// A recorded path as written, or relative to the session folder's current location.
+fn locate(recorded: &Path, original_session_dir: &Path, session_dir: &Path) -> Option<PathBuf> {
+    if recorded.is_file() {
+        return Some(recorded.to_path_buf());
+    }
+    if let Ok(relative) = recorded.strip_prefix(original_session_dir) {
+        let moved = session_dir.join(relative);
+        if moved.is_file() {
+            return Some(moved);
+        }
+    }
+    // Files a path_template put beside the session folder keep their place relative to the output directory.
+    let original_base = original_session_dir.parent()?;
+    let relative = recorded.strip_prefix(original_base).ok()?;
+    let moved = session_dir.parent()?.join(relative);
+    moved.is_file().then_some(moved)
+}
@@ -1,141 +1,218 @@
-use crate::config_loader::{MasterConfig};
+use crate::config_loader::{CaptureDeviceConfig, IpCameraSpecificConfig, MasterConfig, CameraTimeouts};
 use crate::core::camera_manager::CameraManager;
 use crate::camera::camera_media::CameraMediaManager;
+use crate::camera::frame_sink;
+use crate::camera::mkv_mux;
 use crate::camera::ip_camera_device::IpCameraDevice;
+use crate::camera::realsense_device;
+use crate::camera::rtsp_grab::RtspTransport;
+use crate::core::capture_source::VideoSource;
 use anyhow::{Result, anyhow};
 use crate::operations::op_helper;
-use clap::ArgMatches;
+use crate::operations::op_context::OperationContext;
+use crate::operations::op_result::{CameraResult, OperationReport};
+use crate::cli::RecordVideoArgs;
 use log::{info, error, debug, warn};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
-use rerun::RecordingStreamBuilder;
-use rerun::datatypes::{TensorData, TensorBuffer, ColorModel};
-use rerun::archetypes::Image as RerunImage;
+use crate::common::{checksum, disk_space, hooks, sync_start, timing_report};
+use crate::common::rerun_setup::{self, LivePreview, RerunIdentity, RerunParams};
+use crate::common::session::SessionManifest;
+use crate::storage;
 use opencv::prelude::*;
-use opencv::{videoio, imgproc, core as opencv_core};
+use opencv::{videoio, core as opencv_core};
+
+/// Typed parameters for a video recording, independent of the CLI.
+#[derive(Debug, Clone, Default)]
+pub struct RecordVideoParams {
+    pub cameras: Option<Vec<String>>, // None = all configured cameras
+    pub duration: Option<Duration>,   // None = AppSettings.video_duration_default_seconds
+    pub output_dir: Option<PathBuf>,
+    pub rerun: RerunParams,
+    pub require_all: bool,
+    pub session_mkv: Option<bool>, // None = AppSettings.session_mkv
+    pub rerun_live: Option<bool>,    // None = AppSettings.rerun_live_video
+    pub rerun_live_fps: Option<f32>, // None = AppSettings.rerun_live_fps
+    pub vfr: Option<bool>,           // None = AppSettings.video_timing
+    pub timing_report: bool,         // log a per-camera timing table and store it in the manifest
+    pub start_at: Option<String>,    // None = AppSettings.start_at
+    pub start_offset_ms: Option<u64>, // None = AppSettings.start_offset_ms
+    pub stream: Option<String>,       // named stream for every camera; None = per operation_streams
+    pub stream_key: Option<&'static str>, // operation_streams entry to use; None = "capture-video"
+}
+
+impl RecordVideoParams {
+    pub fn from_args(args: &RecordVideoArgs) -> Self {
+        RecordVideoParams {
+            cameras: op_helper::parse_camera_selection(args.cameras.as_ref()),
+            duration: args.duration.map(Duration::from_secs),
+            output_dir: args.output.as_ref().map(PathBuf::from),
+            rerun: RerunParams::from_args(&args.rerun),
+            require_all: args.require_all,
+            session_mkv: args.mkv.then_some(true),
+            rerun_live: args.rerun_live.then_some(true),
+            rerun_live_fps: args.rerun_live_fps,
+            vfr: args.vfr.then_some(true),
+            timing_report: args.timing_report,
+            start_at: args.start_at.clone(),
+            start_offset_ms: args.start_offset_ms,
+            stream: args.stream.clone(),
+            stream_key: None,
+        }
+    }
+}
 
 pub async fn handle_record_video_cli(
     master_config: &MasterConfig,
     camera_manager: &CameraManager,
-    args: &ArgMatches,
-) -> Result<()> {
+    args: &RecordVideoArgs,
+) -> Result<OperationReport> {
+    let params = RecordVideoParams::from_args(args);
+    debug!("Record video CLI parsed into: {:?}", params);
+    execute(&params, &OperationContext::new(master_config, camera_manager)).await
+}
+
+pub async fn execute(params: &RecordVideoParams, ctx: &OperationContext<'_>) -> Result<OperationReport> {
+    let master_config = ctx.master_config;
     let op_start_time = Instant::now();
     let operation_display_name = "Video Recording";
+    let require_all = params.require_all;
+    let mut report = OperationReport::new(operation_display_name);
 
-    let enable_rerun = args.get_one::<bool>("rerun").copied().unwrap_or(false);
-    let mut rec_stream_opt: Option<rerun::RecordingStream> = None;
+    let rec_stream_opt: Option<rerun::RecordingStream> = if params.rerun.enabled {
+        let identity = RerunIdentity::resolve(&master_config.application, &params.rerun, "rcam_video_record");
+        rerun_setup::init_recording_stream(&master_config.application, &identity, operation_display_name)
+    } else {
+        None
+    };
 
-    if enable_rerun {
-        let flush_timeout_secs = master_config.application.rerun_flush_timeout_secs.unwrap_or(10.0);
-
-        let mut opts = rerun::SpawnOptions::default();
-
-        let mut applied_memory_limit = "default".to_string();
-        if let Some(limit) = &master_config.application.rerun_memory_limit {
-            opts.memory_limit = limit.clone().into();
-            applied_memory_limit = limit.clone();
-            debug!("Rerun: Setting memory limit to: {}", limit);
-        } else {
-            debug!("Rerun: Using default memory limit.");
-        }
-
-        let mut applied_latency_config = "not set".to_string();
-        if let Some(latency_str) = &master_config.application.rerun_drop_at_latency {
-            opts.extra_args.push("--drop-at-latency".into());
-            opts.extra_args.push(latency_str.clone().into());
-            applied_latency_config = latency_str.clone();
-            debug!("Rerun: Setting drop-at-latency to: {}", latency_str);
-        } else {
-            debug!("Rerun: drop-at-latency not configured.");
-        }
+    // Events published while recording (connects, starts/stops, low disk) go to Rerun as text logs.
+    let event_log = rec_stream_opt.clone().map(rerun_setup::log_events);
 
-        match RecordingStreamBuilder::new("rcam_video_record")
-            .spawn_opts(&opts, Some(std::time::Duration::from_secs_f32(flush_timeout_secs)))
-        {
-            Ok(stream) => {
-                info!(
-                    "Rerun recording stream initialized and viewer spawned (FlushTimeout: {}s, MemoryLimit: {}, DropAtLatency: {}).",
-                    flush_timeout_secs,
-                    applied_memory_limit,
-                    applied_latency_config
-                );
-                rec_stream_opt = Some(stream);
-            }
-            Err(e) => {
-                error!("Failed to initialize Rerun recording stream: {}. Continuing without Rerun.", e);
-            }
-        }
-    }
-
-    let duration_seconds_arg = args.get_one::<u64>("duration").copied();
-    let duration_seconds = duration_seconds_arg.unwrap_or(master_config.application.video_duration_default_seconds as u64);
-    let recording_duration = Duration::from_secs(duration_seconds);
+    let recording_duration = params.duration
+        .unwrap_or_else(|| Duration::from_secs(master_config.application.video_duration_default_seconds as u64));
     debug!(
-        "Record video CLI: duration_arg: {:?}, effective_duration: {:?}, cameras_arg: {:?}, output_arg: {:?}",
-        duration_seconds_arg, recording_duration, args.get_one::<String>("cameras"), args.get_one::<String>("output")
+        "Record video: requested duration: {:?}, effective_duration: {:?}, cameras: {:?}, output: {:?}",
+        params.duration, recording_duration, params.cameras, params.output_dir
     );
     info!("📹 Preparing to record video for {:?} from specified cameras.", recording_duration);
 
     let media_manager_init_start = Instant::now();
-    let media_manager = CameraMediaManager::new();
+    let mut media_manager = CameraMediaManager::new();
+    // Live mode tees frames to Rerun while recording instead of replaying the files afterwards.
+    let live_preview = rec_stream_opt.as_ref()
+        .filter(|_| params.rerun_live.or(master_config.application.rerun_live_video).unwrap_or(false))
+        .map(|stream| LivePreview {
+            stream: stream.clone(),
+            fps: params.rerun_live_fps.or(master_config.application.rerun_live_fps).unwrap_or(2.0),
+        });
+    if let Some(preview) = &live_preview {
+        if !(preview.fps.is_finite() && preview.fps > 0.0) {
+            return Err(anyhow!("--rerun-live-fps must be positive (got {})", preview.fps));
+        }
+        info!("📡 Rerun: Streaming recorded frames live at up to {} fps per camera.", preview.fps);
+        media_manager.set_live_preview(preview.clone());
+    }
     debug!("CameraMediaManager initialized for video recording in {:?}.", media_manager_init_start.elapsed());
 
     let target_devices = op_helper::determine_target_devices(
-        camera_manager, 
-        args.get_one::<String>("cameras"),
-        operation_display_name
+        ctx.camera_manager,
+        params.cameras.as_deref(),
+        operation_display_name,
+        require_all,
     ).await?;
 
     if target_devices.is_empty() {
         info!("No cameras selected or available for video recording. Exiting.");
-        return Ok(());
+        return Ok(report);
+    }
+
+    let mut target_names = Vec::new();
+    for device_arc in &target_devices {
+        target_names.push(device_arc.lock().await.get_name());
     }
+    // Before the RTSP URLs are built, so they point at where each camera is now.
+    let mac_resolutions = op_helper::resolve_by_mac(master_config, &target_names).await;
 
+    // Each source says how it is recorded: a stream URL recorded through OpenCV, or a
+    // recording it writes itself. Sources with neither are left out.
+    let stream_key = params.stream_key.unwrap_or("capture-video");
     let mut cameras_info = Vec::new();
     for device_arc in &target_devices {
-        let locked_device = device_arc.lock().await;
-        let name = locked_device.get_name();
-        let device_type = locked_device.get_type();
+        let mut device = device_arc.lock().await;
+        let name = device.get_name();
+        let device_type = device.get_type();
+        let ip_specifics = match master_config.cameras.iter().find(|c| *c.get_name() == name) {
+            Some(CaptureDeviceConfig::IpCamera { specifics, .. }) => Some(specifics),
+            _ => None,
+        };
+        let stream = ip_specifics.and_then(|specifics| params.stream.clone().or_else(|| specifics.stream_for(&master_config.application, stream_key)));
+        device.select_stream(stream.as_deref());
 
-        if device_type == "ip-camera" {
-            if let Some(cam_config) = master_config.cameras.iter().find(|c| *c.get_name() == name) {
-                if let crate::config_loader::CaptureDeviceConfig::IpCamera { specifics, .. } = cam_config {
-                    let temp_ip_device = IpCameraDevice::new(name.clone(), specifics.clone());
-                    match temp_ip_device.get_rtsp_url() {
-                        Ok(url) => cameras_info.push((name.clone(), url)),
-                        Err(e) => {
-                            error!("Failed to get RTSP URL for IP camera '{}' (type: {}): {}. This camera will be excluded.", name, device_type, e);
-                        }
-                    }
-                } else {
-                     error!("Device '{}' is type 'ip-camera' but its config in master_config is not IpCameraSpecific. Skipping.", name);
+        match device.video_source().await {
+            Ok(Some(VideoSource::Stream(url))) => {
+                if let Some(specifics) = ip_specifics {
+                    configure_ip_stream(&mut media_manager, master_config, &name, specifics);
                 }
-            } else {
-                error!("Could not find config for IP camera '{}' in master_config. Skipping.", name);
+                cameras_info.push((name, url));
+            }
+            Ok(Some(VideoSource::Local(recorder))) => {
+                cameras_info.push((name.clone(), recorder.describe()));
+                media_manager.set_local_source(&name, recorder);
+            }
+            Ok(None) => info!("Device '{}' ({}) has nothing to record. Skipping for video recording.", name, device_type),
+            Err(e) => {
+                error!("Failed to get the video source of '{}' (type: {}): {:#}. This camera will be excluded.", name, device_type, e);
+                report.push(CameraResult::from_error(name, &e));
             }
-        } else {
-            info!("Device '{}' is of type '{}', not 'ip-camera'. Skipping for video recording.", name, device_type);
         }
     }
     
     if cameras_info.is_empty() {
-        error!("Could not retrieve RTSP URLs for any of the {} selected/available cameras. Cannot proceed with {}.", target_devices.len(), operation_display_name);
-        return Err(anyhow!("Failed to retrieve any usable RTSP URLs for video recording"));
+        error!("None of the {} selected/available cameras has a video source. Cannot proceed with {}.", target_devices.len(), operation_display_name);
+        report.log_summary();
+        if report.results.is_empty() {
+            return Err(anyhow!("No selected camera could be recorded (no stream URL or local recording)"));
+        }
+        // Every camera failed; the report says why (status TotalFailure).
+        report.enforce_require_all(require_all)?;
+        return Ok(report);
     }
+    // Fail fast before opening any stream if a camera is already known to be unusable.
+    report.enforce_require_all(require_all)?;
 
-    let _camera_name_to_index: std::collections::HashMap<String, usize> = cameras_info
-        .iter()
-        .enumerate()
-        .map(|(idx, (name, _))| (name.clone(), idx))
-        .collect();
-
-    let default_subdir_name = master_config.application.video_format.clone();
-    let output_dir = op_helper::determine_operation_output_dir(
+    let base_output_dir = op_helper::determine_operation_output_dir(
         master_config,
-        args,
-        "output",
-        Some(&default_subdir_name), 
+        params.output_dir.as_deref(),
+        None,
         operation_display_name
     )?;
+    disk_space::ensure_free_space(&base_output_dir, disk_space::min_free_bytes(&master_config.application))?;
+    let mut session = SessionManifest::create(master_config, &base_output_dir, "video", operation_display_name)?;
+    report.session = Some(session.output_dir.clone());
+    let output_dir = session.output_dir.clone();
+    for resolution in &mac_resolutions {
+        session.set_resolved_ip(resolution);
+    }
+    for (name, _) in &cameras_info {
+        media_manager.set_camera_output_dir(name, session.camera_dir(name)?);
+    }
+
+    for failed in report.results.iter().filter(|r| !r.is_success()) {
+        session.record_camera(&failed.name, None, Vec::new(), failed.error.clone());
+    }
+    media_manager.set_cancellation(ctx.cancel.clone());
+    media_manager.set_supervisor(ctx.camera_manager.supervisor().clone());
+    let vfr = params.vfr.unwrap_or_else(|| master_config.application.video_timing.as_deref().is_some_and(|t| t.eq_ignore_ascii_case("vfr")));
+    media_manager.set_variable_frame_rate(vfr);
+    if let Some(limit) = master_config.application.max_concurrent_streams {
+        media_manager.set_max_concurrent_streams(limit);
+    }
+    if let Some(sync) = sync_start::resolve(&master_config.application, params.start_at.as_deref(), params.start_offset_ms).await? {
+        media_manager.set_sync_start(sync);
+    }
+    let recording_started_at = chrono::Utc::now();
 
     info!(
         "🎬 Attempting video recording for {} camera(s) to {} for {:?}.",
@@ -144,16 +221,111 @@ pub async fn handle_record_video_cli(
         recording_duration
     );
 
-    match media_manager
+    let record_result = media_manager
         .record_video(
             &cameras_info,
             &master_config.application,
             output_dir.clone(), 
             recording_duration,
         )
-        .await
-    {
-        Ok(paths) => {
+        .await;
+    if let Some(listener) = event_log {
+        listener.finish().await;
+    }
+    match record_result {
+        Ok(per_camera_results) => {
+            if media_manager.stopped_for_low_disk() {
+                error!("💾 Recording stopped early because free disk space fell below min_free_disk_mb; the files hold everything up to that point.");
+                session.event("error", None, "Recording stopped early: free disk space fell below min_free_disk_mb");
+            }
+            if ctx.cancel.is_cancelled() {
+                warn!("🛑 Recording was interrupted; the files hold everything up to the interruption.");
+                session.event("warn", None, "Recording stopped early by Ctrl-C");
+            }
+            // One entry per file; a camera whose recording rolled over contributes one per segment, in order.
+            let mut paths = Vec::new();
+            let mut recorded_names = Vec::new();
+            let mut segmented_cameras = Vec::new();
+            let timings = media_manager.timings();
+            let mut recorded = media_manager.recorded();
+            let mut fallbacks = media_manager.http_fallbacks();
+            let mut timing_rows = Vec::new();
+            for (name, result) in per_camera_results {
+                let camera_timings = timings.get(&name).copied();
+                match result {
+                    Ok(segments) => {
+                        let mut files = segments.clone();
+                        files.extend(segments.iter().map(|p| frame_sink::timestamps_path(p)).filter(|p| p.exists()));
+                        session.record_camera(&name, Some(recording_started_at), files, None);
+                        let mut result = CameraResult::success(name.clone());
+                        if let Some(fallback) = fallbacks.remove(&name) {
+                            result = result.took(fallback.duration);
+                            session.set_recorded(&name, fallback.duration.as_secs_f64(), Vec::new());
+                            session.set_degraded(&name, format!(
+                                "RTSP stream could not be opened; recorded from HTTP snapshots at {:.2} fps effective ({} image(s), {} failed poll(s))",
+                                fallback.effective_fps(), fallback.snapshots, fallback.failed_polls));
+                        }
+                        if let Some(span) = recorded.remove(&name) {
+                            result = result.took(span.duration);
+                            if span.duration + Duration::from_secs(1) < recording_duration {
+                                info!("⏱️ '{}' recorded {:.1}s of the planned {:?}.", name, span.duration.as_secs_f64(), recording_duration);
+                                session.event("info", Some(&name), format!("recorded {:.1}s of the planned {:?}", span.duration.as_secs_f64(), recording_duration));
+                            }
+                            session.set_recorded(&name, span.duration.as_secs_f64(), span.gaps);
+                            if let Some(stream) = span.stream {
+                                session.set_stream(&name, stream);
+                            }
+                        }
+                        report.push(result);
+                        if segments.len() > 1 {
+                            segmented_cameras.push(name.clone());
+                        }
+                        for path in segments {
+                            recorded_names.push(name.clone());
+                            paths.push(path);
+                        }
+                    }
+                    Err(e) => {
+                        session.record_camera(&name, Some(recording_started_at), Vec::new(), Some(format!("{:#}", e)));
+                        report.push(CameraResult::from_error(name.clone(), &e));
+                    }
+                }
+                if params.timing_report {
+                    if let Some(camera_timings) = camera_timings {
+                        session.set_timings(&name, camera_timings);
+                    }
+                    timing_rows.push((name, camera_timings));
+                }
+            }
+            if params.timing_report {
+                timing_report::log_table(operation_display_name, &timing_rows);
+            }
+
+            // Per-camera files are kept either way; a failed mux only costs the convenience file.
+            let want_mkv = params.session_mkv.or(master_config.application.session_mkv).unwrap_or(false);
+            if want_mkv && !segmented_cameras.is_empty() {
+                // The combined file would be larger than the segments that max_file_size_mb just produced.
+                warn!("⚠️ Skipping session MKV: recordings of {} were split at max_file_size_mb.", segmented_cameras.join(", "));
+                session.event("warn", None, "Session MKV skipped because recordings were split into segments");
+            } else if want_mkv && paths.iter().any(|path| !realsense_device::is_bag(path)) {
+                let ffmpeg_path = master_config.application.ffmpeg_path.as_deref().unwrap_or("ffmpeg");
+                let ts_str = chrono::Utc::now().format(&master_config.application.filename_timestamp_format).to_string();
+                let session_path = output_dir.join(format!("session_{}.mkv", ts_str));
+                // RealSense .bag files stay separate; they are not video streams FFmpeg can mux.
+                let tracks: Vec<(String, PathBuf)> = recorded_names.iter().cloned().zip(paths.iter().cloned())
+                    .filter(|(_, path)| !realsense_device::is_bag(path))
+                    .collect();
+                match mkv_mux::mux_session_mkv(ffmpeg_path, &tracks, &session_path).await {
+                    Ok(path) => {
+                        info!("🎞️ Session MKV with {} track(s): {}", tracks.len(), path.display());
+                        session.event("info", None, format!("Session MKV written to {}", path.display()));
+                    }
+                    Err(e) => {
+                        warn!("⚠️ Could not create session MKV {}: {:#}. Per-camera files are unaffected.", session_path.display(), e);
+                        session.event("warn", None, format!("Session MKV failed: {:#}", e));
+                    }
+                }
+            }
             if paths.is_empty() && !cameras_info.is_empty() {
                 warn!(
                     "📹 Video recording completed but no files were produced. This might indicate an issue during recording for all cameras."
@@ -170,22 +342,32 @@ pub async fn handle_record_video_cli(
                     info!("  -> {}", path.display());
                 }
 
-                if let Some(rec_stream) = &rec_stream_opt {
+                if let (Some(rec_stream), Some(_)) = (&rec_stream_opt, &live_preview) {
+                    info!("Rerun: Frames were streamed live; flushing instead of replaying the recorded files.");
+                    rec_stream.flush_blocking();
+                } else if let Some(rec_stream) = &rec_stream_opt {
                     if paths.is_empty() {
                         info!("Rerun: No videos were recorded, nothing to log to Rerun.");
                     } else {
                         info!("Rerun: Logging {} recorded video file(s) frame by frame...", paths.len());
                     }
 
+                    let replay_fps = master_config.application.video_fps.unwrap_or(30.0) as f64;
+                    // Segments of one camera continue where the previous one ended on the timeline.
+                    let mut frames_logged: HashMap<String, i64> = HashMap::new();
                     for (idx, video_path) in paths.iter().enumerate() {
-                        let camera_name_opt = cameras_info.get(idx).map(|(name, _url)| name.as_str());
+                        let camera_name_opt = recorded_names.get(idx).map(|name| name.as_str());
                         
                         let entity_path_str = if let Some(name) = camera_name_opt {
-                            format!("recorded_videos/{}/frame", name)
+                            format!("cameras/{}/video", name)
                         } else {
-                            format!("capture/video_stream_{}", idx)
+                            format!("cameras/video_stream_{}/video", idx)
                         };
 
+                        if realsense_device::is_bag(video_path) {
+                            info!("Rerun: Skipping {}; replay it through a camera with bag_file instead.", video_path.display());
+                            continue;
+                        }
                         debug!("Rerun: Processing video {} for entity path: {}", video_path.display(), entity_path_str);
 
                         match videoio::VideoCapture::from_file(&video_path.to_string_lossy(), videoio::CAP_ANY) {
@@ -195,7 +377,10 @@ pub async fn handle_record_video_cli(
                                     continue;
                                 }
 
-                                let mut frame_idx = 0i64;
+                                let mut frame_idx = frames_logged.get(&entity_path_str).copied().unwrap_or(0);
+                                let first_frame_idx = frame_idx;
+                                // Real capture times when the recording logged them (always for VFR files).
+                                let frame_times = frame_sink::read_timestamps(video_path);
                                 let mut bgr_frame = opencv_core::Mat::default();
                                 loop {
                                     match cap.read(&mut bgr_frame) {
@@ -225,52 +410,27 @@ pub async fn handle_record_video_cli(
                                         break;
                                     }
 
-                                    rec_stream.set_duration_secs("video_timeline", op_start_time.elapsed().as_secs_f64());
+                                    // Without a timestamp log, place frames at their nominal offset from the recording start.
+                                    let captured_at_secs = match frame_times.as_ref().and_then(|times| times.get((frame_idx - first_frame_idx) as usize)) {
+                                        Some(at) => at.timestamp_micros() as f64 / 1e6,
+                                        None => recording_started_at.timestamp_micros() as f64 / 1e6 + frame_idx as f64 / replay_fps,
+                                    };
+                                    rec_stream.set_timestamp_secs_since_epoch("capture_time", captured_at_secs);
                                     
-                                    let mut rgb_frame = opencv_core::Mat::default();
-                                    if let Err(e) = imgproc::cvt_color(&bgr_frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0) {
-                                        error!("Rerun: Failed to convert frame to RGB for {}: {}. Skipping frame.", video_path.display(), e);
-                                        frame_idx += 1;
-                                        continue;
-                                    }
-
-                                    match rgb_frame.data_bytes() {
-                                        Ok(data) => {
-                                            let rows = rgb_frame.rows() as u64;
-                                            let cols = rgb_frame.cols() as u64;
-                                            let channels = rgb_frame.channels() as u64;
-
-                                            let dimension_sizes = vec![rows, cols, channels];
-
-                                            let tensor_data = TensorData::new(
-                                                dimension_sizes,
-                                                TensorBuffer::U8(data.to_vec().into())
-                                            );
-                                            
-                                            match RerunImage::from_color_model_and_tensor(ColorModel::RGB, tensor_data.clone()) {
-                                                Ok(rerun_image_archetype) => {
-                                                    if let Err(e) = rec_stream.log(&*entity_path_str, &rerun_image_archetype) {
-                                                        error!(
-                                                            "Rerun: Failed to log frame {} from {} to Rerun: {}",
-                                                            frame_idx, video_path.display(), e
-                                                        );
-                                                    } else {
-                                                        if frame_idx % 100 == 0 {
-                                                            debug!("Rerun: Logged frame {} for {} to {}", frame_idx, video_path.display(), entity_path_str);
-                                                        }
-                                                    }
-                                                }
-                                                Err(e) => {
-                                                    error!(
-                                                        "Rerun: Failed to create Rerun image for frame {} from {}: {:?}",
-                                                        frame_idx, video_path.display(), e
-                                                    );
-                                                }
+                                    match rerun_setup::bgr_image(&bgr_frame) {
+                                        Ok(rerun_image_archetype) => {
+                                            if let Err(e) = rec_stream.log(&*entity_path_str, &rerun_image_archetype) {
+                                                error!(
+                                                    "Rerun: Failed to log frame {} from {} to Rerun: {}",
+                                                    frame_idx, video_path.display(), e
+                                                );
+                                            } else if frame_idx % 100 == 0 {
+                                                debug!("Rerun: Logged frame {} for {} to {}", frame_idx, video_path.display(), entity_path_str);
                                             }
                                         }
                                         Err(e) => {
                                             error!(
-                                                "Rerun: Failed to get data_bytes for frame {} from {}: {}. Skipping frame.",
+                                                "Rerun: Failed to create Rerun image for frame {} from {}: {:#}",
                                                 frame_idx, video_path.display(), e
                                             );
                                         }
@@ -278,6 +438,7 @@ pub async fn handle_record_video_cli(
                                     frame_idx += 1;
                                 }
                                 info!("Rerun: Finished processing video {} ({} frames) for entity path: {}", video_path.display(), frame_idx, entity_path_str);
+                                frames_logged.insert(entity_path_str.clone(), frame_idx);
                             }
                             Err(e) => {
                                 error!("Rerun: Failed to create VideoCapture for {}: {}", video_path.display(), e);
@@ -291,7 +452,18 @@ pub async fn handle_record_video_cli(
                 }
             }
             info!("📹 All video recording operations completed in {:?}.", op_start_time.elapsed());
-            Ok(())
+            checksum::record_session_checksums(&master_config.application, &mut session).await;
+            if let Err(e) = session.finish() {
+                warn!("⚠️ Could not write session manifest: {:#}", e);
+            }
+            let hooks_result = hooks::run_post_capture_hooks(&master_config.application, &mut session, "video").await;
+            if let Err(e) = storage::upload_session(&master_config.application, &mut session).await {
+                warn!("⚠️ Upload to remote storage incomplete: {:#}", e);
+            }
+            report.log_summary();
+            report.enforce_require_all(require_all)?;
+            hooks_result?;
+            Ok(report)
         }
         Err(e) => {
             error!(
@@ -300,7 +472,35 @@ pub async fn handle_record_video_cli(
                 op_start_time.elapsed(),
                 e
             );
+            session.event("error", None, format!("Recording failed: {:#}", e));
+            if let Err(write_err) = session.finish() {
+                warn!("⚠️ Could not write session manifest: {:#}", write_err);
+            }
             Err(e)
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// Per-camera stream settings of an IP camera: timeouts, CPU pinning, RTSP transport, the
+/// bitrate floor and the HTTP snapshot fallback.
+fn configure_ip_stream(media_manager: &mut CameraMediaManager, master_config: &MasterConfig, name: &str, specifics: &IpCameraSpecificConfig) {
+    let timeouts = CameraTimeouts::resolve(&master_config.application, Some(specifics));
+    media_manager.set_camera_timeouts(name, timeouts);
+    if let Some(cpus) = &specifics.cpu_affinity {
+        media_manager.set_cpu_affinity(name, cpus.clone());
+    }
+    if let Some(transport) = RtspTransport::resolve(&master_config.application, Some(specifics)) {
+        media_manager.set_rtsp_transport(name, transport);
+    }
+    if let Some(kbps) = specifics.min_stream_kbps {
+        media_manager.set_min_stream_kbps(name, kbps);
+    }
+    if !specifics.http_fallback.or(master_config.application.http_fallback).unwrap_or(false) {
+        return;
+    }
+    match IpCameraDevice::new(name.to_string(), specifics.clone(), timeouts) {
+        Ok(device) if device.driver.has_http_api() => media_manager.set_http_fallback(name, device),
+        Ok(_) => warn!("⚠️ '{}' has http_fallback set, but its vendor driver has no HTTP snapshot endpoint; ignored.", name),
+        Err(e) => warn!("⚠️ '{}': HTTP snapshot fallback unavailable: {:#}", name, e),
+    }
+}
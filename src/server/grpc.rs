@@ -0,0 +1,154 @@
+//! gRPC flavour of the agent API (`proto/rcam.proto`), for robot stacks that already speak
+//! gRPC. Shares its state, and so the one-operation-at-a-time rule, with the HTTP API.
+
+use super::ApiState;
+use crate::core::events;
+use crate::errors::{self, ErrorKind};
+use crate::operations::op_result::OperationReport;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+use tonic::{Request, Response, Status};
+
+/// Types generated from `proto/rcam.proto`, including the `RcamControlClient`.
+pub mod pb {
+    tonic::include_proto!("rcam.v1");
+}
+
+use pb::rcam_control_server::{RcamControl, RcamControlServer};
+
+pub use pb::rcam_control_client::RcamControlClient;
+
+struct ControlService {
+    state: Arc<ApiState>,
+}
+
+/// Serves the gRPC API on `listen` until `cancel` fires.
+pub async fn serve(listen: SocketAddr, state: Arc<ApiState>, cancel: CancellationToken) -> Result<()> {
+    let token = state.token.clone();
+    let authorize = move |request: Request<()>| -> Result<Request<()>, Status> {
+        if let Some(token) = &token {
+            let presented = request.metadata().get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+            if !super::token_matches(presented, token) {
+                warn!("🔒 Rejected gRPC call: missing or wrong bearer token.");
+                return Err(Status::unauthenticated("missing or wrong bearer token"));
+            }
+        }
+        Ok(request)
+    };
+    info!("🛰️ gRPC API listening on {} ({} camera(s)).", listen, state.rcam.config().cameras.len());
+    tonic::transport::Server::builder()
+        .add_service(RcamControlServer::with_interceptor(ControlService { state }, authorize))
+        .serve_with_shutdown(listen, async move { cancel.cancelled().await })
+        .await
+        .context("gRPC server failed")
+}
+
+fn selection(cameras: Vec<String>) -> Option<Vec<String>> {
+    if cameras.is_empty() { None } else { Some(cameras) }
+}
+
+impl ControlService {
+    fn reply(&self, result: anyhow::Result<OperationReport>) -> Result<Response<pb::OperationReply>, Status> {
+        let report = result.map_err(|e| {
+            let message = format!("{:#}", e);
+            match errors::classify(&e) {
+                ErrorKind::Config => Status::invalid_argument(message),
+                ErrorKind::Auth => Status::permission_denied(message),
+                ErrorKind::Network => Status::unavailable(message),
+                _ => Status::internal(message),
+            }
+        })?;
+        let manifest_json = report.session.as_ref()
+            .and_then(|dir| std::fs::read_to_string(dir.join(crate::common::session::MANIFEST_FILE)).ok());
+        Ok(Response::new(pb::OperationReply {
+            host: self.state.host.clone(),
+            operation: report.operation.clone(),
+            cameras: report.results.iter().map(|r| pb::CameraOutcome { name: r.name.clone(), error: r.error.clone(), error_kind: r.kind.map(|k| k.name().to_string()) }).collect(),
+            session_dir: report.session.as_ref().map(|dir| dir.display().to_string()),
+            manifest_json,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl RcamControl for ControlService {
+    async fn capture(&self, request: Request<pb::CaptureRequest>) -> Result<Response<pb::OperationReply>, Status> {
+        let request = request.into_inner();
+        let cameras = selection(request.cameras);
+        let running = self.state.begin_snapshot(cameras.as_deref()).ok_or_else(|| Status::aborted("another operation is running"))?;
+        info!("🛰️ gRPC Capture: cameras {:?}, start {:?}.", cameras, request.start_at);
+        let params = crate::operations::image_capture_op::CaptureImageParams {
+            cameras,
+            count: request.count.unwrap_or(1).max(1),
+            interval: Duration::from_millis(request.interval_ms.unwrap_or(0)),
+            retries: request.retries,
+            start_at: request.start_at,
+            ..Default::default()
+        };
+        let state = self.state.clone();
+        let result = self.state.run_detached(running, async move { state.rcam.capture_images(&params).await }).await;
+        self.reply(result)
+    }
+
+    async fn record(&self, request: Request<pb::RecordRequest>) -> Result<Response<pb::OperationReply>, Status> {
+        let running = self.state.begin().ok_or_else(|| Status::aborted("another operation is running"))?;
+        let request = request.into_inner();
+        info!("🛰️ gRPC Record: cameras {:?}, {:?}s, start {:?}.", request.cameras, request.duration_secs, request.start_at);
+        let params = crate::operations::video_record_op::RecordVideoParams {
+            cameras: selection(request.cameras),
+            duration: request.duration_secs.map(Duration::from_secs),
+            start_at: request.start_at,
+            ..Default::default()
+        };
+        let state = self.state.clone();
+        let result = self.state.run_detached(running, async move { state.rcam.record_video(&params).await }).await;
+        self.reply(result)
+    }
+
+    async fn list_cameras(&self, _request: Request<pb::ListCamerasRequest>) -> Result<Response<pb::ListCamerasReply>, Status> {
+        let cameras = self.state.rcam.config().cameras.iter().map(|camera| pb::CameraInfo {
+            name: camera.get_name().clone(),
+            kind: super::camera_kind(camera).to_string(),
+        }).collect();
+        Ok(Response::new(pb::ListCamerasReply {
+            host: self.state.host.clone(),
+            cameras,
+            busy: self.state.busy.try_lock().is_err(),
+        }))
+    }
+
+    type WatchEventsStream = Pin<Box<dyn Stream<Item = Result<pb::Event, Status>> + Send + 'static>>;
+
+    async fn watch_events(&self, request: Request<pb::WatchEventsRequest>) -> Result<Response<Self::WatchEventsStream>, Status> {
+        let request = request.into_inner();
+        info!("🛰️ gRPC WatchEvents: cameras {:?}, types {:?}.", request.cameras, request.types);
+        let stream = BroadcastStream::new(events::subscribe()).filter_map(move |received| {
+            let event = match received {
+                Ok(event) => event,
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    warn!("📣 gRPC event stream fell behind and skipped {} event(s).", skipped);
+                    return None;
+                }
+            };
+            let camera_wanted = request.cameras.is_empty() || event.camera().is_some_and(|c| request.cameras.iter().any(|w| w == c));
+            let type_wanted = request.types.is_empty() || request.types.iter().any(|t| t == event.name());
+            (camera_wanted && type_wanted).then(|| Ok(pb::Event {
+                r#type: event.name().to_string(),
+                camera: event.camera().map(str::to_string),
+                at: event.at().to_rfc3339(),
+                message: event.to_string(),
+                json: serde_json::to_string(&event).unwrap_or_default(),
+            }))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -0,0 +1,349 @@
+//! HTTP API served by `rcam agent`, through which a coordinator (or any other client) runs
+//! captures on this host. JSON in and out; see `protocol` for the request and reply types.
+//! `grpc` serves the same operations over gRPC, and `/` is a small dashboard for operators.
+
+pub mod grpc;
+pub mod protocol;
+
+use crate::api::Rcam;
+use crate::camera::{realsense_hotplug, stream_stats};
+use crate::common::recording_control;
+use crate::common::session::{SessionManifest, MANIFEST_FILE};
+use crate::config_loader::CaptureDeviceConfig;
+use crate::core::events;
+use crate::errors::ErrorKind;
+use crate::operations::op_result::OperationReport;
+use anyhow::{anyhow, Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{self, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::{debug, info, warn};
+use chrono::{DateTime, Utc};
+use protocol::{
+    AgentStatus, CameraState, CaptureImageRequest, ErrorBody, EventsQuery, OperationResponse, PauseState, RecordVideoRequest, StopReply, StopRequest,
+    API_PREFIX,
+};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Mutex, OwnedMutexGuard, OwnedRwLockReadGuard, RwLock};
+use tokio_util::sync::CancellationToken;
+
+/// What the request handlers share. The handlers outlive any borrow, so the server owns its
+/// own `Rcam` built from the same configuration.
+pub struct ApiState {
+    rcam: Rcam,
+    host: String,
+    token: Option<String>,
+    busy: Arc<Mutex<()>>, // held while an operation runs; the cameras do one thing at a time
+    snapshots: Arc<RwLock<()>>, // shared by snapshots taken from recordings in progress, which run without `busy`
+    cameras: std::sync::Mutex<BTreeMap<String, CameraHealth>>, // outcome of each camera's last operation
+}
+
+#[derive(Debug, Clone, Default)]
+struct CameraHealth {
+    error: Option<String>,
+    error_kind: Option<ErrorKind>,
+    at: Option<DateTime<Utc>>,
+    snapshot: Option<PathBuf>,
+}
+
+impl ApiState {
+    pub fn new(rcam: Rcam, token: Option<String>) -> Self {
+        ApiState { rcam, host: hostname(), token, busy: Default::default(), snapshots: Default::default(), cameras: Default::default() }
+    }
+
+    /// Takes `busy` for an operation; None while another one runs. A snapshot taken from a
+    /// recording that has just ended may still be running, so that counts too.
+    fn begin(&self) -> Option<Running> {
+        let running = self.busy.clone().try_lock_owned().ok()?;
+        drop(self.snapshots.clone().try_write_owned().ok()?);
+        Some(Running::Exclusive(running))
+    }
+
+    /// Remembers each camera's outcome, and its newest still, from a finished operation.
+    fn note(&self, result: &Result<OperationReport>) {
+        let Ok(report) = result else { return };
+        let manifest = report.session.as_ref().and_then(|dir| SessionManifest::read(&dir.join(MANIFEST_FILE)).ok());
+        let mut cameras = self.cameras.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for result in &report.results {
+            let health = cameras.entry(result.name.clone()).or_default();
+            health.error = result.error.clone();
+            health.error_kind = result.kind;
+            health.at = Some(Utc::now());
+            let still = manifest.as_ref()
+                .and_then(|m| m.cameras.iter().rev().find(|c| c.name == result.name))
+                .and_then(|c| c.files.iter().rev().find(|f| is_image(f)).cloned());
+            if still.is_some() {
+                health.snapshot = still;
+            }
+        }
+    }
+
+    /// Like `begin`, for a capture-image. While a recording holds `busy`, a snapshot of cameras
+    /// that are all recording still goes ahead: their frames come from the recording
+    /// (`recording_control::shared_frame`), so no second stream is opened. It holds a share of
+    /// `snapshots` instead, taken before checking, so no new operation starts until it is done.
+    fn begin_snapshot(&self, cameras: Option<&[String]>) -> Option<Running> {
+        let snapshot = self.snapshots.clone().try_read_owned().ok()?;
+        if let Ok(running) = self.busy.clone().try_lock_owned() {
+            return Some(Running::Exclusive(running));
+        }
+        let selected: Vec<&String> = match cameras {
+            Some(cameras) => cameras.iter().collect(),
+            None => self.rcam.config().cameras.iter().map(|c| c.get_name()).collect(),
+        };
+        let recording = recording_control::active();
+        if selected.is_empty() || !selected.iter().all(|c| recording.contains(c)) {
+            return None;
+        }
+        info!("🎞️ Snapshot of recording camera(s) {:?}; taking frames from the recording.", selected);
+        Some(Running::Snapshot(snapshot))
+    }
+
+    /// Runs `operation` on its own task and waits for it. When the client disconnects or times
+    /// out, the request future is dropped; the operation still finishes, finalizes its files and
+    /// session, and only then releases `running`.
+    async fn run_detached<F>(self: &Arc<Self>, running: Running, operation: F) -> Result<OperationReport>
+    where
+        F: Future<Output = Result<OperationReport>> + Send + 'static,
+    {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let result = operation.await;
+            state.note(&result);
+            drop(running);
+            result
+        })
+        .await
+        .unwrap_or_else(|e| Err(anyhow!("The operation task failed: {}", e)))
+    }
+}
+
+/// Compares a presented token with the expected one in time that does not depend on where they
+/// differ, so response times do not reveal how much of a guess was right.
+pub(crate) fn token_matches(presented: Option<&str>, token: &str) -> bool {
+    let Some(presented) = presented else { return false };
+    presented.len() == token.len()
+        && presented.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Held while an operation runs; see `ApiState::begin` and `begin_snapshot`.
+#[allow(dead_code)] // held for its drop
+enum Running {
+    Exclusive(OwnedMutexGuard<()>),
+    Snapshot(OwnedRwLockReadGuard<()>),
+}
+
+fn is_image(path: &std::path::Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "bmp" | "webp" | "tif" | "tiff")
+}
+
+pub fn camera_kind(camera: &CaptureDeviceConfig) -> &'static str {
+    match camera {
+        CaptureDeviceConfig::IpCamera { .. } => "ip",
+        CaptureDeviceConfig::RealsenseCamera { .. } => "realsense",
+        CaptureDeviceConfig::ThermalCamera { .. } => "thermal",
+        CaptureDeviceConfig::VirtualCamera { .. } => "virtual",
+        CaptureDeviceConfig::Custom { .. } => "custom",
+    }
+}
+
+/// An error reply: status code plus a JSON `{"error": ...}` body.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(ErrorBody { error: self.1 })).into_response()
+    }
+}
+
+pub fn router(state: Arc<ApiState>) -> Router {
+    Router::new()
+        .route(&format!("{}/status", API_PREFIX), get(status))
+        .route(&format!("{}/capture-image", API_PREFIX), post(capture_image))
+        .route(&format!("{}/capture-video", API_PREFIX), post(capture_video))
+        .route(&format!("{}/pause", API_PREFIX), post(pause))
+        .route(&format!("{}/resume", API_PREFIX), post(resume))
+        .route(&format!("{}/stop-recording", API_PREFIX), post(stop_recording))
+        .route(&format!("{}/cameras", API_PREFIX), get(cameras))
+        .route(&format!("{}/cameras/:name/snapshot", API_PREFIX), get(snapshot))
+        .route("/ws/events", get(ws_events))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        // The page holds no data; it asks for the token and sends it with its own requests.
+        .route("/", get(dashboard))
+        .with_state(state)
+}
+
+/// Serves the API on `listen` until `cancel` fires; a running operation finishes first.
+pub async fn serve(listen: SocketAddr, state: Arc<ApiState>, cancel: CancellationToken) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(listen).await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    info!("🛰️ API listening on http://{}{} ({} camera(s)); dashboard at http://{}/.", listen, API_PREFIX, state.rcam.config().cameras.len(), listen);
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { cancel.cancelled().await })
+        .await
+        .context("API server failed")
+}
+
+/// Checks the bearer token. Browsers cannot set headers on a WebSocket, so `?token=` in the
+/// query string is accepted as well.
+async fn authorize(State(state): State<Arc<ApiState>>, request: Request, next: Next) -> Response {
+    if let Some(token) = &state.token {
+        let presented = request.headers().get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| request.uri().query()
+                .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("token="))));
+        if !token_matches(presented, token) {
+            warn!("🔒 Rejected {} {}: missing or wrong bearer token.", request.method(), request.uri().path());
+            return ApiError(StatusCode::UNAUTHORIZED, "missing or wrong bearer token".to_string()).into_response();
+        }
+    }
+    next.run(request).await
+}
+
+async fn status(State(state): State<Arc<ApiState>>) -> Json<AgentStatus> {
+    Json(AgentStatus {
+        host: state.host.clone(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        cameras: state.rcam.config().cameras.iter().map(|c| c.get_name().clone()).collect(),
+        busy: state.busy.try_lock().is_err(),
+        paused: recording_control::is_paused(),
+        time: chrono::Utc::now(),
+    })
+}
+
+async fn capture_image(State(state): State<Arc<ApiState>>, Json(request): Json<CaptureImageRequest>) -> Result<Json<OperationResponse>, ApiError> {
+    let running = state.begin_snapshot(request.cameras.as_deref()).ok_or_else(|| ApiError(StatusCode::CONFLICT, "another operation is running".to_string()))?;
+    info!("🛰️ Remote capture-image: cameras {:?}, start {:?}.", request.cameras, request.start_at);
+    let task_state = state.clone();
+    let result = state.run_detached(running, async move { task_state.rcam.capture_images(&request.to_params()).await }).await;
+    Ok(Json(OperationResponse::from_report(&state.host, "Image Capture", result)))
+}
+
+async fn capture_video(State(state): State<Arc<ApiState>>, Json(request): Json<RecordVideoRequest>) -> Result<Json<OperationResponse>, ApiError> {
+    let running = state.begin().ok_or_else(|| ApiError(StatusCode::CONFLICT, "another operation is running".to_string()))?;
+    info!("🛰️ Remote capture-video: cameras {:?}, {:?}s, start {:?}.", request.cameras, request.duration_secs, request.start_at);
+    let task_state = state.clone();
+    let result = state.run_detached(running, async move { task_state.rcam.record_video(&request.to_params()).await }).await;
+    Ok(Json(OperationResponse::from_report(&state.host, "Video Recording", result)))
+}
+
+/// Pausing and stopping do not take the busy lock: they are meant for the recording that holds it.
+async fn pause() -> Json<PauseState> {
+    let changed = recording_control::pause();
+    Json(PauseState { paused: true, changed })
+}
+
+async fn resume() -> Json<PauseState> {
+    let changed = recording_control::resume();
+    Json(PauseState { paused: false, changed })
+}
+
+async fn stop_recording(Json(request): Json<StopRequest>) -> Json<StopReply> {
+    Json(StopReply { stopped: recording_control::stop(request.cameras.as_deref()) })
+}
+
+async fn dashboard() -> Html<&'static str> {
+    Html(include_str!("dashboard.html"))
+}
+
+async fn cameras(State(state): State<Arc<ApiState>>) -> Json<Vec<CameraState>> {
+    let health = state.cameras.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    let mut streams = stream_stats::current();
+    Json(state.rcam.config().cameras.iter().map(|camera| {
+        let name = camera.get_name();
+        let last = health.get(name).cloned().unwrap_or_default();
+        CameraState {
+            name: name.clone(),
+            kind: camera_kind(camera).to_string(),
+            health: match (&last.at, &last.error) {
+                (None, _) => "unknown",
+                (Some(_), None) => "ok",
+                (Some(_), Some(_)) => "error",
+            }.to_string(),
+            error: last.error,
+            error_kind: last.error_kind,
+            last_operation: last.at,
+            snapshot_url: last.snapshot.map(|_| format!("{}/cameras/{}/snapshot", API_PREFIX, name)),
+            stream: streams.remove(name),
+            connected: realsense_hotplug::is_connected(name),
+        }
+    }).collect())
+}
+
+/// `GET /api/v1/cameras/<name>/snapshot`: the camera's newest still from this agent.
+async fn snapshot(State(state): State<Arc<ApiState>>, extract::Path(name): extract::Path<String>) -> Result<Response, ApiError> {
+    let path = state.cameras.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&name)
+        .and_then(|health| health.snapshot.clone())
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("no snapshot of '{}' yet", name)))?;
+    let bytes = tokio::fs::read(&path).await
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, format!("{}: {}", path.display(), e)))?;
+    let content_type = match path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase().as_str() {
+        "png" => "image/png",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "tif" | "tiff" => "image/tiff",
+        _ => "image/jpeg",
+    };
+    Ok(([(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, "no-store")], bytes).into_response())
+}
+
+/// `GET /ws/events`: pushes every event (camera connects, captured frames, recording progress,
+/// motion, disk warnings) to the client as a JSON text message, until it disconnects.
+async fn ws_events(ws: WebSocketUpgrade, Query(query): Query<EventsQuery>) -> Response {
+    ws.on_upgrade(move |socket| push_events(socket, query))
+}
+
+async fn push_events(mut socket: WebSocket, query: EventsQuery) {
+    let mut receiver = events::subscribe();
+    info!("📣 WebSocket event client connected (cameras {:?}, types {:?}).", query.cameras, query.types);
+    loop {
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(event) if query.wants(&event) => {
+                    let json = match serde_json::to_string(&event) {
+                        Ok(json) => json,
+                        Err(e) => {
+                            debug!("Failed to serialize event '{}': {}", event.name(), e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => warn!("📣 WebSocket event client fell behind and skipped {} event(s).", skipped),
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                Some(Ok(_)) => {} // clients only listen; pings are answered by axum
+            },
+        }
+    }
+    info!("📣 WebSocket event client disconnected.");
+}
+
+/// This machine's host name, for telling agents apart in replies.
+pub fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its length; gethostname NUL-terminates on success.
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if rc != 0 {
+        return "unknown".to_string();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
@@ -0,0 +1,192 @@
+use crate::camera::stream_stats::StreamRate;
+use crate::common::session::SessionManifest;
+use crate::core::events::Event;
+use crate::errors::{self, ErrorKind};
+use crate::operations::image_capture_op::CaptureImageParams;
+use crate::operations::op_result::OperationReport;
+use crate::operations::video_record_op::RecordVideoParams;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Every agent endpoint lives under this prefix.
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Agents require, and coordinators send, this bearer token when the variable is set.
+pub const AGENT_TOKEN_ENV: &str = "RCAM_AGENT_TOKEN";
+
+/// `GET /api/v1/status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStatus {
+    pub host: String,
+    pub version: String,
+    pub cameras: Vec<String>,
+    pub busy: bool,          // an operation is running; new ones are refused
+    #[serde(default)]
+    pub paused: bool,        // recordings are paused (POST /api/v1/pause)
+    pub time: DateTime<Utc>, // the agent's clock, for a rough skew check
+}
+
+/// `POST /api/v1/capture-image`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaptureImageRequest {
+    #[serde(default)]
+    pub cameras: Option<Vec<String>>, // None = all of the agent's cameras
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>, // shared start instant (NTP time)
+    #[serde(default)]
+    pub count: Option<u32>,
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+    #[serde(default)]
+    pub stream: Option<String>, // named stream for RTSP stills; None = operation_streams
+}
+
+impl CaptureImageRequest {
+    pub fn to_params(&self) -> CaptureImageParams {
+        CaptureImageParams {
+            cameras: self.cameras.clone(),
+            count: self.count.unwrap_or(1),
+            interval: Duration::from_millis(self.interval_ms.unwrap_or(0)),
+            retries: self.retries,
+            start_at: self.start_at.map(rfc3339),
+            stream: self.stream.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// `POST /api/v1/capture-video`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordVideoRequest {
+    #[serde(default)]
+    pub cameras: Option<Vec<String>>,
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub duration_secs: Option<u64>, // None = the agent's video_duration_default_seconds
+    #[serde(default)]
+    pub stream: Option<String>, // named stream to record; None = operation_streams
+}
+
+impl RecordVideoRequest {
+    pub fn to_params(&self) -> RecordVideoParams {
+        RecordVideoParams {
+            cameras: self.cameras.clone(),
+            duration: self.duration_secs.map(Duration::from_secs),
+            start_at: self.start_at.map(rfc3339),
+            stream: self.stream.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraOutcome {
+    pub name: String,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<ErrorKind>, // "config", "network", "auth", "media", "storage" or "other"
+}
+
+/// Reply to a capture request once the operation has finished. `error` is set when the
+/// operation as a whole failed; the per-camera outcomes are then usually empty. Every error
+/// comes with an `error_kind` to branch on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationResponse {
+    pub host: String,
+    pub operation: String,
+    pub cameras: Vec<CameraOutcome>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<ErrorKind>,
+    pub manifest: Option<SessionManifest>,
+}
+
+impl OperationResponse {
+    pub fn from_report(host: &str, operation: &str, result: anyhow::Result<OperationReport>) -> Self {
+        match result {
+            Ok(report) => OperationResponse {
+                host: host.to_string(),
+                operation: report.operation.clone(),
+                cameras: report.results.iter().map(|r| CameraOutcome { name: r.name.clone(), error: r.error.clone(), error_kind: r.kind }).collect(),
+                error: None,
+                error_kind: None,
+                manifest: report.session.as_ref()
+                    .and_then(|dir| SessionManifest::read(&dir.join(crate::common::session::MANIFEST_FILE)).ok()),
+            },
+            Err(e) => OperationResponse {
+                host: host.to_string(),
+                operation: operation.to_string(),
+                cameras: Vec::new(),
+                error: Some(format!("{:#}", e)),
+                error_kind: Some(errors::classify(&e)),
+                manifest: None,
+            },
+        }
+    }
+}
+
+/// Reply to `POST /api/v1/pause` and `/api/v1/resume`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseState {
+    pub paused: bool,
+    pub changed: bool, // false if recordings already were in the requested state
+}
+
+/// `POST /api/v1/stop-recording`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StopRequest {
+    #[serde(default)]
+    pub cameras: Option<Vec<String>>, // None = every recording in progress
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopReply {
+    pub stopped: Vec<String>, // cameras asked to stop; their capture-video reply follows once files are finalized
+}
+
+/// `GET /api/v1/cameras`: what the dashboard shows for each camera.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraState {
+    pub name: String,
+    pub kind: String,                        // "ip" or "realsense"
+    pub health: String,                      // "ok" or "error" after the last operation, "unknown" before the first
+    pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<ErrorKind>,
+    pub last_operation: Option<DateTime<Utc>>,
+    pub snapshot_url: Option<String>,        // latest still this agent captured
+    #[serde(default)]
+    pub stream: Option<StreamRate>,          // frame rate and bitrate while recording
+    #[serde(default)]
+    pub connected: Option<bool>,             // RealSense only: device plugged in, from hot-plug events
+}
+
+/// Query of `GET /ws/events`, e.g. `?cameras=cam1,cam2&types=recording_progress,motion_detected`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventsQuery {
+    pub cameras: Option<String>, // comma-separated; None = all cameras plus rig-wide events
+    pub types: Option<String>,   // comma-separated event types; None = all
+}
+
+impl EventsQuery {
+    pub fn wants(&self, event: &Event) -> bool {
+        let listed = |list: &Option<String>, value: Option<&str>| {
+            list.as_deref().is_none_or(|list| value.is_some_and(|value| list.split(',').any(|item| item.trim() == value)))
+        };
+        listed(&self.cameras, event.camera()) && listed(&self.types, Some(event.name()))
+    }
+}
+
+/// Body of every non-2xx reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
@@ -0,0 +1,39 @@
+use super::StorageBackend;
+use crate::config_loader::StorageConfig;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+
+/// Copies files into a directory, typically an NFS or SMB mount.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(config: &StorageConfig) -> Result<Self> {
+        let root = config.path.as_deref().context("storage.path is required for the local backend")?;
+        Ok(LocalStorage { root: PathBuf::from(root) })
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn upload(&self, local_path: &Path, key: &str) -> Result<String> {
+        let destination = key.split('/').fold(self.root.clone(), |path, part| path.join(part));
+        if let Some(parent) = destination.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        // Copy under a temporary name first, so readers of the share never see a partial file.
+        let partial = PathBuf::from(format!("{}.part", destination.display()));
+        tokio::fs::copy(local_path, &partial).await
+            .with_context(|| format!("Failed to copy {} to {}", local_path.display(), partial.display()))?;
+        tokio::fs::rename(&partial, &destination).await
+            .with_context(|| format!("Failed to move {} into place at {}", partial.display(), destination.display()))?;
+        Ok(destination.display().to_string())
+    }
+}
@@ -0,0 +1,100 @@
+pub mod local;
+pub mod s3;
+pub mod sftp;
+
+use crate::config_loader::{AppSettings, StorageConfig};
+use crate::common::session::{SessionManifest, MANIFEST_FILE};
+use crate::operations::op_helper;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use std::path::Path;
+use std::time::Instant;
+
+/// A place finished captures are copied to after a session (`AppSettings.storage`).
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Uploads one file to `key`, a '/'-separated path relative to the backend's root.
+    /// Returns where the file ended up, for logging and the session journal.
+    async fn upload(&self, local_path: &Path, key: &str) -> Result<String>;
+}
+
+/// Returns the backend for the `storage.backend` config key.
+pub fn backend_for(config: &StorageConfig) -> Result<Box<dyn StorageBackend>> {
+    match config.backend.to_lowercase().as_str() {
+        "local" | "nfs" => Ok(Box::new(local::LocalStorage::new(config)?)),
+        "s3" => Ok(Box::new(s3::S3Storage::new(config)?)),
+        "sftp" => Ok(Box::new(sftp::SftpStorage::new(config)?)),
+        other => bail!("Unknown storage backend '{}' (supported: {})", other, SUPPORTED_BACKENDS.join(", ")),
+    }
+}
+
+pub const SUPPORTED_BACKENDS: &[&str] = &["local", "nfs", "s3", "sftp"];
+
+/// Uploads a finished session's files under `<session_id>/...`, mirroring the local layout,
+/// and records the outcome in the session journal. The manifest goes last, so a manifest at
+/// the destination means the session is complete there. With `delete_local`, media files are
+/// removed once uploaded; the local manifest is always kept. Upload failures are logged and
+/// journaled but never fail the capture, since the local files are still there.
+pub async fn upload_session(app: &AppSettings, session: &mut SessionManifest) -> Result<()> {
+    let Some(config) = &app.storage else {
+        return Ok(());
+    };
+    let backend = backend_for(config)?;
+    let upload_start = Instant::now();
+    let mut files = Vec::new();
+    op_helper::collect_files(&session.output_dir, true, &mut files)?;
+    files.retain(|path| path.file_name().is_none_or(|name| name != MANIFEST_FILE));
+    files.sort();
+    info!("☁️ Uploading {} file(s) of session '{}' to {} storage...", files.len(), session.session_id, backend.name());
+
+    let mut failed = 0;
+    for path in &files {
+        let key = session_key(session, path);
+        match backend.upload(path, &key).await {
+            Ok(location) => {
+                debug!("☁️ {} -> {}", path.display(), location);
+                if config.delete_local.unwrap_or(false) {
+                    if let Err(e) = std::fs::remove_file(path) {
+                        warn!("⚠️ Uploaded {} but could not delete the local copy: {}", path.display(), e);
+                    }
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                warn!("⚠️ Upload of {} to {} storage failed: {:#}. The local file is kept.", path.display(), backend.name(), e);
+                session.event("warn", None, format!("Upload of {} failed: {:#}", path.display(), e));
+            }
+        }
+    }
+
+    session.event(
+        if failed == 0 { "info" } else { "warn" },
+        None,
+        format!("Uploaded {} of {} file(s) to {} storage", files.len() - failed, files.len(), backend.name()),
+    );
+    let manifest_path = session.write()?;
+    let manifest_key = session_key(session, &manifest_path);
+    backend.upload(&manifest_path, &manifest_key).await?;
+    if failed > 0 {
+        bail!("{} of {} file(s) could not be uploaded to {} storage", failed, files.len(), backend.name());
+    }
+    info!("☁️ Session '{}' uploaded to {} storage in {:?}.", session.session_id, backend.name(), upload_start.elapsed());
+    Ok(())
+}
+
+/// `<session_id>/<path in the session dir>`. Files a `path_template` put outside the session
+/// directory keep their layout relative to the output directory instead.
+fn session_key(session: &SessionManifest, path: &Path) -> String {
+    let (mut parts, relative) = match path.strip_prefix(&session.output_dir) {
+        Ok(relative) => (vec![session.session_id.clone()], relative),
+        Err(_) => match path.strip_prefix(session.base_dir()) {
+            Ok(relative) => (Vec::new(), relative),
+            Err(_) => (vec![session.session_id.clone()], Path::new(path.file_name().unwrap_or_default())),
+        },
+    };
+    parts.extend(relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+    parts.join("/")
+}
@@ -0,0 +1,54 @@
+use super::StorageBackend;
+use crate::config_loader::StorageConfig;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use std::path::Path;
+
+/// Uploads to an S3 bucket, or any S3-compatible store (MinIO, Ceph, ...) via `endpoint`.
+/// Credentials come from the usual AWS sources: AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY,
+/// the shared credentials file, or the instance role.
+pub struct S3Storage {
+    bucket: Box<Bucket>,
+    prefix: String,
+}
+
+impl S3Storage {
+    pub fn new(config: &StorageConfig) -> Result<Self> {
+        let s3_config = config.s3.as_ref().context("storage.s3 is required for the s3 backend")?;
+        let region_name = s3_config.region.clone().unwrap_or_else(|| "us-east-1".to_string());
+        let region = match &s3_config.endpoint {
+            Some(endpoint) => Region::Custom { region: region_name, endpoint: endpoint.clone() },
+            None => region_name.parse().map_err(|e| anyhow!("Invalid S3 region '{}': {}", region_name, e))?,
+        };
+        let credentials = Credentials::default().context("No S3 credentials found (set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY)")?;
+        let mut bucket = Bucket::new(&s3_config.bucket, region, credentials)
+            .with_context(|| format!("Failed to configure S3 bucket '{}'", s3_config.bucket))?;
+        if s3_config.path_style.unwrap_or(s3_config.endpoint.is_some()) {
+            bucket = bucket.with_path_style();
+        }
+        let prefix = s3_config.prefix.as_deref().unwrap_or("").trim_matches('/').to_string();
+        Ok(S3Storage { bucket, prefix })
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn upload(&self, local_path: &Path, key: &str) -> Result<String> {
+        let object_key = if self.prefix.is_empty() { key.to_string() } else { format!("{}/{}", self.prefix, key) };
+        let mut file = tokio::fs::File::open(local_path).await
+            .with_context(|| format!("Failed to open {}", local_path.display()))?;
+        // Streams in multipart chunks, so multi-GB recordings are never held in memory.
+        let response = self.bucket.put_object_stream(&mut file, &object_key).await
+            .with_context(|| format!("S3 upload of {} failed", local_path.display()))?;
+        if !(200..300).contains(&response.status_code()) {
+            bail!("S3 upload of {} returned HTTP {}", local_path.display(), response.status_code());
+        }
+        Ok(format!("s3://{}/{}", self.bucket.name(), object_key))
+    }
+}
@@ -0,0 +1,79 @@
+use super::StorageBackend;
+use crate::config_loader::{SftpStorageConfig, StorageConfig};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::debug;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Uploads over SFTP with the OpenSSH `sftp` client in batch mode. Authentication is key based
+/// (`identity_file`, or the ssh agent and ~/.ssh/config); there is never a password prompt.
+pub struct SftpStorage {
+    config: SftpStorageConfig,
+}
+
+impl SftpStorage {
+    pub fn new(config: &StorageConfig) -> Result<Self> {
+        let sftp_config = config.sftp.clone().context("storage.sftp is required for the sftp backend")?;
+        Ok(SftpStorage { config: sftp_config })
+    }
+
+    fn destination(&self) -> String {
+        match &self.config.username {
+            Some(user) => format!("{}@{}", user, self.config.host),
+            None => self.config.host.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SftpStorage {
+    fn name(&self) -> &'static str {
+        "sftp"
+    }
+
+    async fn upload(&self, local_path: &Path, key: &str) -> Result<String> {
+        let remote_root = self.config.remote_dir.trim_end_matches('/');
+        let remote_path = format!("{}/{}", remote_root, key);
+
+        // "-mkdir" ignores errors, so existing directories are fine. Uploading to a temporary
+        // name and renaming keeps readers from seeing a partial file.
+        let mut batch = String::new();
+        let parts: Vec<&str> = key.split('/').collect();
+        let mut dir = remote_root.to_string();
+        for part in &parts[..parts.len().saturating_sub(1)] {
+            dir = format!("{}/{}", dir, part);
+            batch.push_str(&format!("-mkdir \"{}\"\n", dir));
+        }
+        batch.push_str(&format!("put \"{}\" \"{}.part\"\n", local_path.display(), remote_path));
+        batch.push_str(&format!("-rm \"{}\"\n", remote_path));
+        batch.push_str(&format!("rename \"{}.part\" \"{}\"\n", remote_path, remote_path));
+
+        let mut cmd = Command::new("sftp");
+        cmd.arg("-b").arg("-").arg("-o").arg("BatchMode=yes");
+        if let Some(port) = self.config.port {
+            cmd.arg("-P").arg(port.to_string());
+        }
+        if let Some(identity) = &self.config.identity_file {
+            cmd.arg("-i").arg(identity);
+        }
+        cmd.arg(self.destination());
+        cmd.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped());
+        debug!("SFTP: running {:?}", cmd);
+
+        let mut child = cmd.spawn().context("Failed to run sftp; is the OpenSSH client installed and on PATH?")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(batch.as_bytes()).await.context("Failed to send commands to sftp")?;
+        }
+        let output = child.wait_with_output().await.context("Failed to wait for sftp")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "sftp exited with {} while uploading {}: {}",
+                output.status, local_path.display(), String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(format!("sftp://{}{}", self.destination(), remote_path))
+    }
+}
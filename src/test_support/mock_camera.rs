@@ -0,0 +1,445 @@
+use super::mock_rtsp;
+use crate::camera::{auth, virtual_camera};
+use crate::common::opencv_pool;
+use crate::config_loader::{CaptureDeviceConfig, IpCameraSpecificConfig};
+use anyhow::{bail, Context, Result};
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{debug, info, warn};
+use md5::{Digest, Md5};
+use opencv::{core as opencv_core, imgcodecs};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+const REALM: &str = "rcam-mock";
+
+/// Parameters a fresh mock reports, in the vendors' own key formats.
+const DEFAULT_PARAMETERS: &[(&str, &str)] = &[
+    ("table.Encode[0].MainFormat[0].VideoEnable", "true"),
+    ("table.VideoInOptions[0].DayNightColor", "1"),
+    ("root.ImageSource.I0.DayNight.IrCutFilter", "auto"),
+];
+
+/// Configures a `MockCamera`. Defaults: Dahua driver, user "admin" with digest auth, the host's
+/// clock, 640x480 frames and an RTSP stream at 10 fps.
+pub struct MockCameraBuilder {
+    vendor: String,
+    username: String,
+    password: String,
+    require_auth: bool,
+    clock_offset: chrono::Duration,
+    frame_size: (u32, u32),
+    fps: f64,
+    rtsp: bool,
+}
+
+impl Default for MockCameraBuilder {
+    fn default() -> Self {
+        Self {
+            vendor: "dahua".to_string(),
+            username: "admin".to_string(),
+            password: "mock-password".to_string(),
+            require_auth: true,
+            clock_offset: chrono::Duration::zero(),
+            frame_size: (640, 480),
+            fps: 10.0,
+            rtsp: true,
+        }
+    }
+}
+
+impl MockCameraBuilder {
+    /// Vendor the generated camera config names ("dahua" or "axis"). The mock answers both
+    /// vendors' endpoints either way.
+    pub fn vendor(mut self, vendor: &str) -> Self {
+        self.vendor = vendor.to_string();
+        self
+    }
+
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.username = username.to_string();
+        self.password = password.to_string();
+        self
+    }
+
+    /// Serves the HTTP API without a digest challenge.
+    pub fn without_auth(mut self) -> Self {
+        self.require_auth = false;
+        self
+    }
+
+    /// How far the camera's clock is ahead of the host's (negative: behind).
+    pub fn clock_offset(mut self, offset: chrono::Duration) -> Self {
+        self.clock_offset = offset;
+        self
+    }
+
+    /// Snapshot and stream size; multiples of 16 up to 2032 (RTP/JPEG counts 8-pixel blocks).
+    pub fn frame_size(mut self, width: u32, height: u32) -> Self {
+        self.frame_size = (width, height);
+        self
+    }
+
+    pub fn fps(mut self, fps: f64) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    pub fn without_rtsp(mut self) -> Self {
+        self.rtsp = false;
+        self
+    }
+
+    /// Binds loopback ports and starts serving until the `MockCamera` is shut down or dropped.
+    pub async fn start(self) -> Result<MockCamera> {
+        let (width, height) = self.frame_size;
+        if width == 0 || height == 0 || width % 16 != 0 || height % 16 != 0 || width > 2032 || height > 2032 {
+            bail!("Mock camera frame size {}x{} must be non-zero multiples of 16 up to 2032", width, height);
+        }
+        if self.fps.is_nan() || self.fps <= 0.0 {
+            bail!("Mock camera fps must be positive, got {}", self.fps);
+        }
+        let frame_size = opencv_core::Size::new(width as i32, height as i32);
+        let cancel = CancellationToken::new();
+        let state = Arc::new(MockState {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            require_auth: self.require_auth,
+            nonce: format!("{:x}", Md5::digest(format!("{}:{}", REALM, Utc::now().timestamp_nanos_opt().unwrap_or_default()))),
+            clock_offset: self.clock_offset,
+            frame_size,
+            snapshots: AtomicU64::new(0),
+            requests: Mutex::new(HashMap::new()),
+            parameters: Mutex::new(DEFAULT_PARAMETERS.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+        });
+
+        let http_listener = TcpListener::bind("127.0.0.1:0").await.context("Failed to bind mock camera HTTP port")?;
+        let http_addr = http_listener.local_addr()?;
+        let http_cancel = cancel.clone();
+        let app = router(state.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(http_listener, app).with_graceful_shutdown(async move { http_cancel.cancelled().await }).await {
+                warn!("⚠️ Mock camera HTTP server failed: {:#}", e);
+            }
+        });
+
+        let rtsp_addr = if self.rtsp {
+            let rtsp_listener = TcpListener::bind("127.0.0.1:0").await.context("Failed to bind mock camera RTSP port")?;
+            let rtsp_addr = rtsp_listener.local_addr()?;
+            tokio::spawn(mock_rtsp::serve(rtsp_listener, frame_size, self.fps, cancel.clone()));
+            Some(rtsp_addr)
+        } else {
+            None
+        };
+        info!("🧪 Mock camera serving HTTP on {}{}.", http_addr,
+            rtsp_addr.map(|addr| format!(" and RTSP on {}", addr)).unwrap_or_default());
+
+        Ok(MockCamera {
+            vendor: self.vendor,
+            username: self.username,
+            password: self.password,
+            require_auth: self.require_auth,
+            http_addr,
+            rtsp_addr,
+            state,
+            cancel,
+        })
+    }
+}
+
+/// A fake IP camera on loopback. See the module docs.
+pub struct MockCamera {
+    vendor: String,
+    username: String,
+    password: String,
+    require_auth: bool,
+    http_addr: SocketAddr,
+    rtsp_addr: Option<SocketAddr>,
+    state: Arc<MockState>,
+    cancel: CancellationToken,
+}
+
+impl MockCamera {
+    pub fn builder() -> MockCameraBuilder {
+        MockCameraBuilder::default()
+    }
+
+    pub fn http_addr(&self) -> SocketAddr {
+        self.http_addr
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.http_addr)
+    }
+
+    /// The stream URL with credentials, as rcam builds it; None when started `without_rtsp`.
+    pub fn rtsp_url(&self) -> Option<String> {
+        self.rtsp_addr.map(|addr| format!("rtsp://{}:{}@{}{}", self.username, self.password, addr, mock_rtsp::PATH))
+    }
+
+    /// An IP camera config pointing at this mock. RTSP goes over TCP (the mock has no UDP).
+    pub fn camera_config(&self) -> Result<IpCameraSpecificConfig> {
+        let mut config = serde_json::json!({
+            "ip": self.http_addr.ip().to_string(),
+            "vendor": self.vendor,
+            "username": self.username,
+            "auth": if self.require_auth { "digest" } else { "none" },
+            "http_port": self.http_addr.port(),
+        });
+        if let Some(addr) = self.rtsp_addr {
+            config["rtsp_port"] = addr.port().into();
+            config["rtsp_path"] = mock_rtsp::PATH.into();
+            config["rtsp_transport"] = "tcp".into();
+        }
+        serde_json::from_value(config).context("Failed to build mock camera config")
+    }
+
+    pub fn camera_entry(&self, name: &str) -> Result<CaptureDeviceConfig> {
+        Ok(CaptureDeviceConfig::IpCamera { name: name.to_string(), specifics: self.camera_config()? })
+    }
+
+    /// Exports the password as `{NAME}_PASSWORD`, where rcam looks for it. Setting a variable
+    /// races with threads reading the environment, so tests that call this must not run in
+    /// parallel with other tests of the same binary.
+    pub fn set_password_env(&self, camera_name: &str) {
+        std::env::set_var(auth::password_env_var(camera_name), &self.password);
+    }
+
+    /// Authorized requests served for `path` (e.g. "/cgi-bin/snapshot.cgi").
+    pub fn requests(&self, path: &str) -> usize {
+        self.state.requests.lock().unwrap().get(path).copied().unwrap_or(0)
+    }
+
+    /// A stored parameter by its full vendor key, e.g. "table.VideoInOptions[0].DayNightColor"
+    /// or "root.ImageSource.I0.DayNight.IrCutFilter".
+    pub fn parameter(&self, key: &str) -> Option<String> {
+        self.state.parameters.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn set_parameter(&self, key: &str, value: &str) {
+        self.state.parameters.lock().unwrap().insert(key.to_string(), value.to_string());
+    }
+
+    pub fn shutdown(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl Drop for MockCamera {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+struct MockState {
+    username: String,
+    password: String,
+    require_auth: bool,
+    nonce: String,
+    clock_offset: chrono::Duration,
+    frame_size: opencv_core::Size,
+    snapshots: AtomicU64,
+    requests: Mutex<HashMap<String, usize>>,
+    parameters: Mutex<BTreeMap<String, String>>,
+}
+
+impl MockState {
+    fn camera_time(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_offset
+    }
+
+    fn challenge(&self) -> String {
+        format!("Digest realm=\"{}\", qop=\"auth\", nonce=\"{}\", algorithm=MD5", REALM, self.nonce)
+    }
+
+    /// Checks an RFC 2617 `Authorization: Digest` header (MD5, with or without qop=auth).
+    fn verify_digest(&self, method: &str, header: &str) -> bool {
+        let Some(fields) = header.strip_prefix("Digest ") else {
+            return false;
+        };
+        let fields = digest_fields(fields);
+        let field = |name: &str| fields.get(name).map(String::as_str);
+        let (Some(username), Some(nonce), Some(uri), Some(response)) = (field("username"), field("nonce"), field("uri"), field("response")) else {
+            return false;
+        };
+        if username != self.username || nonce != self.nonce {
+            return false;
+        }
+        let ha1 = md5_hex(&format!("{}:{}:{}", username, REALM, self.password));
+        let ha2 = md5_hex(&format!("{}:{}", method, uri));
+        let expected = match (field("qop"), field("nc"), field("cnonce")) {
+            (Some(qop), Some(nc), Some(cnonce)) => md5_hex(&format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2)),
+            _ => md5_hex(&format!("{}:{}:{}", ha1, nonce, ha2)),
+        };
+        expected.eq_ignore_ascii_case(response)
+    }
+
+    /// Lines of stored parameters under `prefix` (a whole key or a dotted/indexed group).
+    fn parameter_lines(&self, prefix: &str) -> String {
+        self.parameters.lock().unwrap().iter()
+            .filter(|(key, _)| *key == prefix || key.strip_prefix(prefix).is_some_and(|rest| rest.starts_with(['.', '['])))
+            .map(|(key, value)| format!("{}={}\r\n", key, value))
+            .collect()
+    }
+
+    fn store_parameters(&self, namespace: &str, query: &[(String, String)]) {
+        let mut parameters = self.parameters.lock().unwrap();
+        for (key, value) in query.iter().filter(|(key, _)| key != "action") {
+            parameters.insert(format!("{}.{}", namespace, key), value.clone());
+        }
+    }
+}
+
+fn md5_hex(input: &str) -> String {
+    format!("{:x}", Md5::digest(input.as_bytes()))
+}
+
+/// Splits `key=value, key="quoted, value"` pairs; keys are lowercased, quotes dropped.
+fn digest_fields(fields: &str) -> HashMap<String, String> {
+    let mut parts = Vec::new();
+    let (mut current, mut quoted) = (String::new(), false);
+    for c in fields.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts.iter()
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect()
+}
+
+fn action(query: &[(String, String)]) -> Option<&str> {
+    query.iter().find(|(key, _)| key == "action").map(|(_, value)| value.as_str())
+}
+
+/// A JPEG of the virtual camera's test pattern; also what the RTSP stream sends.
+pub(super) fn test_frame_jpeg(size: opencv_core::Size, number: u64) -> Result<Vec<u8>> {
+    let frame = virtual_camera::test_pattern(size, number, Utc::now())?;
+    let mut encoded = opencv_core::Vector::<u8>::new();
+    if !imgcodecs::imencode(".jpg", &frame, &mut encoded, &opencv_core::Vector::new())? {
+        bail!("OpenCV: Failed to encode mock camera frame");
+    }
+    Ok(encoded.to_vec())
+}
+
+fn router(state: Arc<MockState>) -> Router {
+    Router::new()
+        .route("/cgi-bin/snapshot.cgi", get(snapshot))
+        .route("/cgi-bin/global.cgi", get(dahua_time))
+        .route("/cgi-bin/configManager.cgi", get(dahua_config))
+        .route("/cgi-bin/magicBox.cgi", get(dahua_magic_box))
+        .route("/axis-cgi/jpg/image.cgi", get(snapshot))
+        .route("/axis-cgi/time.cgi", post(axis_time))
+        .route("/axis-cgi/param.cgi", get(axis_param))
+        .route("/axis-cgi/restart.cgi", get(axis_restart))
+        .layer(middleware::from_fn_with_state(state.clone(), digest_auth))
+        .with_state(state)
+}
+
+/// Answers with a digest challenge until the request carries valid credentials, then counts it.
+async fn digest_auth(State(state): State<Arc<MockState>>, request: Request, next: Next) -> Response {
+    if state.require_auth {
+        let authorized = request.headers().get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| state.verify_digest(request.method().as_str(), value));
+        if !authorized {
+            debug!("🔒 Mock camera challenging {} {}", request.method(), request.uri());
+            return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, state.challenge())], "Unauthorized").into_response();
+        }
+    }
+    *state.requests.lock().unwrap().entry(request.uri().path().to_string()).or_default() += 1;
+    next.run(request).await
+}
+
+async fn snapshot(State(state): State<Arc<MockState>>) -> Response {
+    let number = state.snapshots.fetch_add(1, Ordering::Relaxed);
+    let size = state.frame_size;
+    match opencv_pool::spawn(move || test_frame_jpeg(size, number)).await {
+        Ok(Ok(jpeg)) => ([(header::CONTENT_TYPE, "image/jpeg")], jpeg).into_response(),
+        Ok(Err(e)) | Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    }
+}
+
+async fn dahua_time(State(state): State<Arc<MockState>>, Query(query): Query<Vec<(String, String)>>) -> Response {
+    match action(&query) {
+        Some("getCurrentTime") => format!("result={}\r\n", state.camera_time().format("%Y-%m-%d %H:%M:%S")).into_response(),
+        _ => (StatusCode::BAD_REQUEST, "Error\r\nBad Request!\r\n").into_response(),
+    }
+}
+
+async fn dahua_config(State(state): State<Arc<MockState>>, Query(query): Query<Vec<(String, String)>>) -> Response {
+    match action(&query) {
+        Some("getConfig") => {
+            let name = query.iter().find(|(key, _)| key == "name").map(|(_, value)| value.as_str()).unwrap_or_default();
+            let lines = state.parameter_lines(&format!("table.{}", name));
+            if lines.is_empty() {
+                return (StatusCode::BAD_REQUEST, "Error\r\nBad Request!\r\n").into_response();
+            }
+            lines.into_response()
+        }
+        Some("setConfig") => {
+            state.store_parameters("table", &query);
+            "OK\r\n".into_response()
+        }
+        _ => (StatusCode::BAD_REQUEST, "Error\r\nBad Request!\r\n").into_response(),
+    }
+}
+
+async fn dahua_magic_box(Query(query): Query<Vec<(String, String)>>) -> Response {
+    match action(&query) {
+        Some("reboot") => "OK\r\n".into_response(),
+        _ => (StatusCode::BAD_REQUEST, "Error\r\nBad Request!\r\n").into_response(),
+    }
+}
+
+async fn axis_time(State(state): State<Arc<MockState>>, body: String) -> Response {
+    let method = serde_json::from_str::<serde_json::Value>(&body).ok()
+        .and_then(|request| request.get("method").and_then(|m| m.as_str()).map(str::to_string));
+    let reply = match method.as_deref() {
+        Some("getDateTimeInfo") => serde_json::json!({
+            "apiVersion": "1.0",
+            "method": "getDateTimeInfo",
+            "data": { "dateTime": state.camera_time().to_rfc3339_opts(SecondsFormat::Secs, true) },
+        }),
+        other => serde_json::json!({
+            "apiVersion": "1.0",
+            "error": { "code": 2002, "message": format!("Method not supported: {}", other.unwrap_or("none")) },
+        }),
+    };
+    ([(header::CONTENT_TYPE, "application/json")], reply.to_string()).into_response()
+}
+
+async fn axis_param(State(state): State<Arc<MockState>>, Query(query): Query<Vec<(String, String)>>) -> Response {
+    match action(&query) {
+        Some("list") => {
+            let group = query.iter().find(|(key, _)| key == "group").map(|(_, value)| value.as_str()).unwrap_or_default();
+            let lines = state.parameter_lines(&format!("root.{}", group));
+            if lines.is_empty() {
+                return format!("# Error: Error -1 getting param in group '{}'\r\n", group).into_response();
+            }
+            lines.into_response()
+        }
+        Some("update") => {
+            state.store_parameters("root", &query);
+            "OK\r\n".into_response()
+        }
+        _ => "# Error: Unknown action\r\n".into_response(),
+    }
+}
+
+async fn axis_restart() -> Response {
+    "Restarting...\r\n".into_response()
+}
@@ -0,0 +1,313 @@
+//! A minimal RTSP server for the mock camera: one MJPEG track (RTP payload type 26, RFC 2435)
+//! sent interleaved over the RTSP connection. Clients that try UDP first get `461 Unsupported
+//! Transport` and retry over TCP, as FFmpeg does. Credentials in the URL are not checked.
+
+use super::mock_camera::test_frame_jpeg;
+use crate::common::opencv_pool;
+use anyhow::{bail, Context, Result};
+use log::{debug, warn};
+use opencv::core as opencv_core;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// Path of the mock's only stream.
+pub(super) const PATH: &str = "/live";
+const RTP_JPEG: u8 = 26;
+const RTP_CLOCK_HZ: f64 = 90_000.0;
+const SSRC: u32 = 0x7263_616d; // "rcam"
+/// JPEG payload bytes per RTP packet, keeping packets under a typical MTU.
+const MAX_PAYLOAD: usize = 1400;
+
+pub(super) async fn serve(listener: TcpListener, size: opencv_core::Size, fps: f64, cancel: CancellationToken) {
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = cancel.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("⚠️ Mock RTSP server failed to accept a connection: {}", e);
+                    continue;
+                }
+            },
+        };
+        let session_cancel = cancel.child_token();
+        tokio::spawn(async move {
+            if let Err(e) = session(stream, peer, size, fps, session_cancel).await {
+                debug!("Mock RTSP session with {} ended: {:#}", peer, e);
+            }
+        });
+    }
+}
+
+struct RtspRequest {
+    method: String,
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+impl RtspRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+async fn session(stream: TcpStream, peer: SocketAddr, size: opencv_core::Size, fps: f64, cancel: CancellationToken) -> Result<()> {
+    let (read_half, write_half) = stream.into_split();
+    let mut reader = BufReader::new(read_half);
+    let writer = Arc::new(Mutex::new(write_half));
+    let session_id = format!("{:08X}", std::process::id() ^ ((peer.port() as u32) << 8));
+    let stream_cancel = cancel.child_token();
+    let _stop_stream = stream_cancel.clone().drop_guard(); // the stream ends with the connection
+    let mut streaming = false;
+    debug!("Mock RTSP: connection from {}", peer);
+
+    loop {
+        let request = tokio::select! {
+            _ = cancel.cancelled() => return Ok(()),
+            request = read_request(&mut reader) => request?,
+        };
+        let Some(request) = request else {
+            return Ok(()); // client closed the connection
+        };
+        let cseq = request.header("CSeq").unwrap_or("0").to_string();
+        debug!("Mock RTSP: {} {} (CSeq {})", request.method, request.url, cseq);
+        let session_header = format!("Session: {};timeout=60", session_id);
+        let reply = match request.method.as_str() {
+            "OPTIONS" => response(&cseq, "200 OK", &["Public: OPTIONS, DESCRIBE, SETUP, PLAY, GET_PARAMETER, TEARDOWN".to_string()], ""),
+            "DESCRIBE" => {
+                let content_base = if request.url.ends_with('/') { request.url.clone() } else { format!("{}/", request.url) };
+                let sdp = sdp(fps);
+                response(&cseq, "200 OK", &[format!("Content-Base: {}", content_base), "Content-Type: application/sdp".to_string()], &sdp)
+            }
+            "SETUP" if !request.header("Transport").unwrap_or_default().contains("TCP") => {
+                response(&cseq, "461 Unsupported Transport", &[], "")
+            }
+            "SETUP" => response(&cseq, "200 OK", &["Transport: RTP/AVP/TCP;unicast;interleaved=0-1".to_string(), session_header], ""),
+            "PLAY" => {
+                let reply = response(&cseq, "200 OK", &[session_header, "Range: npt=0.000-".to_string()], "");
+                writer.lock().await.write_all(reply.as_bytes()).await?;
+                if !streaming {
+                    streaming = true;
+                    let (writer, cancel) = (writer.clone(), stream_cancel.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = stream_frames(writer, size, fps, cancel).await {
+                            debug!("Mock RTSP: stream to {} stopped: {:#}", peer, e);
+                        }
+                    });
+                }
+                continue;
+            }
+            "GET_PARAMETER" | "SET_PARAMETER" => response(&cseq, "200 OK", &[session_header], ""),
+            "TEARDOWN" => {
+                writer.lock().await.write_all(response(&cseq, "200 OK", &[session_header], "").as_bytes()).await?;
+                return Ok(());
+            }
+            _ => response(&cseq, "501 Not Implemented", &[], ""),
+        };
+        writer.lock().await.write_all(reply.as_bytes()).await?;
+    }
+}
+
+/// Reads the next RTSP request, skipping interleaved packets (RTCP receiver reports) the client
+/// sends on the same connection. None at end of stream.
+async fn read_request(reader: &mut BufReader<OwnedReadHalf>) -> Result<Option<RtspRequest>> {
+    loop {
+        let buffered = reader.fill_buf().await?;
+        if buffered.is_empty() {
+            return Ok(None);
+        }
+        if buffered[0] == b'$' {
+            let mut header = [0u8; 4];
+            reader.read_exact(&mut header).await?;
+            let mut packet = vec![0u8; u16::from_be_bytes([header[2], header[3]]) as usize];
+            reader.read_exact(&mut packet).await?;
+            continue;
+        }
+
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await? == 0 {
+            return Ok(None);
+        }
+        if request_line.trim().is_empty() {
+            continue;
+        }
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(url)) = (parts.next(), parts.next()) else {
+            bail!("Malformed RTSP request line '{}'", request_line.trim());
+        };
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+        if let Some(length) = headers.get("content-length").and_then(|value| value.parse::<usize>().ok()) {
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).await?;
+        }
+        return Ok(Some(RtspRequest { method: method.to_string(), url: url.to_string(), headers }));
+    }
+}
+
+fn response(cseq: &str, status: &str, headers: &[String], body: &str) -> String {
+    let mut reply = format!("RTSP/1.0 {}\r\nCSeq: {}\r\nServer: rcam-mock\r\n", status, cseq);
+    for header in headers {
+        reply.push_str(header);
+        reply.push_str("\r\n");
+    }
+    if !body.is_empty() {
+        reply.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    reply.push_str("\r\n");
+    reply.push_str(body);
+    reply
+}
+
+fn sdp(fps: f64) -> String {
+    [
+        "v=0",
+        "o=- 0 0 IN IP4 127.0.0.1",
+        "s=rcam mock camera",
+        "c=IN IP4 0.0.0.0",
+        "t=0 0",
+        &format!("m=video 0 RTP/AVP {}", RTP_JPEG),
+        &format!("a=rtpmap:{} JPEG/90000", RTP_JPEG),
+        &format!("a=framerate:{}", fps),
+        "a=control:track1",
+        "",
+    ].join("\r\n")
+}
+
+async fn stream_frames(writer: Arc<Mutex<OwnedWriteHalf>>, size: opencv_core::Size, fps: f64, cancel: CancellationToken) -> Result<()> {
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / fps));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let started = Instant::now();
+    let mut sequence: u16 = 0;
+    let mut number: u64 = 0;
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return Ok(()),
+            _ = ticker.tick() => {}
+        }
+        let timestamp = (started.elapsed().as_secs_f64() * RTP_CLOCK_HZ) as u64 as u32;
+        let jpeg = opencv_pool::spawn(move || test_frame_jpeg(size, number)).await??;
+        let frame = JpegFrame::parse(&jpeg)?;
+        let mut data = Vec::with_capacity(jpeg.len() + 256);
+        for packet in frame.rtp_packets(timestamp, &mut sequence) {
+            // Interleaved framing: '$', channel 0 (RTP), big-endian length.
+            data.push(b'$');
+            data.push(0);
+            data.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+            data.extend_from_slice(&packet);
+        }
+        writer.lock().await.write_all(&data).await?;
+        number += 1;
+    }
+}
+
+/// The parts of a baseline JPEG that RTP/JPEG carries: the entropy-coded scan and the
+/// quantization tables. Huffman tables are the standard ones the receiver assumes.
+struct JpegFrame<'a> {
+    kind: u8, // 0 = 4:2:2, 1 = 4:2:0 luma subsampling
+    width: u16,
+    height: u16,
+    tables: Vec<u8>, // luma then chroma, 64 bytes each in zigzag order
+    scan: &'a [u8],
+}
+
+impl<'a> JpegFrame<'a> {
+    fn parse(jpeg: &'a [u8]) -> Result<Self> {
+        if !jpeg.starts_with(&[0xFF, 0xD8]) {
+            bail!("Mock frame is not a JPEG");
+        }
+        let be16 = |bytes: &[u8]| u16::from_be_bytes([bytes[0], bytes[1]]);
+        let mut tables = BTreeMap::new();
+        let mut sof = None;
+        let mut pos = 2;
+        loop {
+            let marker = jpeg.get(pos..pos + 4).context("JPEG ended before its scan")?;
+            if marker[0] != 0xFF {
+                bail!("Malformed JPEG marker at byte {}", pos);
+            }
+            let length = be16(&marker[2..]) as usize;
+            let segment = jpeg.get(pos + 4..pos + 2 + length).context("Truncated JPEG segment")?;
+            match marker[1] {
+                0xDB => {
+                    let mut rest = segment;
+                    while !rest.is_empty() {
+                        if rest[0] >> 4 != 0 {
+                            bail!("RTP/JPEG needs 8-bit quantization tables");
+                        }
+                        let table = rest.get(1..65).context("Truncated JPEG quantization table")?;
+                        tables.insert(rest[0] & 0x0F, table.to_vec());
+                        rest = &rest[65..];
+                    }
+                }
+                0xC0 => {
+                    if segment.len() < 9 || segment[5] != 3 {
+                        bail!("RTP/JPEG needs a three-component JPEG");
+                    }
+                    let kind = match segment[7] {
+                        0x21 => 0,
+                        0x22 => 1,
+                        other => bail!("Unsupported JPEG luma sampling {:#04x}", other),
+                    };
+                    sof = Some((kind, be16(&segment[3..]), be16(&segment[1..])));
+                }
+                0xC1..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => bail!("RTP/JPEG needs a baseline JPEG"),
+                0xDD if be16(segment) != 0 => bail!("RTP/JPEG type 0/1 does not allow restart markers"),
+                0xDA => {
+                    let start = pos + 2 + length;
+                    let end = if jpeg.ends_with(&[0xFF, 0xD9]) { jpeg.len() - 2 } else { jpeg.len() };
+                    let (kind, width, height) = sof.context("JPEG scan before its frame header")?;
+                    let (Some(luma), Some(chroma)) = (tables.get(&0), tables.get(&1)) else {
+                        bail!("JPEG is missing its luma or chroma quantization table");
+                    };
+                    return Ok(JpegFrame { kind, width, height, tables: [luma.as_slice(), chroma.as_slice()].concat(), scan: &jpeg[start..end] });
+                }
+                _ => {}
+            }
+            pos += 2 + length;
+        }
+    }
+
+    /// RTP packets for the frame (RFC 2435), the quantization tables in the first (Q = 255).
+    fn rtp_packets(&self, timestamp: u32, sequence: &mut u16) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let mut offset = 0;
+        while offset < self.scan.len() {
+            let chunk = (self.scan.len() - offset).min(MAX_PAYLOAD);
+            let last = offset + chunk == self.scan.len();
+            let mut packet = Vec::with_capacity(12 + 8 + 4 + self.tables.len() + chunk);
+            packet.push(0x80); // version 2
+            packet.push(RTP_JPEG | if last { 0x80 } else { 0 }); // marker bit ends the frame
+            packet.extend_from_slice(&sequence.to_be_bytes());
+            packet.extend_from_slice(&timestamp.to_be_bytes());
+            packet.extend_from_slice(&SSRC.to_be_bytes());
+            packet.push(0); // type-specific
+            packet.extend_from_slice(&(offset as u32).to_be_bytes()[1..]);
+            packet.extend_from_slice(&[self.kind, 255, (self.width / 8) as u8, (self.height / 8) as u8]);
+            if offset == 0 {
+                packet.extend_from_slice(&[0, 0]); // MBZ, 8-bit precision
+                packet.extend_from_slice(&(self.tables.len() as u16).to_be_bytes());
+                packet.extend_from_slice(&self.tables);
+            }
+            packet.extend_from_slice(&self.scan[offset..offset + chunk]);
+            packets.push(packet);
+            *sequence = sequence.wrapping_add(1);
+            offset += chunk;
+        }
+        packets
+    }
+}
@@ -0,0 +1,19 @@
+//! Fakes for integration tests, built with the `test-support` feature.
+//!
+//! `MockCamera` serves a camera's HTTP API (Dahua and Axis snapshot, time and parameter CGIs
+//! behind digest auth) and an RTSP stream of test-pattern frames on loopback ports, so
+//! `image_capture_op`, `time_sync_op`, recordings and the vendor drivers run end to end in CI:
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! let camera = rcam::test_support::MockCamera::builder().start().await?;
+//! camera.set_password_env("cam1");
+//! let entry = camera.camera_entry("cam1")?; // a CaptureDeviceConfig pointing at the mock
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod mock_camera;
+mod mock_rtsp;
+
+pub use mock_camera::{MockCamera, MockCameraBuilder};
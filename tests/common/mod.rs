@@ -0,0 +1,74 @@
+//! Shared setup for the integration tests that run against `rcam::test_support::MockCamera`.
+// Each test binary includes this module and uses only part of it.
+#![allow(dead_code)]
+
+use rcam::{CaptureDeviceConfig, MasterConfig};
+use std::path::{Path, PathBuf};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Held for the whole of every test in a binary that exports camera passwords. Changing the
+/// environment while another thread reads it (reqwest and OpenCV do) is a data race, so the
+/// tests run one at a time.
+pub async fn serial() -> MutexGuard<'static, ()> {
+    static SERIAL: Mutex<()> = Mutex::const_new(());
+    SERIAL.lock().await
+}
+
+/// A configuration with `cameras` that writes under `output_dir` and does not insist on a
+/// synchronized host clock (CI runners may not report one).
+pub fn config(cameras: Vec<CaptureDeviceConfig>, output_dir: &Path) -> MasterConfig {
+    let application = serde_json::json!({
+        "output_directory_base": output_dir.to_string_lossy(),
+        "default_config_path": "",
+        "filename_timestamp_format": "%Y%m%d_%H%M%S_%f",
+        "image_format": "jpg",
+        "video_format": "mp4",
+        "video_codec": "mjpeg",
+        "video_duration_default_seconds": 2,
+        "time_sync_tolerance_seconds": 5.0,
+        "require_host_clock_sync": false,
+    });
+    MasterConfig {
+        application: serde_json::from_value(application).expect("valid application settings"),
+        cameras,
+        active_profile: None,
+    }
+}
+
+/// A fresh directory under the system temp dir, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rcam-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Files under `dir` (recursively) with extension `extension`.
+pub fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            found.extend(files_with_extension(&path, extension));
+        } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case(extension)) {
+            found.push(path);
+        }
+    }
+    found
+}
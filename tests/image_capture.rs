@@ -0,0 +1,101 @@
+//! `capture-image` end to end against mock IP cameras: HTTP snapshots behind digest auth,
+//! stills grabbed from the RTSP stream of a camera without an HTTP API, and stills taken through
+//! the agent API while the camera records.
+
+mod common;
+
+use rcam::common::recording_control;
+use rcam::server::protocol::{OperationResponse, API_PREFIX};
+use rcam::server::{self, ApiState};
+use rcam::test_support::MockCamera;
+use rcam::{CaptureImageParams, ErrorKind, OperationStatus, Rcam};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn dahua_snapshot_is_saved() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("capture-dahua");
+    let camera = MockCamera::builder().start().await.unwrap();
+    camera.set_password_env("mock_dahua_snapshot");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_dahua_snapshot").unwrap()], output.path())).unwrap();
+
+    let report = rcam.capture_images(&CaptureImageParams::default()).await.unwrap();
+    assert_eq!(report.succeeded(), 1, "{:?}", report.results);
+    assert_eq!(camera.requests("/cgi-bin/snapshot.cgi"), 1);
+    assert_eq!(common::files_with_extension(output.path(), "jpg").len(), 1);
+}
+
+#[tokio::test]
+async fn axis_snapshot_is_saved() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("capture-axis");
+    let camera = MockCamera::builder().vendor("axis").start().await.unwrap();
+    camera.set_password_env("mock_axis_snapshot");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_axis_snapshot").unwrap()], output.path())).unwrap();
+
+    let report = rcam.capture_images(&CaptureImageParams::default()).await.unwrap();
+    assert_eq!(report.succeeded(), 1, "{:?}", report.results);
+    assert_eq!(camera.requests("/axis-cgi/jpg/image.cgi"), 1);
+    assert_eq!(common::files_with_extension(output.path(), "jpg").len(), 1);
+}
+
+#[tokio::test]
+async fn wrong_password_fails_the_capture() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("capture-auth");
+    let camera = MockCamera::builder().start().await.unwrap();
+    std::env::set_var(rcam::camera::auth::password_env_var("mock_wrong_password"), "not-the-password");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_wrong_password").unwrap()], output.path())).unwrap();
+
+    let report = rcam.capture_images(&CaptureImageParams::default()).await.unwrap();
+    assert_eq!(report.status(), OperationStatus::TotalFailure, "{:?}", report.results);
+    assert_eq!(report.results[0].kind, Some(ErrorKind::Auth), "{:?}", report.results);
+    assert_eq!(camera.requests("/cgi-bin/snapshot.cgi"), 0);
+    assert!(common::files_with_extension(output.path(), "jpg").is_empty());
+}
+
+#[tokio::test]
+async fn rtsp_only_camera_grabs_a_still_from_the_stream() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("capture-rtsp");
+    let camera = MockCamera::builder().vendor("rtsp").start().await.unwrap();
+    camera.set_password_env("mock_rtsp_still");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_rtsp_still").unwrap()], output.path())).unwrap();
+
+    let report = rcam.capture_images(&CaptureImageParams::default()).await.unwrap();
+    assert_eq!(report.succeeded(), 1, "{:?}", report.results);
+    assert_eq!(camera.requests("/cgi-bin/snapshot.cgi"), 0);
+    assert_eq!(common::files_with_extension(output.path(), "jpg").len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn agent_snapshot_during_a_recording_uses_its_frames() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("capture-while-recording");
+    let camera = MockCamera::builder().vendor("rtsp").start().await.unwrap();
+    camera.set_password_env("mock_recording_still");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_recording_still").unwrap()], output.path())).unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let api = format!("http://{}{}", listener.local_addr().unwrap(), API_PREFIX);
+    let app = server::router(Arc::new(ApiState::new(rcam, None)));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = reqwest::Client::new();
+    let recording = tokio::spawn(client.post(format!("{}/capture-video", api)).json(&serde_json::json!({ "duration_secs": 4 })).send());
+    let started = Instant::now();
+    while !recording_control::is_recording("mock_recording_still") {
+        assert!(started.elapsed() < Duration::from_secs(10), "the recording did not start");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    let still = client.post(format!("{}/capture-image", api)).json(&serde_json::json!({})).send().await.unwrap();
+    assert!(still.status().is_success(), "capture-image during the recording: {}", still.status());
+    let still: OperationResponse = still.json().await.unwrap();
+    assert_eq!(still.cameras.len(), 1, "{:?}", still);
+    assert!(still.cameras[0].error.is_none(), "{:?}", still.cameras[0]);
+    assert_eq!(common::files_with_extension(output.path(), "jpg").len(), 1);
+
+    let recorded: OperationResponse = recording.await.unwrap().unwrap().json().await.unwrap();
+    assert!(recorded.cameras.iter().all(|c| c.error.is_none()), "{:?}", recorded);
+}
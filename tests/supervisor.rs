@@ -0,0 +1,67 @@
+//! Restarts and status reporting of the task supervisor.
+
+use rcam::{RestartPolicy, Supervisor, TaskState};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const WAIT_LIMIT: Duration = Duration::from_secs(5);
+
+#[tokio::test]
+async fn panicking_task_is_restarted_and_reported() {
+    let supervisor = Supervisor::new(RestartPolicy::OnFailure { delay: Duration::from_millis(10) });
+    let runs = Arc::new(AtomicU32::new(0));
+    let task_runs = runs.clone();
+    supervisor.spawn("cam1", "flaky", move |cancel| {
+        let run = task_runs.fetch_add(1, Ordering::SeqCst);
+        async move {
+            if run == 0 {
+                panic!("first run fails");
+            }
+            cancel.cancelled().await;
+            Ok(())
+        }
+    });
+
+    let deadline = Instant::now() + WAIT_LIMIT;
+    let status = loop {
+        let status = supervisor.camera_status("cam1");
+        if runs.load(Ordering::SeqCst) == 2 && status.first().is_some_and(|s| s.restarts == 1 && s.state == TaskState::Running) {
+            break status.into_iter().next().unwrap();
+        }
+        assert!(Instant::now() < deadline, "task was not restarted: {:?}", status);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    assert_eq!(status.task, "flaky");
+    let last_error = status.last_error.as_deref().unwrap_or_default();
+    assert!(last_error.contains("panicked"), "last error: {}", last_error);
+    assert!(status.is_healthy());
+
+    supervisor.shutdown().await;
+    assert_eq!(supervisor.camera_status("cam1")[0].state, TaskState::Stopped);
+}
+
+fn crash() -> anyhow::Result<()> {
+    panic!("recording crashed")
+}
+
+#[tokio::test]
+async fn panicking_once_task_fails_without_restart() {
+    let supervisor = Supervisor::new(RestartPolicy::Always { delay: Duration::from_millis(10) });
+    let task = supervisor.spawn_once("cam1", "recording", async { crash() });
+    let result = tokio::time::timeout(WAIT_LIMIT, task).await.expect("task did not finish");
+    assert!(result.is_err());
+
+    let deadline = Instant::now() + WAIT_LIMIT;
+    loop {
+        let status = supervisor.camera_status("cam1");
+        if status.first().is_some_and(|s| s.state == TaskState::Failed) {
+            assert_eq!(status[0].restarts, 0);
+            assert!(!status[0].is_healthy());
+            break;
+        }
+        assert!(Instant::now() < deadline, "task was not reported as failed: {:?}", status);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,57 @@
+//! `verify-times` and the vendor time queries against mock cameras with skewed clocks.
+
+mod common;
+
+use rcam::camera::camera_controller::CameraController;
+use rcam::camera::ip_camera_device::IpCameraDevice;
+use rcam::config_loader::CameraTimeouts;
+use rcam::test_support::MockCamera;
+use rcam::Rcam;
+
+/// Reads the mock's clock through its vendor driver and returns how far it is from the host's.
+async fn camera_offset_secs(camera: &MockCamera, name: &str, rcam: &Rcam) -> i64 {
+    let timeouts = CameraTimeouts::from_app(&rcam.config().application);
+    let device = IpCameraDevice::new(name.to_string(), camera.camera_config().unwrap(), timeouts).unwrap();
+    let camera_time = CameraController::new().get_camera_time(&device).await.unwrap();
+    (camera_time - chrono::Utc::now()).num_seconds()
+}
+
+#[tokio::test]
+async fn verify_times_queries_every_camera() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("verify-times");
+    let dahua = MockCamera::builder().start().await.unwrap();
+    let axis = MockCamera::builder().vendor("axis").start().await.unwrap();
+    dahua.set_password_env("mock_dahua_clock");
+    axis.set_password_env("mock_axis_clock");
+    let cameras = vec![dahua.camera_entry("mock_dahua_clock").unwrap(), axis.camera_entry("mock_axis_clock").unwrap()];
+    let rcam = Rcam::new(common::config(cameras, output.path())).unwrap();
+
+    rcam.verify_times().await.unwrap();
+    assert_eq!(dahua.requests("/cgi-bin/global.cgi"), 1);
+    assert_eq!(axis.requests("/axis-cgi/time.cgi"), 1);
+}
+
+#[tokio::test]
+async fn dahua_clock_offset_is_reported() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("dahua-offset");
+    let camera = MockCamera::builder().clock_offset(chrono::Duration::seconds(90)).start().await.unwrap();
+    camera.set_password_env("mock_dahua_offset");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_dahua_offset").unwrap()], output.path())).unwrap();
+
+    let offset = camera_offset_secs(&camera, "mock_dahua_offset", &rcam).await;
+    assert!((88..=91).contains(&offset), "offset {}s", offset);
+}
+
+#[tokio::test]
+async fn axis_clock_offset_is_reported() {
+    let _serial = common::serial().await;
+    let output = common::TempDir::new("axis-offset");
+    let camera = MockCamera::builder().vendor("axis").clock_offset(chrono::Duration::seconds(-45)).start().await.unwrap();
+    camera.set_password_env("mock_axis_offset");
+    let rcam = Rcam::new(common::config(vec![camera.camera_entry("mock_axis_offset").unwrap()], output.path())).unwrap();
+
+    let offset = camera_offset_secs(&camera, "mock_axis_offset", &rcam).await;
+    assert!((-46..=-44).contains(&offset), "offset {}s", offset);
+}
@@ -0,0 +1,98 @@
+//! The Dahua and Axis drivers' parameter, day/night, stream and reboot calls against the mock
+//! camera, which stores what they set in the vendors' own key formats.
+
+mod common;
+
+use rcam::camera::camera_controller::CameraController;
+use rcam::camera::imaging::DayNightMode;
+use rcam::camera::ip_camera_device::IpCameraDevice;
+use rcam::camera::vendors;
+use rcam::config_loader::CameraTimeouts;
+use rcam::test_support::MockCamera;
+
+fn device(camera: &MockCamera, name: &str) -> IpCameraDevice {
+    camera.set_password_env(name);
+    let app = common::config(Vec::new(), &std::env::temp_dir()).application;
+    IpCameraDevice::new(name.to_string(), camera.camera_config().unwrap(), CameraTimeouts::from_app(&app)).unwrap()
+}
+
+#[tokio::test]
+async fn dahua_day_night_round_trips() {
+    let _serial = common::serial().await;
+    let camera = MockCamera::builder().start().await.unwrap();
+    let device = device(&camera, "mock_dahua_day_night");
+    let controller = CameraController::new();
+
+    assert_eq!(controller.get_day_night(&device).await.unwrap(), DayNightMode::Auto);
+    assert_eq!(controller.set_day_night(&device, DayNightMode::Night).await.unwrap(), DayNightMode::Night);
+    assert_eq!(camera.parameter("table.VideoInOptions[0].DayNightColor").as_deref(), Some("2"));
+}
+
+#[tokio::test]
+async fn dahua_stream_can_be_disabled() {
+    let _serial = common::serial().await;
+    let camera = MockCamera::builder().start().await.unwrap();
+    let device = device(&camera, "mock_dahua_stream");
+    let controller = CameraController::new();
+
+    assert!(!controller.set_camera_enabled(&device, false).await.unwrap());
+    assert_eq!(camera.parameter("table.Encode[0].MainFormat[0].VideoEnable").as_deref(), Some("false"));
+    assert!(controller.set_camera_enabled(&device, true).await.unwrap());
+}
+
+#[tokio::test]
+async fn dahua_reboot_is_accepted() {
+    let _serial = common::serial().await;
+    let camera = MockCamera::builder().start().await.unwrap();
+    let device = device(&camera, "mock_dahua_reboot");
+
+    CameraController::new().reboot(&device).await.unwrap();
+    assert_eq!(camera.requests("/cgi-bin/magicBox.cgi"), 1);
+}
+
+#[tokio::test]
+async fn axis_day_night_round_trips() {
+    let _serial = common::serial().await;
+    let camera = MockCamera::builder().vendor("axis").start().await.unwrap();
+    let device = device(&camera, "mock_axis_day_night");
+    let controller = CameraController::new();
+
+    assert_eq!(controller.get_day_night(&device).await.unwrap(), DayNightMode::Auto);
+    assert_eq!(controller.set_day_night(&device, DayNightMode::Day).await.unwrap(), DayNightMode::Day);
+    assert_eq!(camera.parameter("root.ImageSource.I0.DayNight.IrCutFilter").as_deref(), Some("yes"));
+}
+
+#[tokio::test]
+async fn axis_reboot_is_accepted() {
+    let _serial = common::serial().await;
+    let camera = MockCamera::builder().vendor("axis").start().await.unwrap();
+    let device = device(&camera, "mock_axis_reboot");
+
+    CameraController::new().reboot(&device).await.unwrap();
+    assert_eq!(camera.requests("/axis-cgi/restart.cgi"), 1);
+}
+
+#[tokio::test]
+async fn axis_unknown_ir_cut_value_is_an_error() {
+    let _serial = common::serial().await;
+    let camera = MockCamera::builder().vendor("axis").start().await.unwrap();
+    camera.set_parameter("root.ImageSource.I0.DayNight.IrCutFilter", "sometimes");
+    let device = device(&camera, "mock_axis_bad_value");
+
+    assert!(CameraController::new().get_day_night(&device).await.is_err());
+}
+
+#[tokio::test]
+async fn parameter_values_are_percent_encoded() {
+    let _serial = common::serial().await;
+    for vendor in ["dahua", "axis"] {
+        let camera = MockCamera::builder().vendor(vendor).start().await.unwrap();
+        let device = device(&camera, &format!("mock_{}_encoding", vendor));
+        let (client, auth) = (device.build_http_client().unwrap(), device.http_auth().unwrap());
+        let driver = vendors::driver_for(Some(vendor)).unwrap();
+
+        driver.set_parameter(&device.vendor_context(&client, &auth), "Title.Name", "Gate & Yard #2 = 50%").await.unwrap();
+        let namespace = if vendor == "axis" { "root" } else { "table" };
+        assert_eq!(camera.parameter(&format!("{}.Title.Name", namespace)).as_deref(), Some("Gate & Yard #2 = 50%"), "{}", vendor);
+    }
+}